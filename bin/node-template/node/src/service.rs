@@ -49,6 +49,8 @@ pub fn new_partial(config: &Configuration) -> Result<sc_service::PartialComponen
 	let (grandpa_block_import, grandpa_link) = sc_finality_grandpa::block_import(
 		client.clone(), &(client.clone() as Arc<_>), select_chain.clone(),
 	)?;
+	let grandpa_block_import = grandpa_block_import
+		.with_prometheus_registry(config.prometheus_registry())?;
 
 	let aura_block_import = sc_consensus_aura::AuraBlockImport::<_, _, _, AuraPair>::new(
 		grandpa_block_import.clone(), client.clone(),
@@ -100,11 +102,17 @@ pub fn new_full(config: Configuration) -> Result<TaskManager, ServiceError> {
 	if config.offchain_worker.enabled {
 		sc_service::build_offchain_workers(
 			&config, backend.clone(), task_manager.spawn_handle(), client.clone(), network.clone(),
+			transaction_pool.clone(),
 		);
 	}
 
 	let role = config.role.clone();
 	let force_authoring = config.force_authoring;
+	let post_mortem = sc_service::authorship_post_mortem(
+		&config,
+		task_manager.spawn_handle(),
+		transaction_pool.clone(),
+	);
 	let name = config.network.node_name.clone();
 	let enable_grandpa = !config.disable_grandpa;
 	let prometheus_registry = config.prometheus_registry().cloned();
@@ -157,6 +165,7 @@ pub fn new_full(config: Configuration) -> Result<TaskManager, ServiceError> {
 			network.clone(),
 			inherent_data_providers.clone(),
 			force_authoring,
+			post_mortem,
 			keystore.clone(),
 			can_author_with,
 		)?;
@@ -272,6 +281,7 @@ pub fn new_light(config: Configuration) -> Result<TaskManager, ServiceError> {
 	if config.offchain_worker.enabled {
 		sc_service::build_offchain_workers(
 			&config, backend.clone(), task_manager.spawn_handle(), client.clone(), network.clone(),
+			transaction_pool.clone(),
 		);
 	}
 
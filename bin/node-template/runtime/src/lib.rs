@@ -336,6 +336,15 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl sp_api::TraceMetadata<Block> for Runtime {
+		fn trace_metadata() -> OpaqueMetadata {
+			Runtime::trace_metadata()
+				.with_spans("Executive", frame_executive::TRACE_SPANS)
+				.with_spans("Sudo", pallet_sudo::TRACE_SPANS)
+				.into()
+		}
+	}
+
 	impl sp_block_builder::BlockBuilder<Block> for Runtime {
 		fn apply_extrinsic(extrinsic: <Block as BlockT>::Extrinsic) -> ApplyExtrinsicResult {
 			Executive::apply_extrinsic(extrinsic)
@@ -396,6 +405,14 @@ impl_runtime_apis! {
 		) -> Option<Vec<(Vec<u8>, KeyTypeId)>> {
 			opaque::SessionKeys::decode_into_raw_public_keys(&encoded)
 		}
+
+		fn current_session_index() -> Option<sp_session::SessionIndex> {
+			None
+		}
+
+		fn next_session_keys(_validator: Vec<u8>) -> Option<Vec<u8>> {
+			None
+		}
 	}
 
 	impl fg_primitives::GrandpaApi<Block> for Runtime {
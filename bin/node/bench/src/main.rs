@@ -79,7 +79,7 @@ fn main() {
 	let opt = Opt::from_args();
 
 	if !opt.json {
-		sc_cli::init_logger("");
+		sc_cli::init_logger("", sc_cli::LogOutput::Stderr);
 	}
 
 	let mut import_benchmarks = Vec::new();
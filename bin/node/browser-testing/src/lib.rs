@@ -55,7 +55,7 @@ fn deserialize_rpc_result<T: DeserializeOwned>(js_value: JsValue) -> T {
 
 #[wasm_bindgen_test]
 async fn runs() {
-	let mut client = node_cli::start_client(None, "info".into())
+	let mut client = node_cli::start_client(None, "info".into(), false)
 			.await
 			.unwrap();
 
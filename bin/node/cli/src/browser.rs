@@ -25,15 +25,23 @@ use browser_utils::{
 };
 use std::str::FromStr;
 
-/// Starts the client.
+/// Starts the client, tracing it to the devtools of the browser if `console_tracing` is set.
 #[wasm_bindgen]
-pub async fn start_client(chain_spec: Option<String>, log_level: String) -> Result<Client, JsValue> {
-	start_inner(chain_spec, log_level)
+pub async fn start_client(
+	chain_spec: Option<String>,
+	log_level: String,
+	console_tracing: bool,
+) -> Result<Client, JsValue> {
+	start_inner(chain_spec, log_level, console_tracing)
 		.await
 		.map_err(|err| JsValue::from_str(&err.to_string()))
 }
 
-async fn start_inner(chain_spec: Option<String>, log_level: String) -> Result<Client, Box<dyn std::error::Error>> {
+async fn start_inner(
+	chain_spec: Option<String>,
+	log_level: String,
+	console_tracing: bool,
+) -> Result<Client, Box<dyn std::error::Error>> {
 	set_console_error_panic_hook();
 	init_console_log(log::Level::from_str(&log_level)?)?;
 	let chain_spec = match chain_spec {
@@ -42,7 +50,10 @@ async fn start_inner(chain_spec: Option<String>, log_level: String) -> Result<Cl
 		None => crate::chain_spec::development_config(),
 	};
 
-	let config = browser_configuration(chain_spec).await?;
+	let mut config = browser_configuration(chain_spec).await?;
+	if console_tracing {
+		config.tracing_extensions.push(Box::new(browser_utils::console_tracing));
+	}
 
 	info!("Substrate browser node");
 	info!("✌️  version {}", config.impl_version);
@@ -16,7 +16,7 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
-use sc_cli::{RunCmd, KeySubcommand, SignCmd, VanityCmd, VerifyCmd};
+use sc_cli::{RunCmd, KeySubcommand, SignCmd, TraceSubcommand, VanityCmd, VerifyCmd};
 use structopt::StructOpt;
 
 /// An overarching CLI command definition.
@@ -40,6 +40,9 @@ pub enum Subcommand {
 	/// Key management cli utilities
 	Key(KeySubcommand),
 
+	/// Trace analysis cli utilities
+	Trace(TraceSubcommand),
+
 	/// The custom inspect subcommmand for decoding blocks and extrinsics.
 	#[structopt(
 		name = "inspect",
@@ -94,6 +94,7 @@ pub fn run() -> Result<()> {
 			}
 		}
 		Some(Subcommand::Key(cmd)) => cmd.run(),
+		Some(Subcommand::Trace(cmd)) => cmd.run(),
 		Some(Subcommand::Sign(cmd)) => cmd.run(),
 		Some(Subcommand::Verify(cmd)) => cmd.run(),
 		Some(Subcommand::Vanity(cmd)) => cmd.run(),
@@ -77,6 +77,8 @@ pub fn new_partial(config: &Configuration) -> Result<sc_service::PartialComponen
 	let (grandpa_block_import, grandpa_link) = grandpa::block_import(
 		client.clone(), &(client.clone() as Arc<_>), select_chain.clone(),
 	)?;
+	let grandpa_block_import = grandpa_block_import
+		.with_prometheus_registry(config.prometheus_registry())?;
 	let justification_import = grandpa_block_import.clone();
 
 	let (block_import, babe_link) = sc_consensus_babe::block_import(
@@ -188,11 +190,17 @@ pub fn new_full_base(
 	if config.offchain_worker.enabled {
 		sc_service::build_offchain_workers(
 			&config, backend.clone(), task_manager.spawn_handle(), client.clone(), network.clone(),
+			transaction_pool.clone(),
 		);
 	}
 
 	let role = config.role.clone();
 	let force_authoring = config.force_authoring;
+	let post_mortem = sc_service::authorship_post_mortem(
+		&config,
+		task_manager.spawn_handle(),
+		transaction_pool.clone(),
+	);
 	let name = config.network.node_name.clone();
 	let enable_grandpa = !config.disable_grandpa;
 	let prometheus_registry = config.prometheus_registry().cloned();
@@ -238,6 +246,7 @@ pub fn new_full_base(
 			sync_oracle: network.clone(),
 			inherent_data_providers: inherent_data_providers.clone(),
 			force_authoring,
+			post_mortem,
 			babe_link,
 			can_author_with,
 		};
@@ -409,6 +418,7 @@ pub fn new_light_base(config: Configuration) -> Result<(
 	if config.offchain_worker.enabled {
 		sc_service::build_offchain_workers(
 			&config, backend.clone(), task_manager.spawn_handle(), client.clone(), network.clone(),
+			transaction_pool.clone(),
 		);
 	}
 
@@ -34,6 +34,7 @@ use std::sync::Arc;
 
 use jsonrpc_pubsub::manager::SubscriptionManager;
 use node_primitives::{Block, BlockNumber, AccountId, Index, Balance, Hash};
+use pallet_im_online::sr25519::AuthorityId as ImOnlineId;
 use sc_consensus_babe::{Config, Epoch};
 use sc_consensus_babe_rpc::BabeRpcHandler;
 use sc_consensus_epochs::SharedEpochChanges;
@@ -107,10 +108,11 @@ pub fn create_full<C, P, SC>(
 ) -> jsonrpc_core::IoHandler<sc_rpc_api::Metadata> where
 	C: ProvideRuntimeApi<Block>,
 	C: HeaderBackend<Block> + HeaderMetadata<Block, Error=BlockChainError> + 'static,
-	C: Send + Sync + 'static,
+	C: sc_client_api::AuxStore + Send + Sync + 'static,
 	C::Api: substrate_frame_rpc_system::AccountNonceApi<Block, AccountId, Index>,
 	C::Api: pallet_contracts_rpc::ContractsRuntimeApi<Block, AccountId, Balance, BlockNumber>,
 	C::Api: pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi<Block, Balance>,
+	C::Api: pallet_im_online_rpc::ImOnlineRuntimeApi<Block, ImOnlineId>,
 	C::Api: BabeApi<Block>,
 	C::Api: BlockBuilder<Block>,
 	P: TransactionPool + 'static,
@@ -119,6 +121,7 @@ pub fn create_full<C, P, SC>(
 	use substrate_frame_rpc_system::{FullSystem, SystemApi};
 	use pallet_contracts_rpc::{Contracts, ContractsApi};
 	use pallet_transaction_payment_rpc::{TransactionPayment, TransactionPaymentApi};
+	use pallet_im_online_rpc::{ImOnline, ImOnlineApi, ValidatorApi};
 
 	let mut io = jsonrpc_core::IoHandler::default();
 	let FullDeps {
@@ -154,6 +157,14 @@ pub fn create_full<C, P, SC>(
 	io.extend_with(
 		TransactionPaymentApi::to_delegate(TransactionPayment::new(client.clone()))
 	);
+	io.extend_with(
+		ImOnlineApi::to_delegate(ImOnline::new(client.clone(), keystore.clone(), deny_unsafe))
+	);
+	io.extend_with(
+		ValidatorApi::<ImOnlineId>::to_delegate(
+			ImOnline::new(client.clone(), keystore.clone(), deny_unsafe),
+		)
+	);
 	io.extend_with(
 		sc_consensus_babe_rpc::BabeApi::to_delegate(
 			BabeRpcHandler::new(
@@ -0,0 +1,91 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2019-2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests of the author RPCs against `node-runtime`, which has sessions.
+
+use std::sync::Arc;
+use codec::Encode;
+use jsonrpc_pubsub::manager::SubscriptionManager;
+use node_testing::{
+	client::{TestClientBuilder, TestClientBuilderExt},
+	keyring::{alice, to_session_keys},
+};
+use sc_rpc::{
+	DenyUnsafe, SubscriptionTaskExecutor,
+	author::{Author, AuthorApi, keys::SessionPublicKey},
+};
+use sc_transaction_pool::BasicPool;
+use sp_core::testing::{KeyStore, TaskExecutor};
+use sp_keyring::{Ed25519Keyring, Sr25519Keyring};
+
+#[test]
+fn rotate_keys_with_info_returns_the_session_and_the_registered_keys() {
+	let keystore = KeyStore::new();
+	let client = Arc::new(TestClientBuilder::new().set_keystore(keystore.clone()).build());
+	let pool = BasicPool::new_full(Default::default(), None, TaskExecutor::new(), client.clone());
+	let author = Author::new(
+		client.clone(),
+		pool,
+		SubscriptionManager::new(Arc::new(SubscriptionTaskExecutor::new(TaskExecutor::new()))),
+		keystore,
+		DenyUnsafe::No,
+	);
+
+	// Alice is a validator of the genesis, with the keys of the keyring.
+	let info = author.rotate_keys_with_info(Some(alice().encode().into()))
+		.expect("Rotates the keys");
+
+	assert_eq!(info.generated_at, client.chain_info().best_hash);
+	assert_eq!(info.public_keys.len(), 4);
+	assert!(author.has_session_keys(info.keys.clone()).unwrap());
+	assert_eq!(info.session_index, Some(0));
+	assert_eq!(info.earliest_active_session, Some(2));
+
+	let registered = to_session_keys(&Ed25519Keyring::Alice, &Sr25519Keyring::Alice);
+	assert_eq!(info.previous_keys, Some(registered.encode().into()));
+	let sr25519 = Sr25519Keyring::Alice.public().0.to_vec();
+	assert_eq!(info.previous_public_keys, vec![
+		SessionPublicKey {
+			key_type: "gran".into(),
+			public: Ed25519Keyring::Alice.public().0.to_vec().into(),
+		},
+		SessionPublicKey { key_type: "babe".into(), public: sr25519.clone().into() },
+		SessionPublicKey { key_type: "imon".into(), public: sr25519.clone().into() },
+		SessionPublicKey { key_type: "audi".into(), public: sr25519.into() },
+	]);
+	assert_ne!(info.keys, registered.encode().into());
+}
+
+#[test]
+fn rotate_keys_with_info_without_validator_returns_no_previous_keys() {
+	let keystore = KeyStore::new();
+	let client = Arc::new(TestClientBuilder::new().set_keystore(keystore.clone()).build());
+	let pool = BasicPool::new_full(Default::default(), None, TaskExecutor::new(), client.clone());
+	let author = Author::new(
+		client,
+		pool,
+		SubscriptionManager::new(Arc::new(SubscriptionTaskExecutor::new(TaskExecutor::new()))),
+		keystore,
+		DenyUnsafe::No,
+	);
+
+	let info = author.rotate_keys_with_info(None).expect("Rotates the keys");
+
+	assert_eq!(info.session_index, Some(0));
+	assert_eq!(info.previous_keys, None);
+	assert!(info.previous_public_keys.is_empty());
+}
@@ -109,10 +109,10 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	// and set impl_version to 0. If only runtime
 	// implementation changes and behavior does not, then leave spec_version as
 	// is and increment impl_version.
-	spec_version: 258,
+	spec_version: 265,
 	impl_version: 0,
 	apis: RUNTIME_API_VERSIONS,
-	transaction_version: 1,
+	transaction_version: 3,
 };
 
 /// Native version.
@@ -909,6 +909,9 @@ pub type BlockId = generic::BlockId<Block>;
 /// When you change this, you **MUST** modify [`sign`] in `bin/node/testing/src/keyring.rs`!
 ///
 /// [`sign`]: <../../testing/src/keyring.rs.html>
+///
+/// `frame_system::CheckTraceContext` is left out until all the nodes provide the
+/// `sp_io::wasm_tracing` host functions.
 pub type SignedExtra = (
 	frame_system::CheckSpecVersion<Runtime>,
 	frame_system::CheckTxVersion<Runtime>,
@@ -925,7 +928,18 @@ pub type SignedPayload = generic::SignedPayload<Call, SignedExtra>;
 /// Extrinsic type that has already been checked.
 pub type CheckedExtrinsic = generic::CheckedExtrinsic<AccountId, Call, SignedExtra>;
 /// Executive: handles dispatch to the various modules.
+#[cfg(not(feature = "balances-invariant-checks"))]
 pub type Executive = frame_executive::Executive<Runtime, Block, frame_system::ChainContext<Runtime>, Runtime, AllModules>;
+/// Executive: handles dispatch to the various modules, checking the balances invariants after
+/// every block.
+#[cfg(feature = "balances-invariant-checks")]
+pub type Executive = frame_executive::Executive<
+	Runtime,
+	Block,
+	frame_system::ChainContext<Runtime>,
+	Runtime,
+	(AllModules, pallet_balances::invariants::CheckInvariants<Runtime>),
+>;
 
 impl_runtime_apis! {
 	impl sp_api::Core<Block> for Runtime {
@@ -948,6 +962,21 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl sp_api::TraceMetadata<Block> for Runtime {
+		fn trace_metadata() -> OpaqueMetadata {
+			Runtime::trace_metadata()
+				.with_spans("Executive", frame_executive::TRACE_SPANS)
+				.with_spans("Staking", pallet_staking::TRACE_SPANS)
+				.with_spans("ImOnline", pallet_im_online::TRACE_SPANS)
+				.with_spans("Sudo", pallet_sudo::TRACE_SPANS)
+				.with_spans("Utility", pallet_utility::TRACE_SPANS)
+				.with_spans("Multisig", pallet_multisig::TRACE_SPANS)
+				.with_spans("Scheduler", pallet_scheduler::TRACE_SPANS)
+				.with_spans("Proxy", pallet_proxy::TRACE_SPANS)
+				.into()
+		}
+	}
+
 	impl sp_block_builder::BlockBuilder<Block> for Runtime {
 		fn apply_extrinsic(extrinsic: <Block as BlockT>::Extrinsic) -> ApplyExtrinsicResult {
 			Executive::apply_extrinsic(extrinsic)
@@ -1110,6 +1139,12 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl pallet_im_online_rpc_runtime_api::ImOnlineApi<Block, ImOnlineId> for Runtime {
+		fn heartbeats() -> Vec<(ImOnlineId, pallet_im_online_rpc_runtime_api::HeartbeatInfo)> {
+			ImOnline::heartbeats()
+		}
+	}
+
 	impl pallet_transaction_payment_rpc_runtime_api::TransactionPaymentApi<
 		Block,
 		Balance,
@@ -1129,6 +1164,15 @@ impl_runtime_apis! {
 		) -> Option<Vec<(Vec<u8>, KeyTypeId)>> {
 			SessionKeys::decode_into_raw_public_keys(&encoded)
 		}
+
+		fn current_session_index() -> Option<sp_session::SessionIndex> {
+			Some(Session::current_index())
+		}
+
+		fn next_session_keys(validator: Vec<u8>) -> Option<Vec<u8>> {
+			let validator = AccountId::decode(&mut &validator[..]).ok()?;
+			Session::next_keys(&validator).map(|keys| keys.encode())
+		}
 	}
 
 	#[cfg(feature = "runtime-benchmarks")]
@@ -55,4 +55,12 @@ impl frame_system::WeightInfo for WeightInfo {
 	fn suicide() -> Weight {
 		(29247000 as Weight)
 	}
+	// NOT GENERATED! The weight of `set_heap_pages`, which also writes a single well-known key, with
+	// the per byte weight of `pallet_democracy::note_imminent_preimage`, which also stores the bytes
+	// it is given, until this is regenerated with `benchmark --pallet frame_system --extrinsic set_tracing_targets`.
+	fn set_tracing_targets(t: u32, ) -> Weight {
+		(2023000 as Weight)
+			.saturating_add((3000 as Weight).saturating_mul(t as Weight))
+			.saturating_add(DbWeight::get().writes(1 as Weight))
+	}
 }
@@ -288,6 +288,11 @@ impl <'a, State, Block> KeyIterator<'a, State, Block> {
 			_phantom: PhantomData,
 		}
 	}
+
+	/// Iterate the keys with their values, read from the same state.
+	pub fn with_values(self) -> KeyValueIterator<'a, State, Block> {
+		KeyValueIterator(self)
+	}
 }
 
 impl<'a, State, Block> Iterator for KeyIterator<'a, State, Block> where
@@ -312,6 +317,26 @@ impl<'a, State, Block> Iterator for KeyIterator<'a, State, Block> where
 	}
 }
 
+/// Iterator over the storage keys and values of a state, in lexicographic order of the keys.
+pub struct KeyValueIterator<'a, State, Block>(KeyIterator<'a, State, Block>);
+
+impl<'a, State, Block> Iterator for KeyValueIterator<'a, State, Block> where
+	Block: BlockT,
+	State: StateBackend<HashFor<Block>>,
+{
+	type Item = (StorageKey, StorageData);
+
+	fn next(&mut self) -> Option<Self::Item> {
+		let key = self.0.next()?;
+		// this terminates the iterator the first time it fails, as for the keys.
+		let value = self.0.state
+			.storage(&key.0)
+			.ok()
+			.flatten()?;
+		Some((key, StorageData(value)))
+	}
+}
+
 /// Provides acess to storage primitives
 pub trait StorageProvider<Block: BlockT, B: Backend<Block>> {
 	/// Given a `BlockId` and a key, return the value under the key in that block.
@@ -444,6 +469,13 @@ pub trait Backend<Block: BlockT>: AuxStore + Send + Sync {
 	/// Returns a handle to offchain storage.
 	fn offchain_storage(&self) -> Option<Self::OffchainStorage>;
 
+	/// Returns a handle to the storage of the trace summaries.
+	///
+	/// The backends not storing the trace summaries don't have to implement it.
+	fn trace_summary_storage(&self) -> Option<Arc<dyn TraceSummaryStorage<Block>>> {
+		None
+	}
+
 	/// Returns true if state for given block is available.
 	fn have_state_at(&self, hash: &Block::Hash, _number: NumberFor<Block>) -> bool {
 		self.state_at(BlockId::Hash(hash.clone())).is_ok()
@@ -489,6 +521,77 @@ pub trait Backend<Block: BlockT>: AuxStore + Send + Sync {
 	fn get_import_lock(&self) -> &RwLock<()>;
 }
 
+/// Storage of the summaries of the traces of the imported blocks, e.g. `sc_tracing::BlockSummary`.
+pub trait TraceSummaryStorage<Block: BlockT>: Send + Sync {
+	/// Store the encoded summary of the block `hash`, replacing any previous one.
+	fn insert_summary(
+		&self,
+		hash: Block::Hash,
+		number: NumberFor<Block>,
+		summary: &[u8],
+	) -> sp_blockchain::Result<()>;
+
+	/// Remove the summaries of every block up to the given `number`, included.
+	fn prune_summaries(&self, number: NumberFor<Block>) -> sp_blockchain::Result<()>;
+
+	/// Returns the encoded summary of the block `hash`, if any.
+	fn summary(&self, hash: &Block::Hash) -> sp_blockchain::Result<Option<Vec<u8>>>;
+
+	/// Returns the encoded summaries of at most `count` blocks, the highest first.
+	fn recent_summaries(&self, count: usize) -> sp_blockchain::Result<Vec<Vec<u8>>>;
+
+	/// Remove the summaries of the reverted `blocks`, keeping those of the other blocks at the
+	/// same heights, and return the keys touched.
+	fn revert_summaries(
+		&self,
+		blocks: &[(Block::Hash, NumberFor<Block>)],
+	) -> sp_blockchain::Result<Vec<TouchedKeys>>;
+}
+
+/// Keys of a column of the database removed or rewritten, e.g. by a revert.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TouchedKeys {
+	/// Name of the column.
+	pub column: &'static str,
+	/// What the keys are, e.g. `block hash`.
+	pub kind: &'static str,
+	/// The lowest of the keys touched.
+	pub first: Vec<u8>,
+	/// The highest of the keys touched.
+	pub last: Vec<u8>,
+	/// Number of keys touched.
+	pub count: usize,
+}
+
+impl TouchedKeys {
+	/// The keys `keys` of `kind` in `column`, if any.
+	pub fn new(column: &'static str, kind: &'static str, mut keys: Vec<Vec<u8>>) -> Option<Self> {
+		keys.sort();
+		keys.dedup();
+		Some(TouchedKeys {
+			column,
+			kind,
+			first: keys.first()?.clone(),
+			last: keys.last()?.clone(),
+			count: keys.len(),
+		})
+	}
+}
+
+impl std::fmt::Display for TouchedKeys {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		write!(
+			f,
+			"column {}: {} {} keys in 0x{}..=0x{}",
+			self.column,
+			self.count,
+			self.kind,
+			sp_core::hexdisplay::HexDisplay::from(&self.first),
+			sp_core::hexdisplay::HexDisplay::from(&self.last),
+		)
+	}
+}
+
 /// Changes trie storage that supports pruning.
 pub trait PrunableStateChangesTrieStorage<Block: BlockT>:
 	StateChangesTrieStorage<HashFor<Block>, NumberFor<Block>>
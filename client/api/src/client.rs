@@ -16,7 +16,7 @@
 
 //! A set of APIs supported by the client along with their primitives.
 
-use std::{fmt, collections::HashSet, sync::Arc, convert::TryFrom};
+use std::{fmt, collections::HashSet, sync::Arc, convert::TryFrom, time::Duration};
 use sp_core::storage::StorageKey;
 use sp_runtime::{
 	traits::{Block as BlockT, NumberFor},
@@ -109,6 +109,8 @@ pub struct ClientInfo<Block: BlockT> {
 	pub chain: Info<Block>,
 	/// Usage info, if backend supports this.
 	pub usage: Option<UsageInfo>,
+	/// Timings of the recent block imports.
+	pub import: ImportInfo,
 }
 
 /// A wrapper to store the size of some memory.
@@ -226,6 +228,15 @@ impl fmt::Display for UsageInfo {
 	}
 }
 
+/// Timings of the recent block imports of a client instance.
+#[derive(Default, Clone, Debug, PartialEq, Eq)]
+pub struct ImportInfo {
+	/// Number of the recent imports timed, at most the number the client keeps.
+	pub recent_imports: u32,
+	/// Average time of the recent imports, if any.
+	pub average_time: Option<Duration>,
+}
+
 /// Summary of an imported block
 #[derive(Clone, Debug)]
 pub struct BlockImportNotification<Block: BlockT> {
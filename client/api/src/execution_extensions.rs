@@ -50,6 +50,20 @@ pub struct ExecutionStrategies {
 	pub other: ExecutionStrategy,
 }
 
+impl ExecutionStrategies {
+	/// The name of the execution `context` and the strategy used in it.
+	pub fn for_context(&self, context: &ExecutionContext) -> (&'static str, ExecutionStrategy) {
+		match context {
+			ExecutionContext::BlockConstruction => ("block_construction", self.block_construction),
+			ExecutionContext::Syncing => ("syncing", self.syncing),
+			ExecutionContext::Importing => ("importing", self.importing),
+			ExecutionContext::OffchainCall(Some((_, capabilities))) if capabilities.has_all() =>
+				("offchain_worker", self.offchain_worker),
+			ExecutionContext::OffchainCall(_) => ("other", self.other),
+		}
+	}
+}
+
 impl Default for ExecutionStrategies {
 	fn default() -> ExecutionStrategies {
 		ExecutionStrategies {
@@ -143,18 +157,15 @@ impl<Block: traits::Block> ExecutionExtensions<Block> {
 		ExecutionManager<DefaultHandler<R, E>>,
 		Extensions,
 	) {
-		let manager = match context {
-			ExecutionContext::BlockConstruction =>
-				self.strategies.block_construction.get_manager(),
-			ExecutionContext::Syncing =>
-				self.strategies.syncing.get_manager(),
-			ExecutionContext::Importing =>
-				self.strategies.importing.get_manager(),
-			ExecutionContext::OffchainCall(Some((_, capabilities))) if capabilities.has_all() =>
-				self.strategies.offchain_worker.get_manager(),
-			ExecutionContext::OffchainCall(_) =>
-				self.strategies.other.get_manager(),
-		};
+		let (context_name, strategy) = self.strategies.for_context(&context);
+		sp_tracing::event!(
+			trace;
+			target: sp_tracing::EXECUTION_TARGET,
+			context = context_name,
+			strategy = ?strategy,
+			"Execution strategy",
+		);
+		let manager = strategy.get_manager();
 
 		let capabilities = context.capabilities();
 
@@ -44,6 +44,9 @@ pub use sp_state_machine::{StorageProof, ExecutionStrategy};
 pub trait UsageProvider<Block: sp_runtime::traits::Block> {
 	/// Get usage info about current client.
 	fn usage_info(&self) -> ClientInfo<Block>;
+
+	/// Get the timings of the recent block imports, without gathering the usage statistics.
+	fn import_info(&self) -> ImportInfo;
 }
 
 /// Utility methods for the client.
@@ -0,0 +1,132 @@
+// Copyright 2019-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! RPC API for the authority discovery.
+#![warn(missing_docs)]
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use futures::{FutureExt, TryFutureExt};
+use jsonrpc_core::{Error as RpcError, ErrorCode};
+use jsonrpc_derive::rpc;
+use sc_authority_discovery::Service;
+use sc_rpc_api::DenyUnsafe;
+use serde::{Deserialize, Serialize};
+use sp_authority_discovery::AuthorityId;
+
+pub use self::gen_client::Client as AuthorityDiscoveryClient;
+
+/// Returned when the authority discovery worker is not running anymore.
+pub const WORKER_UNAVAILABLE_ERROR_CODE: i64 = 1;
+
+type FutureResult<T> =
+	Box<dyn jsonrpc_core::futures::Future<Item = T, Error = jsonrpc_core::Error> + Send>;
+
+/// An authority whose addresses are known to the local node.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KnownAuthority {
+	/// The authority discovery key of the authority.
+	pub authority_id: AuthorityId,
+	/// The addresses the authority published on the Dht.
+	pub addresses: Vec<String>,
+	/// When the addresses were last found on the Dht, in milliseconds since the Unix epoch.
+	pub last_refreshed: u64,
+}
+
+impl From<sc_authority_discovery::KnownAuthority> for KnownAuthority {
+	fn from(known: sc_authority_discovery::KnownAuthority) -> Self {
+		KnownAuthority {
+			authority_id: known.authority_id,
+			addresses: known.addresses.iter().map(ToString::to_string).collect(),
+			last_refreshed: unix_millis(known.last_refreshed),
+		}
+	}
+}
+
+fn unix_millis(time: SystemTime) -> u64 {
+	time.duration_since(UNIX_EPOCH)
+		.map(|duration| duration.as_millis() as u64)
+		.unwrap_or(0)
+}
+
+/// Provides RPC methods for inspecting the authority discovery.
+#[rpc]
+pub trait AuthorityDiscoveryApi {
+	/// Returns the authorities of the local address cache, along with their addresses and when
+	/// these were last refreshed.
+	#[rpc(name = "authorityDiscovery_knownAuthorities")]
+	fn known_authorities(&self) -> FutureResult<Vec<KnownAuthority>>;
+}
+
+/// Implements the [`AuthorityDiscoveryApi`] RPC trait by querying the authority discovery
+/// [`Service`].
+pub struct AuthorityDiscovery {
+	service: Service,
+	deny_unsafe: DenyUnsafe,
+}
+
+impl AuthorityDiscovery {
+	/// Create a new [`AuthorityDiscovery`] querying the given [`Service`].
+	pub fn new(service: Service, deny_unsafe: DenyUnsafe) -> Self {
+		AuthorityDiscovery { service, deny_unsafe }
+	}
+}
+
+impl AuthorityDiscoveryApi for AuthorityDiscovery {
+	fn known_authorities(&self) -> FutureResult<Vec<KnownAuthority>> {
+		if let Err(err) = self.deny_unsafe.check_if_safe() {
+			return Box::new(jsonrpc_core::futures::future::err(err.into()));
+		}
+
+		let mut service = self.service.clone();
+		let future = async move {
+			service.known_authorities().await
+				.map(|known| known.into_iter().map(Into::into).collect())
+				.ok_or_else(|| RpcError {
+					code: ErrorCode::ServerError(WORKER_UNAVAILABLE_ERROR_CODE),
+					message: "Authority discovery worker is not running".into(),
+					data: None,
+				})
+		}.boxed();
+
+		Box::new(future.compat())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_authority_discovery::AuthorityPair;
+	use sp_core::crypto::Pair;
+
+	#[test]
+	fn known_authority_serialization() {
+		let authority_id: AuthorityId = AuthorityPair::from_seed(&[1; 32]).public();
+		let address = "/ip6/2001:db8:0:0:0:0:0:2/tcp/30333".parse().unwrap();
+		let known = KnownAuthority::from(sc_authority_discovery::KnownAuthority {
+			authority_id: authority_id.clone(),
+			addresses: vec![address],
+			last_refreshed: UNIX_EPOCH + std::time::Duration::from_millis(1_500),
+		});
+
+		let expected = format!(
+			r#"{{"authorityId":"{}","addresses":["/ip6/2001:db8::2/tcp/30333"],"lastRefreshed":1500}}"#,
+			serde_json::to_value(&authority_id).unwrap().as_str().unwrap(),
+		);
+		assert_eq!(expected, serde_json::to_string(&known).unwrap());
+	}
+}
@@ -28,6 +28,7 @@ pub use crate::{service::Service, worker::{NetworkProvider, Worker, Role}};
 
 use std::pin::Pin;
 use std::sync::Arc;
+use std::time::SystemTime;
 
 use futures::channel::{mpsc, oneshot};
 use futures::Stream;
@@ -74,5 +75,18 @@ pub(crate) enum ServicetoWorkerMsg {
 	/// See [`Service::get_addresses_by_authority_id`].
 	GetAddressesByAuthorityId(AuthorityId, oneshot::Sender<Option<Vec<Multiaddr>>>),
 	/// See [`Service::get_authority_id_by_peer_id`].
-	GetAuthorityIdByPeerId(PeerId, oneshot::Sender<Option<AuthorityId>>)
+	GetAuthorityIdByPeerId(PeerId, oneshot::Sender<Option<AuthorityId>>),
+	/// See [`Service::known_authorities`].
+	GetKnownAuthorities(oneshot::Sender<Vec<KnownAuthority>>),
+}
+
+/// An authority whose addresses are in the local address cache of the [`Worker`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KnownAuthority {
+	/// The [`AuthorityId`] of the authority.
+	pub authority_id: AuthorityId,
+	/// The addresses the authority published on the Dht.
+	pub addresses: Vec<Multiaddr>,
+	/// When the addresses of the authority were last found on the Dht.
+	pub last_refreshed: SystemTime,
 }
@@ -22,6 +22,8 @@ use futures::SinkExt;
 use sc_network::{Multiaddr, PeerId};
 use sp_authority_discovery::AuthorityId;
 
+use crate::KnownAuthority;
+
 /// Service to interact with the [`Worker`].
 #[derive(Clone)]
 pub struct Service {
@@ -67,4 +69,19 @@ impl Service {
 
 		rx.await.ok().flatten()
 	}
+
+	/// Get all the authorities of the local address cache, along with their addresses and when
+	/// these were last refreshed.
+	///
+	/// Returns `None` if connection to the [`crate::Worker`] failed.
+	pub async fn known_authorities(&mut self) -> Option<Vec<KnownAuthority>> {
+		let (tx, rx) = oneshot::channel();
+
+		self.to_worker
+			.send(ServicetoWorkerMsg::GetKnownAuthorities(tx))
+			.await
+			.ok()?;
+
+		rx.await.ok()
+	}
 }
@@ -19,6 +19,7 @@
 use crate::{new_worker_and_service, worker::{tests::{TestApi, TestNetwork}, Role}};
 
 use std::sync::Arc;
+use std::time::SystemTime;
 
 use futures::prelude::*;
 use futures::channel::mpsc::channel;
@@ -76,3 +77,47 @@ fn get_addresses_and_authority_id() {
 		);
 	});
 }
+
+#[test]
+fn get_known_authorities() {
+	let (_dht_event_tx, dht_event_rx) = channel(0);
+	let network: Arc<TestNetwork> = Arc::new(Default::default());
+
+	let key_store = KeyStore::new();
+	let remote_authority_id: AuthorityId = key_store
+		.write()
+		.sr25519_generate_new(key_types::AUTHORITY_DISCOVERY, None)
+		.unwrap()
+		.into();
+
+	let remote_addr = "/ip6/2001:db8:0:0:0:0:0:2/tcp/30333".parse::<Multiaddr>()
+		.unwrap()
+		.with(Protocol::P2p(PeerId::random().into()));
+
+	let test_api = Arc::new(TestApi {
+		authorities: vec![],
+	});
+
+	let (mut worker, mut service) = new_worker_and_service(
+		test_api,
+		network.clone(),
+		vec![],
+		dht_event_rx.boxed(),
+		Role::Authority(key_store),
+		None,
+	);
+
+	let before_insertion = SystemTime::now();
+	worker.inject_addresses(remote_authority_id.clone(), vec![remote_addr.clone()]);
+
+	let mut pool = LocalPool::new();
+	pool.spawner().spawn_local_obj(Box::pin(worker).into()).unwrap();
+
+	pool.run_until(async {
+		let known_authorities = service.known_authorities().await.unwrap();
+		assert_eq!(1, known_authorities.len());
+		assert_eq!(remote_authority_id, known_authorities[0].authority_id);
+		assert_eq!(vec![remote_addr], known_authorities[0].addresses);
+		assert!(known_authorities[0].last_refreshed >= before_insertion);
+	});
+}
@@ -268,6 +268,14 @@ where
 
 		let addresses = self.addresses_to_publish();
 
+		let span = tracing::debug_span!(
+			target: LOG_TARGET,
+			"publish_ext_addresses",
+			addresses = addresses.len() as u64,
+			keys = tracing::field::Empty,
+		);
+		let _guard = span.enter();
+
 		if let Some(metrics) = &self.metrics {
 			metrics.publish.inc();
 			metrics.amount_last_published.set(addresses.len() as u64);
@@ -282,6 +290,7 @@ where
 			&key_store,
 			&self.client,
 		)?.into_iter().map(Into::into).collect::<Vec<_>>();
+		span.record("keys", &(keys.len() as u64));
 
 		let signatures = key_store.read()
 			.sign_with_all(
@@ -315,6 +324,14 @@ where
 	}
 
 	fn request_addresses_of_others(&mut self) -> Result<()> {
+		let span = tracing::debug_span!(
+			target: LOG_TARGET,
+			"request_addresses_of_others",
+			authorities = tracing::field::Empty,
+			requested = tracing::field::Empty,
+		);
+		let _guard = span.enter();
+
 		let id = BlockId::hash(self.client.info().best_hash);
 
 		let authorities = self
@@ -333,6 +350,7 @@ where
 			Role::Sentry => HashSet::new(),
 		};
 
+		let mut requested = 0u64;
 		for authority_id in authorities.iter() {
 			// Make sure we don't look up our own keys.
 			if !local_keys.contains(authority_id.as_ref()) {
@@ -342,9 +360,13 @@ where
 
 				self.network
 					.get_value(&hash_authority_id(authority_id.as_ref()));
+				requested += 1;
 			}
 		}
 
+		span.record("authorities", &(authorities.len() as u64));
+		span.record("requested", &requested);
+
 		Ok(())
 	}
 
@@ -422,6 +444,15 @@ where
 		&mut self,
 		values: Vec<(libp2p::kad::record::Key, Vec<u8>)>,
 	) -> Result<()> {
+		let span = tracing::debug_span!(
+			target: LOG_TARGET,
+			"handle_dht_value_found_event",
+			records = values.len() as u64,
+			authority = tracing::field::Empty,
+			addresses = tracing::field::Empty,
+		);
+		let _guard = span.enter();
+
 		// Ensure `values` is not empty and all its keys equal.
 		let remote_key = values.iter().fold(Ok(None), |acc, (key, _)| {
 			match acc {
@@ -451,6 +482,7 @@ where
 		let authority_id: &AuthorityId = authorities
 			.get(&remote_key)
 			.ok_or(Error::MatchingHashedAuthorityIdWithAuthorityId)?;
+		span.record("authority", &tracing::field::display(authority_id));
 
 		let local_peer_id = self.network.local_peer_id();
 
@@ -501,6 +533,7 @@ where
 			}))
 			.take(MAX_ADDRESSES_PER_AUTHORITY)
 			.collect();
+		span.record("addresses", &(remote_addresses.len() as u64));
 
 		if !remote_addresses.is_empty() {
 			self.addr_cache.insert(authority_id.clone(), remote_addresses);
@@ -649,6 +682,9 @@ where
 						self.addr_cache.get_authority_id_by_peer_id(&peer_id).map(Clone::clone),
 					);
 				}
+				ServicetoWorkerMsg::GetKnownAuthorities(sender) => {
+					let _ = sender.send(self.addr_cache.known_authorities());
+				}
 			}
 		}
 
@@ -17,10 +17,13 @@
 use libp2p::core::multiaddr::{Multiaddr, Protocol};
 use rand::seq::SliceRandom;
 use std::collections::HashMap;
+use std::time::SystemTime;
 
 use sp_authority_discovery::AuthorityId;
 use sc_network::PeerId;
 
+use crate::KnownAuthority;
+
 /// The maximum number of authority connections initialized through the authority discovery module.
 ///
 /// In other words the maximum size of the `authority` peerset priority group.
@@ -30,6 +33,8 @@ const MAX_NUM_AUTHORITY_CONN: usize = 10;
 pub(super) struct AddrCache {
 	authority_id_to_addresses: HashMap<AuthorityId, Vec<Multiaddr>>,
 	peer_id_to_authority_id: HashMap<PeerId, AuthorityId>,
+	/// When the addresses of each [`AuthorityId`] were last inserted.
+	authority_id_to_last_refreshed: HashMap<AuthorityId, SystemTime>,
 }
 
 impl AddrCache {
@@ -37,6 +42,7 @@ impl AddrCache {
 		AddrCache {
 			authority_id_to_addresses: HashMap::new(),
 			peer_id_to_authority_id: HashMap::new(),
+			authority_id_to_last_refreshed: HashMap::new(),
 		}
 	}
 
@@ -57,6 +63,7 @@ impl AddrCache {
 
 		// Insert into `self.authority_id_to_addresses`.
 		addresses.sort_unstable_by(|a, b| a.as_ref().cmp(b.as_ref()));
+		self.authority_id_to_last_refreshed.insert(authority_id.clone(), SystemTime::now());
 		self.authority_id_to_addresses.insert(authority_id, addresses);
 	}
 
@@ -75,6 +82,20 @@ impl AddrCache {
 		self.peer_id_to_authority_id.get(peer_id)
 	}
 
+	/// Returns all the [`AuthorityId`]s in the cache, along with their addresses and when these
+	/// were last refreshed.
+	pub fn known_authorities(&self) -> Vec<KnownAuthority> {
+		self.authority_id_to_addresses.iter()
+			.map(|(authority_id, addresses)| KnownAuthority {
+				authority_id: authority_id.clone(),
+				addresses: addresses.clone(),
+				last_refreshed: self.authority_id_to_last_refreshed.get(authority_id)
+					.cloned()
+					.unwrap_or(SystemTime::UNIX_EPOCH),
+			})
+			.collect()
+	}
+
 	/// Returns a single address for a random subset (maximum of [`MAX_NUM_AUTHORITY_CONN`]) of all
 	/// known authorities.
 	pub fn get_random_subset(&self) -> Vec<Multiaddr> {
@@ -112,6 +133,7 @@ impl AddrCache {
 		for authority_id_to_remove in authority_ids_to_remove {
 			// Remove other entries from `self.authority_id_to_addresses`.
 			let addresses = self.authority_id_to_addresses.remove(&authority_id_to_remove);
+			self.authority_id_to_last_refreshed.remove(&authority_id_to_remove);
 
 			// Remove other entries from `self.peer_id_to_authority_id`.
 			let peer_ids = addresses.iter()
@@ -222,6 +244,10 @@ mod tests {
 				None, cache.get_authority_id_by_peer_id(&peer_id_from_multiaddr(&third.1).unwrap()),
 				"Expect `get_authority_id_by_peer_id` to return `None` for third authority."
 			);
+			assert!(
+				cache.known_authorities().iter().all(|known| known.authority_id != third.0),
+				"Expect `known_authorities` to not return third authority."
+			);
 
 			TestResult::passed()
 		}
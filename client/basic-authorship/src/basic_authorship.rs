@@ -28,7 +28,9 @@ use sp_inherents::InherentData;
 use log::{error, info, debug, trace, warn};
 use sp_runtime::{
 	generic::BlockId,
-	traits::{Block as BlockT, Hash as HashT, Header as HeaderT, DigestFor, BlakeTwo256},
+	traits::{
+		Block as BlockT, Hash as HashT, Header as HeaderT, DigestFor, BlakeTwo256, SaturatedConversion,
+	},
 };
 use sp_transaction_pool::{TransactionPool, InPoolTransaction};
 use sc_telemetry::{telemetry, CONSENSUS_INFO};
@@ -193,6 +195,17 @@ impl<A, B, Block, C> Proposer<B, Block, C, A>
 		/// It allows us to increase block utilization.
 		const MAX_SKIPPED_TRANSACTIONS: usize = 8;
 
+		// The proof recorded while building the block is broken down by the spans under this one,
+		// see `sc_tracing::PovBreakdowns`.
+		let span = tracing::debug_span!(
+			"build_block",
+			block_number = self.parent_number.saturated_into::<u64>() + 1,
+			parent_hash = ?self.parent_hash,
+			block_hash = tracing::field::Empty,
+			proof_size = tracing::field::Empty,
+		);
+		let _enter = span.enter();
+
 		let mut block_builder = self.client.new_block_at(
 			&self.parent_id,
 			inherent_digests,
@@ -280,6 +293,10 @@ impl<A, B, Block, C> Proposer<B, Block, C, A>
 		self.transaction_pool.remove_invalid(&unqueue_invalid);
 
 		let (block, storage_changes, proof) = block_builder.build()?.into_inner();
+		span.record("block_hash", &tracing::field::debug(block.header().hash()));
+		if let Some(proof) = &proof {
+			span.record("proof_size", &(proof.encoded_size() as u64));
+		}
 
 		self.metrics.report(
 			|metrics| {
@@ -18,6 +18,10 @@
 // NOTE: we allow missing docs here because arg_enum! creates the function variants without doc
 #![allow(missing_docs)]
 
+use crate::{Error, Result};
+use std::convert::TryFrom;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
 use structopt::clap::arg_enum;
 
 arg_enum! {
@@ -65,15 +69,108 @@ arg_enum! {
 	pub enum TracingReceiver {
 		Log,
 		Telemetry,
+		Parquet,
+		Binary,
+		Csv,
+		Loki,
+		Influx,
+		Datadog,
+		Gelf,
 	}
 }
 
-impl Into<sc_tracing::TracingReceiver> for TracingReceiver {
-	fn into(self) -> sc_tracing::TracingReceiver {
-		match self {
+/// Default sub directory of the traces written by the `Parquet`, `Binary` and `Csv` receivers.
+pub(crate) const DEFAULT_TRACES_PATH: &str = "traces";
+
+/// Default file of the span statistics written by the `Csv` receiver.
+pub(crate) const DEFAULT_SPAN_STATS_FILE: &str = "span-stats.csv";
+
+/// A new file for the `Binary` receiver, named after the time the node started.
+pub(crate) fn binary_trace_file() -> String {
+	let started = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+	format!("{}.sctrace", started.as_millis())
+}
+
+/// The `Parquet`, `Binary` and `Csv` receivers write to the `traces` directory of the working
+/// directory, see `ImportParams::tracing_receiver` for the one of the base path of a node.
+///
+/// Fails for the `Loki`, `Influx` and `Gelf` receivers, which require an endpoint, see
+/// [`TracingReceiver::with_output`].
+impl TryFrom<TracingReceiver> for sc_tracing::TracingReceiver {
+	type Error = Error;
+
+	fn try_from(receiver: TracingReceiver) -> Result<Self> {
+		let traces = PathBuf::from(DEFAULT_TRACES_PATH);
+		Ok(match receiver {
 			TracingReceiver::Log => sc_tracing::TracingReceiver::Log,
 			TracingReceiver::Telemetry => sc_tracing::TracingReceiver::Telemetry,
-		}
+			TracingReceiver::Parquet => sc_tracing::TracingReceiver::Parquet(traces),
+			TracingReceiver::Binary => sc_tracing::TracingReceiver::Binary(traces.join(binary_trace_file())),
+			TracingReceiver::Csv => sc_tracing::TracingReceiver::Csv(traces.join(DEFAULT_SPAN_STATS_FILE)),
+			TracingReceiver::Datadog => sc_tracing::TracingReceiver::Datadog(Default::default()),
+			TracingReceiver::Loki => return Err(Error::Input(
+				"--tracing-loki-endpoint is required by the Loki receiver".into(),
+			)),
+			TracingReceiver::Influx => return Err(Error::Input(
+				"--tracing-influx-endpoint is required by the Influx receiver".into(),
+			)),
+			TracingReceiver::Gelf => return Err(Error::Input(
+				"--tracing-gelf-endpoint is required by the Gelf receiver".into(),
+			)),
+		})
+	}
+}
+impl TracingReceiver {
+	/// The receiver writing to `output`: the directory of the `Parquet` files, the `Binary` trace
+	/// file, the `Csv` statistics file, or the `Loki`, `Influx`, `Datadog` or `Gelf` endpoint, the
+	/// `Datadog` one defaulting to the local agent.
+	///
+	/// The `Telemetry` receiver is only available to a running node.
+	pub fn with_output(self, output: Option<PathBuf>) -> Result<sc_tracing::TracingReceiver> {
+		let required = || output.clone()
+			.ok_or_else(|| format!("An output is required by the {} receiver", self));
+		Ok(match self {
+			TracingReceiver::Log => sc_tracing::TracingReceiver::Log,
+			TracingReceiver::Telemetry =>
+				return Err("Only a running node traces to telemetry".into()),
+			TracingReceiver::Parquet => sc_tracing::TracingReceiver::Parquet(required()?),
+			TracingReceiver::Binary => sc_tracing::TracingReceiver::Binary(required()?),
+			TracingReceiver::Csv => sc_tracing::TracingReceiver::Csv(required()?),
+			TracingReceiver::Loki => {
+				let endpoint = required()?;
+				let config = sc_tracing::LokiConfig::new(&endpoint.to_string_lossy())
+					.map_err(Error::Input)?;
+				sc_tracing::TracingReceiver::Loki(config)
+			},
+			TracingReceiver::Influx => {
+				let endpoint = required()?;
+				let config = sc_tracing::InfluxConfig::new(&endpoint.to_string_lossy())
+					.map_err(Error::Input)?;
+				sc_tracing::TracingReceiver::Influx(config)
+			},
+			TracingReceiver::Datadog => sc_tracing::TracingReceiver::Datadog(match &output {
+				Some(endpoint) => sc_tracing::DatadogConfig::new(&endpoint.to_string_lossy())
+					.map_err(Error::Input)?,
+				None => Default::default(),
+			}),
+			TracingReceiver::Gelf => {
+				let endpoint = required()?;
+				let config = sc_tracing::GelfConfig::new(&endpoint.to_string_lossy())
+					.map_err(Error::Input)?;
+				sc_tracing::TracingReceiver::Gelf(config)
+			},
+		})
+	}
+}
+
+arg_enum! {
+	/// Where the logs are written.
+	#[allow(missing_docs)]
+	#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+	pub enum LogOutput {
+		Stderr,
+		Syslog,
+		Journald,
 	}
 }
 
@@ -114,6 +211,8 @@ arg_enum! {
 		Both,
 		// Execute with the native build if possible; if it fails, then execute with WebAssembly.
 		NativeElseWasm,
+		// Execute with both builds, reporting where their traces diverge.
+		BothTraced,
 	}
 }
 
@@ -124,6 +223,7 @@ impl Into<sc_client_api::ExecutionStrategy> for ExecutionStrategy {
 			ExecutionStrategy::Wasm => sc_client_api::ExecutionStrategy::AlwaysWasm,
 			ExecutionStrategy::Both => sc_client_api::ExecutionStrategy::Both,
 			ExecutionStrategy::NativeElseWasm => sc_client_api::ExecutionStrategy::NativeElseWasm,
+			ExecutionStrategy::BothTraced => sc_client_api::ExecutionStrategy::BothTraced,
 		}
 	}
 }
@@ -136,6 +236,7 @@ impl ExecutionStrategy {
 			Self::Wasm => "Wasm",
 			Self::Both => "Both",
 			Self::NativeElseWasm => "NativeElseWasm",
+			Self::BothTraced => "BothTraced",
 		}
 	}
 }
@@ -203,3 +304,22 @@ pub const DEFAULT_EXECUTION_BLOCK_CONSTRUCTION: ExecutionStrategy = ExecutionStr
 pub const DEFAULT_EXECUTION_OFFCHAIN_WORKER: ExecutionStrategy = ExecutionStrategy::Native;
 /// Default value for the `--execution-other` parameter.
 pub const DEFAULT_EXECUTION_OTHER: ExecutionStrategy = ExecutionStrategy::Native;
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn receivers_without_their_endpoint_are_input_errors() {
+		assert!(matches!(
+			sc_tracing::TracingReceiver::try_from(TracingReceiver::Log),
+			Ok(sc_tracing::TracingReceiver::Log),
+		));
+		for receiver in vec![TracingReceiver::Loki, TracingReceiver::Influx, TracingReceiver::Gelf] {
+			assert!(matches!(
+				sc_tracing::TracingReceiver::try_from(receiver),
+				Err(Error::Input(_)),
+			));
+		}
+	}
+}
@@ -0,0 +1,158 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Implementation of the `diff` trace subcommand
+
+use crate::Error;
+use sc_tracing::{SpanDiff, SpanStats, TraceStats};
+use std::{fs::File, io::{self, BufReader, Write}, path::{Path, PathBuf}};
+use structopt::StructOpt;
+
+/// The `diff` command
+#[derive(Debug, StructOpt)]
+#[structopt(
+	name = "diff",
+	about = "Compare the spans of two traces written by the `Binary` tracing receiver."
+)]
+pub struct DiffTraceCmd {
+	/// Trace to compare from, e.g. recorded with the previous runtime version.
+	#[structopt(parse(from_os_str))]
+	pub before: PathBuf,
+
+	/// Trace to compare to.
+	#[structopt(parse(from_os_str))]
+	pub after: PathBuf,
+
+	/// Only compare the spans of the import of this block.
+	#[structopt(long, value_name = "NUMBER")]
+	pub block: Option<u64>,
+
+	/// Only report the spans whose duration changed by at least this percentage.
+	#[structopt(long, value_name = "PERCENT", default_value = "0")]
+	pub threshold: f64,
+}
+
+impl DiffTraceCmd {
+	/// Run the command
+	pub fn run(&self) -> Result<(), Error> {
+		self.report(&mut io::stdout().lock())
+	}
+
+	/// Write the spans whose duration changed to `out`.
+	fn report<W: Write>(&self, out: &mut W) -> Result<(), Error> {
+		let before = self.stats(&self.before)?;
+		let after = self.stats(&self.after)?;
+		let diffs: Vec<_> = sc_tracing::diff(&before, &after).into_iter()
+			.filter(|diff| change(diff).map_or(true, |change| change.abs() >= self.threshold))
+			.collect();
+
+		if diffs.is_empty() {
+			writeln!(out, "No changes")?;
+			return Ok(());
+		}
+		writeln!(out, "{:<48} {:>15} {:>15} {:>15} {:>9}", "span", "before", "after", "delta", "change")?;
+		for diff in diffs {
+			let change = match (&diff.before, &diff.after) {
+				(None, _) => "added".to_owned(),
+				(_, None) => "removed".to_owned(),
+				_ => change(&diff).map_or_else(String::new, |change| format!("{:+.1}%", change)),
+			};
+			writeln!(
+				out,
+				"{:<48} {:>15} {:>15} {:>15} {:>9}",
+				format!("{}::{}", diff.target, diff.name),
+				stats(diff.before),
+				stats(diff.after),
+				format!("{:+}ns", diff.delta_ns()),
+				change,
+			)?;
+		}
+
+		Ok(())
+	}
+
+	fn stats(&self, path: &Path) -> Result<TraceStats, Error> {
+		let input = BufReader::new(File::open(path)?);
+		sc_tracing::trace_stats(input, self.block)
+			.map_err(|e| Error::Input(format!("Invalid trace {}: {}", path.display(), e)))
+	}
+}
+
+/// Change of the duration of the spans in percent, `None` if they were added or removed.
+fn change(diff: &SpanDiff) -> Option<f64> {
+	match (diff.before, diff.after) {
+		(Some(before), Some(_)) if before.duration_ns > 0 =>
+			Some(diff.delta_ns() as f64 * 100.0 / before.duration_ns as f64),
+		_ => None,
+	}
+}
+
+fn stats(stats: Option<SpanStats>) -> String {
+	stats.map_or_else(|| "-".to_owned(), |s| format!("{}x {}ns", s.count, s.duration_ns))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::commands::trace::tests::{span, write_trace};
+	use sc_tracing_format::Value;
+
+	fn report(args: &[&str]) -> Vec<Vec<String>> {
+		let mut out = Vec::new();
+		DiffTraceCmd::from_iter(args).report(&mut out).unwrap();
+		String::from_utf8(out).unwrap().lines()
+			.map(|line| line.split_whitespace().map(ToOwned::to_owned).collect())
+			.collect()
+	}
+
+	#[test]
+	fn diff_trace() {
+		let dir = tempfile::tempdir().unwrap();
+		let before = dir.path().join("before.sctrace");
+		let after = dir.path().join("after.sctrace");
+		write_trace(&before, &[
+			span(2, Some(1), "execute", 10, vec![]),
+			span(3, Some(1), "removed", 5, vec![]),
+			span(1, None, "import_block", 20, vec![("block_number", Value::U64(1))]),
+		]);
+		write_trace(&after, &[
+			span(2, Some(1), "execute", 40, vec![]),
+			span(3, Some(1), "added", 20, vec![]),
+			span(1, None, "import_block", 20, vec![("block_number", Value::U64(1))]),
+		]);
+		let (before, after) = (before.to_str().unwrap(), after.to_str().unwrap());
+
+		assert_eq!(report(&["diff", before, after, "--block", "1"]), vec![
+			vec!["span", "before", "after", "delta", "change"],
+			vec!["test::execute", "1x", "10ns", "1x", "40ns", "+30ns", "+300.0%"],
+			vec!["test::added", "-", "1x", "20ns", "+20ns", "added"],
+			vec!["test::removed", "1x", "5ns", "-", "-5ns", "removed"],
+		]);
+		// The spans added or removed are reported whatever the threshold.
+		assert_eq!(report(&["diff", before, after, "--threshold", "500"]), vec![
+			vec!["span", "before", "after", "delta", "change"],
+			vec!["test::added", "-", "1x", "20ns", "+20ns", "added"],
+			vec!["test::removed", "1x", "5ns", "-", "-5ns", "removed"],
+		]);
+		assert_eq!(report(&["diff", before, after, "--block", "2"]), vec![vec!["No changes"]]);
+		assert_eq!(report(&["diff", before, before]), vec![vec!["No changes"]]);
+
+		let cmd = DiffTraceCmd::from_iter(&["diff", before, "missing.sctrace"]);
+		assert!(cmd.run().is_err());
+	}
+}
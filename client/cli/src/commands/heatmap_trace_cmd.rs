@@ -0,0 +1,183 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Implementation of the `heatmap` trace subcommand
+
+use crate::Error;
+use sc_tracing::{PrefixHeat, StorageHeatmap};
+use std::{fs::File, io::{self, BufReader, Write}, path::PathBuf};
+use structopt::StructOpt;
+
+/// The `heatmap` command
+#[derive(Debug, StructOpt)]
+#[structopt(
+	name = "heatmap",
+	about = "Rank the storage prefixes by their accesses in traces written by the `Binary` \
+		tracing receiver, recorded with the `state-access=trace` tracing target."
+)]
+pub struct HeatmapTraceCmd {
+	/// Traces to aggregate, e.g. the successive traces of a block range.
+	#[structopt(parse(from_os_str), required = true)]
+	pub traces: Vec<PathBuf>,
+
+	/// Only aggregate the accesses of the imports of the blocks from this one.
+	#[structopt(long, value_name = "NUMBER")]
+	pub from: Option<u64>,
+
+	/// Only aggregate the accesses of the imports of the blocks up to this one.
+	#[structopt(long, value_name = "NUMBER")]
+	pub to: Option<u64>,
+
+	/// Number of bytes of the keys to aggregate the accesses by.
+	///
+	/// The keys of the storage items start with the 16 bytes hashes of the pallet and item names.
+	#[structopt(long, value_name = "BYTES", default_value = "32")]
+	pub prefix_len: usize,
+
+	/// Number of prefixes to report.
+	#[structopt(long, value_name = "COUNT", default_value = "20")]
+	pub top: usize,
+}
+
+impl HeatmapTraceCmd {
+	/// Run the command
+	pub fn run(&self) -> Result<(), Error> {
+		self.report(&mut io::stdout().lock())
+	}
+
+	/// Write the most accessed prefixes to `out`.
+	fn report<W: Write>(&self, out: &mut W) -> Result<(), Error> {
+		let blocks = match (self.from, self.to) {
+			(None, None) => None,
+			(from, to) => Some(from.unwrap_or(0)..=to.unwrap_or(u64::max_value())),
+		};
+		let mut heatmap = StorageHeatmap::new(self.prefix_len, blocks);
+		for path in &self.traces {
+			let input = BufReader::new(File::open(path)?);
+			heatmap.read_trace(input)
+				.map_err(|e| Error::Input(format!("Invalid trace {}: {}", path.display(), e)))?;
+		}
+
+		let ranked = heatmap.ranked();
+		if ranked.is_empty() {
+			writeln!(out, "No storage accesses")?;
+			return Ok(());
+		}
+		let total: u64 = ranked.iter().map(PrefixHeat::accesses).sum();
+		writeln!(
+			out,
+			"{:<4} {:<66} {:>7} {:>10} {:>10} {:>12} {:>12} {:>8} {:>7}  {}",
+			"#", "prefix", "share", "reads", "writes", "read", "written", "keys", "blocks", "hints",
+		)?;
+		for (rank, heat) in ranked.iter().take(self.top).enumerate() {
+			let hints: Vec<_> = heat.hints().iter().map(ToString::to_string).collect();
+			writeln!(
+				out,
+				"{:<4} {:<66} {:>6.1}% {:>10} {:>10} {:>11}B {:>11}B {:>8} {:>7}  {}",
+				rank + 1,
+				match &heat.child {
+					Some(child) => format!("{}/{}", child, heat.prefix),
+					None => heat.prefix.clone(),
+				},
+				heat.accesses() as f64 * 100.0 / total as f64,
+				heat.reads,
+				heat.writes,
+				heat.read_bytes,
+				heat.written_bytes,
+				heat.keys,
+				heat.blocks,
+				hints.join(","),
+			)?;
+		}
+
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::commands::trace::tests::{span, write_trace};
+	use sc_tracing_format::{Event, Level, Record, Value};
+
+	fn access(parent_id: u64, key: &str, write: bool, len: u64) -> Record {
+		Record::Event(Event {
+			parent_id: Some(parent_id),
+			target: sp_tracing::STORAGE_ACCESS_TARGET.into(),
+			name: "event".into(),
+			level: Level::Trace,
+			time_us: 0,
+			fields: vec![
+				("write".into(), Value::Bool(write)),
+				("len".into(), Value::U64(len)),
+				("key".into(), Value::Str(key.into())),
+			],
+		})
+	}
+
+	fn block(id: u64, number: u64) -> Record {
+		span(id, None, "import_block", 0, vec![("block_number", Value::U64(number))])
+	}
+
+	fn report(args: &[&str]) -> Vec<Vec<String>> {
+		let mut out = Vec::new();
+		HeatmapTraceCmd::from_iter(args).report(&mut out).unwrap();
+		String::from_utf8(out).unwrap().lines()
+			.map(|line| line.split_whitespace().map(ToOwned::to_owned).collect())
+			.collect()
+	}
+
+	#[test]
+	fn heatmap_trace() {
+		let dir = tempfile::tempdir().unwrap();
+		let first = dir.path().join("first.sctrace");
+		let second = dir.path().join("second.sctrace");
+		write_trace(&first, &[
+			access(1, "aa01", false, 4),
+			access(1, "aa01", false, 4),
+			access(1, "aa01", false, 4),
+			access(1, "aa01", false, 4),
+			access(1, "bb01", true, 2048),
+			block(1, 1),
+		]);
+		write_trace(&second, &[
+			access(2, "aa02", false, 4),
+			block(2, 2),
+			access(3, "bb02", true, 8),
+			block(3, 3),
+		]);
+		let (first, second) = (first.to_str().unwrap(), second.to_str().unwrap());
+		let header = vec!["#", "prefix", "share", "reads", "writes", "read", "written", "keys", "blocks", "hints"];
+
+		let args = ["heatmap", first, second, "--from", "1", "--to", "2", "--prefix-len", "1"];
+		assert_eq!(report(&args), vec![
+			header.clone(),
+			vec!["1", "aa", "83.3%", "5", "0", "20B", "0B", "2", "2", "cache"],
+			vec!["2", "bb", "16.7%", "0", "1", "0B", "2048B", "1", "1", "layout"],
+		]);
+		let args = ["heatmap", first, second, "--prefix-len", "1", "--top", "1"];
+		assert_eq!(report(&args), vec![
+			header,
+			vec!["1", "aa", "71.4%", "5", "0", "20B", "0B", "2", "2", "cache"],
+		]);
+		assert_eq!(report(&["heatmap", first, second, "--from", "4"]), vec![vec!["No storage accesses"]]);
+
+		let cmd = HeatmapTraceCmd::from_iter(&["heatmap", first, "missing.sctrace"]);
+		assert!(cmd.run().is_err());
+	}
+}
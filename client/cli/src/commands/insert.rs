@@ -63,7 +63,7 @@ impl InsertCmd {
 			.ok_or_else(|| Error::Other("please supply base path".into()))?;
 
 		let (keystore, public) = match self.keystore_params.keystore_config(base_path)? {
-			KeystoreConfig::Path { path, password } => {
+			KeystoreConfig::Path { path, password, .. } => {
 				let public = with_crypto_scheme!(
 					self.crypto_scheme.scheme,
 					to_vec(&suri, password.clone())
@@ -72,6 +72,9 @@ impl InsertCmd {
 					.map_err(|e| format!("{}", e))?;
 				(keystore, public)
 			},
+			KeystoreConfig::Remote { .. } => {
+				return Err(Error::Other("Keys can not be inserted into a remote signer".into()))
+			},
 			_ => unreachable!("keystore_config always returns path and password; qed")
 		};
 
@@ -17,14 +17,18 @@
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 mod build_spec_cmd;
 mod check_block_cmd;
+mod diff_trace_cmd;
 mod export_blocks_cmd;
 mod export_state_cmd;
+mod heatmap_trace_cmd;
 mod import_blocks_cmd;
 mod purge_chain_cmd;
 mod sign;
 mod verify;
 mod vanity;
 mod revert_cmd;
+mod replay_trace_cmd;
+mod replay_traces_cmd;
 mod run_cmd;
 mod generate_node_key;
 mod generate;
@@ -32,6 +36,8 @@ mod insert;
 mod inspect_node_key;
 mod inspect;
 mod key;
+mod trace;
+mod weights_trace_cmd;
 pub mod utils;
 
 use std::fmt::Debug;
@@ -40,8 +46,10 @@ use structopt::StructOpt;
 pub use self::{
 	build_spec_cmd::BuildSpecCmd,
 	check_block_cmd::CheckBlockCmd,
+	diff_trace_cmd::DiffTraceCmd,
 	export_blocks_cmd::ExportBlocksCmd,
 	export_state_cmd::ExportStateCmd,
+	heatmap_trace_cmd::HeatmapTraceCmd,
 	import_blocks_cmd::ImportBlocksCmd,
 	purge_chain_cmd::PurgeChainCmd,
 	sign::SignCmd,
@@ -51,10 +59,14 @@ pub use self::{
 	generate_node_key::GenerateNodeKeyCmd,
 	inspect_node_key::InspectNodeKeyCmd,
 	key::KeySubcommand,
+	trace::TraceSubcommand,
 	vanity::VanityCmd,
 	verify::VerifyCmd,
 	revert_cmd::RevertCmd,
+	replay_trace_cmd::ReplayTraceCmd,
+	replay_traces_cmd::ReplayTracesCmd,
 	run_cmd::RunCmd,
+	weights_trace_cmd::WeightsTraceCmd,
 };
 
 /// All core commands that are provided by default.
@@ -84,6 +96,9 @@ pub enum Subcommand {
 
 	/// Remove the whole chain data.
 	PurgeChain(PurgeChainCmd),
+
+	/// Replay the trace summaries stored in the database through a tracing receiver.
+	ReplayTraces(ReplayTracesCmd),
 }
 
 /// Macro that helps implement CliConfiguration on an enum of subcommand automatically
@@ -343,6 +358,13 @@ macro_rules! substrate_cli_subcommands {
 				}
 			}
 
+			fn rpc_slow_calls(&self)
+			-> $crate::Result<::sc_service::config::SlowCallThresholds> {
+				match self {
+					$($enum::$variant(cmd) => cmd.rpc_slow_calls()),*
+				}
+			}
+
 			fn rpc_ws_max_connections(&self) -> $crate::Result<::std::option::Option<usize>> {
 				match self {
 					$($enum::$variant(cmd) => cmd.rpc_ws_max_connections()),*
@@ -356,6 +378,12 @@ macro_rules! substrate_cli_subcommands {
 				}
 			}
 
+			fn grpc(&self) -> $crate::Result<::std::option::Option<::std::net::SocketAddr>> {
+				match self {
+					$($enum::$variant(cmd) => cmd.grpc()),*
+				}
+			}
+
 			fn prometheus_config(&self, default_listen_port: u16)
 			-> $crate::Result<std::option::Option<sc_service::config::PrometheusConfig>> {
 				match self {
@@ -363,6 +391,17 @@ macro_rules! substrate_cli_subcommands {
 				}
 			}
 
+			fn metrics_namespace(
+				&self,
+				chain_id: &str,
+				node_name: &str,
+				role: &::sc_service::Role,
+			) -> $crate::Result<sc_service::config::MetricsNamespace> {
+				match self {
+					$($enum::$variant(cmd) => cmd.metrics_namespace(chain_id, node_name, role)),*
+				}
+			}
+
 			fn telemetry_endpoints(
 				&self,
 				chain_spec: &Box<dyn sc_service::ChainSpec>,
@@ -379,6 +418,13 @@ macro_rules! substrate_cli_subcommands {
 				}
 			}
 
+			fn telemetry_batching(&self)
+			-> $crate::Result<::std::option::Option<::sc_service::config::TelemetryBatching>> {
+				match self {
+					$($enum::$variant(cmd) => cmd.telemetry_batching()),*
+				}
+			}
+
 			fn default_heap_pages(&self) -> $crate::Result<::std::option::Option<u64>> {
 				match self {
 					$($enum::$variant(cmd) => cmd.default_heap_pages()),*
@@ -400,6 +446,13 @@ macro_rules! substrate_cli_subcommands {
 				}
 			}
 
+			fn authorship_post_mortem(&self, config_dir: &::std::path::PathBuf)
+			-> $crate::Result<::std::option::Option<::std::path::PathBuf>> {
+				match self {
+					$($enum::$variant(cmd) => cmd.authorship_post_mortem(config_dir)),*
+				}
+			}
+
 			fn disable_grandpa(&self) -> $crate::Result<bool> {
 				match self {
 					$($enum::$variant(cmd) => cmd.disable_grandpa()),*
@@ -418,9 +471,10 @@ macro_rules! substrate_cli_subcommands {
 				}
 			}
 
-			fn tracing_receiver(&self) -> $crate::Result<::sc_service::TracingReceiver> {
+			fn tracing_receiver(&self, base_path: &::std::path::PathBuf)
+			-> $crate::Result<::sc_service::TracingReceiver> {
 				match self {
-					$($enum::$variant(cmd) => cmd.tracing_receiver()),*
+					$($enum::$variant(cmd) => cmd.tracing_receiver(base_path)),*
 				}
 			}
 
@@ -454,5 +508,6 @@ substrate_cli_subcommands!(
 		ImportBlocks,
 		CheckBlock,
 		Revert,
-		PurgeChain
+		PurgeChain,
+		ReplayTraces
 );
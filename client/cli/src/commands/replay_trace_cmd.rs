@@ -0,0 +1,157 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Implementation of the `replay` trace subcommand
+
+use crate::{Error, TracingReceiver};
+use std::{fs::File, io::{self, BufReader, BufWriter}, path::PathBuf};
+use structopt::StructOpt;
+
+/// The `replay` command
+#[derive(Debug, StructOpt)]
+#[structopt(
+	name = "replay",
+	about = "Replay a trace written by the `Binary` tracing receiver through another receiver."
+)]
+pub struct ReplayTraceCmd {
+	/// Trace file to replay.
+	#[structopt(parse(from_os_str))]
+	pub input: PathBuf,
+
+	/// Receiver to replay the trace through.
+	#[structopt(
+		long = "tracing-receiver",
+		value_name = "RECEIVER",
+		possible_values = &TracingReceiver::variants(),
+		case_insensitive = true,
+		default_value = "Log"
+	)]
+	pub tracing_receiver: TracingReceiver,
+
+	/// Output of the receiver: the directory of the `Parquet` files, the `Binary` trace file,
+	/// the `Csv` statistics file, or the `Loki`, `Influx`, `Datadog` or `Gelf` endpoint, the
+	/// `Datadog` one defaulting to the local agent.
+	#[structopt(long, short, value_name = "PATH", parse(from_os_str))]
+	pub output: Option<PathBuf>,
+
+	/// Write the trace as JSON Lines, one object per span or event, to the output file or to
+	/// the standard output, rather than through the receiver.
+	#[structopt(long)]
+	pub json: bool,
+}
+
+impl ReplayTraceCmd {
+	/// Run the command
+	pub fn run(&self) -> Result<(), Error> {
+		if self.json {
+			return self.run_json();
+		}
+		let receiver = self.tracing_receiver.with_output(self.output.clone())?;
+
+		let handler = receiver.into_handler(Default::default());
+		let input = BufReader::new(File::open(&self.input)?);
+		let replayed = sc_tracing::replay(input, &*handler)
+			.map_err(|e| Error::Input(format!("Invalid trace {}: {}", self.input.display(), e)))?;
+
+		println!("Replayed {} spans and {} events", replayed.spans, replayed.events);
+
+		Ok(())
+	}
+
+	fn run_json(&self) -> Result<(), Error> {
+		let input = BufReader::new(File::open(&self.input)?);
+		let replayed = match &self.output {
+			Some(output) => sc_tracing::replay_as_json(input, BufWriter::new(File::create(output)?)),
+			None => sc_tracing::replay_as_json(input, BufWriter::new(io::stdout())),
+		}.map_err(|e| Error::Input(format!("Invalid trace {}: {}", self.input.display(), e)))?;
+
+		eprintln!("Wrote {} spans and {} events", replayed.spans, replayed.events);
+
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::commands::trace::tests::{span, write_trace};
+	use sc_tracing_format::{Event, Level, Record, TraceReader, Value};
+
+	fn records() -> Vec<Record> {
+		vec![
+			Record::Event(Event {
+				parent_id: Some(2),
+				target: "test".into(),
+				name: "event".into(),
+				level: Level::Warn,
+				time_us: 1_600_000_000_000_000,
+				fields: vec![("delta".into(), Value::I64(-1))],
+			}),
+			span(2, Some(1), "execute_block", 10, vec![]),
+			span(1, None, "import_block", 20, vec![("block_number", Value::U64(42))]),
+		]
+	}
+
+	#[test]
+	fn replay_trace() {
+		let dir = tempfile::tempdir().unwrap();
+		let input = dir.path().join("input.sctrace");
+		let output = dir.path().join("output.sctrace");
+		write_trace(&input, &records());
+
+		let cmd = ReplayTraceCmd::from_iter(&["replay", input.to_str().unwrap(), "--tracing-receiver", "binary"]);
+		assert!(cmd.run().is_err());
+
+		let cmd = ReplayTraceCmd::from_iter(&[
+			"replay",
+			input.to_str().unwrap(),
+			"--tracing-receiver",
+			"binary",
+			"--output",
+			output.to_str().unwrap(),
+		]);
+		cmd.run().unwrap();
+		let replayed = TraceReader::new(File::open(&output).unwrap()).unwrap()
+			.collect::<Result<Vec<_>, _>>()
+			.unwrap();
+		assert_eq!(replayed, records());
+	}
+
+	#[test]
+	fn replay_trace_as_json() {
+		let dir = tempfile::tempdir().unwrap();
+		let input = dir.path().join("input.sctrace");
+		let output = dir.path().join("output.json");
+		write_trace(&input, &records());
+
+		let cmd = ReplayTraceCmd::from_iter(&[
+			"replay",
+			input.to_str().unwrap(),
+			"--json",
+			"--output",
+			output.to_str().unwrap(),
+		]);
+		cmd.run().unwrap();
+		let json = std::fs::read_to_string(&output).unwrap();
+		assert_eq!(json.lines().collect::<Vec<_>>(), vec![
+			r#"{"kind":"event","parent_id":2,"target":"test","name":"event","level":"WARN","time_us":1600000000000000,"fields":{"delta":-1}}"#,
+			r#"{"kind":"span","id":2,"parent_id":1,"target":"test","name":"execute_block","level":"INFO","start_us":1600000000000002,"duration_ns":10,"fields":{}}"#,
+			r#"{"kind":"span","id":1,"parent_id":null,"target":"test","name":"import_block","level":"INFO","start_us":1600000000000001,"duration_ns":20,"fields":{"block_number":42}}"#,
+		]);
+	}
+}
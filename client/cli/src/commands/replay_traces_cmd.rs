@@ -0,0 +1,102 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Implementation of the `replay-traces` subcommand
+
+use crate::error;
+use crate::params::{DatabaseParams, PruningParams, SharedParams};
+use crate::{CliConfiguration, TracingReceiver};
+use parity_scale_codec::Decode;
+use sc_client_api::Backend;
+use sp_runtime::traits::Block as BlockT;
+use std::{path::PathBuf, sync::Arc};
+use structopt::StructOpt;
+
+/// The `replay-traces` command used to replay the trace summaries stored in the database
+/// through a tracing receiver.
+#[derive(Debug, StructOpt)]
+pub struct ReplayTracesCmd {
+	/// Number of last blocks to replay the trace summaries of.
+	#[structopt(long, value_name = "COUNT", default_value = "256")]
+	pub blocks: usize,
+
+	/// Receiver to replay the trace summaries through.
+	#[structopt(
+		long = "tracing-receiver",
+		value_name = "RECEIVER",
+		possible_values = &TracingReceiver::variants(),
+		case_insensitive = true,
+		default_value = "Log"
+	)]
+	pub tracing_receiver: TracingReceiver,
+
+	/// Output of the receiver, as for the `trace replay` command.
+	#[structopt(long, short, value_name = "PATH", parse(from_os_str))]
+	pub output: Option<PathBuf>,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub pruning_params: PruningParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub database_params: DatabaseParams,
+}
+
+impl ReplayTracesCmd {
+	/// Run the replay-traces command
+	pub async fn run<B, BA>(&self, backend: Arc<BA>) -> error::Result<()>
+	where
+		B: BlockT,
+		BA: Backend<B>,
+	{
+		let storage = backend.trace_summary_storage()
+			.ok_or_else(|| error::Error::Input("The database doesn't store trace summaries".into()))?;
+		let mut summaries = storage.recent_summaries(self.blocks)?.into_iter()
+			.map(|summary| sc_tracing::BlockSummary::decode(&mut &summary[..]))
+			.collect::<Result<Vec<_>, _>>()?;
+		// The summaries are replayed in the order the blocks were imported.
+		summaries.reverse();
+
+		let receiver = self.tracing_receiver.with_output(self.output.clone())?;
+		let handler = receiver.into_handler(Default::default());
+		let replayed = sc_tracing::replay_summaries(summaries, &*handler);
+
+		println!("Replayed {} spans and {} events", replayed.spans, replayed.events);
+
+		Ok(())
+	}
+}
+
+impl CliConfiguration for ReplayTracesCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn pruning_params(&self) -> Option<&PruningParams> {
+		Some(&self.pruning_params)
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		Some(&self.database_params)
+	}
+}
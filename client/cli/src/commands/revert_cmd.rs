@@ -27,7 +27,8 @@ use std::sync::Arc;
 use structopt::StructOpt;
 use sc_client_api::{Backend, UsageProvider};
 
-/// The `revert` command used revert the chain to a previous state.
+/// The `revert` command used revert the chain to a previous state, along with the auxiliary data
+/// of the reverted blocks.
 #[derive(Debug, StructOpt)]
 pub struct RevertCmd {
 	/// Number of blocks to revert.
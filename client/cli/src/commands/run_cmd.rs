@@ -25,13 +25,19 @@ use crate::params::OffchainWorkerParams;
 use crate::params::SharedParams;
 use crate::params::TransactionPoolParams;
 use crate::CliConfiguration;
+use crate::config::DEFAULT_POST_MORTEM_PATH;
 use regex::Regex;
 use sc_service::{
-	config::{BasePath, MultiaddrWithPeerId, PrometheusConfig, TransactionPoolOptions},
+	config::{
+		BasePath, MetricsNamespace, MultiaddrWithPeerId, PrometheusConfig, SlowCallThresholds,
+		StatsdConfig, TransactionPoolOptions,
+	},
 	ChainSpec, Role,
 };
-use sc_telemetry::TelemetryEndpoints;
-use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use sc_telemetry::{TelemetryBatching, TelemetryEndpoints};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, ToSocketAddrs};
+use std::path::PathBuf;
+use std::time::Duration;
 use structopt::StructOpt;
 
 /// The `run` command used to run a node.
@@ -102,6 +108,16 @@ pub struct RunCmd {
 	)]
 	pub rpc_methods: RpcMethods,
 
+	/// Duration past which an RPC call is logged and accounted as slow, in milliseconds.
+	#[structopt(long = "rpc-slow-call-ms", value_name = "MS", default_value = "1000")]
+	pub rpc_slow_call_ms: u64,
+
+	/// Size of the response past which an RPC call is logged and accounted as slow, in bytes.
+	///
+	/// The responses are only measured with this threshold.
+	#[structopt(long = "rpc-slow-call-bytes", value_name = "BYTES")]
+	pub rpc_slow_call_bytes: Option<usize>,
+
 	/// Listen to all Websocket interfaces.
 	///
 	/// Default is local. Note: not all RPC methods are safe to be exposed publicly. Use an RPC proxy
@@ -134,6 +150,27 @@ pub struct RunCmd {
 	#[structopt(long = "ws-port", value_name = "PORT")]
 	pub ws_port: Option<u16>,
 
+	/// Start a gRPC server on the given TCP port.
+	///
+	/// The server serves the subscriptions to the heads of the chain, the storage queries and the
+	/// submission of extrinsics. It is disabled by default.
+	#[structopt(long = "grpc-port", value_name = "PORT")]
+	pub grpc_port: Option<u16>,
+
+	/// Listen to all gRPC interfaces.
+	///
+	/// Default is local. As the server accepts the submission of extrinsics, this is refused if
+	/// the node is running as a validator. Use `--unsafe-grpc-external` if you understand the
+	/// risks.
+	#[structopt(long = "grpc-external", requires = "grpc-port")]
+	pub grpc_external: bool,
+
+	/// Listen to all gRPC interfaces.
+	///
+	/// Same as `--grpc-external`, also when the node is running as a validator.
+	#[structopt(long = "unsafe-grpc-external", requires = "grpc-port")]
+	pub unsafe_grpc_external: bool,
+
 	/// Maximum number of WS RPC server connections.
 	#[structopt(long = "ws-max-connections", value_name = "COUNT")]
 	pub ws_max_connections: Option<usize>,
@@ -157,6 +194,58 @@ pub struct RunCmd {
 	#[structopt(long = "no-prometheus")]
 	pub no_prometheus: bool,
 
+	/// Prefix the names of the Prometheus metrics with PREFIX instead of `substrate`.
+	#[structopt(long = "prometheus-prefix", value_name = "PREFIX", conflicts_with = "no-prometheus")]
+	pub prometheus_prefix: Option<String>,
+
+	/// Label all the Prometheus metrics with the chain, the name and the role of the node.
+	///
+	/// So that the metrics of several nodes on a host, or of the nodes of several chains, scraped
+	/// by the same Prometheus don't collide. The labels are `chain`, `node` and `role`, the role
+	/// being one of `validator`, `sentry`, `full` and `light`.
+	#[structopt(long = "prometheus-node-labels", conflicts_with = "no-prometheus")]
+	pub prometheus_node_labels: bool,
+
+	/// Push the Prometheus metrics to a StatsD server, e.g. `127.0.0.1:8125`.
+	#[structopt(long = "statsd-endpoint", value_name = "ADDR", conflicts_with = "no-prometheus")]
+	pub statsd_endpoint: Option<String>,
+
+	/// Push the labels of the metrics as DogStatsD tags.
+	#[structopt(long = "statsd-dogstatsd", requires = "statsd-endpoint")]
+	pub statsd_dogstatsd: bool,
+
+	/// Specify the number of seconds between two pushes to StatsD.
+	#[structopt(long = "statsd-interval", value_name = "SECONDS", default_value = "10")]
+	pub statsd_interval: u64,
+
+	/// Serve a live view of the node's async tasks on localhost.
+	///
+	/// Every connection to the console port receives a JSON snapshot of all running tasks, the
+	/// task that spawned them and how much time they spent being polled.
+	#[structopt(long = "tokio-console")]
+	pub tokio_console: bool,
+
+	/// Specify the task console TCP port.
+	#[structopt(long = "tokio-console-port", value_name = "PORT", requires = "tokio-console")]
+	pub tokio_console_port: Option<u16>,
+
+	/// Serve the `/health` and `/ready` HTTP endpoints.
+	///
+	/// Both report the status of the node's subsystems as JSON and can be used as liveness and
+	/// readiness probes.
+	#[structopt(long = "health-check")]
+	pub health_check: bool,
+
+	/// Specify the health check TCP port.
+	#[structopt(long = "health-check-port", value_name = "PORT", requires = "health-check")]
+	pub health_check_port: Option<u16>,
+
+	/// Listen to all network interfaces for health checks.
+	///
+	/// Default is local.
+	#[structopt(long = "health-check-external", requires = "health-check")]
+	pub health_check_external: bool,
+
 	/// The human-readable name for this node.
 	///
 	/// The node name will be reported to the telemetry server, if enabled.
@@ -178,6 +267,22 @@ pub struct RunCmd {
 	#[structopt(long = "telemetry-url", value_name = "URL VERBOSITY", parse(try_from_str = parse_telemetry_endpoints))]
 	pub telemetry_endpoints: Vec<(String, u8)>,
 
+	/// Send the messages to the telemetry servers in batches, waiting up to this many
+	/// milliseconds for more messages after the first one of a batch.
+	///
+	/// A batch is sent as a single WebSocket frame of newline-delimited JSON, which the
+	/// telemetry servers must support. By default, each message is sent in a frame of its own.
+	#[structopt(long = "telemetry-batch-interval", value_name = "MS")]
+	pub telemetry_batch_interval: Option<u64>,
+
+	/// Maximum number of telemetry messages in a batch, see `--telemetry-batch-interval`.
+	#[structopt(long = "telemetry-batch-size", value_name = "COUNT", default_value = "100")]
+	pub telemetry_batch_size: usize,
+
+	/// Compress the batches of telemetry messages with gzip.
+	#[structopt(long = "telemetry-compression", requires = "telemetry-batch-interval")]
+	pub telemetry_compression: bool,
+
 	#[allow(missing_docs)]
 	#[structopt(flatten)]
 	pub offchain_worker_params: OffchainWorkerParams,
@@ -234,6 +339,15 @@ pub struct RunCmd {
 	#[structopt(long = "force-authoring")]
 	pub force_authoring: bool,
 
+	/// Write a post-mortem bundle of every slot in which authoring a block fails or overruns the
+	/// slot to the `post-mortem` directory of the chain.
+	///
+	/// A bundle holds the proposer timings, the inherent data, the number of pending
+	/// transactions and the open spans. Its path is reported in an `ERROR` event of the `slots`
+	/// tracing target.
+	#[structopt(long = "authorship-post-mortem")]
+	pub authorship_post_mortem: bool,
+
 	#[allow(missing_docs)]
 	#[structopt(flatten)]
 	pub keystore_params: KeystoreParams,
@@ -357,6 +471,14 @@ impl CliConfiguration for RunCmd {
 		})
 	}
 
+	fn telemetry_batching(&self) -> Result<Option<TelemetryBatching>> {
+		Ok(self.telemetry_batch_interval.map(|interval| TelemetryBatching {
+			max_messages: self.telemetry_batch_size.max(1),
+			flush_interval: Duration::from_millis(interval),
+			compression: self.telemetry_compression,
+		}))
+	}
+
 	fn role(&self, is_dev: bool) -> Result<Role> {
 		let keyring = self.get_keyring();
 		let is_light = self.light;
@@ -382,6 +504,14 @@ impl CliConfiguration for RunCmd {
 		Ok(self.shared_params.dev || self.force_authoring)
 	}
 
+	fn authorship_post_mortem(&self, config_dir: &PathBuf) -> Result<Option<PathBuf>> {
+		Ok(if self.authorship_post_mortem {
+			Some(config_dir.join(DEFAULT_POST_MORTEM_PATH))
+		} else {
+			None
+		})
+	}
+
 	fn prometheus_config(&self, default_listen_port: u16) -> Result<Option<PrometheusConfig>> {
 		Ok(if self.no_prometheus {
 			None
@@ -401,6 +531,71 @@ impl CliConfiguration for RunCmd {
 		})
 	}
 
+	fn metrics_namespace(&self, chain_id: &str, node_name: &str, role: &Role) -> Result<MetricsNamespace> {
+		if let Some(prefix) = &self.prometheus_prefix {
+			is_metric_prefix_valid(prefix).map_err(|msg| Error::Input(format!(
+				"Invalid Prometheus prefix '{}'. Reason: {}.", prefix, msg,
+			)))?;
+		}
+		Ok(MetricsNamespace {
+			prefix: self.prometheus_prefix.clone(),
+			labels: if self.prometheus_node_labels {
+				MetricsNamespace::node_labels(chain_id, node_name, role)
+			} else {
+				Vec::new()
+			},
+		})
+	}
+
+	fn grpc(&self) -> Result<Option<SocketAddr>> {
+		let interface = grpc_interface(self.grpc_external, self.unsafe_grpc_external, self.validator)?;
+
+		Ok(self.grpc_port.map(|port| SocketAddr::new(interface, port)))
+	}
+
+	fn statsd_config(&self) -> Result<Option<StatsdConfig>> {
+		let endpoint = match &self.statsd_endpoint {
+			Some(endpoint) => endpoint,
+			None => return Ok(None),
+		};
+		let endpoint = endpoint.to_socket_addrs().ok()
+			.and_then(|mut addrs| addrs.next())
+			.ok_or_else(|| Error::Input(format!("Invalid StatsD endpoint `{}`", endpoint)))?;
+		Ok(Some(StatsdConfig {
+			endpoint,
+			dogstatsd: self.statsd_dogstatsd,
+			interval: Duration::from_secs(self.statsd_interval.max(1)),
+		}))
+	}
+
+	fn tokio_console(&self, default_listen_port: u16) -> Result<Option<SocketAddr>> {
+		Ok(if self.tokio_console {
+			Some(SocketAddr::new(
+				Ipv4Addr::LOCALHOST.into(),
+				self.tokio_console_port.unwrap_or(default_listen_port),
+			))
+		} else {
+			None
+		})
+	}
+
+	fn health_check(&self, default_listen_port: u16) -> Result<Option<SocketAddr>> {
+		Ok(if self.health_check {
+			let interface = if self.health_check_external {
+				Ipv4Addr::UNSPECIFIED
+			} else {
+				Ipv4Addr::LOCALHOST
+			};
+
+			Some(SocketAddr::new(
+				interface.into(),
+				self.health_check_port.unwrap_or(default_listen_port),
+			))
+		} else {
+			None
+		})
+	}
+
 	fn disable_grandpa(&self) -> Result<bool> {
 		Ok(self.no_grandpa)
 	}
@@ -460,6 +655,13 @@ impl CliConfiguration for RunCmd {
 		Ok(self.rpc_methods.into())
 	}
 
+	fn rpc_slow_calls(&self) -> Result<SlowCallThresholds> {
+		Ok(SlowCallThresholds {
+			latency: Duration::from_millis(self.rpc_slow_call_ms),
+			response_size: self.rpc_slow_call_bytes,
+		})
+	}
+
 	fn transaction_pool(&self) -> Result<TransactionPoolOptions> {
 		Ok(self.pool_config.transaction_pool())
 	}
@@ -499,6 +701,17 @@ pub fn is_node_name_valid(_name: &str) -> std::result::Result<(), &str> {
 	Ok(())
 }
 
+/// Check whether a prefix of the names of the Prometheus metrics is valid.
+pub fn is_metric_prefix_valid(prefix: &str) -> std::result::Result<(), &str> {
+	let re = Regex::new(r"^[a-zA-Z_:][a-zA-Z0-9_:]*$").unwrap();
+	if !re.is_match(prefix) {
+		return Err("Metric prefix should only contain ASCII letters, digits, '_' and ':', and not \
+			start with a digit");
+	}
+
+	Ok(())
+}
+
 fn rpc_interface(
 	is_external: bool,
 	is_unsafe_external: bool,
@@ -529,6 +742,23 @@ fn rpc_interface(
 	}
 }
 
+fn grpc_interface(is_external: bool, is_unsafe_external: bool, is_validator: bool) -> Result<IpAddr> {
+	if is_external && is_validator && !is_unsafe_external {
+		return Err(Error::Input(
+			"--grpc-external option shouldn't be used if the node is running as a validator, \
+			as the gRPC server accepts the submission of extrinsics. Use `--unsafe-grpc-external` \
+			if you understand the risks."
+				.to_owned(),
+		));
+	}
+
+	if is_external || is_unsafe_external {
+		Ok(Ipv4Addr::UNSPECIFIED.into())
+	} else {
+		Ok(Ipv4Addr::LOCALHOST.into())
+	}
+}
+
 #[derive(Debug)]
 enum TelemetryParsingError {
 	MissingVerbosity,
@@ -622,4 +852,22 @@ mod tests {
 		assert!(is_node_name_valid("www.visit.me").is_err());
 		assert!(is_node_name_valid("email@domain").is_err());
 	}
+
+	#[test]
+	fn tests_metric_prefix() {
+		assert!(is_metric_prefix_valid("polkadot_validator_1").is_ok());
+		assert!(is_metric_prefix_valid("").is_err());
+		assert!(is_metric_prefix_valid("1node").is_err());
+		assert!(is_metric_prefix_valid("my-node").is_err());
+	}
+
+	#[test]
+	fn tests_grpc_external_on_validators() {
+		let local: IpAddr = Ipv4Addr::LOCALHOST.into();
+		let external: IpAddr = Ipv4Addr::UNSPECIFIED.into();
+		assert_eq!(grpc_interface(false, false, true).unwrap(), local);
+		assert_eq!(grpc_interface(true, false, false).unwrap(), external);
+		assert!(grpc_interface(true, false, true).is_err());
+		assert_eq!(grpc_interface(false, true, true).unwrap(), external);
+	}
 }
@@ -0,0 +1,90 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Trace related CLI utilities
+
+use crate::Error;
+use structopt::StructOpt;
+
+use super::{
+	diff_trace_cmd::DiffTraceCmd, heatmap_trace_cmd::HeatmapTraceCmd, replay_trace_cmd::ReplayTraceCmd,
+	weights_trace_cmd::WeightsTraceCmd,
+};
+
+/// trace utilities for the cli.
+#[derive(Debug, StructOpt)]
+pub enum TraceSubcommand {
+	/// Replay a trace written by the `Binary` tracing receiver through another receiver
+	Replay(ReplayTraceCmd),
+
+	/// Compare the spans of two traces written by the `Binary` tracing receiver
+	Diff(DiffTraceCmd),
+
+	/// Rank the storage prefixes by their accesses in traces written by the `Binary` tracing receiver
+	Heatmap(HeatmapTraceCmd),
+
+	/// Suggest the weights of the calls from their dispatches in traces written by the `Binary` tracing receiver
+	Weights(WeightsTraceCmd),
+}
+
+impl TraceSubcommand {
+	/// run the trace subcommands
+	pub fn run(&self) -> Result<(), Error> {
+		match self {
+			TraceSubcommand::Replay(cmd) => cmd.run(),
+			TraceSubcommand::Diff(cmd) => cmd.run(),
+			TraceSubcommand::Heatmap(cmd) => cmd.run(),
+			TraceSubcommand::Weights(cmd) => cmd.run(),
+		}
+	}
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+	use sc_tracing_format::{Level, Record, Span, TraceWriter, Value};
+	use std::{fs::File, io::BufWriter, path::Path};
+
+	/// A span of the `test` target.
+	pub(crate) fn span(
+		id: u64,
+		parent_id: Option<u64>,
+		name: &str,
+		duration_ns: u64,
+		fields: Vec<(&str, Value)>,
+	) -> Record {
+		Record::Span(Span {
+			id,
+			parent_id,
+			target: "test".into(),
+			name: name.into(),
+			level: Level::Info,
+			start_us: 1_600_000_000_000_000 + id,
+			duration_ns,
+			fields: fields.into_iter().map(|(name, value)| (name.to_owned(), value)).collect(),
+		})
+	}
+
+	/// Write the `records` to a binary trace at `path`.
+	pub(crate) fn write_trace(path: &Path, records: &[Record]) {
+		let mut writer = TraceWriter::new(BufWriter::new(File::create(path).unwrap())).unwrap();
+		for record in records {
+			writer.write(record).unwrap();
+		}
+		writer.flush().unwrap();
+	}
+}
@@ -0,0 +1,171 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2018-2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Implementation of the `weights` trace subcommand
+
+use crate::Error;
+use sc_tracing::{CONFIDENCE_LEVELS, DispatchTimes, write_weight_info};
+use std::{fs::File, io::{self, BufReader, Write}, path::PathBuf};
+use structopt::StructOpt;
+
+/// The `weights` command
+#[derive(Debug, StructOpt)]
+#[structopt(
+	name = "weights",
+	about = "Suggest the weights of the calls from the times of their dispatches in traces \
+		written by the `Binary` tracing receiver, recorded with the `dispatch` tracing target."
+)]
+pub struct WeightsTraceCmd {
+	/// Traces to collect the dispatches of, e.g. the successive traces of a block range.
+	#[structopt(parse(from_os_str), required = true)]
+	pub traces: Vec<PathBuf>,
+
+	/// Confidence level of the intervals of the mean times, in percent.
+	///
+	/// The suggested weights are the upper bounds of the intervals.
+	#[structopt(
+		long,
+		value_name = "PERCENT",
+		default_value = "95",
+		possible_values = &["80", "90", "95", "98", "99", "99.9"],
+	)]
+	pub confidence: f64,
+
+	/// Minimum number of dispatches of a call to suggest its weight.
+	#[structopt(long, value_name = "COUNT", default_value = "10")]
+	pub min_samples: u64,
+
+	/// Write the suggested `WeightInfo` implementations to this file, rather than to stdout.
+	#[structopt(long, parse(from_os_str), value_name = "PATH")]
+	pub output: Option<PathBuf>,
+}
+
+impl WeightsTraceCmd {
+	/// Run the command
+	pub fn run(&self) -> Result<(), Error> {
+		let mut times = DispatchTimes::new();
+		for path in &self.traces {
+			let input = BufReader::new(File::open(path)?);
+			times.read_trace(input)
+				.map_err(|e| Error::Input(format!("Invalid trace {}: {}", path.display(), e)))?;
+		}
+
+		let suggestions = times.suggestions(self.confidence, self.min_samples).ok_or_else(|| {
+			let levels: Vec<_> = CONFIDENCE_LEVELS.iter().map(|(level, _)| level.to_string()).collect();
+			Error::Input(format!("Confidence must be one of {}", levels.join(", ")))
+		})?;
+		if suggestions.is_empty() {
+			eprintln!("No call with at least {} dispatches", self.min_samples);
+			return Ok(());
+		}
+
+		eprintln!(
+			"{:<40} {:>8} {:>16} {:>16} {:>16} {:>8}",
+			"call", "samples", "mean", "suggested", "declared", "change",
+		);
+		for suggestion in &suggestions {
+			eprintln!(
+				"{:<40} {:>8} {:>16} {:>16} {:>16} {:>+7.1}%",
+				format!("{}::{}", suggestion.pallet, suggestion.call),
+				suggestion.samples,
+				suggestion.mean,
+				suggestion.upper,
+				suggestion.declared,
+				suggestion.change_percent(),
+			);
+		}
+
+		match &self.output {
+			Some(path) => {
+				let mut output = File::create(path)?;
+				write_weight_info(&mut output, &suggestions, self.confidence)?;
+				output.flush()?;
+			},
+			None => write_weight_info(&mut io::stdout(), &suggestions, self.confidence)?,
+		}
+
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::commands::trace::tests::write_trace;
+	use sc_tracing_format::{Level, Record, Span, Value};
+	use sp_tracing::DISPATCH_TARGET;
+
+	fn dispatch(id: u64, call: &str, duration_ns: u64, weight: u64) -> Record {
+		Record::Span(Span {
+			id,
+			parent_id: None,
+			target: DISPATCH_TARGET.into(),
+			name: DISPATCH_TARGET.into(),
+			level: Level::Info,
+			start_us: 0,
+			duration_ns,
+			fields: vec![
+				("weight".into(), Value::U64(weight)),
+				(sc_tracing::PALLET_KEY.into(), Value::Str("pallet_balances".into())),
+				(sc_tracing::CALL_KEY.into(), Value::Str(call.into())),
+			],
+		})
+	}
+
+	#[test]
+	fn weights_trace() {
+		let dir = tempfile::tempdir().unwrap();
+		let first = dir.path().join("first.sctrace");
+		let second = dir.path().join("second.sctrace");
+		write_trace(&first, &[
+			dispatch(1, "transfer", 9_000, 8_000_000),
+			dispatch(2, "transfer", 10_000, 8_000_000),
+			dispatch(3, "set_balance", 5_000, 5_000_000),
+		]);
+		write_trace(&second, &[
+			dispatch(1, "transfer", 11_000, 8_000_000),
+			dispatch(2, "transfer", 10_000, 8_000_000),
+		]);
+		let output = dir.path().join("weights.rs");
+		let (first, second) = (first.to_str().unwrap(), second.to_str().unwrap());
+
+		let cmd = WeightsTraceCmd::from_iter(&[
+			"weights", first, second, "--min-samples", "2", "--output", output.to_str().unwrap(),
+		]);
+		cmd.run().unwrap();
+		let weights = std::fs::read_to_string(&output).unwrap();
+		assert!(weights.contains("impl pallet_balances::WeightInfo for WeightInfo {"));
+		// The times are in picoseconds, the margin of the 95% interval is 1.96 * 816_497 / 2.
+		assert!(weights.contains(
+			"\t// 4 dispatches: mean 10000000, interval [9199833, 10800167], max 11000000, \
+				declared 8000000 (+35.0%)\n\tfn transfer() -> Weight {\n\t\t(10800167 as Weight)\n\t}"
+		));
+		assert!(!weights.contains("set_balance"));
+
+		// Nothing is written without enough dispatches.
+		std::fs::remove_file(&output).unwrap();
+		let cmd = WeightsTraceCmd::from_iter(&[
+			"weights", first, "--min-samples", "3", "--output", output.to_str().unwrap(),
+		]);
+		cmd.run().unwrap();
+		assert!(!output.exists());
+
+		let cmd = WeightsTraceCmd::from_iter(&["weights", first, "missing.sctrace"]);
+		assert!(cmd.run().is_err());
+	}
+}
@@ -18,8 +18,8 @@
 
 //! Configuration trait for a CLI based on substrate
 
-use crate::arg_enums::Database;
-use crate::error::Result;
+use crate::arg_enums::{Database, LogOutput};
+use crate::error::{Error, Result};
 use crate::{
 	init_logger, DatabaseParams, ImportParams, KeystoreParams, NetworkParams, NodeKeyParams,
 	OffchainWorkerParams, PruningParams, SharedParams, SubstrateCli,
@@ -27,13 +27,15 @@ use crate::{
 use names::{Generator, Name};
 use sc_client_api::execution_extensions::ExecutionStrategies;
 use sc_service::config::{
-	BasePath, Configuration, DatabaseConfig, ExtTransport, KeystoreConfig, NetworkConfiguration,
-	NodeKeyConfig, OffchainWorkerConfig, PrometheusConfig, PruningMode, Role, RpcMethods,
-	TaskExecutor, TelemetryEndpoints, TransactionPoolOptions, WasmExecutionMethod,
+	BasePath, Configuration, DatabaseConfig, ExtTransport, KeystoreConfig, MetricsNamespace,
+	NetworkConfiguration, NodeKeyConfig, OffchainWorkerConfig, PrometheusConfig, PruningMode, Role, RpcMethods,
+	SlowCallThresholds, StatsdConfig, TaskExecutor, TelemetryBatching, TelemetryEndpoints, TransactionPoolOptions,
+	WasmExecutionMethod,
 };
 use sc_service::{ChainSpec, TracingReceiver};
 use std::net::SocketAddr;
 use std::path::PathBuf;
+use std::time::Duration;
 
 /// The maximum number of characters for a node name.
 pub(crate) const NODE_NAME_MAX_LENGTH: usize = 64;
@@ -41,6 +43,12 @@ pub(crate) const NODE_NAME_MAX_LENGTH: usize = 64;
 /// default sub directory to store network config
 pub(crate) const DEFAULT_NETWORK_CONFIG_PATH: &'static str = "network";
 
+/// default sub directory to store the compiled runtimes
+pub(crate) const DEFAULT_WASMTIME_CACHE_PATH: &'static str = "wasmtime-cache";
+
+/// default sub directory to store the authorship post-mortem bundles
+pub(crate) const DEFAULT_POST_MORTEM_PATH: &'static str = "post-mortem";
+
 /// Default configuration values used by Substrate
 ///
 /// These values will be used by [`CliConfiguritation`] to set
@@ -73,6 +81,20 @@ pub trait DefaultConfigurationValues {
 	fn prometheus_listen_port() -> u16 {
 		9615
 	}
+
+	/// The port Substrate should serve the task console on.
+	///
+	/// By default this is `6669`.
+	fn tokio_console_listen_port() -> u16 {
+		6669
+	}
+
+	/// The port Substrate should serve the health check endpoints on.
+	///
+	/// By default this is `9616`.
+	fn health_check_listen_port() -> u16 {
+		9616
+	}
 }
 
 impl DefaultConfigurationValues for () {}
@@ -277,6 +299,23 @@ pub trait CliConfiguration<DCV: DefaultConfigurationValues = ()>: Sized {
 			.unwrap_or_default())
 	}
 
+	/// Whether the compiled runtimes are described in a perf jitdump file.
+	///
+	/// By default this is retrieved from `ImportParams` if it is available. Otherwise its `false`.
+	fn wasmtime_jitdump(&self) -> Result<bool> {
+		Ok(self.import_params().map(|x| x.wasmtime_jitdump()).unwrap_or_default())
+	}
+
+	/// Get the directory of the cache of the compiled runtimes, if any.
+	///
+	/// By default this is the `wasmtime-cache` directory of `config_dir` if enabled in
+	/// `ImportParams`. Otherwise the runtimes are not cached.
+	fn wasmtime_cache(&self, config_dir: &PathBuf) -> Result<Option<PathBuf>> {
+		Ok(self.import_params()
+			.filter(|x| x.wasmtime_cache())
+			.map(|_| config_dir.join(DEFAULT_WASMTIME_CACHE_PATH)))
+	}
+
 	/// Get the execution strategies.
 	///
 	/// By default this is retrieved from `ImportParams` if it is available. Otherwise its
@@ -321,6 +360,13 @@ pub trait CliConfiguration<DCV: DefaultConfigurationValues = ()>: Sized {
 		Ok(Default::default())
 	}
 
+	/// Get the thresholds past which the RPC calls are slow.
+	///
+	/// By default this is `SlowCallThresholds::default()`.
+	fn rpc_slow_calls(&self) -> Result<SlowCallThresholds> {
+		Ok(Default::default())
+	}
+
 	/// Get the RPC websockets maximum connections (`None` if unlimited).
 	///
 	/// By default this is `None`.
@@ -335,6 +381,13 @@ pub trait CliConfiguration<DCV: DefaultConfigurationValues = ()>: Sized {
 		Ok(Some(Vec::new()))
 	}
 
+	/// Get the gRPC address (`None` if disabled).
+	///
+	/// By default this is `None`.
+	fn grpc(&self) -> Result<Option<SocketAddr>> {
+		Ok(None)
+	}
+
 	/// Get the prometheus configuration (`None` if disabled)
 	///
 	/// By default this is `None`.
@@ -342,6 +395,26 @@ pub trait CliConfiguration<DCV: DefaultConfigurationValues = ()>: Sized {
 		Ok(None)
 	}
 
+	/// Get the namespace of the metrics of the node `node_name`, with the role `role`, of the
+	/// chain `chain_id`.
+	///
+	/// By default this is the default namespace, prefixing the metrics with `substrate`.
+	fn metrics_namespace(
+		&self,
+		_chain_id: &str,
+		_node_name: &str,
+		_role: &Role,
+	) -> Result<MetricsNamespace> {
+		Ok(Default::default())
+	}
+
+	/// Get the StatsD configuration (`None` if disabled)
+	///
+	/// By default this is `None`.
+	fn statsd_config(&self) -> Result<Option<StatsdConfig>> {
+		Ok(None)
+	}
+
 	/// Get the telemetry endpoints (if any)
 	///
 	/// By default this is retrieved from the chain spec loaded by `load_spec`.
@@ -359,6 +432,13 @@ pub trait CliConfiguration<DCV: DefaultConfigurationValues = ()>: Sized {
 		Ok(None)
 	}
 
+	/// Get the batching of the telemetry messages
+	///
+	/// By default this is `None`.
+	fn telemetry_batching(&self) -> Result<Option<TelemetryBatching>> {
+		Ok(None)
+	}
+
 	/// Get the default value for heap pages
 	///
 	/// By default this is `None`.
@@ -382,6 +462,13 @@ pub trait CliConfiguration<DCV: DefaultConfigurationValues = ()>: Sized {
 		Ok(Default::default())
 	}
 
+	/// Get the directory of the authorship post-mortem bundles, if written.
+	///
+	/// By default this is `None`.
+	fn authorship_post_mortem(&self, _config_dir: &PathBuf) -> Result<Option<PathBuf>> {
+		Ok(None)
+	}
+
 	/// Returns `Ok(true)` if grandpa should be disabled
 	///
 	/// By default this is `false`.
@@ -406,14 +493,90 @@ pub trait CliConfiguration<DCV: DefaultConfigurationValues = ()>: Sized {
 			.unwrap_or_else(|| Default::default()))
 	}
 
+	/// Get the tracing targets while the node is major syncing, if they differ
+	///
+	/// By default this is retrieved from `ImportParams` if it is available. Otherwise the
+	/// tracing targets don't change during the sync.
+	fn tracing_sync_targets(&self) -> Result<Option<String>> {
+		Ok(self.import_params().and_then(|x| x.tracing_sync_targets()))
+	}
+
+	/// Get the fields to redact from the traces sent to telemetry
+	///
+	/// By default this is retrieved from `ImportParams` if it is available. Otherwise no field
+	/// is redacted.
+	fn tracing_redaction(&self) -> Result<sc_tracing::Redaction> {
+		self.import_params()
+			.map(|x| x.tracing_redaction())
+			.unwrap_or_else(|| Ok(Default::default()))
+	}
+
+	/// Get the Sentry configuration to report the `ERROR` events and the panics to.
+	///
+	/// By default this is retrieved from `ImportParams` if it is available. Otherwise nothing
+	/// is reported.
+	fn tracing_sentry(&self) -> Result<Option<sc_tracing::SentryConfig>> {
+		self.import_params()
+			.map(|x| x.tracing_sentry())
+			.unwrap_or_else(|| Ok(None))
+	}
+
+	/// Get the number of blocks to keep the trace summaries of.
+	///
+	/// By default this is retrieved from `ImportParams` if it is available. Otherwise no summary
+	/// is stored.
+	fn tracing_summaries(&self) -> Result<Option<u32>> {
+		Ok(self.import_params().and_then(|x| x.tracing_summaries()))
+	}
+
+	/// Whether to count the storage accesses under the spans.
+	///
+	/// By default this is retrieved from `ImportParams` if it is available. Otherwise they are
+	/// not counted.
+	fn tracing_storage_counters(&self) -> Result<bool> {
+		Ok(self.import_params().map(|x| x.tracing_storage_counters()).unwrap_or_default())
+	}
+
+	/// Whether to measure the drift of the calls from their weights.
+	///
+	/// By default this is retrieved from `ImportParams` if it is available. Otherwise it is not
+	/// measured.
+	fn tracing_weight_drift(&self) -> Result<bool> {
+		Ok(self.import_params().map(|x| x.tracing_weight_drift()).unwrap_or_default())
+	}
+
+	/// Whether to break down the proofs of the built blocks.
+	///
+	/// By default this is retrieved from `ImportParams` if it is available. Otherwise they are not
+	/// broken down.
+	fn tracing_pov_breakdowns(&self) -> Result<bool> {
+		Ok(self.import_params().map(|x| x.tracing_pov_breakdowns()).unwrap_or_default())
+	}
+
+	/// Whether to report the failed dispatches.
+	///
+	/// By default this is retrieved from `ImportParams` if it is available. Otherwise they are not
+	/// reported.
+	fn tracing_dispatch_errors(&self) -> Result<bool> {
+		Ok(self.import_params().map(|x| x.tracing_dispatch_errors()).unwrap_or_default())
+	}
+
+	/// Get the deadlines past which the imports of the blocks are reported.
+	///
+	/// By default this is retrieved from `ImportParams` if it is available. Otherwise the imports
+	/// are not reported.
+	fn import_deadlines(&self) -> Result<Vec<Duration>> {
+		Ok(self.import_params().map(|x| x.import_deadlines()).unwrap_or_default())
+	}
+
 	/// Get the TracingReceiver value from the current object
 	///
 	/// By default this is retrieved from `ImportParams` if it is available. Otherwise its
 	/// `TracingReceiver::default()`.
-	fn tracing_receiver(&self) -> Result<TracingReceiver> {
-		Ok(self.import_params()
-			.map(|x| x.tracing_receiver())
-			.unwrap_or_default())
+	fn tracing_receiver(&self, base_path: &PathBuf) -> Result<TracingReceiver> {
+		self.import_params()
+			.map(|x| x.tracing_receiver(base_path))
+			.unwrap_or_else(|| Ok(Default::default()))
 	}
 
 	/// Get the node key from the current object
@@ -433,6 +596,20 @@ pub trait CliConfiguration<DCV: DefaultConfigurationValues = ()>: Sized {
 		Ok(Default::default())
 	}
 
+	/// Get the address to serve the live task console on
+	///
+	/// By default this is `None`.
+	fn tokio_console(&self, _default_listen_port: u16) -> Result<Option<SocketAddr>> {
+		Ok(None)
+	}
+
+	/// Get the address to serve the health check endpoints on
+	///
+	/// By default this is `None`.
+	fn health_check(&self, _default_listen_port: u16) -> Result<Option<SocketAddr>> {
+		Ok(None)
+	}
+
 	/// Activate or not the automatic announcing of blocks after import
 	///
 	/// By default this is `false`.
@@ -465,6 +642,15 @@ pub trait CliConfiguration<DCV: DefaultConfigurationValues = ()>: Sized {
 		let role = self.role(is_dev)?;
 		let max_runtime_instances = self.max_runtime_instances()?.unwrap_or(8);
 		let is_validator = role.is_network_authority();
+		let node_name = self.node_name()?;
+		let metrics_namespace = self.metrics_namespace(chain_spec.id(), &node_name, &role)?;
+		let prometheus_config = match self.prometheus_config(DCV::prometheus_listen_port())? {
+			Some(config) if metrics_namespace != MetricsNamespace::default() => Some(
+				PrometheusConfig::new_with_namespace(config.port, metrics_namespace)
+					.map_err(|e| Error::Input(format!("Invalid metrics namespace: {}", e)))?
+			),
+			config => config,
+		};
 
 		let unsafe_pruning = self
 			.import_params()
@@ -481,7 +667,7 @@ pub trait CliConfiguration<DCV: DefaultConfigurationValues = ()>: Sized {
 				is_dev,
 				net_config_dir,
 				client_id.as_str(),
-				self.node_name()?.as_str(),
+				node_name.as_str(),
 				node_key,
 				DCV::p2p_listen_port(),
 			)?,
@@ -491,29 +677,48 @@ pub trait CliConfiguration<DCV: DefaultConfigurationValues = ()>: Sized {
 			state_cache_child_ratio: self.state_cache_child_ratio()?,
 			pruning: self.pruning(unsafe_pruning, &role)?,
 			wasm_method: self.wasm_method()?,
+			wasmtime_jitdump: self.wasmtime_jitdump()?,
+			wasmtime_cache: self.wasmtime_cache(&config_dir)?,
 			execution_strategies: self.execution_strategies(is_dev, is_validator)?,
 			rpc_http: self.rpc_http(DCV::rpc_http_listen_port())?,
 			rpc_ws: self.rpc_ws(DCV::rpc_ws_listen_port())?,
 			rpc_ipc: self.rpc_ipc()?,
 			rpc_methods: self.rpc_methods()?,
+			rpc_slow_calls: self.rpc_slow_calls()?,
 			rpc_ws_max_connections: self.rpc_ws_max_connections()?,
 			rpc_cors: self.rpc_cors(is_dev)?,
-			prometheus_config: self.prometheus_config(DCV::prometheus_listen_port())?,
+			grpc: self.grpc()?,
+			prometheus_config,
+			statsd_config: self.statsd_config()?,
 			telemetry_endpoints: self.telemetry_endpoints(&chain_spec)?,
 			telemetry_external_transport: self.telemetry_external_transport()?,
+			telemetry_batching: self.telemetry_batching()?,
 			default_heap_pages: self.default_heap_pages()?,
 			offchain_worker: self.offchain_worker(&role)?,
 			force_authoring: self.force_authoring()?,
+			authorship_post_mortem: self.authorship_post_mortem(&config_dir)?,
 			disable_grandpa: self.disable_grandpa()?,
 			dev_key_seed: self.dev_key_seed(is_dev)?,
 			tracing_targets: self.tracing_targets()?,
-			tracing_receiver: self.tracing_receiver()?,
+			tracing_sync_targets: self.tracing_sync_targets()?,
+			tracing_receiver: self.tracing_receiver(&config_dir)?,
+			tracing_redaction: self.tracing_redaction()?,
+			tracing_summaries: self.tracing_summaries()?,
+			tracing_storage_counters: self.tracing_storage_counters()?,
+			tracing_weight_drift: self.tracing_weight_drift()?,
+			tracing_pov_breakdowns: self.tracing_pov_breakdowns()?,
+			tracing_dispatch_errors: self.tracing_dispatch_errors()?,
+			import_deadlines: self.import_deadlines()?,
+			tracing_sentry: self.tracing_sentry()?,
+			tracing_extensions: Vec::new(),
 			chain_spec,
 			max_runtime_instances,
 			announce_block: self.announce_block()?,
 			role,
 			base_path: Some(base_path),
 			informant_output_format: Default::default(),
+			tokio_console: self.tokio_console(DCV::tokio_console_listen_port())?,
+			health_check: self.health_check(DCV::health_check_listen_port())?,
 		})
 	}
 
@@ -527,6 +732,13 @@ pub trait CliConfiguration<DCV: DefaultConfigurationValues = ()>: Sized {
 		Ok(self.shared_params().log_filters().join(","))
 	}
 
+	/// Get where to write the logs.
+	///
+	/// By default this is retrieved from `SharedParams`.
+	fn log_output(&self) -> Result<LogOutput> {
+		Ok(self.shared_params().log_output())
+	}
+
 	/// Initialize substrate. This must be done only once.
 	///
 	/// This method:
@@ -537,10 +749,14 @@ pub trait CliConfiguration<DCV: DefaultConfigurationValues = ()>: Sized {
 	fn init<C: SubstrateCli>(&self) -> Result<()> {
 		let logger_pattern = self.log_filters()?;
 
-		sp_panic_handler::set(&C::support_url(), &C::impl_version());
+		sp_panic_handler::set_with_exit_hook(
+			&C::support_url(),
+			&C::impl_version(),
+			sc_tracing::shutdown_after_panic,
+		);
 
 		fdlimit::raise_fd_limit();
-		init_logger(&logger_pattern);
+		init_logger(&logger_pattern, self.log_output()?);
 
 		Ok(())
 	}
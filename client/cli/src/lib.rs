@@ -27,6 +27,8 @@ mod config;
 mod error;
 mod params;
 mod runner;
+#[cfg(unix)]
+mod system_log;
 
 pub use arg_enums::*;
 pub use commands::*;
@@ -229,7 +231,7 @@ pub trait SubstrateCli: Sized {
 }
 
 /// Initialize the logger
-pub fn init_logger(pattern: &str) {
+pub fn init_logger(pattern: &str, output: LogOutput) {
 	use ansi_term::Colour;
 
 	let mut builder = env_logger::Builder::new();
@@ -249,6 +251,23 @@ pub fn init_logger(pattern: &str) {
 	}
 
 	builder.parse_filters(pattern);
+
+	if output != LogOutput::Stderr {
+		#[cfg(unix)]
+		match system_log::SystemLogger::new(output, &mut builder) {
+			Ok(logger) => {
+				let max_level = logger.max_level();
+				match log::set_boxed_logger(Box::new(logger)) {
+					Ok(()) => log::set_max_level(max_level),
+					Err(_) => info!("💬 Not registering Substrate logger, as there is already a global logger registered!"),
+				}
+				return;
+			},
+			Err(e) => eprintln!("Unable to log to {}, logging to stderr: {}", output, e),
+		}
+		#[cfg(not(unix))]
+		eprintln!("Unable to log to {}, logging to stderr: only supported on Unix", output);
+	}
 	let isatty = atty::is(atty::Stream::Stderr);
 	let enable_color = isatty;
 
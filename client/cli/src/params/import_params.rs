@@ -20,10 +20,14 @@ use crate::arg_enums::{
 	ExecutionStrategy, TracingReceiver, WasmExecutionMethod, DEFAULT_EXECUTION_BLOCK_CONSTRUCTION,
 	DEFAULT_EXECUTION_IMPORT_BLOCK, DEFAULT_EXECUTION_IMPORT_BLOCK_VALIDATOR,
 	DEFAULT_EXECUTION_OFFCHAIN_WORKER, DEFAULT_EXECUTION_OTHER, DEFAULT_EXECUTION_SYNCING,
+	DEFAULT_SPAN_STATS_FILE, DEFAULT_TRACES_PATH, binary_trace_file,
 };
+use crate::error;
 use crate::params::DatabaseParams;
 use crate::params::PruningParams;
 use sc_client_api::execution_extensions::ExecutionStrategies;
+use std::path::PathBuf;
+use std::time::Duration;
 use structopt::StructOpt;
 
 /// Parameters for block import.
@@ -55,6 +59,21 @@ pub struct ImportParams {
 	)]
 	pub wasm_method: WasmExecutionMethod,
 
+	/// Describe the code of the compiled runtimes in the `jit-<pid>.dump` file of the working
+	/// directory, for `perf inject --jit` to name the functions of the runtimes in the profiles
+	/// of `perf record -k mono`.
+	///
+	/// Only applies to the `Compiled` Wasm execution method.
+	#[structopt(long = "wasmtime-jitdump")]
+	pub wasmtime_jitdump: bool,
+
+	/// Store the code compiled from the runtimes in the `wasmtime-cache` directory of the chain,
+	/// and load the runtimes compiled before from it, e.g. after a restart.
+	///
+	/// Only applies to the `Compiled` Wasm execution method.
+	#[structopt(long = "wasmtime-cache")]
+	pub wasmtime_cache: bool,
+
 	#[allow(missing_docs)]
 	#[structopt(flatten)]
 	pub execution_strategies: ExecutionStrategiesParams,
@@ -71,6 +90,14 @@ pub struct ImportParams {
 	#[structopt(long = "tracing-targets", value_name = "TARGETS")]
 	pub tracing_targets: Option<String>,
 
+	/// Comma separated list of targets for tracing while the node is major syncing, instead of
+	/// `--tracing-targets`.
+	///
+	/// The `--tracing-targets` are traced again once the node is close to the tip of the chain.
+	/// Empty to only trace the privileged actions, e.g. `sudo`, during the sync.
+	#[structopt(long = "tracing-sync-targets", value_name = "TARGETS")]
+	pub tracing_sync_targets: Option<String>,
+
 	/// Receiver to process tracing messages.
 	#[structopt(
 		long = "tracing-receiver",
@@ -80,12 +107,170 @@ pub struct ImportParams {
 		default_value = "Log"
 	)]
 	pub tracing_receiver: TracingReceiver,
+
+	/// Grafana Loki endpoint the `Loki` receiver pushes to, e.g. `http://localhost:3100`.
+	#[structopt(long = "tracing-loki-endpoint", value_name = "URL")]
+	pub tracing_loki_endpoint: Option<String>,
+
+	/// InfluxDB endpoint the `Influx` receiver pushes to, e.g. `http://localhost:8086/write?db=substrate`
+	/// or `udp://localhost:8089`.
+	#[structopt(long = "tracing-influx-endpoint", value_name = "URL")]
+	pub tracing_influx_endpoint: Option<String>,
+
+	/// Token authorizing the pushes of the `Influx` receiver to the write API of InfluxDB 2.
+	///
+	/// The points are then written to `--tracing-influx-bucket` of `--tracing-influx-org`.
+	#[structopt(long = "tracing-influx-token", value_name = "TOKEN")]
+	pub tracing_influx_token: Option<String>,
+
+	/// InfluxDB 2 organization the `Influx` receiver pushes to, with `--tracing-influx-token`.
+	#[structopt(long = "tracing-influx-org", value_name = "ORG")]
+	pub tracing_influx_org: Option<String>,
+
+	/// InfluxDB 2 bucket the `Influx` receiver pushes to, with `--tracing-influx-token`.
+	#[structopt(long = "tracing-influx-bucket", value_name = "BUCKET")]
+	pub tracing_influx_bucket: Option<String>,
+
+	/// Comma separated list of the measurements pushed by the `Influx` receiver, optionally
+	/// renamed, e.g. `spans=span_stats,metrics`.
+	///
+	/// Both the `spans` and `metrics` measurements are pushed by default.
+	#[structopt(long = "tracing-influx-measurements", value_name = "MEASUREMENTS")]
+	pub tracing_influx_measurements: Option<String>,
+
+	/// Datadog agent endpoint the `Datadog` receiver sends the traces to, `http://localhost:8126`
+	/// by default.
+	#[structopt(long = "tracing-datadog-endpoint", value_name = "URL")]
+	pub tracing_datadog_endpoint: Option<String>,
+
+	/// Graylog GELF input the `Gelf` receiver sends the spans and events to, e.g.
+	/// `udp://localhost:12201` or `tcp://localhost:12201`.
+	#[structopt(long = "tracing-gelf-endpoint", value_name = "URL")]
+	pub tracing_gelf_endpoint: Option<String>,
+
+	/// Sentry DSN to report the `ERROR` events and the panics to, e.g. `https://<key>@<host>/<project>`.
+	///
+	/// The reports include the spans they happened in, redacted as by `--tracing-redact`, and are
+	/// tagged with the node name and the node and runtime versions.
+	#[structopt(long = "tracing-sentry-dsn", value_name = "DSN")]
+	pub tracing_sentry_dsn: Option<String>,
+
+	/// Comma separated list of fields to redact from the traces sent to telemetry, Loki, Datadog, Graylog and Sentry.
+	///
+	/// The values of the fields are hashed with a key drawn at startup, or dropped if the name is
+	/// suffixed with `=drop`, e.g. `sender,remark=drop`.
+	#[structopt(long = "tracing-redact", value_name = "FIELDS")]
+	pub tracing_redact: Option<String>,
+
+	/// Store the summaries of the traces of the last imported blocks, up to the given depth.
+	///
+	/// The summaries are served by the `trace_blockSummary` and `state_recentTraces` RPCs. Only
+	/// the spans of the enabled targets are summed up, along with the `storage_accesses` and
+	/// `block_weight` counters.
+	#[structopt(long = "tracing-summaries", value_name = "BLOCKS")]
+	pub tracing_summaries: Option<u32>,
+
+	/// Count the storage reads and writes, and the bytes read and written, under every span.
+	///
+	/// The counters are values of the spans, e.g. of the calls of the pallets whose targets are
+	/// enabled, to check them against the weights of the calls.
+	#[structopt(long = "tracing-storage-counters")]
+	pub tracing_storage_counters: bool,
+
+	/// Measure the drift of the times of the calls from their declared weights.
+	///
+	/// The drift is served by the `trace_weightDrift` RPC and exposed as the
+	/// `substrate_dispatch_weight_drift` metric. Only the calls of the pallets whose targets are
+	/// enabled are measured, in wasm too, which enables the tracing of the runtime, if the runtime
+	/// is built with the `with-tracing` feature of `frame-executive`.
+	#[structopt(long = "tracing-weight-drift")]
+	pub tracing_weight_drift: bool,
+
+	/// Break down the proofs of validity of the blocks built by the node by the spans their
+	/// trie nodes are read in.
+	///
+	/// The breakdowns are served by the `trace_povBreakdown` RPC. Only the blocks built recording
+	/// a proof, e.g. by collators, are broken down.
+	#[structopt(long = "tracing-pov-breakdowns")]
+	pub tracing_pov_breakdowns: bool,
+
+	/// Report the failed dispatches, with the pallet and call they failed in and their error,
+	/// whatever the tracing targets.
+	///
+	/// The dispatches of the runtime executed in wasm are reported too, if the runtime is built
+	/// with the `with-tracing` feature of `frame-executive`.
+	#[structopt(long = "tracing-dispatch-errors")]
+	pub tracing_dispatch_errors: bool,
+
+	/// Comma separated list of deadlines, in milliseconds, past which the imports of the blocks
+	/// are reported.
+	///
+	/// The imports are reported with a warning past the first deadline and an error past the
+	/// later ones, as events of the `import_deadline` target, along with the time spent in the
+	/// spans of the enabled targets under the import and the spans still open, e.g. `2000,6000`.
+	/// The imports aren't timed without deadlines.
+	#[structopt(long = "import-deadlines", value_name = "MS", use_delimiter = true)]
+	pub import_deadlines: Vec<u64>,
 }
 
 impl ImportParams {
 	/// Receiver to process tracing messages.
-	pub fn tracing_receiver(&self) -> sc_service::TracingReceiver {
-		self.tracing_receiver.clone().into()
+	///
+	/// The `Parquet`, `Binary` and `Csv` receivers write the traces in the `traces` directory of
+	/// `base_path`, the `Binary` one to a new file every time the node starts. The `Loki`,
+	/// `Influx` and `Gelf` ones require `--tracing-loki-endpoint`, `--tracing-influx-endpoint`
+	/// and `--tracing-gelf-endpoint`, the `Datadog` one sends to the local agent without
+	/// `--tracing-datadog-endpoint`.
+	pub fn tracing_receiver(&self, base_path: &PathBuf) -> error::Result<sc_service::TracingReceiver> {
+		Ok(match self.tracing_receiver {
+			TracingReceiver::Log => sc_service::TracingReceiver::Log,
+			TracingReceiver::Telemetry => sc_service::TracingReceiver::Telemetry,
+			TracingReceiver::Parquet =>
+				sc_service::TracingReceiver::Parquet(base_path.join(DEFAULT_TRACES_PATH)),
+			TracingReceiver::Binary => sc_service::TracingReceiver::Binary(
+				base_path.join(DEFAULT_TRACES_PATH).join(binary_trace_file()),
+			),
+			TracingReceiver::Csv => sc_service::TracingReceiver::Csv(
+				base_path.join(DEFAULT_TRACES_PATH).join(DEFAULT_SPAN_STATS_FILE),
+			),
+			TracingReceiver::Loki => {
+				let endpoint = self.tracing_loki_endpoint.as_deref()
+					.ok_or("--tracing-loki-endpoint is required by the Loki receiver")?;
+				sc_service::TracingReceiver::Loki(
+					sc_tracing::LokiConfig::new(endpoint).map_err(error::Error::Input)?,
+				)
+			},
+			TracingReceiver::Influx => {
+				let endpoint = self.tracing_influx_endpoint.as_deref()
+					.ok_or("--tracing-influx-endpoint is required by the Influx receiver")?;
+				let mut config = sc_tracing::InfluxConfig::new(endpoint).map_err(error::Error::Input)?;
+				match (&self.tracing_influx_token, &self.tracing_influx_org, &self.tracing_influx_bucket) {
+					(None, None, None) => {},
+					(Some(token), Some(org), Some(bucket)) => config.set_v2(org, bucket, token.clone())
+						.map_err(error::Error::Input)?,
+					_ => return Err(error::Error::Input(
+						"--tracing-influx-token, --tracing-influx-org and --tracing-influx-bucket go together".into(),
+					)),
+				}
+				if let Some(measurements) = &self.tracing_influx_measurements {
+					config.set_measurements(measurements).map_err(error::Error::Input)?;
+				}
+				sc_service::TracingReceiver::Influx(config)
+			},
+			TracingReceiver::Datadog => sc_service::TracingReceiver::Datadog(
+				match &self.tracing_datadog_endpoint {
+					Some(endpoint) => sc_tracing::DatadogConfig::new(endpoint).map_err(error::Error::Input)?,
+					None => Default::default(),
+				},
+			),
+			TracingReceiver::Gelf => {
+				let endpoint = self.tracing_gelf_endpoint.as_deref()
+					.ok_or("--tracing-gelf-endpoint is required by the Gelf receiver")?;
+				sc_service::TracingReceiver::Gelf(
+					sc_tracing::GelfConfig::new(endpoint).map_err(error::Error::Input)?,
+				)
+			},
+		})
 	}
 
 	/// Comma separated list of targets for tracing.
@@ -93,6 +278,57 @@ impl ImportParams {
 		self.tracing_targets.clone()
 	}
 
+	/// Comma separated list of targets for tracing while major syncing, if they differ.
+	pub fn tracing_sync_targets(&self) -> Option<String> {
+		self.tracing_sync_targets.clone()
+	}
+
+	/// Fields to redact from the traces sent to telemetry.
+	pub fn tracing_redaction(&self) -> error::Result<sc_tracing::Redaction> {
+		sc_tracing::Redaction::new(self.tracing_redact.as_deref().unwrap_or_default())
+			.map_err(error::Error::Input)
+	}
+
+	/// Where to report the `ERROR` events and the panics, if anywhere.
+	pub fn tracing_sentry(&self) -> error::Result<Option<sc_tracing::SentryConfig>> {
+		self.tracing_sentry_dsn.as_deref()
+			.map(|dsn| sc_tracing::SentryConfig::new(dsn).map_err(error::Error::Input))
+			.transpose()
+	}
+
+	/// Number of blocks to keep the trace summaries of.
+	pub fn tracing_summaries(&self) -> Option<u32> {
+		self.tracing_summaries
+	}
+
+	/// Whether to count the storage accesses under the spans.
+	pub fn tracing_storage_counters(&self) -> bool {
+		self.tracing_storage_counters
+	}
+
+	/// Whether to measure the drift of the calls from their weights.
+	pub fn tracing_weight_drift(&self) -> bool {
+		self.tracing_weight_drift
+	}
+
+	/// Whether to break down the proofs of the built blocks.
+	pub fn tracing_pov_breakdowns(&self) -> bool {
+		self.tracing_pov_breakdowns
+	}
+
+	/// Whether to report the failed dispatches.
+	pub fn tracing_dispatch_errors(&self) -> bool {
+		self.tracing_dispatch_errors
+	}
+
+	/// Deadlines past which the imports of the blocks are reported.
+	pub fn import_deadlines(&self) -> Vec<Duration> {
+		self.import_deadlines.iter()
+			.filter(|deadline| **deadline > 0)
+			.map(|deadline| Duration::from_millis(*deadline))
+			.collect()
+	}
+
 	/// Specify the state cache size.
 	pub fn state_cache_size(&self) -> usize {
 		self.state_cache_size
@@ -103,6 +339,16 @@ impl ImportParams {
 		self.wasm_method.into()
 	}
 
+	/// Whether the compiled runtimes are described in a perf jitdump file.
+	pub fn wasmtime_jitdump(&self) -> bool {
+		self.wasmtime_jitdump
+	}
+
+	/// Whether the compiled runtimes are cached on the disk.
+	pub fn wasmtime_cache(&self) -> bool {
+		self.wasmtime_cache
+	}
+
 	/// Get execution strategies for the parameters
 	pub fn execution_strategies(&self, is_dev: bool, is_validator: bool) -> ExecutionStrategies {
 		let exec = &self.execution_strategies;
@@ -18,12 +18,14 @@
 
 use crate::error::Result;
 use sc_service::config::KeystoreConfig;
+use sc_keystore::RemoteSignerConfig;
 use std::fs;
 use std::path::PathBuf;
 use structopt::StructOpt;
 use crate::error;
 use sp_core::crypto::{SecretString, Zeroize};
 use std::str::FromStr;
+use std::time::Duration;
 
 /// default sub directory for the key store
 const DEFAULT_KEYSTORE_CONFIG_PATH: &'static str = "keystore";
@@ -58,6 +60,53 @@ pub struct KeystoreParams {
 		conflicts_with_all = &[ "password-interactive", "password" ]
 	)]
 	pub password_filename: Option<PathBuf>,
+
+	/// Append an audit record of every signing request made to the keystore to the given file.
+	///
+	/// Records are hash-chained, so removed or altered entries can be detected.
+	#[structopt(long = "keystore-audit-log", value_name = "PATH", parse(from_os_str))]
+	pub keystore_audit_log: Option<PathBuf>,
+
+	/// Forward all signing requests to the remote signer at the given `https://` url instead
+	/// of keeping keys on this host.
+	#[structopt(
+		long = "keystore-remote-signer",
+		value_name = "URL",
+		conflicts_with_all = &[ "keystore-path", "password-interactive", "password", "password-filename" ]
+	)]
+	pub keystore_remote_signer: Option<String>,
+
+	/// File that contains the token used to authenticate with the remote signer.
+	#[structopt(
+		long = "keystore-remote-signer-token-filename",
+		value_name = "PATH",
+		parse(from_os_str),
+		requires = "keystore-remote-signer"
+	)]
+	pub keystore_remote_signer_token_filename: Option<PathBuf>,
+
+	/// Timeout in milliseconds for a single request to the remote signer.
+	#[structopt(
+		long = "keystore-remote-signer-timeout",
+		value_name = "MS",
+		default_value = "300"
+	)]
+	pub keystore_remote_signer_timeout: u64,
+
+	/// Deadline in milliseconds for a call to the remote signer, its retries included.
+	///
+	/// The keystore is called synchronously, e.g. by the slot workers, which wait for the call
+	/// up to this deadline.
+	#[structopt(
+		long = "keystore-remote-signer-deadline",
+		value_name = "MS",
+		default_value = "1000"
+	)]
+	pub keystore_remote_signer_deadline: u64,
+
+	/// Accept a plain `http://` url for the remote signer, sending the token in cleartext.
+	#[structopt(long = "keystore-remote-signer-insecure", requires = "keystore-remote-signer")]
+	pub keystore_remote_signer_insecure: bool,
 }
 
 /// Parse a sercret string, returning a displayable error.
@@ -69,6 +118,26 @@ pub fn secret_string_from_str(s: &str) -> std::result::Result<SecretString, Stri
 impl KeystoreParams {
 	/// Get the keystore configuration for the parameters
 	pub fn keystore_config(&self, base_path: &PathBuf) -> Result<KeystoreConfig> {
+		if let Some(url) = &self.keystore_remote_signer {
+			let auth_token = match &self.keystore_remote_signer_token_filename {
+				Some(file) => {
+					let mut token = fs::read_to_string(file)
+						.map_err(|e| format!("{}", e))?;
+					let secret = std::str::FromStr::from_str(token.trim())
+						.map_err(|()| "Error reading remote signer token")?;
+					token.zeroize();
+					Some(secret)
+				},
+				None => None,
+			};
+			let mut signer = RemoteSignerConfig::new(url.clone(), auth_token);
+			signer.timeout = Duration::from_millis(self.keystore_remote_signer_timeout);
+			signer.deadline = Duration::from_millis(self.keystore_remote_signer_deadline);
+			signer.allow_insecure = self.keystore_remote_signer_insecure;
+
+			return Ok(KeystoreConfig::Remote { signer, audit_log: self.keystore_audit_log.clone() })
+		}
+
 		let password = if self.password_interactive {
 			#[cfg(not(target_os = "unknown"))]
 			{
@@ -96,7 +165,7 @@ impl KeystoreParams {
 			.clone()
 			.unwrap_or_else(|| base_path.join(DEFAULT_KEYSTORE_CONFIG_PATH));
 
-		Ok(KeystoreConfig::Path { path, password })
+		Ok(KeystoreConfig::Path { path, password, audit_log: self.keystore_audit_log.clone() })
 	}
 
 	/// helper method to fetch password from `KeyParams` or read from stdin
@@ -20,6 +20,7 @@ use crate::params::node_key_params::NodeKeyParams;
 use sc_network::{
 	config::{NetworkConfiguration, NodeKeyConfig, NonReservedPeerMode, TransportConfig},
 	multiaddr::Protocol,
+	PeerId,
 };
 use sc_service::{ChainSpec, config::{Multiaddr, MultiaddrWithPeerId}};
 use std::path::PathBuf;
@@ -102,6 +103,22 @@ pub struct NetworkParams {
 	/// By default this option is true for `--dev` and false otherwise.
 	#[structopt(long)]
 	pub discover_local: bool,
+
+	/// Send the trace summaries of the imported blocks to this node, over the network.
+	///
+	/// The collector must list this node in its `--trace-reporters`. The summaries are those of
+	/// the block execution traces, enabled with `--tracing-targets`.
+	#[structopt(long, value_name = "ADDR")]
+	pub trace_collector: Option<MultiaddrWithPeerId>,
+
+	/// Send the trace summary of one block out of this many to the trace collector.
+	#[structopt(long, value_name = "COUNT", default_value = "10")]
+	pub trace_collector_sample: u32,
+
+	/// Collect the trace summaries sent by these nodes, re-emitted as the `trace_collection`
+	/// tracing events of this node.
+	#[structopt(long, value_name = "PEER_ID")]
+	pub trace_reporters: Vec<PeerId>,
 }
 
 impl NetworkParams {
@@ -161,6 +178,9 @@ impl NetworkParams {
 			},
 			max_parallel_downloads: self.max_parallel_downloads,
 			allow_non_globals_in_dht: self.discover_local || is_dev,
+			trace_collector: self.trace_collector.clone(),
+			trace_collector_sample: self.trace_collector_sample,
+			trace_reporters: self.trace_reporters.clone(),
 		}
 	}
 }
@@ -16,6 +16,7 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
+use crate::arg_enums::LogOutput;
 use sc_service::config::BasePath;
 use std::path::PathBuf;
 use structopt::StructOpt;
@@ -41,6 +42,20 @@ pub struct SharedParams {
 	/// By default, all targets log `info`. The global log level can be set with -l<level>.
 	#[structopt(short = "l", long, value_name = "LOG_PATTERN")]
 	pub log: Vec<String>,
+
+	/// Where to write the logs.
+	///
+	/// `Syslog` sends them to the local syslog daemon in the RFC 5424 format, `Journald` to
+	/// systemd-journald, the fields of the traces logged by the `Log` tracing receiver becoming
+	/// structured fields.
+	#[structopt(
+		long = "log-output",
+		value_name = "OUTPUT",
+		possible_values = &LogOutput::variants(),
+		case_insensitive = true,
+		default_value = "Stderr"
+	)]
+	pub log_output: LogOutput,
 }
 
 impl SharedParams {
@@ -72,4 +87,9 @@ impl SharedParams {
 	pub fn log_filters(&self) -> &[String] {
 		&self.log
 	}
+
+	/// Where to write the logs
+	pub fn log_output(&self) -> LogOutput {
+		self.log_output
+	}
 }
@@ -0,0 +1,318 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Logging to the local syslog daemon, in the RFC 5424 format, and to systemd-journald, through
+//! its native protocol.
+//!
+//! The structured fields of the records, e.g. the values of the spans logged by the `Log`
+//! tracing receiver, become the structured data of the syslog messages and the fields of the
+//! journal entries.
+
+use std::{io, os::unix::net::UnixDatagram, path::Path};
+use log::kv;
+
+use crate::arg_enums::LogOutput;
+
+/// Socket of the local syslog daemon.
+const SYSLOG_SOCKET: &str = "/dev/log";
+
+/// Socket of the native protocol of journald.
+const JOURNALD_SOCKET: &str = "/run/systemd/journal/socket";
+
+/// Facility of the syslog messages, `daemon`.
+const SYSLOG_FACILITY: u8 = 3;
+
+/// Identifier of the structured data of the syslog messages, under the enterprise number
+/// reserved for documentation, as there is none registered for Substrate.
+const SYSLOG_SD_ID: &str = "fields@32473";
+
+/// Fields set by `journald_entry`, renamed if a record has a field with the same name.
+const JOURNALD_FIELDS: &[&str] = &[
+	"MESSAGE", "PRIORITY", "SYSLOG_IDENTIFIER", "SYSLOG_PID", "TARGET", "CODE_FILE",
+	"CODE_LINE", "CODE_MODULE", "THREAD",
+];
+
+/// Where the records are sent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+	Syslog,
+	Journald,
+}
+
+/// Logger sending the records to the syslog daemon or to journald, filtered by `filter`.
+pub struct SystemLogger {
+	socket: UnixDatagram,
+	format: Format,
+	filter: env_logger::Logger,
+	identifier: String,
+	hostname: String,
+}
+
+impl SystemLogger {
+	/// Connect to the socket of `output`, then build the filter of the records from `builder`.
+	pub fn new(output: LogOutput, builder: &mut env_logger::Builder) -> io::Result<Self> {
+		let (path, format) = match output {
+			LogOutput::Syslog => (SYSLOG_SOCKET, Format::Syslog),
+			LogOutput::Journald => (JOURNALD_SOCKET, Format::Journald),
+			LogOutput::Stderr =>
+				return Err(io::Error::new(io::ErrorKind::InvalidInput, "stderr isn't a system log")),
+		};
+		let socket = UnixDatagram::unbound()?;
+		socket.connect(Path::new(path))?;
+		let identifier = std::env::current_exe().ok()
+			.and_then(|exe| exe.file_name().map(|name| name.to_string_lossy().into_owned()))
+			.unwrap_or_else(|| "substrate".into());
+		let hostname = std::fs::read_to_string("/proc/sys/kernel/hostname")
+			.map(|name| name.trim().to_owned())
+			.unwrap_or_default();
+		Ok(SystemLogger { socket, format, filter: builder.build(), identifier, hostname })
+	}
+
+	/// The most verbose level of the records logged.
+	pub fn max_level(&self) -> log::LevelFilter {
+		self.filter.filter()
+	}
+
+	fn encode(&self, record: &log::Record) -> Vec<u8> {
+		let mut fields = Fields::default();
+		let _ = record.key_values().visit(&mut fields);
+		match self.format {
+			Format::Syslog => syslog_message(
+				severity(record.level()),
+				&time::now_utc(),
+				&self.hostname,
+				&self.identifier,
+				record,
+				&fields.0,
+			).into_bytes(),
+			Format::Journald => journald_entry(severity(record.level()), &self.identifier, record, &fields.0),
+		}
+	}
+}
+
+impl log::Log for SystemLogger {
+	fn enabled(&self, metadata: &log::Metadata) -> bool {
+		self.filter.enabled(metadata)
+	}
+
+	fn log(&self, record: &log::Record) {
+		if self.filter.matches(record) {
+			// The records are dropped if the daemon is gone, there is nowhere to report it.
+			let _ = self.socket.send(&self.encode(record));
+		}
+	}
+
+	fn flush(&self) {}
+}
+
+/// The structured fields of a record.
+#[derive(Default)]
+struct Fields(Vec<(String, String)>);
+
+impl<'kvs> kv::Visitor<'kvs> for Fields {
+	fn visit_pair(&mut self, key: kv::Key<'kvs>, value: kv::Value<'kvs>) -> Result<(), kv::Error> {
+		self.0.push((key.to_string(), value.to_string()));
+		Ok(())
+	}
+}
+
+/// The syslog severity of `level`.
+fn severity(level: log::Level) -> u8 {
+	match level {
+		log::Level::Error => 3,
+		log::Level::Warn => 4,
+		log::Level::Info => 6,
+		log::Level::Debug | log::Level::Trace => 7,
+	}
+}
+
+/// Format an RFC 5424 message, without the optional BOM.
+fn syslog_message(
+	severity: u8,
+	now: &time::Tm,
+	hostname: &str,
+	identifier: &str,
+	record: &log::Record,
+	fields: &[(String, String)],
+) -> String {
+	let timestamp = format!(
+		"{}.{:06}Z",
+		time::strftime("%Y-%m-%dT%H:%M:%S", now).unwrap_or_default(),
+		now.tm_nsec / 1000,
+	);
+	let mut message = format!(
+		"<{}>1 {} {} {} {} {} ",
+		SYSLOG_FACILITY * 8 + severity,
+		timestamp,
+		header_field(hostname, 255),
+		header_field(identifier, 48),
+		std::process::id(),
+		header_field(record.target(), 32),
+	);
+	if fields.is_empty() {
+		message.push('-');
+	} else {
+		message.push('[');
+		message.push_str(SYSLOG_SD_ID);
+		for (name, value) in fields {
+			let name: String = name.chars()
+				.filter(|c| c.is_ascii_graphic() && !matches!(c, '=' | ']' | '"'))
+				.take(32)
+				.collect();
+			if name.is_empty() {
+				continue;
+			}
+			message.push_str(&format!(" {}=\"", name));
+			for c in value.chars() {
+				if matches!(c, '"' | '\\' | ']') {
+					message.push('\\');
+				}
+				message.push(c);
+			}
+			message.push('"');
+		}
+		message.push(']');
+	}
+	message.push(' ');
+	message.push_str(&record.args().to_string());
+	message
+}
+
+/// A header field of a syslog message: at most `max` printable ASCII characters, or `-`.
+fn header_field(value: &str, max: usize) -> String {
+	let value: String = value.chars().filter(char::is_ascii_graphic).take(max).collect();
+	if value.is_empty() {
+		"-".into()
+	} else {
+		value
+	}
+}
+
+/// Format a journal entry, in the native protocol.
+fn journald_entry(
+	severity: u8,
+	identifier: &str,
+	record: &log::Record,
+	fields: &[(String, String)],
+) -> Vec<u8> {
+	let mut entry = Vec::new();
+	journald_field(&mut entry, "MESSAGE", &record.args().to_string());
+	journald_field(&mut entry, "PRIORITY", &severity.to_string());
+	journald_field(&mut entry, "SYSLOG_IDENTIFIER", identifier);
+	journald_field(&mut entry, "SYSLOG_PID", &std::process::id().to_string());
+	journald_field(&mut entry, "TARGET", record.target());
+	if let Some(file) = record.file() {
+		journald_field(&mut entry, "CODE_FILE", file);
+	}
+	if let Some(line) = record.line() {
+		journald_field(&mut entry, "CODE_LINE", &line.to_string());
+	}
+	if let Some(module) = record.module_path() {
+		journald_field(&mut entry, "CODE_MODULE", module);
+	}
+	if let Some(thread) = std::thread::current().name() {
+		journald_field(&mut entry, "THREAD", thread);
+	}
+	for (name, value) in fields {
+		if let Some(name) = journald_name(name) {
+			journald_field(&mut entry, &name, value);
+		}
+	}
+	entry
+}
+
+/// The journal field name of `name`: uppercase ASCII letters, digits and underscores, starting
+/// with a letter, prefixed with `FIELD_` if it's one of the fields set by the logger.
+fn journald_name(name: &str) -> Option<String> {
+	let name: String = name.chars()
+		.map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+		.skip_while(|c| !c.is_ascii_alphabetic())
+		.collect();
+	if name.is_empty() {
+		return None;
+	}
+	let name = if JOURNALD_FIELDS.contains(&name.as_str()) { format!("FIELD_{}", name) } else { name };
+	Some(name.chars().take(64).collect())
+}
+
+/// Append a field to a journal entry, the values with newlines being length prefixed.
+fn journald_field(entry: &mut Vec<u8>, name: &str, value: &str) {
+	entry.extend_from_slice(name.as_bytes());
+	if value.contains('\n') {
+		entry.push(b'\n');
+		entry.extend_from_slice(&(value.len() as u64).to_le_bytes());
+	} else {
+		entry.push(b'=');
+	}
+	entry.extend_from_slice(value.as_bytes());
+	entry.push(b'\n');
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn fields() -> Vec<(String, String)> {
+		vec![
+			("trace_name".into(), "import_block".into()),
+			("message".into(), "say \"hi\"\nthere".into()),
+		]
+	}
+
+	#[test]
+	fn formats_syslog_messages() {
+		let now = time::at_utc(time::Timespec::new(1_600_000_000, 123_456_789));
+		let args = format_args!("Imported #42");
+		let record = log::Record::builder().args(args).level(log::Level::Warn).target("sync").build();
+		let message = syslog_message(severity(record.level()), &now, "host", "node", &record, &fields());
+		assert_eq!(message, format!(
+			"<28>1 2020-09-13T12:26:40.123456Z host node {} sync \
+			[fields@32473 trace_name=\"import_block\" message=\"say \\\"hi\\\"\nthere\"] Imported #42",
+			std::process::id(),
+		));
+		let message = syslog_message(severity(record.level()), &now, "", "node", &record, &[]);
+		assert!(message.starts_with("<28>1 2020-09-13T12:26:40.123456Z - node "));
+		assert!(message.ends_with(" sync - Imported #42"));
+	}
+
+	#[test]
+	fn formats_journal_entries() {
+		let args = format_args!("Imported #42");
+		let record = log::Record::builder().args(args).level(log::Level::Info).target("sync").build();
+		let entry = journald_entry(severity(record.level()), "node", &record, &fields());
+		let mut expected = format!(
+			"MESSAGE=Imported #42\nPRIORITY=6\nSYSLOG_IDENTIFIER=node\nSYSLOG_PID={}\nTARGET=sync\n",
+			std::process::id(),
+		).into_bytes();
+		if let Some(thread) = std::thread::current().name() {
+			expected.extend_from_slice(format!("THREAD={}\n", thread).as_bytes());
+		}
+		expected.extend_from_slice(b"TRACE_NAME=import_block\nFIELD_MESSAGE\n");
+		expected.extend_from_slice(&14u64.to_le_bytes());
+		expected.extend_from_slice(b"say \"hi\"\nthere\n");
+		assert_eq!(entry, expected);
+	}
+
+	#[test]
+	fn sanitizes_journal_field_names() {
+		assert_eq!(journald_name("block-number").as_deref(), Some("BLOCK_NUMBER"));
+		assert_eq!(journald_name("_private").as_deref(), Some("PRIVATE"));
+		assert_eq!(journald_name("target").as_deref(), Some("FIELD_TARGET"));
+		assert_eq!(journald_name("42"), None);
+	}
+}
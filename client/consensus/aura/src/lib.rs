@@ -147,11 +147,12 @@ pub fn start_aura<B, C, SC, E, I, P, SO, CAW, Error>(
 	sync_oracle: SO,
 	inherent_data_providers: InherentDataProviders,
 	force_authoring: bool,
+	post_mortem: Option<sc_consensus_slots::PostMortem>,
 	keystore: KeyStorePtr,
 	can_author_with: CAW,
 ) -> Result<impl Future<Output = ()>, sp_consensus::Error> where
 	B: BlockT,
-	C: ProvideRuntimeApi<B> + BlockOf + ProvideCache<B> + AuxStore + Send + Sync,
+	C: ProvideRuntimeApi<B> + BlockOf + ProvideCache<B> + AuxStore + Send + Sync + 'static,
 	C::Api: AuraApi<B, AuthorityId<P>>,
 	SC: SelectChain<B>,
 	E: Environment<B, Error = Error> + Send + Sync + 'static,
@@ -171,6 +172,7 @@ pub fn start_aura<B, C, SC, E, I, P, SO, CAW, Error>(
 		keystore,
 		sync_oracle: sync_oracle.clone(),
 		force_authoring,
+		post_mortem,
 		_key_type: PhantomData::<P>,
 	};
 	register_aura_inherent_data_provider(
@@ -195,12 +197,13 @@ struct AuraWorker<C, E, I, P, SO> {
 	keystore: KeyStorePtr,
 	sync_oracle: SO,
 	force_authoring: bool,
+	post_mortem: Option<sc_consensus_slots::PostMortem>,
 	_key_type: PhantomData<P>,
 }
 
 impl<B, C, E, I, P, Error, SO> sc_consensus_slots::SimpleSlotWorker<B> for AuraWorker<C, E, I, P, SO> where
 	B: BlockT,
-	C: ProvideRuntimeApi<B> + BlockOf + ProvideCache<B> + Sync,
+	C: ProvideRuntimeApi<B> + BlockOf + ProvideCache<B> + AuxStore + Send + Sync + 'static,
 	C::Api: AuraApi<B, AuthorityId<P>>,
 	E: Environment<B, Error = Error>,
 	E::Proposer: Proposer<B, Error = Error, Transaction = sp_api::TransactionFor<C, B>>,
@@ -228,6 +231,10 @@ impl<B, C, E, I, P, Error, SO> sc_consensus_slots::SimpleSlotWorker<B> for AuraW
 		self.block_import.clone()
 	}
 
+	fn aux_store(&self) -> Option<Arc<dyn sc_consensus_slots::SlotRecordStore>> {
+		Some(self.client.clone())
+	}
+
 	fn epoch_data(
 		&self,
 		header: &B::Header,
@@ -312,6 +319,10 @@ impl<B, C, E, I, P, Error, SO> sc_consensus_slots::SimpleSlotWorker<B> for AuraW
 		self.force_authoring
 	}
 
+	fn post_mortem(&self) -> Option<sc_consensus_slots::PostMortem> {
+		self.post_mortem.clone()
+	}
+
 	fn sync_oracle(&mut self) -> &mut Self::SyncOracle {
 		&mut self.sync_oracle
 	}
@@ -352,7 +363,7 @@ impl<B, C, E, I, P, Error, SO> sc_consensus_slots::SimpleSlotWorker<B> for AuraW
 
 impl<B: BlockT, C, E, I, P, Error, SO> SlotWorker<B> for AuraWorker<C, E, I, P, SO> where
 	B: BlockT,
-	C: ProvideRuntimeApi<B> + BlockOf + ProvideCache<B> + Sync + Send,
+	C: ProvideRuntimeApi<B> + BlockOf + ProvideCache<B> + AuxStore + Sync + Send + 'static,
 	C::Api: AuraApi<B, AuthorityId<P>>,
 	E: Environment<B, Error = Error> + Send + Sync,
 	E::Proposer: Proposer<B, Error = Error, Transaction = sp_api::TransactionFor<C, B>>,
@@ -525,11 +536,9 @@ impl<C, P, CAW> AuraVerifier<C, P, CAW> where
 			return Ok(())
 		}
 
-		let inherent_res = self.client.runtime_api().check_inherents(
-			&block_id,
-			block,
-			inherent_data,
-		).map_err(Error::Client)?;
+		let inherent_res = self.inherent_data_providers.check_inherents(inherent_data, |inherent_data| {
+			self.client.runtime_api().check_inherents(&block_id, block, inherent_data)
+		}).map_err(Error::Client)?;
 
 		if !inherent_res.ok() {
 			inherent_res
@@ -1040,6 +1049,7 @@ mod tests {
 				DummyOracle,
 				inherent_data_providers,
 				false,
+				None,
 				keystore,
 				sp_consensus::AlwaysCanAuthor,
 			).expect("Starts aura"));
@@ -1100,6 +1110,7 @@ mod tests {
 			keystore,
 			sync_oracle: DummyOracle.clone(),
 			force_authoring: false,
+			post_mortem: None,
 			_key_type: PhantomData::<AuthorityPair>,
 		};
 
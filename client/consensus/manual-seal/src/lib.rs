@@ -27,7 +27,7 @@ use sp_consensus::{
 use sp_blockchain::HeaderBackend;
 use sp_inherents::InherentDataProviders;
 use sp_runtime::{traits::Block as BlockT, Justification};
-use sc_client_api::backend::{Backend as ClientBackend, Finalizer};
+use sc_client_api::{backend::{Backend as ClientBackend, Finalizer}, StorageProvider};
 use sc_transaction_pool::txpool;
 use std::{sync::Arc, marker::PhantomData};
 use prometheus_endpoint::Registry;
@@ -36,6 +36,7 @@ mod error;
 mod finalize_block;
 mod seal_new_block;
 pub mod rpc;
+pub mod trace;
 
 use self::{
 	finalize_block::{finalize_block, FinalizeBlockParams},
@@ -44,6 +45,7 @@ use self::{
 pub use self::{
 	error::Error,
 	rpc::{EngineCommand, CreatedBlock},
+	trace::BlockTrace,
 };
 
 /// The verifier for the manual seal engine; instantly finalizes.
@@ -100,7 +102,7 @@ pub async fn run_manual_seal<B, CB, E, C, A, SC, S, T>(
 	where
 		A: txpool::ChainApi<Block=B> + 'static,
 		B: BlockT + 'static,
-		C: HeaderBackend<B> + Finalizer<B, CB> + 'static,
+		C: HeaderBackend<B> + Finalizer<B, CB> + StorageProvider<B, CB> + 'static,
 		CB: ClientBackend<B> + 'static,
 		E: Environment<B> + 'static,
 		E::Error: std::fmt::Display,
@@ -114,12 +116,14 @@ pub async fn run_manual_seal<B, CB, E, C, A, SC, S, T>(
 				create_empty,
 				finalize,
 				parent_hash,
+				trace_targets,
 				sender,
 			} => {
 				seal_new_block(
 					SealBlockParams {
 						sender,
 						parent_hash,
+						trace_targets,
 						finalize,
 						create_empty,
 						env: &mut env,
@@ -128,6 +132,7 @@ pub async fn run_manual_seal<B, CB, E, C, A, SC, S, T>(
 						inherent_data_provider: &inherent_data_providers,
 						pool: pool.clone(),
 						client: client.clone(),
+						_phantom: PhantomData,
 					}
 				).await;
 			}
@@ -160,7 +165,7 @@ pub async fn run_instant_seal<B, CB, E, C, A, SC, T>(
 	where
 		A: txpool::ChainApi<Block=B> + 'static,
 		B: BlockT + 'static,
-		C: HeaderBackend<B> + Finalizer<B, CB> + 'static,
+		C: HeaderBackend<B> + Finalizer<B, CB> + StorageProvider<B, CB> + 'static,
 		CB: ClientBackend<B> + 'static,
 		E: Environment<B> + 'static,
 		E::Error: std::fmt::Display,
@@ -176,6 +181,7 @@ pub async fn run_instant_seal<B, CB, E, C, A, SC, T>(
 				create_empty: false,
 				finalize: false,
 				parent_hash: None,
+				trace_targets: None,
 				sender: None,
 			}
 		});
@@ -242,6 +248,7 @@ mod tests {
 					create_empty: false,
 					finalize: true,
 					parent_hash: None,
+					trace_targets: None,
 					sender
 				}
 			});
@@ -276,7 +283,8 @@ mod tests {
 					bad_justification: false,
 					needs_finality_proof: false,
 					is_new_best: true,
-				}
+				},
+				trace: None,
 			}
 		);
 		// assert that there's a new block in the db.
@@ -324,6 +332,7 @@ mod tests {
 			sender: Some(tx),
 			create_empty: false,
 			finalize: false,
+			trace_targets: None,
 		}).await.unwrap();
 		let created_block = rx.await.unwrap().unwrap();
 
@@ -339,7 +348,8 @@ mod tests {
 					bad_justification: false,
 					needs_finality_proof: false,
 					is_new_best: true,
-				}
+				},
+				trace: None,
 			}
 		);
 		// assert that there's a new block in the db.
@@ -354,6 +364,54 @@ mod tests {
 		assert_eq!(rx.await.unwrap().unwrap(), ());
 	}
 
+	#[tokio::test]
+	async fn manual_seal_with_trace() {
+		let builder = TestClientBuilder::new();
+		let (client, select_chain) = builder.build_with_longest_chain();
+		let client = Arc::new(client);
+		let inherent_data_providers = InherentDataProviders::new();
+		let spawner = sp_core::testing::TaskExecutor::new();
+		let pool = Arc::new(BasicPool::with_revalidation_type(
+			Options::default(), api(), None, RevalidationType::Full, spawner,
+		));
+		let env = ProposerFactory::new(
+			client.clone(),
+			pool.clone(),
+			None,
+		);
+		// this test checks that the execution of the block is traced when requested.
+		let (mut sink, stream) = futures::channel::mpsc::channel(1024);
+		let future = run_manual_seal(
+			Box::new(client.clone()),
+			env,
+			client.clone(),
+			pool.pool().clone(),
+			stream,
+			select_chain,
+			inherent_data_providers,
+		);
+		std::thread::spawn(|| {
+			let mut rt = tokio::runtime::Runtime::new().unwrap();
+			// spawn the background authorship task
+			rt.block_on(future);
+		});
+		let (tx, rx) = futures::channel::oneshot::channel();
+		sink.send(EngineCommand::SealNewBlock {
+			parent_hash: None,
+			sender: Some(tx),
+			create_empty: true,
+			finalize: false,
+			trace_targets: Some("substrate_test_runtime".into()),
+		}).await.unwrap();
+		let created_block = rx.await.unwrap().unwrap();
+
+		// the test runtime doesn't use `frame_system`, so the weight is unknown.
+		let trace = created_block.trace.expect("trace was requested");
+		assert!(trace.duration_ns > 0);
+		assert_eq!(trace.weight, None);
+		assert!(client.header(&BlockId::Hash(created_block.hash)).unwrap().is_some());
+	}
+
 	#[tokio::test]
 	async fn manual_seal_fork_blocks() {
 		let builder = TestClientBuilder::new();
@@ -397,6 +455,7 @@ mod tests {
 			sender: Some(tx),
 			create_empty: false,
 			finalize: false,
+			trace_targets: None,
 		}).await.unwrap();
 		let created_block = rx.await.unwrap().unwrap();
 		pool_api.increment_nonce(Alice.into());
@@ -413,7 +472,8 @@ mod tests {
 					bad_justification: false,
 					needs_finality_proof: false,
 					is_new_best: true
-				}
+				},
+				trace: None,
 			}
 		);
 		let block = client.block(&BlockId::Number(1)).unwrap().unwrap().block;
@@ -432,6 +492,7 @@ mod tests {
 			sender: Some(tx1),
 			create_empty: false,
 			finalize: false,
+			trace_targets: None,
 		}).await.is_ok());
 		assert_matches::assert_matches!(
 			rx1.await.expect("should be no error receiving"),
@@ -448,6 +509,7 @@ mod tests {
 			sender: Some(tx2),
 			create_empty: false,
 			finalize: false,
+			trace_targets: None,
 		}).await.is_ok());
 		let imported = rx2.await.unwrap().unwrap();
 		// assert that fork block is in the db
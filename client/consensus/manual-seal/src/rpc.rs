@@ -26,6 +26,7 @@ use futures::{
 };
 use serde::{Deserialize, Serialize};
 use sp_runtime::Justification;
+use crate::trace::BlockTrace;
 pub use self::gen_client::Client as ManualSealClient;
 
 /// Future's type for jsonrpc
@@ -49,6 +50,8 @@ pub enum EngineCommand<Hash> {
 		finalize: bool,
 		/// specify the parent hash of the about-to-created block
 		parent_hash: Option<Hash>,
+		/// if set, the execution of the block is traced for these comma separated targets.
+		trace_targets: Option<String>,
 		/// sender to report errors/success to the rpc.
 		sender: Sender<CreatedBlock<Hash>>,
 	},
@@ -67,12 +70,16 @@ pub enum EngineCommand<Hash> {
 #[rpc]
 pub trait ManualSealApi<Hash> {
 	/// Instructs the manual-seal authorship task to create a new block
+	///
+	/// If `trace_targets` is given, e.g. `"pallet,frame"`, the result includes a summary of the
+	/// spans of these targets entered while executing the block.
 	#[rpc(name = "engine_createBlock")]
 	fn create_block(
 		&self,
 		create_empty: bool,
 		finalize: bool,
-		parent_hash: Option<Hash>
+		parent_hash: Option<Hash>,
+		trace_targets: Option<String>,
 	) -> FutureResult<CreatedBlock<Hash>>;
 
 	/// Instructs the manual-seal authorship task to finalize a block
@@ -95,7 +102,10 @@ pub struct CreatedBlock<Hash> {
 	/// hash of the created block.
 	pub hash: Hash,
 	/// some extra details about the import operation
-	pub aux: ImportedAux
+	pub aux: ImportedAux,
+	/// summary of the execution of the block, if it was requested.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub trace: Option<BlockTrace>,
 }
 
 impl<Hash> ManualSeal<Hash> {
@@ -110,7 +120,8 @@ impl<Hash: Send + 'static> ManualSealApi<Hash> for ManualSeal<Hash> {
 		&self,
 		create_empty: bool,
 		finalize: bool,
-		parent_hash: Option<Hash>
+		parent_hash: Option<Hash>,
+		trace_targets: Option<String>,
 	) -> FutureResult<CreatedBlock<Hash>> {
 		let mut sink = self.import_block_channel.clone();
 		let future = async move {
@@ -119,6 +130,7 @@ impl<Hash: Send + 'static> ManualSealApi<Hash> for ManualSeal<Hash> {
 				create_empty,
 				finalize,
 				parent_hash,
+				trace_targets,
 				sender: Some(sender),
 			};
 			sink.send(command).await?;
@@ -16,13 +16,14 @@
 
 //! Block sealing utilities
 
-use crate::{Error, rpc};
-use std::sync::Arc;
+use crate::{Error, rpc, trace};
+use std::{sync::Arc, marker::PhantomData};
 use sp_runtime::{
 	traits::{Block as BlockT, Header as HeaderT},
 	generic::BlockId,
 };
 use futures::prelude::*;
+use sc_client_api::{backend::Backend as ClientBackend, StorageProvider};
 use sc_transaction_pool::txpool;
 use rpc::CreatedBlock;
 
@@ -41,7 +42,7 @@ use sp_inherents::InherentDataProviders;
 const MAX_PROPOSAL_DURATION: u64 = 10;
 
 /// params for sealing a new block
-pub struct SealBlockParams<'a, B: BlockT, SC, HB, E, T, P: txpool::ChainApi> {
+pub struct SealBlockParams<'a, B: BlockT, SC, HB, E, T, P: txpool::ChainApi, CB> {
 	/// if true, empty blocks(without extrinsics) will be created.
 	/// otherwise, will return Error::EmptyTransactionPool.
 	pub create_empty: bool,
@@ -49,6 +50,8 @@ pub struct SealBlockParams<'a, B: BlockT, SC, HB, E, T, P: txpool::ChainApi> {
 	pub finalize: bool,
 	/// specify the parent hash of the about-to-created block
 	pub parent_hash: Option<<B as BlockT>::Hash>,
+	/// targets to trace the execution of the block for, if any.
+	pub trace_targets: Option<String>,
 	/// sender to report errors/success to the rpc.
 	pub sender: rpc::Sender<CreatedBlock<<B as BlockT>::Hash>>,
 	/// transaction pool
@@ -63,15 +66,18 @@ pub struct SealBlockParams<'a, B: BlockT, SC, HB, E, T, P: txpool::ChainApi> {
 	pub block_import: &'a mut BoxBlockImport<B, T>,
 	/// inherent data provider
 	pub inherent_data_provider: &'a InherentDataProviders,
+	/// phantom type to pin the Backend type
+	pub _phantom: PhantomData<CB>,
 }
 
 /// seals a new block with the given params
-pub async fn seal_new_block<B, SC, HB, E, T, P>(
+pub async fn seal_new_block<B, SC, HB, E, T, P, CB>(
 	SealBlockParams {
 		create_empty,
 		finalize,
 		pool,
 		parent_hash,
+		trace_targets,
 		client,
 		select_chain,
 		block_import,
@@ -79,11 +85,12 @@ pub async fn seal_new_block<B, SC, HB, E, T, P>(
 		inherent_data_provider,
 		mut sender,
 		..
-	}: SealBlockParams<'_, B, SC, HB, E, T, P>
+	}: SealBlockParams<'_, B, SC, HB, E, T, P, CB>
 )
 	where
 		B: BlockT,
-		HB: HeaderBackend<B>,
+		HB: HeaderBackend<B> + StorageProvider<B, CB>,
+		CB: ClientBackend<B>,
 		E: Environment<B>,
 		<E as Environment<B>>::Error: std::fmt::Display,
 		<E::Proposer as Proposer<B>>::Error: std::fmt::Display,
@@ -125,9 +132,24 @@ pub async fn seal_new_block<B, SC, HB, E, T, P>(
 		params.finalized = finalize;
 		params.fork_choice = Some(ForkChoiceStrategy::LongestChain);
 
-		match block_import.import_block(params, HashMap::new())? {
+		let hash = <B as BlockT>::Header::hash(&header);
+		// the block is executed again on import, which is the part being traced.
+		let import = || block_import.import_block(params, HashMap::new());
+		let (result, trace) = match trace_targets {
+			Some(targets) => {
+				let (result, trace) = trace::trace(&targets, import);
+				(result, Some(trace))
+			},
+			None => (import(), None),
+		};
+
+		match result? {
 			ImportResult::Imported(aux) => {
-				Ok(CreatedBlock { hash: <B as BlockT>::Header::hash(&header), aux })
+				let trace = trace.map(|trace| trace::BlockTrace {
+					weight: trace::block_weight(&*client, hash),
+					..trace
+				});
+				Ok(CreatedBlock { hash, aux, trace })
 			},
 			other => Err(other.into()),
 		}
@@ -0,0 +1,138 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Execution trace summaries of the blocks created by the engine.
+
+use std::{collections::BTreeMap, sync::Arc, time::Instant};
+use codec::Decode;
+use parking_lot::Mutex;
+use sc_client_api::{backend::Backend as ClientBackend, StorageProvider};
+use sc_tracing::{ProfilingSubscriber, SpanDatum, TraceEvent, TraceHandler};
+use serde::{Deserialize, Serialize};
+use sp_core::{storage::StorageKey, twox_128};
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+
+/// Total time spent in the spans of a given target and name.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SpanSummary {
+	/// target of the spans.
+	pub target: String,
+	/// name of the spans.
+	pub name: String,
+	/// number of spans entered.
+	pub count: u64,
+	/// nanoseconds spent inside the spans, including the nested ones.
+	pub duration_ns: u64,
+}
+
+/// Summary of the execution of a created block, returned by `engine_createBlock` on request.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockTrace {
+	/// nanoseconds spent importing, thus executing, the block.
+	pub duration_ns: u64,
+	/// weight consumed by the extrinsics of the block, if the runtime uses `frame_system`.
+	pub weight: Option<u64>,
+	/// spans of the traced targets, ordered by target and name.
+	pub spans: Vec<SpanSummary>,
+}
+
+/// Collects the spans reported by the `ProfilingSubscriber`.
+struct SpanCollector(Arc<Mutex<Vec<SpanDatum>>>);
+
+impl TraceHandler for SpanCollector {
+	fn handle_span(&self, span: SpanDatum) {
+		self.0.lock().push(span);
+	}
+
+	fn handle_event(&self, _: TraceEvent) {}
+}
+
+/// Runs `f`, tracing the spans of `targets` entered on the current thread, including the spans
+/// of the wasm runtime.
+///
+/// `targets` is a comma separated list of targets, in the format of `--tracing-targets`.
+/// The `weight` of the returned trace is left to the caller.
+pub fn trace<R>(targets: &str, f: impl FnOnce() -> R) -> (R, BlockTrace) {
+	let spans = Arc::new(Mutex::new(Vec::new()));
+	let subscriber = ProfilingSubscriber::new_with_handler(
+		Box::new(SpanCollector(spans.clone())),
+		targets,
+	);
+
+	// Only the import on this thread is traced, not the blocks imported concurrently.
+	let start = Instant::now();
+	let result = sp_tracing::with_wasm_tracing(|| tracing::subscriber::with_default(subscriber, f));
+	let duration = start.elapsed();
+
+	let mut summaries = BTreeMap::<_, (u64, u64)>::new();
+	for span in spans.lock().drain(..) {
+		let (count, duration_ns) = summaries.entry((span.target, span.name)).or_default();
+		*count += 1;
+		*duration_ns = duration_ns.saturating_add(span.overall_time.as_nanos() as u64);
+	}
+
+	let trace = BlockTrace {
+		duration_ns: duration.as_nanos() as u64,
+		weight: None,
+		spans: summaries.into_iter()
+			.map(|((target, name), (count, duration_ns))| SpanSummary { target, name, count, duration_ns })
+			.collect(),
+	};
+	(result, trace)
+}
+
+/// Returns the weight consumed by the extrinsics of the given block.
+///
+/// This reads `System::BlockWeight` from the state of the block, so it only succeeds if the
+/// runtime uses `frame_system`.
+pub fn block_weight<B, C, CB>(client: &C, hash: B::Hash) -> Option<u64>
+	where
+		B: BlockT,
+		C: StorageProvider<B, CB>,
+		CB: ClientBackend<B>,
+{
+	let key = StorageKey([twox_128(b"System"), twox_128(b"BlockWeight")].concat());
+	let data = client.storage(&BlockId::Hash(hash), &key).ok()??;
+	// `ExtrinsicsWeight` is the weight of the normal and of the operational extrinsics.
+	let (normal, operational) = <(u64, u64)>::decode(&mut &data.0[..]).ok()?;
+	Some(normal.saturating_add(operational))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn summarizes_spans_by_target_and_name() {
+		let (result, trace) = trace("test_target", || {
+			for _ in 0..2 {
+				tracing::info_span!(target: "test_target", "apply_extrinsic").in_scope(|| ());
+			}
+			tracing::info_span!(target: "other_target", "ignored").in_scope(|| ());
+			tracing::info_span!(target: "test_target", "finalize_block").in_scope(|| 42)
+		});
+
+		assert_eq!(result, 42);
+		let spans = trace.spans.iter()
+			.map(|span| (span.name.as_str(), span.count))
+			.collect::<Vec<_>>();
+		assert_eq!(spans, vec![("apply_extrinsic", 2), ("finalize_block", 1)]);
+		assert_eq!(trace.weight, None);
+		assert!(!sp_tracing::wasm_tracing_enabled());
+	}
+}
@@ -270,11 +270,9 @@ impl<B, I, C, S, Algorithm, CAW> PowBlockImport<B, I, C, S, Algorithm, CAW> wher
 			return Ok(())
 		}
 
-		let inherent_res = self.client.runtime_api().check_inherents(
-			&block_id,
-			block,
-			inherent_data,
-		).map_err(Error::Client)?;
+		let inherent_res = self.inherent_data_providers.check_inherents(inherent_data, |inherent_data| {
+			self.client.runtime_api().check_inherents(&block_id, block, inherent_data)
+		}).map_err(Error::Client)?;
 
 		if !inherent_res.ok() {
 			inherent_res
@@ -0,0 +1,236 @@
+// Copyright 2019-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Record of the slots the authorities of the node are scheduled to author, and of what became of
+//! them, in the aux-db.
+//!
+//! A slot is recorded once, when its authoring ends, under the next of the sequence numbers kept
+//! in the aux-db, so that recording it doesn't depend on the number of slots already recorded.
+
+use std::sync::Arc;
+
+use codec::{Decode, Encode};
+use log::warn;
+use parking_lot::Mutex;
+use sc_client_api::backend::AuxStore;
+use sp_blockchain::{Result as ClientResult, Error as ClientError};
+
+use crate::aux_schema::load_decode;
+
+const SLOT_RECORD_MAP_KEY: &[u8] = b"slot_record_map";
+const SLOT_RECORD_BOUNDS: &[u8] = b"slot_record_bounds";
+
+/// Number of slots kept in the record, the oldest being dropped first.
+pub const MAX_SLOT_RECORDS: usize = 4096;
+
+/// What became of a scheduled slot.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub enum SlotOutcome {
+	/// The authoring of the block of the slot was abandoned before it ended, e.g. as the node
+	/// stopped.
+	Pending,
+	/// The block of the slot was authored and imported.
+	Authored,
+	/// No block was authored in the slot, for the given reason.
+	Missed(String),
+}
+
+/// A slot an authority of the node is scheduled to author, as seen by a slot worker.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub struct SlotRecord {
+	/// The slot number.
+	pub slot: u64,
+	/// The logging target of the worker, e.g. `aura` or `babe`.
+	pub engine: String,
+	/// The SCALE encoded hash of the block the slot was scheduled on.
+	pub parent: Vec<u8>,
+	/// The time it took to propose the block in milliseconds, if it was proposed in time.
+	pub proposal_ms: Option<u64>,
+	/// What became of the slot.
+	pub outcome: SlotOutcome,
+}
+
+/// The aux-db the slot workers record the scheduled slots in, see
+/// [`SimpleSlotWorker::aux_store`](crate::SimpleSlotWorker::aux_store).
+pub trait SlotRecordStore: Send + Sync {
+	/// See [`AuxStore::get_aux`].
+	fn get_record(&self, key: &[u8]) -> ClientResult<Option<Vec<u8>>>;
+
+	/// See [`AuxStore::insert_aux`].
+	fn insert_records(&self, insert: &[(&[u8], &[u8])], delete: &[&[u8]]) -> ClientResult<()>;
+}
+
+impl<C: AuxStore + Send + Sync> SlotRecordStore for C {
+	fn get_record(&self, key: &[u8]) -> ClientResult<Option<Vec<u8>>> {
+		AuxStore::get_aux(self, key)
+	}
+
+	fn insert_records(&self, insert: &[(&[u8], &[u8])], delete: &[&[u8]]) -> ClientResult<()> {
+		AuxStore::insert_aux(self, insert.iter(), delete.iter())
+	}
+}
+
+fn record_key(sequence: u64) -> Vec<u8> {
+	let mut key = SLOT_RECORD_MAP_KEY.to_vec();
+	sequence.using_encoded(|s| key.extend(s));
+	key
+}
+
+/// The slots scheduled to the authorities of the node and seen by its slot workers, at most the
+/// last [`MAX_SLOT_RECORDS`], in the order their authoring ended.
+pub fn slot_records<C: AuxStore>(backend: &C) -> ClientResult<Vec<SlotRecord>> {
+	let (first, next) = load_decode::<_, (u64, u64)>(backend, SLOT_RECORD_BOUNDS)?.unwrap_or_default();
+	let mut records = Vec::with_capacity((next - first) as usize);
+	for sequence in first..next {
+		records.extend(load_decode::<_, SlotRecord>(backend, &record_key(sequence))?);
+	}
+	Ok(records)
+}
+
+/// Append `record` to the records, dropping the oldest one past [`MAX_SLOT_RECORDS`].
+fn append_record(store: &dyn SlotRecordStore, record: &SlotRecord) -> ClientResult<()> {
+	let (mut first, next) = match store.get_record(SLOT_RECORD_BOUNDS)? {
+		Some(bounds) => <(u64, u64)>::decode(&mut &bounds[..]).map_err(|e| ClientError::Backend(
+			format!("Slots DB is corrupted. Decode error: {}", e.what()),
+		))?,
+		None => (0, 0),
+	};
+	let mut pruned = Vec::new();
+	while next + 1 - first > MAX_SLOT_RECORDS as u64 {
+		pruned.push(record_key(first));
+		first += 1;
+	}
+	let (key, record, bounds) = (record_key(next), record.encode(), (first, next + 1).encode());
+	store.insert_records(
+		&[(&key[..], &record[..]), (SLOT_RECORD_BOUNDS, &bounds[..])],
+		&pruned.iter().map(|key| &key[..]).collect::<Vec<_>>(),
+	)
+}
+
+/// The record of a claimed slot, kept in memory during its authoring and written to the aux-db
+/// once, with its outcome, or as `Pending` if the authoring is abandoned.
+pub(crate) struct SlotRecorder {
+	store: Option<Arc<dyn SlotRecordStore>>,
+	logging_target: &'static str,
+	record: Mutex<Option<SlotRecord>>,
+}
+
+impl SlotRecorder {
+	pub(crate) fn new(
+		store: Option<Arc<dyn SlotRecordStore>>,
+		logging_target: &'static str,
+		slot: u64,
+		parent: Vec<u8>,
+	) -> Self {
+		let record = store.as_ref().map(|_| SlotRecord {
+			slot,
+			engine: logging_target.into(),
+			parent,
+			proposal_ms: None,
+			outcome: SlotOutcome::Pending,
+		});
+		SlotRecorder { store, logging_target, record: Mutex::new(record) }
+	}
+
+	/// The block of the slot was proposed in `proposal_ms`.
+	pub(crate) fn proposed(&self, proposal_ms: u64) {
+		if let Some(record) = &mut *self.record.lock() {
+			record.proposal_ms = Some(proposal_ms);
+		}
+	}
+
+	/// Write the record of the slot with its `outcome`. Failing to doesn't hinder the authoring.
+	pub(crate) fn finish(&self, outcome: SlotOutcome) {
+		let mut record = match self.record.lock().take() {
+			Some(record) => record,
+			None => return,
+		};
+		record.outcome = outcome;
+		if let Some(store) = &self.store {
+			if let Err(e) = append_record(&**store, &record) {
+				warn!(target: self.logging_target, "Unable to record the scheduled slot {}: {:?}", record.slot, e);
+			}
+		}
+	}
+}
+
+impl Drop for SlotRecorder {
+	fn drop(&mut self) {
+		self.finish(SlotOutcome::Pending);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn slots_are_recorded_once_their_authoring_ends() {
+		let client = Arc::new(substrate_test_runtime_client::new());
+		let store = || Some(client.clone() as Arc<dyn SlotRecordStore>);
+		let authored = SlotRecorder::new(store(), "test", 7, vec![1]);
+		let missed = SlotRecorder::new(store(), "test", 8, vec![2]);
+		let abandoned = SlotRecorder::new(store(), "test", 9, vec![3]);
+		authored.proposed(10);
+		assert!(slot_records(&*client).unwrap().is_empty());
+
+		authored.finish(SlotOutcome::Authored);
+		missed.finish(SlotOutcome::Missed("timeout".into()));
+		drop((authored, missed, abandoned));
+
+		assert_eq!(slot_records(&*client).unwrap(), vec![
+			SlotRecord {
+				slot: 7,
+				engine: "test".into(),
+				parent: vec![1],
+				proposal_ms: Some(10),
+				outcome: SlotOutcome::Authored,
+			},
+			SlotRecord {
+				slot: 8,
+				engine: "test".into(),
+				parent: vec![2],
+				proposal_ms: None,
+				outcome: SlotOutcome::Missed("timeout".into()),
+			},
+			SlotRecord {
+				slot: 9,
+				engine: "test".into(),
+				parent: vec![3],
+				proposal_ms: None,
+				outcome: SlotOutcome::Pending,
+			},
+		]);
+	}
+
+	#[test]
+	fn oldest_slots_are_pruned() {
+		let client = substrate_test_runtime_client::new();
+		for slot in 0..MAX_SLOT_RECORDS as u64 + 2 {
+			append_record(&client, &SlotRecord {
+				slot,
+				engine: "test".into(),
+				parent: Vec::new(),
+				proposal_ms: None,
+				outcome: SlotOutcome::Authored,
+			}).unwrap();
+		}
+		let records = slot_records(&client).unwrap();
+		assert_eq!(records.len(), MAX_SLOT_RECORDS);
+		assert_eq!(records[0].slot, 2);
+		assert!(client.get_aux(&record_key(1)).unwrap().is_none());
+	}
+}
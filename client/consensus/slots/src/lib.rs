@@ -24,10 +24,14 @@
 
 mod slots;
 mod aux_schema;
+mod authorship;
+mod post_mortem;
 
 pub use slots::{SignedDuration, SlotInfo};
 use slots::Slots;
 pub use aux_schema::{check_equivocation, MAX_SLOT_CAPACITY, PRUNING_BOUND};
+pub use authorship::{MAX_SLOT_RECORDS, SlotOutcome, SlotRecord, SlotRecordStore, slot_records};
+pub use post_mortem::{MAX_BUNDLES, MAX_BUNDLE_SIZE, PostMortem, PostMortemConfig};
 
 use codec::{Decode, Encode};
 use sp_consensus::{BlockImport, Proposer, SyncOracle, SelectChain, CanAuthorWith, SlotData, RecordProof};
@@ -42,6 +46,11 @@ use std::{fmt::Debug, ops::Deref, pin::Pin, sync::Arc, time::{Instant, Duration}
 use sc_telemetry::{telemetry, CONSENSUS_DEBUG, CONSENSUS_WARN, CONSENSUS_INFO};
 use parking_lot::Mutex;
 
+/// Tracing target of the slot claims, proposals and missed slots of every slot worker.
+///
+/// The events carry the logging target of the worker as `engine`, e.g. `aura` or `babe`.
+pub const TRACING_TARGET: &str = "slots";
+
 /// The changes that need to applied to the storage to create the state for a block.
 ///
 /// See [`sp_state_machine::StorageChanges`] for more information.
@@ -88,6 +97,12 @@ pub trait SimpleSlotWorker<B: BlockT> {
 	/// A handle to a `BlockImport`.
 	fn block_import(&self) -> Arc<Mutex<Self::BlockImport>>;
 
+	/// A handle to the aux-db the claimed slots are recorded in, if they are, see
+	/// [`slot_records`].
+	fn aux_store(&self) -> Option<Arc<dyn SlotRecordStore>> {
+		None
+	}
+
 	/// Returns the epoch data necessary for authoring. For time-dependent epochs,
 	/// use the provided slot number as a canonical source of time.
 	fn epoch_data(&self, header: &B::Header, slot_number: u64) -> Result<Self::EpochData, sp_consensus::Error>;
@@ -138,6 +153,12 @@ pub trait SimpleSlotWorker<B: BlockT> {
 	/// Whether to force authoring if offline.
 	fn force_authoring(&self) -> bool;
 
+	/// Where to write the post-mortem bundles of the slots in which authoring a block fails or
+	/// overruns the slot, if anywhere.
+	fn post_mortem(&self) -> Option<PostMortem> {
+		None
+	}
+
 	/// Returns a handle to a `SyncOracle`.
 	fn sync_oracle(&mut self) -> &mut Self::SyncOracle;
 
@@ -180,6 +201,13 @@ pub trait SimpleSlotWorker<B: BlockT> {
 					"Skipping proposal slot {} since our current view is {}",
 					slot_number, slot_now,
 				);
+				tracing::debug!(
+					target: TRACING_TARGET,
+					engine = self.logging_target(),
+					slot = slot_number,
+					slot_now,
+					"Skipped slot, our view of the time is ahead",
+				);
 
 				return Box::pin(future::ready(Ok(())));
 			}
@@ -209,6 +237,12 @@ pub trait SimpleSlotWorker<B: BlockT> {
 			authorities_len.map(|a| a > 1).unwrap_or(false)
 		{
 			debug!(target: self.logging_target(), "Skipping proposal slot. Waiting for the network.");
+			tracing::debug!(
+				target: TRACING_TARGET,
+				engine = self.logging_target(),
+				slot = slot_number,
+				"Skipped slot, waiting for the network",
+			);
 			telemetry!(
 				CONSENSUS_DEBUG;
 				"slots.skipping_proposal_slot";
@@ -219,10 +253,34 @@ pub trait SimpleSlotWorker<B: BlockT> {
 		}
 
 		let claim = match self.claim_slot(&chain_head, slot_number, &epoch_data) {
-			None => return Box::pin(future::ready(Ok(()))),
+			None => {
+				tracing::trace!(
+					target: TRACING_TARGET,
+					engine = self.logging_target(),
+					slot = slot_number,
+					"Slot not claimed",
+				);
+				return Box::pin(future::ready(Ok(())))
+			},
 			Some(claim) => claim,
 		};
 
+		let logging_target = self.logging_target();
+		let recorder = Arc::new(authorship::SlotRecorder::new(
+			self.aux_store(),
+			logging_target,
+			slot_number,
+			chain_head.hash().encode(),
+		));
+
+		tracing::debug!(
+			target: TRACING_TARGET,
+			engine = logging_target,
+			slot = slot_number,
+			parent = ?chain_head.hash(),
+			"Slot claimed",
+		);
+
 		debug!(
 			target: self.logging_target(), "Starting authorship at slot {}; timestamp = {}",
 			slot_number,
@@ -234,8 +292,31 @@ pub trait SimpleSlotWorker<B: BlockT> {
 			"timestamp" => timestamp,
 		);
 
+		let slot_remaining_duration = self.slot_remaining_duration(&slot_info);
+		let proposing_remaining_duration = self.proposing_remaining_duration(&chain_head, &slot_info);
+		let authorship = Arc::new(post_mortem::Authorship::new(
+			self.post_mortem(),
+			logging_target,
+			slot_number,
+			format!("{:?}", chain_head.hash()),
+			slot_remaining_duration,
+			proposing_remaining_duration,
+			&slot_info.inherent_data,
+		));
+
+		let proposer_authorship = authorship.clone();
+		let proposer_recorder = recorder.clone();
 		let awaiting_proposer = self.proposer(&chain_head).map_err(move |err| {
 			warn!("Unable to author block in slot {:?}: {:?}", slot_number, err);
+			tracing::warn!(
+				target: TRACING_TARGET,
+				engine = logging_target,
+				slot = slot_number,
+				error = ?err,
+				"Missed slot, unable to create a proposer",
+			);
+			proposer_recorder.finish(SlotOutcome::Missed(format!("Unable to create a proposer: {:?}", err)));
+			proposer_authorship.capture(&format!("Unable to create a proposer: {:?}", err));
 
 			telemetry!(CONSENSUS_WARN; "slots.unable_authoring_block";
 				"slot" => slot_number, "err" => ?err
@@ -244,9 +325,8 @@ pub trait SimpleSlotWorker<B: BlockT> {
 			err
 		});
 
-		let slot_remaining_duration = self.slot_remaining_duration(&slot_info);
-		let proposing_remaining_duration = self.proposing_remaining_duration(&chain_head, &slot_info);
 		let logs = self.pre_digest_data(slot_number, &claim);
+		let proposing_start = Instant::now();
 
 		// deadline our production to approx. the end of the slot
 		let proposing = awaiting_proposer.and_then(move |proposer| proposer.propose(
@@ -263,11 +343,49 @@ pub trait SimpleSlotWorker<B: BlockT> {
 			None => Box::new(future::pending()),
 		};
 
+		let proposal_authorship = authorship.clone();
+		let proposal_recorder = recorder.clone();
 		let proposal_work =
 			Box::new(futures::future::select(proposing, delay).map(move |v| match v {
-				futures::future::Either::Left((b, _)) => b.map(|b| (b, claim)),
+				futures::future::Either::Left((b, _)) => {
+					let elapsed = proposing_start.elapsed();
+					tracing::debug!(
+						target: TRACING_TARGET,
+						engine = logging_target,
+						slot = slot_number,
+						proposal_ms = elapsed.as_millis() as u64,
+						slot_remaining_ms = slot_remaining_duration
+							.checked_sub(elapsed)
+							.unwrap_or_default()
+							.as_millis() as u64,
+						"Proposal ready",
+					);
+					match &b {
+						Ok(_) => {
+							proposal_recorder.proposed(elapsed.as_millis() as u64);
+							if elapsed > slot_remaining_duration {
+								proposal_authorship.capture("Proposal overran the slot");
+							}
+						},
+						Err(err) => {
+							let reason = format!("Unable to propose: {:?}", err);
+							proposal_recorder.finish(SlotOutcome::Missed(reason.clone()));
+							proposal_authorship.capture(&reason);
+						},
+					}
+					b.map(|b| (b, claim))
+				},
 				futures::future::Either::Right(_) => {
 					info!("⌛️ Discarding proposal for slot {}; block production took too long", slot_number);
+					tracing::warn!(
+						target: TRACING_TARGET,
+						engine = logging_target,
+						slot = slot_number,
+						proposal_ms = proposing_start.elapsed().as_millis() as u64,
+						"Missed slot, block production took too long",
+					);
+					proposal_recorder.finish(SlotOutcome::Missed("Block production took too long".into()));
+					proposal_authorship.capture("Block production took too long");
 					// If the node was compiled with debug, tell the user to use release optimizations.
 					#[cfg(build_type="debug")]
 					info!("👉 Recompile your node in `--release` mode to mitigate this problem.");
@@ -280,7 +398,6 @@ pub trait SimpleSlotWorker<B: BlockT> {
 
 		let block_import_params_maker = self.block_import_params();
 		let block_import = self.block_import();
-		let logging_target = self.logging_target();
 
 		Box::pin(proposal_work.and_then(move |(proposal, claim)| {
 			let (header, body) = proposal.block.deconstruct();
@@ -299,7 +416,12 @@ pub trait SimpleSlotWorker<B: BlockT> {
 
 			let block_import_params = match block_import_params {
 				Ok(params) => params,
-				Err(e) => return future::err(e),
+				Err(e) => {
+					let reason = format!("Unable to seal the block: {:?}", e);
+					recorder.finish(SlotOutcome::Missed(reason.clone()));
+					authorship.capture(&reason);
+					return future::err(e)
+				},
 			};
 
 			info!(
@@ -315,16 +437,29 @@ pub trait SimpleSlotWorker<B: BlockT> {
 				"hash_previously" => ?header_hash,
 			);
 
-			if let Err(err) = block_import.lock().import_block(block_import_params, Default::default()) {
+			let imported = block_import.lock().import_block(block_import_params, Default::default());
+			if let Err(err) = imported {
 				warn!(target: logging_target,
 					"Error with block built on {:?}: {:?}",
 					parent_hash,
 					err,
 				);
+				tracing::warn!(
+					target: TRACING_TARGET,
+					engine = logging_target,
+					slot = slot_number,
+					error = ?err,
+					"Missed slot, unable to import the authored block",
+				);
+				let reason = format!("Unable to import the block: {:?}", err);
+				recorder.finish(SlotOutcome::Missed(reason.clone()));
+				authorship.capture(&reason);
 
 				telemetry!(CONSENSUS_WARN; "slots.err_with_block_built_on";
 					"hash" => ?parent_hash, "err" => ?err,
 				);
+			} else {
+				recorder.finish(SlotOutcome::Authored);
 			}
 			future::ready(Ok(()))
 		}))
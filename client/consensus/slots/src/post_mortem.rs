@@ -0,0 +1,308 @@
+// Copyright 2019-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Post-mortem bundles of the slots in which authoring a block failed or overran the slot.
+//!
+//! A bundle is a JSON file holding the slot, the reason of the failure, the proposer timings,
+//! the inherent data and, if sources are configured, the number of pending transactions and the
+//! open spans. Its path is reported in an `ERROR` event of the [`TRACING_TARGET`].
+//!
+//! The bundles are written on blocking tasks, so that the slot workers don't wait for the disk.
+//! They are named after the number of the bundle, counting from the last one in the directory,
+//! followed by the engine and the slot, e.g. `12-babe-slot-1042.json`. At most [`MAX_BUNDLES`]
+//! bundles are kept, and the inherent data and the open spans are left out of the bundles larger
+//! than [`MAX_BUNDLE_SIZE`].
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use codec::{Decode, Encode};
+use sp_core::{hexdisplay::HexDisplay, traits::SpawnNamed};
+use sp_inherents::{InherentData, InherentIdentifier};
+
+use crate::TRACING_TARGET;
+
+/// Number of bundles kept in the directory, the oldest being removed first.
+pub const MAX_BUNDLES: usize = 64;
+/// Size of a bundle in bytes beyond which its inherent data and open spans are left out.
+pub const MAX_BUNDLE_SIZE: usize = 1024 * 1024;
+
+/// Where to write the post-mortem bundles, and the sources of what they hold beside the slot.
+pub struct PostMortemConfig {
+	/// The directory the bundles are written to.
+	pub directory: PathBuf,
+	/// The number of transactions ready to be included in a block.
+	pub pending_transactions: Option<Box<dyn Fn() -> usize + Send + Sync>>,
+	/// A description of every open span, e.g. `target::name (12ms)`.
+	pub open_spans: Option<Box<dyn Fn() -> Vec<String> + Send + Sync>>,
+}
+
+/// Writes a post-mortem bundle of every slot whose authoring fails or overruns the slot, given to
+/// the slot workers.
+#[derive(Clone)]
+pub struct PostMortem {
+	config: Arc<PostMortemConfig>,
+	spawner: Box<dyn SpawnNamed>,
+}
+
+impl PostMortem {
+	/// Write the bundles as configured by `config`, on blocking tasks of `spawner`.
+	pub fn new(config: PostMortemConfig, spawner: impl SpawnNamed + 'static) -> Self {
+		PostMortem { config: Arc::new(config), spawner: Box::new(spawner) }
+	}
+}
+
+/// The authorship of a slot, as needed by its post-mortem bundle.
+pub(crate) struct Authorship {
+	pub engine: &'static str,
+	pub slot: u64,
+	pub parent: String,
+	pub started: Instant,
+	pub slot_remaining: Duration,
+	pub proposing_remaining: Option<Duration>,
+	/// The inherent data of the proposal, only kept if the bundles are written.
+	pub inherent_data: Option<InherentData>,
+	pub post_mortem: Option<PostMortem>,
+}
+
+impl Authorship {
+	pub fn new(
+		post_mortem: Option<PostMortem>,
+		engine: &'static str,
+		slot: u64,
+		parent: String,
+		slot_remaining: Duration,
+		proposing_remaining: Option<Duration>,
+		inherent_data: &InherentData,
+	) -> Self {
+		Authorship {
+			engine,
+			slot,
+			parent,
+			started: Instant::now(),
+			slot_remaining,
+			proposing_remaining,
+			inherent_data: post_mortem.as_ref().map(|_| inherent_data.clone()),
+			post_mortem,
+		}
+	}
+
+	/// Write the bundle of the slot, failed for `reason`, if the bundles are written.
+	pub fn capture(&self, reason: &str) {
+		let post_mortem = match &self.post_mortem {
+			Some(post_mortem) => post_mortem,
+			None => return,
+		};
+		let bundle = self.bundle(reason, &post_mortem.config);
+		let directory = post_mortem.config.directory.clone();
+		let (engine, slot, reason) = (self.engine, self.slot, reason.to_owned());
+		post_mortem.spawner.spawn_blocking("authorship-post-mortem", Box::pin(async move {
+			match write_bundle(&directory, engine, slot, bundle) {
+				Ok(path) => tracing::error!(
+					target: TRACING_TARGET,
+					engine,
+					slot,
+					reason = reason.as_str(),
+					path = %path.display(),
+					"Authorship post-mortem written",
+				),
+				Err(e) => log::warn!(
+					target: engine,
+					"Unable to write the post-mortem of slot {}: {}",
+					slot,
+					e,
+				),
+			}
+		}));
+	}
+
+	fn bundle(&self, reason: &str, config: &PostMortemConfig) -> serde_json::Value {
+		let inherent_data = self.inherent_data.as_ref()
+			.and_then(|data| BTreeMap::<InherentIdentifier, Vec<u8>>::decode(&mut &data.encode()[..]).ok())
+			.unwrap_or_default()
+			.into_iter()
+			.map(|(id, value)| (
+				String::from_utf8_lossy(&id).into_owned(),
+				format!("0x{}", HexDisplay::from(&value)).into(),
+			))
+			.collect::<serde_json::Map<_, _>>();
+		serde_json::json!({
+			"engine": self.engine,
+			"slot": self.slot,
+			"parent": self.parent,
+			"reason": reason,
+			"time": SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64,
+			"timings": {
+				"elapsedMs": self.started.elapsed().as_millis() as u64,
+				"slotRemainingMs": self.slot_remaining.as_millis() as u64,
+				"proposingRemainingMs": self.proposing_remaining.map(|d| d.as_millis() as u64),
+			},
+			"inherentData": inherent_data,
+			"pendingTransactions": config.pending_transactions.as_ref().map(|f| f()),
+			"openSpans": config.open_spans.as_ref().map(|f| f()),
+		})
+	}
+}
+
+/// Write the bundle of a slot in `directory`, then remove the oldest bundles beyond
+/// [`MAX_BUNDLES`].
+fn write_bundle(
+	directory: &Path,
+	engine: &str,
+	slot: u64,
+	mut bundle: serde_json::Value,
+) -> io::Result<PathBuf> {
+	let mut content = serde_json::to_vec_pretty(&bundle)?;
+	if content.len() > MAX_BUNDLE_SIZE {
+		if let Some(bundle) = bundle.as_object_mut() {
+			bundle.remove("inherentData");
+			bundle.remove("openSpans");
+			bundle.insert("truncated".into(), true.into());
+		}
+		content = serde_json::to_vec_pretty(&bundle)?;
+	}
+	if content.len() > MAX_BUNDLE_SIZE {
+		return Err(io::Error::new(io::ErrorKind::InvalidData, "the bundle is too large"));
+	}
+	fs::create_dir_all(directory)?;
+	let mut bundles = bundles(directory)?;
+	let number = bundles.iter().map(|(number, _)| number + 1).max().unwrap_or(0);
+	let path = directory.join(format!("{}-{}-slot-{}.json", number, engine, slot));
+	fs::write(&path, content)?;
+	bundles.push((number, path.clone()));
+	if bundles.len() > MAX_BUNDLES {
+		bundles.sort();
+		for (_, path) in &bundles[..bundles.len() - MAX_BUNDLES] {
+			fs::remove_file(path)?;
+		}
+	}
+	Ok(path)
+}
+
+/// The bundles of `directory`, with their number.
+fn bundles(directory: &Path) -> io::Result<Vec<(u64, PathBuf)>> {
+	let mut bundles = Vec::new();
+	for entry in fs::read_dir(directory)? {
+		let path = entry?.path();
+		let name = match path.file_name().and_then(|name| name.to_str()) {
+			Some(name) if name.ends_with(".json") && name.contains("-slot-") => name,
+			_ => continue,
+		};
+		if let Some(number) = name.split('-').next().and_then(|number| number.parse().ok()) {
+			bundles.push((number, path));
+		}
+	}
+	Ok(bundles)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use futures::future::BoxFuture;
+
+	/// Runs the tasks right away.
+	#[derive(Clone)]
+	struct Inline;
+
+	impl SpawnNamed for Inline {
+		fn spawn_blocking(&self, _: &'static str, future: BoxFuture<'static, ()>) {
+			futures::executor::block_on(future)
+		}
+
+		fn spawn(&self, _: &'static str, future: BoxFuture<'static, ()>) {
+			futures::executor::block_on(future)
+		}
+	}
+
+	fn authorship(post_mortem: &PostMortem, slot: u64, inherent_data: &InherentData) -> Authorship {
+		Authorship::new(
+			Some(post_mortem.clone()),
+			"test",
+			slot,
+			"0x00".into(),
+			Duration::from_secs(1),
+			None,
+			inherent_data,
+		)
+	}
+
+	fn read_bundle(directory: &Path, slot: u64) -> serde_json::Value {
+		let path = directory.join(format!("0-test-slot-{}.json", slot));
+		serde_json::from_slice(&fs::read(path).unwrap()).unwrap()
+	}
+
+	#[test]
+	fn bundle_is_written_on_capture() {
+		let directory = tempfile::tempdir().unwrap();
+		let post_mortem = PostMortem::new(PostMortemConfig {
+			directory: directory.path().to_owned(),
+			pending_transactions: Some(Box::new(|| 3)),
+			open_spans: None,
+		}, Inline);
+		let mut inherent_data = InherentData::new();
+		inherent_data.put_data(*b"timstap0", &42u64).unwrap();
+		authorship(&post_mortem, 7, &inherent_data).capture("Block production took too long");
+
+		let bundle = read_bundle(directory.path(), 7);
+		assert_eq!(bundle["reason"], "Block production took too long");
+		assert_eq!(bundle["pendingTransactions"], 3);
+		assert_eq!(bundle["inherentData"]["timstap0"], "0x2a00000000000000");
+		assert!(bundle["openSpans"].is_null());
+	}
+
+	#[test]
+	fn large_bundles_are_truncated() {
+		let directory = tempfile::tempdir().unwrap();
+		let post_mortem = PostMortem::new(PostMortemConfig {
+			directory: directory.path().to_owned(),
+			pending_transactions: None,
+			open_spans: Some(Box::new(|| vec!["a".repeat(MAX_BUNDLE_SIZE)])),
+		}, Inline);
+		authorship(&post_mortem, 7, &InherentData::new()).capture("Unable to propose");
+
+		let bundle = read_bundle(directory.path(), 7);
+		assert_eq!(bundle["reason"], "Unable to propose");
+		assert_eq!(bundle["truncated"], true);
+		assert!(bundle["openSpans"].is_null());
+	}
+
+	#[test]
+	fn oldest_bundles_are_removed() {
+		let directory = tempfile::tempdir().unwrap();
+		fs::write(directory.path().join("notes.txt"), b"kept").unwrap();
+		let post_mortem = PostMortem::new(PostMortemConfig {
+			directory: directory.path().to_owned(),
+			pending_transactions: None,
+			open_spans: None,
+		}, Inline);
+		// The slots are in the reverse order of the bundles.
+		for slot in (0..MAX_BUNDLES as u64 + 2).rev() {
+			authorship(&post_mortem, slot, &InherentData::new()).capture("Unable to propose");
+		}
+
+		let files = fs::read_dir(directory.path()).unwrap().count();
+		assert_eq!(files, MAX_BUNDLES + 1);
+		assert!(!directory.path().join("0-test-slot-65.json").exists());
+		assert!(!directory.path().join("1-test-slot-64.json").exists());
+		assert!(directory.path().join("2-test-slot-63.json").exists());
+		assert!(directory.path().join("65-test-slot-0.json").exists());
+		assert!(directory.path().join("notes.txt").exists());
+	}
+}
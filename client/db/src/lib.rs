@@ -38,6 +38,7 @@ mod children;
 mod cache;
 mod changes_tries_storage;
 mod storage_cache;
+mod trace_summaries;
 #[cfg(any(feature = "with-kvdb-rocksdb", test))]
 mod upgrade;
 mod utils;
@@ -54,7 +55,7 @@ use std::collections::{HashMap, HashSet};
 
 use sc_client_api::{
 	UsageInfo, MemoryInfo, IoInfo, MemorySize,
-	backend::{NewBlockState, PrunableStateChangesTrieStorage, ProvideChtRoots},
+	backend::{NewBlockState, PrunableStateChangesTrieStorage, ProvideChtRoots, TraceSummaryStorage},
 	leaves::{LeafSet, FinalizationDisplaced}, cht,
 };
 use sp_blockchain::{
@@ -81,6 +82,7 @@ use sp_state_machine::{
 };
 use crate::utils::{DatabaseType, Meta, meta_keys, read_db, read_meta};
 use crate::changes_tries_storage::{DbChangesTrieStorage, DbChangesTrieStorageTransaction};
+use crate::trace_summaries::DbTraceSummaryStorage;
 use sc_state_db::StateDb;
 use sp_blockchain::{CachedHeaderMetadata, HeaderMetadata, HeaderMetadataCache};
 use crate::storage_cache::{CachingState, SyncingCachingState, SharedCache, new_shared_cache};
@@ -342,6 +344,8 @@ pub(crate) mod columns {
 	/// Offchain workers local storage
 	pub const OFFCHAIN: u32 = 9;
 	pub const CACHE: u32 = 10;
+	/// Trace summaries of the imported blocks
+	pub const TRACE_SUMMARIES: u32 = 11;
 }
 
 struct PendingBlock<Block: BlockT> {
@@ -837,6 +841,7 @@ pub struct Backend<Block: BlockT> {
 	storage: Arc<StorageDb<Block>>,
 	offchain_storage: offchain::LocalStorage,
 	changes_tries_storage: DbChangesTrieStorage<Block>,
+	trace_summary_storage: Arc<DbTraceSummaryStorage<Block>>,
 	blockchain: BlockchainDb<Block>,
 	canonicalization_delay: u64,
 	shared_cache: SharedCache<Block>,
@@ -892,6 +897,7 @@ impl<Block: BlockT> Backend<Block> {
 			prefix_keys: !config.source.supports_ref_counting(),
 		};
 		let offchain_storage = offchain::LocalStorage::new(db.clone());
+		let trace_summary_storage = Arc::new(DbTraceSummaryStorage::new(db.clone()));
 		let changes_tries_storage = DbChangesTrieStorage::new(
 			db,
 			blockchain.header_metadata_cache.clone(),
@@ -912,6 +918,7 @@ impl<Block: BlockT> Backend<Block> {
 			storage: Arc::new(storage_db),
 			offchain_storage,
 			changes_tries_storage,
+			trace_summary_storage,
 			blockchain,
 			canonicalization_delay,
 			shared_cache: new_shared_cache(
@@ -1519,6 +1526,10 @@ impl<Block: BlockT> sc_client_api::backend::Backend<Block> for Backend<Block> {
 		Some(self.offchain_storage.clone())
 	}
 
+	fn trace_summary_storage(&self) -> Option<Arc<dyn TraceSummaryStorage<Block>>> {
+		Some(self.trace_summary_storage.clone())
+	}
+
 	fn usage_info(&self) -> Option<UsageInfo> {
 		let (io_stats, state_stats) = self.io_stats.take_or_else(||
 			(
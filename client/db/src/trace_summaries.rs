@@ -0,0 +1,259 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2017-2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! DB-backed storage of the trace summaries of the imported blocks.
+//!
+//! The summaries are stored in a column of their own, by block hash, along with the hashes of the
+//! blocks with a summary by block number, and the lowest and highest numbers with a summary, so
+//! that the summaries of a range of heights can be pruned and the most recent ones listed.
+
+use std::{collections::BTreeMap, marker::PhantomData, sync::Arc};
+
+use codec::{Decode, Encode};
+use sc_client_api::backend::{TouchedKeys, TraceSummaryStorage};
+use sp_runtime::traits::{Block as BlockT, NumberFor};
+
+use crate::{columns, Database, DbHash, Transaction};
+use crate::utils::number_index_key;
+
+/// Name of the column the summaries are stored in, in the reports of the keys touched.
+const COLUMN_NAME: &str = "trace_summaries";
+/// Prefix of the keys of the summaries, followed by the block hash.
+const SUMMARY_PREFIX: &[u8] = b"trace_summary";
+/// Prefix of the keys of the hashes of the blocks with a summary, followed by the block number.
+const NUMBER_PREFIX: &[u8] = b"trace_summary_number";
+/// Key of the lowest and highest block numbers with a summary.
+const BOUNDS_KEY: &[u8] = b"trace_summary_bounds";
+
+fn summary_key(hash: &[u8]) -> Vec<u8> {
+	[SUMMARY_PREFIX, hash].concat()
+}
+
+fn number_key(number: u32) -> sp_blockchain::Result<Vec<u8>> {
+	Ok([NUMBER_PREFIX, &number_index_key(number)?[..]].concat())
+}
+
+fn to_u32<N: std::convert::TryInto<u32>>(number: N) -> sp_blockchain::Result<u32> {
+	number.try_into().map_err(|_|
+		sp_blockchain::Error::Backend("Block number cannot be converted to u32".into())
+	)
+}
+
+/// Trace summaries storage, in the `TRACE_SUMMARIES` column.
+pub struct DbTraceSummaryStorage<Block: BlockT> {
+	db: Arc<dyn Database<DbHash>>,
+	_phantom: PhantomData<Block>,
+}
+
+impl<Block: BlockT> DbTraceSummaryStorage<Block> {
+	/// Create trace summaries storage with given database.
+	pub fn new(db: Arc<dyn Database<DbHash>>) -> Self {
+		DbTraceSummaryStorage { db, _phantom: PhantomData }
+	}
+
+	fn decode<T: Decode>(&self, key: &[u8], what: &str) -> sp_blockchain::Result<Option<T>> {
+		self.db.get(columns::TRACE_SUMMARIES, key)
+			.map(|value| Decode::decode(&mut &value[..]).map_err(|e| sp_blockchain::Error::Backend(
+				format!("Error decoding the trace summaries {}: {}", what, e)
+			)))
+			.transpose()
+	}
+
+	fn hashes(&self, number: u32) -> sp_blockchain::Result<Vec<Block::Hash>> {
+		Ok(self.decode(&number_key(number)?, &format!("of block {}", number))?.unwrap_or_default())
+	}
+
+	/// The lowest and highest block numbers with a summary, if any.
+	fn bounds(&self) -> sp_blockchain::Result<Option<(u32, u32)>> {
+		self.decode(BOUNDS_KEY, "bounds")
+	}
+}
+
+impl<Block: BlockT> TraceSummaryStorage<Block> for DbTraceSummaryStorage<Block> {
+	fn insert_summary(
+		&self,
+		hash: Block::Hash,
+		number: NumberFor<Block>,
+		summary: &[u8],
+	) -> sp_blockchain::Result<()> {
+		let number = to_u32(number)?;
+		let mut hashes = self.hashes(number)?;
+		let mut transaction = Transaction::new();
+		if !hashes.contains(&hash) {
+			hashes.push(hash);
+			transaction.set_from_vec(columns::TRACE_SUMMARIES, &number_key(number)?, hashes.encode());
+		}
+		let bounds = match self.bounds()? {
+			Some((lowest, highest)) => (lowest.min(number), highest.max(number)),
+			None => (number, number),
+		};
+		transaction.set_from_vec(columns::TRACE_SUMMARIES, BOUNDS_KEY, bounds.encode());
+		transaction.set(columns::TRACE_SUMMARIES, &summary_key(hash.as_ref()), summary);
+		Ok(self.db.commit(transaction)?)
+	}
+
+	fn prune_summaries(&self, number: NumberFor<Block>) -> sp_blockchain::Result<()> {
+		let number = to_u32(number)?;
+		let (lowest, highest) = match self.bounds()? {
+			Some((lowest, highest)) if lowest <= number => (lowest, highest),
+			_ => return Ok(()),
+		};
+		let mut transaction = Transaction::new();
+		for pruned in lowest..=number.min(highest) {
+			for hash in self.hashes(pruned)? {
+				transaction.remove(columns::TRACE_SUMMARIES, &summary_key(hash.as_ref()));
+			}
+			transaction.remove(columns::TRACE_SUMMARIES, &number_key(pruned)?);
+		}
+		if number >= highest {
+			transaction.remove(columns::TRACE_SUMMARIES, BOUNDS_KEY);
+		} else {
+			transaction.set_from_vec(columns::TRACE_SUMMARIES, BOUNDS_KEY, (number + 1, highest).encode());
+		}
+		Ok(self.db.commit(transaction)?)
+	}
+
+	fn summary(&self, hash: &Block::Hash) -> sp_blockchain::Result<Option<Vec<u8>>> {
+		Ok(self.db.get(columns::TRACE_SUMMARIES, &summary_key(hash.as_ref())))
+	}
+
+	fn recent_summaries(&self, count: usize) -> sp_blockchain::Result<Vec<Vec<u8>>> {
+		let mut summaries = Vec::new();
+		let (lowest, highest) = match self.bounds()? {
+			Some(bounds) => bounds,
+			None => return Ok(summaries),
+		};
+		for number in (lowest..=highest).rev() {
+			for hash in self.hashes(number)?.iter().rev() {
+				if summaries.len() == count {
+					return Ok(summaries);
+				}
+				summaries.extend(self.summary(hash)?);
+			}
+		}
+		Ok(summaries)
+	}
+
+	fn revert_summaries(
+		&self,
+		blocks: &[(Block::Hash, NumberFor<Block>)],
+	) -> sp_blockchain::Result<Vec<TouchedKeys>> {
+		let mut transaction = Transaction::new();
+		let (mut hash_keys, mut number_keys) = (Vec::new(), Vec::new());
+		let mut by_number = BTreeMap::<_, Vec<_>>::new();
+		for (hash, number) in blocks {
+			by_number.entry(to_u32(*number)?).or_default().push(hash.clone());
+		}
+		for (number, reverted) in by_number {
+			let (removed, kept): (Vec<_>, Vec<_>) = self.hashes(number)?.into_iter()
+				.partition(|hash| reverted.contains(hash));
+			if removed.is_empty() {
+				continue;
+			}
+			for hash in removed {
+				let key = summary_key(hash.as_ref());
+				transaction.remove(columns::TRACE_SUMMARIES, &key);
+				hash_keys.push(key);
+			}
+			let key = number_key(number)?;
+			if kept.is_empty() {
+				transaction.remove(columns::TRACE_SUMMARIES, &key);
+			} else {
+				transaction.set_from_vec(columns::TRACE_SUMMARIES, &key, kept.encode());
+			}
+			number_keys.push(key);
+		}
+		self.db.commit(transaction)?;
+		Ok(vec![
+			TouchedKeys::new(COLUMN_NAME, "block number", number_keys),
+			TouchedKeys::new(COLUMN_NAME, "block hash", hash_keys),
+		].into_iter().flatten().collect())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use sp_core::{H256, hexdisplay::HexDisplay};
+	use super::*;
+	use crate::tests::Block;
+
+	#[test]
+	fn prunes_the_summaries_of_every_block_up_to_a_number() {
+		let db = sp_database::as_database(kvdb_memorydb::create(crate::utils::NUM_COLUMNS));
+		let storage = DbTraceSummaryStorage::<Block>::new(db);
+		let (a, b, c, d) = (H256::repeat_byte(1), H256::repeat_byte(2), H256::repeat_byte(3), H256::repeat_byte(4));
+		storage.insert_summary(a, 1, b"a").unwrap();
+		storage.insert_summary(b, 1, b"b").unwrap();
+		storage.insert_summary(b, 1, b"b2").unwrap();
+		storage.insert_summary(c, 2, b"c").unwrap();
+		storage.insert_summary(d, 5, b"d").unwrap();
+		assert_eq!(storage.summary(&b).unwrap(), Some(b"b2".to_vec()));
+		assert_eq!(storage.recent_summaries(3).unwrap(), vec![b"d".to_vec(), b"c".to_vec(), b"b2".to_vec()]);
+
+		// Heights 1 and 2 are both pruned, although 3 has no summary.
+		storage.prune_summaries(3).unwrap();
+		assert_eq!(storage.summary(&a).unwrap(), None);
+		assert_eq!(storage.summary(&b).unwrap(), None);
+		assert_eq!(storage.summary(&c).unwrap(), None);
+		assert_eq!(storage.summary(&d).unwrap(), Some(b"d".to_vec()));
+		assert!(storage.hashes(1).unwrap().is_empty());
+		assert_eq!(storage.bounds().unwrap(), Some((4, 5)));
+		assert_eq!(storage.recent_summaries(3).unwrap(), vec![b"d".to_vec()]);
+
+		storage.prune_summaries(5).unwrap();
+		assert_eq!(storage.bounds().unwrap(), None);
+		assert!(storage.recent_summaries(3).unwrap().is_empty());
+	}
+
+	#[test]
+	fn reverts_summaries_of_the_reverted_blocks_only() {
+		let db = sp_database::as_database(kvdb_memorydb::create(crate::utils::NUM_COLUMNS));
+		let storage = DbTraceSummaryStorage::<Block>::new(db);
+		let (a, b, c) = (H256::repeat_byte(1), H256::repeat_byte(2), H256::repeat_byte(3));
+		storage.insert_summary(a, 1, b"a").unwrap();
+		storage.insert_summary(b, 1, b"b").unwrap();
+		storage.insert_summary(c, 2, b"c").unwrap();
+
+		let touched = storage.revert_summaries(&[(a, 1), (c, 2), (H256::repeat_byte(4), 3)]).unwrap();
+		assert_eq!(touched, vec![
+			TouchedKeys {
+				column: COLUMN_NAME,
+				kind: "block number",
+				first: number_key(1).unwrap(),
+				last: number_key(2).unwrap(),
+				count: 2,
+			},
+			TouchedKeys {
+				column: COLUMN_NAME,
+				kind: "block hash",
+				first: summary_key(a.as_ref()),
+				last: summary_key(c.as_ref()),
+				count: 2,
+			},
+		]);
+		assert_eq!(
+			touched[0].to_string(),
+			format!("column trace_summaries: 2 block number keys in 0x{0}00000001..=0x{0}00000002", HexDisplay::from(&NUMBER_PREFIX)),
+		);
+		assert_eq!(storage.summary(&a).unwrap(), None);
+		assert_eq!(storage.summary(&b).unwrap(), Some(b"b".to_vec()));
+		assert_eq!(storage.summary(&c).unwrap(), None);
+		assert_eq!(storage.hashes(1).unwrap(), vec![b]);
+		assert!(storage.hashes(2).unwrap().is_empty());
+	}
+}
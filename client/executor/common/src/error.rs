@@ -21,6 +21,8 @@
 use sp_serializer;
 use wasmi;
 
+use crate::trap::TrapReport;
+
 /// Result type alias.
 pub type Result<T> = std::result::Result<T, Error>;
 
@@ -81,6 +83,20 @@ pub enum Error {
 	/// Execution of a host function failed.
 	#[display(fmt="Host function {} execution failed with: {}", _0, _1)]
 	FunctionExecution(String, String),
+	/// The runtime trapped, with what it was doing.
+	#[display(fmt="{}", _0)]
+	#[from(ignore)]
+	WasmTrap(Box<TrapReport>),
+	/// The execution failed, e.g. trapped, within the spans of the runtime, see
+	/// `sp_tracing::span_context`.
+	#[display(fmt="{} (in spans: {})", error, spans)]
+	#[from(ignore)]
+	InSpans {
+		/// The error of the execution.
+		error: Box<Error>,
+		/// The spans the runtime was in.
+		spans: String,
+	},
 }
 
 impl std::error::Error for Error {
@@ -89,6 +105,7 @@ impl std::error::Error for Error {
 			Error::InvalidData(ref err) => Some(err),
 			Error::Trap(ref err) => Some(err),
 			Error::Wasmi(ref err) => Some(err),
+			Error::InSpans { ref error, .. } => Some(&**error),
 			_ => None,
 		}
 	}
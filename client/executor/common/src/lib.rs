@@ -19,6 +19,8 @@
 #![warn(missing_docs)]
 
 pub mod error;
+pub mod metrics;
 pub mod sandbox;
+pub mod trap;
 pub mod util;
 pub mod wasm_runtime;
@@ -0,0 +1,99 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Statistics of the pools of runtime instances, shared by all the execution engines.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+static CREATED: AtomicU64 = AtomicU64::new(0);
+static REUSED: AtomicU64 = AtomicU64::new(0);
+static EXHAUSTED: AtomicU64 = AtomicU64::new(0);
+static MEMORY_RESETS: AtomicU64 = AtomicU64::new(0);
+static MEMORY_RESET_NANOS: AtomicU64 = AtomicU64::new(0);
+
+/// The use of the pools of runtime instances since the node started.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct InstancePoolStats {
+	/// Number of instances created into a free slot of a pool.
+	pub created: u64,
+	/// Number of calls made on an instance taken back from a pool.
+	pub reused: u64,
+	/// Number of calls made while every instance of the pool was busy, on an instance created
+	/// for the call and discarded after it.
+	pub exhausted: u64,
+	/// Number of times the memory and globals of an instance were reset before a call.
+	pub memory_resets: u64,
+	/// Total time spent resetting the memory and globals of the instances.
+	pub memory_reset_time: Duration,
+}
+
+/// The statistics of the pools of runtime instances.
+pub fn instance_pool_stats() -> InstancePoolStats {
+	InstancePoolStats {
+		created: CREATED.load(Ordering::Relaxed),
+		reused: REUSED.load(Ordering::Relaxed),
+		exhausted: EXHAUSTED.load(Ordering::Relaxed),
+		memory_resets: MEMORY_RESETS.load(Ordering::Relaxed),
+		memory_reset_time: Duration::from_nanos(MEMORY_RESET_NANOS.load(Ordering::Relaxed)),
+	}
+}
+
+/// Account an instance created into a free slot of a pool.
+pub fn instance_created() {
+	CREATED.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Account a call made on an instance taken back from a pool.
+pub fn instance_reused() {
+	REUSED.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Account a call made while every instance of a pool was busy.
+pub fn pool_exhausted() {
+	EXHAUSTED.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Reset the memory and globals of an instance with `reset`, accounting the time it takes.
+pub fn memory_reset<R>(reset: impl FnOnce() -> R) -> R {
+	let started = Instant::now();
+	let result = reset();
+	let elapsed = started.elapsed().as_nanos() as u64;
+	MEMORY_RESETS.fetch_add(1, Ordering::Relaxed);
+	MEMORY_RESET_NANOS.fetch_add(elapsed, Ordering::Relaxed);
+	result
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn memory_resets_are_accounted() {
+		let before = instance_pool_stats();
+		let result = memory_reset(|| {
+			std::thread::sleep(Duration::from_millis(1));
+			42
+		});
+		let after = instance_pool_stats();
+
+		assert_eq!(result, 42);
+		assert!(after.memory_resets > before.memory_resets);
+		assert!(after.memory_reset_time - before.memory_reset_time >= Duration::from_millis(1));
+	}
+}
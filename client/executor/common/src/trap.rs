@@ -0,0 +1,162 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Diagnostics of the traps of the runtime, shared by all the execution engines.
+//!
+//! The engines record the host functions the runtime calls with [`record_host_call`], the last
+//! [`MAX_HOST_CALLS`] of them being kept per thread, and report a trap as a [`TrapReport`] with
+//! the wasm backtrace they have, if any, and the host calls of the call that trapped.
+
+use std::{cell::RefCell, fmt};
+
+/// Number of host calls kept for the reports.
+pub const MAX_HOST_CALLS: usize = 16;
+
+/// A frame of the wasm backtrace of a trap.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WasmFrame {
+	/// Index of the function in the module.
+	pub func_index: u32,
+	/// Name of the function, from the name section of the module, demangled.
+	pub func_name: Option<String>,
+	/// Offset of the instruction in the module.
+	pub module_offset: usize,
+}
+
+/// What the runtime was doing when it trapped.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TrapReport {
+	/// Why the runtime trapped, e.g. `wasm trap: unreachable`.
+	pub message: String,
+	/// The frames of the wasm stack, the innermost first, if the engine knows them.
+	pub backtrace: Vec<WasmFrame>,
+	/// The spans the runtime was in, see `sp_tracing::span_context`.
+	pub spans: Option<String>,
+	/// The last host functions the call into the runtime called, the most recent last.
+	pub host_calls: Vec<&'static str>,
+}
+
+impl TrapReport {
+	/// The report of a trap with `message` and `backtrace`, and the host calls recorded since
+	/// the call started.
+	pub fn new(message: impl Into<String>, backtrace: Vec<WasmFrame>) -> Self {
+		TrapReport { message: message.into(), backtrace, spans: None, host_calls: host_calls() }
+	}
+}
+
+impl fmt::Display for TrapReport {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "Wasm execution trapped: {}", self.message)?;
+		if !self.backtrace.is_empty() {
+			write!(f, ", wasm backtrace: ")?;
+			for (i, frame) in self.backtrace.iter().enumerate() {
+				let separator = if i == 0 { "" } else { " < " };
+				match &frame.func_name {
+					Some(name) => write!(f, "{}{}", separator, name)?,
+					None => write!(f, "{}<wasm function {}>", separator, frame.func_index)?,
+				}
+			}
+		}
+		if let Some(spans) = &self.spans {
+			write!(f, ", in spans: {}", spans)?;
+		}
+		if !self.host_calls.is_empty() {
+			write!(f, ", last host calls: {}", self.host_calls.join(", "))?;
+		}
+		Ok(())
+	}
+}
+
+#[derive(Default)]
+struct HostCalls {
+	calls: [&'static str; MAX_HOST_CALLS],
+	/// Number of calls recorded, the last `MAX_HOST_CALLS` being kept.
+	len: usize,
+}
+
+thread_local! {
+	static HOST_CALLS: RefCell<HostCalls> = RefCell::new(HostCalls::default());
+}
+
+/// Record a call of the runtime to the host function `name`.
+pub fn record_host_call(name: &'static str) {
+	HOST_CALLS.with(|host_calls| {
+		let mut host_calls = host_calls.borrow_mut();
+		let next = host_calls.len % MAX_HOST_CALLS;
+		host_calls.calls[next] = name;
+		host_calls.len += 1;
+	});
+}
+
+/// Forget the host calls recorded, when a call into the runtime starts.
+pub fn clear_host_calls() {
+	HOST_CALLS.with(|host_calls| host_calls.borrow_mut().len = 0);
+}
+
+/// The last host calls recorded on this thread, the most recent last.
+pub fn host_calls() -> Vec<&'static str> {
+	HOST_CALLS.with(|host_calls| {
+		let host_calls = host_calls.borrow();
+		let kept = host_calls.len.min(MAX_HOST_CALLS);
+		(host_calls.len - kept..host_calls.len)
+			.map(|i| host_calls.calls[i % MAX_HOST_CALLS])
+			.collect()
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn keeps_the_last_host_calls() {
+		clear_host_calls();
+		record_host_call("ext_storage_get_version_1");
+		assert_eq!(host_calls(), vec!["ext_storage_get_version_1"]);
+
+		let names = (0..MAX_HOST_CALLS + 2).map(|i| &*Box::leak(format!("ext_{}", i).into_boxed_str()))
+			.collect::<Vec<_>>();
+		for name in &names {
+			record_host_call(name);
+		}
+		assert_eq!(host_calls(), names[2..].to_vec());
+
+		clear_host_calls();
+		assert!(host_calls().is_empty());
+	}
+
+	#[test]
+	fn formats_the_report() {
+		let report = TrapReport {
+			message: "wasm trap: unreachable".into(),
+			backtrace: vec![
+				WasmFrame { func_index: 7, func_name: Some("rust_begin_unwind".into()), module_offset: 0x10 },
+				WasmFrame { func_index: 3, func_name: None, module_offset: 0x20 },
+			],
+			spans: Some("frame_executive::apply_extrinsic > pallet_balances::transfer".into()),
+			host_calls: vec!["ext_storage_get_version_1", "ext_misc_print_utf8_version_1"],
+		};
+		assert_eq!(
+			report.to_string(),
+			"Wasm execution trapped: wasm trap: unreachable, \
+			wasm backtrace: rust_begin_unwind < <wasm function 3>, \
+			in spans: frame_executive::apply_extrinsic > pallet_balances::transfer, \
+			last host calls: ext_storage_get_version_1, ext_misc_print_utf8_version_1",
+		);
+	}
+}
@@ -0,0 +1,253 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2017-2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Hooks letting a fuzzing harness perturb the responses of the host calls of the runtime.
+//!
+//! A harness re-executes recorded blocks in wasm with an executor built
+//! `with_host_call_hooks`, registering a [`HostCallFuzzerExt`] in the externalities of the
+//! executions to perturb. The perturbed responses are traced as events of the
+//! [`HOST_CALL_FUZZ_TARGET`], so that the traces of the executions can be compared with the
+//! traces of the recorded blocks.
+
+use sp_externalities::ExternalitiesExt;
+use sp_wasm_interface::{Function, FunctionContext, Result, Signature, Value};
+
+/// Target of the events of the perturbed host calls.
+pub const HOST_CALL_FUZZ_TARGET: &str = "host_call_fuzz";
+
+/// Perturbs the responses of the host calls of the runtime.
+pub trait HostCallFuzzer: Send + Sync {
+	/// The response of the host function `name` called with `args`, instead of its `result`.
+	///
+	/// The responses pointing in the memory of the runtime, e.g. the values read from the
+	/// storage, are perturbed by writing to the memory through `context`.
+	fn perturb(
+		&self,
+		name: &str,
+		args: &[Value],
+		result: Option<Value>,
+		context: &mut dyn FunctionContext,
+	) -> Result<Option<Value>>;
+}
+
+sp_externalities::decl_extension! {
+	/// Extension perturbing the host calls of the executions it is registered in, if the executor
+	/// was built `with_host_call_hooks`.
+	pub struct HostCallFuzzerExt(Box<dyn HostCallFuzzer>);
+}
+
+impl HostCallFuzzerExt {
+	/// Perturb the host calls with `fuzzer`.
+	pub fn new(fuzzer: impl HostCallFuzzer + 'static) -> Self {
+		HostCallFuzzerExt(Box::new(fuzzer))
+	}
+}
+
+/// A host function whose responses are perturbed by the `HostCallFuzzerExt`, if any.
+struct HookedFunction(&'static dyn Function);
+
+impl Function for HookedFunction {
+	fn name(&self) -> &str {
+		self.0.name()
+	}
+
+	fn signature(&self) -> Signature {
+		self.0.signature()
+	}
+
+	fn execute(
+		&self,
+		context: &mut dyn FunctionContext,
+		args: &mut dyn Iterator<Item = Value>,
+	) -> Result<Option<Value>> {
+		let args: Vec<_> = args.collect();
+		let result = self.0.execute(context, &mut args.iter().cloned())?;
+		let perturbed = sp_externalities::with_externalities(|mut ext| {
+			ext.extension::<HostCallFuzzerExt>()
+				.map(|fuzzer| fuzzer.perturb(self.name(), &args, result, context))
+		}).flatten().transpose()?;
+		match perturbed {
+			Some(perturbed) => {
+				if perturbed != result {
+					tracing::debug!(
+						target: HOST_CALL_FUZZ_TARGET,
+						function = self.name(),
+						result = ?result,
+						perturbed = ?perturbed,
+					);
+				}
+				Ok(perturbed)
+			},
+			None => Ok(result),
+		}
+	}
+}
+
+/// Wrap the `host_functions` with the hooks of the `HostCallFuzzerExt`.
+///
+/// As the host functions are `'static`, the wrappers are leaked: the executors with hooks are
+/// meant to be built once by a harness.
+pub(crate) fn hook_host_functions(
+	host_functions: &[&'static dyn Function],
+) -> Vec<&'static dyn Function> {
+	host_functions.iter()
+		.map(|function| &*Box::leak(Box::new(HookedFunction(*function))) as &'static dyn Function)
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_io::TestExternalities;
+	use sp_wasm_interface::{MemoryId, Pointer, Sandbox, ValueType, WordSize};
+
+	struct Add;
+
+	impl Function for Add {
+		fn name(&self) -> &str {
+			"add"
+		}
+
+		fn signature(&self) -> Signature {
+			Signature::new(&[ValueType::I32, ValueType::I32][..], Some(ValueType::I32))
+		}
+
+		fn execute(
+			&self,
+			_: &mut dyn FunctionContext,
+			args: &mut dyn Iterator<Item = Value>,
+		) -> Result<Option<Value>> {
+			let sum = args.map(|arg| match arg {
+				Value::I32(arg) => arg,
+				_ => 0,
+			}).sum();
+			Ok(Some(Value::I32(sum)))
+		}
+	}
+
+	struct Increment;
+
+	impl HostCallFuzzer for Increment {
+		fn perturb(
+			&self,
+			_: &str,
+			args: &[Value],
+			result: Option<Value>,
+			_: &mut dyn FunctionContext,
+		) -> Result<Option<Value>> {
+			assert_eq!(args, &[Value::I32(1), Value::I32(2)]);
+			Ok(match result {
+				Some(Value::I32(result)) => Some(Value::I32(result + 1)),
+				result => result,
+			})
+		}
+	}
+
+	/// A context without memory nor sandbox, for the host functions not using them.
+	struct NoMemory;
+
+	impl FunctionContext for NoMemory {
+		fn read_memory_into(&self, _: Pointer<u8>, _: &mut [u8]) -> Result<()> {
+			Err("No memory".into())
+		}
+
+		fn write_memory(&mut self, _: Pointer<u8>, _: &[u8]) -> Result<()> {
+			Err("No memory".into())
+		}
+
+		fn allocate_memory(&mut self, _: WordSize) -> Result<Pointer<u8>> {
+			Err("No memory".into())
+		}
+
+		fn deallocate_memory(&mut self, _: Pointer<u8>) -> Result<()> {
+			Err("No memory".into())
+		}
+
+		fn sandbox(&mut self) -> &mut dyn Sandbox {
+			self
+		}
+	}
+
+	impl Sandbox for NoMemory {
+		fn memory_get(
+			&mut self,
+			_: MemoryId,
+			_: WordSize,
+			_: Pointer<u8>,
+			_: WordSize,
+		) -> Result<u32> {
+			Err("No sandbox".into())
+		}
+
+		fn memory_set(
+			&mut self,
+			_: MemoryId,
+			_: WordSize,
+			_: Pointer<u8>,
+			_: WordSize,
+		) -> Result<u32> {
+			Err("No sandbox".into())
+		}
+
+		fn memory_teardown(&mut self, _: MemoryId) -> Result<()> {
+			Err("No sandbox".into())
+		}
+
+		fn memory_new(&mut self, _: u32, _: u32) -> Result<MemoryId> {
+			Err("No sandbox".into())
+		}
+
+		fn invoke(
+			&mut self,
+			_: u32,
+			_: &str,
+			_: &[u8],
+			_: Pointer<u8>,
+			_: WordSize,
+			_: u32,
+		) -> Result<u32> {
+			Err("No sandbox".into())
+		}
+
+		fn instance_teardown(&mut self, _: u32) -> Result<()> {
+			Err("No sandbox".into())
+		}
+
+		fn instance_new(&mut self, _: u32, _: &[u8], _: &[u8], _: u32) -> Result<u32> {
+			Err("No sandbox".into())
+		}
+
+		fn get_global_val(&self, _: u32, _: &str) -> Result<Option<Value>> {
+			Err("No sandbox".into())
+		}
+	}
+
+	#[test]
+	fn perturbs_the_host_calls_with_the_extension() {
+		static ADD: Add = Add;
+		let add = hook_host_functions(&[&ADD])[0];
+		let call = || add.execute(&mut NoMemory, &mut vec![Value::I32(1), Value::I32(2)].into_iter());
+
+		assert_eq!(call(), Ok(Some(Value::I32(3))));
+		TestExternalities::default().execute_with(|| assert_eq!(call(), Ok(Some(Value::I32(3)))));
+
+		let mut ext = TestExternalities::default();
+		ext.register_extension(HostCallFuzzerExt::new(Increment));
+		ext.execute_with(|| assert_eq!(call(), Ok(Some(Value::I32(4)))));
+	}
+}
@@ -90,7 +90,7 @@ fn call_not_existing_function(wasm_method: WasmExecutionMethod) {
 			match wasm_method {
 				WasmExecutionMethod::Interpreted => assert_eq!(
 					&format!("{:?}", e),
-					"\"Trap: Trap { kind: Host(Other(\\\"Function `missing_external` is only a stub. Calling a stub is not allowed.\\\")) }\""
+					"\"Wasm execution trapped: Function `missing_external` is only a stub. Calling a stub is not allowed.\""
 				),
 				#[cfg(feature = "wasmtime")]
 				WasmExecutionMethod::Compiled => assert!(
@@ -118,7 +118,7 @@ fn call_yet_another_not_existing_function(wasm_method: WasmExecutionMethod) {
 			match wasm_method {
 				WasmExecutionMethod::Interpreted => assert_eq!(
 					&format!("{:?}", e),
-					"\"Trap: Trap { kind: Host(Other(\\\"Function `yet_another_missing_external` is only a stub. Calling a stub is not allowed.\\\")) }\""
+					"\"Wasm execution trapped: Function `yet_another_missing_external` is only a stub. Calling a stub is not allowed.\""
 				),
 				#[cfg(feature = "wasmtime")]
 				WasmExecutionMethod::Compiled => assert!(
@@ -160,6 +160,22 @@ fn panicking_should_work(wasm_method: WasmExecutionMethod) {
 	assert!(output.is_err());
 }
 
+#[test_case(WasmExecutionMethod::Interpreted)]
+#[cfg_attr(feature = "wasmtime", test_case(WasmExecutionMethod::Compiled))]
+fn panics_are_reported_with_the_last_host_calls(wasm_method: WasmExecutionMethod) {
+	let mut ext = TestExternalities::default();
+	let mut ext = ext.ext();
+
+	let error = call_in_wasm("test_panic", &[], wasm_method, &mut ext).unwrap_err();
+	// The panic handler logs the panic, then traps.
+	assert!(error.starts_with("Wasm execution trapped: "), "{}", error);
+	assert!(error.ends_with("last host calls: ext_logging_log_version_1"), "{}", error);
+	#[cfg(feature = "wasmtime")]
+	if let WasmExecutionMethod::Compiled = wasm_method {
+		assert!(error.contains("wasm backtrace: "), "{}", error);
+	}
+}
+
 #[test_case(WasmExecutionMethod::Interpreted)]
 #[cfg_attr(feature = "wasmtime", test_case(WasmExecutionMethod::Compiled))]
 fn storage_should_work(wasm_method: WasmExecutionMethod) {
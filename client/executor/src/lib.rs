@@ -31,6 +31,7 @@
 #![warn(missing_docs)]
 #![recursion_limit="128"]
 
+pub mod fuzz;
 #[macro_use]
 mod native_executor;
 mod wasm_runtime;
@@ -46,8 +47,15 @@ pub use sp_core::traits::{Externalities, CallInWasm};
 #[doc(hidden)]
 pub use sp_wasm_interface;
 pub use wasm_runtime::WasmExecutionMethod;
+#[cfg(feature = "wasmtime")]
+pub use sc_executor_wasmtime::set_jitdump as set_wasmtime_jitdump;
+#[cfg(feature = "wasmtime")]
+pub use sc_executor_wasmtime::{
+	CacheStats as WasmtimeCacheStats, cache_stats as wasmtime_cache_stats, set_cache as set_wasmtime_cache,
+};
 
 pub use sc_executor_common::{error, sandbox};
+pub use sc_executor_common::metrics::{InstancePoolStats, instance_pool_stats};
 
 /// Provides runtime information.
 pub trait RuntimeInfo {
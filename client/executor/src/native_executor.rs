@@ -26,6 +26,8 @@ use sp_core::{
 	NativeOrEncoded, traits::{CodeExecutor, Externalities, RuntimeCode, MissingHostFunctions},
 };
 use log::trace;
+use sp_externalities::ExternalitiesExt;
+use sp_io::TracingProxyExt;
 use std::{result, panic::{UnwindSafe, AssertUnwindSafe}, sync::Arc};
 use sp_wasm_interface::{HostFunctions, Function};
 use sc_executor_common::wasm_runtime::WasmInstance;
@@ -36,11 +38,16 @@ const DEFAULT_HEAP_PAGES: u64 = 1024;
 /// Set up the externalities and safe calling environment to execute runtime calls.
 ///
 /// If the inner closure panics, it will be caught and return an error.
-pub fn with_externalities_safe<F, U>(ext: &mut dyn Externalities, f: F) -> Result<U>
+///
+/// The wasm spans the call leaves entered, e.g. because the runtime trapped, are exited when it
+/// returns, so that the spans entered afterwards, e.g. by the fallback to native, are not nested
+/// in them.
+pub fn with_externalities_safe<F, U>(mut ext: &mut dyn Externalities, f: F) -> Result<U>
 	where F: UnwindSafe + FnOnce() -> U
 {
-	sp_externalities::set_and_run_with_externalities(
-		ext,
+	let last_span = ext.extension::<TracingProxyExt>().map_or(0, |proxy| proxy.last_id());
+	let result = sp_externalities::set_and_run_with_externalities(
+		&mut *ext,
 		move || {
 			// Substrate uses custom panic hook that terminates process on panic. Disable
 			// termination for the native call.
@@ -55,7 +62,27 @@ pub fn with_externalities_safe<F, U>(ext: &mut dyn Externalities, f: F) -> Resul
 				}
 			})
 		},
-	)
+	);
+	if let Some(proxy) = ext.extension::<TracingProxyExt>() {
+		proxy.exit_spans_after(last_span);
+	}
+	result
+}
+
+/// Attach the spans the runtime is in to the error of a call that failed, e.g. because it
+/// trapped, so that the error identifies the pallet and call that were executing.
+///
+/// The spans of a trap are part of its report. This is called before the spans the call left
+/// entered are exited by [`with_externalities_safe`].
+fn with_span_context<T>(result: Result<T>) -> Result<T> {
+	result.map_err(|error| match (sp_tracing::span_context(), error) {
+		(Some(spans), Error::WasmTrap(mut report)) => {
+			report.spans = Some(spans);
+			Error::WasmTrap(report)
+		},
+		(Some(spans), error) => Error::InSpans { error: Box::new(error), spans },
+		(None, error) => error,
+	})
 }
 
 /// Delegate for dispatching a CodeExecutor call.
@@ -115,6 +142,17 @@ impl WasmExecutor {
 		}
 	}
 
+	/// Let the `HostCallFuzzerExt` registered in the externalities of an execution, if any,
+	/// perturb the responses of its host calls, see [`crate::fuzz`].
+	pub fn with_host_call_hooks(self) -> Self {
+		WasmExecutor {
+			host_functions: Arc::new(crate::fuzz::hook_host_functions(&self.host_functions)),
+			// The runtimes are instantiated with the host functions.
+			cache: Arc::new(RuntimeCache::new(self.max_runtime_instances)),
+			..self
+		}
+	}
+
 	/// Execute the given closure `f` with the latest runtime (based on `runtime_code`).
 	///
 	/// The closure `f` is expected to return `Err(_)` when there happened a `panic!` in native code
@@ -182,7 +220,7 @@ impl sp_core::traits::CallInWasm for WasmExecutor {
 			self.with_instance(&code, ext, allow_missing_host_functions, |instance, _, mut ext| {
 				with_externalities_safe(
 					&mut **ext,
-					move || instance.call(method, call_data),
+					move || with_span_context(instance.call(method, call_data)),
 				)
 			}).map_err(|e| e.to_string())
 		} else {
@@ -203,7 +241,7 @@ impl sp_core::traits::CallInWasm for WasmExecutor {
 
 			with_externalities_safe(
 				&mut **ext,
-				move || instance.call(method, call_data),
+				move || with_span_context(instance.call(method, call_data)),
 			)
 			.and_then(|r| r)
 			.map_err(|e| e.to_string())
@@ -253,6 +291,11 @@ impl<D: NativeExecutionDispatch> NativeExecutor<D> {
 			wasm: wasm_executor,
 		}
 	}
+
+	/// See [`WasmExecutor::with_host_call_hooks`]. Only the calls executed in wasm are hooked.
+	pub fn with_host_call_hooks(self) -> Self {
+		NativeExecutor { wasm: self.wasm.with_host_call_hooks(), ..self }
+	}
 }
 
 impl<D: NativeExecutionDispatch> RuntimeInfo for NativeExecutor<D> {
@@ -290,6 +333,7 @@ impl<D: NativeExecutionDispatch + 'static> CodeExecutor for NativeExecutor<D> {
 		use_native: bool,
 		native_call: Option<NC>,
 	) -> (Result<NativeOrEncoded<R>>, bool) {
+		sp_tracing::probes::call_enter(method);
 		let mut used_native = false;
 		let result = self.wasm.with_instance(
 			runtime_code,
@@ -311,16 +355,29 @@ impl<D: NativeExecutionDispatch + 'static> CodeExecutor for NativeExecutor<D> {
 							self.native_version.runtime_version,
 							onchain_version,
 						);
+						if use_native {
+							sp_tracing::event!(
+								debug;
+								target: sp_tracing::EXECUTION_TARGET,
+								method,
+								reason = "incompatible_version",
+								native = %self.native_version.runtime_version,
+								chain = %onchain_version,
+								"Native runtime can't be used, falling back to wasm",
+							);
+						}
 
 						with_externalities_safe(
 							&mut **ext,
-							move || instance.call(method, data).map(NativeOrEncoded::Encoded)
+							move || with_span_context(instance.call(method, data))
+								.map(NativeOrEncoded::Encoded)
 						)
 					}
 					(false, _, _) => {
 						with_externalities_safe(
 							&mut **ext,
-							move || instance.call(method, data).map(NativeOrEncoded::Encoded)
+							move || with_span_context(instance.call(method, data))
+								.map(NativeOrEncoded::Encoded)
 						)
 					},
 					(true, true, Some(call)) => {
@@ -355,6 +412,7 @@ impl<D: NativeExecutionDispatch + 'static> CodeExecutor for NativeExecutor<D> {
 				}
 			}
 		);
+		sp_tracing::probes::call_exit(method, used_native, result.is_ok());
 		(result, used_native)
 	}
 }
@@ -26,7 +26,7 @@ use codec::Decode;
 use sp_core::traits::{Externalities, RuntimeCode, FetchRuntimeCode};
 use sp_version::RuntimeVersion;
 use std::panic::AssertUnwindSafe;
-use sc_executor_common::wasm_runtime::{WasmModule, WasmInstance};
+use sc_executor_common::{metrics, wasm_runtime::{WasmModule, WasmInstance}};
 
 use sp_wasm_interface::Function;
 
@@ -86,6 +86,17 @@ impl VersionedRuntime {
 				let (instance, new_inst) = locked.take()
 					.map(|r| Ok((r, false)))
 					.unwrap_or_else(|| self.module.new_instance().map(|i| (i, true)))?;
+				if new_inst {
+					metrics::instance_created();
+					tracing::debug!(
+						target: "wasm-runtime",
+						instance = index as u64,
+						instances = self.instances.len() as u64,
+						"instance_created",
+					);
+				} else {
+					metrics::instance_reused();
+				}
 
 				let result = f(&*instance, self.version.as_ref(), ext);
 				if let Err(e) = &result {
@@ -119,6 +130,12 @@ impl VersionedRuntime {
 			},
 			None => {
 				log::warn!(target: "wasm-runtime", "Ran out of free WASM instances");
+				metrics::pool_exhausted();
+				tracing::warn!(
+					target: "wasm-runtime",
+					instances = self.instances.len() as u64,
+					"instance_pool_exhausted",
+				);
 
 				// Allocate a new instance
 				let instance = self.module.new_instance()?;
@@ -344,12 +361,22 @@ fn create_versioned_wasm_runtime(
 		Err(_) => None,
 	};
 	#[cfg(not(target_os = "unknown"))]
-	log::debug!(
-		target: "wasm-runtime",
-		"Prepared new runtime version {:?} in {} ms.",
-		version,
-		time.elapsed().as_millis(),
-	);
+	{
+		log::debug!(
+			target: "wasm-runtime",
+			"Prepared new runtime version {:?} in {} ms.",
+			version,
+			time.elapsed().as_millis(),
+		);
+		tracing::info!(
+			target: "wasm-runtime",
+			spec_version = version.as_ref().map_or(0, |v| v.spec_version),
+			code_hash = %sp_core::hexdisplay::HexDisplay::from(&code_hash),
+			code_size = code.len() as u64,
+			compilation_ms = time.elapsed().as_millis() as u64,
+			"Prepared new runtime",
+		);
+	}
 
 	let mut instances = Vec::with_capacity(max_instances);
 	instances.resize_with(max_instances, || Mutex::new(None));
@@ -19,7 +19,7 @@
 use std::{str, cell::RefCell, sync::Arc};
 use wasmi::{
 	Module, ModuleInstance, MemoryInstance, MemoryRef, TableRef, ImportsBuilder, ModuleRef,
-	memory_units::Pages,
+	TrapKind, memory_units::Pages,
 	RuntimeValue::{I32, I64, self},
 };
 use codec::{Encode, Decode};
@@ -33,6 +33,7 @@ use sc_executor_common::wasm_runtime::{WasmModule, WasmInstance};
 use sc_executor_common::{
 	error::{Error, WasmError},
 	sandbox,
+	trap::TrapReport,
 };
 use sc_executor_common::util::{DataSegmentsSnapshot, WasmModuleInfo};
 
@@ -387,6 +388,7 @@ impl<'a> wasmi::Externals for FunctionExecutor<'a> {
 		let mut args = args.as_ref().iter().copied().map(Into::into);
 
 		if let Some(function) = self.host_functions.get(index) {
+			sc_executor_common::trap::record_host_call(function.name());
 			function.execute(self, &mut args)
 				.map_err(|msg| Error::FunctionExecution(function.name().to_string(), msg))
 				.map_err(wasmi::Trap::from)
@@ -459,6 +461,7 @@ fn call_in_wasm_module(
 	let offset = fec.allocate_memory(data.len() as u32)?;
 	fec.write_memory(offset, data)?;
 
+	sc_executor_common::trap::clear_host_calls();
 	let result = module_instance.invoke_export(
 		method,
 		&[I32(u32::from(offset) as i32), I32(data.len() as i32)],
@@ -476,12 +479,27 @@ fn call_in_wasm_module(
 				"Failed to execute code with {} pages",
 				memory.current_size().0
 			);
-			Err(e.into())
+			match e {
+				// The interpreter doesn't know the wasm stack of its traps.
+				wasmi::Error::Trap(trap) => Err(Error::WasmTrap(Box::new(TrapReport::new(
+					trap_message(&trap),
+					Vec::new(),
+				)))),
+				e => Err(e.into()),
+			}
 		},
 		_ => Err(Error::InvalidReturn),
 	}
 }
 
+/// Why the runtime trapped, the message of the host error if the trap is one.
+fn trap_message(trap: &wasmi::Trap) -> String {
+	match trap.kind() {
+		TrapKind::Host(error) => error.to_string(),
+		kind => format!("{:?}", kind),
+	}
+}
+
 /// Prepare module instance
 fn instantiate_module(
 	heap_pages: usize,
@@ -681,21 +699,25 @@ impl WasmInstance for WasmiInstance {
 		// We reuse a single wasm instance for multiple calls and a previous call (if any)
 		// altered the state. Therefore, we need to restore the instance to original state.
 
-		// First, zero initialize the linear memory.
-		self.memory.erase().map_err(|e| {
-			// Snapshot restoration failed. This is pretty unexpected since this can happen
-			// if some invariant is broken or if the system is under extreme memory pressure
-			// (so erasing fails).
-			error!(target: "wasm-executor", "snapshot restoration failed: {}", e);
-			WasmError::ErasingFailed(e.to_string())
-		})?;
+		sc_executor_common::metrics::memory_reset(|| -> Result<(), Error> {
+			// First, zero initialize the linear memory.
+			self.memory.erase().map_err(|e| {
+				// Snapshot restoration failed. This is pretty unexpected since this can happen
+				// if some invariant is broken or if the system is under extreme memory pressure
+				// (so erasing fails).
+				error!(target: "wasm-executor", "snapshot restoration failed: {}", e);
+				WasmError::ErasingFailed(e.to_string())
+			})?;
+
+			// Second, reapply data segments into the linear memory.
+			self.data_segments_snapshot
+				.apply(|offset, contents| self.memory.set(offset, contents))?;
 
-		// Second, reapply data segments into the linear memory.
-		self.data_segments_snapshot
-			.apply(|offset, contents| self.memory.set(offset, contents))?;
+			// Third, restore the global variables to their initial values.
+			self.global_vals_snapshot.apply(&self.instance)?;
 
-		// Third, restore the global variables to their initial values.
-		self.global_vals_snapshot.apply(&self.instance)?;
+			Ok(())
+		})?;
 
 		call_in_wasm_module(
 			&self.instance,
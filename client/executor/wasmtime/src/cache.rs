@@ -0,0 +1,236 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Persistent cache of the code compiled from the runtimes.
+//!
+//! The compiled code is stored in the cache of wasmtime, keyed by the hash of the Wasm code and
+//! of the configuration of the engine, so that the runtimes already compiled are loaded from the
+//! disk after a restart or a runtime upgrade, instead of being compiled again.
+//!
+//! Wasmtime counts the hits and misses of its cache, but doesn't expose them on the engines, so
+//! the runtimes compiled into the cache are recorded next to it, by hash of their code. As the
+//! key of wasmtime also covers the configuration of the engine, which only changes with the
+//! version of this crate, the records are kept by version. The compilations of a runtime are
+//! done one at a time, so that a compilation is a hit if the runtime was recorded by a previous
+//! one, while different runtimes are compiled concurrently. A runtime evicted by the cleanup of
+//! the cache of wasmtime is still counted as a hit.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock, atomic::{AtomicU64, Ordering}};
+
+use serde::Serialize;
+use sp_core::{hashing::blake2_256, hexdisplay::HexDisplay};
+use wasmtime::Config;
+
+lazy_static::lazy_static! {
+	/// The cache the runtimes are compiled into, if any.
+	static ref CACHE: RwLock<Option<Arc<Cache>>> = RwLock::new(None);
+}
+
+/// The number of compiled runtimes loaded from the cache, and compiled.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+	/// Number of runtimes loaded from the cache.
+	pub hits: u64,
+	/// Number of runtimes compiled, for want of a cached compilation.
+	pub misses: u64,
+}
+
+/// A cache of compiled code in a directory, and the wasmtime configuration file enabling it.
+pub(crate) struct Cache {
+	config_file: PathBuf,
+	/// Directory of the records of the runtimes compiled into the cache.
+	compiled: PathBuf,
+	hits: AtomicU64,
+	misses: AtomicU64,
+	/// Held while a runtime is compiled into the cache, by hash of its code.
+	compiling: Mutex<HashMap<[u8; 32], Arc<Mutex<()>>>>,
+}
+
+/// The wasmtime configuration file of a cache.
+#[derive(Serialize)]
+struct ConfigFile<'a> {
+	cache: CacheSection<'a>,
+}
+
+#[derive(Serialize)]
+struct CacheSection<'a> {
+	enabled: bool,
+	directory: &'a Path,
+}
+
+/// Store the code compiled from now on in `directory`, and load the runtimes already compiled
+/// from it. `None` disables the cache.
+pub fn set_cache(directory: Option<PathBuf>) {
+	let cache = directory.and_then(|directory| match Cache::new(directory) {
+		Ok(cache) => Some(Arc::new(cache)),
+		Err(e) => {
+			log::warn!(target: "wasm-runtime", "Unable to set up the wasmtime cache: {}", e);
+			None
+		},
+	});
+	*CACHE.write().unwrap_or_else(|e| e.into_inner()) = cache;
+}
+
+/// The number of runtimes loaded from the cache and compiled since it was set.
+pub fn cache_stats() -> CacheStats {
+	CACHE.read().unwrap_or_else(|e| e.into_inner()).as_ref().map(|cache| cache.stats()).unwrap_or_default()
+}
+
+/// Enable the cache in `config`, if set. Returns the cache enabled.
+pub(crate) fn configure(config: &mut Config) -> Option<Arc<Cache>> {
+	let cache = CACHE.read().unwrap_or_else(|e| e.into_inner()).clone()?;
+	if cache.configure(config) {
+		Some(cache)
+	} else {
+		None
+	}
+}
+
+impl Cache {
+	/// A cache in `directory`, writing the wasmtime configuration file in it.
+	pub fn new(directory: PathBuf) -> io::Result<Self> {
+		fs::create_dir_all(&directory)?;
+		let config_file = directory.join("wasmtime-cache.toml");
+		let config = ConfigFile { cache: CacheSection { enabled: true, directory: &directory } };
+		let config = toml::to_string(&config).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+		fs::write(&config_file, config)?;
+		let compiled = directory.join("compiled").join(env!("CARGO_PKG_VERSION"));
+		fs::create_dir_all(&compiled)?;
+		Ok(Cache {
+			config_file,
+			compiled,
+			hits: AtomicU64::new(0),
+			misses: AtomicU64::new(0),
+			compiling: Default::default(),
+		})
+	}
+
+	/// The number of runtimes loaded from the cache and compiled into it.
+	pub fn stats(&self) -> CacheStats {
+		CacheStats {
+			hits: self.hits.load(Ordering::Relaxed),
+			misses: self.misses.load(Ordering::Relaxed),
+		}
+	}
+
+	/// Enable the cache in `config`. Returns whether it is enabled.
+	pub fn configure(&self, config: &mut Config) -> bool {
+		match config.cache_config_load(&self.config_file) {
+			Ok(_) => true,
+			Err(e) => {
+				log::warn!(target: "wasm-runtime", "Unable to load the wasmtime cache: {}", e);
+				false
+			},
+		}
+	}
+
+	/// Compile the runtime of `code` with `compile`, with a configuration enabling the cache,
+	/// accounting whether it was loaded from the cache.
+	pub fn compile<R, E>(&self, code: &[u8], compile: impl FnOnce() -> Result<R, E>) -> Result<R, E> {
+		let span = tracing::info_span!(
+			target: "wasm-runtime",
+			"wasmtime_cache_load",
+			cache = tracing::field::Empty,
+		);
+		let _enter = span.enter();
+		let hash = blake2_256(code);
+		let lock = self.compiling.lock().unwrap_or_else(|e| e.into_inner())
+			.entry(hash)
+			.or_default()
+			.clone();
+		let result = {
+			let _compiling = lock.lock().unwrap_or_else(|e| e.into_inner());
+			let record = self.compiled.join(format!("{}", HexDisplay::from(&hash)));
+			let result = compile();
+			if result.is_ok() {
+				if record.exists() {
+					self.hits.fetch_add(1, Ordering::Relaxed);
+					span.record("cache", &"hit");
+				} else {
+					self.misses.fetch_add(1, Ordering::Relaxed);
+					span.record("cache", &"miss");
+					if let Err(e) = fs::write(&record, b"") {
+						log::warn!(target: "wasm-runtime", "Unable to record the compiled runtime: {}", e);
+					}
+				}
+			}
+			result
+		};
+		// The lock is dropped once no other compilation of the runtime holds it.
+		let mut compiling = self.compiling.lock().unwrap_or_else(|e| e.into_inner());
+		if Arc::strong_count(&lock) == 2 {
+			compiling.remove(&hash);
+		}
+		result
+	}
+}
+
+/// Compile the runtime of `code` with `compile`, through the `cache` enabled in its
+/// configuration, if any.
+pub(crate) fn compile<R, E>(
+	cache: Option<&Cache>,
+	code: &[u8],
+	compile: impl FnOnce() -> Result<R, E>,
+) -> Result<R, E> {
+	match cache {
+		Some(cache) => cache.compile(code, compile),
+		None => compile(),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use wasmtime::{Engine, Module};
+
+	const CODE: &str = r#"(module (func (export "answer") (result i32) i32.const 42))"#;
+	const OTHER_CODE: &str = r#"(module (func (export "answer") (result i32) i32.const 43))"#;
+
+	#[test]
+	fn compiled_modules_are_loaded_from_the_cache() {
+		let directory = tempfile::tempdir().unwrap();
+		// The configuration file escapes the directory.
+		let cache = Cache::new(directory.path().join("it's a \"cache\"")).unwrap();
+		let compile_module = |code: &str| {
+			let mut config = Config::new();
+			assert!(cache.configure(&mut config));
+			let engine = Engine::new(&config);
+			cache.compile(code.as_bytes(), || Module::new(&engine, code)).unwrap();
+		};
+
+		compile_module(CODE);
+		compile_module(CODE);
+		compile_module(OTHER_CODE);
+		assert_eq!(cache.stats(), CacheStats { hits: 1, misses: 2 });
+
+		// Failed compilations are not recorded.
+		assert!(cache.compile(b"not wasm", || Err::<(), _>("invalid")).is_err());
+		assert_eq!(cache.stats(), CacheStats { hits: 1, misses: 2 });
+		assert!(cache.compiling.lock().unwrap().is_empty());
+
+		// The runtimes compiled are loaded from the cache of another node.
+		let cache = Cache::new(directory.path().join("it's a \"cache\"")).unwrap();
+		let mut config = Config::new();
+		assert!(cache.configure(&mut config));
+		let engine = Engine::new(&config);
+		cache.compile(CODE.as_bytes(), || Module::new(&engine, CODE)).unwrap();
+		assert_eq!(cache.stats(), CacheStats { hits: 1, misses: 0 });
+	}
+}
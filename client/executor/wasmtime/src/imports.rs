@@ -170,6 +170,7 @@ fn call_static(
 	wasmtime_params: &[Val],
 	wasmtime_results: &mut [Val],
 ) -> Result<(), wasmtime::Trap> {
+	sc_executor_common::trap::record_host_call(static_func.name());
 	let unwind_result = state_holder::with_context(|host_ctx| {
 		let mut host_ctx = host_ctx.expect(
 			"host functions can be called only from wasm instance;
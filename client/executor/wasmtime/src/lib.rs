@@ -16,6 +16,7 @@
 
 ///! Defines a `WasmRuntime` that uses the Wasmtime JIT to execute.
 
+mod cache;
 mod host;
 mod runtime;
 mod state_holder;
@@ -23,4 +24,5 @@ mod imports;
 mod instance_wrapper;
 mod util;
 
-pub use runtime::create_runtime;
+pub use cache::{CacheStats, cache_stats, set_cache};
+pub use runtime::{create_runtime, set_jitdump};
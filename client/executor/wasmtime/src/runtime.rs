@@ -16,21 +16,65 @@
 
 //! Defines the compiled Wasm runtime that uses Wasmtime internally.
 
+use crate::cache;
 use crate::host::HostState;
 use crate::imports::{Imports, resolve_imports};
 use crate::instance_wrapper::{ModuleWrapper, InstanceWrapper, GlobalsSnapshot};
 use crate::state_holder;
 
 use std::rc::Rc;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, atomic::{AtomicBool, Ordering}};
 use sc_executor_common::{
 	error::{Error, Result, WasmError},
+	trap::{TrapReport, WasmFrame},
 	wasm_runtime::{WasmModule, WasmInstance},
 };
 use sp_allocator::FreeingBumpHeapAllocator;
 use sp_runtime_interface::unpack_ptr_and_len;
 use sp_wasm_interface::{Function, Pointer, WordSize, Value};
-use wasmtime::{Config, Engine, Store};
+use wasmtime::{Config, Engine, ProfilingStrategy, Store};
+
+/// Whether the code compiled from now on is described in a perf jitdump file.
+static JITDUMP: AtomicBool = AtomicBool::new(false);
+
+lazy_static::lazy_static! {
+	/// The configuration of the profiled runtimes, sharing a single jitdump file.
+	static ref JITDUMP_CONFIG: Mutex<Option<Config>> = Mutex::new(None);
+}
+
+/// Describe the code of the runtimes created from now on in the `jit-<pid>.dump` file of the
+/// working directory, for `perf inject --jit` to attribute the samples of `perf record -k mono`
+/// to the functions of the runtimes.
+///
+/// The timestamps of the file come from the monotonic clock, as the `trace_start_ns` field of the
+/// spans logged by the `Log` tracing receiver, to correlate the samples with the spans.
+pub fn set_jitdump(enabled: bool) {
+	JITDUMP.store(enabled, Ordering::Relaxed);
+}
+
+/// The configuration of a new runtime, profiled if the jitdump is enabled and can be written.
+fn config() -> Config {
+	if !JITDUMP.load(Ordering::Relaxed) {
+		return Config::new();
+	}
+	let mut jitdump_config = JITDUMP_CONFIG.lock().unwrap_or_else(|e| e.into_inner());
+	if let Some(config) = &*jitdump_config {
+		return config.clone();
+	}
+	let mut config = Config::new();
+	// Every profiler truncates the file, so they are all clones of this one.
+	match config.profiler(ProfilingStrategy::JitDump) {
+		Ok(_) => {
+			*jitdump_config = Some(config.clone());
+			config
+		},
+		Err(e) => {
+			log::warn!(target: "wasm-runtime", "Unable to create the jitdump file: {}", e);
+			set_jitdump(false);
+			Config::new()
+		},
+	}
+}
 
 /// A `WasmModule` implementation using wasmtime to compile the runtime module to machine code
 /// and execute the compiled code.
@@ -94,14 +138,16 @@ impl WasmInstance for WasmtimeInstance {
 		let entrypoint = self.instance_wrapper.resolve_entrypoint(method)?;
 		let allocator = FreeingBumpHeapAllocator::new(self.heap_base);
 
-		self.module_wrapper
-			.data_segments_snapshot()
-			.apply(|offset, contents| {
-				self.instance_wrapper
-					.write_memory_from(Pointer::new(offset), contents)
-			})?;
+		sc_executor_common::metrics::memory_reset(|| -> Result<()> {
+			self.module_wrapper
+				.data_segments_snapshot()
+				.apply(|offset, contents| {
+					self.instance_wrapper
+						.write_memory_from(Pointer::new(offset), contents)
+				})?;
 
-		self.globals_snapshot.apply(&*self.instance_wrapper)?;
+			self.globals_snapshot.apply(&*self.instance_wrapper)
+		})?;
 
 		perform_call(
 			data,
@@ -126,12 +172,13 @@ pub fn create_runtime(
 	allow_missing_func_imports: bool,
 ) -> std::result::Result<WasmtimeRuntime, WasmError> {
 	// Create the engine, store and finally the module from the given code.
-	let mut config = Config::new();
+	let mut config = config();
 	config.cranelift_opt_level(wasmtime::OptLevel::SpeedAndSize);
+	let cache = cache::configure(&mut config);
 
 	let engine = Engine::new(&config);
 
-	let module_wrapper = ModuleWrapper::new(&engine, code)
+	let module_wrapper = cache::compile(cache.as_deref(), code, || ModuleWrapper::new(&engine, code))
 		.map_err(|e| WasmError::Other(format!("cannot create module: {}", e)))?;
 
 	Ok(WasmtimeRuntime {
@@ -151,6 +198,7 @@ fn perform_call(
 ) -> Result<Vec<u8>> {
 	let (data_ptr, data_len) = inject_input_data(&instance_wrapper, &mut allocator, data)?;
 
+	sc_executor_common::trap::clear_host_calls();
 	let host_state = HostState::new(allocator, instance_wrapper.clone());
 	let ret = state_holder::with_initialized_state(&host_state, || {
 		match entrypoint.call(&[
@@ -161,12 +209,10 @@ fn perform_call(
 				let retval = results[0].unwrap_i64() as u64;
 				Ok(unpack_ptr_and_len(retval))
 			}
-			Err(trap) => {
-				return Err(Error::from(format!(
-					"Wasm execution trapped: {}",
-					trap
-				)));
-			}
+			Err(error) => Err(Error::WasmTrap(Box::new(match error.downcast_ref::<wasmtime::Trap>() {
+				Some(trap) => trap_report(trap),
+				None => TrapReport::new(error.to_string(), Vec::new()),
+			}))),
 		}
 	});
 	let (output_ptr, output_len) = ret?;
@@ -175,6 +221,22 @@ fn perform_call(
 	Ok(output)
 }
 
+/// The report of `trap`, the functions of its backtrace being named after the name section of
+/// the runtime.
+fn trap_report(trap: &wasmtime::Trap) -> TrapReport {
+	// The trap displays its reason followed by its backtrace, which is reported on its own.
+	let message = trap.to_string();
+	let message = message.split("\nwasm backtrace:").next().unwrap_or_default();
+	let backtrace = trap.trace().iter()
+		.map(|frame| WasmFrame {
+			func_index: frame.func_index(),
+			func_name: frame.func_name().map(|name| format!("{:#}", rustc_demangle::demangle(name))),
+			module_offset: frame.module_offset(),
+		})
+		.collect();
+	TrapReport::new(message, backtrace)
+}
+
 fn inject_input_data(
 	instance: &InstanceWrapper,
 	allocator: &mut FreeingBumpHeapAllocator,
@@ -37,7 +37,7 @@ use finality_grandpa::{
 use sp_blockchain::{HeaderBackend, HeaderMetadata, Error as ClientError};
 use sp_runtime::generic::BlockId;
 use sp_runtime::traits::{
-	Block as BlockT, Header as HeaderT, NumberFor, One, Zero,
+	Block as BlockT, Header as HeaderT, NumberFor, One, SaturatedConversion, Zero,
 };
 use sc_telemetry::{telemetry, CONSENSUS_INFO};
 
@@ -782,6 +782,7 @@ where
 				.expect("checked previously that key exists; qed.");
 
 			*current_round = HasVoted::Yes(local_id, Vote::Propose(propose));
+			sp_tracing::finality_vote!(crate::FINALITY_GADGET, "propose", round);
 
 			let set_state = VoterSetState::<Block>::Live {
 				completed_rounds: completed_rounds.clone(),
@@ -823,6 +824,7 @@ where
 				.expect("checked previously that key exists; qed.");
 
 			*current_round = HasVoted::Yes(local_id, Vote::Prevote(propose.cloned(), prevote));
+			sp_tracing::finality_vote!(crate::FINALITY_GADGET, "prevote", round);
 
 			let set_state = VoterSetState::<Block>::Live {
 				completed_rounds: completed_rounds.clone(),
@@ -874,6 +876,7 @@ where
 				local_id,
 				Vote::Precommit(propose.cloned(), prevote.clone(), precommit),
 			);
+			sp_tracing::finality_vote!(crate::FINALITY_GADGET, "precommit", round);
 
 			let set_state = VoterSetState::<Block>::Live {
 				completed_rounds: completed_rounds.clone(),
@@ -903,6 +906,7 @@ where
 			state.estimate.as_ref().map(|e| e.1),
 			state.finalized.as_ref().map(|e| e.1),
 		);
+		sp_tracing::finality_round!(crate::FINALITY_GADGET, self.set_id, round);
 
 		self.update_voter_set_state(|voter_set_state| {
 			// NOTE: we don't use `with_current_round` here, it is possible that
@@ -1233,6 +1237,7 @@ where
 		telemetry!(CONSENSUS_INFO; "afg.finalized_blocks_up_to";
 			"number" => ?number, "hash" => ?hash,
 		);
+		sp_tracing::finality_finalized!(crate::FINALITY_GADGET, number.saturated_into::<u64>());
 
 		let new_authorities = if let Some((canon_hash, canon_number)) = status.new_set_block {
 			// the authority set has changed.
@@ -194,6 +194,10 @@ impl<B, Block> sc_network::config::FinalityProofProvider<Block> for FinalityProo
 		for_block: Block::Hash,
 		request: &[u8],
 	) -> Result<Option<Vec<u8>>, ClientError> {
+		let span = tracing::debug_span!(target: "afg", "finality_proof_request", for_block = ?for_block);
+		let _guard = span.enter();
+		sp_tracing::enter_finality_proof_span!(crate::FINALITY_GADGET, "generate");
+
 		let request: FinalityProofRequest<Block::Hash> = Decode::decode(&mut &request[..])
 			.map_err(|e| {
 				warn!(target: "afg", "Unable to decode finality proof request: {}", e.what());
@@ -16,11 +16,12 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
-use std::{sync::Arc, collections::HashMap};
+use std::{sync::Arc, collections::HashMap, time::Instant};
 
 use log::debug;
 use parity_scale_codec::Encode;
 use parking_lot::RwLockWriteGuard;
+use prometheus_endpoint::{Histogram, HistogramOpts, PrometheusError, Registry, register};
 
 use sp_blockchain::{BlockStatus, well_known_cache_keys};
 use sc_client_api::{backend::Backend, utils::is_descendent_of};
@@ -64,9 +65,30 @@ pub struct GrandpaBlockImport<Backend, Block: BlockT, Client, SC> {
 	consensus_changes: SharedConsensusChanges<Block::Hash, NumberFor<Block>>,
 	authority_set_hard_forks: HashMap<Block::Hash, PendingChange<Block::Hash, NumberFor<Block>>>,
 	justification_sender: GrandpaJustificationSender<Block>,
+	metrics: Option<Metrics>,
 	_phantom: PhantomData<Backend>,
 }
 
+/// Prometheus metrics of the import of the justifications.
+#[derive(Clone)]
+pub(crate) struct Metrics {
+	justification_verification_time: Histogram,
+}
+
+impl Metrics {
+	pub(crate) fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+		Ok(Self {
+			justification_verification_time: register(
+				Histogram::with_opts(HistogramOpts::new(
+					"finality_grandpa_justification_verification_time",
+					"Histogram of time taken to verify the imported GRANDPA justifications",
+				))?,
+				registry,
+			)?,
+		})
+	}
+}
+
 impl<Backend, Block: BlockT, Client, SC: Clone> Clone for
 	GrandpaBlockImport<Backend, Block, Client, SC>
 {
@@ -79,6 +101,7 @@ impl<Backend, Block: BlockT, Client, SC: Clone> Clone for
 			consensus_changes: self.consensus_changes.clone(),
 			authority_set_hard_forks: self.authority_set_hard_forks.clone(),
 			justification_sender: self.justification_sender.clone(),
+			metrics: self.metrics.clone(),
 			_phantom: PhantomData,
 		}
 	}
@@ -117,6 +140,12 @@ impl<BE, Block: BlockT, Client, SC> JustificationImport<Block>
 				if let Ok(Some(hash)) = effective_block_hash {
 					if let Ok(Some(header)) = self.inner.header(BlockId::Hash(hash)) {
 						if *header.number() == pending_change.effective_number() {
+							tracing::debug!(
+								target: "afg",
+								block_number = ?header.number(),
+								reason = "pending_change",
+								"Requesting justification",
+							);
 							out.push((header.hash(), *header.number()));
 						}
 					}
@@ -520,6 +549,12 @@ impl<BE, Block: BlockT, Client, SC> BlockImport<Block>
 					if needs_justification || enacts_consensus_change {
 						debug!(target: "afg", "Imported block #{} that enacts authority set change with \
 							invalid justification: {:?}, requesting justification from peers.", number, err);
+						tracing::debug!(
+							target: "afg",
+							block_number = ?number,
+							reason = "bad_justification",
+							"Requesting justification",
+						);
 						imported_aux.bad_justification = true;
 						imported_aux.needs_justification = true;
 					}
@@ -532,6 +567,12 @@ impl<BE, Block: BlockT, Client, SC> BlockImport<Block>
 						"Imported unjustified block #{} that enacts authority set change, waiting for finality for enactment.",
 						number,
 					);
+					tracing::debug!(
+						target: "afg",
+						block_number = ?number,
+						reason = "authority_set_change",
+						"Requesting justification",
+					);
 
 					imported_aux.needs_justification = true;
 				}
@@ -608,9 +649,20 @@ impl<Backend, Block: BlockT, Client, SC> GrandpaBlockImport<Backend, Block, Clie
 			consensus_changes,
 			authority_set_hard_forks,
 			justification_sender,
+			metrics: None,
 			_phantom: PhantomData,
 		}
 	}
+
+	/// Register the metrics of the import of the justifications, e.g. the time taken to verify
+	/// them, in `registry`, if any.
+	pub fn with_prometheus_registry(
+		self,
+		registry: Option<&Registry>,
+	) -> Result<Self, PrometheusError> {
+		let metrics = registry.map(Metrics::register).transpose()?;
+		Ok(GrandpaBlockImport { metrics, ..self })
+	}
 }
 
 impl<BE, Block: BlockT, Client, SC> GrandpaBlockImport<BE, Block, Client, SC>
@@ -631,12 +683,27 @@ where
 		enacts_change: bool,
 		initial_sync: bool,
 	) -> Result<(), ConsensusError> {
+		let set_id = self.authority_set.set_id();
+		let span = tracing::debug_span!(
+			target: "afg",
+			"import_justification",
+			block_number = ?number,
+			block_hash = ?hash,
+			set_id,
+			initial_sync,
+		);
+		let _guard = span.enter();
+
+		let started = Instant::now();
 		let justification = GrandpaJustification::decode_and_verify_finalizes(
 			&justification,
 			(hash, number),
-			self.authority_set.set_id(),
+			set_id,
 			&self.authority_set.current_authorities(),
 		);
+		if let Some(metrics) = &self.metrics {
+			metrics.justification_verification_time.observe(started.elapsed().as_secs_f64());
+		}
 
 		let justification = match justification {
 			Err(e) => return Err(ConsensusError::ClientImport(e.to_string())),
@@ -110,7 +110,36 @@ impl<Block: BlockT> GrandpaJustification<Block> {
 	}
 
 	/// Validate the commit and the votes' ancestry proofs.
+	///
+	/// The verification is traced in a `verify_justification` span, recording the number of
+	/// signatures checked.
 	pub(crate) fn verify(&self, set_id: u64, voters: &VoterSet<AuthorityId>) -> Result<(), ClientError>
+	where
+		NumberFor<Block>: finality_grandpa::BlockNumberOps,
+	{
+		let span = tracing::debug_span!(
+			target: "afg",
+			"verify_justification",
+			round = self.round,
+			set_id,
+			precommits = self.commit.precommits.len() as u64,
+			signature_checks = tracing::field::Empty,
+		);
+		let _guard = span.enter();
+		sp_tracing::enter_finality_proof_span!(crate::FINALITY_GADGET, "verify");
+
+		let mut signature_checks = 0u64;
+		let result = self.verify_commit(set_id, voters, &mut signature_checks);
+		span.record("signature_checks", &signature_checks);
+		result
+	}
+
+	fn verify_commit(
+		&self,
+		set_id: u64,
+		voters: &VoterSet<AuthorityId>,
+		signature_checks: &mut u64,
+	) -> Result<(), ClientError>
 	where
 		NumberFor<Block>: finality_grandpa::BlockNumberOps,
 	{
@@ -133,6 +162,7 @@ impl<Block: BlockT> GrandpaJustification<Block> {
 		let mut buf = Vec::new();
 		let mut visited_hashes = HashSet::new();
 		for signed in self.commit.precommits.iter() {
+			*signature_checks += 1;
 			if !sp_finality_grandpa::check_message_signature_with_buffer(
 				&finality_grandpa::Message::Precommit(signed.precommit.clone()),
 				&signed.id,
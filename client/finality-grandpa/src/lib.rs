@@ -148,6 +148,10 @@ use std::marker::PhantomData;
 #[cfg(test)]
 mod tests;
 
+/// Name of GRANDPA in the spans and events of the finality gadgets, see
+/// `sp_tracing::FINALITY_TARGET`.
+pub(crate) const FINALITY_GADGET: &str = "grandpa";
+
 /// A GRANDPA message for a substrate chain.
 pub type Message<Block> = finality_grandpa::Message<<Block as BlockT>::Hash, NumberFor<Block>>;
 
@@ -0,0 +1,7 @@
+const PROTOS: &[&str] = &[
+	"src/schema/node.v1.proto",
+];
+
+fn main() {
+	prost_build::compile_protos(PROTOS, &["src/schema"]).unwrap();
+}
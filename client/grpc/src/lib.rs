@@ -0,0 +1,318 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2017-2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Substrate gRPC server.
+//!
+//! Serves the `node.v1.Node` service of `src/schema/node.v1.proto`: the subscriptions to the heads
+//! of the chain, the storage queries and the submission of extrinsics, for the tooling talking
+//! gRPC rather than JSON-RPC. Each call is traced in a span of the [`GRPC_TARGET`], with its
+//! method, the code of its status and its duration.
+
+#![warn(missing_docs)]
+
+mod node;
+mod protocol;
+mod schema;
+
+use std::{convert::Infallible, net::SocketAddr, sync::Arc};
+
+use futures::StreamExt;
+use hyper::{
+	body::HttpBody, header::CONTENT_TYPE, service::{make_service_fn, service_fn},
+	Body, Request, Response, Server, StatusCode,
+};
+use log::info;
+use prometheus_endpoint::networking;
+use sp_tracing::tracing;
+use tracing_futures::Instrument;
+use wasm_timer::Instant;
+
+pub use node::{Node, NodeApi};
+pub use protocol::{Code, Status};
+pub use schema::v1;
+
+/// Target of the traces of the calls.
+pub const GRPC_TARGET: &str = "grpc";
+
+/// Maximal size of the requests, as for the RPC servers.
+const MAX_REQUEST_SIZE: usize = 15 * 1024 * 1024;
+
+/// Errors of the gRPC server.
+#[derive(Debug, derive_more::Display, derive_more::From)]
+pub enum Error {
+	/// Hyper internal error.
+	Hyper(hyper::Error),
+	/// i/o error.
+	Io(std::io::Error),
+	#[display(fmt = "gRPC port {} already in use.", _0)]
+	/// The address of the server is in use.
+	PortInUse(SocketAddr),
+}
+
+impl std::error::Error for Error {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		match self {
+			Error::Hyper(error) => Some(error),
+			Error::Io(error) => Some(error),
+			Error::PortInUse(_) => None,
+		}
+	}
+}
+
+/// Start a gRPC server listening on `addr`, serving the APIs of the `node`.
+pub async fn start_server<N: NodeApi>(addr: SocketAddr, node: Arc<N>) -> Result<(), Error> {
+	let listener = async_std::net::TcpListener::bind(&addr)
+		.await
+		.map_err(|_| Error::PortInUse(addr))?;
+
+	info!("gRPC server started at {}", addr);
+
+	serve(listener, node).await
+}
+
+async fn serve<N: NodeApi>(
+	listener: async_std::net::TcpListener,
+	node: Arc<N>,
+) -> Result<(), Error> {
+	let service = make_service_fn(move |_| {
+		let node = node.clone();
+
+		async move {
+			Ok::<_, Infallible>(service_fn(move |request: Request<Body>| {
+				handle_request(request, node.clone())
+			}))
+		}
+	});
+
+	Server::builder(networking::Incoming(listener.incoming()))
+		.http2_only(true)
+		.executor(networking::Executor)
+		.serve(service)
+		.await
+		.map_err(Into::into)
+}
+
+/// Read the body of the `request`, up to [`MAX_REQUEST_SIZE`].
+async fn read_body(request: Request<Body>) -> Result<Vec<u8>, Status> {
+	let mut body = request.into_body();
+	let mut buffer = Vec::new();
+	while let Some(chunk) = body.data().await {
+		let chunk = chunk.map_err(|error| Status::new(Code::Internal, error.to_string()))?;
+		if buffer.len() + chunk.len() > MAX_REQUEST_SIZE {
+			return Err(Status::new(Code::ResourceExhausted, "Request too large"));
+		}
+		buffer.extend_from_slice(&chunk);
+	}
+	Ok(buffer)
+}
+
+async fn call<N: NodeApi>(method: &str, body: &[u8], node: &Arc<N>) -> Result<protocol::GrpcBody, Status> {
+	use protocol::{decode_message, encode_message, GrpcBody};
+
+	match method {
+		"/node.v1.Node/SubscribeHeads" => {
+			let request: v1::SubscribeHeadsRequest = decode_message(body)?;
+			let heads = node.subscribe_heads(request.finalized)
+				.map(|head| encode_message(&head))
+				.boxed();
+			Ok(GrpcBody::Stream(heads))
+		},
+		"/node.v1.Node/GetStorage" => {
+			let v1::GetStorageRequest { key, block } = decode_message(body)?;
+			let block = Some(block).filter(|block| !block.is_empty());
+			// The storage is read from the database, which blocks.
+			let (node, span) = (node.clone(), tracing::Span::current());
+			let value = async_std::task::spawn_blocking(move || {
+				span.in_scope(|| node.storage(block.as_deref(), key))
+			}).await?;
+			let response = v1::GetStorageResponse { value: value.map(|data| v1::StorageValue { data }) };
+			Ok(GrpcBody::Unary(Some(encode_message(&response))))
+		},
+		"/node.v1.Node/SubmitExtrinsic" => {
+			let request: v1::SubmitExtrinsicRequest = decode_message(body)?;
+			let hash = node.submit_extrinsic(&request.extrinsic).await?;
+			let response = v1::SubmitExtrinsicResponse { hash };
+			Ok(GrpcBody::Unary(Some(encode_message(&response))))
+		},
+		_ => Err(Status::new(Code::Unimplemented, format!("Unknown method {}", method))),
+	}
+}
+
+async fn handle_request<N: NodeApi>(
+	request: Request<Body>,
+	node: Arc<N>,
+) -> Result<Response<protocol::GrpcBody>, Infallible> {
+	let is_grpc = request.headers().get(CONTENT_TYPE)
+		.and_then(|content_type| content_type.to_str().ok())
+		.map_or(false, |content_type| content_type.starts_with("application/grpc"));
+	if !is_grpc {
+		let mut response = Response::new(protocol::GrpcBody::Empty);
+		*response.status_mut() = StatusCode::UNSUPPORTED_MEDIA_TYPE;
+		return Ok(response);
+	}
+
+	let method = request.uri().path().to_owned();
+	let span = tracing::debug_span!(
+		target: GRPC_TARGET,
+		"grpc_call",
+		method = %method,
+		code = tracing::field::Empty,
+		duration_ms = tracing::field::Empty,
+	);
+	let start = Instant::now();
+	let result = async {
+		let body = read_body(request).await?;
+		call(&method, &body, &node).await
+	}.instrument(span.clone()).await;
+
+	let code = result.as_ref().err().map_or(Code::Ok, |status| status.code);
+	span.record("code", &tracing::field::display(code));
+	span.record("duration_ms", &(start.elapsed().as_millis() as u64));
+	span.in_scope(|| match &result {
+		Ok(_) => tracing::trace!(target: GRPC_TARGET, "Call served"),
+		Err(status) => tracing::debug!(target: GRPC_TARGET, message = %status.message, "Call failed"),
+	});
+
+	let (body, headers) = match result {
+		Ok(body) => (body, None),
+		Err(status) => (protocol::GrpcBody::Empty, Some(status.headers())),
+	};
+	let mut response = Response::new(body);
+	response.headers_mut().insert(CONTENT_TYPE, "application/grpc".parse().expect("Valid header; qed"));
+	response.headers_mut().extend(headers.into_iter().flatten());
+	Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use futures::{future::{self, BoxFuture}, stream::BoxStream, FutureExt};
+	use hyper::{client::conn, HeaderMap};
+	use protocol::{decode_message, encode_message};
+
+	struct TestNode;
+
+	impl NodeApi for TestNode {
+		fn subscribe_heads(&self, finalized: bool) -> BoxStream<'static, v1::Head> {
+			let heads = (1..=2).map(move |number| v1::Head {
+				hash: vec![number as u8],
+				number: if finalized { number } else { number + 10 },
+				header: Vec::new(),
+			});
+			futures::stream::iter(heads).boxed()
+		}
+
+		fn storage(&self, block: Option<&[u8]>, key: Vec<u8>) -> Result<Option<Vec<u8>>, Status> {
+			match block {
+				Some(_) => Err(Status::new(Code::NotFound, "Unknown block")),
+				None => Ok(Some(key).filter(|key| key == b"key").map(|_| b"value".to_vec())),
+			}
+		}
+
+		fn submit_extrinsic(&self, extrinsic: &[u8]) -> BoxFuture<'static, Result<Vec<u8>, Status>> {
+			let hash = extrinsic.iter().rev().cloned().collect();
+			future::ready(Ok(hash)).boxed()
+		}
+	}
+
+	/// Call the `method` of a server serving a [`TestNode`], returning the messages and the
+	/// status of the response, from its trailers or its headers.
+	fn call_test_node(method: &str, request: &impl prost::Message) -> (Vec<Vec<u8>>, HeaderMap) {
+		async_std::task::block_on(async {
+			let listener = async_std::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+			let addr = listener.local_addr().unwrap();
+			async_std::task::spawn(serve(listener, Arc::new(TestNode)));
+
+			let stream = async_std::net::TcpStream::connect(addr).await.unwrap();
+			let (mut sender, connection) = conn::Builder::new()
+				.http2_only(true)
+				.executor(networking::Executor)
+				.handshake::<_, Body>(networking::TcpStream(stream))
+				.await
+				.unwrap();
+			async_std::task::spawn(connection);
+
+			let request = Request::post(format!("http://{}{}", addr, method))
+				.header(CONTENT_TYPE, "application/grpc")
+				.header("te", "trailers")
+				.body(Body::from(encode_message(request)))
+				.unwrap();
+			let response = sender.send_request(request).await.unwrap();
+			assert_eq!(response.status(), StatusCode::OK);
+			let headers = response.headers().clone();
+			let mut body = response.into_body();
+			let mut data = Vec::new();
+			while let Some(chunk) = body.data().await {
+				data.extend_from_slice(&chunk.unwrap());
+			}
+			let trailers = body.trailers().await.unwrap();
+
+			let mut messages = Vec::new();
+			let mut data = &data[..];
+			while !data.is_empty() {
+				let mut len = [0; 4];
+				len.copy_from_slice(&data[1..5]);
+				let len = 5 + u32::from_be_bytes(len) as usize;
+				messages.push(data[..len].to_vec());
+				data = &data[len..];
+			}
+			(messages, trailers.unwrap_or(headers))
+		})
+	}
+
+	#[test]
+	fn serves_storage_queries() {
+		let request = v1::GetStorageRequest { key: b"key".to_vec(), block: Vec::new() };
+		let (messages, status) = call_test_node("/node.v1.Node/GetStorage", &request);
+		assert_eq!(status["grpc-status"], "0");
+		let response: v1::GetStorageResponse = decode_message(&messages[0]).unwrap();
+		assert_eq!(response.value, Some(v1::StorageValue { data: b"value".to_vec() }));
+
+		let request = v1::GetStorageRequest { key: b"key".to_vec(), block: vec![1] };
+		let (messages, status) = call_test_node("/node.v1.Node/GetStorage", &request);
+		assert!(messages.is_empty());
+		assert_eq!(status["grpc-status"], "5");
+		assert_eq!(status["grpc-message"], "Unknown block");
+	}
+
+	#[test]
+	fn streams_the_heads() {
+		let request = v1::SubscribeHeadsRequest { finalized: true };
+		let (messages, status) = call_test_node("/node.v1.Node/SubscribeHeads", &request);
+		assert_eq!(status["grpc-status"], "0");
+		let numbers = messages.iter()
+			.map(|message| decode_message::<v1::Head>(message).unwrap().number)
+			.collect::<Vec<_>>();
+		assert_eq!(numbers, vec![1, 2]);
+	}
+
+	#[test]
+	fn submits_extrinsics() {
+		let request = v1::SubmitExtrinsicRequest { extrinsic: vec![1, 2, 3] };
+		let (messages, status) = call_test_node("/node.v1.Node/SubmitExtrinsic", &request);
+		assert_eq!(status["grpc-status"], "0");
+		let response: v1::SubmitExtrinsicResponse = decode_message(&messages[0]).unwrap();
+		assert_eq!(response.hash, vec![3, 2, 1]);
+	}
+
+	#[test]
+	fn unknown_methods_are_unimplemented() {
+		let (_, status) = call_test_node("/node.v1.Node/Unknown", &v1::SubscribeHeadsRequest::default());
+		assert_eq!(status["grpc-status"], "12");
+	}
+}
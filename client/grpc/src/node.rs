@@ -0,0 +1,122 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2017-2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! The node APIs served over gRPC.
+
+use std::{marker::PhantomData, sync::Arc};
+
+use codec::{Decode, Encode};
+use futures::{future::{self, BoxFuture}, stream::BoxStream, FutureExt, StreamExt};
+use sc_client_api::{BlockchainEvents, StorageProvider};
+use sp_blockchain::HeaderBackend;
+use sp_core::storage::StorageKey;
+use sp_runtime::{generic::BlockId, traits::{Block as BlockT, Header as HeaderT, UniqueSaturatedInto}};
+use sp_transaction_pool::{TransactionPool, TransactionSource};
+
+use crate::{protocol::{Code, Status}, schema::v1};
+
+/// The node APIs served over gRPC.
+pub trait NodeApi: Send + Sync + 'static {
+	/// Stream the new best heads, or the finalized heads.
+	fn subscribe_heads(&self, finalized: bool) -> BoxStream<'static, v1::Head>;
+
+	/// The value of the storage entry under the `key`, at the `block` or the best block.
+	///
+	/// Called on a blocking task, as the storage is read from the database.
+	fn storage(&self, block: Option<&[u8]>, key: Vec<u8>) -> Result<Option<Vec<u8>>, Status>;
+
+	/// Submit the encoded `extrinsic` to the transaction pool, returning its encoded hash.
+	fn submit_extrinsic(&self, extrinsic: &[u8]) -> BoxFuture<'static, Result<Vec<u8>, Status>>;
+}
+
+/// The node APIs of a client and its transaction pool.
+pub struct Node<Block, Backend, Client, Pool> {
+	client: Arc<Client>,
+	pool: Arc<Pool>,
+	_phantom: PhantomData<fn() -> (Block, Backend)>,
+}
+
+impl<Block, Backend, Client, Pool> Node<Block, Backend, Client, Pool> {
+	/// Serve the APIs of the `client` and the `pool`.
+	pub fn new(client: Arc<Client>, pool: Arc<Pool>) -> Self {
+		Node { client, pool, _phantom: PhantomData }
+	}
+}
+
+fn head<Header: HeaderT>(hash: Header::Hash, header: &Header) -> v1::Head {
+	v1::Head {
+		hash: hash.encode(),
+		number: UniqueSaturatedInto::<u64>::unique_saturated_into(*header.number()),
+		header: header.encode(),
+	}
+}
+
+impl<Block, Backend, Client, Pool> NodeApi for Node<Block, Backend, Client, Pool>
+where
+	Block: BlockT,
+	Backend: sc_client_api::Backend<Block> + 'static,
+	Client: BlockchainEvents<Block> + StorageProvider<Block, Backend> + HeaderBackend<Block> + 'static,
+	Pool: TransactionPool<Block = Block, Hash = Block::Hash> + 'static,
+{
+	fn subscribe_heads(&self, finalized: bool) -> BoxStream<'static, v1::Head> {
+		if finalized {
+			self.client.finality_notification_stream()
+				.map(|notification| head(notification.hash, &notification.header))
+				.boxed()
+		} else {
+			self.client.import_notification_stream()
+				.filter(|notification| future::ready(notification.is_new_best))
+				.map(|notification| head(notification.hash, &notification.header))
+				.boxed()
+		}
+	}
+
+	fn storage(&self, block: Option<&[u8]>, key: Vec<u8>) -> Result<Option<Vec<u8>>, Status> {
+		let hash = match block {
+			Some(mut block) => Block::Hash::decode(&mut block)
+				.ok()
+				.filter(|_| block.is_empty())
+				.ok_or_else(|| Status::new(Code::InvalidArgument, "Malformed block hash"))?,
+			None => self.client.info().best_hash,
+		};
+		let at = BlockId::Hash(hash);
+		let internal = |error: sp_blockchain::Error| Status::new(Code::Internal, error.to_string());
+		if self.client.header(at).map_err(internal)?.is_none() {
+			return Err(Status::new(Code::NotFound, format!("Unknown block {}", hash)));
+		}
+		self.client.storage(&at, &StorageKey(key))
+			.map(|value| value.map(|value| value.0))
+			.map_err(internal)
+	}
+
+	fn submit_extrinsic(&self, mut extrinsic: &[u8]) -> BoxFuture<'static, Result<Vec<u8>, Status>> {
+		let extrinsic = match Block::Extrinsic::decode(&mut extrinsic) {
+			Ok(extrinsic) => extrinsic,
+			Err(error) => return future::ready(Err(Status::new(
+				Code::InvalidArgument,
+				format!("Malformed extrinsic: {}", error.what()),
+			))).boxed(),
+		};
+		let best_hash = self.client.info().best_hash;
+		self.pool.submit_one(&BlockId::Hash(best_hash), TransactionSource::External, extrinsic)
+			.map(|result| result
+				.map(|hash| hash.encode())
+				.map_err(|error| Status::new(Code::FailedPrecondition, error.to_string())))
+			.boxed()
+	}
+}
@@ -0,0 +1,198 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2017-2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! The gRPC protocol over HTTP/2: the framing of the messages and the status of the calls.
+//!
+//! The messages are prefixed by a byte telling whether they are compressed, which they never are
+//! here, and their length as a big-endian `u32`. The status of a call is sent in the trailers of
+//! its response, or in its headers if the call failed before sending any message.
+
+use std::convert::Infallible;
+use std::fmt;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use bytes::Bytes;
+use futures::{stream::BoxStream, StreamExt};
+use hyper::{HeaderMap, header::HeaderValue};
+
+/// Length of the prefix of the messages.
+const PREFIX_LEN: usize = 5;
+
+/// Status codes of the calls.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Code {
+	/// The call succeeded.
+	Ok = 0,
+	/// The request is malformed.
+	InvalidArgument = 3,
+	/// The requested entity doesn't exist.
+	NotFound = 5,
+	/// The request is too large.
+	ResourceExhausted = 8,
+	/// The request is valid, but the node can't fulfill it in its state.
+	FailedPrecondition = 9,
+	/// The method, or a feature of the request, is not served.
+	Unimplemented = 12,
+	/// The node failed to serve the request.
+	Internal = 13,
+}
+
+impl fmt::Display for Code {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{}", *self as u8)
+	}
+}
+
+/// Status of a failed call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Status {
+	/// Code of the failure.
+	pub code: Code,
+	/// Description of the failure.
+	pub message: String,
+}
+
+impl Status {
+	/// A failure with the `code`, described by the `message`.
+	pub fn new(code: Code, message: impl Into<String>) -> Self {
+		Status { code, message: message.into() }
+	}
+
+	/// The headers, or trailers, of the status.
+	pub(crate) fn headers(&self) -> HeaderMap {
+		let mut headers = status_headers(self.code);
+		if let Ok(message) = HeaderValue::from_str(&percent_encode(&self.message)) {
+			headers.insert("grpc-message", message);
+		}
+		headers
+	}
+}
+
+fn status_headers(code: Code) -> HeaderMap {
+	let mut headers = HeaderMap::new();
+	headers.insert("grpc-status", HeaderValue::from(code as u16));
+	headers
+}
+
+/// Percent-encode the `message`, as required in the `grpc-message` header.
+fn percent_encode(message: &str) -> String {
+	message.bytes().map(|byte| match byte {
+		b' '..=b'~' if byte != b'%' => (byte as char).to_string(),
+		_ => format!("%{:02X}", byte),
+	}).collect()
+}
+
+/// Prefix the encoded `message`.
+pub(crate) fn encode_message(message: &impl prost::Message) -> Bytes {
+	let len = message.encoded_len();
+	let mut buffer = Vec::with_capacity(PREFIX_LEN + len);
+	buffer.push(0);
+	buffer.extend_from_slice(&(len as u32).to_be_bytes());
+	message.encode(&mut buffer).expect("The buffer has the capacity of the message; qed");
+	buffer.into()
+}
+
+/// Decode the single prefixed message of the `body` of a request.
+pub(crate) fn decode_message<M: prost::Message + Default>(body: &[u8]) -> Result<M, Status> {
+	if body.len() < PREFIX_LEN {
+		return Err(Status::new(Code::InvalidArgument, "Truncated message"));
+	}
+	if body[0] != 0 {
+		return Err(Status::new(Code::Unimplemented, "Compressed messages are not supported"));
+	}
+	let mut len = [0; 4];
+	len.copy_from_slice(&body[1..PREFIX_LEN]);
+	if body.len() - PREFIX_LEN != u32::from_be_bytes(len) as usize {
+		return Err(Status::new(Code::InvalidArgument, "Expected a single message"));
+	}
+	M::decode(&body[PREFIX_LEN..])
+		.map_err(|error| Status::new(Code::InvalidArgument, error.to_string()))
+}
+
+/// Body of the responses to the calls.
+pub enum GrpcBody {
+	/// The response of a failed call, without body, its status being in the headers.
+	Empty,
+	/// A single message, followed by the success of the call.
+	Unary(Option<Bytes>),
+	/// A stream of messages, followed by the success of the call when it ends.
+	Stream(BoxStream<'static, Bytes>),
+}
+
+impl hyper::body::HttpBody for GrpcBody {
+	type Data = Bytes;
+	type Error = Infallible;
+
+	fn poll_data(
+		self: Pin<&mut Self>,
+		cx: &mut Context,
+	) -> Poll<Option<Result<Bytes, Infallible>>> {
+		match self.get_mut() {
+			GrpcBody::Empty => Poll::Ready(None),
+			GrpcBody::Unary(message) => Poll::Ready(message.take().map(Ok)),
+			GrpcBody::Stream(messages) => messages.poll_next_unpin(cx).map(|message| message.map(Ok)),
+		}
+	}
+
+	fn poll_trailers(
+		self: Pin<&mut Self>,
+		_: &mut Context,
+	) -> Poll<Result<Option<HeaderMap>, Infallible>> {
+		Poll::Ready(Ok(match *self {
+			GrpcBody::Empty => None,
+			GrpcBody::Unary(_) | GrpcBody::Stream(_) => Some(status_headers(Code::Ok)),
+		}))
+	}
+
+	fn is_end_stream(&self) -> bool {
+		matches!(self, GrpcBody::Empty)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::schema::v1;
+
+	#[test]
+	fn messages_are_prefixed() {
+		let request = v1::GetStorageRequest { key: b"key".to_vec(), block: Vec::new() };
+		let encoded = encode_message(&request);
+		assert_eq!(&encoded[..PREFIX_LEN], &[0, 0, 0, 0, 5]);
+		assert_eq!(decode_message::<v1::GetStorageRequest>(&encoded), Ok(request));
+
+		let mut compressed = encoded.to_vec();
+		compressed[0] = 1;
+		assert_eq!(
+			decode_message::<v1::GetStorageRequest>(&compressed).map_err(|status| status.code),
+			Err(Code::Unimplemented),
+		);
+		assert_eq!(
+			decode_message::<v1::GetStorageRequest>(&encoded[..6]).map_err(|status| status.code),
+			Err(Code::InvalidArgument),
+		);
+	}
+
+	#[test]
+	fn messages_of_the_statuses_are_percent_encoded() {
+		let headers = Status::new(Code::NotFound, "Unknown block 100%").headers();
+		assert_eq!(headers["grpc-status"], "5");
+		assert_eq!(headers["grpc-message"], "Unknown block 100%25");
+	}
+}
@@ -0,0 +1,325 @@
+// Copyright 2017-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate. If not, see <http://www.gnu.org/licenses/>.
+
+//! Audit trail of keystore signing operations.
+//!
+//! Every signing request that goes through the keystore emits an event into the
+//! [`AUDIT_TARGET`] tracing target once its result is known. Optionally the same records are
+//! appended to a file, one JSON object per line. Each line carries its sequence number and the
+//! hash of the previous line, so removing or altering a record breaks the chain and is detected
+//! by [`verify_audit_log`].
+//!
+//! Dropping the last records of the file leaves a valid chain, so the events also carry the
+//! [`AuditHead`] of the log, the number of records and the hash of the last one. Checking the
+//! file against the last head seen outside of the node, e.g. in the collected logs, with
+//! [`verify_audit_log_head`] detects the truncation.
+
+use std::{fs::{File, OpenOptions}, io::{BufRead, BufReader, Write}, path::{Path, PathBuf}};
+use std::time::{SystemTime, UNIX_EPOCH};
+use sp_core::{crypto::KeyTypeId, hashing::blake2_256};
+use sp_core::traits::Error as TraitError;
+use crate::{Error, Result};
+
+/// Tracing target used for keystore audit events.
+pub const AUDIT_TARGET: &str = "keystore";
+
+/// Hash used as `prev` for the first record of a log.
+const GENESIS_HASH: [u8; 32] = [0u8; 32];
+
+/// The end of the hash chain of an audit log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AuditHead {
+	/// Number of records in the log.
+	pub records: u64,
+	/// Hash of the last record, [`GENESIS_HASH`] if there is none.
+	pub hash: [u8; 32],
+}
+
+impl Default for AuditHead {
+	fn default() -> Self {
+		AuditHead { records: 0, hash: GENESIS_HASH }
+	}
+}
+
+/// Append-only, hash-chained file sink for audit records.
+pub struct AuditLog {
+	path: PathBuf,
+	file: File,
+	head: AuditHead,
+}
+
+impl AuditLog {
+	/// Open the audit log at the given path, creating it if it does not exist.
+	///
+	/// If the file already contains records, the chain is continued from the last one.
+	pub fn open<T: Into<PathBuf>>(path: T) -> Result<Self> {
+		let path = path.into();
+		if let Some(parent) = path.parent() {
+			std::fs::create_dir_all(parent)?;
+		}
+
+		let head = match File::open(&path) {
+			Ok(file) => {
+				let mut head = AuditHead::default();
+				for line in BufReader::new(file).lines() {
+					let line = line?;
+					if !line.is_empty() {
+						head.records += 1;
+						head.hash = blake2_256(line.as_bytes());
+					}
+				}
+				head
+			},
+			Err(_) => AuditHead::default(),
+		};
+
+		let file = OpenOptions::new().create(true).append(true).open(&path)?;
+		Ok(AuditLog { path, file, head })
+	}
+
+	/// The end of the hash chain, after the last record appended.
+	pub fn head(&self) -> AuditHead {
+		self.head
+	}
+
+	/// The path of the underlying file.
+	pub fn path(&self) -> &Path {
+		&self.path
+	}
+
+	/// Append a record to the log, chaining it to the previous one.
+	fn append(&mut self, mut record: serde_json::Value) -> Result<AuditHead> {
+		record["seq"] = serde_json::Value::from(self.head.records);
+		record["prev"] = serde_json::Value::String(hex::encode(self.head.hash));
+		let line = serde_json::to_string(&record)?;
+		writeln!(self.file, "{}", line)?;
+		self.file.flush()?;
+		self.head = AuditHead { records: self.head.records + 1, hash: blake2_256(line.as_bytes()) };
+		Ok(self.head)
+	}
+}
+
+/// Kind of operation that made use of a private key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+	/// Plain signature over a message.
+	Sign,
+	/// VRF signature over a transcript.
+	VrfSign,
+}
+
+impl Operation {
+	fn as_str(&self) -> &'static str {
+		match self {
+			Operation::Sign => "sign",
+			Operation::VrfSign => "vrf_sign",
+		}
+	}
+}
+
+/// Record a use of the key identified by `key_type` and `public`, which resulted in `result`.
+///
+/// An event is always emitted into the [`AUDIT_TARGET`] target; if `log` is given the
+/// record is first appended to the file sink and the event carries the new head of the log.
+/// Failing to write the file is reported but does not fail the signing request.
+pub(crate) fn audit(
+	log: Option<&mut AuditLog>,
+	operation: Operation,
+	key_type: KeyTypeId,
+	crypto: &str,
+	public: &[u8],
+	payload: &[u8],
+	result: std::result::Result<(), &TraitError>,
+) {
+	let key_type = String::from_utf8_lossy(&key_type.0).into_owned();
+	let public = hex::encode(public);
+	let payload_hash = hex::encode(blake2_256(payload));
+	let span = tracing::Span::current();
+	let context = span.metadata()
+		.map(|m| format!("{}::{}", m.target(), m.name()))
+		.unwrap_or_else(|| "none".into());
+	let result = match result {
+		Ok(()) => "ok".to_string(),
+		Err(e) => format!("error: {}", e),
+	};
+
+	let head = log.and_then(|log| {
+		let timestamp = SystemTime::now()
+			.duration_since(UNIX_EPOCH)
+			.map(|d| d.as_millis() as u64)
+			.unwrap_or(0);
+		let record = serde_json::json!({
+			"timestamp": timestamp,
+			"operation": operation.as_str(),
+			"key_type": key_type,
+			"crypto": crypto,
+			"public": public,
+			"payload_hash": payload_hash,
+			"context": context,
+			"result": result,
+		});
+		match log.append(record) {
+			Ok(head) => Some(head),
+			Err(e) => {
+				tracing::error!(
+					target: AUDIT_TARGET,
+					"Failed to write keystore audit record to {}: {}",
+					log.path().display(),
+					e,
+				);
+				None
+			},
+		}
+	});
+
+	let (records, head) = match head {
+		Some(head) => (head.records, hex::encode(head.hash)),
+		None => (0, "none".into()),
+	};
+	tracing::info!(
+		target: AUDIT_TARGET,
+		operation = operation.as_str(),
+		key_type = key_type.as_str(),
+		crypto,
+		public = public.as_str(),
+		payload_hash = payload_hash.as_str(),
+		context = context.as_str(),
+		result = result.as_str(),
+		records,
+		head = head.as_str(),
+		"Key used",
+	);
+}
+
+/// Verify the hash chain of the audit log at `path`.
+///
+/// Returns the head of the log on success, or `Error::AuditChainBroken` with the (1-based)
+/// line number of the first record that does not chain to its predecessor.
+pub fn verify_audit_log<P: AsRef<Path>>(path: P) -> Result<AuditHead> {
+	walk_audit_log(path, |_| ())
+}
+
+/// Verify the hash chain of the audit log at `path` and that it still holds the records up to
+/// `expected`, a head seen earlier, e.g. in the events of the [`AUDIT_TARGET`].
+///
+/// Returns `Error::AuditLogTruncated` if the log holds fewer records than `expected`.
+pub fn verify_audit_log_head<P: AsRef<Path>>(path: P, expected: &AuditHead) -> Result<AuditHead> {
+	let mut hash = if expected.records == 0 { Some(GENESIS_HASH) } else { None };
+	let head = walk_audit_log(path, |head| if head.records == expected.records {
+		hash = Some(head.hash);
+	})?;
+	match hash {
+		None => Err(Error::AuditLogTruncated(expected.records, head.records)),
+		Some(hash) if hash != expected.hash => Err(Error::AuditChainBroken(expected.records as usize)),
+		Some(_) => Ok(head),
+	}
+}
+
+/// Check the chain of the log at `path`, calling `on_record` with the head after every record.
+fn walk_audit_log<P: AsRef<Path>>(path: P, mut on_record: impl FnMut(&AuditHead)) -> Result<AuditHead> {
+	let file = File::open(path)?;
+	let mut head = AuditHead::default();
+	for (index, line) in BufReader::new(file).lines().enumerate() {
+		let line = line?;
+		if line.is_empty() {
+			continue;
+		}
+		let record: serde_json::Value = serde_json::from_str(&line)?;
+		if record["prev"].as_str() != Some(hex::encode(head.hash).as_str())
+			|| record["seq"].as_u64() != Some(head.records)
+		{
+			return Err(Error::AuditChainBroken(index + 1));
+		}
+		head = AuditHead { records: head.records + 1, hash: blake2_256(line.as_bytes()) };
+		on_record(&head);
+	}
+	Ok(head)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use tempfile::TempDir;
+
+	fn write_records(path: &Path, n: usize) {
+		let mut log = AuditLog::open(path).unwrap();
+		for i in 0..n {
+			audit(Some(&mut log), Operation::Sign, KeyTypeId(*b"test"), "sr25", &[i as u8], b"msg", Ok(()));
+		}
+	}
+
+	#[test]
+	fn chain_is_continued_across_reopen() {
+		let temp_dir = TempDir::new().unwrap();
+		let path = temp_dir.path().join("audit.log");
+
+		write_records(&path, 3);
+		write_records(&path, 2);
+
+		assert_eq!(verify_audit_log(&path).unwrap().records, 5);
+	}
+
+	#[test]
+	fn tampering_is_detected() {
+		let temp_dir = TempDir::new().unwrap();
+		let path = temp_dir.path().join("audit.log");
+		write_records(&path, 3);
+
+		let content = std::fs::read_to_string(&path).unwrap();
+		let mut lines = content.lines().collect::<Vec<_>>();
+		lines.remove(1);
+		std::fs::write(&path, lines.join("\n")).unwrap();
+
+		match verify_audit_log(&path) {
+			Err(Error::AuditChainBroken(2)) => {},
+			other => panic!("Unexpected result: {:?}", other),
+		}
+	}
+
+	#[test]
+	fn truncation_is_detected_against_a_known_head() {
+		let temp_dir = TempDir::new().unwrap();
+		let path = temp_dir.path().join("audit.log");
+		write_records(&path, 3);
+		let head = AuditLog::open(&path).unwrap().head();
+		assert_eq!(verify_audit_log_head(&path, &head).unwrap(), head);
+
+		let content = std::fs::read_to_string(&path).unwrap();
+		let lines = content.lines().collect::<Vec<_>>();
+		std::fs::write(&path, lines[..2].join("\n")).unwrap();
+
+		// The remaining chain is valid, only the head tells the last record is missing.
+		assert_eq!(verify_audit_log(&path).unwrap().records, 2);
+		match verify_audit_log_head(&path, &head) {
+			Err(Error::AuditLogTruncated(3, 2)) => {},
+			other => panic!("Unexpected result: {:?}", other),
+		}
+	}
+
+	#[test]
+	fn failed_signing_is_recorded_with_its_error() {
+		let temp_dir = TempDir::new().unwrap();
+		let path = temp_dir.path().join("audit.log");
+		let mut log = AuditLog::open(&path).unwrap();
+		let error = TraitError::Unavailable;
+		audit(Some(&mut log), Operation::Sign, KeyTypeId(*b"test"), "sr25", &[0], b"msg", Err(&error));
+
+		let content = std::fs::read_to_string(&path).unwrap();
+		let record: serde_json::Value = serde_json::from_str(content.lines().next().unwrap()).unwrap();
+		assert_eq!(record["result"], "error: Keystore unavailable");
+		assert_eq!(record["seq"], 0);
+	}
+}
@@ -17,16 +17,22 @@
 //! Keystore (and session key management) for ed25519 based chains like Polkadot.
 
 #![warn(missing_docs)]
+mod audit;
+pub mod remote;
+
 use std::{collections::{HashMap, HashSet}, path::PathBuf, fs::{self, File}, io::{self, Write}, sync::Arc};
 use sp_core::{
-	crypto::{IsWrappedBy, CryptoTypePublicPair, KeyTypeId, Pair as PairT, ExposeSecret, SecretString, Public},
+	crypto::{IsWrappedBy, CryptoTypeId, CryptoTypePublicPair, KeyTypeId, Pair as PairT, ExposeSecret, SecretString, Public},
 	traits::{BareCryptoStore, Error as TraitError},
 	sr25519::{Public as Sr25519Public, Pair as Sr25519Pair},
 	vrf::{VRFTranscriptData, VRFSignature, make_transcript},
 	Encode,
 };
 use sp_application_crypto::{AppKey, AppPublic, AppPair, ed25519, sr25519, ecdsa};
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
+
+pub use audit::{AuditHead, AuditLog, AUDIT_TARGET, verify_audit_log, verify_audit_log_head};
+pub use remote::{RemoteSigner, RemoteSignerConfig};
 
 /// Keystore pointer
 pub type KeyStorePtr = Arc<RwLock<Store>>;
@@ -56,6 +62,18 @@ pub enum Error {
 	/// Keystore unavailable
 	#[display(fmt="Keystore unavailable")]
 	Unavailable,
+	/// Remote signer url is not a valid `https://` url
+	#[display(fmt="Invalid remote signer url")]
+	InvalidRemoteSignerUrl,
+	/// Remote signer url is a plain `http://` url, which was not allowed
+	#[display(fmt="Remote signer url is not an https:// url, which must be allowed explicitly")]
+	InsecureRemoteSignerUrl,
+	/// Audit log hash chain does not match at the given line
+	#[display(fmt="Audit log hash chain broken at line {}", "_0")]
+	AuditChainBroken(usize),
+	/// Audit log holds fewer records than a head seen earlier
+	#[display(fmt="Audit log truncated, {} records expected but {} found", "_0", "_1")]
+	AuditLogTruncated(u64, u64),
 }
 
 /// Keystore Result
@@ -70,6 +88,10 @@ impl From<Error> for TraitError {
 				TraitError::ValidationError(error.to_string())
 			},
 			Error::Unavailable => TraitError::Unavailable,
+			Error::InvalidRemoteSignerUrl | Error::InsecureRemoteSignerUrl | Error::AuditChainBroken(_)
+			| Error::AuditLogTruncated(..) => {
+				TraitError::Other(error.to_string())
+			},
 			Error::Io(e) => TraitError::Other(e.to_string()),
 			Error::Json(e) => TraitError::Other(e.to_string()),
 		}
@@ -91,11 +113,16 @@ impl std::error::Error for Error {
 /// Stores key pairs in a file system store + short lived key pairs in memory.
 ///
 /// Every pair that is being generated by a `seed`, will be placed in memory.
+///
+/// Alternatively the store can be backed by a [`RemoteSigner`], in which case all signing
+/// requests are forwarded and no private keys are held locally.
 pub struct Store {
 	path: Option<PathBuf>,
 	/// Map over `(KeyTypeId, Raw public key)` -> `Key phrase/seed`
 	additional: HashMap<(KeyTypeId, Vec<u8>), String>,
 	password: Option<SecretString>,
+	audit_log: Option<Mutex<AuditLog>>,
+	remote: Option<RemoteSigner>,
 }
 
 impl Store {
@@ -106,7 +133,7 @@ impl Store {
 		let path = path.into();
 		fs::create_dir_all(&path)?;
 
-		let instance = Self { path: Some(path), additional: HashMap::new(), password };
+		let instance = Self { path: Some(path), additional: HashMap::new(), password, audit_log: None, remote: None };
 		Ok(Arc::new(RwLock::new(instance)))
 	}
 
@@ -115,10 +142,129 @@ impl Store {
 		Arc::new(RwLock::new(Self {
 			path: None,
 			additional: HashMap::new(),
-			password: None
+			password: None,
+			audit_log: None,
+			remote: None,
 		}))
 	}
 
+	/// Create a new store that forwards all signing requests to a remote signer.
+	pub fn new_remote(config: RemoteSignerConfig) -> Result<KeyStorePtr> {
+		Ok(Arc::new(RwLock::new(Self {
+			path: None,
+			additional: HashMap::new(),
+			password: None,
+			audit_log: None,
+			remote: Some(RemoteSigner::new(config)?),
+		})))
+	}
+
+	/// Fail if keys are held by a remote signer, as they can not be created locally then.
+	fn ensure_local(&self) -> Result<()> {
+		match self.remote {
+			Some(_) => Err(Error::Unavailable),
+			None => Ok(()),
+		}
+	}
+
+	/// Public keys of the given crypto type held by the remote signer, none if they can't be
+	/// listed.
+	fn remote_public_keys(
+		remote: &RemoteSigner,
+		key_type: KeyTypeId,
+		crypto: CryptoTypeId,
+	) -> Vec<Vec<u8>> {
+		match remote.keys(key_type) {
+			Ok(keys) => keys.into_iter().filter(|k| k.0 == crypto).map(|k| k.1).collect(),
+			Err(e) => {
+				tracing::warn!(
+					target: remote::LOG_TARGET,
+					"Unable to list the {:?} keys of the remote signer: {:?}",
+					key_type,
+					e,
+				);
+				Vec::new()
+			},
+		}
+	}
+
+	/// Append an audit record of every signing request to the file at the given path.
+	///
+	/// Audit events are always emitted into the `keystore` tracing target, this additionally
+	/// persists them in a hash-chained file that can be checked with [`verify_audit_log`].
+	pub fn set_audit_log<T: Into<PathBuf>>(&mut self, path: T) -> Result<()> {
+		self.audit_log = Some(Mutex::new(AuditLog::open(path)?));
+		Ok(())
+	}
+
+	/// Record a use of the given key in the audit trail, once its result is known.
+	fn audit<R>(
+		&self,
+		operation: audit::Operation,
+		key: &CryptoTypePublicPair,
+		key_type: KeyTypeId,
+		payload: &[u8],
+		result: std::result::Result<R, TraitError>,
+	) -> std::result::Result<R, TraitError> {
+		let crypto = String::from_utf8_lossy(&(key.0).0).into_owned();
+		let mut log = self.audit_log.as_ref().map(|l| l.lock());
+		audit::audit(
+			log.as_deref_mut(), operation, key_type, &crypto, &key.1, payload, result.as_ref().map(|_| ()),
+		);
+		result
+	}
+
+	/// Sign `msg` with a key held by this store.
+	fn sign_locally(
+		&self,
+		id: KeyTypeId,
+		key: &CryptoTypePublicPair,
+		msg: &[u8],
+	) -> std::result::Result<Vec<u8>, TraitError> {
+		match key.0 {
+			ed25519::CRYPTO_ID => {
+				let pub_key = ed25519::Public::from_slice(key.1.as_slice());
+				let key_pair: ed25519::Pair = self
+					.key_pair_by_type::<ed25519::Pair>(&pub_key, id)
+					.map_err(|e| TraitError::from(e))?;
+				Ok(key_pair.sign(msg).encode())
+			}
+			sr25519::CRYPTO_ID => {
+				let pub_key = sr25519::Public::from_slice(key.1.as_slice());
+				let key_pair: sr25519::Pair = self
+					.key_pair_by_type::<sr25519::Pair>(&pub_key, id)
+					.map_err(|e| TraitError::from(e))?;
+				Ok(key_pair.sign(msg).encode())
+			},
+			ecdsa::CRYPTO_ID => {
+				let pub_key = ecdsa::Public::from_slice(key.1.as_slice());
+				let key_pair: ecdsa::Pair = self
+					.key_pair_by_type::<ecdsa::Pair>(&pub_key, id)
+					.map_err(|e| TraitError::from(e))?;
+				Ok(key_pair.sign(msg).encode())
+			}
+			_ => Err(TraitError::KeyNotSupported(id))
+		}
+	}
+
+	/// VRF sign `transcript_data` with a key held by this store.
+	fn sr25519_vrf_sign_locally(
+		&self,
+		key_type: KeyTypeId,
+		public: &Sr25519Public,
+		transcript_data: VRFTranscriptData,
+	) -> std::result::Result<VRFSignature, TraitError> {
+		let transcript = make_transcript(transcript_data);
+		let pair = self.key_pair_by_type::<Sr25519Pair>(public, key_type)
+			.map_err(|e| TraitError::PairNotFound(e.to_string()))?;
+
+		let (inout, proof, _) = pair.as_ref().vrf_sign(transcript);
+		Ok(VRFSignature {
+			output: inout.to_output(),
+			proof,
+		})
+	}
+
 	/// Get the key phrase for the given public key and key type from the in-memory store.
 	fn get_additional_pair(
 		&self,
@@ -141,6 +287,7 @@ impl Store {
 	///
 	/// Places it into the file system store.
 	fn insert_unknown(&self, key_type: KeyTypeId, suri: &str, public: &[u8]) -> Result<()> {
+		self.ensure_local()?;
 		if let Some(path) = self.key_file_path(public, key_type) {
 			let mut file = File::create(path).map_err(Error::Io)?;
 			serde_json::to_writer(&file, &suri).map_err(Error::Json)?;
@@ -153,6 +300,7 @@ impl Store {
 	///
 	/// Places it into the file system store.
 	pub fn insert_by_type<Pair: PairT>(&self, key_type: KeyTypeId, suri: &str) -> Result<Pair> {
+		self.ensure_local()?;
 		let pair = Pair::from_string(
 			suri,
 			self.password()
@@ -173,6 +321,7 @@ impl Store {
 	///
 	/// Places it into the file system store.
 	pub fn generate_by_type<Pair: PairT>(&self, key_type: KeyTypeId) -> Result<Pair> {
+		self.ensure_local()?;
 		let (pair, phrase, _) = Pair::generate_with_phrase(self.password());
 		if let Some(path) = self.key_file_path(pair.public().as_slice(), key_type) {
 			let mut file = File::create(path)?;
@@ -197,6 +346,7 @@ impl Store {
 		seed: &str,
 		key_type: KeyTypeId,
 	) -> Result<Pair> {
+		self.ensure_local()?;
 		let pair = Pair::from_string(seed, None).map_err(|_| Error::InvalidSeed)?;
 		self.insert_ephemeral_pair(&pair, seed, key_type);
 		Ok(pair)
@@ -305,6 +455,9 @@ impl BareCryptoStore for Store {
 		&self,
 		id: KeyTypeId
 	) -> std::result::Result<Vec<CryptoTypePublicPair>, TraitError> {
+		if let Some(remote) = &self.remote {
+			return remote.keys(id)
+		}
 		let raw_keys = self.raw_public_keys(id)?;
 		Ok(raw_keys.into_iter()
 			.fold(Vec::new(), |mut v, k| {
@@ -332,33 +485,20 @@ impl BareCryptoStore for Store {
 		key: &CryptoTypePublicPair,
 		msg: &[u8],
 	) -> std::result::Result<Vec<u8>, TraitError> {
-		match key.0 {
-			ed25519::CRYPTO_ID => {
-				let pub_key = ed25519::Public::from_slice(key.1.as_slice());
-				let key_pair: ed25519::Pair = self
-					.key_pair_by_type::<ed25519::Pair>(&pub_key, id)
-					.map_err(|e| TraitError::from(e))?;
-				Ok(key_pair.sign(msg).encode())
-			}
-			sr25519::CRYPTO_ID => {
-				let pub_key = sr25519::Public::from_slice(key.1.as_slice());
-				let key_pair: sr25519::Pair = self
-					.key_pair_by_type::<sr25519::Pair>(&pub_key, id)
-					.map_err(|e| TraitError::from(e))?;
-				Ok(key_pair.sign(msg).encode())
-			},
-			ecdsa::CRYPTO_ID => {
-				let pub_key = ecdsa::Public::from_slice(key.1.as_slice());
-				let key_pair: ecdsa::Pair = self
-					.key_pair_by_type::<ecdsa::Pair>(&pub_key, id)
-					.map_err(|e| TraitError::from(e))?;
-				Ok(key_pair.sign(msg).encode())
-			}
-			_ => Err(TraitError::KeyNotSupported(id))
-		}
+		let result = match &self.remote {
+			Some(remote) => remote.sign_with(id, key, msg),
+			None => self.sign_locally(id, key, msg),
+		};
+		self.audit(audit::Operation::Sign, key, id, msg, result)
 	}
 
 	fn sr25519_public_keys(&self, key_type: KeyTypeId) -> Vec<sr25519::Public> {
+		if let Some(remote) = &self.remote {
+			return Self::remote_public_keys(remote, key_type, sr25519::CRYPTO_ID)
+				.into_iter()
+				.map(|k| sr25519::Public::from_slice(k.as_slice()))
+				.collect()
+		}
 		self.raw_public_keys(key_type)
 			.map(|v| {
 				v.into_iter()
@@ -382,6 +522,12 @@ impl BareCryptoStore for Store {
 	}
 
 	fn ed25519_public_keys(&self, key_type: KeyTypeId) -> Vec<ed25519::Public> {
+		if let Some(remote) = &self.remote {
+			return Self::remote_public_keys(remote, key_type, ed25519::CRYPTO_ID)
+				.into_iter()
+				.map(|k| ed25519::Public::from_slice(k.as_slice()))
+				.collect()
+		}
 		self.raw_public_keys(key_type)
 			.map(|v| {
 				v.into_iter()
@@ -405,6 +551,12 @@ impl BareCryptoStore for Store {
 	}
 
 	fn ecdsa_public_keys(&self, key_type: KeyTypeId) -> Vec<ecdsa::Public> {
+		if let Some(remote) = &self.remote {
+			return Self::remote_public_keys(remote, key_type, ecdsa::CRYPTO_ID)
+				.into_iter()
+				.map(|k| ecdsa::Public::from_slice(k.as_slice()))
+				.collect()
+		}
 		self.raw_public_keys(key_type)
 			.map(|v| {
 				v.into_iter()
@@ -440,6 +592,16 @@ impl BareCryptoStore for Store {
 	}
 
 	fn has_keys(&self, public_keys: &[(Vec<u8>, KeyTypeId)]) -> bool {
+		if let Some(remote) = &self.remote {
+			return remote.has_keys(public_keys).unwrap_or_else(|e| {
+				tracing::warn!(
+					target: remote::LOG_TARGET,
+					"Unable to check the keys of the remote signer: {:?}",
+					e,
+				);
+				false
+			})
+		}
 		public_keys.iter().all(|(p, t)| self.key_phrase_by_type(&p, *t).is_ok())
 	}
 
@@ -449,15 +611,18 @@ impl BareCryptoStore for Store {
 		public: &Sr25519Public,
 		transcript_data: VRFTranscriptData,
 	) -> std::result::Result<VRFSignature, TraitError> {
-		let transcript = make_transcript(transcript_data);
-		let pair = self.key_pair_by_type::<Sr25519Pair>(public, key_type)
-			.map_err(|e| TraitError::PairNotFound(e.to_string()))?;
-
-		let (inout, proof, _) = pair.as_ref().vrf_sign(transcript);
-		Ok(VRFSignature {
-			output: inout.to_output(),
-			proof,
-		})
+		let encoded = transcript_data.encode();
+		let result = match &self.remote {
+			Some(remote) => remote.sr25519_vrf_sign(key_type, public.as_ref(), &transcript_data),
+			None => self.sr25519_vrf_sign_locally(key_type, public, transcript_data),
+		};
+		self.audit(
+			audit::Operation::VrfSign,
+			&CryptoTypePublicPair(sr25519::CRYPTO_ID, public.to_raw_vec()),
+			key_type,
+			&encoded,
+			result,
+		)
 	}
 }
 
@@ -0,0 +1,482 @@
+// Copyright 2017-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate. If not, see <http://www.gnu.org/licenses/>.
+
+//! Keystore backend forwarding signing requests to a remote signer.
+//!
+//! The remote signer is reached over JSON-RPC 2.0 on HTTPS, authenticated with a bearer
+//! token. Plain `http://` urls are refused, as the token would be sent in cleartext, unless
+//! [`RemoteSignerConfig::allow_insecure`] is set. Private keys never leave the signer, so only
+//! operations that go through `BareCryptoStore` (listing keys, `sign_with`, VRF signing) are
+//! supported; asking for a key pair or generating new keys fails with `Error::Unavailable`.
+//!
+//! The requests are made by an asynchronous client running on a runtime of its own, which
+//! also waits out the delays between the retries. The keystore only waits for the reply, up
+//! to [`RemoteSignerConfig::deadline`], without holding any lock of its own, so that the
+//! readers of the keystore don't wait for each other's requests. As the keystore is called
+//! synchronously, e.g. by the slot workers, the deadline is kept short.
+//!
+//! The signer is expected to implement the following methods, with all binary data passed
+//! as `0x`-prefixed hex strings:
+//!
+//! - `signer_keys(key_type)` -> `[[crypto_type, public], ...]`
+//! - `signer_hasKeys([[key_type, public], ...])` -> `true` if the signer holds all the keys
+//! - `signer_signWith(key_type, crypto_type, public, message)` -> `signature`
+//! - `signer_vrfSign(key_type, public, transcript)` -> `{ "output": .., "proof": .. }`,
+//!   where `transcript` is the SCALE encoded `VRFTranscriptData`.
+
+use std::{sync::mpsc, time::{Duration, Instant}};
+use std::sync::atomic::{AtomicU64, Ordering};
+use hyper::{Body, Client as HyperClient, Request, Uri, client::HttpConnector, header};
+use hyper_rustls::HttpsConnector;
+use parking_lot::Mutex;
+use sp_core::{
+	crypto::{CryptoTypeId, CryptoTypePublicPair, ExposeSecret, KeyTypeId, SecretString},
+	traits::Error as TraitError,
+	vrf::{VRFTranscriptData, VRFSignature},
+	Encode,
+};
+use serde_json::{json, Value};
+
+/// Tracing target for remote signer requests.
+pub(crate) const LOG_TARGET: &str = "keystore::remote";
+
+/// Configuration of a remote signer connection.
+#[derive(Debug, Clone)]
+pub struct RemoteSignerConfig {
+	/// Url of the signer, e.g. `https://10.0.0.2:8443/sign`.
+	pub url: String,
+	/// Bearer token sent with every request.
+	pub auth_token: Option<SecretString>,
+	/// Timeout for a single request to the signer, connecting included.
+	pub timeout: Duration,
+	/// Number of times a failed request is retried.
+	pub retries: u32,
+	/// Delay before the first retry; doubled for every subsequent retry.
+	pub retry_delay: Duration,
+	/// Longest time a call may take, all its attempts and the delays between them included.
+	/// The call fails, and its remaining attempts are abandoned, past it.
+	pub deadline: Duration,
+	/// Accept a plain `http://` url, sending the token in cleartext.
+	pub allow_insecure: bool,
+}
+
+impl RemoteSignerConfig {
+	/// Create a configuration with default timeout and retry policy for the given url.
+	pub fn new(url: String, auth_token: Option<SecretString>) -> Self {
+		RemoteSignerConfig {
+			url,
+			auth_token,
+			timeout: Duration::from_millis(300),
+			retries: 2,
+			retry_delay: Duration::from_millis(50),
+			deadline: Duration::from_secs(1),
+			allow_insecure: false,
+		}
+	}
+}
+
+/// Error of a single request to the remote signer.
+#[derive(Debug, derive_more::Display)]
+enum RequestError {
+	/// The signer could not be reached or returned garbage. Worth retrying.
+	#[display(fmt = "Transport error: {}", _0)]
+	Transport(String),
+	/// The signer answered with a JSON-RPC error. Retrying will not help.
+	#[display(fmt = "Remote signer error: {}", _0)]
+	Rpc(String),
+}
+
+type Client = HyperClient<HttpsConnector<HttpConnector>, Body>;
+
+/// Connection to a remote signer.
+pub struct RemoteSigner {
+	config: RemoteSignerConfig,
+	uri: Uri,
+	next_id: AtomicU64,
+	client: Client,
+	handle: tokio::runtime::Handle,
+	/// The runtime of the client, only locked to be shut down.
+	runtime: Mutex<Option<tokio::runtime::Runtime>>,
+}
+
+impl RemoteSigner {
+	/// Create a new remote signer connection.
+	///
+	/// No connection is made until the first request.
+	pub fn new(config: RemoteSignerConfig) -> crate::Result<Self> {
+		let uri = parse_url(&config.url, config.allow_insecure)?;
+		let runtime = tokio::runtime::Builder::new()
+			.threaded_scheduler()
+			.core_threads(1)
+			.enable_all()
+			.thread_name("remote-signer")
+			.build()?;
+		let handle = runtime.handle().clone();
+		// The connector must be created within the runtime it runs on.
+		let client = handle.enter(|| HyperClient::builder().build(HttpsConnector::new()));
+		Ok(RemoteSigner {
+			config,
+			uri,
+			next_id: AtomicU64::new(1),
+			client,
+			handle,
+			runtime: Mutex::new(Some(runtime)),
+		})
+	}
+
+	/// Issue a JSON-RPC call, retrying transport failures according to the retry policy.
+	fn call(&self, method: &'static str, params: Value) -> Result<Value, TraitError> {
+		let span = tracing::debug_span!(target: LOG_TARGET, "remote_signer_request", method);
+		let _guard = span.enter();
+
+		let started = Instant::now();
+		let (tx, rx) = mpsc::sync_channel(1);
+		let deadline = self.config.deadline;
+		let request = tokio::time::timeout(deadline, Self::call_with_retries(
+			self.client.clone(),
+			self.uri.clone(),
+			self.config.clone(),
+			self.next_id.fetch_add(1, Ordering::Relaxed),
+			method,
+			params,
+		));
+		self.handle.spawn(async move {
+			let _ = tx.send(request.await);
+		});
+		let result = rx.recv_timeout(deadline).ok().and_then(Result::ok)
+			.unwrap_or_else(|| (0, Err(RequestError::Transport(format!("Deadline of {:?} reached", deadline)))));
+		let elapsed = started.elapsed().as_millis() as u64;
+		match result {
+			(attempt, Ok(value)) => {
+				tracing::debug!(
+					target: LOG_TARGET,
+					method,
+					attempt,
+					latency_ms = elapsed,
+					"Remote signer request succeeded",
+				);
+				Ok(value)
+			},
+			(attempt, Err(RequestError::Rpc(e))) => {
+				tracing::warn!(
+					target: LOG_TARGET,
+					method,
+					attempt,
+					latency_ms = elapsed,
+					"Remote signer rejected request: {}",
+					e,
+				);
+				Err(TraitError::Other(e))
+			},
+			(attempt, Err(RequestError::Transport(e))) => {
+				tracing::error!(
+					target: LOG_TARGET,
+					method,
+					attempt,
+					latency_ms = elapsed,
+					"Remote signer unavailable: {}",
+					e,
+				);
+				Err(TraitError::Unavailable)
+			},
+		}
+	}
+
+	/// The attempts of a call, returning the result of the last one and the number made.
+	async fn call_with_retries(
+		client: Client,
+		uri: Uri,
+		config: RemoteSignerConfig,
+		id: u64,
+		method: &'static str,
+		params: Value,
+	) -> (u32, Result<Value, RequestError>) {
+		let body = json!({
+			"jsonrpc": "2.0",
+			"id": id,
+			"method": method,
+			"params": params,
+		}).to_string();
+
+		let mut delay = config.retry_delay;
+		let mut attempt = 0;
+		loop {
+			attempt += 1;
+			let request = Self::request(&client, &uri, config.auth_token.as_ref(), body.clone());
+			let result = match tokio::time::timeout(config.timeout, request).await {
+				Ok(result) => result,
+				Err(_) => Err(RequestError::Transport(format!("Timeout after {:?}", config.timeout))),
+			};
+			match result {
+				Err(RequestError::Transport(e)) if attempt <= config.retries => {
+					tracing::debug!(
+						target: LOG_TARGET,
+						method,
+						attempt,
+						"Remote signer request failed, retrying in {:?}: {}",
+						delay,
+						e,
+					);
+					tokio::time::delay_for(delay).await;
+					delay *= 2;
+				},
+				result => return (attempt, result),
+			}
+		}
+	}
+
+	/// A single HTTP round trip to the signer.
+	async fn request(
+		client: &Client,
+		uri: &Uri,
+		auth_token: Option<&SecretString>,
+		body: String,
+	) -> Result<Value, RequestError> {
+		let transport = |e: hyper::Error| RequestError::Transport(e.to_string());
+
+		let mut request = Request::post(uri.clone())
+			.header(header::CONTENT_TYPE, "application/json");
+		if let Some(token) = auth_token {
+			request = request.header(header::AUTHORIZATION, format!("Bearer {}", token.expose_secret()));
+		}
+		let request = request.body(Body::from(body))
+			.map_err(|e| RequestError::Transport(e.to_string()))?;
+
+		let response = client.request(request).await.map_err(transport)?;
+		let status = response.status();
+		if !status.is_success() {
+			return Err(RequestError::Transport(format!("HTTP status {}", status)));
+		}
+		let body = hyper::body::to_bytes(response.into_body()).await.map_err(transport)?;
+
+		let mut reply: Value = serde_json::from_slice(&body)
+			.map_err(|e| RequestError::Transport(e.to_string()))?;
+		if let Some(error) = reply.get("error") {
+			return Err(RequestError::Rpc(error.to_string()));
+		}
+		Ok(reply["result"].take())
+	}
+
+	/// Returns all keys of the given key type the signer holds.
+	pub fn keys(&self, id: KeyTypeId) -> Result<Vec<CryptoTypePublicPair>, TraitError> {
+		let result = self.call("signer_keys", json!([to_hex(&id.0)]))?;
+		let keys = result.as_array().ok_or_else(invalid_response)?;
+		keys.iter()
+			.map(|key| {
+				let crypto = key.get(0).and_then(from_hex).ok_or_else(invalid_response)?;
+				let public = key.get(1).and_then(from_hex).ok_or_else(invalid_response)?;
+				if crypto.len() != 4 {
+					return Err(invalid_response())
+				}
+				let mut crypto_id = [0u8; 4];
+				crypto_id.copy_from_slice(&crypto);
+				Ok(CryptoTypePublicPair(CryptoTypeId(crypto_id), public))
+			})
+			.collect()
+	}
+
+	/// Whether the signer holds all the given keys, asked in a single request.
+	pub fn has_keys(&self, public_keys: &[(Vec<u8>, KeyTypeId)]) -> Result<bool, TraitError> {
+		let keys = public_keys.iter()
+			.map(|(public, id)| json!([to_hex(&id.0), to_hex(public)]))
+			.collect::<Vec<_>>();
+		let result = self.call("signer_hasKeys", json!([keys]))?;
+		result.as_bool().ok_or_else(invalid_response)
+	}
+
+	/// Sign the message with the given key.
+	pub fn sign_with(
+		&self,
+		id: KeyTypeId,
+		key: &CryptoTypePublicPair,
+		msg: &[u8],
+	) -> Result<Vec<u8>, TraitError> {
+		let result = self.call(
+			"signer_signWith",
+			json!([to_hex(&id.0), to_hex(&(key.0).0), to_hex(&key.1), to_hex(msg)]),
+		)?;
+		from_hex(&result).ok_or_else(invalid_response)
+	}
+
+	/// Produce a VRF signature over the given transcript.
+	pub fn sr25519_vrf_sign(
+		&self,
+		id: KeyTypeId,
+		public: &[u8],
+		transcript_data: &VRFTranscriptData,
+	) -> Result<VRFSignature, TraitError> {
+		let result = self.call(
+			"signer_vrfSign",
+			json!([to_hex(&id.0), to_hex(public), to_hex(&transcript_data.encode())]),
+		)?;
+		let output = result.get("output").and_then(from_hex).ok_or_else(invalid_response)?;
+		let proof = result.get("proof").and_then(from_hex).ok_or_else(invalid_response)?;
+		Ok(VRFSignature {
+			output: schnorrkel::vrf::VRFOutput::from_bytes(&output)
+				.map_err(|_| invalid_response())?,
+			proof: schnorrkel::vrf::VRFProof::from_bytes(&proof)
+				.map_err(|_| invalid_response())?,
+		})
+	}
+}
+
+fn invalid_response() -> TraitError {
+	TraitError::Other("Invalid response from remote signer".into())
+}
+
+fn to_hex(data: &[u8]) -> String {
+	format!("0x{}", hex::encode(data))
+}
+
+fn from_hex(value: &Value) -> Option<Vec<u8>> {
+	let s = value.as_str()?;
+	hex::decode(s.strip_prefix("0x").unwrap_or(s)).ok()
+}
+
+impl Drop for RemoteSigner {
+	fn drop(&mut self) {
+		// The keystore may be dropped within another runtime, which must not block.
+		if let Some(runtime) = self.runtime.lock().take() {
+			runtime.shutdown_background();
+		}
+	}
+}
+
+/// Parse the url of the signer, refusing plain `http://` unless `allow_insecure`.
+fn parse_url(url: &str, allow_insecure: bool) -> crate::Result<Uri> {
+	let uri: Uri = url.parse().map_err(|_| crate::Error::InvalidRemoteSignerUrl)?;
+	if uri.host().map_or(true, str::is_empty) {
+		return Err(crate::Error::InvalidRemoteSignerUrl)
+	}
+	match uri.scheme_str() {
+		Some("https") => Ok(uri),
+		Some("http") if allow_insecure => Ok(uri),
+		Some("http") => Err(crate::Error::InsecureRemoteSignerUrl),
+		_ => Err(crate::Error::InvalidRemoteSignerUrl),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::{io::{Read, Write}, net::TcpListener};
+	use std::sync::{Arc, atomic::AtomicUsize};
+
+	/// Serve `responses` in order, one per connection, counting the requests.
+	fn serve(responses: Vec<Option<&'static str>>) -> (String, Arc<AtomicUsize>) {
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let url = format!("http://{}/", listener.local_addr().unwrap());
+		let count = Arc::new(AtomicUsize::new(0));
+		let counter = count.clone();
+		std::thread::spawn(move || {
+			for response in responses {
+				let (mut stream, _) = listener.accept().unwrap();
+				let mut buf = [0u8; 4096];
+				let _ = stream.read(&mut buf);
+				counter.fetch_add(1, Ordering::SeqCst);
+				if let Some(body) = response {
+					let reply = format!(
+						"HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: {}\r\n\r\n{}",
+						body.len(),
+						body,
+					);
+					let _ = stream.write_all(reply.as_bytes());
+				}
+			}
+		});
+		(url, count)
+	}
+
+	fn signer(url: String) -> RemoteSigner {
+		let mut config = RemoteSignerConfig::new(url, None);
+		config.retry_delay = Duration::from_millis(1);
+		config.deadline = Duration::from_secs(5);
+		config.allow_insecure = true;
+		RemoteSigner::new(config).unwrap()
+	}
+
+	#[test]
+	fn refuses_insecure_urls_unless_allowed() {
+		assert!(parse_url("https://host:9000/sign", false).is_ok());
+		assert!(matches!(parse_url("http://host/sign", false), Err(crate::Error::InsecureRemoteSignerUrl)));
+		assert!(parse_url("http://host/sign", true).is_ok());
+		assert!(matches!(parse_url("ftp://host", true), Err(crate::Error::InvalidRemoteSignerUrl)));
+		assert!(matches!(parse_url("/sign", true), Err(crate::Error::InvalidRemoteSignerUrl)));
+	}
+
+	#[test]
+	fn sign_with_is_forwarded() {
+		let (url, _) = serve(vec![Some(r#"{"jsonrpc":"2.0","id":1,"result":"0x0102"}"#)]);
+		let key = CryptoTypePublicPair(CryptoTypeId(*b"sr25"), vec![1; 32]);
+
+		let signature = signer(url).sign_with(KeyTypeId(*b"test"), &key, b"msg").unwrap();
+
+		assert_eq!(signature, vec![1, 2]);
+	}
+
+	#[test]
+	fn transport_failures_are_retried() {
+		let (url, count) = serve(vec![
+			None,
+			None,
+			Some(r#"{"jsonrpc":"2.0","id":3,"result":[["0x73723235","0x0101"]]}"#),
+		]);
+
+		let keys = signer(url).keys(KeyTypeId(*b"test")).unwrap();
+
+		assert_eq!(keys, vec![CryptoTypePublicPair(CryptoTypeId(*b"sr25"), vec![1, 1])]);
+		assert_eq!(count.load(Ordering::SeqCst), 3);
+	}
+
+	#[test]
+	fn rpc_errors_are_not_retried() {
+		let (url, count) = serve(vec![
+			Some(r#"{"jsonrpc":"2.0","id":1,"error":{"code":-1,"message":"denied"}}"#),
+		]);
+
+		match signer(url).keys(KeyTypeId(*b"test")) {
+			Err(TraitError::Other(_)) => {},
+			other => panic!("Unexpected result: {:?}", other),
+		}
+		assert_eq!(count.load(Ordering::SeqCst), 1);
+	}
+
+	#[test]
+	fn calls_fail_once_the_deadline_is_reached() {
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let mut config = RemoteSignerConfig::new(format!("http://{}/", listener.local_addr().unwrap()), None);
+		config.allow_insecure = true;
+		config.timeout = Duration::from_secs(5);
+		config.deadline = Duration::from_millis(50);
+
+		// The signer accepts the connection but never answers.
+		let started = Instant::now();
+		match RemoteSigner::new(config).unwrap().keys(KeyTypeId(*b"test")) {
+			Err(TraitError::Unavailable) => {},
+			other => panic!("Unexpected result: {:?}", other),
+		}
+		assert!(started.elapsed() < Duration::from_secs(5));
+	}
+
+	#[test]
+	fn has_keys_is_a_single_request() {
+		let (url, count) = serve(vec![Some(r#"{"jsonrpc":"2.0","id":1,"result":true}"#)]);
+		let keys = vec![(vec![1; 32], KeyTypeId(*b"test")), (vec![2; 32], KeyTypeId(*b"babe"))];
+
+		assert!(signer(url).has_keys(&keys).unwrap());
+		assert_eq!(count.load(Ordering::SeqCst), 1);
+	}
+}
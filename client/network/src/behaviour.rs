@@ -16,7 +16,7 @@
 
 use crate::{
 	config::{ProtocolId, Role}, block_requests, light_client_handler, finality_requests,
-	peer_info, discovery::{DiscoveryBehaviour, DiscoveryConfig, DiscoveryOut},
+	peer_info, trace_collection, discovery::{DiscoveryBehaviour, DiscoveryConfig, DiscoveryOut},
 	protocol::{message::{self, Roles}, CustomMessageOutcome, NotificationsSink, Protocol},
 	ObservedRole, DhtEvent, ExHashT,
 };
@@ -27,7 +27,7 @@ use libp2p::NetworkBehaviour;
 use libp2p::core::{Multiaddr, PeerId, PublicKey};
 use libp2p::identify::IdentifyInfo;
 use libp2p::kad::record;
-use libp2p::swarm::{NetworkBehaviourAction, NetworkBehaviourEventProcess, PollParameters};
+use libp2p::swarm::{NetworkBehaviourAction, NetworkBehaviourEventProcess, PollParameters, toggle::Toggle};
 use log::debug;
 use sp_consensus::{BlockOrigin, import_queue::{IncomingBlock, Origin}};
 use sp_runtime::{traits::{Block as BlockT, NumberFor}, ConsensusEngineId, Justification};
@@ -56,6 +56,8 @@ pub struct Behaviour<B: BlockT, H: ExHashT> {
 	finality_proof_requests: finality_requests::FinalityProofRequests<B>,
 	/// Light client request handling.
 	light_client_handler: light_client_handler::LightClientHandler<B>,
+	/// Trace summaries sending and collection, only enabled with a collector or reporters.
+	trace_collection: Toggle<trace_collection::TraceCollection>,
 
 	/// Queue of events to produce for the outside.
 	#[behaviour(ignore)]
@@ -148,6 +150,14 @@ pub enum BehaviourOut<B: BlockT> {
 	/// Events generated by a DHT as a response to get_value or put_value requests as well as the
 	/// request duration.
 	Dht(DhtEvent, Duration),
+
+	/// Received a trace summary from one of our reporters.
+	TraceSummary {
+		/// Reporter which sent the summary.
+		peer: PeerId,
+		/// The summary, as sent by the reporter.
+		summary: Vec<u8>,
+	},
 }
 
 impl<B: BlockT, H: ExHashT> Behaviour<B, H> {
@@ -160,6 +170,7 @@ impl<B: BlockT, H: ExHashT> Behaviour<B, H> {
 		block_requests: block_requests::BlockRequests<B>,
 		finality_proof_requests: finality_requests::FinalityProofRequests<B>,
 		light_client_handler: light_client_handler::LightClientHandler<B>,
+		trace_collection: Option<trace_collection::TraceCollection>,
 		disco_config: DiscoveryConfig,
 	) -> Self {
 		Behaviour {
@@ -169,6 +180,7 @@ impl<B: BlockT, H: ExHashT> Behaviour<B, H> {
 			block_requests,
 			finality_proof_requests,
 			light_client_handler,
+			trace_collection: trace_collection.into(),
 			events: VecDeque::new(),
 			role,
 		}
@@ -260,6 +272,13 @@ impl<B: BlockT, H: ExHashT> Behaviour<B, H> {
 	pub fn light_client_request(&mut self, r: light_client_handler::Request<B>) -> Result<(), light_client_handler::Error> {
 		self.light_client_handler.request(r)
 	}
+
+	/// Send a trace summary to the collector, if any.
+	pub fn send_trace_summary(&mut self, summary: Vec<u8>) {
+		if let Some(trace_collection) = self.trace_collection.as_mut() {
+			trace_collection.send_summary(summary)
+		}
+	}
 }
 
 fn reported_roles_to_observed_role(local_role: &Role, remote: &PeerId, roles: Roles) -> ObservedRole {
@@ -412,6 +431,15 @@ impl<B: BlockT, H: ExHashT> NetworkBehaviourEventProcess<finality_requests::Even
 	}
 }
 
+impl<B: BlockT, H: ExHashT> NetworkBehaviourEventProcess<trace_collection::Event> for Behaviour<B, H> {
+	fn inject_event(&mut self, event: trace_collection::Event) {
+		match event {
+			trace_collection::Event::Summary { peer, summary } =>
+				self.events.push_back(BehaviourOut::TraceSummary { peer, summary }),
+		}
+	}
+}
+
 impl<B: BlockT, H: ExHashT> NetworkBehaviourEventProcess<peer_info::PeerInfoEvent>
 	for Behaviour<B, H> {
 	fn inject_event(&mut self, event: peer_info::PeerInfoEvent) {
@@ -425,6 +425,12 @@ pub struct NetworkConfiguration {
 	pub max_parallel_downloads: u32,
 	/// Should we insert non-global addresses into the DHT?
 	pub allow_non_globals_in_dht: bool,
+	/// Node to send the trace summaries of the imported blocks to.
+	pub trace_collector: Option<MultiaddrWithPeerId>,
+	/// The summary of one block out of this many, by number, is sent to the trace collector.
+	pub trace_collector_sample: u32,
+	/// Nodes allowed to send their trace summaries to this node, which collects them if any.
+	pub trace_reporters: Vec<PeerId>,
 }
 
 impl NetworkConfiguration {
@@ -456,6 +462,9 @@ impl NetworkConfiguration {
 			},
 			max_parallel_downloads: 5,
 			allow_non_globals_in_dht: false,
+			trace_collector: None,
+			trace_collector_sample: 10,
+			trace_reporters: Vec::new(),
 		}
 	}
 }
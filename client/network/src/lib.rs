@@ -255,6 +255,7 @@ mod on_demand_layer;
 mod protocol;
 mod schema;
 mod service;
+mod trace_collection;
 mod transport;
 mod utils;
 
@@ -21,6 +21,10 @@
 //! encoded as protocol buffers (cf. `api.v1.proto`).
 //!
 //! For every outgoing request we likewise open a separate substream.
+//!
+//! Every incoming request is served in a span of the [`LIGHT_CLIENT_TARGET`], with its `kind`,
+//! the `peer` it came from and the `proof_size` of the response, and counted in the metrics by
+//! kind, with the sizes of the proofs and the time taken to build them.
 
 #![allow(unused)]
 
@@ -55,6 +59,9 @@ use libp2p::{
 	}
 };
 use nohash_hasher::IntMap;
+use prometheus_endpoint::{
+	register, CounterVec, HistogramOpts, HistogramVec, Opts, PrometheusError, Registry, U64,
+};
 use prost::Message;
 use sc_client_api::{
 	StorageProof,
@@ -88,6 +95,9 @@ use wasm_timer::Instant;
 /// Reputation change for a peer when a request timed out.
 pub(crate) const TIMEOUT_REPUTATION_CHANGE: i32 = -(1 << 8);
 
+/// Target of the spans of the light client requests served.
+pub const LIGHT_CLIENT_TARGET: &str = "sub-libp2p::light";
+
 /// Configuration options for `LightClientHandler` behaviour.
 #[derive(Debug, Clone)]
 pub struct Config {
@@ -303,20 +313,69 @@ pub struct LightClientHandler<B: Block> {
 	next_request_id: RequestId,
 	/// Handle to use for reporting misbehaviour of peers.
 	peerset: sc_peerset::PeersetHandle,
+	/// Metrics of the requests served, if a registry was provided.
+	metrics: Option<Metrics>,
+}
+
+/// Metrics of the light client requests served, by kind of request.
+struct Metrics {
+	requests: CounterVec<U64>,
+	failures: CounterVec<U64>,
+	proof_bytes: CounterVec<U64>,
+	build_time: HistogramVec,
+}
+
+impl Metrics {
+	fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+		Ok(Metrics {
+			requests: register(CounterVec::new(
+				Opts::new(
+					"sub_libp2p_light_client_requests_served_total",
+					"Number of light client requests served",
+				),
+				&["kind"],
+			)?, registry)?,
+			failures: register(CounterVec::new(
+				Opts::new(
+					"sub_libp2p_light_client_requests_failed_total",
+					"Number of light client requests that could not be served",
+				),
+				&["kind"],
+			)?, registry)?,
+			proof_bytes: register(CounterVec::new(
+				Opts::new(
+					"sub_libp2p_light_client_proof_bytes_total",
+					"Total size of the proofs sent in response to light client requests",
+				),
+				&["kind"],
+			)?, registry)?,
+			build_time: register(HistogramVec::new(
+				HistogramOpts {
+					common_opts: Opts::new(
+						"sub_libp2p_light_client_request_build_seconds",
+						"Time taken to build the responses to light client requests",
+					),
+					buckets: vec![0.0001, 0.0005, 0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0, 5.0],
+				},
+				&["kind"],
+			)?, registry)?,
+		})
+	}
 }
 
 impl<B> LightClientHandler<B>
 where
 	B: Block,
 {
-	/// Construct a new light client handler.
+	/// Construct a new light client handler, registering its metrics in `metrics_registry`.
 	pub fn new(
 		cfg: Config,
 		chain: Arc<dyn Client<B>>,
 		checker: Arc<dyn light::FetchChecker<B>>,
 		peerset: sc_peerset::PeersetHandle,
-	) -> Self {
-		LightClientHandler {
+		metrics_registry: Option<&Registry>,
+	) -> Result<Self, PrometheusError> {
+		Ok(LightClientHandler {
 			config: cfg,
 			chain,
 			checker,
@@ -326,7 +385,8 @@ where
 			outstanding: IntMap::default(),
 			next_request_id: 1,
 			peerset,
-		}
+			metrics: metrics_registry.map(Metrics::register).transpose()?,
+		})
 	}
 
 	/// We rely on external information about peers best blocks as we lack the
@@ -744,6 +804,30 @@ where
 	}
 }
 
+/// The kind of the light client `request`, labelling its metrics.
+fn request_kind(request: &schema::v1::light::request::Request) -> &'static str {
+	match request {
+		schema::v1::light::request::Request::RemoteCallRequest(_) => "remote_call",
+		schema::v1::light::request::Request::RemoteReadRequest(_) => "remote_read",
+		schema::v1::light::request::Request::RemoteHeaderRequest(_) => "remote_header",
+		schema::v1::light::request::Request::RemoteReadChildRequest(_) => "remote_read_child",
+		schema::v1::light::request::Request::RemoteChangesRequest(_) => "remote_changes",
+	}
+}
+
+/// The size of the encoded proofs of the `response`.
+fn proof_size(response: &schema::v1::light::Response) -> usize {
+	use schema::v1::light::response::Response;
+	match &response.response {
+		Some(Response::RemoteCallResponse(r)) => r.proof.len(),
+		Some(Response::RemoteReadResponse(r)) => r.proof.len(),
+		Some(Response::RemoteHeaderResponse(r)) => r.proof.len(),
+		Some(Response::RemoteChangesResponse(r)) =>
+			r.proof.iter().map(Vec::len).sum::<usize>() + r.roots_proof.len(),
+		None => 0,
+	}
+}
+
 impl<B> NetworkBehaviour for LightClientHandler<B>
 where
 	B: Block
@@ -822,22 +906,45 @@ where
 			// An incoming request from remote has been received.
 			Event::Request(request, mut stream) => {
 				log::trace!("incoming request from {}", peer);
-				let result = match &request.request {
-					Some(schema::v1::light::request::Request::RemoteCallRequest(r)) =>
+				let request = match request.request {
+					Some(request) => request,
+					None => {
+						log::debug!("ignoring request without request data from peer {}", peer);
+						return
+					}
+				};
+				let kind = request_kind(&request);
+				let span = sp_tracing::tracing::debug_span!(
+					target: LIGHT_CLIENT_TARGET,
+					"serve_request",
+					kind,
+					peer = %peer,
+					proof_size = sp_tracing::tracing::field::Empty,
+				);
+				let started = Instant::now();
+				let result = span.in_scope(|| match &request {
+					schema::v1::light::request::Request::RemoteCallRequest(r) =>
 						self.on_remote_call_request(&peer, r),
-					Some(schema::v1::light::request::Request::RemoteReadRequest(r)) =>
+					schema::v1::light::request::Request::RemoteReadRequest(r) =>
 						self.on_remote_read_request(&peer, r),
-					Some(schema::v1::light::request::Request::RemoteHeaderRequest(r)) =>
+					schema::v1::light::request::Request::RemoteHeaderRequest(r) =>
 						self.on_remote_header_request(&peer, r),
-					Some(schema::v1::light::request::Request::RemoteReadChildRequest(r)) =>
+					schema::v1::light::request::Request::RemoteReadChildRequest(r) =>
 						self.on_remote_read_child_request(&peer, r),
-					Some(schema::v1::light::request::Request::RemoteChangesRequest(r)) =>
+					schema::v1::light::request::Request::RemoteChangesRequest(r) =>
 						self.on_remote_changes_request(&peer, r),
-					None => {
-						log::debug!("ignoring request without request data from peer {}", peer);
-						return
+				});
+				let proof_size = result.as_ref().map(proof_size).unwrap_or_default();
+				span.record("proof_size", &(proof_size as u64));
+				if let Some(metrics) = &self.metrics {
+					metrics.requests.with_label_values(&[kind]).inc();
+					if result.is_err() {
+						metrics.failures.with_label_values(&[kind]).inc();
 					}
-				};
+					metrics.proof_bytes.with_label_values(&[kind]).inc_by(proof_size as u64);
+					metrics.build_time.with_label_values(&[kind])
+						.observe(started.elapsed().as_secs_f64());
+				}
 				match result {
 					Ok(response) => {
 						log::trace!("enqueueing response for peer {}", peer);
@@ -1347,6 +1454,15 @@ mod tests {
 	}
 
 	fn make_swarm(ok: bool, ps: sc_peerset::PeersetHandle, cf: super::Config) -> Swarm {
+		make_swarm_with_registry(ok, ps, cf, None)
+	}
+
+	fn make_swarm_with_registry(
+		ok: bool,
+		ps: sc_peerset::PeersetHandle,
+		cf: super::Config,
+		registry: Option<&prometheus_endpoint::Registry>,
+	) -> Swarm {
 		let client = Arc::new(substrate_test_runtime_client::new());
 		let checker = Arc::new(DummyFetchChecker { ok, _mark: std::marker::PhantomData });
 		let id_key = identity::Keypair::generate_ed25519();
@@ -1359,7 +1475,7 @@ mod tests {
 			.map(|(peer, muxer), _| (peer, StreamMuxerBox::new(muxer)))
 			.map_err(|e| io::Error::new(io::ErrorKind::Other, e))
 			.boxed();
-		Swarm::new(transport, LightClientHandler::new(cf, client, checker, ps), local_peer)
+		Swarm::new(transport, LightClientHandler::new(cf, client, checker, ps, registry).unwrap(), local_peer)
 	}
 
 	struct DummyFetchChecker<B> {
@@ -1503,7 +1619,7 @@ mod tests {
 	{
 		let client = Arc::new(substrate_test_runtime_client::new());
 		let checker = Arc::new(DummyFetchChecker { ok, _mark: std::marker::PhantomData });
-		LightClientHandler::new(cf, client, checker, ps)
+		LightClientHandler::new(cf, client, checker, ps, None).unwrap()
 	}
 
 	fn empty_dialer() -> ConnectedPoint {
@@ -1934,10 +2050,17 @@ mod tests {
 	}
 
 	fn send_receive(request: Request<Block>) {
+		send_receive_with_registry(request, None)
+	}
+
+	fn send_receive_with_registry(
+		request: Request<Block>,
+		registry: Option<&prometheus_endpoint::Registry>,
+	) {
 		// We start a swarm on the listening side which awaits incoming requests and answers them:
 		let local_pset = peerset();
 		let local_listen_addr: libp2p::Multiaddr = libp2p::multiaddr::Protocol::Memory(rand::random()).into();
-		let mut local_swarm = make_swarm(true, local_pset.1, make_config());
+		let mut local_swarm = make_swarm_with_registry(true, local_pset.1, make_config(), registry);
 		Swarm::listen_on(&mut local_swarm, local_listen_addr.clone()).unwrap();
 
 		// We also start a swarm that makes requests and awaits responses:
@@ -1972,6 +2095,28 @@ mod tests {
 		//              ^--- from `DummyFetchChecker::check_execution_proof`
 	}
 
+	#[test]
+	fn served_requests_are_measured() {
+		let registry = prometheus_endpoint::Registry::new();
+		let chan = oneshot::channel();
+		let request = light::RemoteCallRequest {
+			block: Default::default(),
+			header: dummy_header(),
+			method: "test".into(),
+			call_data: vec![],
+			retry_count: None,
+		};
+		send_receive_with_registry(Request::Call { request, sender: chan.0 }, Some(&registry));
+		assert_eq!(vec![42], task::block_on(chan.1).unwrap().unwrap());
+
+		let served = registry.gather().into_iter()
+			.find(|family| family.get_name() == "sub_libp2p_light_client_requests_served_total")
+			.expect("the served requests are counted");
+		let metric = &served.get_metric()[0];
+		assert_eq!(metric.get_label()[0].get_value(), "remote_call");
+		assert_eq!(metric.get_counter().get_value(), 1.0);
+	}
+
 	#[test]
 	fn send_receive_read() {
 		let chan = oneshot::channel();
@@ -38,7 +38,7 @@ use crate::{
 		NetworkState, NotConnectedPeer as NetworkStateNotConnectedPeer, Peer as NetworkStatePeer,
 	},
 	on_demand_layer::AlwaysBadChecker,
-	light_client_handler, block_requests, finality_requests,
+	light_client_handler, block_requests, finality_requests, trace_collection,
 	protocol::{self, event::Event, NotifsHandlerError, LegacyConnectionKillError, NotificationsSink, Ready, sync::SyncState, PeerInfo, Protocol},
 	transport, ReputationChange,
 };
@@ -106,6 +106,8 @@ pub struct NetworkService<B: BlockT + 'static, H: ExHashT> {
 	/// Field extracted from the [`Metrics`] struct and necessary to report the
 	/// notifications-related metrics.
 	notifications_sizes_metric: Option<HistogramVec>,
+	/// Send the trace summary of one block out of this many to the collector, if any.
+	trace_collector_sample: Option<u32>,
 	/// Marker to pin the `H` generic. Serves no purpose except to not break backwards
 	/// compatibility.
 	_marker: PhantomData<H>,
@@ -245,6 +247,10 @@ impl<B: BlockT + 'static, H: ExHashT> NetworkWorker<B, H> {
 			.map(|od| od.checker().clone())
 			.unwrap_or_else(|| Arc::new(AlwaysBadChecker));
 
+		let trace_collector_sample = params.network_config.trace_collector_sample.max(1);
+		let trace_collector_sample = params.network_config.trace_collector.as_ref()
+			.map(|_| trace_collector_sample);
+
 		let num_connected = Arc::new(AtomicUsize::new(0));
 		let is_major_syncing = Arc::new(AtomicBool::new(false));
 		let (protocol, peerset_handle) = Protocol::new(
@@ -285,7 +291,21 @@ impl<B: BlockT + 'static, H: ExHashT> NetworkWorker<B, H> {
 					params.chain,
 					checker,
 					peerset_handle.clone(),
-				)
+					params.metrics_registry.as_ref(),
+				)?
+			};
+			// The protocol is neither advertised nor used without a collector or reporters.
+			let trace_collection = if params.network_config.trace_collector.is_some()
+				|| !params.network_config.trace_reporters.is_empty()
+			{
+				let mut config = trace_collection::Config::new(&params.protocol_id);
+				if let Some(collector) = &params.network_config.trace_collector {
+					config.set_collector(collector.peer_id.clone(), collector.multiaddr.clone());
+				}
+				config.set_reporters(params.network_config.trace_reporters.iter().cloned());
+				Some(trace_collection::TraceCollection::new(config))
+			} else {
+				None
 			};
 
 			let discovery_config = {
@@ -317,6 +337,7 @@ impl<B: BlockT + 'static, H: ExHashT> NetworkWorker<B, H> {
 				block_requests,
 				finality_proof_requests,
 				light_client_handler,
+				trace_collection,
 				discovery_config
 			);
 
@@ -388,6 +409,7 @@ impl<B: BlockT + 'static, H: ExHashT> NetworkWorker<B, H> {
 			protocol_name_by_engine,
 			notifications_sizes_metric:
 				metrics.as_ref().map(|metrics| metrics.notifications_sizes.clone()),
+			trace_collector_sample,
 			_marker: PhantomData,
 		});
 
@@ -401,6 +423,7 @@ impl<B: BlockT + 'static, H: ExHashT> NetworkWorker<B, H> {
 			from_service,
 			light_client_rqs: params.on_demand.and_then(|od| od.extract_receiver()),
 			event_streams: out_events::OutChannels::new(params.metrics_registry.as_ref())?,
+			trace_summaries: Vec::new(),
 			peers_notifications_sinks,
 			metrics,
 			boot_node_ids,
@@ -856,6 +879,31 @@ impl<B: BlockT + 'static, H: ExHashT> NetworkService<B, H> {
 			.unbounded_send(ServiceToWorkerMsg::PutValue(key, value));
 	}
 
+	/// Send the trace summary of the block `number` to the trace collector.
+	///
+	/// Only the summaries of one block out of `trace_collector_sample` are sent, the others
+	/// being dropped, as are all of them if there is no collector.
+	pub fn send_trace_summary(&self, number: u64, summary: Vec<u8>) {
+		if let Some(sample) = self.trace_collector_sample {
+			if number % u64::from(sample) == 0 {
+				let _ = self
+					.to_worker
+					.unbounded_send(ServiceToWorkerMsg::SendTraceSummary(summary));
+			}
+		}
+	}
+
+	/// Returns a stream of the trace summaries sent by the reporters of this node, along with
+	/// the reporter which sent them.
+	///
+	/// If this method is called multiple times, the summaries are duplicated. The stream never
+	/// ends (unless the `NetworkWorker` gets shut down).
+	pub fn trace_summaries(&self) -> impl Stream<Item = (PeerId, Vec<u8>)> {
+		let (tx, rx) = tracing_unbounded("mpsc_network_trace_summaries");
+		let _ = self.to_worker.unbounded_send(ServiceToWorkerMsg::TraceSummaries(tx));
+		rx
+	}
+
 	/// Connect to unreserved peers and allow unreserved peers to connect.
 	pub fn accept_unreserved_peers(&self) {
 		self.peerset.set_reserved_only(false);
@@ -1093,6 +1141,8 @@ enum ServiceToWorkerMsg<B: BlockT, H: ExHashT> {
 	AnnounceBlock(B::Hash, Vec<u8>),
 	GetValue(record::Key),
 	PutValue(record::Key, Vec<u8>),
+	SendTraceSummary(Vec<u8>),
+	TraceSummaries(TracingUnboundedSender<(PeerId, Vec<u8>)>),
 	AddKnownAddress(PeerId, Multiaddr),
 	SyncFork(Vec<PeerId>, B::Hash, NumberFor<B>),
 	EventStream(out_events::Sender),
@@ -1128,6 +1178,8 @@ pub struct NetworkWorker<B: BlockT + 'static, H: ExHashT> {
 	light_client_rqs: Option<TracingUnboundedReceiver<light_client_handler::Request<B>>>,
 	/// Senders for events that happen on the network.
 	event_streams: out_events::OutChannels,
+	/// Senders for the trace summaries sent by the reporters.
+	trace_summaries: Vec<TracingUnboundedSender<(PeerId, Vec<u8>)>>,
 	/// Prometheus network metrics.
 	metrics: Option<Metrics>,
 	/// The `PeerId`'s of all boot nodes.
@@ -1440,6 +1492,10 @@ impl<B: BlockT + 'static, H: ExHashT> Future for NetworkWorker<B, H> {
 					this.network_service.get_value(&key),
 				ServiceToWorkerMsg::PutValue(key, value) =>
 					this.network_service.put_value(key, value),
+				ServiceToWorkerMsg::SendTraceSummary(summary) =>
+					this.network_service.send_trace_summary(summary),
+				ServiceToWorkerMsg::TraceSummaries(sender) =>
+					this.trace_summaries.push(sender),
 				ServiceToWorkerMsg::AddKnownAddress(peer_id, addr) =>
 					this.network_service.add_known_address(peer_id, addr),
 				ServiceToWorkerMsg::SyncFork(peer_ids, hash, number) =>
@@ -1610,6 +1666,11 @@ impl<B: BlockT + 'static, H: ExHashT> Future for NetworkWorker<B, H> {
 
 					this.event_streams.send(Event::Dht(event));
 				},
+				Poll::Ready(SwarmEvent::Behaviour(BehaviourOut::TraceSummary { peer, summary })) => {
+					this.trace_summaries.retain(|sender| {
+						sender.unbounded_send((peer.clone(), summary.clone())).is_ok()
+					});
+				},
 				Poll::Ready(SwarmEvent::ConnectionEstablished { peer_id, endpoint, num_established }) => {
 					trace!(target: "sub-libp2p", "Libp2p => Connected({:?})", peer_id);
 
@@ -1635,14 +1696,14 @@ impl<B: BlockT + 'static, H: ExHashT> Future for NetworkWorker<B, H> {
 						let reason = match cause {
 							Some(ConnectionError::IO(_)) => "transport-error",
 							Some(ConnectionError::Handler(NodeHandlerWrapperError::Handler(EitherError::A(EitherError::A(
-								EitherError::A(EitherError::A(EitherError::B(
-								EitherError::A(PingFailure::Timeout))))))))) => "ping-timeout",
+								EitherError::A(EitherError::A(EitherError::A(EitherError::B(
+								EitherError::A(PingFailure::Timeout)))))))))) => "ping-timeout",
 							Some(ConnectionError::Handler(NodeHandlerWrapperError::Handler(EitherError::A(EitherError::A(
-								EitherError::A(EitherError::A(EitherError::A(
-								NotifsHandlerError::Legacy(LegacyConnectionKillError))))))))) =>	"force-closed",
+								EitherError::A(EitherError::A(EitherError::A(EitherError::A(
+								NotifsHandlerError::Legacy(LegacyConnectionKillError)))))))))) =>	"force-closed",
 							Some(ConnectionError::Handler(NodeHandlerWrapperError::Handler(EitherError::A(EitherError::A(
-								EitherError::A(EitherError::A(EitherError::A(
-								NotifsHandlerError::SyncNotificationsClogged)))))))) => "sync-notifications-clogged",
+								EitherError::A(EitherError::A(EitherError::A(EitherError::A(
+								NotifsHandlerError::SyncNotificationsClogged))))))))) => "sync-notifications-clogged",
 							Some(ConnectionError::Handler(NodeHandlerWrapperError::Handler(_))) => "protocol-error",
 							Some(ConnectionError::Handler(NodeHandlerWrapperError::KeepAliveTimeout)) => "keep-alive-timeout",
 							None => "actively-closed",
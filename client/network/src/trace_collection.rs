@@ -0,0 +1,468 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+//
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! `NetworkBehaviour` implementation which sends the trace summaries of this node to a collector
+//! peer, and receives those of its reporters when this node is a collector.
+//!
+//! Every summary is sent on a separate substream, which gets closed once it has been written,
+//! without any response. The peers being authenticated by the transport, the summaries are only
+//! sent to the configured collector, and only accepted from the configured reporters. The
+//! collector is dialed when a summary is sent, unless it is being dialed already, the summaries
+//! waiting for the connection being dropped, oldest first, beyond [`MAX_QUEUED`].
+
+use bytes::Bytes;
+use crate::config::ProtocolId;
+use futures::{future::BoxFuture, prelude::*};
+use libp2p::{
+	core::{
+		Multiaddr,
+		PeerId,
+		connection::ConnectionId,
+		upgrade::{InboundUpgrade, OutboundUpgrade, ReadOneError, UpgradeInfo},
+		upgrade::{read_one, write_one}
+	},
+	swarm::{
+		DialPeerCondition,
+		NetworkBehaviour,
+		NetworkBehaviourAction,
+		NotifyHandler,
+		OneShotHandler,
+		OneShotHandlerConfig,
+		PollParameters,
+		SubstreamProtocol
+	}
+};
+use std::{
+	collections::{HashSet, VecDeque},
+	io,
+	iter,
+	time::Duration,
+	task::{Context, Poll}
+};
+
+/// Maximum number of summaries waiting for the connection to the collector.
+pub const MAX_QUEUED: usize = 64;
+
+/// Event generated by the trace collection behaviour.
+#[derive(Debug)]
+pub enum Event {
+	/// A reporter has sent a summary.
+	Summary {
+		peer: PeerId,
+		/// The summary, as sent by the reporter.
+		summary: Vec<u8>,
+	},
+}
+
+/// Configuration options for `TraceCollection`.
+#[derive(Debug, Clone)]
+pub struct Config {
+	max_summary_len: usize,
+	inactivity_timeout: Duration,
+	protocol: Bytes,
+	collector: Option<(PeerId, Multiaddr)>,
+	reporters: HashSet<PeerId>,
+}
+
+impl Config {
+	/// Create a fresh configuration with the following options:
+	///
+	/// - max. summary size = 1 MiB
+	/// - inactivity timeout = 15s
+	/// - no collector, and no reporters
+	pub fn new(id: &ProtocolId) -> Self {
+		let mut c = Config {
+			max_summary_len: 1024 * 1024,
+			inactivity_timeout: Duration::from_secs(15),
+			protocol: Bytes::new(),
+			collector: None,
+			reporters: HashSet::new(),
+		};
+		c.set_protocol(id);
+		c
+	}
+
+	/// Set protocol to use for upgrade negotiation.
+	pub fn set_protocol(&mut self, id: &ProtocolId) -> &mut Self {
+		let mut v = Vec::new();
+		v.extend_from_slice(b"/");
+		v.extend_from_slice(id.as_bytes());
+		v.extend_from_slice(b"/trace-summaries/1");
+		self.protocol = v.into();
+		self
+	}
+
+	/// Send the summaries to `peer`, reachable at `address`.
+	pub fn set_collector(&mut self, peer: PeerId, address: Multiaddr) -> &mut Self {
+		self.collector = Some((peer, address));
+		self
+	}
+
+	/// Accept the summaries of `reporters`, the incoming protocol being disabled if there are
+	/// none.
+	pub fn set_reporters(&mut self, reporters: impl IntoIterator<Item = PeerId>) -> &mut Self {
+		self.reporters = reporters.into_iter().collect();
+		self
+	}
+}
+
+/// The trace collection behaviour.
+pub struct TraceCollection {
+	/// This behaviour's configuration.
+	config: Config,
+	/// Whether we are connected to the collector.
+	collector_connected: bool,
+	/// Whether the collector is being dialed, until it is connected or the dial fails.
+	dialing: bool,
+	/// Summaries waiting for the connection to the collector, oldest first.
+	queued: VecDeque<Vec<u8>>,
+	/// Events to return as soon as possible from `poll`.
+	pending_events: VecDeque<NetworkBehaviourAction<OutboundProtocol, Event>>,
+}
+
+impl TraceCollection {
+	/// Initializes the behaviour.
+	pub fn new(cfg: Config) -> Self {
+		TraceCollection {
+			config: cfg,
+			collector_connected: false,
+			dialing: false,
+			queued: VecDeque::new(),
+			pending_events: VecDeque::new(),
+		}
+	}
+
+	/// Send a summary to the collector, dialing it if we aren't connected to it.
+	///
+	/// The summary is dropped if there is no collector.
+	pub fn send_summary(&mut self, summary: Vec<u8>) {
+		let collector = match &self.config.collector {
+			Some((peer, _)) => peer.clone(),
+			None => return,
+		};
+
+		if self.collector_connected {
+			self.notify(collector, summary);
+			return;
+		}
+
+		if self.queued.len() >= MAX_QUEUED {
+			log::debug!(target: "sub-libp2p", "Dropping a trace summary for {}, not connected", collector);
+			self.queued.pop_front();
+		}
+		self.queued.push_back(summary);
+		if !self.dialing {
+			self.dialing = true;
+			self.pending_events.push_back(NetworkBehaviourAction::DialPeer {
+				peer_id: collector,
+				condition: DialPeerCondition::Disconnected,
+			});
+		}
+	}
+
+	fn notify(&mut self, collector: PeerId, summary: Vec<u8>) {
+		log::trace!(target: "sub-libp2p", "Enqueueing a trace summary for {}", collector);
+		self.pending_events.push_back(NetworkBehaviourAction::NotifyHandler {
+			peer_id: collector,
+			handler: NotifyHandler::Any,
+			event: OutboundProtocol {
+				summary,
+				protocol: self.config.protocol.clone(),
+			},
+		});
+	}
+
+	fn is_collector(&self, peer: &PeerId) -> bool {
+		self.config.collector.as_ref().map_or(false, |(collector, _)| collector == peer)
+	}
+}
+
+impl NetworkBehaviour for TraceCollection {
+	type ProtocolsHandler = OneShotHandler<InboundProtocol, OutboundProtocol, NodeEvent>;
+	type OutEvent = Event;
+
+	fn new_handler(&mut self) -> Self::ProtocolsHandler {
+		let p = InboundProtocol {
+			max_summary_len: self.config.max_summary_len,
+			protocol: if self.config.reporters.is_empty() {
+				None
+			} else {
+				Some(self.config.protocol.clone())
+			},
+		};
+		let mut cfg = OneShotHandlerConfig::default();
+		cfg.keep_alive_timeout = self.config.inactivity_timeout;
+		OneShotHandler::new(SubstreamProtocol::new(p), cfg)
+	}
+
+	fn addresses_of_peer(&mut self, peer: &PeerId) -> Vec<Multiaddr> {
+		match &self.config.collector {
+			Some((collector, address)) if collector == peer => vec![address.clone()],
+			_ => Vec::new(),
+		}
+	}
+
+	fn inject_connected(&mut self, peer: &PeerId) {
+		if !self.is_collector(peer) {
+			return;
+		}
+		self.collector_connected = true;
+		self.dialing = false;
+		for summary in std::mem::take(&mut self.queued) {
+			self.notify(peer.clone(), summary);
+		}
+	}
+
+	fn inject_disconnected(&mut self, peer: &PeerId) {
+		if self.is_collector(peer) {
+			self.collector_connected = false;
+		}
+	}
+
+	fn inject_dial_failure(&mut self, peer: &PeerId) {
+		if self.is_collector(peer) {
+			self.dialing = false;
+			log::debug!(
+				target: "sub-libp2p",
+				"Failed to reach the trace collector {}, {} summaries queued",
+				peer,
+				self.queued.len(),
+			);
+		}
+	}
+
+	fn inject_event(
+		&mut self,
+		peer: PeerId,
+		_connection: ConnectionId,
+		event: NodeEvent
+	) {
+		match event {
+			NodeEvent::Summary(summary) if self.config.reporters.contains(&peer) => {
+				let ev = Event::Summary { peer, summary };
+				self.pending_events.push_back(NetworkBehaviourAction::GenerateEvent(ev));
+			}
+			NodeEvent::Summary(_) =>
+				log::debug!(target: "sub-libp2p", "Ignoring the trace summary of {}, not a reporter", peer),
+			NodeEvent::Sent => {}
+		}
+	}
+
+	fn poll(&mut self, _: &mut Context, _: &mut impl PollParameters)
+		-> Poll<NetworkBehaviourAction<OutboundProtocol, Event>>
+	{
+		while let Some(ev) = self.pending_events.pop_front() {
+			// The collector got connected since the dial was queued.
+			if let NetworkBehaviourAction::DialPeer { .. } = ev {
+				if self.collector_connected {
+					continue;
+				}
+			}
+			return Poll::Ready(ev);
+		}
+		Poll::Pending
+	}
+}
+
+/// Output type of inbound and outbound substream upgrades.
+#[derive(Debug)]
+pub enum NodeEvent {
+	/// Incoming summary from remote.
+	Summary(Vec<u8>),
+	/// The summary has been sent to remote.
+	Sent,
+}
+
+/// Substream upgrade protocol.
+///
+/// Reads an incoming summary, which becomes visible via `inject_event`.
+#[derive(Debug, Clone)]
+pub struct InboundProtocol {
+	/// The max. summary length in bytes.
+	max_summary_len: usize,
+	/// The protocol to use during upgrade negotiation. If `None`, then the incoming protocol
+	/// is simply disabled.
+	protocol: Option<Bytes>,
+}
+
+impl UpgradeInfo for InboundProtocol {
+	type Info = Bytes;
+	type InfoIter = std::option::IntoIter<Self::Info>;
+
+	fn protocol_info(&self) -> Self::InfoIter {
+		self.protocol.clone().into_iter()
+	}
+}
+
+impl<T> InboundUpgrade<T> for InboundProtocol
+where
+	T: AsyncRead + AsyncWrite + Unpin + Send + 'static
+{
+	type Output = NodeEvent;
+	type Error = ReadOneError;
+	type Future = BoxFuture<'static, Result<Self::Output, Self::Error>>;
+
+	fn upgrade_inbound(self, mut s: T, _: Self::Info) -> Self::Future {
+		async move {
+			let summary = read_one(&mut s, self.max_summary_len).await?;
+			Ok(NodeEvent::Summary(summary))
+		}.boxed()
+	}
+}
+
+/// Substream upgrade protocol.
+///
+/// Sends a summary to remote.
+#[derive(Debug, Clone)]
+pub struct OutboundProtocol {
+	/// The summary to send.
+	summary: Vec<u8>,
+	/// The protocol to use for upgrade negotiation.
+	protocol: Bytes,
+}
+
+impl UpgradeInfo for OutboundProtocol {
+	type Info = Bytes;
+	type InfoIter = iter::Once<Self::Info>;
+
+	fn protocol_info(&self) -> Self::InfoIter {
+		iter::once(self.protocol.clone())
+	}
+}
+
+impl<T> OutboundUpgrade<T> for OutboundProtocol
+where
+	T: AsyncRead + AsyncWrite + Unpin + Send + 'static
+{
+	type Output = NodeEvent;
+	type Error = io::Error;
+	type Future = BoxFuture<'static, Result<Self::Output, Self::Error>>;
+
+	fn upgrade_outbound(self, mut s: T, _: Self::Info) -> Self::Future {
+		async move {
+			write_one(&mut s, &self.summary).await?;
+			Ok(NodeEvent::Sent)
+		}.boxed()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::config::ProtocolId;
+	use futures::prelude::*;
+	use libp2p::identity::Keypair;
+	use libp2p::Multiaddr;
+	use libp2p::core::upgrade;
+	use libp2p::core::transport::{Transport, MemoryTransport};
+	use libp2p::core::upgrade::{InboundUpgradeExt, OutboundUpgradeExt};
+	use libp2p::swarm::{NetworkBehaviour, NetworkBehaviourAction, Swarm};
+	use std::task::Poll;
+	use super::{Config, Event, TraceCollection};
+
+	fn build_swarm(keypair: Keypair, config: Config) -> Swarm<TraceCollection> {
+		let keypair2 = keypair.clone();
+
+		let transport = MemoryTransport
+			.and_then(move |out, endpoint| {
+				let secio = libp2p::secio::SecioConfig::new(keypair2);
+				libp2p::core::upgrade::apply(
+					out,
+					secio,
+					endpoint,
+					upgrade::Version::V1
+				)
+			})
+			.and_then(move |(peer_id, stream), endpoint| {
+				let peer_id2 = peer_id.clone();
+				let upgrade = libp2p::yamux::Config::default()
+					.map_inbound(move |muxer| (peer_id, muxer))
+					.map_outbound(move |muxer| (peer_id2, muxer));
+				upgrade::apply(stream, upgrade, endpoint, upgrade::Version::V1)
+			});
+
+		Swarm::new(transport, TraceCollection::new(config), keypair.public().into_peer_id())
+	}
+
+	#[test]
+	fn collector_is_dialed_once_at_a_time() {
+		let collector = Keypair::generate_ed25519().public().into_peer_id();
+		let mut config = Config::new(&ProtocolId::from(b"dot".as_ref()));
+		config.set_collector(collector.clone(), "/memory/1".parse().unwrap());
+		let mut behaviour = TraceCollection::new(config);
+		let dials = |behaviour: &TraceCollection| behaviour.pending_events.iter()
+			.filter(|ev| matches!(ev, NetworkBehaviourAction::DialPeer { .. }))
+			.count();
+
+		for _ in 0..3 {
+			behaviour.send_summary(b"summary".to_vec());
+		}
+		assert_eq!(dials(&behaviour), 1);
+		assert_eq!(behaviour.queued.len(), 3);
+
+		// Dialed again by the next summary once the dial failed.
+		behaviour.inject_dial_failure(&collector);
+		behaviour.send_summary(b"summary".to_vec());
+		assert_eq!(dials(&behaviour), 2);
+
+		// The queued summaries are sent once connected, without dialing again.
+		behaviour.inject_connected(&collector);
+		behaviour.send_summary(b"summary".to_vec());
+		assert!(behaviour.queued.is_empty());
+		assert_eq!(dials(&behaviour), 2);
+		let notified = behaviour.pending_events.iter()
+			.filter(|ev| matches!(ev, NetworkBehaviourAction::NotifyHandler { .. }))
+			.count();
+		assert_eq!(notified, 5);
+	}
+
+	#[test]
+	fn only_summaries_of_reporters_are_collected() {
+		let protocol_id = ProtocolId::from(b"dot".as_ref());
+		let listen_addr: Multiaddr = format!("/memory/{}", rand::random::<u64>()).parse().unwrap();
+		let collector_key = Keypair::generate_ed25519();
+		let reporter_key = Keypair::generate_ed25519();
+
+		let mut collector = {
+			let mut config = Config::new(&protocol_id);
+			config.set_reporters(vec![reporter_key.public().into_peer_id()]);
+			build_swarm(collector_key.clone(), config)
+		};
+		Swarm::listen_on(&mut collector, listen_addr.clone()).unwrap();
+
+		// Both send to the collector, which only accepts the summaries of the first one.
+		let mut reporters = vec![reporter_key, Keypair::generate_ed25519()].into_iter().map(|key| {
+			let mut config = Config::new(&protocol_id);
+			config.set_collector(collector_key.public().into_peer_id(), listen_addr.clone());
+			build_swarm(key, config)
+		}).collect::<Vec<_>>();
+		reporters[1].send_summary(b"unknown".to_vec());
+		reporters[0].send_summary(b"reported".to_vec());
+
+		futures::executor::block_on(future::poll_fn(move |cx| {
+			for reporter in &mut reporters {
+				let _ = reporter.poll_next_unpin(cx);
+			}
+			match collector.poll_next_unpin(cx) {
+				Poll::Ready(Some(Event::Summary { peer, summary })) => {
+					assert_eq!(&peer, Swarm::local_peer_id(&reporters[0]));
+					assert_eq!(summary, b"reported");
+					Poll::Ready(())
+				},
+				_ => Poll::Pending,
+			}
+		}));
+	}
+}
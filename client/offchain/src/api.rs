@@ -28,7 +28,7 @@ use sc_network::{PeerId, Multiaddr, NetworkStateInfo};
 use codec::{Encode, Decode};
 use sp_core::offchain::{
 	Externalities as OffchainExt, HttpRequestId, Timestamp, HttpRequestStatus, HttpError,
-	OpaqueNetworkState, OpaquePeerId, OpaqueMultiaddr, StorageKind,
+	OpaqueNetworkState, OpaquePeerId, OpaqueMultiaddr, StorageKind, NodeMetrics,
 };
 pub use sp_offchain::STORAGE_PREFIX;
 pub use http::SharedClient;
@@ -43,6 +43,8 @@ mod http_dummy;
 
 mod timestamp;
 
+use crate::NodeMetricsProvider;
+
 /// Asynchronous offchain API.
 ///
 /// NOTE this is done to prevent recursive calls into the runtime (which are not supported currently).
@@ -53,6 +55,8 @@ pub(crate) struct Api<Storage> {
 	network_state: Arc<dyn NetworkStateInfo + Send + Sync>,
 	/// Is this node a potential validator?
 	is_validator: bool,
+	/// A NodeMetrics provider, the metrics being all unknown without one.
+	node_metrics: Option<Arc<dyn NodeMetricsProvider>>,
 	/// Everything HTTP-related is handled by a different struct.
 	http: http::HttpApi,
 }
@@ -82,6 +86,10 @@ impl<Storage: OffchainStorage> OffchainExt for Api<Storage> {
 		Ok(OpaqueNetworkState::from(state))
 	}
 
+	fn node_metrics(&self) -> NodeMetrics {
+		self.node_metrics.as_ref().map(|provider| provider.node_metrics()).unwrap_or_default()
+	}
+
 	fn timestamp(&mut self) -> Timestamp {
 		timestamp::now()
 	}
@@ -261,6 +269,7 @@ impl AsyncApi {
 		db: S,
 		network_state: Arc<dyn NetworkStateInfo + Send + Sync>,
 		is_validator: bool,
+		node_metrics: Option<Arc<dyn NodeMetricsProvider>>,
 		shared_client: SharedClient,
 	) -> (Api<S>, Self) {
 		let (http_api, http_worker) = http::http(shared_client);
@@ -269,6 +278,7 @@ impl AsyncApi {
 			db,
 			network_state,
 			is_validator,
+			node_metrics,
 			http: http_api,
 		};
 
@@ -317,6 +327,7 @@ mod tests {
 			db,
 			mock,
 			false,
+			None,
 			shared_client,
 		)
 	}
@@ -355,6 +366,28 @@ mod tests {
 		assert!(new_now.unix_millis() - 100 >= now.unix_millis());
 	}
 
+	#[test]
+	fn should_get_node_metrics() {
+		let api = offchain_api().0;
+		assert_eq!(api.node_metrics(), NodeMetrics::default());
+
+		let metrics = NodeMetrics {
+			recent_imports: 2,
+			average_import_micros: Some(1_500),
+			ready_transactions: 3,
+			future_transactions: 1,
+		};
+		let provided = metrics.clone();
+		let (api, _) = AsyncApi::new(
+			LocalStorage::new_test(),
+			Arc::new(MockNetworkStateInfo()),
+			false,
+			Some(Arc::new(move || provided.clone())),
+			SharedClient::new(),
+		);
+		assert_eq!(api.node_metrics(), metrics);
+	}
+
 	#[test]
 	fn should_set_and_get_local_storage() {
 		// given
@@ -41,7 +41,9 @@ use sp_api::{ApiExt, ProvideRuntimeApi};
 use futures::future::Future;
 use log::{debug, warn};
 use sc_network::NetworkStateInfo;
-use sp_core::{offchain::{self, OffchainStorage}, ExecutionContext, traits::SpawnNamed};
+use sp_core::{
+	offchain::{self, OffchainStorage}, ExecutionContext, hexdisplay::HexDisplay, traits::SpawnNamed,
+};
 use sp_runtime::{generic::BlockId, traits::{self, Header}};
 use futures::{prelude::*, future::ready};
 
@@ -50,10 +52,24 @@ use api::SharedClient;
 
 pub use sp_offchain::{OffchainWorkerApi, STORAGE_PREFIX};
 
+/// Provides the recent performance aggregates of the node to the offchain workers, see
+/// `sp_io::offchain::node_metrics`.
+pub trait NodeMetricsProvider: Send + Sync {
+	/// The current aggregates.
+	fn node_metrics(&self) -> offchain::NodeMetrics;
+}
+
+impl<F: Fn() -> offchain::NodeMetrics + Send + Sync> NodeMetricsProvider for F {
+	fn node_metrics(&self) -> offchain::NodeMetrics {
+		self()
+	}
+}
+
 /// An offchain workers manager.
 pub struct OffchainWorkers<Client, Storage, Block: traits::Block> {
 	client: Arc<Client>,
 	db: Storage,
+	node_metrics: Option<Arc<dyn NodeMetricsProvider>>,
 	_block: PhantomData<Block>,
 	thread_pool: Mutex<ThreadPool>,
 	shared_client: SharedClient,
@@ -66,11 +82,18 @@ impl<Client, Storage, Block: traits::Block> OffchainWorkers<Client, Storage, Blo
 		Self {
 			client,
 			db,
+			node_metrics: None,
 			_block: PhantomData,
 			thread_pool: Mutex::new(ThreadPool::new(num_cpus::get())),
 			shared_client,
 		}
 	}
+
+	/// Provide the offchain workers with the performance aggregates of the node, which are all
+	/// unknown otherwise.
+	pub fn with_node_metrics(self, node_metrics: Arc<dyn NodeMetricsProvider>) -> Self {
+		Self { node_metrics: Some(node_metrics), ..self }
+	}
 }
 
 impl<Client, Storage, Block: traits::Block> fmt::Debug for OffchainWorkers<
@@ -124,15 +147,21 @@ impl<Client, Storage, Block> OffchainWorkers<
 				self.db.clone(),
 				network_state.clone(),
 				is_validator,
+				self.node_metrics.clone(),
 				self.shared_client.clone(),
 			);
 			debug!("Spawning offchain workers at {:?}", at);
 			let header = header.clone();
 			let client = self.client.clone();
 			self.spawn_worker(move || {
+				// Every run is a trace context of its own, whose trace id the runtime may attach to
+				// the transactions it submits, see `sp_io::wasm_tracing::trace_id`.
+				let trace_id = rand::random::<[u8; 16]>();
+				let trace_context = sp_tracing::proxy::trace_context_span(&trace_id);
+				let _trace_context = trace_context.enter();
 				let runtime = client.runtime_api();
 				let api = Box::new(api);
-				debug!("Running offchain workers at {:?}", at);
+				debug!("Running offchain workers at {:?}, trace id {}", at, HexDisplay::from(&trace_id));
 				let context = ExecutionContext::OffchainCall(Some(
 					(api, offchain::Capabilities::all())
 				));
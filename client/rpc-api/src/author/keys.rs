@@ -0,0 +1,59 @@
+// Copyright 2019-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! Session keys helpers for author RPC module.
+
+use sp_core::Bytes;
+use serde::{Serialize, Deserialize};
+
+/// Session keys generated by `author_rotateKeysWithInfo`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RotatedKeys<BlockHash> {
+	/// SCALE encoded session keys, the same as returned by `author_rotateKeys`.
+	pub keys: Bytes,
+	/// The public key of every key type of the session keys.
+	pub public_keys: Vec<SessionPublicKey>,
+	/// The best block at the time the keys were generated, which the session state is read at.
+	///
+	/// This is not the block `set_keys` is included in: the keys are only used once `set_keys`
+	/// is included in a later block and the next validator set was queued with them.
+	pub generated_at: BlockHash,
+	/// The session at `generated_at`, `None` if the runtime doesn't provide it.
+	pub session_index: Option<u32>,
+	/// The earliest session the keys can be used in, if `set_keys` is included before
+	/// `session_index` ends.
+	///
+	/// The keys set during a session are queued at its end and active from the session after,
+	/// so including `set_keys` in a later session delays them accordingly.
+	pub earliest_active_session: Option<u32>,
+	/// SCALE encoded session keys currently set for the validator given, which are used until
+	/// the new keys are active.
+	pub previous_keys: Option<Bytes>,
+	/// The public key of every key type of `previous_keys`.
+	pub previous_public_keys: Vec<SessionPublicKey>,
+}
+
+/// A public key of some session keys.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionPublicKey {
+	/// The key type, e.g. `babe` or `gran`.
+	pub key_type: String,
+	/// The raw public key.
+	pub public: Bytes,
+}
@@ -20,6 +20,8 @@
 
 pub mod error;
 pub mod hash;
+pub mod keys;
+pub mod status;
 
 use jsonrpc_derive::rpc;
 use jsonrpc_pubsub::{typed::Subscriber, SubscriptionId};
@@ -52,6 +54,16 @@ pub trait AuthorApi<Hash, BlockHash> {
 	#[rpc(name = "author_rotateKeys")]
 	fn rotate_keys(&self) -> Result<Bytes>;
 
+	/// Generate new session keys like `author_rotateKeys` and returns them along with their
+	/// public keys, the block they were generated at and the earliest session they can be active in.
+	///
+	/// If the SCALE encoded `validator` is given, the keys it currently has set are returned too.
+	#[rpc(name = "author_rotateKeysWithInfo")]
+	fn rotate_keys_with_info(
+		&self,
+		validator: Option<Bytes>,
+	) -> Result<keys::RotatedKeys<BlockHash>>;
+
 	/// Checks if the keystore has private keys for the given session public keys.
 	///
 	/// `session_keys` is the SCALE encoded session keys object from the runtime.
@@ -70,6 +82,19 @@ pub trait AuthorApi<Hash, BlockHash> {
 	#[rpc(name = "author_pendingExtrinsics")]
 	fn pending_extrinsics(&self) -> Result<Vec<Bytes>>;
 
+	/// Returns the status of the transaction pool, with the statistics of the revalidation of its
+	/// transactions.
+	///
+	/// The transactions dropped from the pool without being included in a block are mostly
+	/// removed as invalid by their revalidation.
+	#[rpc(name = "author_poolStatusDetailed")]
+	fn pool_status_detailed(&self) -> Result<status::DetailedPoolStatus>;
+
+	/// Returns the priority, longevity, tags and queue of the given pending extrinsic, or `None`
+	/// if it is not in the pool.
+	#[rpc(name = "author_pendingExtrinsicInfo")]
+	fn pending_extrinsic_info(&self, hash: Hash) -> Result<Option<status::PendingExtrinsicInfo<Hash>>>;
+
 	/// Remove given extrinsic from the pool and temporarily ban it to prevent reimporting.
 	#[rpc(name = "author_removeExtrinsic")]
 	fn remove_extrinsic(&self,
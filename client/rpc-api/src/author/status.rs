@@ -0,0 +1,99 @@
+// Copyright 2019-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+
+
+//! Transaction pool status helpers for author RPC module.
+
+use serde::{Serialize, Deserialize};
+use sp_core::Bytes;
+use sp_transaction_pool::{InPoolTransaction, PoolStatus, RevalidationStats};
+
+/// Status of the transaction pool returned by `author_poolStatusDetailed`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DetailedPoolStatus {
+	/// Number of transactions in the ready queue.
+	pub ready: usize,
+	/// Sum of bytes of the ready transaction encodings.
+	pub ready_bytes: usize,
+	/// Number of transactions in the future queue.
+	pub future: usize,
+	/// Sum of bytes of the future transaction encodings.
+	pub future_bytes: usize,
+	/// Statistics of the revalidation of the ready transactions, the transactions it removed
+	/// from the pool included.
+	pub revalidation: RevalidationStats,
+}
+
+impl DetailedPoolStatus {
+	/// The detailed status of a pool with the given `status` and `revalidation` statistics.
+	pub fn new(status: PoolStatus, revalidation: RevalidationStats) -> Self {
+		DetailedPoolStatus {
+			ready: status.ready,
+			ready_bytes: status.ready_bytes,
+			future: status.future,
+			future_bytes: status.future_bytes,
+			revalidation,
+		}
+	}
+}
+
+/// Queue of the transaction pool a transaction is in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PoolQueue {
+	/// The transaction can be included in the next block.
+	Ready,
+	/// The transaction requires tags that no ready transaction provides yet.
+	Future,
+}
+
+/// Validity of a pending extrinsic returned by `author_pendingExtrinsicInfo`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingExtrinsicInfo<Hash> {
+	/// Hash of the extrinsic.
+	pub hash: Hash,
+	/// Queue the extrinsic is in.
+	pub queue: PoolQueue,
+	/// Priority of the extrinsic, the higher the sooner it is included.
+	pub priority: u64,
+	/// Block at which the extrinsic becomes invalid, its longevity past the block it was last
+	/// validated at.
+	pub longevity: u64,
+	/// Tags the extrinsic requires to be ready.
+	pub requires: Vec<Bytes>,
+	/// Tags the extrinsic provides.
+	pub provides: Vec<Bytes>,
+	/// Whether the extrinsic is propagated to the other peers.
+	pub propagate: bool,
+}
+
+impl<Hash: Clone> PendingExtrinsicInfo<Hash> {
+	/// The validity of the transaction `tx` in the `queue`.
+	pub fn new<T: InPoolTransaction<Hash = Hash>>(tx: &T, queue: PoolQueue) -> Self {
+		PendingExtrinsicInfo {
+			hash: tx.hash().clone(),
+			queue,
+			priority: *tx.priority(),
+			longevity: *tx.longevity(),
+			requires: tx.requires().iter().cloned().map(Bytes).collect(),
+			provides: tx.provides().iter().cloned().map(Bytes).collect(),
+			propagate: tx.is_propagable(),
+		}
+	}
+}
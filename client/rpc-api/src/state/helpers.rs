@@ -18,7 +18,7 @@
 
 //! Substrate state API helpers.
 
-use sp_core::Bytes;
+use sp_core::{Bytes, storage::{StorageKey, StorageData}};
 use serde::{Serialize, Deserialize};
 
 /// ReadProof struct returned by the RPC
@@ -30,3 +30,29 @@ pub struct ReadProof<Hash> {
 	/// A proof used to prove that storage entries are included in the storage trie
 	pub proof: Vec<Bytes>,
 }
+
+/// A storage entry that differs between the states of two blocks
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageEntryDiff {
+	/// Key of the storage entry
+	pub key: StorageKey,
+	/// Value of the entry at the first block, if any
+	pub from: Option<StorageData>,
+	/// Value of the entry at the second block, if any
+	pub to: Option<StorageData>,
+}
+
+/// StorageDiff struct returned by the RPC
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageDiff<Hash> {
+	/// First block compared
+	pub from: Hash,
+	/// Second block compared
+	pub to: Hash,
+	/// The entries that differ, in lexicographic order of their keys
+	pub changes: Vec<StorageEntryDiff>,
+	/// Key to pass as `start_key` to resume the comparison, if it stopped before the last key
+	pub next_key: Option<StorageKey>,
+}
@@ -31,7 +31,7 @@ use sp_version::RuntimeVersion;
 use self::error::FutureResult;
 
 pub use self::gen_client::Client as StateClient;
-pub use self::helpers::ReadProof;
+pub use self::helpers::{ReadProof, StorageDiff, StorageEntryDiff};
 
 /// Substrate state API
 #[rpc]
@@ -104,6 +104,21 @@ pub trait StateApi<Hash> {
 		at: Option<Hash>,
 	) -> FutureResult<Vec<StorageChangeSet<Hash>>>;
 
+	/// Returns the storage entries with prefix that differ between the states of two blocks.
+	/// Up to `count` entries will be returned, in lexicographic order of their keys.
+	/// If `start_key` is passed, compare the next keys in storage in lexicographic order.
+	/// The comparison may stop before the last key, `nextKey` of the result telling where to
+	/// resume it.
+	#[rpc(name = "state_storageDiff")]
+	fn storage_diff(
+		&self,
+		from: Hash,
+		to: Hash,
+		prefix: Option<StorageKey>,
+		count: u32,
+		start_key: Option<StorageKey>,
+	) -> FutureResult<StorageDiff<Hash>>;
+
 	/// Returns proof of storage entries at a specific block's state.
 	#[rpc(name = "state_getReadProof")]
 	fn read_proof(&self, keys: Vec<StorageKey>, hash: Option<Hash>) -> FutureResult<ReadProof<Hash>>;
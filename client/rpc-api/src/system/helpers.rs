@@ -88,6 +88,54 @@ pub enum NodeRole {
 	Sentry,
 }
 
+/// Status of the tasks spawned by the node under a given name.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskStatus {
+	/// Name the tasks were spawned with.
+	pub name: String,
+	/// Whether the exit of the tasks shuts the node down.
+	pub essential: bool,
+	/// Number of tasks with this name currently running.
+	pub running: u64,
+	/// The latest exits of the tasks with this name, the most recent last.
+	pub exits: Vec<TaskExit>,
+}
+
+/// Exit of a task spawned by the node.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskExit {
+	/// Why the task exited: `finished`, `interrupted` or `panicked: <message>`.
+	pub reason: String,
+	/// The spans the task was in when it exited, if traced.
+	pub spans: Option<String>,
+	/// When the task exited, in milliseconds since the Unix epoch.
+	pub exited_at: u64,
+}
+
+/// Health of a path exporting the telemetry or the traces out of the node.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExporterStatus {
+	/// Name of the exporter, e.g. `loki` or `telemetry <address>`.
+	pub name: String,
+	/// Number of records waiting to be exported.
+	pub queued: u64,
+	/// Number of records exported.
+	pub sent: u64,
+	/// Number of records dropped, e.g. because the queue was full or the export failed.
+	pub dropped: u64,
+	/// Number of times the exporter reconnected to its endpoint.
+	pub reconnects: u64,
+	/// Number of sends, a send exporting one or more records.
+	pub sends: u64,
+	/// Total time spent sending, in microseconds.
+	pub send_time_us: u64,
+	/// Time the last send took, in microseconds.
+	pub last_send_latency_us: Option<u64>,
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -117,4 +165,38 @@ mod tests {
 			r#"{"peerId":"2","roles":"a","protocolVersion":2,"bestHash":5,"bestNumber":6}"#,
 		);
 	}
+
+	#[test]
+	fn should_serialize_task_status() {
+		assert_eq!(
+			::serde_json::to_string(&TaskStatus {
+				name: "import-queue".into(),
+				essential: true,
+				running: 0,
+				exits: vec![TaskExit {
+					reason: "finished".into(),
+					spans: Some("substrate_task::task{name=import-queue}".into()),
+					exited_at: 42,
+				}],
+			}).unwrap(),
+			r#"{"name":"import-queue","essential":true,"running":0,"exits":[{"reason":"finished","spans":"substrate_task::task{name=import-queue}","exitedAt":42}]}"#,
+		);
+	}
+
+	#[test]
+	fn should_serialize_exporter_status() {
+		assert_eq!(
+			::serde_json::to_string(&ExporterStatus {
+				name: "loki".into(),
+				queued: 3,
+				sent: 10,
+				dropped: 1,
+				reconnects: 0,
+				sends: 2,
+				send_time_us: 1500,
+				last_send_latency_us: None,
+			}).unwrap(),
+			r#"{"name":"loki","queued":3,"sent":10,"dropped":1,"reconnects":0,"sends":2,"sendTimeUs":1500,"lastSendLatencyUs":null}"#,
+		);
+	}
 }
@@ -27,7 +27,7 @@ use futures::{future::BoxFuture, compat::Compat};
 
 use self::error::Result as SystemResult;
 
-pub use self::helpers::{SystemInfo, Health, PeerInfo, NodeRole};
+pub use self::helpers::{SystemInfo, Health, PeerInfo, NodeRole, TaskStatus, TaskExit, ExporterStatus};
 pub use self::gen_client::Client as SystemClient;
 
 /// Substrate system RPC API
@@ -103,4 +103,16 @@ pub trait SystemApi<Hash, Number> {
 	/// Returns the roles the node is running as.
 	#[rpc(name = "system_nodeRoles", returns = "Vec<NodeRole>")]
 	fn system_node_roles(&self) -> Receiver<Vec<NodeRole>>;
+
+	/// Returns the tasks spawned by the node, with how many are running and why they last
+	/// exited.
+	#[rpc(name = "system_taskStatus", returns = "Vec<TaskStatus>")]
+	fn system_task_status(&self)
+		-> Compat<BoxFuture<'static, Result<Vec<TaskStatus>, jsonrpc_core::Error>>>;
+
+	/// Returns the health of the paths exporting the telemetry and the traces out of the node:
+	/// their queue, sends, reconnections and dropped records.
+	#[rpc(name = "system_exportersStatus", returns = "Vec<ExporterStatus>")]
+	fn system_exporters_status(&self)
+		-> Compat<BoxFuture<'static, Result<Vec<ExporterStatus>, jsonrpc_core::Error>>>;
 }
@@ -0,0 +1,77 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Trace RPC errors.
+
+use crate::errors;
+use jsonrpc_core as rpc;
+
+/// Trace RPC Result type.
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Trace RPC errors.
+#[derive(Debug, derive_more::Display, derive_more::From)]
+pub enum Error {
+	/// Client error.
+	#[display(fmt="Client error: {}", _0)]
+	Client(Box<dyn std::error::Error + Send>),
+	/// A stored summary couldn't be decoded.
+	#[display(fmt="Invalid trace summary: {}", "_0.what()")]
+	InvalidSummary(codec::Error),
+	/// The node doesn't measure the drift of the weights.
+	#[display(fmt="The drift of the weights is not measured")]
+	WeightDriftDisabled,
+	/// The node doesn't break down the proofs of the built blocks.
+	#[display(fmt="The proofs of the built blocks are not broken down")]
+	PovBreakdownsDisabled,
+}
+
+impl std::error::Error for Error {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		match self {
+			Error::Client(ref err) => Some(&**err),
+			_ => None,
+		}
+	}
+}
+
+/// Base error code for all trace errors.
+const BASE_ERROR: i64 = 6000;
+
+impl From<Error> for rpc::Error {
+	fn from(e: Error) -> Self {
+		match e {
+			Error::InvalidSummary(e) => rpc::Error {
+				code: rpc::ErrorCode::ServerError(BASE_ERROR + 1),
+				message: format!("Invalid trace summary: {}", e.what()),
+				data: None,
+			},
+			Error::WeightDriftDisabled => rpc::Error {
+				code: rpc::ErrorCode::ServerError(BASE_ERROR + 2),
+				message: "The drift of the weights is not measured".into(),
+				data: None,
+			},
+			Error::PovBreakdownsDisabled => rpc::Error {
+				code: rpc::ErrorCode::ServerError(BASE_ERROR + 3),
+				message: "The proofs of the built blocks are not broken down".into(),
+				data: None,
+			},
+			e => errors::internal(e),
+		}
+	}
+}
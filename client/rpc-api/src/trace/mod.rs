@@ -0,0 +1,49 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Substrate trace API.
+
+pub mod error;
+
+use jsonrpc_derive::rpc;
+use sc_tracing::{BlockSummary, CallDrift, PovBreakdown};
+use self::error::Result;
+
+pub use self::gen_client::Client as TraceClient;
+
+/// Substrate trace RPC API
+#[rpc]
+pub trait TraceApi<Hash> {
+	/// Returns the summary of the trace of the import of the given block, if the node stored it.
+	#[rpc(name = "trace_blockSummary")]
+	fn block_summary(&self, hash: Hash) -> Result<Option<BlockSummary>>;
+
+	/// Returns the summaries of the traces of the imports of the last `count` blocks the node
+	/// stored one of, 10 by default, the highest first.
+	#[rpc(name = "state_recentTraces")]
+	fn recent_traces(&self, count: Option<u32>) -> Result<Vec<BlockSummary>>;
+
+	/// Returns the drift of the measured times of the calls from their declared weights, by call.
+	#[rpc(name = "trace_weightDrift")]
+	fn weight_drift(&self) -> Result<Vec<CallDrift>>;
+
+	/// Returns the breakdowns of the proofs of validity of the last blocks built by the node, the
+	/// most recent last, or only those of the blocks of the given number.
+	#[rpc(name = "trace_povBreakdown")]
+	fn pov_breakdown(&self, block_number: Option<u64>) -> Result<Vec<PovBreakdown>>;
+}
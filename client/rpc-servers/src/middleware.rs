@@ -0,0 +1,339 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2017-2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Middleware of the RPC servers reporting the slow calls.
+//!
+//! A call is slow when it takes longer than the [`SlowCallThresholds::latency`] or its response
+//! is larger than the [`SlowCallThresholds::response_size`], if any. Each slow call is logged with
+//! its method, the hash of its params, its duration and the size of its response, if measured, and
+//! the methods with the most slow calls are exposed as Prometheus gauges, the [`SLOWEST_METHODS`]
+//! first of them.
+
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+use std::time::Duration;
+
+use jsonrpc_core::{
+	futures::{future::Either, Future},
+	middleware, Call, Metadata, Middleware, Output,
+};
+use parking_lot::Mutex;
+use prometheus_endpoint::{register, Counter, GaugeVec, Opts, PrometheusError, Registry, U64};
+use wasm_timer::Instant;
+
+/// Log target of the slow calls.
+pub const SLOW_CALL_TARGET: &str = "rpc::slow";
+
+/// Number of methods with the most slow calls exposed as Prometheus gauges.
+pub const SLOWEST_METHODS: usize = 10;
+
+/// Number of methods accounted, the others being accounted under [`OTHER_METHODS`].
+const MAX_METHODS: usize = 256;
+
+/// Method of the slow calls past [`MAX_METHODS`] methods.
+const OTHER_METHODS: &str = "other";
+
+/// Thresholds past which a call is slow.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SlowCallThresholds {
+	/// Duration of the slow calls.
+	pub latency: Duration,
+	/// Size of the responses of the slow calls, in bytes, if the responses are measured.
+	///
+	/// The servers don't report the size of the responses they send, so measuring them serializes
+	/// every response once more.
+	pub response_size: Option<usize>,
+}
+
+impl Default for SlowCallThresholds {
+	fn default() -> Self {
+		SlowCallThresholds {
+			latency: Duration::from_secs(1),
+			response_size: None,
+		}
+	}
+}
+
+/// The slow calls of a method.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SlowMethod {
+	/// Number of slow calls.
+	pub calls: u64,
+	/// Longest duration of the slow calls.
+	pub max_duration: Duration,
+	/// Largest response of the slow calls, in bytes, if the responses are measured.
+	pub max_response_size: usize,
+}
+
+#[derive(Clone)]
+struct Metrics {
+	slow_calls: Counter<U64>,
+	slowest_methods: GaugeVec<U64>,
+	slowest_durations: GaugeVec<U64>,
+}
+
+impl Metrics {
+	fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+		Ok(Self {
+			slow_calls: register(Counter::new(
+				"sub_rpc_slow_calls_total",
+				"Total number of RPC calls past the latency or response size thresholds",
+			)?, registry)?,
+			slowest_methods: register(GaugeVec::new(
+				Opts::new(
+					"sub_rpc_slowest_methods",
+					"Number of slow RPC calls of the methods with the most of them",
+				),
+				&["method"],
+			)?, registry)?,
+			slowest_durations: register(GaugeVec::new(
+				Opts::new(
+					"sub_rpc_slowest_methods_max_duration_ms",
+					"Longest slow RPC call of the methods with the most slow calls, in milliseconds",
+				),
+				&["method"],
+			)?, registry)?,
+		})
+	}
+}
+
+/// The slow calls of the methods.
+#[derive(Default)]
+struct SlowMethods {
+	methods: HashMap<String, SlowMethod>,
+	/// The [`SLOWEST_METHODS`] methods with the most slow calls, the ones with the most first.
+	slowest: Vec<String>,
+}
+
+impl SlowMethods {
+	/// Account the slow call of `method`, returning whether the slowest methods changed, and the
+	/// method dropped from them, if any.
+	fn record(&mut self, method: &str, duration: Duration, response_size: usize) -> (bool, Option<String>) {
+		let method = if self.methods.contains_key(method) || self.methods.len() + 1 < MAX_METHODS {
+			method
+		} else {
+			OTHER_METHODS
+		};
+		let slow = self.methods.entry(method.to_owned()).or_default();
+		slow.calls = slow.calls.saturating_add(1);
+		slow.max_duration = slow.max_duration.max(duration);
+		slow.max_response_size = slow.max_response_size.max(response_size);
+
+		// The calls of a method only increase, so it may only move up among the slowest methods,
+		// or replace the last of them.
+		let methods = &self.methods;
+		let is_before = |a: &str, b: &str|
+			most_slow_calls((a, &methods[a]), (b, &methods[b])) == std::cmp::Ordering::Less;
+		let mut dropped = None;
+		let mut index = match self.slowest.iter().position(|slowest| slowest == method) {
+			Some(index) => index,
+			None if self.slowest.len() < SLOWEST_METHODS => {
+				self.slowest.push(method.to_owned());
+				self.slowest.len() - 1
+			},
+			None => match self.slowest.last() {
+				Some(last) if is_before(method, last) => {
+					dropped = self.slowest.pop();
+					self.slowest.push(method.to_owned());
+					self.slowest.len() - 1
+				},
+				_ => return (false, None),
+			},
+		};
+		while index > 0 && is_before(&self.slowest[index], &self.slowest[index - 1]) {
+			self.slowest.swap(index, index - 1);
+			index -= 1;
+		}
+		(true, dropped)
+	}
+}
+
+/// Middleware of the RPC servers reporting the slow calls.
+#[derive(Clone)]
+pub struct RpcMiddleware {
+	thresholds: SlowCallThresholds,
+	metrics: Option<Metrics>,
+	methods: Arc<Mutex<SlowMethods>>,
+}
+
+impl RpcMiddleware {
+	/// Report the calls past the `thresholds`, registering the metrics in `metrics_registry`.
+	pub fn new(
+		thresholds: SlowCallThresholds,
+		metrics_registry: Option<&Registry>,
+	) -> Result<Self, PrometheusError> {
+		Ok(RpcMiddleware {
+			thresholds,
+			metrics: metrics_registry.map(Metrics::register).transpose()?,
+			methods: Default::default(),
+		})
+	}
+
+	/// The methods with slow calls, the ones with the most of them first.
+	pub fn slow_methods(&self) -> Vec<(String, SlowMethod)> {
+		let mut methods = self.methods.lock().methods.iter()
+			.map(|(method, slow)| (method.clone(), slow.clone()))
+			.collect::<Vec<_>>();
+		methods.sort_by(|a, b| most_slow_calls((a.0.as_str(), &a.1), (b.0.as_str(), &b.1)));
+		methods
+	}
+
+	fn record(&self, method: &str, params_hash: u64, duration: Duration, response_size: usize) {
+		let slow_latency = duration > self.thresholds.latency;
+		let slow_size = self.thresholds.response_size.map_or(false, |threshold| response_size > threshold);
+		if !slow_latency && !slow_size {
+			return;
+		}
+
+		sp_tracing::tracing::warn!(
+			target: SLOW_CALL_TARGET,
+			method,
+			params_hash = %format_args!("{:016x}", params_hash),
+			duration_ms = duration.as_millis() as u64,
+			response_size = response_size as u64,
+			slow_latency,
+			slow_size,
+			"Slow RPC call",
+		);
+
+		let mut methods = self.methods.lock();
+		let (changed, dropped) = methods.record(method, duration, response_size);
+
+		if let Some(metrics) = &self.metrics {
+			metrics.slow_calls.inc();
+			if let Some(dropped) = dropped {
+				let _ = metrics.slowest_methods.remove_label_values(&[dropped.as_str()]);
+				let _ = metrics.slowest_durations.remove_label_values(&[dropped.as_str()]);
+			}
+			if changed {
+				for method in &methods.slowest {
+					let slow = &methods.methods[method];
+					metrics.slowest_methods.with_label_values(&[method]).set(slow.calls);
+					metrics.slowest_durations.with_label_values(&[method])
+						.set(slow.max_duration.as_millis() as u64);
+				}
+			}
+		}
+	}
+}
+
+/// Order of the methods with the most slow calls first, by name otherwise.
+fn most_slow_calls(a: (&str, &SlowMethod), b: (&str, &SlowMethod)) -> std::cmp::Ordering {
+	b.1.calls.cmp(&a.1.calls).then_with(|| a.0.cmp(b.0))
+}
+
+/// Hash of the params of a call, to tell the repeated queries apart in the logs.
+///
+/// This is not a cryptographic hash, and it is only stable across the runs of the same binary.
+fn params_hash(params: &jsonrpc_core::Params) -> u64 {
+	let mut hasher = DefaultHasher::new();
+	serde_json::to_string(params).unwrap_or_default().hash(&mut hasher);
+	hasher.finish()
+}
+
+impl<M: Metadata> Middleware<M> for RpcMiddleware {
+	type Future = middleware::NoopFuture;
+	type CallFuture = middleware::NoopCallFuture;
+
+	fn on_call<F, X>(&self, call: Call, meta: M, next: F) -> Either<Self::CallFuture, X>
+	where
+		F: Fn(Call, M) -> X + Send + Sync,
+		X: Future<Item = Option<Output>, Error = ()> + Send + 'static,
+	{
+		let (method, params_hash) = match &call {
+			Call::MethodCall(call) => (call.method.clone(), params_hash(&call.params)),
+			Call::Notification(notification) =>
+				(notification.method.clone(), params_hash(&notification.params)),
+			Call::Invalid { .. } => return Either::B(next(call, meta)),
+		};
+
+		let middleware = self.clone();
+		let start = Instant::now();
+		Either::A(Box::new(next(call, meta).map(move |output| {
+			// The responses are serialized once more to be measured, as the servers don't report
+			// the size of the responses they send, so only with a size threshold.
+			let response_size = match middleware.thresholds.response_size {
+				Some(_) => output.as_ref()
+					.and_then(|output| serde_json::to_string(output).ok())
+					.map_or(0, |response| response.len()),
+				None => 0,
+			};
+			middleware.record(&method, params_hash, start.elapsed(), response_size);
+			output
+		})))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use jsonrpc_core::{MetaIoHandler, Value};
+
+	#[test]
+	fn reports_the_slow_calls() {
+		let registry = Registry::new();
+		let middleware = RpcMiddleware::new(
+			SlowCallThresholds { latency: Duration::from_secs(60), response_size: Some(64) },
+			Some(&registry),
+		).unwrap();
+		let mut io = MetaIoHandler::<(), _>::with_middleware(middleware.clone());
+		io.add_method("small", |_| Ok(Value::Bool(true)));
+		io.add_method("large", |_| Ok(Value::String("a".repeat(64))));
+
+		let call = |method: &str| io.handle_request_sync(
+			&format!(r#"{{"jsonrpc":"2.0","method":"{}","params":[],"id":1}}"#, method),
+			(),
+		).unwrap();
+		call("small");
+		call("large");
+		call("large");
+
+		let slow_methods = middleware.slow_methods();
+		assert_eq!(slow_methods.len(), 1);
+		let (method, slow) = &slow_methods[0];
+		assert_eq!((method.as_str(), slow.calls), ("large", 2));
+		assert!(slow.max_response_size > 64);
+
+		let gathered = registry.gather();
+		let slowest = gathered.iter().find(|family| family.get_name() == "sub_rpc_slowest_methods").unwrap();
+		let metric = &slowest.get_metric()[0];
+		assert_eq!(metric.get_label()[0].get_value(), "large");
+		assert_eq!(metric.get_gauge().get_value() as u64, 2);
+	}
+
+	#[test]
+	fn keeps_the_methods_with_the_most_slow_calls() {
+		let mut methods = SlowMethods::default();
+		for method in 0..SLOWEST_METHODS {
+			methods.record(&format!("method_{:02}", method), Duration::default(), 0);
+		}
+		assert_eq!(methods.record("method_05", Duration::default(), 0), (true, None));
+		assert_eq!(methods.slowest[0], "method_05");
+
+		// A method with as many slow calls as the last one only replaces it once it has more.
+		assert_eq!(methods.record("other_method", Duration::default(), 0), (false, None));
+		assert_eq!(
+			methods.record("other_method", Duration::default(), 0),
+			(true, Some(format!("method_{:02}", SLOWEST_METHODS - 1))),
+		);
+		assert_eq!(methods.slowest.len(), SLOWEST_METHODS);
+		assert_eq!(&methods.slowest[..3], &["method_05", "other_method", "method_00"]);
+	}
+}
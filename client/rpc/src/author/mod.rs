@@ -22,7 +22,7 @@
 mod tests;
 
 use std::{sync::Arc, convert::TryInto};
-use log::warn;
+use log::{info, warn};
 
 use sp_blockchain::{Error as ClientError, HeaderBackend};
 
@@ -35,14 +35,14 @@ use futures::future::{ready, FutureExt, TryFutureExt};
 use sc_rpc_api::DenyUnsafe;
 use jsonrpc_pubsub::{typed::Subscriber, SubscriptionId, manager::SubscriptionManager};
 use codec::{Encode, Decode};
-use sp_core::{Bytes, traits::BareCryptoStorePtr};
-use sp_api::ProvideRuntimeApi;
+use sp_core::{Bytes, hexdisplay::HexDisplay, traits::BareCryptoStorePtr};
+use sp_api::{ApiExt, ProvideRuntimeApi};
 use sp_runtime::generic;
 use sp_transaction_pool::{
 	TransactionPool, InPoolTransaction, TransactionStatus, TransactionSource,
 	BlockHash, TxHash, TransactionFor, error::IntoPoolError,
 };
-use sp_session::SessionKeys;
+use sp_session::{SessionIndex, SessionKeys};
 
 /// Re-export the API for backward compatibility.
 pub use sc_rpc_api::author::*;
@@ -81,6 +81,71 @@ impl<P, Client> Author<P, Client> {
 	}
 }
 
+impl<P, Client> Author<P, Client>
+	where
+		P: TransactionPool,
+		Client: HeaderBackend<P::Block> + ProvideRuntimeApi<P::Block>,
+		Client::Api: SessionKeys<P::Block, Error = ClientError>,
+{
+	/// The public keys of the SCALE encoded session keys, empty if the runtime can't decode them.
+	fn public_keys(
+		&self,
+		at: &generic::BlockId<P::Block>,
+		session_keys: &[u8],
+	) -> Vec<keys::SessionPublicKey> {
+		match self.client.runtime_api().decode_session_keys(at, session_keys.to_vec()) {
+			Ok(Some(keys)) => keys.into_iter()
+				.map(|(public, key_type)| keys::SessionPublicKey {
+					key_type: String::from_utf8_lossy(&key_type.0).into_owned(),
+					public: public.into(),
+				})
+				.collect(),
+			Ok(None) => {
+				warn!(target: "session", "Session keys are not encoded correctly");
+				Vec::new()
+			},
+			Err(e) => {
+				warn!(target: "session", "Unable to decode the session keys: {:?}", e);
+				Vec::new()
+			},
+		}
+	}
+
+	/// The current session at `at` and the SCALE encoded session keys set by `validator`, both
+	/// `None` if the runtime doesn't provide them.
+	fn session_state(
+		&self,
+		at: &generic::BlockId<P::Block>,
+		validator: Option<&[u8]>,
+	) -> (Option<SessionIndex>, Option<Vec<u8>>) {
+		let runtime_api = self.client.runtime_api();
+		// The session state is only queried from version 2 of the api.
+		match runtime_api.has_api_with::<dyn SessionKeys<P::Block, Error = ClientError>, _>(
+			at,
+			|version| version >= 2,
+		) {
+			Ok(true) => {},
+			Ok(false) => return (None, None),
+			Err(e) => {
+				warn!(target: "session", "Unable to check the session keys api at {}: {:?}", at, e);
+				return (None, None)
+			},
+		}
+
+		let session_index = runtime_api.current_session_index(at).unwrap_or_else(|e| {
+			warn!(target: "session", "Unable to read the current session at {}: {:?}", at, e);
+			None
+		});
+		let previous_keys = validator.and_then(|validator| {
+			runtime_api.next_session_keys(at, validator.to_vec()).unwrap_or_else(|e| {
+				warn!(target: "session", "Unable to read the session keys at {}: {:?}", at, e);
+				None
+			})
+		});
+		(session_index, previous_keys)
+	}
+}
+
 /// Currently we treat all RPC transactions as externals.
 ///
 /// Possibly in the future we could allow opt-in for special treatment
@@ -91,7 +156,8 @@ const TX_SOURCE: TransactionSource = TransactionSource::External;
 impl<P, Client> AuthorApi<TxHash<P>, BlockHash<P>> for Author<P, Client>
 	where
 		P: TransactionPool + Sync + Send + 'static,
-		Client: HeaderBackend<P::Block> + ProvideRuntimeApi<P::Block> + Send + Sync + 'static,
+		Client: HeaderBackend<P::Block> + ProvideRuntimeApi<P::Block>
+			+ Send + Sync + 'static,
 		Client::Api: SessionKeys<P::Block, Error = ClientError>,
 {
 	type Metadata = crate::Metadata;
@@ -121,6 +187,51 @@ impl<P, Client> AuthorApi<TxHash<P>, BlockHash<P>> for Author<P, Client>
 		).map(Into::into).map_err(|e| Error::Client(Box::new(e)))
 	}
 
+	fn rotate_keys_with_info(
+		&self,
+		validator: Option<Bytes>,
+	) -> Result<keys::RotatedKeys<BlockHash<P>>> {
+		self.deny_unsafe.check_if_safe()?;
+
+		// The keys are generated at the best block, but they are only used once `set_keys` is
+		// included in some later block.
+		let generated_at = self.client.info().best_hash;
+		let at = generic::BlockId::Hash(generated_at);
+		let new_keys = self.client.runtime_api().generate_session_keys(&at, None)
+			.map_err(|e| Error::Client(Box::new(e)))?;
+		// The keys are in the keystore from now on, so they are returned even if the runtime
+		// fails to decode them or to provide the session state.
+		let public_keys = self.public_keys(&at, &new_keys);
+
+		let (session_index, previous_keys) = self.session_state(
+			&at,
+			validator.as_ref().map(|validator| &validator[..]),
+		);
+		let previous_public_keys = previous_keys.as_ref()
+			.map(|keys| self.public_keys(&at, keys))
+			.unwrap_or_default();
+
+		info!(
+			target: "session",
+			"🔑 Generated new session keys at block {}: {}",
+			generated_at,
+			public_keys.iter()
+				.map(|key| format!("{}: {}", key.key_type, HexDisplay::from(&&*key.public)))
+				.collect::<Vec<_>>()
+				.join(", "),
+		);
+
+		Ok(keys::RotatedKeys {
+			keys: new_keys.into(),
+			public_keys,
+			generated_at,
+			session_index,
+			earliest_active_session: session_index.map(|index| index.saturating_add(2)),
+			previous_keys: previous_keys.map(Into::into),
+			previous_public_keys,
+		})
+	}
+
 	fn has_session_keys(&self, session_keys: Bytes) -> Result<bool> {
 		self.deny_unsafe.check_if_safe()?;
 
@@ -160,6 +271,24 @@ impl<P, Client> AuthorApi<TxHash<P>, BlockHash<P>> for Author<P, Client>
 		Ok(self.pool.ready().map(|tx| tx.data().encode().into()).collect())
 	}
 
+	fn pool_status_detailed(&self) -> Result<status::DetailedPoolStatus> {
+		Ok(status::DetailedPoolStatus::new(self.pool.status(), self.pool.revalidation_stats()))
+	}
+
+	fn pending_extrinsic_info(
+		&self,
+		hash: TxHash<P>,
+	) -> Result<Option<status::PendingExtrinsicInfo<TxHash<P>>>> {
+		let (tx, queue) = match self.pool.ready_transaction(&hash) {
+			Some(tx) => (tx, status::PoolQueue::Ready),
+			None => match self.pool.future_transaction(&hash) {
+				Some(tx) => (tx, status::PoolQueue::Future),
+				None => return Ok(None),
+			},
+		};
+		Ok(Some(status::PendingExtrinsicInfo::new(&*tx, queue)))
+	}
+
 	fn remove_extrinsic(
 		&self,
 		bytes_or_hash: Vec<hash::ExtrinsicOrHash<TxHash<P>>>,
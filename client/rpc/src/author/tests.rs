@@ -199,6 +199,43 @@ fn should_return_pending_extrinsics() {
 	);
 }
 
+#[test]
+fn should_return_detailed_pool_status() {
+	let p = TestSetup::default().author();
+
+	let ex = uxt(AccountKeyring::Alice, 0);
+	AuthorApi::submit_extrinsic(&p, ex.encode().into()).wait().unwrap();
+	assert_matches!(
+		p.pool_status_detailed(),
+		Ok(status::DetailedPoolStatus { ready: 1, future: 0, ref revalidation, .. })
+			if revalidation.invalidated == 0
+	);
+}
+
+#[test]
+fn should_return_pending_extrinsic_info() {
+	let p = TestSetup::default().author();
+
+	let ready = AuthorApi::submit_extrinsic(&p, uxt(AccountKeyring::Alice, 0).encode().into())
+		.wait()
+		.unwrap();
+	let future = AuthorApi::submit_extrinsic(&p, uxt(AccountKeyring::Alice, 2).encode().into())
+		.wait()
+		.unwrap();
+
+	assert_matches!(
+		p.pending_extrinsic_info(ready),
+		Ok(Some(status::PendingExtrinsicInfo { queue: status::PoolQueue::Ready, ref requires, .. }))
+			if requires.is_empty()
+	);
+	assert_matches!(
+		p.pending_extrinsic_info(future),
+		Ok(Some(status::PendingExtrinsicInfo { queue: status::PoolQueue::Future, ref requires, .. }))
+			if requires.len() == 1
+	);
+	assert_matches!(p.pending_extrinsic_info(Default::default()), Ok(None));
+}
+
 #[test]
 fn should_remove_extrinsics() {
 	let setup = TestSetup::default();
@@ -257,6 +294,39 @@ fn should_rotate_keys() {
 	assert!(sr25519_public_keys.contains(&CryptoTypePublicPair(sr25519::CRYPTO_ID, session_keys.sr25519.to_raw_vec())));
 }
 
+#[test]
+fn should_rotate_keys_with_info() {
+	let setup = TestSetup::default();
+	let p = setup.author();
+
+	let info = p.rotate_keys_with_info(Some(vec![1; 32].into())).expect("Rotates the keys");
+
+	let session_keys = SessionKeys::decode(&mut &info.keys[..])
+		.expect("SessionKeys decode successfully");
+
+	assert_eq!(info.generated_at, setup.client.info().best_hash);
+	assert_eq!(info.public_keys, vec![
+		keys::SessionPublicKey {
+			key_type: "ed25".into(),
+			public: session_keys.ed25519.to_raw_vec().into(),
+		},
+		keys::SessionPublicKey {
+			key_type: "sr25".into(),
+			public: session_keys.sr25519.to_raw_vec().into(),
+		},
+		keys::SessionPublicKey {
+			key_type: "ecds".into(),
+			public: session_keys.ecdsa.to_raw_vec().into(),
+		},
+	]);
+	// The test runtime has no sessions.
+	assert_eq!(info.session_index, None);
+	assert_eq!(info.earliest_active_session, None);
+	assert_eq!(info.previous_keys, None);
+	assert!(info.previous_public_keys.is_empty());
+	assert!(p.has_session_keys(info.keys).unwrap());
+}
+
 #[test]
 fn test_has_session_keys() {
 	let setup = TestSetup::default();
@@ -24,7 +24,7 @@ use log::warn;
 use jsonrpc_pubsub::{typed::Subscriber, SubscriptionId, manager::SubscriptionManager};
 use rpc::{Result as RpcResult, futures::{stream, Future, Sink, Stream, future::result}};
 
-use sc_rpc_api::state::ReadProof;
+use sc_rpc_api::state::{ReadProof, StorageDiff, StorageEntryDiff};
 use sc_client_api::backend::Backend;
 use sp_blockchain::{Result as ClientResult, Error as ClientError, HeaderMetadata, CachedHeaderMetadata, HeaderBackend};
 use sc_client_api::BlockchainEvents;
@@ -43,6 +43,10 @@ use super::{StateBackend, ChildStateBackend, error::{FutureResult, Error, Result
 use std::marker::PhantomData;
 use sc_client_api::{CallExecutor, StorageProvider, ExecutorProvider, ProofProvider};
 
+/// Maximal number of keys compared by a call of state_storageDiff, as many as the keys of a page
+/// of state_getKeysPaged.
+const STORAGE_DIFF_MAX_KEYS: usize = super::STORAGE_KEYS_PAGED_MAX_COUNT as usize;
+
 /// Ranges to query in state_queryStorage.
 struct QueryStorageRange<Block: BlockT> {
 	/// Hashes of all the blocks in the range.
@@ -214,6 +218,84 @@ impl<BE, Block: BlockT, Client> FullState<BE, Block, Client>
 		changes.extend(changes_map.into_iter().map(|(_, cs)| cs));
 		Ok(())
 	}
+
+	/// Merges the entries of both states in lexicographic order of their keys, collecting those that
+	/// differ, until `count` of them are collected or [`STORAGE_DIFF_MAX_KEYS`] keys are compared.
+	fn diff_storage(
+		&self,
+		from: Block::Hash,
+		to: Block::Hash,
+		prefix: Option<StorageKey>,
+		count: u32,
+		start_key: Option<StorageKey>,
+	) -> ClientResult<StorageDiff<Block::Hash>> {
+		const PEEKED_PROOF: &str = "The entry has been peeked; qed";
+
+		let started = std::time::Instant::now();
+		let (from_id, to_id) = (BlockId::Hash(from), BlockId::Hash(to));
+		let mut from_entries = self.client
+			.storage_keys_iter(&from_id, prefix.as_ref(), start_key.as_ref())?
+			.with_values()
+			.peekable();
+		let mut to_entries = self.client
+			.storage_keys_iter(&to_id, prefix.as_ref(), start_key.as_ref())?
+			.with_values()
+			.peekable();
+
+		let mut changes = Vec::new();
+		let mut compared = 0;
+		let mut last_key = None;
+		let complete = loop {
+			if changes.len() >= count as usize || compared >= STORAGE_DIFF_MAX_KEYS {
+				break false;
+			}
+			// The next key of either state, once if both have it, with its value in each state.
+			let (key, from_value, to_value) = match (from_entries.peek(), to_entries.peek()) {
+				(None, None) => break true,
+				(Some((from_key, _)), Some((to_key, _))) if from_key == to_key => {
+					let (key, from_value) = from_entries.next().expect(PEEKED_PROOF);
+					let (_, to_value) = to_entries.next().expect(PEEKED_PROOF);
+					(key, Some(from_value), Some(to_value))
+				},
+				(Some((from_key, _)), Some((to_key, _))) if from_key > to_key => {
+					let (key, to_value) = to_entries.next().expect(PEEKED_PROOF);
+					(key, None, Some(to_value))
+				},
+				(Some(_), _) => {
+					let (key, from_value) = from_entries.next().expect(PEEKED_PROOF);
+					(key, Some(from_value), None)
+				},
+				(None, Some(_)) => {
+					let (key, to_value) = to_entries.next().expect(PEEKED_PROOF);
+					(key, None, Some(to_value))
+				},
+			};
+
+			compared += 1;
+			if from_value != to_value {
+				changes.push(StorageEntryDiff { key: key.clone(), from: from_value, to: to_value });
+			}
+			last_key = Some(key);
+		};
+
+		sp_tracing::tracing::debug!(
+			target: "rpc",
+			from = ?from,
+			to = ?to,
+			compared,
+			changes = changes.len(),
+			complete,
+			duration_ms = started.elapsed().as_millis() as u64,
+			"Compared the storage of two blocks",
+		);
+
+		Ok(StorageDiff {
+			from,
+			to,
+			changes,
+			next_key: if complete { None } else { last_key.or(start_key) },
+		})
+	}
 }
 
 impl<BE, Block, Client> StateBackend<Block, Client> for FullState<BE, Block, Client> where
@@ -401,6 +483,19 @@ impl<BE, Block, Client> StateBackend<Block, Client> for FullState<BE, Block, Cli
 		))
 	}
 
+	fn storage_diff(
+		&self,
+		from: Block::Hash,
+		to: Block::Hash,
+		prefix: Option<StorageKey>,
+		count: u32,
+		start_key: Option<StorageKey>,
+	) -> FutureResult<StorageDiff<Block::Hash>> {
+		Box::new(result(
+			self.diff_storage(from, to, prefix, count, start_key).map_err(client_err)
+		))
+	}
+
 	fn subscribe_runtime_version(
 		&self,
 		_meta: crate::Metadata,
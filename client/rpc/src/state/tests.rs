@@ -431,6 +431,51 @@ fn should_split_ranges() {
 }
 
 
+#[test]
+fn should_return_storage_diff() {
+	let mut client = Arc::new(substrate_test_runtime_client::new());
+	let (api, _child) = new_full(client.clone(), SubscriptionManager::new(Arc::new(TaskExecutor)));
+
+	let mut add_block = |changes: &[(&[u8], Option<&[u8]>)]| {
+		let mut builder = client.new_block(Default::default()).unwrap();
+		for (key, value) in changes {
+			builder.push_storage_change(key.to_vec(), value.map(|value| value.to_vec())).unwrap();
+		}
+		let block = builder.build().unwrap().block;
+		let hash = block.header.hash();
+		client.import(BlockOrigin::Own, block).unwrap();
+		hash
+	};
+	let block1_hash = add_block(&[(b"diff:a", Some(b"1")), (b"diff:b", Some(b"1")), (b"diff:c", Some(b"1"))]);
+	let block2_hash = add_block(&[(b"diff:a", None), (b"diff:b", Some(b"2")), (b"diff:d", Some(b"2"))]);
+
+	let key = |key: &[u8]| StorageKey(key.to_vec());
+	let data = |data: &[u8]| Some(StorageData(data.to_vec()));
+	let prefix = Some(key(b"diff:"));
+	let diff = api.storage_diff(block1_hash, block2_hash, prefix.clone(), 10, None).wait().unwrap();
+	assert_eq!(diff, StorageDiff {
+		from: block1_hash,
+		to: block2_hash,
+		changes: vec![
+			StorageEntryDiff { key: key(b"diff:a"), from: data(b"1"), to: None },
+			StorageEntryDiff { key: key(b"diff:b"), from: data(b"1"), to: data(b"2") },
+			StorageEntryDiff { key: key(b"diff:d"), from: None, to: data(b"2") },
+		],
+		next_key: None,
+	});
+
+	let page = api.storage_diff(block1_hash, block2_hash, prefix.clone(), 1, Some(key(b"diff:a")))
+		.wait()
+		.unwrap();
+	assert_eq!(page.changes, vec![StorageEntryDiff { key: key(b"diff:b"), from: data(b"1"), to: data(b"2") }]);
+	assert_eq!(page.next_key, Some(key(b"diff:b")));
+
+	assert_matches!(
+		api.storage_diff(block1_hash, block2_hash, prefix, STORAGE_KEYS_PAGED_MAX_COUNT + 1, None).wait(),
+		Err(Error::InvalidCount { .. })
+	);
+}
+
 #[test]
 fn should_return_runtime_version() {
 	let client = Arc::new(substrate_test_runtime_client::new());
@@ -107,6 +107,26 @@ fn api<T: Into<Option<Status>>>(sync: T) -> System<Block> {
 				Request::NodeRoles(sender) => {
 					let _ = sender.send(vec![NodeRole::Authority]);
 				}
+				Request::TaskStatus(sender) => {
+					let _ = sender.send(vec![TaskStatus {
+						name: "import-queue".into(),
+						essential: true,
+						running: 1,
+						exits: vec![],
+					}]);
+				}
+				Request::ExportersStatus(sender) => {
+					let _ = sender.send(vec![ExporterStatus {
+						name: "loki".into(),
+						queued: 0,
+						sent: 10,
+						dropped: 2,
+						reconnects: 1,
+						sends: 1,
+						send_time_us: 300,
+						last_send_latency_us: Some(300),
+					}]);
+				}
 			};
 
 			future::ready(())
@@ -297,6 +317,22 @@ fn system_node_roles() {
 	);
 }
 
+#[test]
+fn system_task_status() {
+	let mut runtime = tokio::runtime::current_thread::Runtime::new().unwrap();
+
+	let res = runtime.block_on(api(None).system_task_status()).unwrap();
+	assert_eq!(
+		res,
+		vec![TaskStatus {
+			name: "import-queue".into(),
+			essential: true,
+			running: 1,
+			exits: vec![],
+		}]
+	);
+}
+
 #[test]
 fn system_network_add_reserved() {
 	let good_peer_id = "/ip4/198.51.100.19/tcp/30333/p2p/QmSk5HQbn6LhUwDiNMseVUjuRYhEtYj4aUZ6WfWoGURpdV";
@@ -320,3 +356,12 @@ fn system_network_remove_reserved() {
 	assert_eq!(runtime.block_on(good_fut), Ok(()));
 	assert!(runtime.block_on(bad_fut).is_err());
 }
+
+#[test]
+fn system_exporters_status() {
+	let mut runtime = tokio::runtime::current_thread::Runtime::new().unwrap();
+
+	let res = runtime.block_on(api(None).system_exporters_status()).unwrap();
+	assert_eq!(res.len(), 1);
+	assert_eq!((res[0].name.as_str(), res[0].sent, res[0].dropped), ("loki", 10, 2));
+}
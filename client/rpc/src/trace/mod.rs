@@ -0,0 +1,93 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Substrate trace API.
+
+#[cfg(test)]
+mod tests;
+
+/// Re-export the API for backward compatibility.
+pub use sc_rpc_api::trace::*;
+use self::error::{Error, Result};
+use codec::Decode;
+use sc_client_api::backend::TraceSummaryStorage;
+use sc_tracing::{BlockSummary, CallDrift, PovBreakdown, PovBreakdowns, WeightDrift};
+use sp_runtime::traits::Block as BlockT;
+use std::sync::Arc;
+
+/// Number of summaries returned by `state_recentTraces` by default.
+const DEFAULT_RECENT_TRACES: u32 = 10;
+
+/// Maximum number of summaries returned by `state_recentTraces`.
+const MAX_RECENT_TRACES: u32 = 256;
+
+/// Trace API
+pub struct Trace<Block: BlockT> {
+	/// Trace summaries storage, if the summaries are stored
+	storage: Option<Arc<dyn TraceSummaryStorage<Block>>>,
+	/// Drift of the weights, if measured
+	weight_drift: Option<WeightDrift>,
+	/// Breakdowns of the proofs of the built blocks, if broken down
+	pov_breakdowns: Option<PovBreakdowns>,
+}
+
+impl<Block: BlockT> Trace<Block> {
+	/// Create new instance of Trace API.
+	pub fn new(
+		storage: Option<Arc<dyn TraceSummaryStorage<Block>>>,
+		weight_drift: Option<WeightDrift>,
+		pov_breakdowns: Option<PovBreakdowns>,
+	) -> Self {
+		Trace { storage, weight_drift, pov_breakdowns }
+	}
+}
+
+impl<Block: BlockT> TraceApi<Block::Hash> for Trace<Block> {
+	fn block_summary(&self, hash: Block::Hash) -> Result<Option<BlockSummary>> {
+		let storage = match &self.storage {
+			Some(storage) => storage,
+			None => return Ok(None),
+		};
+		let summary = storage.summary(&hash).map_err(|e| Error::Client(Box::new(e)))?;
+		Ok(summary.map(|summary| BlockSummary::decode(&mut &summary[..])).transpose()?)
+	}
+
+	fn recent_traces(&self, count: Option<u32>) -> Result<Vec<BlockSummary>> {
+		let storage = match &self.storage {
+			Some(storage) => storage,
+			None => return Ok(Vec::new()),
+		};
+		let count = count.unwrap_or(DEFAULT_RECENT_TRACES).min(MAX_RECENT_TRACES);
+		let summaries = storage.recent_summaries(count as usize).map_err(|e| Error::Client(Box::new(e)))?;
+		Ok(summaries.into_iter()
+			.map(|summary| BlockSummary::decode(&mut &summary[..]))
+			.collect::<std::result::Result<_, _>>()?)
+	}
+
+	fn weight_drift(&self) -> Result<Vec<CallDrift>> {
+		let weight_drift = self.weight_drift.as_ref().ok_or(Error::WeightDriftDisabled)?;
+		Ok(weight_drift.calls())
+	}
+
+	fn pov_breakdown(&self, block_number: Option<u64>) -> Result<Vec<PovBreakdown>> {
+		let pov_breakdowns = self.pov_breakdowns.as_ref().ok_or(Error::PovBreakdownsDisabled)?;
+		Ok(pov_breakdowns.breakdowns().into_iter()
+			.filter(|breakdown| block_number.map_or(true, |number| breakdown.block_number == number))
+			.collect())
+	}
+}
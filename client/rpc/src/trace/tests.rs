@@ -0,0 +1,116 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use super::*;
+use assert_matches::assert_matches;
+use codec::Encode;
+use parking_lot::Mutex;
+use sc_client_api::backend::TouchedKeys;
+use sc_tracing::SpanSummary;
+use sp_runtime::traits::NumberFor;
+use substrate_test_runtime_client::runtime::{Block, Hash};
+
+#[derive(Default)]
+struct TestStorage(Mutex<Vec<(Hash, Vec<u8>)>>);
+
+impl TraceSummaryStorage<Block> for TestStorage {
+	fn insert_summary(&self, hash: Hash, _: NumberFor<Block>, summary: &[u8]) -> sp_blockchain::Result<()> {
+		let mut summaries = self.0.lock();
+		summaries.retain(|(h, _)| *h != hash);
+		summaries.push((hash, summary.to_vec()));
+		Ok(())
+	}
+
+	fn prune_summaries(&self, _: NumberFor<Block>) -> sp_blockchain::Result<()> {
+		Ok(())
+	}
+
+	fn summary(&self, hash: &Hash) -> sp_blockchain::Result<Option<Vec<u8>>> {
+		Ok(self.0.lock().iter().find(|(h, _)| h == hash).map(|(_, summary)| summary.clone()))
+	}
+
+	fn recent_summaries(&self, count: usize) -> sp_blockchain::Result<Vec<Vec<u8>>> {
+		Ok(self.0.lock().iter().rev().take(count).map(|(_, summary)| summary.clone()).collect())
+	}
+
+	fn revert_summaries(&self, _: &[(Hash, NumberFor<Block>)]) -> sp_blockchain::Result<Vec<TouchedKeys>> {
+		Ok(Vec::new())
+	}
+}
+
+fn summary(number: u64) -> BlockSummary {
+	BlockSummary {
+		block_hash: format!("{:?}", Hash::repeat_byte(number as u8)),
+		block_number: number,
+		duration_ns: 1_000,
+		spans: vec![SpanSummary {
+			target: "pallet_balances".into(),
+			name: "transfer".into(),
+			count: 2,
+			duration_ns: 500,
+		}],
+		counters: Vec::new(),
+	}
+}
+
+#[test]
+fn block_summary_should_work() {
+	let storage = Arc::new(TestStorage::default());
+	let summary = summary(1);
+	storage.insert_summary(Hash::repeat_byte(1), 1, &summary.encode()).unwrap();
+	storage.insert_summary(Hash::repeat_byte(2), 2, b"\x01").unwrap();
+	let trace = Trace::new(Some(storage), None, None);
+
+	assert_matches!(trace.block_summary(Hash::repeat_byte(1)), Ok(Some(ref s)) if *s == summary);
+	assert_matches!(trace.block_summary(Hash::repeat_byte(3)), Ok(None));
+	assert_matches!(trace.block_summary(Hash::repeat_byte(2)), Err(Error::InvalidSummary(_)));
+}
+
+#[test]
+fn recent_traces_should_work() {
+	let storage = Arc::new(TestStorage::default());
+	for number in 1..=12 {
+		storage.insert_summary(Hash::repeat_byte(number), number.into(), &summary(number.into()).encode()).unwrap();
+	}
+	let trace = Trace::new(Some(storage), None, None);
+
+	assert_eq!(trace.recent_traces(Some(2)).unwrap(), vec![summary(12), summary(11)]);
+	assert_eq!(trace.recent_traces(None).unwrap().len(), 10);
+	assert_matches!(Trace::<Block>::new(None, None, None).recent_traces(None), Ok(ref s) if s.is_empty());
+}
+
+#[test]
+fn weight_drift_should_work() {
+	let trace = Trace::<Block>::new(None, Some(WeightDrift::new(10, 2.0)), None);
+
+	assert_matches!(trace.block_summary(Hash::repeat_byte(1)), Ok(None));
+	assert_matches!(trace.weight_drift(), Ok(ref calls) if calls.is_empty());
+	assert_matches!(Trace::<Block>::new(None, None, None).weight_drift(), Err(Error::WeightDriftDisabled));
+}
+
+#[test]
+fn pov_breakdown_should_work() {
+	let trace = Trace::<Block>::new(None, None, Some(PovBreakdowns::new(10)));
+
+	assert_matches!(trace.pov_breakdown(None), Ok(ref breakdowns) if breakdowns.is_empty());
+	assert_matches!(trace.pov_breakdown(Some(1)), Ok(ref breakdowns) if breakdowns.is_empty());
+	assert_matches!(
+		Trace::<Block>::new(None, None, None).pov_breakdown(None),
+		Err(Error::PovBreakdownsDisabled)
+	);
+}
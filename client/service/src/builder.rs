@@ -20,7 +20,11 @@ use crate::{
 	NetworkStatus, NetworkState, error::Error, DEFAULT_PROTOCOL_ID, MallocSizeOfWasm,
 	TelemetryConnectionSinks, RpcHandlers, NetworkStatusSinks,
 	start_rpc_servers, build_network_future, TransactionPoolAdapter, TaskManager, SpawnTaskHandle,
-	status_sinks, metrics::MetricsService,
+	start_grpc_server, status_sinks, health, metrics::{
+		MetricsService, influx_metrics, register_dispatch_origins, register_finality_metrics,
+		register_import_deadlines, register_loki_metrics, register_runtime_counters,
+		register_span_metrics, register_trace_queue_metrics, register_weight_drift,
+	},
 	client::{light, Client, ClientConfig},
 	config::{Configuration, KeystoreConfig, PrometheusConfig},
 };
@@ -36,15 +40,16 @@ use sp_consensus::{
 use futures::{FutureExt, StreamExt, future::ready, channel::oneshot};
 use jsonrpc_pubsub::manager::SubscriptionManager;
 use sc_keystore::Store as Keystore;
-use log::{info, warn, error};
+use log::{info, warn, error, debug};
 use sc_network::config::{Role, FinalityProofProvider, OnDemand, BoxFinalityProofRequestBuilder};
-use sc_network::NetworkService;
+use sc_network::{NetworkService, SyncState};
 use parking_lot::RwLock;
+use codec::{Decode, Encode};
 use sp_runtime::generic::BlockId;
 use sp_runtime::traits::{
 	Block as BlockT, SaturatedConversion, HashFor, Zero, BlockIdTo,
 };
-use sp_api::{ProvideRuntimeApi, CallApiAt};
+use sp_api::{ProvideRuntimeApi, CallApiAt, Metadata};
 use sc_executor::{NativeExecutor, NativeExecutionDispatch, RuntimeInfo};
 use std::{collections::HashMap, sync::Arc};
 use wasm_timer::SystemTime;
@@ -56,7 +61,7 @@ use sp_core::traits::{CodeExecutor, SpawnNamed};
 use sp_runtime::BuildStorage;
 use sc_client_api::{
 	BlockBackend, BlockchainEvents,
-	backend::StorageProvider,
+	backend::{StorageProvider, TraceSummaryStorage},
 	proof_provider::ProofProvider,
 	execution_extensions::ExecutionExtensions
 };
@@ -196,6 +201,37 @@ pub fn new_full_client<TBl, TRtApi, TExecDisp>(
 	new_full_parts(config).map(|parts| parts.0)
 }
 
+/// Create the task manager of the node, serving the task console if configured.
+fn new_task_manager(config: &Configuration) -> Result<TaskManager, Error> {
+	let registry = config.prometheus_config.as_ref().map(|cfg| &cfg.registry);
+	let mut task_manager = TaskManager::new(config.task_executor.clone(), registry)?;
+	if let Some(addr) = config.tokio_console {
+		task_manager.enable_console(addr)?;
+	}
+	Ok(task_manager)
+}
+
+/// Open the keystore described by the given configuration.
+fn open_keystore(config: &KeystoreConfig) -> Result<Arc<RwLock<Keystore>>, Error> {
+	match config {
+		KeystoreConfig::Path { path, password, audit_log } => {
+			let keystore = Keystore::open(path.clone(), password.clone())?;
+			if let Some(audit_log) = audit_log {
+				keystore.write().set_audit_log(audit_log.clone())?;
+			}
+			Ok(keystore)
+		},
+		KeystoreConfig::Remote { signer, audit_log } => {
+			let keystore = Keystore::new_remote(signer.clone())?;
+			if let Some(audit_log) = audit_log {
+				keystore.write().set_audit_log(audit_log.clone())?;
+			}
+			Ok(keystore)
+		},
+		KeystoreConfig::InMemory => Ok(Keystore::new_in_memory()),
+	}
+}
+
 /// Create the initial parts of a full node.
 pub fn new_full_parts<TBl, TRtApi, TExecDisp>(
 	config: &Configuration,
@@ -203,19 +239,14 @@ pub fn new_full_parts<TBl, TRtApi, TExecDisp>(
 	TBl: BlockT,
 	TExecDisp: NativeExecutionDispatch + 'static,
 {
-	let keystore = match &config.keystore {
-		KeystoreConfig::Path { path, password } => Keystore::open(
-			path.clone(),
-			password.clone()
-		)?,
-		KeystoreConfig::InMemory => Keystore::new_in_memory(),
-	};
+	let keystore = open_keystore(&config.keystore)?;
 
-	let task_manager = {
-		let registry = config.prometheus_config.as_ref().map(|cfg| &cfg.registry);
-		TaskManager::new(config.task_executor.clone(), registry)?
-	};
+	let task_manager = new_task_manager(config)?;
 
+	#[cfg(feature = "wasmtime")]
+	sc_executor::set_wasmtime_jitdump(config.wasmtime_jitdump);
+	#[cfg(feature = "wasmtime")]
+	sc_executor::set_wasmtime_cache(config.wasmtime_cache.clone());
 	let executor = NativeExecutor::<TExecDisp>::new(
 		config.wasm_method,
 		config.default_heap_pages,
@@ -272,19 +303,14 @@ pub fn new_light_parts<TBl, TRtApi, TExecDisp>(
 	TExecDisp: NativeExecutionDispatch + 'static,
 {
 
-	let task_manager = {
-		let registry = config.prometheus_config.as_ref().map(|cfg| &cfg.registry);
-		TaskManager::new(config.task_executor.clone(), registry)?
-	};
+	let task_manager = new_task_manager(config)?;
 
-	let keystore = match &config.keystore {
-		KeystoreConfig::Path { path, password } => Keystore::open(
-			path.clone(),
-			password.clone()
-		)?,
-		KeystoreConfig::InMemory => Keystore::new_in_memory(),
-	};
+	let keystore = open_keystore(&config.keystore)?;
 
+	#[cfg(feature = "wasmtime")]
+	sc_executor::set_wasmtime_jitdump(config.wasmtime_jitdump);
+	#[cfg(feature = "wasmtime")]
+	sc_executor::set_wasmtime_cache(config.wasmtime_cache.clone());
 	let executor = NativeExecutor::<TExecDisp>::new(
 		config.wasm_method,
 		config.default_heap_pages,
@@ -399,22 +425,43 @@ pub struct SpawnTasksParams<'a, TBl: BlockT, TCl, TExPool, TRpc, Backend> {
 }
 
 /// Build a shared offchain workers instance.
-pub fn build_offchain_workers<TBl, TBackend, TCl>(
+///
+/// The offchain workers are provided with the recent import times of the client and the size of
+/// the transaction pool, see `sp_io::offchain::node_metrics`.
+pub fn build_offchain_workers<TBl, TBackend, TCl, TExPool>(
 	config: &Configuration,
 	backend: Arc<TBackend>,
 	spawn_handle: SpawnTaskHandle,
 	client: Arc<TCl>,
 	network: Arc<NetworkService<TBl, <TBl as BlockT>::Hash>>,
+	transaction_pool: Arc<TExPool>,
 ) -> Option<Arc<sc_offchain::OffchainWorkers<TCl, TBackend::OffchainStorage, TBl>>>
 	where
 		TBl: BlockT, TBackend: sc_client_api::Backend<TBl>,
 		<TBackend as sc_client_api::Backend<TBl>>::OffchainStorage: 'static,
-		TCl: Send + Sync + ProvideRuntimeApi<TBl> + BlockchainEvents<TBl> + 'static,
+		TCl: Send + Sync + ProvideRuntimeApi<TBl> + BlockchainEvents<TBl> + UsageProvider<TBl> + 'static,
 		<TCl as ProvideRuntimeApi<TBl>>::Api: sc_offchain::OffchainWorkerApi<TBl>,
+		TExPool: MaintainedTransactionPool<Block=TBl, Hash = <TBl as BlockT>::Hash> + 'static,
 {
+	let node_metrics = {
+		let client = client.clone();
+		move || {
+			let import = client.import_info();
+			let status = transaction_pool.status();
+			sp_core::offchain::NodeMetrics {
+				recent_imports: import.recent_imports,
+				average_import_micros: import.average_time.map(|time| time.as_micros() as u64),
+				ready_transactions: status.ready as u64,
+				future_transactions: status.future as u64,
+			}
+		}
+	};
 	let offchain_workers = match backend.offchain_storage() {
 		Some(db) => {
-			Some(Arc::new(sc_offchain::OffchainWorkers::new(client.clone(), db)))
+			Some(Arc::new(
+				sc_offchain::OffchainWorkers::new(client.clone(), db)
+					.with_node_metrics(Arc::new(node_metrics))
+			))
 		},
 		None => {
 			warn!("Offchain workers disabled, due to lack of offchain storage support in backend.");
@@ -439,6 +486,31 @@ pub fn build_offchain_workers<TBl, TBackend, TCl>(
 	offchain_workers
 }
 
+/// The post-mortem bundles of the slots in which authoring a block fails or overruns the slot,
+/// given to the slot workers if `config.authorship_post_mortem` is set.
+///
+/// The bundles only hold the open spans if the subscriber of the node is the global default.
+pub fn authorship_post_mortem<TExPool>(
+	config: &Configuration,
+	spawn_handle: SpawnTaskHandle,
+	transaction_pool: Arc<TExPool>,
+) -> Option<sc_consensus_slots::PostMortem>
+	where TExPool: MaintainedTransactionPool + 'static,
+{
+	let directory = config.authorship_post_mortem.clone()?;
+	let open_spans = move || sc_tracing::global_open_spans().iter().map(|span| format!(
+		"{}::{} ({}ms)",
+		span.target,
+		span.name,
+		span.start_time.elapsed().as_millis(),
+	)).collect();
+	Some(sc_consensus_slots::PostMortem::new(sc_consensus_slots::PostMortemConfig {
+		directory,
+		pending_transactions: Some(Box::new(move || transaction_pool.status().ready)),
+		open_spans: Some(Box::new(open_spans)),
+	}, spawn_handle))
+}
+
 /// Spawn the tasks that are required to run a node.
 pub fn spawn_tasks<TBl, TBackend, TExPool, TRpc, TCl>(
 	params: SpawnTasksParams<TBl, TCl, TExPool, TRpc, TBackend>,
@@ -511,16 +583,50 @@ pub fn spawn_tasks<TBl, TBackend, TExPool, TRpc, TCl>(
 	{
 		// Set static metrics.
 		let metrics = MetricsService::with_prometheus(&registry, &config)?;
+		crate::metrics::register_instance_pool_metrics(&registry)?;
+		crate::metrics::register_exporter_metrics(&registry)?;
+		#[cfg(feature = "wasmtime")]
+		crate::metrics::register_wasmtime_cache_metrics(&registry)?;
 		spawn_handle.spawn(
 			"prometheus-endpoint",
-			prometheus_endpoint::init_prometheus(port, registry).map(drop)
+			prometheus_endpoint::init_prometheus(port, registry.clone()).map(drop)
 		);
+		if let Some(statsd) = config.statsd_config.clone() {
+			spawn_handle.spawn(
+				"statsd-exporter",
+				prometheus_endpoint::init_statsd(statsd, registry).map(drop)
+			);
+		}
 
 		metrics
 	} else {
 		MetricsService::new()
 	};
 
+	// Health check endpoints.
+	if let Some(addr) = config.health_check {
+		let health = health::Health::default();
+		spawn_handle.spawn("health-check-server", health::start_server(addr, health.clone())?);
+		let telemetry_enabled = config.telemetry_endpoints.as_ref().map_or(false, |e| !e.is_empty());
+		spawn_handle.spawn("health-check", health::update_health(
+			health,
+			client.clone(),
+			network_status_sinks.clone(),
+			telemetry_enabled,
+		));
+	}
+
+	// Periodically sample the resources used by the node.
+	let resource_sampler = crate::resources::ResourceSampler::new(
+		config.database.path().map(Into::into),
+		config.prometheus_registry(),
+		task_manager.scheduled_tasks(),
+	)?;
+	spawn_handle.spawn(
+		"resource-sampler",
+		resource_sampler.run(client.clone(), network_status_sinks.clone()),
+	);
+
 	// Periodically notify the telemetry.
 	spawn_handle.spawn("telemetry-periodic-send", telemetry_periodic_send(
 		client.clone(), transaction_pool.clone(), metrics_service, network_status_sinks.clone()
@@ -532,16 +638,44 @@ pub fn spawn_tasks<TBl, TBackend, TExPool, TRpc, TCl>(
 		telemetry_periodic_network_state(network_status_sinks.clone()),
 	);
 
+	// The drift of the calls from their weights, measured by the subscriber of the node and
+	// exposed by the `trace_weightDrift` RPC. The calls executed in wasm are only measured with
+	// wasm tracing enabled.
+	let weight_drift = if config.tracing_weight_drift {
+		sp_tracing::set_wasm_tracing(true);
+		Some(sc_tracing::WeightDrift::new(WEIGHT_DRIFT_WINDOW, WEIGHT_DRIFT_ALERT_RATIO))
+	} else {
+		None
+	};
+	// The breakdowns of the proofs of the blocks built recording one, e.g. by collators, exposed
+	// by the `trace_povBreakdown` RPC.
+	let pov_breakdowns = if config.tracing_pov_breakdowns {
+		Some(sc_tracing::PovBreakdowns::new(POV_BREAKDOWNS))
+	} else {
+		None
+	};
+
 	// RPC
+	// The slow calls are accounted across all the servers.
+	let rpc_middleware = sc_rpc_server::RpcMiddleware::new(
+		config.rpc_slow_calls.clone(),
+		config.prometheus_registry(),
+	)?;
 	let gen_handler = |deny_unsafe: sc_rpc::DenyUnsafe| gen_handler(
 		deny_unsafe, &config, task_manager.spawn_handle(), client.clone(), transaction_pool.clone(),
 		keystore.clone(), on_demand.clone(), remote_blockchain.clone(), &*rpc_extensions_builder,
-		backend.offchain_storage(), system_rpc_tx.clone()
+		backend.offchain_storage(), backend.trace_summary_storage(), weight_drift.clone(),
+		pov_breakdowns.clone(), system_rpc_tx.clone(), rpc_middleware.clone(),
 	);
 	let rpc = start_rpc_servers(&config, gen_handler)?;
 	// This is used internally, so don't restrict access to unsafe RPC
 	let rpc_handlers = RpcHandlers(Arc::new(gen_handler(sc_rpc::DenyUnsafe::No).into()));
 
+	// gRPC
+	start_grpc_server::<_, TBackend, _, _>(
+		&config, &spawn_handle, client.clone(), transaction_pool.clone(),
+	);
+
 	// Telemetry
 	let telemetry = config.telemetry_endpoints.clone().and_then(|endpoints| {
 		if endpoints.is_empty() {
@@ -560,15 +694,189 @@ pub fn spawn_tasks<TBl, TBackend, TExPool, TRpc, TCl>(
 		))
 	});
 
-	// Instrumentation
-	if let Some(tracing_targets) = config.tracing_targets.as_ref() {
-		let subscriber = sc_tracing::ProfilingSubscriber::new(
-			config.tracing_receiver, tracing_targets
-		);
-		match tracing::subscriber::set_global_default(subscriber) {
-			Ok(_) => (),
-			Err(e) => error!(target: "tracing", "Unable to set global default subscriber {}", e),
+	// Instrumentation, only installed when the profiling is configured, the audit events being
+	// forwarded otherwise.
+	if tracing_configured(&config) {
+		let mut tracing_receiver = config.tracing_receiver;
+		let loki_metrics = match &mut tracing_receiver {
+			sc_tracing::TracingReceiver::Loki(loki) => {
+				loki.labels.push(("chain".into(), config.chain_spec.name().into()));
+				loki.labels.push(("node".into(), config.network.node_name.clone()));
+				Some(loki.metrics.clone())
+			},
+			sc_tracing::TracingReceiver::Influx(influx) => {
+				influx.tags.push(("chain".into(), config.chain_spec.name().into()));
+				influx.tags.push(("node".into(), config.network.node_name.clone()));
+				influx.metrics = config.prometheus_config.as_ref().map(|c| influx_metrics(c.registry.clone()));
+				None
+			},
+			sc_tracing::TracingReceiver::Datadog(datadog) => {
+				datadog.service = config.chain_spec.name().into();
+				datadog.meta.push(("node".into(), config.network.node_name.clone()));
+				None
+			},
+			sc_tracing::TracingReceiver::Gelf(gelf) => {
+				gelf.host = config.network.node_name.clone();
+				gelf.fields.push(("chain".into(), config.chain_spec.name().into()));
+				None
+			},
+			_ => None,
+		};
+		let subscriber = sc_tracing::TracingBuilder::new()
+			.with_receiver(tracing_receiver)
+			.with_targets(config.tracing_targets.as_deref().unwrap_or_default())
+			.with_redaction(config.tracing_redaction.clone())
+			.with_queue(TRACE_QUEUE_SIZE)
+			.with_limits(Default::default())
+			.with_span_metrics(MAX_SPAN_METRIC_SERIES)
+			.with_dispatch_origins(sc_tracing::DispatchOrigins::new())
+			.with_finality_metrics(sc_tracing::FinalityMetrics::new());
+		let subscriber = if config.tracing_storage_counters {
+			subscriber.with_storage_counters()
+		} else {
+			subscriber
+		};
+		let subscriber = if config.tracing_dispatch_errors {
+			subscriber.with_dispatch_errors()
+		} else {
+			subscriber
+		};
+		let subscriber = match weight_drift {
+			Some(weight_drift) => subscriber.with_weight_drift(weight_drift),
+			None => subscriber,
+		};
+		let subscriber = match pov_breakdowns {
+			Some(pov_breakdowns) => subscriber.with_pov_breakdowns(pov_breakdowns),
+			None => subscriber,
+		};
+		let subscriber = if config.import_deadlines.is_empty() {
+			subscriber
+		} else {
+			subscriber.with_import_deadlines(sc_tracing::ImportDeadlines::new(config.import_deadlines.clone()))
+		};
+		// The metadata of the best block is used to decode the runtime traces, until the runtime of
+		// the best block is upgraded, see `update_scale_decoder`.
+		let best_block = BlockId::Hash(chain_info.best_hash);
+		let spec_version = client.runtime_version_at(&best_block).ok().map(|version| version.spec_version);
+		let subscriber = match build_scale_decoder(&*client, &best_block, &config.chain_spec.properties()) {
+			Some(scale_decoder) => subscriber.with_scale_decoder(scale_decoder),
+			None => subscriber,
+		};
+		// The summaries of the traces of the imported blocks, for the `trace_blockSummary` RPC and
+		// the trace collector.
+		let storage = config.tracing_summaries
+			.and_then(|keep| backend.trace_summary_storage().map(|storage| (keep, storage)));
+		let collector = config.network.trace_collector.as_ref().map(|_| network.clone());
+		let subscriber = if storage.is_some() || collector.is_some() {
+			subscriber.with_block_summaries(TRACE_SUMMARY_SPANS, move |summary| {
+				if let Some(network) = &collector {
+					network.send_trace_summary(summary.block_number, summary.encode());
+				}
+				if let Some((keep, storage)) = &storage {
+					store_trace_summary::<TBl>(&**storage, *keep, summary);
+				}
+			})
+		} else {
+			subscriber
+		};
+		// The reports are tagged with the versions the node started with.
+		let mut sentry = config.tracing_sentry;
+		if let Some(sentry) = &mut sentry {
+			sentry.tags.push(("node".into(), config.network.node_name.clone()));
+			sentry.tags.push(("version".into(), config.impl_version.clone()));
+			sentry.tags.push(("chain".into(), config.chain_spec.name().into()));
+			if let Ok(version) = client.runtime_version_at(&BlockId::Hash(chain_info.best_hash)) {
+				sentry.tags.push(("runtime".into(), format!("{}-{}", version.spec_name, version.spec_version)));
+			}
 		}
+		let subscriber = match sentry {
+			Some(sentry) => subscriber.with_sentry(sc_tracing::SentryReporter::new(sentry)),
+			None => subscriber,
+		};
+		let subscriber = config.tracing_extensions.iter()
+			.fold(subscriber, |subscriber, extension| subscriber.with_extension(&**extension));
+		match subscriber.init() {
+			Ok(handle) => {
+				if let Some(sync_targets) = config.tracing_sync_targets.clone() {
+					spawn_handle.spawn("tracing-sync-targets", trace_sync_targets(
+						handle.clone(),
+						config.tracing_targets.clone().unwrap_or_default(),
+						sync_targets,
+						network_status_sinks.clone(),
+					));
+				}
+				spawn_handle.spawn("tracing-scale-decoder", update_scale_decoder(
+					handle.clone(),
+					client.clone(),
+					config.chain_spec.properties(),
+					spec_version,
+				));
+				if handle.import_deadlines().is_some() {
+					spawn_handle.spawn("import-deadline-watchdog", watch_import_deadlines(handle.clone()));
+				}
+				if let Some(registry) = config.prometheus_config.as_ref().map(|c| &c.registry) {
+					if let Err(e) = register_runtime_counters(registry, handle.counters()) {
+						error!(target: "tracing", "Unable to register the runtime counters {}", e);
+					}
+					if let Some(span_metrics) = handle.span_metrics() {
+						if let Err(e) = register_span_metrics(registry, span_metrics) {
+							error!(target: "tracing", "Unable to register the span metrics {}", e);
+						}
+					}
+					if let Some(weight_drift) = handle.weight_drift() {
+						if let Err(e) = register_weight_drift(registry, weight_drift) {
+							error!(target: "tracing", "Unable to register the weight drift {}", e);
+						}
+					}
+					if let Some(dispatch_origins) = handle.dispatch_origins() {
+						if let Err(e) = register_dispatch_origins(registry, dispatch_origins) {
+							error!(target: "tracing", "Unable to register the dispatch origins {}", e);
+						}
+					}
+					if let Some(finality_metrics) = handle.finality_metrics() {
+						if let Err(e) = register_finality_metrics(registry, finality_metrics) {
+							error!(target: "tracing", "Unable to register the finality metrics {}", e);
+						}
+					}
+					if let Some(import_deadlines) = handle.import_deadlines() {
+						if let Err(e) = register_import_deadlines(registry, import_deadlines) {
+							error!(target: "tracing", "Unable to register the import deadlines {}", e);
+						}
+					}
+					if let Some(queue_metrics) = handle.queue_metrics() {
+						if let Err(e) = register_trace_queue_metrics(registry, queue_metrics) {
+							error!(target: "tracing", "Unable to register the trace queue metrics {}", e);
+						}
+					}
+					if let Some(loki_metrics) = loki_metrics {
+						if let Err(e) = register_loki_metrics(registry, loki_metrics) {
+							error!(target: "tracing", "Unable to register the Loki metrics {}", e);
+						}
+					}
+				}
+			},
+			// Another service of the process may have installed its subscriber already.
+			Err(e) => warn!(target: "tracing", "Unable to set global default subscriber {}", e),
+		}
+	} else {
+		// The audit events, e.g. of the sudo actions, are forwarded to telemetry whatever the
+		// tracing configuration, unless a subscriber is already installed, e.g. by another node
+		// of the process.
+		let subscriber = sc_tracing::AuditSubscriber::new(config.tracing_redaction.clone());
+		if let Err(e) = subscriber.init() {
+			debug!(target: "tracing", "Unable to forward the audit events: {}", e);
+		}
+	}
+
+	// The summaries collected from the reporters are exported along with the traces of the node.
+	if !config.network.trace_reporters.is_empty() {
+		spawn_handle.spawn("trace-collection", network.trace_summaries().for_each(|(peer, summary)| {
+			match sc_tracing::BlockSummary::decode(&mut &summary[..]) {
+				Ok(summary) => sc_tracing::record_collected_summary(&peer.to_base58(), &summary),
+				Err(e) => debug!(target: "tracing", "Invalid trace summary from {}: {:?}", peer, e),
+			}
+			ready(())
+		}));
 	}
 
 	// Spawn informant task
@@ -584,6 +892,55 @@ pub fn spawn_tasks<TBl, TBackend, TExPool, TRpc, TCl>(
 	Ok(rpc_handlers)
 }
 
+/// Number of targets and names of spans the trace summaries keep the time spent in.
+const TRACE_SUMMARY_SPANS: usize = 20;
+
+/// Number of spans and events queued for the trace receiver and handlers before they are dropped.
+const TRACE_QUEUE_SIZE: usize = 10_000;
+
+/// Maximum number of targets and names of the spans exported as Prometheus series, see
+/// `sc_tracing::SpanMetrics`.
+const MAX_SPAN_METRIC_SERIES: usize = 500;
+
+/// Number of last dispatches of a call its drift from its weight is measured over.
+const WEIGHT_DRIFT_WINDOW: usize = 100;
+
+/// Picoseconds per unit of weight above which the drift of a call is warned about, i.e. the
+/// calls taking twice the time benchmarked for their weights.
+const WEIGHT_DRIFT_ALERT_RATIO: f64 = 2.0;
+
+/// Number of last built blocks the breakdowns of the proofs are kept of.
+const POV_BREAKDOWNS: usize = 16;
+
+/// How often the imports in progress are checked against their deadlines.
+const IMPORT_DEADLINE_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_millis(250);
+
+/// Store the trace summary of an imported block, pruning the summaries of the blocks `keep` blocks
+/// or more below it.
+fn store_trace_summary<TBl: BlockT>(
+	storage: &dyn TraceSummaryStorage<TBl>,
+	keep: u32,
+	summary: sc_tracing::BlockSummary,
+) {
+	let hash = sp_core::bytes::from_hex(&summary.block_hash).ok()
+		.and_then(|hash| TBl::Hash::decode(&mut &hash[..]).ok());
+	let hash = match hash {
+		Some(hash) => hash,
+		None => {
+			debug!(target: "tracing", "Invalid block hash of trace summary: {}", summary.block_hash);
+			return;
+		},
+	};
+	let result = storage.insert_summary(hash, summary.block_number.saturated_into(), &summary.encode())
+		.and_then(|_| match summary.block_number.checked_sub(keep.into()) {
+			Some(pruned) => storage.prune_summaries(pruned.saturated_into()),
+			None => Ok(()),
+		});
+	if let Err(e) = result {
+		warn!(target: "tracing", "Unable to store the trace summary of block {}: {:?}", hash, e);
+	}
+}
+
 async fn transaction_notifications<TBl, TExPool>(
 	transaction_pool: Arc<TExPool>,
 	network: Arc<NetworkService<TBl, <TBl as BlockT>::Hash>>
@@ -647,6 +1004,117 @@ async fn telemetry_periodic_network_state<TBl: BlockT>(
 	}).await;
 }
 
+/// Trace the `sync_targets` instead of the `targets` while the node is major syncing.
+async fn trace_sync_targets<TBl: BlockT>(
+	tracing: sc_tracing::TracingHandle,
+	targets: String,
+	sync_targets: String,
+	network_status_sinks: NetworkStatusSinks<TBl>,
+) {
+	let (status_tx, status_rx) = tracing_unbounded::<(NetworkStatus<_>, NetworkState)>("mpsc_netstat_tracing");
+	network_status_sinks.0.push(std::time::Duration::from_secs(5), status_tx);
+	let mut syncing = false;
+	status_rx.for_each(move |(net_status, _)| {
+		let major_syncing = net_status.sync_state == SyncState::Downloading;
+		if major_syncing != syncing {
+			syncing = major_syncing;
+			let targets = if syncing { &sync_targets } else { &targets };
+			info!(target: "tracing", "Tracing the targets {:?} while {}", targets,
+				if syncing { "major syncing" } else { "close to the tip of the chain" });
+			tracing.set_targets(targets);
+		}
+		ready(())
+	}).await;
+}
+
+/// Build the decoder of the SCALE encoded trace values from the metadata of the runtime at `at`.
+fn build_scale_decoder<TBl, TCl>(
+	client: &TCl,
+	at: &BlockId<TBl>,
+	properties: &sc_chain_spec::Properties,
+) -> Option<sc_tracing::ScaleDecoder>
+	where
+		TBl: BlockT,
+		TCl: ProvideRuntimeApi<TBl>,
+		<TCl as ProvideRuntimeApi<TBl>>::Api: sp_api::Metadata<TBl>,
+{
+	match client.runtime_api().metadata(at) {
+		Ok(metadata) => match sc_tracing::ScaleDecoder::new(&metadata, properties) {
+			Ok(scale_decoder) => Some(scale_decoder),
+			Err(e) => {
+				debug!(target: "tracing", "Unable to decode the traces with the runtime metadata: {}", e);
+				None
+			},
+		},
+		Err(e) => {
+			debug!(target: "tracing", "Unable to fetch the runtime metadata: {:?}", e);
+			None
+		},
+	}
+}
+
+/// Rebuild the decoder of the SCALE encoded trace values whenever the runtime of the new best
+/// block has another spec version than the one it was built for.
+async fn update_scale_decoder<TBl, TCl>(
+	tracing: sc_tracing::TracingHandle,
+	client: Arc<TCl>,
+	properties: sc_chain_spec::Properties,
+	mut spec_version: Option<u32>,
+)
+	where
+		TBl: BlockT,
+		TCl: ProvideRuntimeApi<TBl> + BlockchainEvents<TBl> + CallApiAt<TBl, Error=sp_blockchain::Error>,
+		<TCl as ProvideRuntimeApi<TBl>>::Api: sp_api::Metadata<TBl>,
+{
+	client.import_notification_stream().for_each(move |notification| {
+		if !notification.is_new_best {
+			return ready(());
+		}
+		let at = BlockId::Hash(notification.hash);
+		let version = match client.runtime_version_at(&at) {
+			Ok(version) => version.spec_version,
+			Err(e) => {
+				debug!(target: "tracing", "Unable to fetch the runtime version: {:?}", e);
+				return ready(());
+			},
+		};
+		if spec_version != Some(version) {
+			if let Some(scale_decoder) = build_scale_decoder(&*client, &at, &properties) {
+				debug!(target: "tracing", "Decoding the traces with the metadata of spec version {}", version);
+				tracing.set_scale_decoder(scale_decoder);
+				spec_version = Some(version);
+			}
+		}
+		ready(())
+	}).await;
+}
+
+/// Report the imports of the blocks past their deadlines, see
+/// `sc_tracing::ProfilingSubscriber::check_import_deadlines`.
+async fn watch_import_deadlines(tracing: sc_tracing::TracingHandle) {
+	loop {
+		futures_timer::Delay::new(IMPORT_DEADLINE_CHECK_INTERVAL).await;
+		tracing.check_import_deadlines();
+	}
+}
+
+/// Whether the node is configured to profile itself: tracing targets, a receiver other than the
+/// log, or one of the features built on its subscriber, e.g. Sentry or the trace summaries.
+fn tracing_configured(config: &Configuration) -> bool {
+	config.tracing_targets.is_some()
+		|| !matches!(config.tracing_receiver, sc_tracing::TracingReceiver::Log)
+		|| config.tracing_sync_targets.is_some()
+		|| config.tracing_summaries.is_some()
+		|| config.tracing_storage_counters
+		|| config.tracing_weight_drift
+		|| config.tracing_pov_breakdowns
+		|| config.tracing_dispatch_errors
+		|| config.tracing_sentry.is_some()
+		|| !config.tracing_extensions.is_empty()
+		|| !config.import_deadlines.is_empty()
+		|| config.network.trace_collector.is_some()
+}
+
 fn build_telemetry<TBl: BlockT>(
 	config: &mut Configuration,
 	endpoints: sc_telemetry::TelemetryEndpoints,
@@ -664,6 +1132,7 @@ fn build_telemetry<TBl: BlockT>(
 	let telemetry = sc_telemetry::init_telemetry(sc_telemetry::TelemetryConfig {
 		endpoints,
 		wasm_external_transport: config.telemetry_external_transport.take(),
+		batching: config.telemetry_batching.clone(),
 	});
 	let startup_time = SystemTime::UNIX_EPOCH.elapsed()
 		.map(|dur| dur.as_millis())
@@ -709,8 +1178,12 @@ fn gen_handler<TBl, TBackend, TExPool, TRpc, TCl>(
 	remote_blockchain: Option<Arc<dyn RemoteBlockchain<TBl>>>,
 	rpc_extensions_builder: &(dyn RpcExtensionBuilder<Output = TRpc> + Send),
 	offchain_storage: Option<<TBackend as sc_client_api::backend::Backend<TBl>>::OffchainStorage>,
-	system_rpc_tx: TracingUnboundedSender<sc_rpc::system::Request<TBl>>
-) -> jsonrpc_pubsub::PubSubHandler<sc_rpc::Metadata>
+	trace_summary_storage: Option<Arc<dyn TraceSummaryStorage<TBl>>>,
+	weight_drift: Option<sc_tracing::WeightDrift>,
+	pov_breakdowns: Option<sc_tracing::PovBreakdowns>,
+	system_rpc_tx: TracingUnboundedSender<sc_rpc::system::Request<TBl>>,
+	rpc_middleware: sc_rpc_server::RpcMiddleware,
+) -> sc_rpc_server::RpcHandler<sc_rpc::Metadata>
 	where
 		TBl: BlockT,
 		TCl: ProvideRuntimeApi<TBl> + BlockchainEvents<TBl> + HeaderBackend<TBl> +
@@ -724,7 +1197,7 @@ fn gen_handler<TBl, TBackend, TExPool, TRpc, TCl>(
 			sp_session::SessionKeys<TBl> +
 			sp_api::Metadata<TBl, Error = sp_blockchain::Error>,
 {
-	use sc_rpc::{chain, state, author, system, offchain};
+	use sc_rpc::{chain, state, author, system, offchain, trace};
 
 	let system_info = sc_rpc::system::SystemInfo {
 		chain_name: config.chain_spec.name().into(),
@@ -779,15 +1252,20 @@ fn gen_handler<TBl, TBackend, TExPool, TRpc, TCl>(
 			delegate.into_iter().collect::<HashMap<_, _>>()
 	}).unwrap_or_default();
 
+	let trace = sc_rpc::trace::Trace::new(
+		trace_summary_storage, weight_drift, pov_breakdowns,
+	);
+
 	sc_rpc_server::rpc_handler((
 		state::StateApi::to_delegate(state),
 		state::ChildStateApi::to_delegate(child_state),
 		chain::ChainApi::to_delegate(chain),
 		maybe_offchain_rpc,
+		trace::TraceApi::to_delegate(trace),
 		author::AuthorApi::to_delegate(author),
 		system::SystemApi::to_delegate(system),
 		rpc_extensions_builder.build(deny_unsafe, subscriptions),
-	))
+	), rpc_middleware)
 }
 
 /// Parameters to pass into `build_network`.
@@ -897,6 +1375,7 @@ pub fn build_network<TBl, TExPool, TImpQu, TCl>(
 		client,
 		network_status_sinks.clone(),
 		system_rpc_rx,
+		spawn_handle.task_statuses(),
 		has_bootnodes,
 		config.announce_block,
 	);
@@ -16,28 +16,58 @@
 
 use crate::error::Error;
 use log::info;
-use sp_runtime::traits::{Block as BlockT, NumberFor, Zero};
-use sc_client_api::{Backend, UsageProvider};
+use sp_blockchain::HeaderBackend;
+use sp_runtime::traits::{Block as BlockT, NumberFor, One, Saturating, SaturatedConversion, Zero};
+use sc_client_api::{Backend, UsageProvider, backend::TouchedKeys};
 use std::sync::Arc;
 
 /// Performs a revert of `blocks` blocks.
+///
+/// The auxiliary data of the reverted blocks, i.e. their trace summaries, is removed along with
+/// them. Returns the keys of the columns of the database the removal touched.
 pub fn revert_chain<B, BA, C>(
 	client: Arc<C>,
 	backend: Arc<BA>,
 	blocks: NumberFor<B>
-) -> Result<(), Error>
+) -> Result<Vec<TouchedKeys>, Error>
 where
 	B: BlockT,
 	C: UsageProvider<B>,
 	BA: Backend<B>,
 {
+	// The blocks a revert can remove, the best first.
+	let before = client.usage_info().chain;
+	let revertible = blocks.min(before.best_number.saturating_sub(before.finalized_number));
+	let mut candidates = Vec::new();
+	let mut number = before.best_number;
+	for _ in 0..revertible.saturated_into::<u64>() {
+		if let Some(hash) = backend.blockchain().hash(number)? {
+			candidates.push((hash, number));
+		}
+		number = number.saturating_sub(One::one());
+	}
+
 	let reverted = backend.revert(blocks, false)?;
 	let info = client.usage_info().chain;
 
 	if reverted.0.is_zero() {
 		info!("There aren't any non-finalized blocks to revert.");
-	} else {
-		info!("Reverted {} blocks. Best: #{} ({})", reverted.0, info.best_number, info.best_hash);
+		return Ok(Vec::new());
+	}
+	info!("Reverted {} blocks. Best: #{} ({})", reverted.0, info.best_number, info.best_hash);
+
+	let reverted_blocks = candidates.into_iter()
+		.filter(|(_, number)| *number > info.best_number)
+		.collect::<Vec<_>>();
+	let touched = match backend.trace_summary_storage() {
+		Some(storage) => storage.revert_summaries(&reverted_blocks)?,
+		None => Vec::new(),
+	};
+	if touched.is_empty() {
+		info!("No auxiliary data of the reverted blocks to remove.");
+	}
+	for keys in &touched {
+		info!("Removed the auxiliary data of the reverted blocks: {}", keys);
 	}
-	Ok(())
+	Ok(touched)
 }
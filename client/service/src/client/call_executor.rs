@@ -28,13 +28,22 @@ use sp_state_machine::{
 use sc_executor::{RuntimeVersion, RuntimeInfo, NativeVersion};
 use sp_externalities::Extensions;
 use sp_core::{
-	NativeOrEncoded, NeverNativeValue, traits::{CodeExecutor, SpawnNamed},
+	NativeOrEncoded, NeverNativeValue, traits::{CodeExecutor, SpawnNamed, TracingTargetsExt},
+	storage::well_known_keys,
 	offchain::storage::OffchainOverlayedChanges,
 };
 use sp_api::{ProofRecorder, InitializeBlock, StorageTransactionCache};
+use sp_blockchain::HeaderBackend;
 use sc_client_api::{backend, call_executor::CallExecutor};
 use super::client::ClientConfig;
 
+/// Tracing target of the spans of runtime API calls.
+///
+/// A span records the method and the block of the call, and it is entered for the duration of
+/// the call, so the spans of the runtime entered during the call, wasm ones included, are its
+/// children, while it is itself a child of the span of the component making the call.
+pub const RUNTIME_API_TARGET: &str = "runtime_api";
+
 /// Call executor that executes methods locally, querying all required
 /// data from local backend.
 pub struct LocalCallExecutor<B, E> {
@@ -61,6 +70,38 @@ impl<B, E> LocalCallExecutor<B, E> {
 	}
 }
 
+impl<B, E> LocalCallExecutor<B, E> {
+	/// The span of a call to `method` at block `id`.
+	fn call_span<Block>(&self, id: &BlockId<Block>, method: &str) -> tracing::Span
+		where
+			B: backend::Backend<Block>,
+			Block: BlockT,
+	{
+		// The fields are only evaluated if the span is enabled, so is the block hash lookup.
+		tracing::debug_span!(
+			target: RUNTIME_API_TARGET,
+			"runtime_api_call",
+			method,
+			block = ?self.backend.blockchain().block_hash_from_id(id).ok().flatten(),
+		)
+	}
+}
+
+/// The `extensions` of a call, with the on-chain tracing targets of `state` registered.
+///
+/// The targets are read here, before the execution, so that the reads are never recorded in the
+/// proofs of the execution, see `TracingTargetsExt`.
+fn with_tracing_targets<Block: BlockT, S: sp_state_machine::Backend<HashFor<Block>>>(
+	state: &S,
+	extensions: Option<Extensions>,
+) -> sp_blockchain::Result<Extensions> {
+	let mut extensions = extensions.unwrap_or_default();
+	let targets = state.storage(well_known_keys::TRACING_TARGETS)
+		.map_err(|e| sp_blockchain::Error::from_state(Box::new(e)))?;
+	extensions.register(TracingTargetsExt::new(targets));
+	Ok(extensions)
+}
+
 impl<B, E> Clone for LocalCallExecutor<B, E> where E: Clone {
 	fn clone(&self) -> Self {
 		LocalCallExecutor {
@@ -90,6 +131,9 @@ where
 		strategy: ExecutionStrategy,
 		extensions: Option<Extensions>,
 	) -> sp_blockchain::Result<Vec<u8>> {
+		let span = self.call_span(id, method);
+		let _guard = span.enter();
+
 		let mut changes = OverlayedChanges::default();
 		let mut offchain_changes = if self.client_config.offchain_indexing_api {
 			OffchainOverlayedChanges::enabled()
@@ -100,6 +144,7 @@ where
 			id, self.backend.changes_trie_storage()
 		)?;
 		let state = self.backend.state_at(*id)?;
+		let extensions = with_tracing_targets::<Block, _>(&state, extensions)?;
 		let state_runtime_code = sp_state_machine::backend::BackendRuntimeCode::new(&state);
 		let return_data = StateMachine::new(
 			&state,
@@ -109,7 +154,7 @@ where
 			&self.executor,
 			method,
 			call_data,
-			extensions.unwrap_or_default(),
+			extensions,
 			&state_runtime_code.runtime_code()?,
 			self.spawn_handle.clone(),
 		).execute_using_consensus_failure_handler::<_, NeverNativeValue, fn() -> _>(
@@ -146,6 +191,9 @@ where
 		recorder: &Option<ProofRecorder<Block>>,
 		extensions: Option<Extensions>,
 	) -> Result<NativeOrEncoded<R>, sp_blockchain::Error> where ExecutionManager<EM>: Clone {
+		let span = self.call_span(at, method);
+		let _guard = span.enter();
+
 		match initialize_block {
 			InitializeBlock::Do(ref init_block)
 				if init_block.borrow().as_ref().map(|id| id != at).unwrap_or(true) => {
@@ -159,6 +207,8 @@ where
 		let mut storage_transaction_cache = storage_transaction_cache.map(|c| c.borrow_mut());
 
 		let mut state = self.backend.state_at(*at)?;
+		// Like the runtime code, the tracing targets are read before creating the proof recorder.
+		let extensions = with_tracing_targets::<Block, _>(&state, extensions)?;
 
 		let changes = &mut *changes.borrow_mut();
 		let offchain_changes = &mut *offchain_changes.borrow_mut();
@@ -188,7 +238,7 @@ where
 					&self.executor,
 					method,
 					call_data,
-					extensions.unwrap_or_default(),
+					extensions,
 					&runtime_code,
 					self.spawn_handle.clone(),
 				);
@@ -207,7 +257,7 @@ where
 					&self.executor,
 					method,
 					call_data,
-					extensions.unwrap_or_default(),
+					extensions,
 					&runtime_code,
 					self.spawn_handle.clone(),
 				).with_storage_transaction_cache(storage_transaction_cache.as_mut().map(|c| &mut **c));
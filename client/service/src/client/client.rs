@@ -20,15 +20,15 @@
 
 use std::{
 	marker::PhantomData,
-	collections::{HashSet, BTreeMap, HashMap},
-	sync::Arc, panic::UnwindSafe, result,
+	collections::{HashSet, BTreeMap, HashMap, VecDeque},
+	sync::Arc, panic::UnwindSafe, result, time::{Duration, Instant},
 };
 use log::{info, trace, warn};
 use parking_lot::{Mutex, RwLock};
 use codec::{Encode, Decode};
 use hash_db::Prefix;
 use sp_core::{
-	convert_hash,
+	convert_hash, hashing::blake2_256, hexdisplay::HexDisplay,
 	storage::{well_known_keys, ChildInfo, PrefixedStorageKey, StorageData, StorageKey},
 	ChangesTrieConfiguration, ExecutionContext, NativeOrEncoded,
 };
@@ -74,7 +74,7 @@ use sc_client_api::{
 	client::{
 		ImportNotifications, FinalityNotification, FinalityNotifications, BlockImportNotification,
 		ClientInfo, BlockchainEvents, BlockBackend, ProvideUncles, BadBlocks, ForkBlocks,
-		BlockOf,
+		BlockOf, ImportInfo,
 	},
 	execution_extensions::ExecutionExtensions,
 	notifications::{StorageNotifications, StorageEventStream},
@@ -100,6 +100,26 @@ use {
 
 type NotificationSinks<T> = Mutex<Vec<TracingUnboundedSender<T>>>;
 
+/// Tracing target of the runtime upgrade events.
+const RUNTIME_UPGRADE_TARGET: &str = "runtime_upgrade";
+
+/// Maximum number of `:code` changes waiting for a child block to be executed.
+///
+/// Changes imported on forks that are never built upon are forgotten once this is reached.
+const MAX_PENDING_RUNTIME_UPGRADES: usize = 16;
+
+/// Number of the last block imports whose times are averaged in the [`ImportInfo`].
+const MAX_RECENT_IMPORTS: usize = 64;
+
+/// A block that changed `:code`, whose first child was not imported yet.
+#[derive(Clone)]
+struct PendingRuntimeUpgrade {
+	number: u64,
+	old_spec_version: u32,
+	code_hash: String,
+	code_size: usize,
+}
+
 /// Substrate Client
 pub struct Client<B, E, Block, RA> where Block: BlockT {
 	backend: Arc<B>,
@@ -109,6 +129,10 @@ pub struct Client<B, E, Block, RA> where Block: BlockT {
 	finality_notification_sinks: NotificationSinks<FinalityNotification<Block>>,
 	// holds the block hash currently being imported. TODO: replace this with block queue
 	importing_block: RwLock<Option<Block::Hash>>,
+	// blocks that changed `:code`, by hash.
+	pending_runtime_upgrades: Mutex<HashMap<Block::Hash, PendingRuntimeUpgrade>>,
+	// times of the last blocks imported, the most recent last.
+	recent_imports: Mutex<VecDeque<Duration>>,
 	block_rules: BlockRules<Block>,
 	execution_extensions: ExecutionExtensions<Block>,
 	config: ClientConfig,
@@ -317,6 +341,8 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 			import_notification_sinks: Default::default(),
 			finality_notification_sinks: Default::default(),
 			importing_block: Default::default(),
+			pending_runtime_upgrades: Default::default(),
+			recent_imports: Default::default(),
 			block_rules: BlockRules::new(fork_blocks, bad_blocks),
 			execution_extensions,
 			config,
@@ -324,6 +350,14 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 		})
 	}
 
+	fn record_import_time(&self, time: Duration) {
+		let mut recent_imports = self.recent_imports.lock();
+		if recent_imports.len() == MAX_RECENT_IMPORTS {
+			recent_imports.pop_front();
+		}
+		recent_imports.push_back(time);
+	}
+
 	/// returns a reference to the block import notification sinks
 	/// useful for test environments.
 	pub fn import_notification_sinks(&self) -> &NotificationSinks<BlockImportNotification<Block>> {
@@ -745,6 +779,13 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 					changes_trie_tx,
 				) = storage_changes.into_inner();
 
+				let new_code = main_sc.iter()
+					.find(|(key, _)| &key[..] == well_known_keys::CODE)
+					.and_then(|(_, value)| value.as_ref());
+				if let Some(code) = new_code {
+					self.note_code_change(hash, *import_headers.post().number(), parent_hash, code);
+				}
+
 				if self.config.offchain_indexing_api {
 					operation.op.update_offchain_storage(offchain_sc)?;
 				}
@@ -820,6 +861,109 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 		Ok(ImportResult::imported(is_new_best))
 	}
 
+	/// Reports a block changing `:code` and remembers it, so that the outcome of the upgrade is
+	/// reported once the first block using the new runtime is imported.
+	fn note_code_change(
+		&self,
+		hash: Block::Hash,
+		number: NumberFor<Block>,
+		parent_hash: Block::Hash,
+		code: &[u8],
+	) {
+		let old_spec_version = match self.runtime_version_at(&BlockId::Hash(parent_hash)) {
+			Ok(version) => version.spec_version,
+			Err(e) => {
+				warn!("Failed to get the runtime version replaced in block {}: {:?}", hash, e);
+				return
+			},
+		};
+		let code_hash = format!("0x{}", HexDisplay::from(&blake2_256(code)));
+		let number = number.saturated_into::<u64>();
+
+		tracing::info!(
+			target: RUNTIME_UPGRADE_TARGET,
+			block = ?hash,
+			number,
+			old_spec_version,
+			code_hash = code_hash.as_str(),
+			code_size = code.len() as u64,
+			"Runtime code changed",
+		);
+		telemetry!(SUBSTRATE_INFO; "runtime.code_changed";
+			"height" => number,
+			"hash" => ?hash,
+			"old_spec_version" => old_spec_version,
+			"code_hash" => &code_hash,
+			"code_size" => code.len(),
+		);
+
+		let mut pending = self.pending_runtime_upgrades.lock();
+		if pending.len() >= MAX_PENDING_RUNTIME_UPGRADES && !pending.contains_key(&hash) {
+			// Forget the oldest change, most likely on a fork that is never built upon.
+			let oldest = pending.iter().min_by_key(|(_, upgrade)| upgrade.number).map(|(hash, _)| *hash);
+			if let Some(oldest) = oldest {
+				pending.remove(&oldest);
+			}
+		}
+		pending.insert(hash, PendingRuntimeUpgrade {
+			number,
+			old_spec_version,
+			code_hash,
+			code_size: code.len(),
+		});
+	}
+
+	/// Reports the first block executed with the runtime set by its parent.
+	///
+	/// `migration` is the time taken by the migrations, `on_runtime_upgrade`, see
+	/// `sp_tracing::time_runtime_upgrade`. It is `None` if the block was not executed by the
+	/// client, e.g. because it was authored locally, or if the runtime doesn't mark them.
+	fn report_runtime_upgrade(
+		&self,
+		upgrade: PendingRuntimeUpgrade,
+		header: &Block::Header,
+		migration: Option<Duration>,
+	) {
+		let hash = header.hash();
+		let new_spec_version = match self.runtime_version_at(&BlockId::Hash(*header.parent_hash())) {
+			Ok(version) => version.spec_version,
+			Err(e) => {
+				warn!("Failed to get the runtime version used by block {}: {:?}", hash, e);
+				return
+			},
+		};
+		let number = (*header.number()).saturated_into::<u64>();
+		let migration_ms = migration.map(|d| d.as_millis() as u64);
+
+		info!(
+			"⬆️  Runtime upgraded from spec version {} to {} at #{} ({})",
+			upgrade.old_spec_version,
+			new_spec_version,
+			number,
+			hash,
+		);
+		tracing::info!(
+			target: RUNTIME_UPGRADE_TARGET,
+			block = ?hash,
+			number,
+			old_spec_version = upgrade.old_spec_version,
+			new_spec_version,
+			code_hash = upgrade.code_hash.as_str(),
+			code_size = upgrade.code_size as u64,
+			migration_ms = ?migration_ms,
+			"Runtime upgraded",
+		);
+		telemetry!(SUBSTRATE_INFO; "runtime.upgraded";
+			"height" => number,
+			"hash" => ?hash,
+			"old_spec_version" => upgrade.old_spec_version,
+			"new_spec_version" => new_spec_version,
+			"code_hash" => upgrade.code_hash,
+			"code_size" => upgrade.code_size,
+			"migration_ms" => migration_ms,
+		);
+	}
+
 	/// Prepares the storage changes for a block.
 	///
 	/// It checks if the state should be enacted and if the `import_block` maybe already provides
@@ -844,6 +988,14 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 			BlockStatus::KnownBad => return Ok(Some(ImportResult::KnownBad)),
 		};
 
+		// Removed once the block is executed, so that a failing block doesn't lose the upgrade.
+		let runtime_upgrade = if enact_state {
+			self.pending_runtime_upgrades.lock().get(parent_hash).cloned()
+		} else {
+			None
+		};
+		let mut migration = None;
+
 		match (enact_state, &mut import_block.storage_changes, &mut import_block.body) {
 			// We have storage changes and should enact the state, so we don't need to do anything
 			// here
@@ -858,11 +1010,12 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 					ExecutionContext::Importing
 				};
 
-				runtime_api.execute_block_with_context(
-					&at,
-					execution_context,
-					Block::new(import_block.header.clone(), body.clone()),
-				)?;
+				let block = Block::new(import_block.header.clone(), body.clone());
+				let (result, elapsed) = sp_tracing::time_runtime_upgrade(|| {
+					runtime_api.execute_block_with_context(&at, execution_context, block)
+				});
+				result?;
+				migration = elapsed;
 
 				let state = self.backend.state_at(at)?;
 				let changes_trie_state = changes_tries_state_at_block(
@@ -892,6 +1045,11 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 			}
 		};
 
+		if let Some(upgrade) = runtime_upgrade {
+			self.pending_runtime_upgrades.lock().remove(parent_hash);
+			self.report_runtime_upgrade(upgrade, &import_block.header, migration);
+		}
+
 		Ok(None)
 	}
 
@@ -1174,8 +1332,20 @@ impl<B, E, Block, RA> UsageProvider<Block> for Client<B, E, Block, RA> where
 		ClientInfo {
 			chain: self.chain_info(),
 			usage: self.backend.usage_info(),
+			import: self.import_info(),
 		}
 	}
+
+	/// Get the timings of the last blocks imported.
+	fn import_info(&self) -> ImportInfo {
+		let recent_imports = self.recent_imports.lock();
+		let average_time = if recent_imports.is_empty() {
+			None
+		} else {
+			Some(recent_imports.iter().sum::<Duration>() / recent_imports.len() as u32)
+		};
+		ImportInfo { recent_imports: recent_imports.len() as u32, average_time }
+	}
 }
 
 impl<B, E, Block, RA> ProofProvider<Block> for Client<B, E, Block, RA> where
@@ -1690,22 +1860,34 @@ impl<B, E, Block, RA> sp_consensus::BlockImport<Block> for &Client<B, E, Block,
 		mut import_block: BlockImportParams<Block, backend::TransactionFor<B, Block>>,
 		new_cache: HashMap<CacheKeyId, Vec<u8>>,
 	) -> Result<ImportResult, Self::Error> {
-		let span = tracing::span!(tracing::Level::DEBUG, "import_block");
+		let number = (*import_block.header.number()).saturated_into::<u64>();
+		let span = tracing::span!(
+			tracing::Level::DEBUG,
+			"import_block",
+			block_number = number,
+			block_hash = ?import_block.post_hash(),
+		);
 		let _enter = span.enter();
+		sp_tracing::probes::import_enter(number, import_block.post_hash().as_ref());
+		let started = Instant::now();
 
-		if let Some(res) = self.prepare_block_storage_changes(&mut import_block).map_err(|e| {
+		let result = self.prepare_block_storage_changes(&mut import_block).map_err(|e| {
 			warn!("Block prepare storage changes error:\n{:?}", e);
-			ConsensusError::ClientImport(e.to_string())
-		})? {
-			return Ok(res)
-		}
-
-		self.lock_import_and_run(|operation| {
-			self.apply_block(operation, import_block, new_cache)
-		}).map_err(|e| {
-			warn!("Block import error:\n{:?}", e);
 			ConsensusError::ClientImport(e.to_string()).into()
-		})
+		}).and_then(|prepared| match prepared {
+			Some(res) => Ok(res),
+			None => self.lock_import_and_run(|operation| {
+				self.apply_block(operation, import_block, new_cache)
+			}).map_err(|e| {
+				warn!("Block import error:\n{:?}", e);
+				ConsensusError::ClientImport(e.to_string()).into()
+			}),
+		});
+		sp_tracing::probes::import_exit(number, result.is_ok());
+		if let Ok(ImportResult::Imported(_)) = result {
+			self.record_import_time(started.elapsed());
+		}
+		result
 	}
 
 	/// Check block preconditions.
@@ -51,7 +51,7 @@ mod client;
 mod block_rules;
 
 pub use self::{
-	call_executor::LocalCallExecutor,
+	call_executor::{LocalCallExecutor, RUNTIME_API_TARGET},
 	client::{Client, ClientConfig},
 };
 
@@ -28,8 +28,10 @@ use std::{io, future::Future, path::{PathBuf, Path}, pin::Pin, net::SocketAddr,
 pub use sc_transaction_pool::txpool::Options as TransactionPoolOptions;
 use sc_chain_spec::ChainSpec;
 use sp_core::crypto::SecretString;
-pub use sc_telemetry::TelemetryEndpoints;
+pub use sc_telemetry::{TelemetryBatching, TelemetryEndpoints};
+pub use sc_rpc_server::SlowCallThresholds;
 use prometheus_endpoint::Registry;
+pub use prometheus_endpoint::StatsdConfig;
 #[cfg(not(target_os = "unknown"))]
 use tempfile::TempDir;
 
@@ -62,6 +64,12 @@ pub struct Configuration {
 	pub chain_spec: Box<dyn ChainSpec>,
 	/// Wasm execution method.
 	pub wasm_method: WasmExecutionMethod,
+	/// Whether the compiled runtimes are described in a perf jitdump file, see
+	/// `sc_executor::set_wasmtime_jitdump`.
+	pub wasmtime_jitdump: bool,
+	/// Directory of the cache of the compiled runtimes, if any, see
+	/// `sc_executor::set_wasmtime_cache`.
+	pub wasmtime_cache: Option<PathBuf>,
 	/// Execution strategies.
 	pub execution_strategies: ExecutionStrategies,
 	/// RPC over HTTP binding address. `None` if disabled.
@@ -76,19 +84,30 @@ pub struct Configuration {
 	pub rpc_cors: Option<Vec<String>>,
 	/// RPC methods to expose (by default only a safe subset or all of them).
 	pub rpc_methods: RpcMethods,
+	/// Thresholds past which the RPC calls are logged and accounted as slow.
+	pub rpc_slow_calls: SlowCallThresholds,
+	/// gRPC binding address. `None` if disabled.
+	pub grpc: Option<SocketAddr>,
 	/// Prometheus endpoint configuration. `None` if disabled.
 	pub prometheus_config: Option<PrometheusConfig>,
+	/// StatsD configuration, pushing the metrics of the Prometheus registry
+	pub statsd_config: Option<StatsdConfig>,
 	/// Telemetry service URL. `None` if disabled.
 	pub telemetry_endpoints: Option<TelemetryEndpoints>,
 	/// External WASM transport for the telemetry. If `Some`, when connection to a telemetry
 	/// endpoint, this transport will be tried in priority before all others.
 	pub telemetry_external_transport: Option<ExtTransport>,
+	/// Batching of the telemetry messages. `None` to send one WebSocket frame per message.
+	pub telemetry_batching: Option<TelemetryBatching>,
 	/// The default number of 64KB pages to allocate for Wasm execution
 	pub default_heap_pages: Option<u64>,
 	/// Should offchain workers be executed.
 	pub offchain_worker: OffchainWorkerConfig,
 	/// Enable authoring even when offline.
 	pub force_authoring: bool,
+	/// Directory of the post-mortem bundles of the slots in which authoring a block failed or
+	/// overran the slot, if written, see `sc_service::authorship_post_mortem`.
+	pub authorship_post_mortem: Option<PathBuf>,
 	/// Disable GRANDPA when running in validator mode
 	pub disable_grandpa: bool,
 	/// Development key seed.
@@ -99,8 +118,34 @@ pub struct Configuration {
 	pub dev_key_seed: Option<String>,
 	/// Tracing targets
 	pub tracing_targets: Option<String>,
+	/// Tracing targets while the node is major syncing, if they differ from the tracing targets
+	pub tracing_sync_targets: Option<String>,
 	/// Tracing receiver
 	pub tracing_receiver: sc_tracing::TracingReceiver,
+	/// Fields to redact from the traces sent to telemetry
+	pub tracing_redaction: sc_tracing::Redaction,
+	/// Number of blocks to keep the trace summaries of, if any
+	pub tracing_summaries: Option<u32>,
+	/// Whether to count the storage accesses under the spans
+	pub tracing_storage_counters: bool,
+	/// Whether to measure the drift of the calls from their weights, see `sc_tracing::WeightDrift`
+	pub tracing_weight_drift: bool,
+	/// Whether to break down the proofs of the built blocks, see `sc_tracing::PovBreakdowns`
+	pub tracing_pov_breakdowns: bool,
+	/// Whether to report the failed dispatches, see
+	/// `sc_tracing::ProfilingSubscriber::with_dispatch_errors`
+	pub tracing_dispatch_errors: bool,
+	/// Deadlines past which the imports of the blocks are reported, with the breakdown of their
+	/// spans, see `sc_tracing::ImportDeadlines`
+	pub import_deadlines: Vec<std::time::Duration>,
+	/// Where to report the `ERROR` events and the panics, if anywhere
+	pub tracing_sentry: Option<sc_tracing::SentryConfig>,
+	/// Extensions adding the layers, trace handlers and wasm span wrappers of the node binary to
+	/// the subscriber built from the tracing configuration above.
+	///
+	/// Empty when built by `sc-cli`, the node binary pushes its extensions before building the
+	/// service.
+	pub tracing_extensions: Vec<Box<dyn sc_tracing::TracingExtension>>,
 	/// The size of the instances cache.
 	///
 	/// The default value is 8.
@@ -111,6 +156,10 @@ pub struct Configuration {
 	pub base_path: Option<BasePath>,
 	/// Configuration of the output format that the informant uses.
 	pub informant_output_format: sc_informant::OutputFormat,
+	/// Address to serve the live task console on. `None` if disabled.
+	pub tokio_console: Option<SocketAddr>,
+	/// Address to serve the `/health` and `/ready` endpoints on. `None` if disabled.
+	pub health_check: Option<SocketAddr>,
 }
 
 /// Type for tasks spawned by the executor.
@@ -130,7 +179,16 @@ pub enum KeystoreConfig {
 		/// The path of the keystore.
 		path: PathBuf,
 		/// Node keystore's password.
-		password: Option<SecretString>
+		password: Option<SecretString>,
+		/// File to append the audit trail of signing requests to, if any.
+		audit_log: Option<PathBuf>,
+	},
+	/// Keystore forwarding all signing requests to a remote signer.
+	Remote {
+		/// Connection to the remote signer.
+		signer: sc_keystore::RemoteSignerConfig,
+		/// File to append the audit trail of signing requests to, if any.
+		audit_log: Option<PathBuf>,
 	},
 	/// In-memory keystore. Recommended for in-browser nodes.
 	InMemory,
@@ -141,7 +199,7 @@ impl KeystoreConfig {
 	pub fn path(&self) -> Option<&Path> {
 		match self {
 			Self::Path { path, .. } => Some(path),
-			Self::InMemory => None,
+			Self::Remote { .. } | Self::InMemory => None,
 		}
 	}
 }
@@ -174,6 +232,50 @@ impl PrometheusConfig {
 				.expect("this can only fail if the prefix is empty")
 		}
 	}
+
+	/// Create a new config using a registry prefixing and labelling all the metrics as given by
+	/// `namespace`.
+	pub fn new_with_namespace(
+		port: SocketAddr,
+		namespace: MetricsNamespace,
+	) -> Result<Self, prometheus_endpoint::PrometheusError> {
+		let prefix = namespace.prefix.unwrap_or_else(|| "substrate".into());
+		let labels = if namespace.labels.is_empty() {
+			None
+		} else {
+			Some(namespace.labels.into_iter().collect())
+		};
+		Ok(Self { port, registry: Registry::new_custom(Some(prefix), labels)? })
+	}
+}
+
+/// Namespace of the metrics of the node, so that the metrics of several nodes scraped by the
+/// same Prometheus, e.g. of several nodes on a host or of the nodes of several chains, don't
+/// collide.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MetricsNamespace {
+	/// Prefix of the names of the metrics, `substrate` if `None`.
+	pub prefix: Option<String>,
+	/// Labels added to all the metrics, see [`MetricsNamespace::node_labels`].
+	pub labels: Vec<(String, String)>,
+}
+
+impl MetricsNamespace {
+	/// The `chain`, `node` and `role` labels of a node, the role being one of `validator`,
+	/// `sentry`, `full` and `light`.
+	pub fn node_labels(chain_id: &str, node_name: &str, role: &Role) -> Vec<(String, String)> {
+		let role = match role {
+			Role::Authority { .. } => "validator",
+			Role::Sentry { .. } => "sentry",
+			Role::Full => "full",
+			Role::Light => "light",
+		};
+		vec![
+			("chain".into(), chain_id.into()),
+			("node".into(), node_name.into()),
+			("role".into(), role.into()),
+		]
+	}
 }
 
 impl Configuration {
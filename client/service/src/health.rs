@@ -0,0 +1,267 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2017-2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! HTTP endpoint reporting the status of the node's subsystems.
+//!
+//! `GET /health` succeeds as long as the node is alive, that is its status keeps being
+//! refreshed and the database can be read. `GET /ready` additionally requires the node to be
+//! synced: not major syncing and at most [`MAX_SYNC_DISTANCE`] blocks behind the best block
+//! seen on the network. Both answer `200 OK` or `503 Service Unavailable` with the status of
+//! every subsystem as JSON, which makes them usable as Kubernetes liveness and readiness probes.
+
+use std::{io, net::SocketAddr, sync::Arc, time::{Duration, Instant}};
+use futures::{prelude::*, future::ready};
+use parking_lot::Mutex;
+use sc_network::SyncState;
+use sp_blockchain::HeaderBackend;
+use sp_runtime::{generic::BlockId, traits::{Block as BlockT, SaturatedConversion}};
+use crate::NetworkStatusSinks;
+
+/// Maximum number of blocks the node may be behind the network and still be ready.
+pub const MAX_SYNC_DISTANCE: u64 = 5;
+
+/// How often the status is refreshed.
+const UPDATE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// The node is not considered alive if its status wasn't refreshed for this long.
+const STALE_AFTER: Duration = Duration::from_secs(15);
+
+/// Last observed status of the node's subsystems.
+#[derive(Debug, Clone, Default)]
+struct Status {
+	best_block: u64,
+	best_seen_block: Option<u64>,
+	major_syncing: bool,
+	peers: usize,
+	finalized_block: u64,
+	database_available: bool,
+	telemetry_enabled: bool,
+	telemetry_connected: bool,
+}
+
+impl Status {
+	fn sync_distance(&self) -> u64 {
+		self.best_seen_block.unwrap_or(0).saturating_sub(self.best_block)
+	}
+
+	fn finality_lag(&self) -> u64 {
+		self.best_block.saturating_sub(self.finalized_block)
+	}
+}
+
+/// Shared view of the node's health, updated by [`update_health`] and served by [`start_server`].
+#[derive(Clone, Default)]
+pub struct Health {
+	inner: Arc<Mutex<(Status, Option<Instant>)>>,
+}
+
+impl Health {
+	fn update(&self, f: impl FnOnce(&mut Status)) {
+		let mut inner = self.inner.lock();
+		f(&mut inner.0);
+		inner.1 = Some(Instant::now());
+	}
+
+	/// Returns whether the node is alive, whether it is ready and the JSON report.
+	fn report(&self) -> (bool, bool, serde_json::Value) {
+		let (status, updated) = self.inner.lock().clone();
+		let fresh = updated.map_or(false, |updated| updated.elapsed() < STALE_AFTER);
+		let healthy = fresh && status.database_available;
+		let ready = healthy && !status.major_syncing && status.sync_distance() <= MAX_SYNC_DISTANCE;
+
+		let report = serde_json::json!({
+			"healthy": healthy,
+			"ready": ready,
+			"sync": {
+				"bestBlock": status.best_block,
+				"bestSeenBlock": status.best_seen_block,
+				"distance": status.sync_distance(),
+				"majorSyncing": status.major_syncing,
+			},
+			"network": {
+				"peers": status.peers,
+			},
+			"finality": {
+				"finalizedBlock": status.finalized_block,
+				"lag": status.finality_lag(),
+			},
+			"database": {
+				"available": status.database_available,
+			},
+			"telemetry": {
+				"enabled": status.telemetry_enabled,
+				"connected": status.telemetry_connected,
+			},
+		});
+		(healthy, ready, report)
+	}
+}
+
+/// Keep `health` up to date with the state of the client, the network and the telemetry.
+///
+/// The telemetry is connected as long as one of its endpoints is, see
+/// `sc_telemetry::telemetry_connected`.
+pub async fn update_health<TBl, TCl>(
+	health: Health,
+	client: Arc<TCl>,
+	network_status_sinks: NetworkStatusSinks<TBl>,
+	telemetry_enabled: bool,
+)
+	where
+		TBl: BlockT,
+		TCl: HeaderBackend<TBl>,
+{
+	network_status_sinks.network_status(UPDATE_INTERVAL).for_each(move |(net_status, _)| {
+		let info = client.info();
+		let database_available = matches!(client.header(BlockId::Hash(info.best_hash)), Ok(Some(_)));
+		let telemetry_connected = telemetry_enabled && sc_telemetry::telemetry_connected();
+
+		health.update(|status| {
+			status.best_block = info.best_number.saturated_into::<u64>();
+			status.best_seen_block = net_status.best_seen_block.map(|n| n.saturated_into::<u64>());
+			status.major_syncing = net_status.sync_state == SyncState::Downloading;
+			status.peers = net_status.num_connected_peers;
+			status.finalized_block = info.finalized_number.saturated_into::<u64>();
+			status.database_available = database_available;
+			status.telemetry_enabled = telemetry_enabled;
+			status.telemetry_connected = telemetry_connected;
+		});
+		ready(())
+	}).await
+}
+
+/// Bind the address of the `/health` and `/ready` endpoints, returning the future serving them.
+pub fn start_server(addr: SocketAddr, health: Health) -> io::Result<impl Future<Output = ()>> {
+	let listener = std::net::TcpListener::bind(addr)?;
+	log::info!("❤️ Health check endpoint listening on {}", addr);
+	Ok(serve(listener, health))
+}
+
+#[cfg(not(target_os = "unknown"))]
+async fn serve(listener: std::net::TcpListener, health: Health) {
+	use hyper::{Body, Request, Server, service::{make_service_fn, service_fn}};
+	use prometheus_endpoint::networking::{Incoming, Executor};
+
+	let listener = async_std::net::TcpListener::from(listener);
+	let service = make_service_fn(move |_| {
+		let health = health.clone();
+		async move {
+			Ok::<_, hyper::Error>(service_fn(move |request: Request<Body>| {
+				ready(respond(&request, &health))
+			}))
+		}
+	});
+	let server = Server::builder(Incoming(listener.incoming()))
+		.executor(Executor)
+		.serve(service);
+	if let Err(e) = server.await {
+		log::warn!("Health check endpoint failed: {}", e);
+	}
+}
+
+// There is no TCP listener in the browser, `health_check` is not configured there.
+#[cfg(target_os = "unknown")]
+async fn serve(_: std::net::TcpListener, _: Health) {}
+
+#[cfg(not(target_os = "unknown"))]
+fn respond(
+	request: &hyper::Request<hyper::Body>,
+	health: &Health,
+) -> Result<hyper::Response<hyper::Body>, hyper::http::Error> {
+	use hyper::{Body, Method, Response, StatusCode};
+
+	let path = request.uri().path();
+	let (status, body) = match request.method() {
+		&Method::GET if path == "/health" || path == "/ready" => {
+			let (healthy, ready, report) = health.report();
+			let ok = if path == "/health" { healthy } else { ready };
+			let status = if ok { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+			(status, report.to_string())
+		},
+		_ => (StatusCode::NOT_FOUND, "\"Not found.\"".to_owned()),
+	};
+	Response::builder()
+		.status(status)
+		.header("Content-Type", "application/json")
+		.body(Body::from(body))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn synced_status() -> Status {
+		Status {
+			best_block: 100,
+			best_seen_block: Some(102),
+			major_syncing: false,
+			peers: 3,
+			finalized_block: 98,
+			database_available: true,
+			telemetry_enabled: false,
+			telemetry_connected: false,
+		}
+	}
+
+	#[test]
+	fn readiness_requires_sync() {
+		let health = Health::default();
+		assert!(!health.report().0);
+
+		health.update(|status| *status = synced_status());
+		let (healthy, ready, report) = health.report();
+		assert!(healthy && ready);
+		assert_eq!(report["sync"]["distance"], 2);
+		assert_eq!(report["finality"]["lag"], 2);
+
+		health.update(|status| status.best_seen_block = Some(200));
+		let (healthy, ready, _) = health.report();
+		assert!(healthy && !ready);
+
+		health.update(|status| status.database_available = false);
+		assert!(!health.report().0);
+	}
+
+	#[test]
+	fn serves_endpoints() {
+		use std::io::{Read, Write};
+
+		let health = Health::default();
+		health.update(|status| {
+			*status = synced_status();
+			status.major_syncing = true;
+		});
+		let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+		let addr = listener.local_addr().unwrap();
+		async_std::task::spawn(serve(listener, health.clone()));
+
+		let request = |path: &str| {
+			let mut client = std::net::TcpStream::connect(addr).unwrap();
+			write!(client, "GET {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n", path).unwrap();
+			let mut response = String::new();
+			client.read_to_string(&mut response).unwrap();
+			response
+		};
+
+		let response = request("/health");
+		assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+		assert!(response.ends_with(&health.report().2.to_string()));
+		assert!(request("/ready").starts_with("HTTP/1.1 503 Service Unavailable\r\n"));
+		assert!(request("/metrics").starts_with("HTTP/1.1 404 Not Found\r\n"));
+	}
+}
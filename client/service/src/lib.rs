@@ -28,6 +28,8 @@ pub mod error;
 
 mod metrics;
 mod builder;
+mod health;
+mod resources;
 #[cfg(feature = "test-helpers")]
 pub mod client;
 #[cfg(not(feature = "test-helpers"))]
@@ -54,6 +56,7 @@ pub use self::error::Error;
 pub use self::builder::{
 	new_full_client, new_client, new_full_parts, new_light_parts,
 	spawn_tasks, build_network, BuildNetworkParams, NetworkStarter, build_offchain_workers,
+	authorship_post_mortem,
 	SpawnTasksParams, TFullClient, TLightClient, TFullBackend, TLightBackend,
 	TLightBackendWithHash, TLightClientWithBackend,
 	TFullCallExecutor, TLightCallExecutor, RpcExtensionBuilder, NoopRpcExtensionBuilder,
@@ -97,7 +100,9 @@ impl<T> MallocSizeOfWasm for T {}
 
 /// RPC handlers that can perform RPC queries.
 #[derive(Clone)]
-pub struct RpcHandlers(Arc<jsonrpc_core::MetaIoHandler<sc_rpc::Metadata>>);
+pub struct RpcHandlers(
+	Arc<jsonrpc_core::MetaIoHandler<sc_rpc::Metadata, sc_rpc_server::RpcMiddleware>>,
+);
 
 impl RpcHandlers {
 	/// Starts an RPC query.
@@ -118,7 +123,9 @@ impl RpcHandlers {
 	}
 
 	/// Provides access to the underlying `MetaIoHandler`
-	pub fn io_handler(&self) -> Arc<jsonrpc_core::MetaIoHandler<sc_rpc::Metadata>> {
+	pub fn io_handler(
+		&self,
+	) -> Arc<jsonrpc_core::MetaIoHandler<sc_rpc::Metadata, sc_rpc_server::RpcMiddleware>> {
 		self.0.clone()
 	}
 }
@@ -194,6 +201,7 @@ async fn build_network_future<
 	client: Arc<C>,
 	status_sinks: NetworkStatusSinks<B>,
 	mut rpc_rx: TracingUnboundedReceiver<sc_rpc::system::Request<B>>,
+	task_statuses: Arc<task_manager::TaskStatuses>,
 	should_have_peers: bool,
 	announce_imported_blocks: bool,
 ) {
@@ -311,6 +319,25 @@ async fn build_network_future<
 
 						let _ = sender.send(vec![node_role]);
 					}
+					sc_rpc::system::Request::TaskStatus(sender) => {
+						let _ = sender.send(task_statuses.snapshot());
+					}
+					sc_rpc::system::Request::ExportersStatus(sender) => {
+						let statuses = sc_telemetry::exporters().into_iter()
+							.map(|exporter| sc_rpc::system::ExporterStatus {
+								name: exporter.name,
+								queued: exporter.queued,
+								sent: exporter.sent,
+								dropped: exporter.dropped,
+								reconnects: exporter.reconnects,
+								sends: exporter.sends,
+								send_time_us: exporter.send_time.as_micros() as u64,
+								last_send_latency_us: exporter.last_send_latency
+									.map(|latency| latency.as_micros() as u64),
+							})
+							.collect();
+						let _ = sender.send(statuses);
+					}
 				}
 			}
 
@@ -436,6 +463,40 @@ fn start_rpc_servers<H: FnMut(sc_rpc::DenyUnsafe) -> sc_rpc_server::RpcHandler<s
 	Ok(Box::new(()))
 }
 
+/// Starts the gRPC server, if enabled, serving the APIs of the `client` and its `transaction_pool`.
+#[cfg(not(target_os = "unknown"))]
+fn start_grpc_server<TBl, TBackend, TCl, TExPool>(
+	config: &Configuration,
+	spawn_handle: &SpawnTaskHandle,
+	client: Arc<TCl>,
+	transaction_pool: Arc<TExPool>,
+) where
+	TBl: BlockT,
+	TBackend: sc_client_api::Backend<TBl> + 'static,
+	TCl: BlockchainEvents<TBl> + sc_client_api::StorageProvider<TBl, TBackend> +
+		sp_blockchain::HeaderBackend<TBl> + 'static,
+	TExPool: TransactionPool<Block = TBl, Hash = <TBl as BlockT>::Hash> + 'static,
+{
+	if let Some(addr) = config.grpc {
+		let node = Arc::new(sc_grpc::Node::new(client, transaction_pool));
+		spawn_handle.spawn("grpc-server", sc_grpc::start_server(addr, node).map(|result| {
+			if let Err(err) = result {
+				error!("gRPC server failed: {}", err);
+			}
+		}));
+	}
+}
+
+/// Starts the gRPC server, if enabled, serving the APIs of the `client` and its `transaction_pool`.
+#[cfg(target_os = "unknown")]
+fn start_grpc_server<TBl, TBackend, TCl, TExPool>(
+	_: &Configuration,
+	_: &SpawnTaskHandle,
+	_: Arc<TCl>,
+	_: Arc<TExPool>,
+) {
+}
+
 /// An RPC session. Used to perform in-memory RPC queries (ie. RPC queries that don't go through
 /// the HTTP or WebSockets server).
 #[derive(Clone)]
@@ -16,10 +16,13 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
-use std::{convert::TryFrom, time::SystemTime};
+use std::{convert::TryFrom, sync::Arc, time::SystemTime};
 
 use crate::{NetworkStatus, config::Configuration};
-use prometheus_endpoint::{register, Gauge, U64, Registry, PrometheusError, Opts, GaugeVec};
+use prometheus_endpoint::{
+	register, Gauge, U64, Registry, PrometheusError, Opts, GaugeVec, SourcedCounter, SourcedGauge,
+	MetricSource,
+};
 use sc_telemetry::{telemetry, SUBSTRATE_INFO};
 use sp_runtime::traits::{NumberFor, Block, SaturatedConversion, UniqueSaturatedInto};
 use sp_transaction_pool::PoolStatus;
@@ -99,6 +102,598 @@ impl PrometheusMetrics {
 	}
 }
 
+/// Source of the counters incremented by the runtime through `sp_tracing::counter`.
+#[derive(Clone)]
+struct RuntimeCounters(sc_tracing::Counters);
+
+impl MetricSource for RuntimeCounters {
+	type N = u64;
+
+	fn collect(&self, mut set: impl FnMut(&[&str], Self::N)) {
+		self.0.for_each(|name, label, value| set(&[name, label], value))
+	}
+}
+
+/// Expose the counters of the tracing subscriber as the `substrate_runtime_counters_total` metric.
+pub fn register_runtime_counters(
+	registry: &Registry,
+	counters: sc_tracing::Counters,
+) -> Result<(), PrometheusError> {
+	register(SourcedCounter::new(
+		&Opts::new(
+			"runtime_counters_total",
+			"Counters incremented by the runtime, by name and label",
+		).variable_label("name").variable_label("label"),
+		RuntimeCounters(counters),
+	)?, registry)?;
+
+	Ok(())
+}
+
+/// Source of the number of lines sent to Loki, retried and dropped.
+#[derive(Clone)]
+struct LokiLines(sc_tracing::LokiMetrics);
+
+impl MetricSource for LokiLines {
+	type N = u64;
+
+	fn collect(&self, mut set: impl FnMut(&[&str], Self::N)) {
+		set(&["sent"], self.0.sent());
+		set(&["dropped"], self.0.dropped());
+		set(&["retried"], self.0.retries());
+	}
+}
+
+/// Expose the metrics of the Loki trace receiver as the `substrate_tracing_loki_lines_total`
+/// metric.
+pub fn register_loki_metrics(
+	registry: &Registry,
+	metrics: sc_tracing::LokiMetrics,
+) -> Result<(), PrometheusError> {
+	register(SourcedCounter::new(
+		&Opts::new(
+			"tracing_loki_lines_total",
+			"Lines pushed to Loki, by status: sent, dropped, or retried for the retried batches",
+		).variable_label("status"),
+		LokiLines(metrics),
+	)?, registry)?;
+
+	Ok(())
+}
+
+/// Source of the number of spans closed, by target and name.
+#[derive(Clone)]
+struct SpanCounts(sc_tracing::SpanMetrics);
+
+impl MetricSource for SpanCounts {
+	type N = u64;
+
+	fn collect(&self, mut set: impl FnMut(&[&str], Self::N)) {
+		self.0.for_each(|target, name, count, _| set(&[target, name], count))
+	}
+}
+
+/// Source of the time spent in the spans, by target and name.
+#[derive(Clone)]
+struct SpanTimes(sc_tracing::SpanMetrics);
+
+impl MetricSource for SpanTimes {
+	type N = f64;
+
+	fn collect(&self, mut set: impl FnMut(&[&str], Self::N)) {
+		self.0.for_each(|target, name, _, time| set(&[target, name], time.as_secs_f64()))
+	}
+}
+
+/// Source of the number of spans accounted under the `other` series.
+#[derive(Clone)]
+struct SpanOverflow(sc_tracing::SpanMetrics);
+
+impl MetricSource for SpanOverflow {
+	type N = u64;
+
+	fn collect(&self, mut set: impl FnMut(&[&str], Self::N)) {
+		set(&[], self.0.overflow())
+	}
+}
+
+/// Expose the metrics of the spans as the `substrate_tracing_spans_total`,
+/// `substrate_tracing_span_seconds_total` and `substrate_tracing_span_series_overflow_total`
+/// metrics.
+pub fn register_span_metrics(
+	registry: &Registry,
+	metrics: sc_tracing::SpanMetrics,
+) -> Result<(), PrometheusError> {
+	register(SourcedCounter::new(
+		&Opts::new(
+			"tracing_spans_total",
+			"Spans closed, by target and name",
+		).variable_label("target").variable_label("name"),
+		SpanCounts(metrics.clone()),
+	)?, registry)?;
+	register(SourcedCounter::new(
+		&Opts::new(
+			"tracing_span_seconds_total",
+			"Time spent in the spans, by target and name",
+		).variable_label("target").variable_label("name"),
+		SpanTimes(metrics.clone()),
+	)?, registry)?;
+	register(SourcedCounter::new(
+		&Opts::new(
+			"tracing_span_series_overflow_total",
+			"Spans counted under the `other` target and name, past the cap of the series",
+		),
+		SpanOverflow(metrics),
+	)?, registry)?;
+
+	Ok(())
+}
+
+/// Source of the drift of the calls from their declared weights, by target and name.
+#[derive(Clone)]
+struct CallDrifts(sc_tracing::WeightDrift);
+
+impl MetricSource for CallDrifts {
+	type N = f64;
+
+	fn collect(&self, mut set: impl FnMut(&[&str], Self::N)) {
+		for call in self.0.calls() {
+			set(&[&call.target, &call.name], call.score)
+		}
+	}
+}
+
+/// Expose the drift of the calls from their declared weights as the
+/// `substrate_dispatch_weight_drift` metric.
+pub fn register_weight_drift(
+	registry: &Registry,
+	weight_drift: sc_tracing::WeightDrift,
+) -> Result<(), PrometheusError> {
+	register(SourcedGauge::new(
+		&Opts::new(
+			"dispatch_weight_drift",
+			"Picoseconds spent per unit of declared weight over the last dispatches, by target and name",
+		).variable_label("target").variable_label("name"),
+		CallDrifts(weight_drift),
+	)?, registry)?;
+
+	Ok(())
+}
+
+/// Source of the number of dispatches, by kind of origin.
+#[derive(Clone)]
+struct OriginDispatches(sc_tracing::DispatchOrigins);
+
+impl MetricSource for OriginDispatches {
+	type N = u64;
+
+	fn collect(&self, mut set: impl FnMut(&[&str], Self::N)) {
+		for (origin, stats) in self.0.origins() {
+			set(&[&origin], stats.dispatches)
+		}
+	}
+}
+
+/// Source of the time spent in the dispatches, by kind of origin.
+#[derive(Clone)]
+struct OriginTimes(sc_tracing::DispatchOrigins);
+
+impl MetricSource for OriginTimes {
+	type N = f64;
+
+	fn collect(&self, mut set: impl FnMut(&[&str], Self::N)) {
+		for (origin, stats) in self.0.origins() {
+			set(&[&origin], stats.time.as_secs_f64())
+		}
+	}
+}
+
+/// Source of the weight declared by the dispatches, by kind of origin.
+#[derive(Clone)]
+struct OriginWeights(sc_tracing::DispatchOrigins);
+
+impl MetricSource for OriginWeights {
+	type N = u64;
+
+	fn collect(&self, mut set: impl FnMut(&[&str], Self::N)) {
+		for (origin, stats) in self.0.origins() {
+			set(&[&origin], stats.weight)
+		}
+	}
+}
+
+/// Expose the dispatches by kind of origin as the `substrate_dispatch_origin_total`,
+/// `substrate_dispatch_origin_seconds_total` and `substrate_dispatch_origin_weight_total` metrics.
+pub fn register_dispatch_origins(
+	registry: &Registry,
+	dispatch_origins: sc_tracing::DispatchOrigins,
+) -> Result<(), PrometheusError> {
+	register(SourcedCounter::new(
+		&Opts::new(
+			"dispatch_origin_total",
+			"Dispatches, by kind of origin: signed, none, inherent, root or custom",
+		).variable_label("origin"),
+		OriginDispatches(dispatch_origins.clone()),
+	)?, registry)?;
+	register(SourcedCounter::new(
+		&Opts::new(
+			"dispatch_origin_seconds_total",
+			"Time spent in the dispatches, by kind of origin",
+		).variable_label("origin"),
+		OriginTimes(dispatch_origins.clone()),
+	)?, registry)?;
+	register(SourcedCounter::new(
+		&Opts::new(
+			"dispatch_origin_weight_total",
+			"Weight declared by the dispatches, by kind of origin",
+		).variable_label("origin"),
+		OriginWeights(dispatch_origins),
+	)?, registry)?;
+
+	Ok(())
+}
+
+/// Source of a value of the finality gadgets, by gadget.
+#[derive(Clone)]
+struct GadgetValue<N>(sc_tracing::FinalityMetrics, fn(&sc_tracing::GadgetStats) -> N);
+
+impl<N: prometheus_endpoint::prometheus::core::Number> MetricSource for GadgetValue<N> {
+	type N = N;
+
+	fn collect(&self, mut set: impl FnMut(&[&str], Self::N)) {
+		for (gadget, stats) in self.0.gadgets() {
+			set(&[&gadget], (self.1)(&stats))
+		}
+	}
+}
+
+/// Source of the votes cast with the finality gadgets, by gadget and kind of vote.
+#[derive(Clone)]
+struct GadgetVotes(sc_tracing::FinalityMetrics);
+
+impl MetricSource for GadgetVotes {
+	type N = u64;
+
+	fn collect(&self, mut set: impl FnMut(&[&str], Self::N)) {
+		for (gadget, stats) in self.0.gadgets() {
+			for (kind, votes) in &stats.votes {
+				set(&[&gadget, kind], *votes)
+			}
+		}
+	}
+}
+
+/// Source of a value of the finality proofs, by gadget and kind of proof.
+#[derive(Clone)]
+struct GadgetProofs<N>(sc_tracing::FinalityMetrics, fn(&sc_tracing::ProofStats) -> N);
+
+impl<N: prometheus_endpoint::prometheus::core::Number> MetricSource for GadgetProofs<N> {
+	type N = N;
+
+	fn collect(&self, mut set: impl FnMut(&[&str], Self::N)) {
+		for (gadget, stats) in self.0.gadgets() {
+			for (kind, proofs) in &stats.proofs {
+				set(&[&gadget, kind], (self.1)(proofs))
+			}
+		}
+	}
+}
+
+/// Expose what the finality gadgets did as the `substrate_finality_*` metrics, labelled by
+/// gadget, so that GRANDPA and the other gadgets are exposed alike.
+pub fn register_finality_metrics(
+	registry: &Registry,
+	finality_metrics: sc_tracing::FinalityMetrics,
+) -> Result<(), PrometheusError> {
+	register(SourcedCounter::new(
+		&Opts::new(
+			"finality_rounds_total",
+			"Rounds completed by the finality gadgets",
+		).variable_label("gadget"),
+		GadgetValue(finality_metrics.clone(), |stats| stats.rounds),
+	)?, registry)?;
+	register(SourcedCounter::new(
+		&Opts::new(
+			"finality_round_seconds_total",
+			"Time between the completions of the rounds of the finality gadgets",
+		).variable_label("gadget"),
+		GadgetValue(finality_metrics.clone(), |stats| stats.round_time.as_secs_f64()),
+	)?, registry)?;
+	register(SourcedGauge::new(
+		&Opts::new(
+			"finality_round",
+			"Last round completed by the finality gadgets",
+		).variable_label("gadget"),
+		GadgetValue(finality_metrics.clone(), |stats| stats.last_round),
+	)?, registry)?;
+	register(SourcedGauge::new(
+		&Opts::new(
+			"finality_finalized_number",
+			"Number of the last block finalized by the finality gadgets",
+		).variable_label("gadget"),
+		GadgetValue(finality_metrics.clone(), |stats| stats.finalized_number),
+	)?, registry)?;
+	register(SourcedCounter::new(
+		&Opts::new(
+			"finality_votes_total",
+			"Votes cast with the finality gadgets, by kind of vote",
+		).variable_label("gadget").variable_label("kind"),
+		GadgetVotes(finality_metrics.clone()),
+	)?, registry)?;
+	register(SourcedCounter::new(
+		&Opts::new(
+			"finality_proofs_total",
+			"Finality proofs generated or verified by the finality gadgets, by kind of proof",
+		).variable_label("gadget").variable_label("kind"),
+		GadgetProofs(finality_metrics.clone(), |stats| stats.proofs),
+	)?, registry)?;
+	register(SourcedCounter::new(
+		&Opts::new(
+			"finality_proof_seconds_total",
+			"Time spent generating or verifying finality proofs, by kind of proof",
+		).variable_label("gadget").variable_label("kind"),
+		GadgetProofs(finality_metrics, |stats| stats.time.as_secs_f64()),
+	)?, registry)?;
+
+	Ok(())
+}
+
+/// Source of the number of imports of the blocks past each of their deadlines.
+#[derive(Clone)]
+struct ImportsPastDeadlines(sc_tracing::ImportDeadlines);
+
+impl MetricSource for ImportsPastDeadlines {
+	type N = u64;
+
+	fn collect(&self, mut set: impl FnMut(&[&str], Self::N)) {
+		for (deadline, exceeded) in self.0.exceeded() {
+			set(&[&format!("{}s", deadline.as_secs_f64())], exceeded)
+		}
+	}
+}
+
+/// Expose the number of imports of the blocks past their deadlines as the
+/// `substrate_block_import_deadline_exceeded_total` metric.
+pub fn register_import_deadlines(
+	registry: &Registry,
+	import_deadlines: sc_tracing::ImportDeadlines,
+) -> Result<(), PrometheusError> {
+	register(SourcedCounter::new(
+		&Opts::new(
+			"block_import_deadline_exceeded_total",
+			"Imports of blocks that took longer than a deadline, by deadline",
+		).variable_label("deadline"),
+		ImportsPastDeadlines(import_deadlines),
+	)?, registry)?;
+
+	Ok(())
+}
+
+/// Source of the number of spans and events exported from the trace queue and dropped.
+#[derive(Clone)]
+struct TraceQueue(sc_tracing::QueueMetrics);
+
+impl MetricSource for TraceQueue {
+	type N = u64;
+
+	fn collect(&self, mut set: impl FnMut(&[&str], Self::N)) {
+		set(&["exported"], self.0.exported());
+		set(&["dropped"], self.0.dropped());
+	}
+}
+
+/// Expose the metrics of the queue of the trace handlers as the `substrate_tracing_queue_total`
+/// metric.
+pub fn register_trace_queue_metrics(
+	registry: &Registry,
+	metrics: sc_tracing::QueueMetrics,
+) -> Result<(), PrometheusError> {
+	register(SourcedCounter::new(
+		&Opts::new(
+			"tracing_queue_total",
+			"Spans and events queued for the trace handlers, by status: exported or dropped",
+		).variable_label("status"),
+		TraceQueue(metrics),
+	)?, registry)?;
+
+	Ok(())
+}
+
+/// Source of the number of records exported by the exporters and dropped.
+#[derive(Clone)]
+struct ExporterRecords;
+
+impl MetricSource for ExporterRecords {
+	type N = u64;
+
+	fn collect(&self, mut set: impl FnMut(&[&str], Self::N)) {
+		for exporter in sc_telemetry::exporters() {
+			set(&[&exporter.name, "sent"], exporter.sent);
+			set(&[&exporter.name, "dropped"], exporter.dropped);
+		}
+	}
+}
+
+/// Source of a value of the health of the exporters, if they have it.
+#[derive(Clone)]
+struct ExporterValue<N>(fn(&sc_telemetry::ExporterStatus) -> Option<N>);
+
+impl<N: prometheus_endpoint::prometheus::core::Number> MetricSource for ExporterValue<N> {
+	type N = N;
+
+	fn collect(&self, mut set: impl FnMut(&[&str], Self::N)) {
+		for exporter in sc_telemetry::exporters() {
+			if let Some(value) = (self.0)(&exporter) {
+				set(&[&exporter.name], value);
+			}
+		}
+	}
+}
+
+/// Expose the health of the telemetry and trace exporters as the `substrate_exporter_*` metrics,
+/// labelled by exporter.
+pub fn register_exporter_metrics(registry: &Registry) -> Result<(), PrometheusError> {
+	register(SourcedCounter::new(
+		&Opts::new(
+			"exporter_records_total",
+			"Records of the telemetry and trace exporters, by status: sent or dropped",
+		).variable_label("exporter").variable_label("status"),
+		ExporterRecords,
+	)?, registry)?;
+	register(SourcedGauge::new(
+		&Opts::new(
+			"exporter_queued",
+			"Records waiting to be sent by the telemetry and trace exporters",
+		).variable_label("exporter"),
+		ExporterValue(|exporter| Some(exporter.queued)),
+	)?, registry)?;
+	register(SourcedCounter::new(
+		&Opts::new(
+			"exporter_reconnects_total",
+			"Reconnections of the telemetry and trace exporters to their endpoint",
+		).variable_label("exporter"),
+		ExporterValue(|exporter| Some(exporter.reconnects)),
+	)?, registry)?;
+	register(SourcedCounter::new(
+		&Opts::new(
+			"exporter_sends_total",
+			"Sends of the telemetry and trace exporters, a send exporting one or more records",
+		).variable_label("exporter"),
+		ExporterValue(|exporter| Some(exporter.sends)),
+	)?, registry)?;
+	register(SourcedCounter::new(
+		&Opts::new(
+			"exporter_send_seconds_total",
+			"Time spent sending by the telemetry and trace exporters",
+		).variable_label("exporter"),
+		ExporterValue(|exporter| Some(exporter.send_time.as_secs_f64())),
+	)?, registry)?;
+	register(SourcedGauge::new(
+		&Opts::new(
+			"exporter_last_send_seconds",
+			"Time the last send of the telemetry and trace exporters took",
+		).variable_label("exporter"),
+		ExporterValue(|exporter| exporter.last_send_latency.map(|latency| latency.as_secs_f64())),
+	)?, registry)?;
+
+	Ok(())
+}
+
+/// Source of the number of runtime instances created, reused and missing from their pool.
+#[derive(Clone)]
+struct InstancePool;
+
+impl MetricSource for InstancePool {
+	type N = u64;
+
+	fn collect(&self, mut set: impl FnMut(&[&str], Self::N)) {
+		let stats = sc_executor::instance_pool_stats();
+		set(&["created"], stats.created);
+		set(&["reused"], stats.reused);
+		set(&["exhausted"], stats.exhausted);
+	}
+}
+
+/// Source of the time spent resetting the runtime instances before their calls.
+#[derive(Clone)]
+struct MemoryResets;
+
+impl MetricSource for MemoryResets {
+	type N = f64;
+
+	fn collect(&self, mut set: impl FnMut(&[&str], Self::N)) {
+		set(&[], sc_executor::instance_pool_stats().memory_reset_time.as_secs_f64());
+	}
+}
+
+/// Expose the use of the pools of runtime instances as the `substrate_wasm_instances_total` and
+/// `substrate_wasm_memory_reset_seconds_total` metrics.
+pub fn register_instance_pool_metrics(registry: &Registry) -> Result<(), PrometheusError> {
+	register(SourcedCounter::new(
+		&Opts::new(
+			"wasm_instances_total",
+			"Calls into the runtime by instance used: created into the pool, reused from the \
+			pool, or created for want of a free instance in the pool (exhausted)",
+		).variable_label("status"),
+		InstancePool,
+	)?, registry)?;
+	register(SourcedCounter::new(
+		&Opts::new(
+			"wasm_memory_reset_seconds_total",
+			"Time spent resetting the memory and globals of the runtime instances",
+		),
+		MemoryResets,
+	)?, registry)?;
+
+	Ok(())
+}
+
+/// Source of the number of runtimes loaded from the wasmtime cache and compiled.
+#[cfg(feature = "wasmtime")]
+#[derive(Clone)]
+struct WasmtimeCache;
+
+#[cfg(feature = "wasmtime")]
+impl MetricSource for WasmtimeCache {
+	type N = u64;
+
+	fn collect(&self, mut set: impl FnMut(&[&str], Self::N)) {
+		let stats = sc_executor::wasmtime_cache_stats();
+		set(&["hit"], stats.hits);
+		set(&["miss"], stats.misses);
+	}
+}
+
+/// Expose the loads from the cache of the compiled runtimes as the
+/// `substrate_wasmtime_cache_total` metric.
+#[cfg(feature = "wasmtime")]
+pub fn register_wasmtime_cache_metrics(registry: &Registry) -> Result<(), PrometheusError> {
+	register(SourcedCounter::new(
+		&Opts::new(
+			"wasmtime_cache_total",
+			"Runtimes compiled with the wasmtime cache enabled, by status: hit or miss",
+		).variable_label("status"),
+		WasmtimeCache,
+	)?, registry)?;
+
+	Ok(())
+}
+
+/// The metrics of `registry` pushed by the InfluxDB trace receiver, the histograms and summaries
+/// as their `_sum` and `_count`.
+pub fn influx_metrics(registry: Registry) -> sc_tracing::MetricsSource {
+	use prometheus_endpoint::prometheus::proto::MetricType;
+
+	Arc::new(move || {
+		let mut metrics = Vec::new();
+		for family in registry.gather() {
+			let name = family.get_name();
+			for metric in family.get_metric() {
+				let labels: Vec<_> = metric.get_label().iter()
+					.map(|label| (label.get_name().to_owned(), label.get_value().to_owned()))
+					.collect();
+				let mut push = |name: String, value| metrics.push((name, labels.clone(), value));
+				match family.get_field_type() {
+					MetricType::COUNTER => push(name.to_owned(), metric.get_counter().get_value()),
+					MetricType::GAUGE => push(name.to_owned(), metric.get_gauge().get_value()),
+					MetricType::HISTOGRAM => {
+						let histogram = metric.get_histogram();
+						push(format!("{}_sum", name), histogram.get_sample_sum());
+						push(format!("{}_count", name), histogram.get_sample_count() as f64);
+					},
+					MetricType::SUMMARY => {
+						let summary = metric.get_summary();
+						push(format!("{}_sum", name), summary.get_sample_sum());
+						push(format!("{}_count", name), summary.get_sample_count() as f64);
+					},
+					MetricType::UNTYPED => {},
+				}
+			}
+		}
+		metrics
+	})
+}
+
 pub struct MetricsService {
 	metrics: Option<PrometheusMetrics>,
 	last_update: Instant,
@@ -0,0 +1,282 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Sampler of the resources used by the node.
+//!
+//! Every [`SAMPLE_INTERVAL`], the CPU and memory used by the process, its open file descriptors,
+//! the disk space taken by the database and the tasks waiting to be polled by the executor are
+//! reported as Prometheus metrics and as a trace event on [`RESOURCES_TARGET`], along with the
+//! best and finalized blocks, so that they line up with the traces of the chain activity.
+
+use std::{
+	fs, io, path::{Path, PathBuf}, sync::Arc, time::{Duration, Instant},
+};
+use futures::{prelude::*, future::ready};
+use prometheus_endpoint::{register, Gauge, GaugeVec, PrometheusError, Registry, F64, U64};
+use sp_blockchain::HeaderBackend;
+use sp_runtime::traits::{Block as BlockT, SaturatedConversion};
+use crate::NetworkStatusSinks;
+
+/// Target of the trace events reporting the resources used by the node.
+pub const RESOURCES_TARGET: &str = "resources";
+
+/// How often the resources are sampled.
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Resources used by the node at a point in time, `None` where the platform doesn't allow
+/// measuring them.
+#[derive(Debug, Clone, Default)]
+struct Sample {
+	cpu_time: Option<Duration>,
+	resident_memory: Option<u64>,
+	open_fds: Option<u64>,
+	database_size: Option<u64>,
+}
+
+impl Sample {
+	fn take(database_path: Option<&Path>) -> Self {
+		Sample {
+			cpu_time: process_cpu_time(),
+			resident_memory: resident_memory(),
+			open_fds: open_fds(),
+			database_size: database_path.and_then(|path| disk_usage(path).ok()),
+		}
+	}
+}
+
+/// CPU time consumed by all the threads of the process so far.
+#[cfg(unix)]
+fn process_cpu_time() -> Option<Duration> {
+	let mut time = libc::timespec { tv_sec: 0, tv_nsec: 0 };
+	// Safe, as `time` is a valid pointer to a `timespec` for the duration of the call.
+	let res = unsafe { libc::clock_gettime(libc::CLOCK_PROCESS_CPUTIME_ID, &mut time) };
+	if res == 0 {
+		Some(Duration::new(time.tv_sec as u64, time.tv_nsec as u32))
+	} else {
+		None
+	}
+}
+
+/// CPU time consumed by all the threads of the process so far.
+#[cfg(not(unix))]
+fn process_cpu_time() -> Option<Duration> {
+	None
+}
+
+/// Memory of the process resident in RAM, in bytes.
+#[cfg(target_os = "linux")]
+fn resident_memory() -> Option<u64> {
+	// The second field of `statm` is the number of resident pages.
+	let statm = fs::read_to_string("/proc/self/statm").ok()?;
+	let pages: u64 = statm.split_whitespace().nth(1)?.parse().ok()?;
+	// Safe, `sysconf` has no precondition.
+	let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+	if page_size > 0 {
+		Some(pages * page_size as u64)
+	} else {
+		None
+	}
+}
+
+/// Memory of the process resident in RAM, in bytes.
+#[cfg(not(target_os = "linux"))]
+fn resident_memory() -> Option<u64> {
+	None
+}
+
+/// Number of file descriptors opened by the process.
+#[cfg(target_os = "linux")]
+fn open_fds() -> Option<u64> {
+	let fds = fs::read_dir("/proc/self/fd").ok()?.count() as u64;
+	// Not counting the descriptor of the directory being read.
+	Some(fds.saturating_sub(1))
+}
+
+/// Number of file descriptors opened by the process.
+#[cfg(not(target_os = "linux"))]
+fn open_fds() -> Option<u64> {
+	None
+}
+
+/// Total size of the files under `path`, not following the symbolic links.
+fn disk_usage(path: &Path) -> io::Result<u64> {
+	let metadata = fs::symlink_metadata(path)?;
+	if !metadata.is_dir() {
+		return Ok(metadata.len());
+	}
+
+	let mut size = 0;
+	for entry in fs::read_dir(path)? {
+		// The files of the database come and go, e.g. during compactions.
+		size += entry.and_then(|entry| disk_usage(&entry.path())).unwrap_or(0);
+	}
+	Ok(size)
+}
+
+struct Metrics {
+	cpu_usage: Gauge<F64>,
+	resident_memory: Gauge<U64>,
+	open_fds: Gauge<U64>,
+	database_size: Gauge<U64>,
+}
+
+impl Metrics {
+	fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+		Ok(Self {
+			cpu_usage: register(Gauge::new(
+				"process_cpu_usage",
+				"CPU time used by the process per second over the last sample, across all cores",
+			)?, registry)?,
+			resident_memory: register(Gauge::new(
+				"process_resident_memory_bytes", "Memory of the process resident in RAM in bytes",
+			)?, registry)?,
+			open_fds: register(Gauge::new(
+				"process_open_fds", "Number of file descriptors opened by the process",
+			)?, registry)?,
+			database_size: register(Gauge::new(
+				"database_disk_usage_bytes", "Disk space taken by the database in bytes",
+			)?, registry)?,
+		})
+	}
+}
+
+/// Periodically samples the resources used by the node, see the module documentation.
+pub struct ResourceSampler {
+	database_path: Option<PathBuf>,
+	metrics: Option<Metrics>,
+	scheduled_tasks: Option<GaugeVec<U64>>,
+	last_sample: Sample,
+	last_sampled_at: Instant,
+}
+
+impl ResourceSampler {
+	/// Create a sampler of the database at `database_path`, if any, reporting to `registry`.
+	///
+	/// The tasks waiting to be polled are read from `scheduled_tasks`, as reported by the task
+	/// manager.
+	pub fn new(
+		database_path: Option<PathBuf>,
+		registry: Option<&Registry>,
+		scheduled_tasks: Option<GaugeVec<U64>>,
+	) -> Result<Self, PrometheusError> {
+		Ok(ResourceSampler {
+			metrics: registry.map(Metrics::register).transpose()?,
+			last_sample: Sample::take(None),
+			last_sampled_at: Instant::now(),
+			database_path,
+			scheduled_tasks,
+		})
+	}
+
+	/// Sample the resources, returning the CPU usage since the last sample.
+	fn sample(&mut self) -> (Sample, Option<f64>) {
+		let sample = Sample::take(self.database_path.as_deref());
+		let now = Instant::now();
+		let elapsed = (now - self.last_sampled_at).as_secs_f64();
+		let cpu_usage = match (self.last_sample.cpu_time, sample.cpu_time) {
+			(Some(last), Some(current)) if elapsed > 0.0 =>
+				Some(current.checked_sub(last).unwrap_or_default().as_secs_f64() / elapsed),
+			_ => None,
+		};
+
+		self.last_sample = sample.clone();
+		self.last_sampled_at = now;
+		(sample, cpu_usage)
+	}
+
+	fn scheduled_tasks(&self, kind: &str) -> u64 {
+		self.scheduled_tasks.as_ref().map_or(0, |gauge| gauge.with_label_values(&[kind]).get())
+	}
+
+	/// Sample the resources until the network stops reporting its status.
+	pub async fn run<TBl, TCl>(
+		mut self,
+		client: Arc<TCl>,
+		network_status_sinks: NetworkStatusSinks<TBl>,
+	)
+		where
+			TBl: BlockT,
+			TCl: HeaderBackend<TBl>,
+	{
+		network_status_sinks.network_status(SAMPLE_INTERVAL).for_each(move |(net_status, _)| {
+			let (sample, cpu_usage) = self.sample();
+
+			if let Some(metrics) = &self.metrics {
+				if let Some(cpu_usage) = cpu_usage {
+					metrics.cpu_usage.set(cpu_usage);
+				}
+				if let Some(resident_memory) = sample.resident_memory {
+					metrics.resident_memory.set(resident_memory);
+				}
+				if let Some(open_fds) = sample.open_fds {
+					metrics.open_fds.set(open_fds);
+				}
+				if let Some(database_size) = sample.database_size {
+					metrics.database_size.set(database_size);
+				}
+			}
+
+			let info = client.info();
+			tracing::info!(
+				target: RESOURCES_TARGET,
+				best = info.best_number.saturated_into::<u64>(),
+				finalized = info.finalized_number.saturated_into::<u64>(),
+				peers = net_status.num_connected_peers as u64,
+				cpu_usage_percent = (cpu_usage.unwrap_or(0.0) * 100.0) as u64,
+				cpu_time_ms = sample.cpu_time.unwrap_or_default().as_millis() as u64,
+				resident_memory = sample.resident_memory.unwrap_or(0),
+				open_fds = sample.open_fds.unwrap_or(0),
+				database_size = sample.database_size.unwrap_or(0),
+				scheduled_async_tasks = self.scheduled_tasks("async"),
+				scheduled_blocking_tasks = self.scheduled_tasks("blocking"),
+				"Resources used by the node",
+			);
+			ready(())
+		}).await;
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn disk_usage_sums_the_files() {
+		let dir = tempfile::tempdir().unwrap();
+		fs::write(dir.path().join("a"), vec![0u8; 100]).unwrap();
+		fs::create_dir(dir.path().join("nested")).unwrap();
+		fs::write(dir.path().join("nested").join("b"), vec![0u8; 20]).unwrap();
+
+		assert_eq!(disk_usage(dir.path()).unwrap(), 120);
+		assert!(disk_usage(&dir.path().join("missing")).is_err());
+	}
+
+	#[cfg(target_os = "linux")]
+	#[test]
+	fn samples_the_process() {
+		let registry = Registry::new();
+		let mut sampler = ResourceSampler::new(None, Some(&registry), None).unwrap();
+		let (sample, _) = sampler.sample();
+
+		assert!(sample.cpu_time.is_some());
+		assert!(sample.resident_memory.unwrap() > 0);
+		assert!(sample.open_fds.unwrap() > 0);
+		assert!(sample.database_size.is_none());
+		assert_eq!(sampler.scheduled_tasks("async"), 0);
+	}
+}
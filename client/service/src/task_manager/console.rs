@@ -0,0 +1,201 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2017-2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Live introspection of the tasks spawned through the task manager.
+//!
+//! When enabled, every spawned task is registered in a [`TaskRegistry`] that records which
+//! task spawned it, how often and for how long it was polled and whether it is currently
+//! being polled. A snapshot of the registry is served as JSON to every client connecting to
+//! the console address, e.g. `nc 127.0.0.1 6669 | jq`.
+
+use std::{
+	cell::Cell, collections::BTreeMap, io::{self, Write}, net::{SocketAddr, TcpListener},
+	sync::{Arc, atomic::{AtomicU64, Ordering}}, time::{Duration, Instant},
+};
+use parking_lot::Mutex;
+use serde::Serialize;
+
+thread_local! {
+	/// Id of the task that is currently polled on this thread, if any.
+	static CURRENT_TASK: Cell<Option<u64>> = Cell::new(None);
+}
+
+/// Information about a single live task.
+#[derive(Debug, Clone)]
+struct TaskInfo {
+	name: &'static str,
+	blocking: bool,
+	parent: Option<u64>,
+	spawned_at: Instant,
+	polls: u64,
+	busy: Duration,
+	last_poll: Option<Instant>,
+	polling: bool,
+}
+
+/// Serializable view of a task, as returned to console clients.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskSnapshot {
+	/// Unique id of the task.
+	pub id: u64,
+	/// Name the task was spawned with.
+	pub name: &'static str,
+	/// Whether the task was spawned as blocking.
+	pub blocking: bool,
+	/// Id of the task in which this task was spawned, if any.
+	pub parent: Option<u64>,
+	/// Milliseconds since the task was spawned.
+	pub age_ms: u64,
+	/// Number of times the task was polled.
+	pub polls: u64,
+	/// Total milliseconds spent inside `Future::poll`.
+	pub busy_ms: u64,
+	/// Milliseconds since the task was last polled, `None` if it was never polled.
+	pub idle_ms: Option<u64>,
+	/// Whether the task is being polled right now.
+	pub polling: bool,
+}
+
+/// Registry of all live tasks of a task manager.
+#[derive(Default)]
+pub struct TaskRegistry {
+	next_id: AtomicU64,
+	tasks: Mutex<BTreeMap<u64, TaskInfo>>,
+}
+
+impl TaskRegistry {
+	/// Register a newly spawned task, returning its id.
+	///
+	/// If called from within a polled task, that task is recorded as the parent.
+	pub fn register(&self, name: &'static str, blocking: bool) -> u64 {
+		let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+		let parent = CURRENT_TASK.with(|c| c.get());
+		self.tasks.lock().insert(id, TaskInfo {
+			name,
+			blocking,
+			parent,
+			spawned_at: Instant::now(),
+			polls: 0,
+			busy: Duration::default(),
+			last_poll: None,
+			polling: false,
+		});
+		id
+	}
+
+	/// Mark the task as being polled on the current thread.
+	///
+	/// Returns the id of the task that was previously marked as current, which must be given
+	/// back to `poll_ended`.
+	pub fn poll_started(&self, id: u64) -> Option<u64> {
+		if let Some(task) = self.tasks.lock().get_mut(&id) {
+			task.polling = true;
+		}
+		CURRENT_TASK.with(|c| c.replace(Some(id)))
+	}
+
+	/// Mark the end of a poll that took `elapsed`.
+	pub fn poll_ended(&self, id: u64, elapsed: Duration, previous: Option<u64>) {
+		CURRENT_TASK.with(|c| c.set(previous));
+		if let Some(task) = self.tasks.lock().get_mut(&id) {
+			task.polling = false;
+			task.polls += 1;
+			task.busy += elapsed;
+			task.last_poll = Some(Instant::now());
+		}
+	}
+
+	/// Remove a task that has ended.
+	pub fn remove(&self, id: u64) {
+		self.tasks.lock().remove(&id);
+	}
+
+	/// A snapshot of all live tasks.
+	pub fn snapshot(&self) -> Vec<TaskSnapshot> {
+		let now = Instant::now();
+		self.tasks.lock().iter().map(|(id, task)| TaskSnapshot {
+			id: *id,
+			name: task.name,
+			blocking: task.blocking,
+			parent: task.parent,
+			age_ms: (now - task.spawned_at).as_millis() as u64,
+			polls: task.polls,
+			busy_ms: task.busy.as_millis() as u64,
+			idle_ms: task.last_poll.map(|t| (now - t).as_millis() as u64),
+			polling: task.polling,
+		}).collect()
+	}
+}
+
+/// Serve snapshots of the registry on the given address from a dedicated thread.
+pub fn start_console(addr: SocketAddr, registry: Arc<TaskRegistry>) -> io::Result<()> {
+	let listener = TcpListener::bind(addr)?;
+	log::info!("🔬 Task console listening on {}", addr);
+
+	std::thread::Builder::new()
+		.name("task-console".into())
+		.spawn(move || {
+			for stream in listener.incoming() {
+				let mut stream = match stream {
+					Ok(stream) => stream,
+					Err(e) => {
+						log::debug!("Task console connection failed: {}", e);
+						continue
+					},
+				};
+				let snapshot = registry.snapshot();
+				let result = serde_json::to_writer_pretty(&mut stream, &snapshot)
+					.map_err(io::Error::from)
+					.and_then(|_| stream.write_all(b"\n"));
+				if let Err(e) = result {
+					log::debug!("Failed to write task console snapshot: {}", e);
+				}
+			}
+		})?;
+
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn records_parent_and_polls() {
+		let registry = TaskRegistry::default();
+		let parent = registry.register("parent", false);
+
+		let previous = registry.poll_started(parent);
+		let child = registry.register("child", true);
+		registry.poll_ended(parent, Duration::from_millis(5), previous);
+		let orphan = registry.register("orphan", false);
+
+		let snapshot = registry.snapshot();
+		assert_eq!(snapshot.len(), 3);
+		assert_eq!(snapshot[0].polls, 1);
+		assert_eq!(snapshot[0].busy_ms, 5);
+		assert_eq!(snapshot[1].parent, Some(parent));
+		assert!(snapshot[1].blocking);
+		assert_eq!(snapshot[2].parent, None);
+
+		registry.remove(child);
+		registry.remove(orphan);
+		assert_eq!(registry.snapshot().len(), 1);
+	}
+}
@@ -13,7 +13,7 @@
 
 //! Substrate service tasks management module.
 
-use std::{panic, result::Result, pin::Pin};
+use std::{panic, result::Result, pin::Pin, net::SocketAddr, sync::Arc};
 use exit_future::Signal;
 use log::{debug, error};
 use futures::{
@@ -24,12 +24,17 @@ use futures::{
 use prometheus_endpoint::{
 	exponential_buckets, register,
 	PrometheusError,
-	CounterVec, HistogramOpts, HistogramVec, Opts, Registry, U64
+	CounterVec, GaugeVec, HistogramOpts, HistogramVec, Opts, Registry, F64, U64
 };
 use sp_utils::mpsc::{TracingUnboundedSender, TracingUnboundedReceiver, tracing_unbounded};
 use crate::{config::{TaskExecutor, TaskType, JoinFuture}, Error};
+use console::TaskRegistry;
+pub(crate) use status::TaskStatuses;
 
+mod console;
 mod prometheus_future;
+mod status;
+mod task_span;
 #[cfg(test)]
 mod tests;
 
@@ -39,6 +44,8 @@ pub struct SpawnTaskHandle {
 	on_exit: exit_future::Exit,
 	executor: TaskExecutor,
 	metrics: Option<Metrics>,
+	console: Option<Arc<TaskRegistry>>,
+	statuses: Arc<TaskStatuses>,
 	task_notifier: TracingUnboundedSender<JoinFuture>,
 }
 
@@ -52,20 +59,29 @@ impl SpawnTaskHandle {
 	/// In other words, it would be a bad idea for someone to do for example
 	/// `spawn(format!("{:?}", some_public_key))`.
 	pub fn spawn(&self, name: &'static str, task: impl Future<Output = ()> + Send + 'static) {
-		self.spawn_inner(name, task, TaskType::Async)
+		self.spawn_inner(name, task, TaskType::Async, None)
 	}
 
 	/// Spawns the blocking task with the given name. See also `spawn`.
 	pub fn spawn_blocking(&self, name: &'static str, task: impl Future<Output = ()> + Send + 'static) {
-		self.spawn_inner(name, task, TaskType::Blocking)
+		self.spawn_inner(name, task, TaskType::Blocking, None)
+	}
+
+	/// The status of the tasks spawned through this handle.
+	pub(crate) fn task_statuses(&self) -> Arc<TaskStatuses> {
+		self.statuses.clone()
 	}
 
 	/// Helper function that implements the spawning logic. See `spawn` and `spawn_blocking`.
+	///
+	/// If given `essential_failed`, the task is essential: its channel is closed when the task
+	/// finishes or panics.
 	fn spawn_inner(
 		&self,
 		name: &'static str,
 		task: impl Future<Output = ()> + Send + 'static,
 		task_type: TaskType,
+		essential_failed: Option<TracingUnboundedSender<()>>,
 	) {
 		if self.task_notifier.is_closed() {
 			debug!("Attempt to spawn a new task has been prevented: {}", name);
@@ -74,6 +90,18 @@ impl SpawnTaskHandle {
 
 		let on_exit = self.on_exit.clone();
 		let metrics = self.metrics.clone();
+		let statuses = self.statuses.clone();
+		let kind = if task_type == TaskType::Blocking { "blocking" } else { "async" };
+		// Every task gets a span that is entered whenever the task is polled, so that
+		// everything traced from within the task can be attributed to it.
+		let span = tracing::debug_span!(target: task_span::TASK_TARGET, "task", name);
+		let task = task_span::with_span(
+			span.clone(),
+			name,
+			task_type == TaskType::Blocking,
+			self.console.clone(),
+			task,
+		);
 
 		// Note that we increase the started counter here and not within the future. This way,
 		// we could properly visualize on Prometheus situations where the spawning doesn't work.
@@ -82,37 +110,73 @@ impl SpawnTaskHandle {
 			// We do a dummy increase in order for the task to show up in metrics.
 			metrics.tasks_ended.with_label_values(&[name, "finished"]).inc_by(0);
 		}
+		statuses.spawned(name, essential_failed.is_some());
 
 		let future = async move {
-			if let Some(metrics) = metrics {
-				// Add some wrappers around `task`.
-				let task = {
+			// Add some wrappers around `task`.
+			let task = match &metrics {
+				Some(metrics) => {
 					let poll_duration = metrics.poll_duration.with_label_values(&[name]);
 					let poll_start = metrics.poll_start.with_label_values(&[name]);
-					let inner = prometheus_future::with_poll_durations(poll_duration, poll_start, task);
-					// The logic of `AssertUnwindSafe` here is ok considering that we throw
-					// away the `Future` after it has panicked.
-					panic::AssertUnwindSafe(inner).catch_unwind()
-				};
-				futures::pin_mut!(task);
-
-				match select(on_exit, task).await {
-					Either::Right((Err(payload), _)) => {
-						metrics.tasks_ended.with_label_values(&[name, "panic"]).inc();
-						panic::resume_unwind(payload)
-					}
-					Either::Right((Ok(()), _)) => {
-						metrics.tasks_ended.with_label_values(&[name, "finished"]).inc();
-					}
-					Either::Left(((), _)) => {
-						// The `on_exit` has triggered.
-						metrics.tasks_ended.with_label_values(&[name, "interrupted"]).inc();
-					}
-				}
+					let poll_cpu_time = metrics.poll_cpu_time.with_label_values(&[name]);
+					let scheduled = metrics.tasks_scheduled.with_label_values(&[kind]);
+					Either::Left(prometheus_future::with_scheduling(
+						scheduled,
+						prometheus_future::with_poll_durations(
+							poll_duration,
+							poll_start,
+							poll_cpu_time,
+							task,
+						),
+					))
+				},
+				None => Either::Right(task),
+			};
+			// The logic of `AssertUnwindSafe` here is ok considering that we throw
+			// away the `Future` after it has panicked.
+			let task = panic::AssertUnwindSafe(task).catch_unwind();
+			futures::pin_mut!(task);
+
+			let (reason, payload) = match select(on_exit, task).await {
+				Either::Right((Err(payload), _)) => ("panic", Some(payload)),
+				Either::Right((Ok(()), _)) => ("finished", None),
+				// The `on_exit` has triggered.
+				Either::Left(((), _)) => ("interrupted", None),
+			};
+
+			if let Some(metrics) = metrics {
+				metrics.tasks_ended.with_label_values(&[name, reason]).inc();
+			}
 
-			} else {
-				futures::pin_mut!(task);
-				let _ = select(on_exit, task).await;
+			let reason = match &payload {
+				Some(payload) => format!("panicked: {}", status::panic_message(&**payload)),
+				None => reason.to_owned(),
+			};
+			// The spans the task was spawned in, as it is not polled anymore.
+			let spans = span.in_scope(sp_tracing::span_context);
+			statuses.exited(name, reason.clone(), spans.clone());
+
+			match essential_failed {
+				// Interruptions are not failures, the service is already shutting down.
+				Some(essential_failed) if reason != "interrupted" => {
+					let spans = spans.unwrap_or_default();
+					error!(
+						"Essential task `{}` failed ({}) in spans `{}`. Shutting down service.",
+						name, reason, spans,
+					);
+					tracing::error!(
+						target: task_span::TASK_TARGET,
+						task = name,
+						reason = reason.as_str(),
+						spans = spans.as_str(),
+						"Essential task failed, shutting down the service",
+					);
+					let _ = essential_failed.close_channel();
+				},
+				None => if let Some(payload) = payload {
+					panic::resume_unwind(payload)
+				},
+				Some(_) => {},
 			}
 		};
 
@@ -184,15 +248,7 @@ impl SpawnEssentialTaskHandle {
 		task: impl Future<Output = ()> + Send + 'static,
 		task_type: TaskType,
 	) {
-		let essential_failed = self.essential_failed_tx.clone();
-		let essential_task = std::panic::AssertUnwindSafe(task)
-			.catch_unwind()
-			.map(move |_| {
-				log::error!("Essential task `{}` failed. Shutting down service.", name);
-				let _ = essential_failed.close_channel();
-			});
-
-		let _ = self.inner.spawn_inner(name, essential_task, task_type);
+		self.inner.spawn_inner(name, task, task_type, Some(self.essential_failed_tx.clone()))
 	}
 }
 
@@ -207,6 +263,10 @@ pub struct TaskManager {
 	executor: TaskExecutor,
 	/// Prometheus metric where to report the polling times.
 	metrics: Option<Metrics>,
+	/// Registry of live tasks, if the task console is enabled.
+	console: Option<Arc<TaskRegistry>>,
+	/// Running tasks and latest exits, by name.
+	statuses: Arc<TaskStatuses>,
 	/// Send a signal when a spawned essential task has concluded. The next time
 	/// the service future is polled it should complete with an error.
 	essential_failed_tx: TracingUnboundedSender<()>,
@@ -252,6 +312,8 @@ impl TaskManager {
 			signal: Some(signal),
 			executor,
 			metrics,
+			console: None,
+			statuses: Default::default(),
 			essential_failed_tx,
 			essential_failed_rx,
 			keep_alive: Box::new(()),
@@ -267,10 +329,28 @@ impl TaskManager {
 			on_exit: self.on_exit.clone(),
 			executor: self.executor.clone(),
 			metrics: self.metrics.clone(),
+			console: self.console.clone(),
+			statuses: self.statuses.clone(),
 			task_notifier: self.task_notifier.clone(),
 		}
 	}
 
+	/// Serve a live view of all tasks spawned from now on at the given address.
+	///
+	/// Only tasks spawned through handles obtained after this call are tracked.
+	pub(super) fn enable_console(&mut self, addr: SocketAddr) -> std::io::Result<()> {
+		let registry = Arc::new(TaskRegistry::default());
+		console::start_console(addr, registry.clone())?;
+		self.console = Some(registry);
+		Ok(())
+	}
+
+	/// The number of tasks scheduled for polling by the executor, by kind, if the task manager
+	/// reports to Prometheus.
+	pub(crate) fn scheduled_tasks(&self) -> Option<GaugeVec<U64>> {
+		self.metrics.as_ref().map(|metrics| metrics.tasks_scheduled.clone())
+	}
+
 	/// Get a handle for spawning essential tasks.
 	pub fn spawn_essential_handle(&self) -> SpawnEssentialTaskHandle {
 		SpawnEssentialTaskHandle::new(self.essential_failed_tx.clone(), self.spawn_handle())
@@ -294,6 +374,9 @@ impl TaskManager {
 		Box::pin(async move {
 			join_all(children_shutdowns).await;
 			completion_future.await;
+			// The telemetry is part of `keep_alive`, flush everything while it is still there.
+			sc_tracing::flush_global();
+			sc_telemetry::flush();
 			drop(keep_alive);
 		})
 	}
@@ -355,8 +438,10 @@ impl TaskManager {
 #[derive(Clone)]
 struct Metrics {
 	// This list is ordered alphabetically
+	poll_cpu_time: CounterVec<F64>,
 	poll_duration: HistogramVec,
 	poll_start: CounterVec<U64>,
+	tasks_scheduled: GaugeVec<U64>,
 	tasks_spawned: CounterVec<U64>,
 	tasks_ended: CounterVec<U64>,
 }
@@ -364,6 +449,13 @@ struct Metrics {
 impl Metrics {
 	fn register(registry: &Registry) -> Result<Self, PrometheusError> {
 		Ok(Self {
+			poll_cpu_time: register(CounterVec::new(
+				Opts::new(
+					"tasks_polling_cpu_seconds_total",
+					"Total CPU time in seconds spent by the polling thread inside Future::poll"
+				),
+				&["task_name"]
+			)?, registry)?,
 			poll_duration: register(HistogramVec::new(
 				HistogramOpts {
 					common_opts: Opts::new(
@@ -382,6 +474,14 @@ impl Metrics {
 				),
 				&["task_name"]
 			)?, registry)?,
+			tasks_scheduled: register(GaugeVec::new(
+				Opts::new(
+					"tasks_scheduled",
+					"Number of tasks woken up and waiting to be polled by the executor, by kind: \
+					async or blocking"
+				),
+				&["kind"]
+			)?, registry)?,
 			tasks_spawned: register(CounterVec::new(
 				Opts::new(
 					"tasks_spawned_total",
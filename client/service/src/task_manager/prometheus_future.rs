@@ -13,24 +13,49 @@
 
 //! Wrapper around a `Future` that reports statistics about when the `Future` is polled.
 
-use futures::prelude::*;
-use prometheus_endpoint::{Counter, Histogram, U64};
-use std::{fmt, pin::Pin, task::{Context, Poll}};
+use futures::{prelude::*, task::{ArcWake, waker_ref}};
+use parking_lot::Mutex;
+use prometheus_endpoint::{Counter, F64, Gauge, Histogram, U64};
+use std::{
+	fmt, pin::Pin, sync::{Arc, atomic::{AtomicBool, Ordering}}, task::{Context, Poll, Waker},
+	time::Duration,
+};
 
-/// Wraps around a `Future`. Report the polling duration to the `Histogram` and when the polling
-/// starts to the `Counter`.
+/// Wraps around a `Future`. Report the polling duration to the `Histogram`, when the polling
+/// starts to the `Counter` and the CPU time spent by the polling thread to `poll_cpu_time`.
 pub fn with_poll_durations<T>(
 	poll_duration: Histogram,
 	poll_start: Counter<U64>,
+	poll_cpu_time: Counter<F64>,
 	inner: T
 ) -> PrometheusFuture<T> {
 	PrometheusFuture {
 		inner,
 		poll_duration,
 		poll_start,
+		poll_cpu_time,
 	}
 }
 
+/// CPU time consumed by the current thread so far, if the platform supports measuring it.
+#[cfg(unix)]
+pub fn thread_cpu_time() -> Option<Duration> {
+	let mut time = libc::timespec { tv_sec: 0, tv_nsec: 0 };
+	// Safe, as `time` is a valid pointer to a `timespec` for the duration of the call.
+	let res = unsafe { libc::clock_gettime(libc::CLOCK_THREAD_CPUTIME_ID, &mut time) };
+	if res == 0 {
+		Some(Duration::new(time.tv_sec as u64, time.tv_nsec as u32))
+	} else {
+		None
+	}
+}
+
+/// CPU time consumed by the current thread so far, if the platform supports measuring it.
+#[cfg(not(unix))]
+pub fn thread_cpu_time() -> Option<Duration> {
+	None
+}
+
 /// Wraps around `Future` and adds diagnostics to it.
 #[pin_project::pin_project]
 #[derive(Clone)]
@@ -40,6 +65,7 @@ pub struct PrometheusFuture<T> {
 	inner: T,
 	poll_duration: Histogram,
 	poll_start: Counter<U64>,
+	poll_cpu_time: Counter<F64>,
 }
 
 impl<T> Future for PrometheusFuture<T>
@@ -52,9 +78,15 @@ where
 		let this = self.project();
 
 		this.poll_start.inc();
+		let cpu_start = thread_cpu_time();
 		let _timer = this.poll_duration.start_timer();
-		Future::poll(this.inner, cx)
+		let result = Future::poll(this.inner, cx);
+
+		if let (Some(start), Some(end)) = (cpu_start, thread_cpu_time()) {
+			this.poll_cpu_time.inc_by(end.checked_sub(start).unwrap_or_default().as_secs_f64());
+		}
 
+		result
 		// `_timer` is dropped here and will observe the duration
 	}
 }
@@ -67,3 +99,94 @@ where
 		fmt::Debug::fmt(&self.inner, f)
 	}
 }
+
+/// Wraps around a `Future`. Counts it in `scheduled` from when it is woken up until it is polled,
+/// that is while it waits in the queue of the executor.
+///
+/// The future is counted from its creation, as it is created to be spawned.
+pub fn with_scheduling<T>(scheduled: Gauge<U64>, inner: T) -> ScheduledFuture<T> {
+	scheduled.inc();
+	let state = Arc::new(Scheduling {
+		scheduled: AtomicBool::new(true),
+		gauge: scheduled,
+		waker: Mutex::new(None),
+	});
+
+	ScheduledFuture { inner, state: Unschedule(state) }
+}
+
+/// Whether a future is scheduled, woken up by the waker wrapping the one of its executor.
+struct Scheduling {
+	scheduled: AtomicBool,
+	gauge: Gauge<U64>,
+	waker: Mutex<Option<Waker>>,
+}
+
+impl Scheduling {
+	fn unschedule(&self) {
+		if self.scheduled.swap(false, Ordering::Relaxed) {
+			self.gauge.dec();
+		}
+	}
+}
+
+impl ArcWake for Scheduling {
+	fn wake_by_ref(arc_self: &Arc<Self>) {
+		if !arc_self.scheduled.swap(true, Ordering::Relaxed) {
+			arc_self.gauge.inc();
+		}
+		if let Some(waker) = &*arc_self.waker.lock() {
+			waker.wake_by_ref();
+		}
+	}
+}
+
+/// Uncounts the future when dropped, e.g. while scheduled when its task is interrupted.
+struct Unschedule(Arc<Scheduling>);
+
+impl Drop for Unschedule {
+	fn drop(&mut self) {
+		self.0.unschedule();
+	}
+}
+
+/// Wraps around `Future` and counts it while scheduled.
+#[pin_project::pin_project]
+pub struct ScheduledFuture<T> {
+	/// The inner future doing the actual work.
+	#[pin]
+	inner: T,
+	state: Unschedule,
+}
+
+impl<T> Future for ScheduledFuture<T>
+where
+	T: Future,
+{
+	type Output = T::Output;
+
+	fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+		let this = self.project();
+		let state = &this.state.0;
+
+		state.unschedule();
+		{
+			let mut waker = state.waker.lock();
+			if !waker.as_ref().map_or(false, |waker| waker.will_wake(cx.waker())) {
+				*waker = Some(cx.waker().clone());
+			}
+		}
+
+		let waker = waker_ref(state);
+		Future::poll(this.inner, &mut Context::from_waker(&waker))
+	}
+}
+
+impl<T> fmt::Debug for ScheduledFuture<T>
+where
+	T: fmt::Debug,
+{
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		fmt::Debug::fmt(&self.inner, f)
+	}
+}
@@ -0,0 +1,112 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2017-2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Status of the tasks spawned through the task manager, as served by the `system_taskStatus`
+//! RPC.
+
+use std::{any::Any, collections::{BTreeMap, VecDeque}, time::SystemTime};
+use parking_lot::Mutex;
+use sc_rpc::system::{TaskExit, TaskStatus};
+
+/// Maximum number of exits remembered per task name.
+const MAX_EXITS_PER_TASK: usize = 8;
+
+#[derive(Default)]
+struct Entry {
+	essential: bool,
+	running: u64,
+	exits: VecDeque<TaskExit>,
+}
+
+/// Running tasks and latest exits, by task name.
+///
+/// The names of the tasks are `&'static str`, so that the number of entries is bounded.
+#[derive(Default)]
+pub struct TaskStatuses {
+	tasks: Mutex<BTreeMap<&'static str, Entry>>,
+}
+
+impl TaskStatuses {
+	/// Record that a task was spawned.
+	pub fn spawned(&self, name: &'static str, essential: bool) {
+		let mut tasks = self.tasks.lock();
+		let entry = tasks.entry(name).or_default();
+		entry.essential |= essential;
+		entry.running += 1;
+	}
+
+	/// Record that a task exited, for the given reason, in the given spans.
+	pub fn exited(&self, name: &'static str, reason: String, spans: Option<String>) {
+		let exited_at = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH)
+			.unwrap_or_default()
+			.as_millis() as u64;
+
+		let mut tasks = self.tasks.lock();
+		let entry = tasks.entry(name).or_default();
+		entry.running = entry.running.saturating_sub(1);
+		if entry.exits.len() == MAX_EXITS_PER_TASK {
+			entry.exits.pop_front();
+		}
+		entry.exits.push_back(TaskExit { reason, spans, exited_at });
+	}
+
+	/// The status of all the tasks spawned so far, by name.
+	pub fn snapshot(&self) -> Vec<TaskStatus> {
+		self.tasks.lock().iter()
+			.map(|(name, entry)| TaskStatus {
+				name: name.to_string(),
+				essential: entry.essential,
+				running: entry.running,
+				exits: entry.exits.iter().cloned().collect(),
+			})
+			.collect()
+	}
+}
+
+/// The message of a panic, from its payload.
+pub fn panic_message(payload: &(dyn Any + Send)) -> &str {
+	match payload.downcast_ref::<&'static str>() {
+		Some(message) => message,
+		None => match payload.downcast_ref::<String>() {
+			Some(message) => &message[..],
+			None => "Box<Any>",
+		},
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn records_the_latest_exits() {
+		let statuses = TaskStatuses::default();
+		statuses.spawned("task", true);
+		statuses.spawned("task", false);
+		for i in 0..10 {
+			statuses.exited("task", format!("exit {}", i), None);
+		}
+
+		let snapshot = statuses.snapshot();
+		assert_eq!(snapshot.len(), 1);
+		assert!(snapshot[0].essential);
+		assert_eq!(snapshot[0].running, 0);
+		let reasons: Vec<_> = snapshot[0].exits.iter().map(|exit| exit.reason.as_str()).collect();
+		assert_eq!(reasons, (2..10).map(|i| format!("exit {}", i)).collect::<Vec<_>>());
+	}
+}
@@ -0,0 +1,203 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2017-2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use futures::Future;
+use std::{pin::Pin, sync::Arc, task::{Context, Poll}, time::{Duration, Instant}};
+use super::console::TaskRegistry;
+
+/// Tracing target of the spans created for spawned tasks.
+pub const TASK_TARGET: &str = "substrate_task";
+
+/// Polls exceeding this duration are reported as an event inside the task span.
+const SLOW_POLL: Duration = Duration::from_millis(100);
+
+/// Wraps around a `Future`, entering `span` for the duration of every poll.
+///
+/// If a `registry` is given, the task is registered in it until the future is dropped.
+pub fn with_span<T>(
+	span: tracing::Span,
+	name: &'static str,
+	blocking: bool,
+	registry: Option<Arc<TaskRegistry>>,
+	inner: T,
+) -> TaskSpanFuture<T> {
+	let registered = registry.map(|registry| {
+		let id = registry.register(name, blocking);
+		(registry, id)
+	});
+	TaskSpanFuture { inner, span, name, registered }
+}
+
+/// Wraps around `Future` and enters a long-lived span whenever it is polled.
+#[pin_project::pin_project(PinnedDrop)]
+pub struct TaskSpanFuture<T> {
+	/// The inner future doing the actual work.
+	#[pin]
+	inner: T,
+	span: tracing::Span,
+	name: &'static str,
+	registered: Option<(Arc<TaskRegistry>, u64)>,
+}
+
+#[pin_project::pinned_drop]
+impl<T> PinnedDrop for TaskSpanFuture<T> {
+	fn drop(self: Pin<&mut Self>) {
+		if let Some((registry, id)) = self.project().registered {
+			registry.remove(*id);
+		}
+	}
+}
+
+impl<T> Future for TaskSpanFuture<T>
+where
+	T: Future,
+{
+	type Output = T::Output;
+
+	fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+		let this = self.project();
+		let _guard = this.span.enter();
+
+		let start = Instant::now();
+		let poll_guard = this.registered.as_ref().map(|(registry, id)| PollGuard {
+			previous: registry.poll_started(*id),
+			registry,
+			id: *id,
+			start,
+		});
+		let result = Future::poll(this.inner, cx);
+		drop(poll_guard);
+		let elapsed = start.elapsed();
+
+		if elapsed > SLOW_POLL {
+			tracing::debug!(
+				target: TASK_TARGET,
+				task = *this.name,
+				duration_ms = elapsed.as_millis() as u64,
+				"Task blocked the executor for a single poll",
+			);
+		}
+
+		result
+	}
+}
+
+/// Ends the poll of a registered task when dropped, even if the poll panicked, so that the
+/// task is not left as the current task of the thread.
+struct PollGuard<'a> {
+	registry: &'a TaskRegistry,
+	id: u64,
+	previous: Option<u64>,
+	start: Instant,
+}
+
+impl<'a> Drop for PollGuard<'a> {
+	fn drop(&mut self) {
+		self.registry.poll_ended(self.id, self.start.elapsed(), self.previous);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use futures::task::noop_waker_ref;
+	use parking_lot::Mutex;
+	use prometheus_endpoint::{Counter, F64, Histogram, HistogramOpts, U64};
+	use std::panic::{AssertUnwindSafe, catch_unwind};
+	use crate::task_manager::prometheus_future;
+
+	struct SpanCollector(Arc<Mutex<Vec<sc_tracing::SpanDatum>>>);
+
+	impl sc_tracing::TraceHandler for SpanCollector {
+		fn handle_span(&self, span: sc_tracing::SpanDatum) {
+			self.0.lock().push(span);
+		}
+
+		fn handle_event(&self, _: sc_tracing::TraceEvent) {}
+	}
+
+	#[test]
+	fn busy_poll_is_accounted() {
+		const BUSY: Duration = Duration::from_millis(20);
+
+		let spans = Arc::new(Mutex::new(Vec::new()));
+		let subscriber = sc_tracing::ProfilingSubscriber::new_with_handler(
+			Box::new(SpanCollector(spans.clone())),
+			TASK_TARGET,
+		);
+		let registry = Arc::new(TaskRegistry::default());
+		let poll_cpu_time = Counter::<F64>::new("poll_cpu_time", "help").unwrap();
+
+		tracing::subscriber::with_default(subscriber, || {
+			let mut polled = false;
+			// Spins for `BUSY` on its first poll.
+			let busy = futures::future::poll_fn(move |_| {
+				if polled {
+					return Poll::Ready(());
+				}
+				polled = true;
+				let start = Instant::now();
+				while start.elapsed() < BUSY {}
+				Poll::Pending
+			});
+			let span = tracing::debug_span!(target: TASK_TARGET, "task", name = "busy");
+			let task = prometheus_future::with_poll_durations(
+				Histogram::with_opts(HistogramOpts::new("poll_duration", "help")).unwrap(),
+				Counter::<U64>::new("poll_start", "help").unwrap(),
+				poll_cpu_time.clone(),
+				with_span(span, "busy", false, Some(registry.clone()), busy),
+			);
+			futures::pin_mut!(task);
+			let mut cx = Context::from_waker(noop_waker_ref());
+
+			assert!(task.as_mut().poll(&mut cx).is_pending());
+			let snapshot = registry.snapshot();
+			assert_eq!(snapshot.len(), 1);
+			assert_eq!(snapshot[0].name, "busy");
+			assert_eq!(snapshot[0].polls, 1);
+			assert!(snapshot[0].busy_ms >= BUSY.as_millis() as u64);
+			assert!(!snapshot[0].polling);
+
+			assert!(task.as_mut().poll(&mut cx).is_ready());
+		});
+
+		if cfg!(unix) {
+			assert!(poll_cpu_time.get() > 0.0);
+		}
+		assert!(registry.snapshot().is_empty());
+		let spans = spans.lock();
+		assert_eq!(spans.len(), 1);
+		assert_eq!(spans[0].target, TASK_TARGET);
+		assert_eq!(spans[0].name, "task");
+		assert_eq!(spans[0].values.string_values.get("name").map(String::as_str), Some("busy"));
+		assert!(spans[0].overall_time >= BUSY);
+	}
+
+	#[test]
+	fn panicking_poll_resets_the_current_task() {
+		let registry = Arc::new(TaskRegistry::default());
+		let task = with_span(tracing::Span::none(), "panics", false, Some(registry.clone()), async {
+			panic!("task panicked");
+		});
+		assert!(catch_unwind(AssertUnwindSafe(|| futures::executor::block_on(task))).is_err());
+
+		let next = registry.register("next", false);
+		let snapshot = registry.snapshot();
+		assert_eq!(snapshot.iter().find(|task| task.id == next).unwrap().parent, None);
+	}
+}
@@ -308,3 +308,50 @@ fn ensure_task_manager_future_continues_when_childs_not_essential_task_fails() {
 	runtime.block_on(task_manager.clean_shutdown());
 	assert_eq!(drop_tester, 0);
 }
+
+#[test]
+fn ensure_scheduled_tasks_are_counted_until_polled() {
+	let mut runtime = tokio::runtime::Runtime::new().unwrap();
+	let handle = runtime.handle().clone();
+	let task_executor: TaskExecutor = (move |future, _| handle.spawn(future).map(|_| ())).into();
+
+	let registry = prometheus_endpoint::Registry::new();
+	let task_manager = TaskManager::new(task_executor, Some(&registry)).unwrap();
+	let scheduled = task_manager.scheduled_tasks().unwrap();
+	let spawn_handle = task_manager.spawn_handle();
+	let (tx, rx) = futures::channel::oneshot::channel::<()>();
+	spawn_handle.spawn("task1", rx.map(drop));
+	// allow the task to be polled, after which it waits on `rx`
+	runtime.block_on(async { tokio::time::delay_for(Duration::from_millis(100)).await });
+	assert_eq!(scheduled.with_label_values(&["async"]).get(), 0);
+	drop(tx);
+	runtime.block_on(async { tokio::time::delay_for(Duration::from_millis(100)).await });
+	assert_eq!(scheduled.with_label_values(&["async"]).get(), 0);
+	runtime.block_on(task_manager.clean_shutdown());
+	assert_eq!(scheduled.with_label_values(&["async"]).get(), 0);
+	assert_eq!(scheduled.with_label_values(&["blocking"]).get(), 0);
+}
+
+#[test]
+fn ensure_task_statuses_record_the_exits() {
+	let mut runtime = tokio::runtime::Runtime::new().unwrap();
+	let handle = runtime.handle().clone();
+	let task_executor: TaskExecutor = (move |future, _| handle.spawn(future).map(|_| ())).into();
+
+	let mut task_manager = TaskManager::new(task_executor, None).unwrap();
+	let spawn_handle = task_manager.spawn_handle();
+	let statuses = spawn_handle.task_statuses();
+	spawn_handle.spawn("task1", run_background_task(()));
+	task_manager.spawn_essential_handle().spawn("task2", async { panic!("task failed") });
+	runtime.block_on(task_manager.future()).expect_err("future()'s Result must be Err");
+
+	let statuses = statuses.snapshot();
+	assert_eq!(statuses.len(), 2);
+	assert_eq!((statuses[0].name.as_str(), statuses[0].essential), ("task1", false));
+	assert_eq!(statuses[0].running, 1);
+	assert!(statuses[0].exits.is_empty());
+	assert_eq!((statuses[1].name.as_str(), statuses[1].essential), ("task2", true));
+	assert_eq!(statuses[1].running, 0);
+	assert_eq!(statuses[1].exits[0].reason, "panicked: task failed");
+	runtime.block_on(task_manager.clean_shutdown());
+}
@@ -362,6 +362,41 @@ fn client_initializes_from_genesis_ok() {
 	);
 }
 
+#[test]
+fn runtime_api_calls_are_traced() {
+	struct SpanCollector(Arc<parking_lot::Mutex<Vec<sc_tracing::SpanDatum>>>);
+
+	impl sc_tracing::TraceHandler for SpanCollector {
+		fn handle_span(&self, span: sc_tracing::SpanDatum) {
+			self.0.lock().push(span);
+		}
+
+		fn handle_event(&self, _: sc_tracing::TraceEvent) {}
+	}
+
+	let client = substrate_test_runtime_client::new();
+	let genesis_hash = client.chain_info().genesis_hash;
+	let spans = Arc::new(parking_lot::Mutex::new(Vec::new()));
+	let subscriber = sc_tracing::ProfilingSubscriber::new_with_handler(
+		Box::new(SpanCollector(spans.clone())),
+		client::RUNTIME_API_TARGET,
+	);
+
+	tracing::subscriber::with_default(subscriber, || {
+		client.runtime_api().balance_of(
+			&BlockId::Number(0),
+			AccountKeyring::Alice.into(),
+		).unwrap();
+	});
+
+	let spans = spans.lock();
+	let span = spans.iter()
+		.find(|span| span.values.string_values.get("method").map(|m| m.as_str()) == Some("TestAPI_balance_of"))
+		.expect("runtime API call is traced");
+	assert_eq!(span.target, client::RUNTIME_API_TARGET);
+	assert_eq!(span.values.string_values.get("block"), Some(&format!("{:?}", Some(genesis_hash))));
+}
+
 #[test]
 fn block_builder_works_with_no_transactions() {
 	let mut client = substrate_test_runtime_client::new();
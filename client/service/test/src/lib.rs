@@ -237,7 +237,8 @@ fn node_config<G: RuntimeGenesis + 'static, E: ChainSpecExtension + Clone + 'sta
 		network: network_config,
 		keystore: KeystoreConfig::Path {
 			path: root.join("key"),
-			password: None
+			password: None,
+			audit_log: None,
 		},
 		database: DatabaseConfig::RocksDb {
 			path: root.join("db"),
@@ -248,6 +249,8 @@ fn node_config<G: RuntimeGenesis + 'static, E: ChainSpecExtension + Clone + 'sta
 		pruning: Default::default(),
 		chain_spec: Box::new((*spec).clone()),
 		wasm_method: sc_service::config::WasmExecutionMethod::Interpreted,
+		wasmtime_jitdump: false,
+		wasmtime_cache: None,
 		execution_strategies: Default::default(),
 		rpc_http: None,
 		rpc_ipc: None,
@@ -255,20 +258,37 @@ fn node_config<G: RuntimeGenesis + 'static, E: ChainSpecExtension + Clone + 'sta
 		rpc_ws_max_connections: None,
 		rpc_cors: None,
 		rpc_methods: Default::default(),
+		rpc_slow_calls: Default::default(),
+		grpc: None,
 		prometheus_config: None,
+		statsd_config: None,
 		telemetry_endpoints: None,
 		telemetry_external_transport: None,
+		telemetry_batching: None,
 		default_heap_pages: None,
 		offchain_worker: Default::default(),
 		force_authoring: false,
+		authorship_post_mortem: None,
 		disable_grandpa: false,
 		dev_key_seed: key_seed,
 		tracing_targets: None,
+		tracing_sync_targets: None,
 		tracing_receiver: Default::default(),
+		tracing_redaction: Default::default(),
+		tracing_summaries: None,
+		tracing_storage_counters: false,
+		tracing_weight_drift: false,
+		tracing_pov_breakdowns: false,
+		tracing_dispatch_errors: false,
+		import_deadlines: Default::default(),
+		tracing_sentry: None,
+		tracing_extensions: Vec::new(),
 		max_runtime_instances: 8,
 		announce_block: true,
 		base_path: Some(BasePath::new(root)),
 		informant_output_format: Default::default(),
+		tokio_console: None,
+		health_check: None,
 	}
 }
 
@@ -8,6 +8,8 @@ use slog::{Serializer, OwnedKVList, Key};
 use std::fmt;
 use take_mut::take;
 
+use crate::routing::{RedactFields, RedactingSerializer};
+
 struct ToSendSerializer {
 	kv: Box<dyn KV + Send>,
 }
@@ -138,6 +140,25 @@ impl AsyncRecord {
 		}
 	}
 
+	/// The verbosity of the record, from its tag.
+	pub fn verbosity(&self) -> u8 {
+		self.tag.parse().unwrap_or_default()
+	}
+
+	/// The message of the record, i.e. the id of the telemetry message.
+	pub fn msg(&self) -> &str {
+		&self.msg
+	}
+
+	/// The record, with its fields redacted by `redaction`.
+	pub fn redacted(self, redaction: &dyn RedactFields) -> Self {
+		let mut ser = RedactingSerializer { inner: ToSendSerializer::new(), redaction };
+		self.as_record_values(|record, _| {
+			record.kv().serialize(record, &mut ser).expect("`ToSendSerializer` can't fail");
+		});
+		AsyncRecord { kv: ser.inner.finish(), ..self }
+	}
+
 	/// Deconstruct this `AsyncRecord` into a record and `OwnedKVList`.
 	pub fn as_record_values(&self, mut f: impl FnMut(&Record, &OwnedKVList)) {
 		let rs = RecordStatic {
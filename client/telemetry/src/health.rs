@@ -0,0 +1,199 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2017-2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Health of the paths exporting the telemetry and the traces out of the node.
+//!
+//! Every exporter, e.g. the connection to a telemetry endpoint or the writer of a trace file,
+//! creates an [`ExporterHealth`] which it updates as it exports its records, so that its queue
+//! filling up, its sends slowing down or its records being dropped don't go unnoticed. The node
+//! exposes the health of all the exporters alive, see [`exporters`].
+
+use std::{
+	sync::{Arc, Weak, atomic::{AtomicBool, AtomicU64, Ordering}},
+	time::Duration,
+};
+use parking_lot::Mutex;
+
+/// Prefix of the names of the exporters of the connections to the telemetry endpoints.
+pub(crate) const TELEMETRY_EXPORTER: &str = "telemetry ";
+
+lazy_static::lazy_static! {
+	/// The exporters created, pruned of the dropped ones by [`exporters`].
+	static ref EXPORTERS: Mutex<Vec<Weak<Inner>>> = Mutex::new(Vec::new());
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+	name: String,
+	queued: AtomicU64,
+	sent: AtomicU64,
+	dropped: AtomicU64,
+	reconnects: AtomicU64,
+	sends: AtomicU64,
+	send_time_ns: AtomicU64,
+	last_send_latency_ns: AtomicU64,
+	connected: AtomicBool,
+}
+
+/// The health of an exporter, updated by the exporter and shared with the node.
+#[derive(Debug, Clone)]
+pub struct ExporterHealth {
+	inner: Arc<Inner>,
+}
+
+/// A snapshot of the health of an exporter.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExporterStatus {
+	/// Name of the exporter, e.g. `loki` or `telemetry <address>`.
+	pub name: String,
+	/// Number of records waiting to be exported.
+	pub queued: u64,
+	/// Number of records exported.
+	pub sent: u64,
+	/// Number of records dropped, e.g. because the queue was full or the export failed.
+	pub dropped: u64,
+	/// Number of times the exporter reconnected to its endpoint.
+	pub reconnects: u64,
+	/// Number of sends, a send exporting one or more records.
+	pub sends: u64,
+	/// Total time spent sending.
+	pub send_time: Duration,
+	/// Time the last send took, if any.
+	pub last_send_latency: Option<Duration>,
+	/// Whether the exporter is connected to its endpoint, for the exporters keeping a connection.
+	pub connected: bool,
+}
+
+impl ExporterHealth {
+	/// Create the health of the exporter `name`, exposed by [`exporters`] until it is dropped.
+	pub fn new(name: impl Into<String>) -> Self {
+		let inner = Arc::new(Inner { name: name.into(), ..Default::default() });
+		let mut exporters = EXPORTERS.lock();
+		exporters.retain(|exporter| exporter.strong_count() > 0);
+		exporters.push(Arc::downgrade(&inner));
+		ExporterHealth { inner }
+	}
+
+	/// Record `count` records added to the queue of the exporter.
+	pub fn record_queued(&self, count: u64) {
+		self.inner.queued.fetch_add(count, Ordering::Relaxed);
+	}
+
+	/// Record `count` records taken from the queue of the exporter, to be sent or dropped.
+	pub fn record_dequeued(&self, count: u64) {
+		let _ = self.inner.queued.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |queued| {
+			Some(queued.saturating_sub(count))
+		});
+	}
+
+	/// Record a send of `count` records, which took `latency`.
+	pub fn record_sent(&self, count: u64, latency: Duration) {
+		let latency = latency.as_nanos() as u64;
+		self.inner.sent.fetch_add(count, Ordering::Relaxed);
+		self.inner.sends.fetch_add(1, Ordering::Relaxed);
+		self.inner.send_time_ns.fetch_add(latency, Ordering::Relaxed);
+		// 0 stands for no send, so that the sends of less than a nanosecond are still reported.
+		self.inner.last_send_latency_ns.store(latency.max(1), Ordering::Relaxed);
+	}
+
+	/// Record `count` records dropped.
+	pub fn record_dropped(&self, count: u64) {
+		self.inner.dropped.fetch_add(count, Ordering::Relaxed);
+	}
+
+	/// Record a reconnection to the endpoint of the exporter.
+	pub fn record_reconnect(&self) {
+		self.inner.reconnects.fetch_add(1, Ordering::Relaxed);
+	}
+
+	/// Record the exporter connecting to or disconnecting from its endpoint.
+	pub fn record_connected(&self, connected: bool) {
+		self.inner.connected.store(connected, Ordering::Relaxed);
+	}
+
+	/// The current health of the exporter.
+	pub fn status(&self) -> ExporterStatus {
+		self.inner.status()
+	}
+}
+
+impl Inner {
+	fn status(&self) -> ExporterStatus {
+		let last_send_latency = self.last_send_latency_ns.load(Ordering::Relaxed);
+		ExporterStatus {
+			name: self.name.clone(),
+			queued: self.queued.load(Ordering::Relaxed),
+			sent: self.sent.load(Ordering::Relaxed),
+			dropped: self.dropped.load(Ordering::Relaxed),
+			reconnects: self.reconnects.load(Ordering::Relaxed),
+			sends: self.sends.load(Ordering::Relaxed),
+			send_time: Duration::from_nanos(self.send_time_ns.load(Ordering::Relaxed)),
+			last_send_latency: if last_send_latency > 0 {
+				Some(Duration::from_nanos(last_send_latency))
+			} else {
+				None
+			},
+			connected: self.connected.load(Ordering::Relaxed),
+		}
+	}
+}
+
+/// The health of the exporters alive, in the order they were created in.
+pub fn exporters() -> Vec<ExporterStatus> {
+	let mut exporters = EXPORTERS.lock();
+	exporters.retain(|exporter| exporter.strong_count() > 0);
+	exporters.iter().filter_map(Weak::upgrade).map(|exporter| exporter.status()).collect()
+}
+
+/// Whether the node is connected to at least one of the telemetry endpoints.
+pub fn telemetry_connected() -> bool {
+	exporters().iter().any(|exporter| exporter.name.starts_with(TELEMETRY_EXPORTER) && exporter.connected)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn exporters_are_reported_until_dropped() {
+		let health = ExporterHealth::new("test-exporter");
+		health.record_queued(3);
+		health.record_dequeued(2);
+		health.record_sent(1, Duration::from_millis(20));
+		health.record_sent(1, Duration::from_millis(10));
+		health.record_dropped(1);
+		health.record_reconnect();
+		health.record_connected(true);
+
+		let status = exporters().into_iter().find(|status| status.name == "test-exporter").unwrap();
+		assert_eq!(status, ExporterStatus {
+			name: "test-exporter".into(),
+			queued: 1,
+			sent: 2,
+			dropped: 1,
+			reconnects: 1,
+			sends: 2,
+			send_time: Duration::from_millis(30),
+			last_send_latency: Some(Duration::from_millis(10)),
+			connected: true,
+		});
+
+		drop(health);
+		assert!(exporters().iter().all(|status| status.name != "test-exporter"));
+	}
+}
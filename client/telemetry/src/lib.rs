@@ -34,6 +34,9 @@
 //!
 //! > **Note**: Cloning the [`Telemetry`] and polling from multiple clones has an unspecified behaviour.
 //!
+//! The messages can be routed through the `tracing` subscriber of the node before they are sent,
+//! for its filters and redaction to apply to them, see [`route_through_tracing`].
+//!
 //! # Example
 //!
 //! ```no_run
@@ -46,6 +49,8 @@
 //! 	]).expect("Invalid URL or multiaddr provided"),
 //! 	// Can be used to pass an external implementation of WebSockets.
 //! 	wasm_external_transport: None,
+//! 	// Send one WebSocket frame per message.
+//! 	batching: None,
 //! });
 //!
 //! // The `telemetry` object implements `Stream` and must be processed.
@@ -65,7 +70,7 @@ use libp2p::{Multiaddr, wasm_ext};
 use log::{error, warn};
 use parking_lot::Mutex;
 use serde::{Serialize, Deserialize, Deserializer};
-use std::{pin::Pin, sync::Arc, task::{Context, Poll}, time::Duration};
+use std::{pin::Pin, sync::{Arc, Weak}, task::{Context, Poll}, time::Duration};
 use wasm_timer::Instant;
 
 pub use libp2p::wasm_ext::ExtTransport;
@@ -73,8 +78,16 @@ pub use slog_scope::with_logger;
 pub use slog;
 
 mod async_record;
+mod health;
+mod routing;
 mod worker;
 
+pub use health::{ExporterHealth, ExporterStatus, exporters, telemetry_connected};
+pub use routing::{
+	RedactFields, TELEMETRY_TARGET, admit_routed, route_through_tracing, verbosity_level,
+	without_routing,
+};
+
 /// Configuration for telemetry.
 pub struct TelemetryConfig {
 	/// Collection of telemetry WebSocket servers with a corresponding verbosity level.
@@ -91,6 +104,24 @@ pub struct TelemetryConfig {
 	/// >                internal buffering going on. In the context of WebSockets, each `write`
 	/// >                must be one individual WebSockets frame.
 	pub wasm_external_transport: Option<wasm_ext::ExtTransport>,
+
+	/// Send the messages to each endpoint in batches, instead of one WebSocket frame per message.
+	/// `None` to disable the batching.
+	pub batching: Option<TelemetryBatching>,
+}
+
+/// Batching of the telemetry messages sent to an endpoint.
+///
+/// A batch is sent as one WebSocket frame holding its messages as newline-delimited JSON, which
+/// is compressed with gzip if `compression` is set. The endpoints must understand this format.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TelemetryBatching {
+	/// Maximum number of messages in a batch. A full batch is sent right away.
+	pub max_messages: usize,
+	/// How long a batch waits for more messages after its first one, before it is sent.
+	pub flush_interval: Duration,
+	/// Whether to compress the batches.
+	pub compression: bool,
 }
 
 /// List of telemetry servers we want to talk to. Contains the URL of the server, and the
@@ -181,6 +212,11 @@ struct TelemetryInner {
 	receiver: mpsc::Receiver<async_record::AsyncRecord>,
 }
 
+lazy_static::lazy_static! {
+	/// The most recently initialized telemetry, drained by [`flush`].
+	static ref ACTIVE_TELEMETRY: Mutex<Option<Weak<Mutex<TelemetryInner>>>> = Mutex::new(None);
+}
+
 /// Implements `slog::Drain`.
 struct TelemetryDrain {
 	/// Sends log entries.
@@ -193,7 +229,8 @@ struct TelemetryDrain {
 /// doesn't provide any way of knowing whether a global logger has already been registered.
 pub fn init_telemetry(config: TelemetryConfig) -> Telemetry {
 	// Build the list of telemetry endpoints.
-	let (endpoints, wasm_external_transport) = (config.endpoints.0, config.wasm_external_transport);
+	let (endpoints, wasm_external_transport, batching) =
+		(config.endpoints.0, config.wasm_external_transport, config.batching);
 
 	let (sender, receiver) = mpsc::channel(16);
 	let guard = {
@@ -202,7 +239,7 @@ pub fn init_telemetry(config: TelemetryConfig) -> Telemetry {
 		slog_scope::set_global_logger(root)
 	};
 
-	let worker = match worker::TelemetryWorker::new(endpoints, wasm_external_transport) {
+	let worker = match worker::TelemetryWorker::new(endpoints, wasm_external_transport, batching) {
 		Ok(w) => Some(w),
 		Err(err) => {
 			error!(target: "telemetry", "Failed to initialize telemetry worker: {:?}", err);
@@ -210,15 +247,67 @@ pub fn init_telemetry(config: TelemetryConfig) -> Telemetry {
 		}
 	};
 
+	let inner = Arc::new(Mutex::new(TelemetryInner {
+		worker,
+		receiver,
+	}));
+	*ACTIVE_TELEMETRY.lock() = Some(Arc::downgrade(&inner));
+
 	Telemetry {
-		inner: Arc::new(Mutex::new(TelemetryInner {
-			worker,
-			receiver,
-		})),
+		inner,
 		_guard: Arc::new(guard),
 	}
 }
 
+/// Hands all queued messages of the most recently initialized telemetry to its endpoints.
+///
+/// This doesn't block, so messages can only be sent to the endpoints that are currently
+/// connected. It is meant to be called right before the process exits, so that the messages
+/// still waiting in the queue are not lost. Does nothing if the telemetry is being polled
+/// concurrently.
+pub fn flush() {
+	let inner = match ACTIVE_TELEMETRY.lock().as_ref().and_then(Weak::upgrade) {
+		Some(inner) => inner,
+		None => return,
+	};
+	let mut inner = match inner.try_lock() {
+		Some(inner) => inner,
+		None => return,
+	};
+	let waker = futures::task::noop_waker();
+	inner.poll(&mut Context::from_waker(&waker));
+}
+
+impl TelemetryInner {
+	/// Processes the queued messages. Returns `true` if an endpoint has connected.
+	fn poll(&mut self, cx: &mut Context) -> bool {
+		let mut has_connected = false;
+
+		// The polling pattern is: poll the worker so that it processes its queue, then add one
+		// message from the receiver (if possible), then poll the worker again, and so on.
+		loop {
+			if let Some(worker) = self.worker.as_mut() {
+				while let Poll::Ready(event) = worker.poll(cx) {
+					// Right now we only have one possible event. This line is here in order to not
+					// forget to handle any possible new event type.
+					let worker::TelemetryWorkerEvent::Connected = event;
+					has_connected = true;
+				}
+			}
+
+			if let Poll::Ready(Some(log_entry)) = Stream::poll_next(Pin::new(&mut self.receiver), cx) {
+				if let Some(worker) = self.worker.as_mut() {
+					log_entry.as_record_values(|rec, val| { let _ = worker.log(rec, val); });
+				}
+			} else {
+				break;
+			}
+		}
+
+		has_connected
+	}
+}
+
 /// Event generated when polling the worker.
 #[derive(Debug)]
 pub enum TelemetryEvent {
@@ -252,28 +341,7 @@ impl Stream for Telemetry {
 			}
 		};
 
-		let mut has_connected = false;
-
-		// The polling pattern is: poll the worker so that it processes its queue, then add one
-		// message from the receiver (if possible), then poll the worker again, and so on.
-		loop {
-			if let Some(worker) = inner.worker.as_mut() {
-				while let Poll::Ready(event) = worker.poll(cx) {
-					// Right now we only have one possible event. This line is here in order to not
-					// forget to handle any possible new event type.
-					let worker::TelemetryWorkerEvent::Connected = event;
-					has_connected = true;
-				}
-			}
-
-			if let Poll::Ready(Some(log_entry)) = Stream::poll_next(Pin::new(&mut inner.receiver), cx) {
-				if let Some(worker) = inner.worker.as_mut() {
-					log_entry.as_record_values(|rec, val| { let _ = worker.log(rec, val); });
-				}
-			} else {
-				break;
-			}
-		}
+		let has_connected = inner.poll(cx);
 
 		if before.elapsed() > Duration::from_millis(200) {
 			warn!(target: "telemetry", "Polling the telemetry took more than 200ms");
@@ -295,6 +363,12 @@ impl slog::Drain for TelemetryDrain {
 		let before = Instant::now();
 
 		let serialized = async_record::AsyncRecord::from(record, values);
+		let (verbosity, id) = (serialized.verbosity(), serialized.msg().to_owned());
+		let serialized = match routing::route(serialized, verbosity, &id) {
+			Some(serialized) => serialized,
+			// Not admitted by the subscriber.
+			None => return Ok(()),
+		};
 		// Note: interestingly, `try_send` requires a `&mut` because it modifies some internal value, while `clone()`
 		// is lock-free.
 		if let Err(err) = self.sender.clone().try_send(serialized) {
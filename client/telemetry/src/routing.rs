@@ -0,0 +1,276 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2017-2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Routing of the telemetry messages through the `tracing` subscriber.
+//!
+//! Once [`route_through_tracing`] is called, every message of the `telemetry!` macro is emitted
+//! as an event of the [`TELEMETRY_TARGET`], at the level of its verbosity, see
+//! [`verbosity_level`], before it is sent. The message is only sent to the endpoints if the
+//! subscriber admits it through [`admit_routed`], e.g. after filtering it by target and level
+//! and redacting its fields, on the thread emitting the event.
+
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use slog::{Key, Serializer};
+
+use crate::async_record::AsyncRecord;
+
+/// Target of the events of the telemetry messages routed through the `tracing` subscriber.
+pub const TELEMETRY_TARGET: &str = "telemetry";
+
+static ROUTED: AtomicBool = AtomicBool::new(false);
+
+enum Routed {
+	/// The message of the event being emitted, not admitted yet.
+	Pending(AsyncRecord),
+	/// The message admitted by the subscriber, redacted.
+	Admitted(AsyncRecord),
+}
+
+thread_local! {
+	static ROUTING: RefCell<Option<Routed>> = RefCell::new(None);
+	static DIRECT: Cell<bool> = Cell::new(false);
+}
+
+/// Route the telemetry messages through the global `tracing` subscriber, which has to admit them
+/// with [`admit_routed`] for them to be sent.
+pub fn route_through_tracing() {
+	ROUTED.store(true, Ordering::Relaxed);
+}
+
+/// Send the telemetry messages of `f` directly to the endpoints.
+///
+/// The subscriber can't be notified of events from within its own callbacks, so it has to send
+/// its own messages, e.g. the traces of the telemetry receiver, directly.
+pub fn without_routing<R>(f: impl FnOnce() -> R) -> R {
+	struct Restore(bool);
+	impl Drop for Restore {
+		fn drop(&mut self) {
+			DIRECT.with(|direct| direct.set(self.0));
+		}
+	}
+	let _restore = Restore(DIRECT.with(|direct| direct.replace(true)));
+	f()
+}
+
+/// The level of the events of the messages of `verbosity`, keeping their order: `0` and `1`, e.g.
+/// `SUBSTRATE_INFO` and `CONSENSUS_INFO`, are `INFO`, up to `5`, e.g. `CONSENSUS_DEBUG`, are
+/// `DEBUG`, and above, e.g. `SUBSTRATE_DEBUG`, are `TRACE`.
+pub fn verbosity_level(verbosity: u8) -> tracing::Level {
+	match verbosity {
+		0..=1 => tracing::Level::INFO,
+		2..=5 => tracing::Level::DEBUG,
+		_ => tracing::Level::TRACE,
+	}
+}
+
+/// Redaction of the fields of the routed messages, see [`admit_routed`].
+pub trait RedactFields {
+	/// Whether the field `key` is redacted.
+	fn is_redacted(&self, key: &str) -> bool;
+	/// The redacted `value` of the field `key`, or `None` to drop the field.
+	fn redact(&self, key: &str, value: String) -> Option<String>;
+}
+
+/// Admit the message of the event of the [`TELEMETRY_TARGET`] being emitted on this thread, with
+/// its fields redacted by `redaction`. Returns whether there was a message to admit.
+pub fn admit_routed(redaction: &dyn RedactFields) -> bool {
+	ROUTING.with(|routing| {
+		let mut routing = routing.borrow_mut();
+		match routing.take() {
+			Some(Routed::Pending(record)) => {
+				*routing = Some(Routed::Admitted(record.redacted(redaction)));
+				true
+			},
+			other => {
+				*routing = other;
+				false
+			},
+		}
+	})
+}
+
+/// Emit the message `record` as an event, returning it if it is to be sent: when the messages are
+/// not routed, or when the subscriber admits it.
+pub(crate) fn route(record: AsyncRecord, verbosity: u8, id: &str) -> Option<AsyncRecord> {
+	if !ROUTED.load(Ordering::Relaxed) || DIRECT.with(|direct| direct.get()) {
+		return Some(record);
+	}
+	ROUTING.with(|routing| *routing.borrow_mut() = Some(Routed::Pending(record)));
+	let level = verbosity_level(verbosity);
+	let verbosity = verbosity as u64;
+	match level {
+		tracing::Level::INFO => tracing::info!(target: TELEMETRY_TARGET, verbosity, id),
+		tracing::Level::DEBUG => tracing::debug!(target: TELEMETRY_TARGET, verbosity, id),
+		_ => tracing::trace!(target: TELEMETRY_TARGET, verbosity, id),
+	}
+	match ROUTING.with(|routing| routing.borrow_mut().take()) {
+		Some(Routed::Admitted(record)) => Some(record),
+		_ => None,
+	}
+}
+
+/// Serializes the fields of a record, redacting them first.
+pub(crate) struct RedactingSerializer<'a, S> {
+	pub inner: S,
+	pub redaction: &'a dyn RedactFields,
+}
+
+macro_rules! emit {
+	($($method:ident: $ty:ty),*) => {
+		$(
+			fn $method(&mut self, key: Key, val: $ty) -> slog::Result {
+				if self.redaction.is_redacted(key) {
+					self.emit_redacted(key, val.to_string())
+				} else {
+					self.inner.$method(key, val)
+				}
+			}
+		)*
+	}
+}
+
+impl<'a, S: Serializer> RedactingSerializer<'a, S> {
+	fn emit_redacted(&mut self, key: Key, val: String) -> slog::Result {
+		match self.redaction.redact(key, val) {
+			Some(val) => self.inner.emit_str(key, &val),
+			None => Ok(()),
+		}
+	}
+}
+
+impl<'a, S: Serializer> Serializer for RedactingSerializer<'a, S> {
+	emit!(
+		emit_bool: bool, emit_char: char, emit_u8: u8, emit_i8: i8, emit_u16: u16, emit_i16: i16,
+		emit_u32: u32, emit_i32: i32, emit_f32: f32, emit_u64: u64, emit_i64: i64, emit_f64: f64,
+		emit_usize: usize, emit_isize: isize, emit_str: &str
+	);
+
+	fn emit_unit(&mut self, key: Key) -> slog::Result {
+		if self.redaction.is_redacted(key) {
+			self.emit_redacted(key, "()".to_owned())
+		} else {
+			self.inner.emit_unit(key)
+		}
+	}
+
+	fn emit_none(&mut self, key: Key) -> slog::Result {
+		if self.redaction.is_redacted(key) {
+			self.emit_redacted(key, "None".to_owned())
+		} else {
+			self.inner.emit_none(key)
+		}
+	}
+
+	fn emit_arguments(&mut self, key: Key, val: &fmt::Arguments) -> slog::Result {
+		if self.redaction.is_redacted(key) {
+			self.emit_redacted(key, fmt::format(*val))
+		} else {
+			self.inner.emit_arguments(key, val)
+		}
+	}
+
+	fn emit_serde(&mut self, key: Key, value: &dyn slog::SerdeValue) -> slog::Result {
+		if self.redaction.is_redacted(key) {
+			let val = serde_json::to_string(value.as_serde()).unwrap_or_default();
+			self.emit_redacted(key, val)
+		} else {
+			self.inner.emit_serde(key, value)
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use futures::channel::mpsc;
+	use slog::{Drain, KV};
+	use tracing::{span, Event, Metadata, Subscriber};
+
+	/// Admits the messages up to `INFO`, dropping their `sender`.
+	struct Admitting;
+
+	impl RedactFields for Admitting {
+		fn is_redacted(&self, key: &str) -> bool {
+			key == "sender"
+		}
+
+		fn redact(&self, _key: &str, _value: String) -> Option<String> {
+			None
+		}
+	}
+
+	impl Subscriber for Admitting {
+		fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+			metadata.target() == TELEMETRY_TARGET && *metadata.level() <= tracing::Level::INFO
+		}
+
+		fn new_span(&self, _span: &span::Attributes<'_>) -> span::Id {
+			span::Id::from_u64(1)
+		}
+
+		fn record(&self, _span: &span::Id, _values: &span::Record<'_>) {}
+
+		fn record_follows_from(&self, _span: &span::Id, _follows: &span::Id) {}
+
+		fn event(&self, _event: &Event<'_>) {
+			admit_routed(self);
+		}
+
+		fn enter(&self, _span: &span::Id) {}
+
+		fn exit(&self, _span: &span::Id) {}
+	}
+
+	struct Fields(Vec<String>);
+
+	impl Serializer for Fields {
+		fn emit_arguments(&mut self, key: Key, val: &fmt::Arguments) -> slog::Result {
+			self.0.push(format!("{}={}", key, val));
+			Ok(())
+		}
+	}
+
+	#[test]
+	fn routes_the_messages_through_the_subscriber() {
+		let (sender, mut receiver) = mpsc::channel(16);
+		let drain = crate::TelemetryDrain { sender: std::panic::AssertUnwindSafe(sender) };
+		let logger = slog::Logger::root(drain.fuse(), slog::o!());
+		route_through_tracing();
+		tracing::subscriber::with_default(Admitting, || {
+			slog::slog_info!(logger, #"0", "admitted"; "sender" => "alice", "best" => 7);
+			slog::slog_info!(logger, #"9", "filtered"; "best" => 7);
+			without_routing(|| slog::slog_info!(logger, #"9", "direct"; "best" => 7));
+		});
+
+		let mut received = Vec::new();
+		while let Ok(Some(record)) = receiver.try_next() {
+			record.as_record_values(|record, _| {
+				let mut fields = Fields(Vec::new());
+				record.kv().serialize(record, &mut fields).unwrap();
+				received.push((record.msg().to_string(), fields.0));
+			});
+		}
+		assert_eq!(received, vec![
+			("admitted".to_owned(), vec!["best=7".to_owned()]),
+			("direct".to_owned(), vec!["best=7".to_owned()]),
+		]);
+	}
+}
@@ -34,6 +34,9 @@ use log::{trace, warn, error};
 use slog::Drain;
 use std::{io, pin::Pin, task::Context, task::Poll, time};
 
+use crate::TelemetryBatching;
+
+mod batch;
 mod node;
 
 /// Timeout after which a connection attempt is considered failed. Includes the WebSocket HTTP
@@ -73,9 +76,12 @@ impl TelemetryWorker {
 	/// The endpoints must be a list of targets, plus a verbosity level. When you send a message
 	/// to the telemetry, only the targets whose verbosity is higher than the verbosity of the
 	/// message will receive it.
+	///
+	/// The messages are sent to each target in batches if `batching` is `Some`.
 	pub fn new(
 		endpoints: impl IntoIterator<Item = (Multiaddr, u8)>,
-		wasm_external_transport: impl Into<Option<wasm_ext::ExtTransport>>
+		wasm_external_transport: impl Into<Option<wasm_ext::ExtTransport>>,
+		batching: Option<TelemetryBatching>,
 	) -> Result<Self, io::Error> {
 		let transport = match wasm_external_transport.into() {
 			Some(t) => OptionalTransport::some(t),
@@ -114,7 +120,7 @@ impl TelemetryWorker {
 
 		Ok(TelemetryWorker {
 			nodes: endpoints.into_iter().map(|(addr, verbosity)| {
-				let node = node::Node::new(transport.clone(), addr);
+				let node = node::Node::new(transport.clone(), addr, batching.clone());
 				(node, verbosity)
 			}).collect()
 		})
@@ -0,0 +1,135 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2017-2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Contains the `Batch` struct, which gathers the messages sent to a telemetry node into a single
+//! WebSocket frame.
+//!
+//! The messages are JSON objects ending with a newline, so that the frame of a batch is the
+//! newline-delimited JSON of its messages, compressed with gzip if configured.
+
+use flate2::{Compression, write::GzEncoder};
+use futures::{prelude::*, ready};
+use futures_timer::Delay;
+use log::warn;
+use std::{io::Write as _, mem, pin::Pin, task::Context, task::Poll};
+
+use crate::TelemetryBatching;
+
+/// Messages waiting to be sent to a node as a single frame.
+pub struct Batch {
+	config: TelemetryBatching,
+	/// The messages, one after the other.
+	messages: Vec<u8>,
+	/// Number of messages in `messages`.
+	len: usize,
+	/// Fires once the flush interval has elapsed since the first message of the batch.
+	flush: Option<Delay>,
+}
+
+impl Batch {
+	/// Builds a new empty batch.
+	pub fn new(config: TelemetryBatching) -> Self {
+		Batch {
+			config,
+			messages: Vec::new(),
+			len: 0,
+			flush: None,
+		}
+	}
+
+	/// Adds a message to the batch. Returns the frame of the batch once it holds the maximum
+	/// number of messages.
+	pub fn push(&mut self, message: &[u8]) -> Option<Vec<u8>> {
+		self.messages.extend_from_slice(message);
+		self.len += 1;
+		if self.flush.is_none() {
+			self.flush = Some(Delay::new(self.config.flush_interval));
+		}
+		if self.len >= self.config.max_messages {
+			self.take()
+		} else {
+			None
+		}
+	}
+
+	/// Returns the frame of the batch once the flush interval has elapsed.
+	pub fn poll(&mut self, cx: &mut Context) -> Poll<Vec<u8>> {
+		if let Some(flush) = self.flush.as_mut() {
+			ready!(Future::poll(Pin::new(flush), cx));
+			if let Some(frame) = self.take() {
+				return Poll::Ready(frame)
+			}
+		}
+		Poll::Pending
+	}
+
+	/// Empties the batch, returning its frame if it isn't empty.
+	pub fn take(&mut self) -> Option<Vec<u8>> {
+		self.flush = None;
+		if self.len == 0 {
+			return None
+		}
+		self.len = 0;
+		let messages = mem::take(&mut self.messages);
+		if !self.config.compression {
+			return Some(messages)
+		}
+
+		let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+		match encoder.write_all(&messages).and_then(|()| encoder.finish()) {
+			Ok(frame) => Some(frame),
+			Err(err) => {
+				warn!(target: "telemetry", "Failed to compress telemetry batch: {:?}", err);
+				None
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use flate2::read::GzDecoder;
+	use std::{io::Read as _, time::Duration};
+
+	fn batch(compression: bool) -> Batch {
+		Batch::new(TelemetryBatching {
+			max_messages: 2,
+			flush_interval: Duration::from_secs(60),
+			compression,
+		})
+	}
+
+	#[test]
+	fn full_batch_is_one_frame() {
+		let mut batch = batch(false);
+		assert_eq!(batch.push(b"{\"msg\":\"a\"}\n"), None);
+		assert_eq!(batch.push(b"{\"msg\":\"b\"}\n"), Some(b"{\"msg\":\"a\"}\n{\"msg\":\"b\"}\n".to_vec()));
+		assert_eq!(batch.take(), None);
+	}
+
+	#[test]
+	fn frames_are_compressed() {
+		let mut batch = batch(true);
+		assert_eq!(batch.push(b"{\"msg\":\"a\"}\n"), None);
+		let frame = batch.take().expect("the batch holds a message");
+		let mut messages = String::new();
+		GzDecoder::new(&frame[..]).read_to_string(&mut messages).unwrap();
+		assert_eq!(messages, "{\"msg\":\"a\"}\n");
+	}
+}
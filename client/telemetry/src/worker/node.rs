@@ -25,6 +25,10 @@ use libp2p::core::transport::Transport;
 use log::{trace, debug, warn, error};
 use rand::Rng as _;
 use std::{collections::VecDeque, fmt, mem, pin::Pin, task::Context, task::Poll, time::Duration};
+use wasm_timer::Instant;
+
+use super::batch::Batch;
+use crate::{ExporterHealth, TelemetryBatching, health::TELEMETRY_EXPORTER};
 
 /// Maximum number of pending telemetry messages.
 const MAX_PENDING: usize = 10;
@@ -37,6 +41,10 @@ pub struct Node<TTrans: Transport> {
 	socket: NodeSocket<TTrans>,
 	/// Transport used to establish new connections.
 	transport: TTrans,
+	/// Messages waiting to be sent as a single frame, if batching.
+	batch: Option<Batch>,
+	/// Health of the connection, the frames being its records.
+	health: ExporterHealth,
 }
 
 enum NodeSocket<TTrans: Transport> {
@@ -61,6 +69,8 @@ struct NodeSocketConnected<TTrans: Transport> {
 	need_flush: bool,
 	/// A timeout for the socket to write data.
 	timeout: Option<Delay>,
+	/// Number of packets sent since the last flush, and when the first of them was sent.
+	unflushed: Option<(u64, Instant)>,
 }
 
 /// Event that can happen with this node.
@@ -85,12 +95,14 @@ pub enum ConnectionError<TSinkErr> {
 }
 
 impl<TTrans: Transport> Node<TTrans> {
-	/// Builds a new node handler.
-	pub fn new(transport: TTrans, addr: Multiaddr) -> Self {
+	/// Builds a new node handler. The messages are sent in batches if `batching` is `Some`.
+	pub fn new(transport: TTrans, addr: Multiaddr, batching: Option<TelemetryBatching>) -> Self {
 		Node {
+			health: ExporterHealth::new(format!("{}{}", TELEMETRY_EXPORTER, addr)),
 			addr,
 			socket: NodeSocket::ReconnectNow,
 			transport,
+			batch: batching.map(Batch::new),
 		}
 	}
 
@@ -107,21 +119,22 @@ where TTrans: Clone + Unpin, TTrans::Dial: Unpin,
 		+ Unpin,
 	TSinkErr: fmt::Debug
 {
-	/// Sends a WebSocket frame to the node. Returns an error if we are not connected to the node.
+	/// Sends a WebSocket frame to the node, or adds the message to the batch of the next one.
+	/// Returns an error if we are not connected to the node.
 	///
 	/// After calling this method, you should call `poll` in order for it to be properly processed.
 	pub fn send_message(&mut self, payload: impl Into<Vec<u8>>) -> Result<(), ()> {
 		if let NodeSocket::Connected(NodeSocketConnected { pending, .. }) = &mut self.socket {
-			if pending.len() <= MAX_PENDING {
-				trace!(target: "telemetry", "Adding log entry to queue for {:?}", self.addr);
-				pending.push_back(payload.into());
-				Ok(())
-			} else {
-				warn!(target: "telemetry", "⚠️  Rejected log entry because queue is full for {:?}",
-					self.addr);
-				Err(())
-			}
+			let payload = match self.batch.as_mut() {
+				Some(batch) => match batch.push(&payload.into()) {
+					Some(frame) => frame,
+					None => return Ok(()),
+				},
+				None => payload.into(),
+			};
+			queue_frame(pending, payload, &self.addr, &self.health)
 		} else {
+			self.health.record_dropped(1);
 			Err(())
 		}
 	}
@@ -132,13 +145,25 @@ where TTrans: Clone + Unpin, TTrans::Dial: Unpin,
 		self.socket = loop {
 			match socket {
 				NodeSocket::Connected(mut conn) => {
-					match NodeSocketConnected::poll(Pin::new(&mut conn), cx, &self.addr) {
+					let node = &mut *self;
+					if let Some(batch) = node.batch.as_mut() {
+						if let Poll::Ready(frame) = batch.poll(cx) {
+							let _ = queue_frame(&mut conn.pending, frame, &node.addr, &node.health);
+						}
+					}
+					match NodeSocketConnected::poll(Pin::new(&mut conn), cx, &self.addr, &self.health) {
 						Poll::Ready(Ok(v)) => match v {},
 						Poll::Pending => {
 							break NodeSocket::Connected(conn)
 						},
 						Poll::Ready(Err(err)) => {
 							warn!(target: "telemetry", "⚠️  Disconnected from {}: {:?}", self.addr, err);
+							// The batched messages are lost, as the queued ones.
+							let batched = self.batch.as_mut().and_then(|batch| batch.take()).is_some();
+							let lost = conn.pending.len() as u64;
+							self.health.record_dequeued(lost);
+							self.health.record_dropped(lost + batched as u64);
+							self.health.record_connected(false);
 							let timeout = gen_rand_reconnect_delay();
 							self.socket = NodeSocket::WaitingReconnect(timeout);
 							return Poll::Ready(NodeEvent::Disconnected(err))
@@ -153,8 +178,10 @@ where TTrans: Clone + Unpin, TTrans::Dial: Unpin,
 							pending: VecDeque::new(),
 							need_flush: false,
 							timeout: None,
+							unflushed: None,
 						};
 						self.socket = NodeSocket::Connected(conn);
+						self.health.record_connected(true);
 						return Poll::Ready(NodeEvent::Connected)
 					},
 					Poll::Pending => break NodeSocket::Dialing(s),
@@ -177,6 +204,7 @@ where TTrans: Clone + Unpin, TTrans::Dial: Unpin,
 				}
 				NodeSocket::WaitingReconnect(mut s) =>
 					if let Poll::Ready(_) = Future::poll(Pin::new(&mut s), cx) {
+						self.health.record_reconnect();
 						socket = NodeSocket::ReconnectNow;
 					} else {
 						break NodeSocket::WaitingReconnect(s)
@@ -192,6 +220,25 @@ where TTrans: Clone + Unpin, TTrans::Dial: Unpin,
 	}
 }
 
+/// Adds a frame to the queue of packets of a connected node, unless the queue is full.
+fn queue_frame(
+	pending: &mut VecDeque<Vec<u8>>,
+	frame: Vec<u8>,
+	addr: &Multiaddr,
+	health: &ExporterHealth,
+) -> Result<(), ()> {
+	if pending.len() <= MAX_PENDING {
+		trace!(target: "telemetry", "Adding log entry to queue for {:?}", addr);
+		pending.push_back(frame);
+		health.record_queued(1);
+		Ok(())
+	} else {
+		warn!(target: "telemetry", "⚠️  Rejected log entry because queue is full for {:?}", addr);
+		health.record_dropped(1);
+		Err(())
+	}
+}
+
 /// Generates a `Delay` object with a random timeout.
 ///
 /// If there are general connection issues, not all endpoints should be synchronized in their
@@ -208,11 +255,12 @@ where TTrans::Output: Sink<Vec<u8>, Error = TSinkErr>
 {
 	/// Processes the queue of messages for the connected socket.
 	///
-	/// The address is passed for logging purposes only.
+	/// The address is passed for logging purposes only, and the sends are recorded in `health`.
 	fn poll(
 		mut self: Pin<&mut Self>,
 		cx: &mut Context,
 		my_addr: &Multiaddr,
+		health: &ExporterHealth,
 	) -> Poll<Result<futures::never::Never, ConnectionError<TSinkErr>>> {
 
 		while let Some(item) = self.pending.pop_front() {
@@ -229,6 +277,9 @@ where TTrans::Output: Sink<Vec<u8>, Error = TSinkErr>
 					target: "telemetry", "Successfully sent {:?} bytes message to {}",
 					item_len, my_addr
 				);
+				health.record_dequeued(1);
+				let unflushed = self.unflushed.get_or_insert_with(|| (0, Instant::now()));
+				unflushed.0 += 1;
 				self.need_flush = true;
 
 			} else {
@@ -254,6 +305,9 @@ where TTrans::Output: Sink<Vec<u8>, Error = TSinkErr>
 				Poll::Ready(Ok(())) => {
 					self.timeout = None;
 					self.need_flush = false;
+					if let Some((sent, started)) = self.unflushed.take() {
+						health.record_sent(sent, started.elapsed());
+					}
 				},
 			}
 		}
@@ -0,0 +1,649 @@
+// Copyright 2019-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Compact binary format of the traces recorded by `sc-tracing`.
+//!
+//! A trace is written with a [`TraceWriter`] and read back, possibly while it is still being
+//! written, with a [`TraceReader`].
+//!
+//! # Format
+//!
+//! A trace starts with the magic bytes `SCTR` and the version of the format, as a byte. It is
+//! followed by a stream of frames, each made of a kind byte, the SCALE compact encoded length of
+//! its payload and the payload:
+//!
+//! - `0`, a string of the dictionary: its id, compact encoded, and the string. Targets, names and
+//!   field names are written once, then referred to by id.
+//! - `1`, a span: its id and the id of its parent, `0` for none, its target and name, its level,
+//!   its start, its duration in nanoseconds and its fields.
+//! - `2`, an event: the id of its span, its target and name, its level, its time and its fields.
+//!
+//! Times are in microseconds since the UNIX epoch, written as the difference with the previous
+//! time of the trace.
+//!
+//! Frames are at most [`MAX_FRAME_LEN`] bytes long, so that readers don't allocate whatever
+//! length a corrupted trace claims.
+//!
+//! # JSON
+//!
+//! A trace can be converted to JSON Lines, one object per record, with [`write_json`], e.g. for
+//! the tools that don't read the binary format.
+//!
+//! # Evolution
+//!
+//! Readers skip the frames of unknown kinds, and ignore the bytes of a payload that follow the
+//! fields they know of. Minor changes, new kinds of frames or new fields at the end of a payload,
+//! keep the version unchanged, while other changes increment it, which readers of older versions
+//! refuse.
+
+use std::io::{self, Read, Write};
+use codec::{Compact, Decode, Encode};
+use rustc_hash::FxHashMap;
+use serde::{Serialize, Serializer};
+
+/// Magic bytes starting a trace.
+pub const MAGIC: &[u8; 4] = b"SCTR";
+
+/// Current version of the format.
+pub const VERSION: u8 = 1;
+
+/// Maximum length of the payload of a frame, beyond which the frame is refused.
+pub const MAX_FRAME_LEN: u32 = 16 * 1024 * 1024;
+
+const FRAME_STRING: u8 = 0;
+const FRAME_SPAN: u8 = 1;
+const FRAME_EVENT: u8 = 2;
+
+/// Trace format error.
+#[derive(Debug, derive_more::Display, derive_more::From)]
+pub enum Error {
+	/// IO error.
+	Io(io::Error),
+	/// A frame couldn't be decoded.
+	#[display(fmt="Invalid frame: {}", "_0.what()")]
+	Codec(codec::Error),
+	/// The input doesn't start with the magic bytes.
+	#[display(fmt="Not a trace")]
+	InvalidMagic,
+	/// The trace was written with a newer version of the format.
+	#[display(fmt="Unsupported trace format version {}", "_0")]
+	UnsupportedVersion(u8),
+	/// A record refers to a string missing from the dictionary.
+	#[display(fmt="Unknown string {}", "_0")]
+	UnknownString(u32),
+	/// A frame is longer than [`MAX_FRAME_LEN`].
+	#[display(fmt="Frame of {} bytes exceeds the maximum length", "_0")]
+	FrameTooLarge(u64),
+}
+
+impl std::error::Error for Error {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		match self {
+			Error::Io(ref err) => Some(err),
+			_ => None,
+		}
+	}
+}
+
+/// Trace format result.
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Level of a span or event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Encode, Decode, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Level {
+	/// Error level.
+	Error,
+	/// Warn level.
+	Warn,
+	/// Info level.
+	Info,
+	/// Debug level.
+	Debug,
+	/// Trace level.
+	Trace,
+}
+
+/// Value of a field.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(untagged)]
+pub enum Value {
+	/// A boolean.
+	Bool(bool),
+	/// A signed integer.
+	I64(i64),
+	/// An unsigned integer.
+	U64(u64),
+	/// A string.
+	Str(String),
+}
+
+/// A closed span.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Span {
+	/// Id of the span.
+	pub id: u64,
+	/// Id of the parent span, if any.
+	pub parent_id: Option<u64>,
+	/// Target of the span.
+	pub target: String,
+	/// Name of the span.
+	pub name: String,
+	/// Level of the span.
+	pub level: Level,
+	/// Time the span was entered, in microseconds since the UNIX epoch.
+	pub start_us: u64,
+	/// Time spent in the span, in nanoseconds.
+	pub duration_ns: u64,
+	/// Fields recorded by the span.
+	#[serde(serialize_with = "serialize_fields")]
+	pub fields: Vec<(String, Value)>,
+}
+
+/// An event.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Event {
+	/// Id of the span of the event, if any.
+	pub parent_id: Option<u64>,
+	/// Target of the event.
+	pub target: String,
+	/// Name of the event.
+	pub name: String,
+	/// Level of the event.
+	pub level: Level,
+	/// Time of the event, in microseconds since the UNIX epoch.
+	pub time_us: u64,
+	/// Fields of the event.
+	#[serde(serialize_with = "serialize_fields")]
+	pub fields: Vec<(String, Value)>,
+}
+
+/// A record of a trace.
+///
+/// Serialized as an object with a `kind`, `span` or `event`, and the fields of the record.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum Record {
+	/// A span.
+	Span(Span),
+	/// An event.
+	Event(Event),
+}
+
+/// The fields are serialized as an object, rather than as a list of pairs.
+fn serialize_fields<S: Serializer>(fields: &[(String, Value)], serializer: S) -> std::result::Result<S::Ok, S::Error> {
+	serializer.collect_map(fields.iter().map(|(name, value)| (name, value)))
+}
+
+/// Value of a field, as written.
+#[derive(Encode, Decode)]
+enum WireValue {
+	Bool(bool),
+	/// Zigzag encoded.
+	I64(Compact<u64>),
+	U64(Compact<u64>),
+	Str(String),
+}
+
+#[derive(Encode, Decode)]
+struct WireSpan {
+	id: Compact<u64>,
+	parent_id: Compact<u64>,
+	target: Compact<u32>,
+	name: Compact<u32>,
+	level: Level,
+	/// Zigzag encoded difference with the previous time.
+	start: Compact<u64>,
+	duration_ns: Compact<u64>,
+	fields: Vec<(Compact<u32>, WireValue)>,
+}
+
+#[derive(Encode, Decode)]
+struct WireEvent {
+	parent_id: Compact<u64>,
+	target: Compact<u32>,
+	name: Compact<u32>,
+	level: Level,
+	/// Zigzag encoded difference with the previous time.
+	time: Compact<u64>,
+	fields: Vec<(Compact<u32>, WireValue)>,
+}
+
+fn zigzag(v: i64) -> u64 {
+	((v << 1) ^ (v >> 63)) as u64
+}
+
+fn unzigzag(v: u64) -> i64 {
+	(v >> 1) as i64 ^ -((v & 1) as i64)
+}
+
+/// Writes records to a trace.
+pub struct TraceWriter<W> {
+	inner: W,
+	strings: FxHashMap<String, u32>,
+	last_time_us: u64,
+	buffer: Vec<u8>,
+}
+
+impl<W: Write> TraceWriter<W> {
+	/// Start a trace, writing its header to `inner`.
+	///
+	/// `inner` should be buffered, every record being written separately.
+	pub fn new(mut inner: W) -> Result<Self> {
+		inner.write_all(MAGIC)?;
+		inner.write_all(&[VERSION])?;
+		Ok(TraceWriter {
+			inner,
+			strings: Default::default(),
+			last_time_us: 0,
+			buffer: Vec::new(),
+		})
+	}
+
+	/// Write a record.
+	pub fn write(&mut self, record: &Record) -> Result<()> {
+		match record {
+			Record::Span(span) => {
+				let span = WireSpan {
+					id: Compact(span.id),
+					parent_id: Compact(span.parent_id.unwrap_or(0)),
+					target: Compact(self.intern(&span.target)?),
+					name: Compact(self.intern(&span.name)?),
+					level: span.level,
+					start: Compact(self.time_delta(span.start_us)),
+					duration_ns: Compact(span.duration_ns),
+					fields: self.wire_fields(&span.fields)?,
+				};
+				self.write_frame(FRAME_SPAN, &span)
+			},
+			Record::Event(event) => {
+				let event = WireEvent {
+					parent_id: Compact(event.parent_id.unwrap_or(0)),
+					target: Compact(self.intern(&event.target)?),
+					name: Compact(self.intern(&event.name)?),
+					level: event.level,
+					time: Compact(self.time_delta(event.time_us)),
+					fields: self.wire_fields(&event.fields)?,
+				};
+				self.write_frame(FRAME_EVENT, &event)
+			},
+		}
+	}
+
+	/// Flush the underlying writer.
+	pub fn flush(&mut self) -> Result<()> {
+		Ok(self.inner.flush()?)
+	}
+
+	/// Returns the underlying writer.
+	pub fn into_inner(self) -> W {
+		self.inner
+	}
+
+	fn intern(&mut self, s: &str) -> Result<u32> {
+		if let Some(id) = self.strings.get(s) {
+			return Ok(*id);
+		}
+		let id = self.strings.len() as u32;
+		self.write_frame(FRAME_STRING, &(Compact(id), s))?;
+		self.strings.insert(s.to_owned(), id);
+		Ok(id)
+	}
+
+	fn time_delta(&mut self, time_us: u64) -> u64 {
+		let delta = time_us.wrapping_sub(self.last_time_us) as i64;
+		self.last_time_us = time_us;
+		zigzag(delta)
+	}
+
+	fn wire_fields(&mut self, fields: &[(String, Value)]) -> Result<Vec<(Compact<u32>, WireValue)>> {
+		fields.iter()
+			.map(|(name, value)| {
+				let value = match value {
+					Value::Bool(v) => WireValue::Bool(*v),
+					Value::I64(v) => WireValue::I64(Compact(zigzag(*v))),
+					Value::U64(v) => WireValue::U64(Compact(*v)),
+					Value::Str(v) => WireValue::Str(v.clone()),
+				};
+				Ok((Compact(self.intern(name)?), value))
+			})
+			.collect()
+	}
+
+	fn write_frame(&mut self, kind: u8, payload: &impl Encode) -> Result<()> {
+		self.buffer.clear();
+		payload.encode_to(&mut self.buffer);
+		if self.buffer.len() > MAX_FRAME_LEN as usize {
+			return Err(Error::FrameTooLarge(self.buffer.len() as u64));
+		}
+		self.inner.write_all(&[kind])?;
+		self.inner.write_all(&Compact(self.buffer.len() as u32).encode())?;
+		Ok(self.inner.write_all(&self.buffer)?)
+	}
+}
+
+/// Reads the records of a trace.
+pub struct TraceReader<R> {
+	inner: R,
+	version: u8,
+	strings: FxHashMap<u32, String>,
+	last_time_us: u64,
+}
+
+impl<R: Read> TraceReader<R> {
+	/// Start reading a trace, checking its header.
+	pub fn new(mut inner: R) -> Result<Self> {
+		let mut header = [0; 5];
+		inner.read_exact(&mut header)?;
+		if &header[..4] != MAGIC {
+			return Err(Error::InvalidMagic);
+		}
+		if header[4] > VERSION {
+			return Err(Error::UnsupportedVersion(header[4]));
+		}
+		Ok(TraceReader {
+			inner,
+			version: header[4],
+			strings: Default::default(),
+			last_time_us: 0,
+		})
+	}
+
+	/// The version of the format the trace was written with.
+	pub fn version(&self) -> u8 {
+		self.version
+	}
+
+	/// Read the next record, `None` at the end of the trace.
+	pub fn read(&mut self) -> Result<Option<Record>> {
+		loop {
+			let mut kind = [0];
+			if self.inner.read(&mut kind)? == 0 {
+				return Ok(None);
+			}
+			let len = <Compact<u32>>::decode(&mut ReadInput(&mut self.inner))?.0;
+			if len > MAX_FRAME_LEN {
+				return Err(Error::FrameTooLarge(len as u64));
+			}
+			// The payload grows as it is read, rather than being allocated upfront.
+			let mut payload = Vec::new();
+			(&mut self.inner).take(len as u64).read_to_end(&mut payload)?;
+			if payload.len() != len as usize {
+				return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+			}
+			let input = &mut &payload[..];
+
+			match kind[0] {
+				FRAME_STRING => {
+					let (id, s) = <(Compact<u32>, String)>::decode(input)?;
+					self.strings.insert(id.0, s);
+				},
+				FRAME_SPAN => {
+					let span = WireSpan::decode(input)?;
+					return Ok(Some(Record::Span(Span {
+						id: span.id.0,
+						parent_id: Some(span.parent_id.0).filter(|id| *id != 0),
+						target: self.string(span.target.0)?,
+						name: self.string(span.name.0)?,
+						level: span.level,
+						start_us: self.time(span.start.0),
+						duration_ns: span.duration_ns.0,
+						fields: self.fields(span.fields)?,
+					})));
+				},
+				FRAME_EVENT => {
+					let event = WireEvent::decode(input)?;
+					return Ok(Some(Record::Event(Event {
+						parent_id: Some(event.parent_id.0).filter(|id| *id != 0),
+						target: self.string(event.target.0)?,
+						name: self.string(event.name.0)?,
+						level: event.level,
+						time_us: self.time(event.time.0),
+						fields: self.fields(event.fields)?,
+					})));
+				},
+				// Frames of a newer minor version.
+				_ => {},
+			}
+		}
+	}
+
+	fn string(&self, id: u32) -> Result<String> {
+		self.strings.get(&id).cloned().ok_or(Error::UnknownString(id))
+	}
+
+	fn time(&mut self, delta: u64) -> u64 {
+		self.last_time_us = self.last_time_us.wrapping_add(unzigzag(delta) as u64);
+		self.last_time_us
+	}
+
+	fn fields(&self, fields: Vec<(Compact<u32>, WireValue)>) -> Result<Vec<(String, Value)>> {
+		fields.into_iter()
+			.map(|(name, value)| {
+				let value = match value {
+					WireValue::Bool(v) => Value::Bool(v),
+					WireValue::I64(v) => Value::I64(unzigzag(v.0)),
+					WireValue::U64(v) => Value::U64(v.0),
+					WireValue::Str(v) => Value::Str(v),
+				};
+				Ok((self.string(name.0)?, value))
+			})
+			.collect()
+	}
+}
+
+impl<R: Read> Iterator for TraceReader<R> {
+	type Item = Result<Record>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		self.read().transpose()
+	}
+}
+
+/// Write `records`, e.g. read by a [`TraceReader`], to `out` as JSON Lines, one object per
+/// record, see [`Record`].
+///
+/// Returns the number of records written.
+pub fn write_json<W: Write>(records: impl IntoIterator<Item = Result<Record>>, mut out: W) -> Result<u64> {
+	let mut written = 0;
+	for record in records {
+		serde_json::to_writer(&mut out, &record?).map_err(io::Error::from)?;
+		out.write_all(b"\n")?;
+		written += 1;
+	}
+	out.flush()?;
+	Ok(written)
+}
+
+/// Adapts a reader to the SCALE `Input`, without requiring it to be seekable.
+struct ReadInput<'a, R>(&'a mut R);
+
+impl<'a, R: Read> codec::Input for ReadInput<'a, R> {
+	fn remaining_len(&mut self) -> std::result::Result<Option<usize>, codec::Error> {
+		Ok(None)
+	}
+
+	fn read(&mut self, into: &mut [u8]) -> std::result::Result<(), codec::Error> {
+		self.0.read_exact(into).map_err(|_| "Unexpected end of trace".into())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn span(id: u64, start_us: u64) -> Span {
+		Span {
+			id,
+			parent_id: Some(1).filter(|_| id != 1),
+			target: "pallet_balances".into(),
+			name: "transfer".into(),
+			level: Level::Info,
+			start_us,
+			duration_ns: 12_345 + id,
+			fields: vec![
+				("amount".into(), Value::U64(1_000_000_000_000)),
+				("keep_alive".into(), Value::Bool(true)),
+				("weight_delta".into(), Value::I64(-(id as i64))),
+			],
+		}
+	}
+
+	fn records() -> Vec<Record> {
+		let mut records = (1..=1000)
+			.map(|id| Record::Span(span(id, 1_602_670_000_000_000 + id * 100)))
+			.collect::<Vec<_>>();
+		records.push(Record::Event(Event {
+			parent_id: None,
+			target: "sudo".into(),
+			name: "event".into(),
+			level: Level::Warn,
+			// Earlier than the previous record.
+			time_us: 1_602_669_000_000_000,
+			fields: vec![("call".into(), Value::Str("set_code".into()))],
+		}));
+		records
+	}
+
+	fn write(records: &[Record]) -> Vec<u8> {
+		let mut writer = TraceWriter::new(Vec::new()).unwrap();
+		for record in records {
+			writer.write(record).unwrap();
+		}
+		writer.into_inner()
+	}
+
+	#[test]
+	fn roundtrips_records() {
+		let records = records();
+		let trace = write(&records);
+		let read = TraceReader::new(&trace[..]).unwrap().collect::<Result<Vec<_>>>().unwrap();
+		assert_eq!(read, records);
+	}
+
+	#[test]
+	fn is_smaller_than_json() {
+		let records = records();
+		let json = records.iter()
+			.filter_map(|record| match record {
+				Record::Span(span) => Some(span),
+				Record::Event(_) => None,
+			})
+			.map(|span| serde_json::json!({
+				"id": span.id,
+				"parent_id": span.parent_id,
+				"target": span.target,
+				"name": span.name,
+				"level": "INFO",
+				"start_us": span.start_us,
+				"duration_ns": span.duration_ns,
+				"values": {
+					"amount": 1_000_000_000_000u64,
+					"keep_alive": true,
+					"weight_delta": -(span.id as i64),
+				},
+			}).to_string())
+			.collect::<Vec<_>>()
+			.join("\n");
+		let trace = write(&records);
+		assert!(json.len() > trace.len() * 5, "json: {}, trace: {}", json.len(), trace.len());
+	}
+
+	#[test]
+	fn refuses_frames_longer_than_the_maximum() {
+		let mut trace = write(&[]);
+		trace.push(FRAME_STRING);
+		Compact(u32::max_value()).encode_to(&mut trace);
+		let mut reader = TraceReader::new(&trace[..]).unwrap();
+		assert!(matches!(reader.read(), Err(Error::FrameTooLarge(len)) if len == u32::max_value() as u64));
+
+		let mut writer = TraceWriter::new(Vec::new()).unwrap();
+		let mut span = span(1, 0);
+		span.fields.push(("code".into(), Value::Str("0".repeat(MAX_FRAME_LEN as usize + 1))));
+		assert!(matches!(writer.write(&Record::Span(span)), Err(Error::FrameTooLarge(_))));
+	}
+
+	#[test]
+	fn writes_json_lines() {
+		let records = records();
+		let trace = write(&records[999..]);
+		let mut json = Vec::new();
+		assert_eq!(write_json(TraceReader::new(&trace[..]).unwrap(), &mut json).unwrap(), 2);
+
+		let lines = String::from_utf8(json).unwrap().lines()
+			.map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+			.collect::<Vec<_>>();
+		assert_eq!(lines, vec![
+			serde_json::json!({
+				"kind": "span",
+				"id": 1000,
+				"parent_id": 1,
+				"target": "pallet_balances",
+				"name": "transfer",
+				"level": "INFO",
+				"start_us": 1_602_670_000_100_000u64,
+				"duration_ns": 13_345,
+				"fields": { "amount": 1_000_000_000_000u64, "keep_alive": true, "weight_delta": -1000 },
+			}),
+			serde_json::json!({
+				"kind": "event",
+				"parent_id": null,
+				"target": "sudo",
+				"name": "event",
+				"level": "WARN",
+				"time_us": 1_602_669_000_000_000u64,
+				"fields": { "call": "set_code" },
+			}),
+		]);
+	}
+
+	#[test]
+	fn reads_incrementally_and_skips_unknown_frames() {
+		let records = records();
+		let mut trace = write(&records[..2]);
+		// A frame of a kind unknown to this version, followed by a span with an extra field.
+		trace.extend_from_slice(&[7, 2 << 2, 0xaa, 0xbb]);
+		let mut extended = WireSpan {
+			id: Compact(3),
+			parent_id: Compact(1),
+			target: Compact(0),
+			name: Compact(1),
+			level: Level::Info,
+			start: Compact(zigzag(100)),
+			duration_ns: Compact(12_348),
+			fields: Vec::new(),
+		}.encode();
+		extended.push(0xcc);
+		trace.push(FRAME_SPAN);
+		Compact(extended.len() as u32).encode_to(&mut trace);
+		trace.extend(extended);
+
+		let mut reader = TraceReader::new(&trace[..]).unwrap();
+		assert_eq!(reader.version(), VERSION);
+		assert_eq!(reader.read().unwrap(), Some(records[0].clone()));
+		assert_eq!(reader.read().unwrap(), Some(records[1].clone()));
+		let mut expected = span(3, 1_602_670_000_000_300);
+		expected.fields.clear();
+		assert_eq!(reader.read().unwrap(), Some(Record::Span(expected)));
+		assert_eq!(reader.read().unwrap(), None);
+
+		// A truncated record is an error, not the end of the trace.
+		let trace = write(&records[..1]);
+		let mut reader = TraceReader::new(&trace[..trace.len() - 1]).unwrap();
+		assert!(reader.read().is_err());
+		assert!(matches!(TraceReader::new(&b"SCTR\x02"[..]), Err(Error::UnsupportedVersion(2))));
+	}
+}
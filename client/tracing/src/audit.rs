@@ -0,0 +1,157 @@
+// Copyright 2019-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Forwarding of the audit events to telemetry, e.g. of the sudo actions.
+//!
+//! A `ProfilingSubscriber` forwards them along with the rest of its traces. A node which doesn't
+//! profile itself installs an [`AuditSubscriber`] instead, only enabling the audit targets, so
+//! that they are forwarded whatever the tracing configuration.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use parking_lot::Mutex;
+use rustc_hash::FxHashMap;
+use tracing::{
+	Event,
+	dispatcher::SetGlobalDefaultError,
+	metadata::Metadata,
+	span::{Attributes, Id, Record},
+	subscriber::Subscriber,
+};
+use tracing_core::span::Current;
+use tracing_subscriber::CurrentSpan;
+
+use sc_telemetry::{telemetry, SUBSTRATE_INFO};
+
+use crate::{AUDIT_TARGETS, Redaction, Values};
+
+/// Send an event of the audit targets to telemetry as `tracing.audit`, with the `values` of the
+/// event and of its span.
+pub(crate) fn forward_audit_event(event: &Event<'_>, values: &Values, redaction: &Redaction) {
+	sc_telemetry::without_routing(|| telemetry!(SUBSTRATE_INFO; "tracing.audit";
+		"name" => event.metadata().name(),
+		"target" => event.metadata().target(),
+		"level" => event.metadata().level().to_string(),
+		"values" => redaction.redact(values)
+	));
+}
+
+/// Subscriber only enabling the audit targets, and forwarding their events to telemetry.
+pub struct AuditSubscriber {
+	redaction: Redaction,
+	next_id: AtomicU64,
+	spans: Mutex<FxHashMap<u64, (&'static Metadata<'static>, Values)>>,
+	current_span: CurrentSpan,
+}
+
+impl AuditSubscriber {
+	/// A subscriber applying `redaction` to the forwarded values.
+	pub fn new(redaction: Redaction) -> Self {
+		AuditSubscriber {
+			redaction,
+			next_id: AtomicU64::new(1),
+			spans: Default::default(),
+			current_span: Default::default(),
+		}
+	}
+
+	/// Set the subscriber as the global default.
+	pub fn init(self) -> Result<(), SetGlobalDefaultError> {
+		tracing::subscriber::set_global_default(self)
+	}
+}
+
+impl Subscriber for AuditSubscriber {
+	fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+		AUDIT_TARGETS.contains(&metadata.target())
+	}
+
+	fn new_span(&self, attrs: &Attributes<'_>) -> Id {
+		let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+		let mut values = Values::default();
+		attrs.record(&mut values);
+		self.spans.lock().insert(id, (attrs.metadata(), values));
+		Id::from_u64(id)
+	}
+
+	fn record(&self, span: &Id, values: &Record<'_>) {
+		if let Some((_, span_values)) = self.spans.lock().get_mut(&span.into_u64()) {
+			values.record(span_values);
+		}
+	}
+
+	fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+	fn event(&self, event: &Event<'_>) {
+		let mut values = Values::default();
+		event.record(&mut values);
+		// Audit records must be self-contained, so they include the values of their span.
+		let parent_id = event.parent().cloned().or_else(|| self.current_span.id());
+		if let Some(parent_id) = parent_id {
+			if let Some((_, parent_values)) = self.spans.lock().get(&parent_id.into_u64()) {
+				values.extend_missing(parent_values);
+			}
+		}
+		forward_audit_event(event, &values, &self.redaction);
+	}
+
+	fn enter(&self, span: &Id) {
+		self.current_span.enter(span.clone());
+	}
+
+	fn exit(&self, _span: &Id) {
+		self.current_span.exit();
+	}
+
+	fn current_span(&self) -> Current {
+		let id = match self.current_span.id() {
+			Some(id) => id,
+			None => return Current::none(),
+		};
+		let metadata = self.spans.lock().get(&id.into_u64()).map(|(metadata, _)| *metadata);
+		match metadata {
+			Some(metadata) => Current::new(id, metadata),
+			None => Current::none(),
+		}
+	}
+
+	fn try_close(&self, span: Id) -> bool {
+		self.spans.lock().remove(&span.into_u64());
+		true
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use tracing::Dispatch;
+
+	#[test]
+	fn only_the_spans_of_the_audit_targets_are_kept() {
+		let dispatch = Dispatch::new(AuditSubscriber::new(Default::default()));
+		let subscriber = dispatch.downcast_ref::<AuditSubscriber>().unwrap();
+		tracing::dispatcher::with_default(&dispatch, || {
+			assert!(tracing::info_span!(target: "pallet", "call").is_disabled());
+
+			let span = tracing::info_span!(target: "sudo", "sudo_call", call = "set_code");
+			let _guard = span.enter();
+			let id = subscriber.current_span().id().unwrap().into_u64();
+			let spans = subscriber.spans.lock();
+			assert_eq!(spans.len(), 1);
+			assert_eq!(spans[&id].1.string_values.get("call").unwrap(), "set_code");
+		});
+		assert!(subscriber.spans.lock().is_empty());
+	}
+}
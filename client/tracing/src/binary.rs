@@ -0,0 +1,161 @@
+// Copyright 2019-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Export of the spans and events to a file in the compact binary format of `sc-tracing-format`.
+
+use std::{fs::{self, File}, io::BufWriter, path::Path, time::{Instant, UNIX_EPOCH}};
+use parking_lot::Mutex;
+use sc_telemetry::ExporterHealth;
+use sc_tracing_format::{Event, Level, Record, Span, TraceWriter, Value};
+
+use crate::{SpanDatum, TraceEvent, TraceHandler, Values};
+
+/// TraceHandler for writing the spans and events to a binary trace file.
+pub struct BinaryTraceHandler {
+	writer: Option<Mutex<TraceWriter<BufWriter<File>>>>,
+	health: ExporterHealth,
+}
+
+impl BinaryTraceHandler {
+	/// Create a handler writing to the file at `path`, replacing any existing file.
+	///
+	/// Nothing is recorded if the file can't be created, the records being counted as dropped.
+	pub fn new(path: &Path) -> Self {
+		let health = ExporterHealth::new(format!("binary {}", path.display()));
+		let writer = path.parent().map_or(Ok(()), fs::create_dir_all)
+			.and_then(|_| File::create(path))
+			.map_err(sc_tracing_format::Error::from)
+			.and_then(|file| TraceWriter::new(BufWriter::new(file)));
+		match writer {
+			Ok(writer) => BinaryTraceHandler { writer: Some(Mutex::new(writer)), health },
+			Err(e) => {
+				log::warn!(target: "tracing", "Unable to create the trace {}: {}", path.display(), e);
+				BinaryTraceHandler { writer: None, health }
+			},
+		}
+	}
+
+	fn write(&self, record: Record) {
+		let writer = match &self.writer {
+			Some(writer) => writer,
+			None => return self.health.record_dropped(1),
+		};
+		let start = Instant::now();
+		match writer.lock().write(&record) {
+			Ok(()) => self.health.record_sent(1, start.elapsed()),
+			Err(e) => {
+				log::warn!(target: "tracing", "Unable to write the trace: {}", e);
+				self.health.record_dropped(1);
+			},
+		}
+	}
+}
+
+impl TraceHandler for BinaryTraceHandler {
+	fn handle_span(&self, span_datum: SpanDatum) {
+		let start = span_datum.start_wall_time;
+		self.write(Record::Span(Span {
+			id: span_datum.id.into_u64(),
+			parent_id: span_datum.parent_id.map(|id| id.into_u64()),
+			target: span_datum.target,
+			name: span_datum.name,
+			level: level(&span_datum.level),
+			start_us: start.duration_since(UNIX_EPOCH).unwrap_or_default().as_micros() as u64,
+			duration_ns: span_datum.overall_time.as_nanos() as u64,
+			fields: fields(span_datum.values),
+		}));
+	}
+
+	fn handle_event(&self, event: TraceEvent) {
+		self.write(Record::Event(Event {
+			parent_id: event.parent_id.map(|id| id.into_u64()),
+			target: event.target,
+			name: event.name.to_owned(),
+			level: level(&event.level),
+			time_us: event.time.duration_since(UNIX_EPOCH).unwrap_or_default().as_micros() as u64,
+			fields: fields(event.values),
+		}));
+	}
+
+	fn flush(&self) {
+		if let Some(writer) = &self.writer {
+			if let Err(e) = writer.lock().flush() {
+				log::warn!(target: "tracing", "Unable to write the trace: {}", e);
+			}
+		}
+	}
+}
+
+fn level(level: &tracing::Level) -> Level {
+	match *level {
+		tracing::Level::ERROR => Level::Error,
+		tracing::Level::WARN => Level::Warn,
+		tracing::Level::INFO => Level::Info,
+		tracing::Level::DEBUG => Level::Debug,
+		tracing::Level::TRACE => Level::Trace,
+	}
+}
+
+fn fields(values: Values) -> Vec<(String, Value)> {
+	values.bool_values.into_iter().map(|(k, v)| (k, Value::Bool(v)))
+		.chain(values.i64_values.into_iter().map(|(k, v)| (k, Value::I64(v))))
+		.chain(values.u64_values.into_iter().map(|(k, v)| (k, Value::U64(v))))
+		.chain(values.string_values.into_iter().map(|(k, v)| (k, Value::Str(v))))
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::ProfilingSubscriber;
+
+	#[test]
+	fn writes_spans_and_events() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("traces").join("trace.sctrace");
+		let handler = BinaryTraceHandler::new(&path);
+		let subscriber = ProfilingSubscriber::new_with_handler(Box::new(handler), "test_target");
+		let dispatch = tracing::Dispatch::new(subscriber);
+		tracing::dispatcher::with_default(&dispatch, || {
+			tracing::info_span!(target: "test_target", "import_block", block_number = 42u64).in_scope(|| {
+				tracing::warn!(target: "test_target", delta = -1i64);
+			});
+		});
+		// Flushes the trace.
+		drop(dispatch);
+
+		let file = File::open(&path).unwrap();
+		let records = sc_tracing_format::TraceReader::new(file).unwrap()
+			.collect::<Result<Vec<_>, _>>()
+			.unwrap();
+		assert_eq!(records.len(), 2);
+		let span = match &records[1] {
+			Record::Span(span) => span,
+			record => panic!("Unexpected record {:?}", record),
+		};
+		assert_eq!((span.target.as_str(), span.name.as_str()), ("test_target", "import_block"));
+		assert_eq!(span.fields, vec![("block_number".to_owned(), Value::U64(42))]);
+		match &records[0] {
+			Record::Event(event) => {
+				assert_eq!(event.parent_id, Some(span.id));
+				assert_eq!(event.level, Level::Warn);
+				assert_eq!(event.fields, vec![("delta".to_owned(), Value::I64(-1))]);
+				assert!(event.time_us >= span.start_us);
+			},
+			record => panic!("Unexpected record {:?}", record),
+		}
+	}
+}
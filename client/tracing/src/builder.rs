@@ -0,0 +1,671 @@
+// Copyright 2019-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Programmatic composition of the subscriber of a node.
+//!
+//! The `--tracing-*` flags of `sc-cli` only cover the receivers of this crate. A node can
+//! instead compose its subscriber with a [`TracingBuilder`]: any number of trace handlers, to
+//! export the spans and events elsewhere, and `tracing-subscriber` layers, e.g. to filter them or
+//! to format them with a [`FmtLayer`](crate::FmtLayer).
+//! The [`TracingHandle`] it returns reconfigures the subscriber while the node runs.
+//!
+//! The nodes built by `sc-service` compose it from their configuration, and then let the
+//! [`TracingExtension`]s of the configuration add to it.
+
+use std::fmt;
+
+use tracing::{
+	Dispatch, Event, Metadata,
+	dispatcher::SetGlobalDefaultError,
+	span::{Attributes, Id, Record},
+	subscriber::Interest,
+};
+use tracing_subscriber::layer::{Context, Layer};
+
+use crate::{
+	BlockSummary, Clock, Counters, DispatchOrigins, FinalityMetrics, ImportDeadlines, PovBreakdowns,
+	ProfilingSubscriber, QueueMetrics, QueuedTraceHandler, Redaction, ScaleDecoder, SentryReporter,
+	SpanDatum, SpanMetrics, TraceEvent,
+	TraceHandler, TraceLimits, TracingReceiver, WasmSpanWrapper, WeightDrift, summary::BlockSummaries,
+};
+
+/// A layer of the subscriber built by a [`TracingBuilder`].
+pub type BoxedLayer = Box<dyn Layer<ProfilingSubscriber> + Send + Sync>;
+
+/// Builds the `ProfilingSubscriber` of a node, and the layers on top of it.
+///
+/// ```
+/// use sc_tracing::{LogTraceHandler, TracingBuilder, TracingReceiver};
+/// use tracing_subscriber::filter::LevelFilter;
+///
+/// let handle = TracingBuilder::new()
+///     .with_targets("pallet=debug,runtime")
+///     .with_receiver(TracingReceiver::Telemetry)
+///     .with_handler(LogTraceHandler)
+///     .with_layer(LevelFilter::DEBUG)
+///     .build();
+/// # let _ = handle;
+/// ```
+pub struct TracingBuilder {
+	targets: String,
+	receiver: Option<TracingReceiver>,
+	handlers: Vec<Box<dyn TraceHandler>>,
+	redaction: Redaction,
+	scale_decoder: Option<ScaleDecoder>,
+	block_summaries: Option<BlockSummaries>,
+	limits: Option<TraceLimits>,
+	import_deadlines: Option<ImportDeadlines>,
+	span_metrics: Option<usize>,
+	weight_drift: Option<WeightDrift>,
+	dispatch_origins: Option<DispatchOrigins>,
+	finality_metrics: Option<FinalityMetrics>,
+	storage_counters: bool,
+	dispatch_errors: bool,
+	pov_breakdowns: Option<PovBreakdowns>,
+	sentry: Option<SentryReporter>,
+	layers: Vec<BoxedLayer>,
+	wasm_wrappers: Vec<Box<dyn WasmSpanWrapper>>,
+	queue: Option<usize>,
+	clock: Option<Box<dyn Clock>>,
+}
+
+impl Default for TracingBuilder {
+	fn default() -> Self {
+		TracingBuilder {
+			targets: String::new(),
+			receiver: None,
+			handlers: Vec::new(),
+			redaction: Default::default(),
+			scale_decoder: None,
+			block_summaries: None,
+			limits: None,
+			import_deadlines: None,
+			span_metrics: None,
+			weight_drift: None,
+			dispatch_origins: None,
+			finality_metrics: None,
+			storage_counters: false,
+			dispatch_errors: false,
+			pov_breakdowns: None,
+			sentry: None,
+			layers: Vec::new(),
+			wasm_wrappers: Vec::new(),
+			queue: None,
+			clock: None,
+		}
+	}
+}
+
+impl TracingBuilder {
+	/// A builder enabling only the audit targets, and sending them to the logger.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// The targets, in the format of `--tracing-targets`, e.g. "pallet=trace,frame=debug".
+	pub fn with_targets(self, targets: &str) -> Self {
+		TracingBuilder { targets: targets.to_owned(), ..self }
+	}
+
+	/// Send the spans and events to `receiver`, as `--tracing-receiver` does.
+	pub fn with_receiver(self, receiver: TracingReceiver) -> Self {
+		TracingBuilder { receiver: Some(receiver), ..self }
+	}
+
+	/// Also send the spans and events to `handler`, after the receiver and the handlers added
+	/// before it.
+	///
+	/// Without a receiver nor a handler, they are sent to the logger.
+	pub fn with_handler(mut self, handler: impl TraceHandler + 'static) -> Self {
+		self.handlers.push(Box::new(handler));
+		self
+	}
+
+	/// The redaction of the receiver, see [`ProfilingSubscriber::new`].
+	///
+	/// The handlers get the values as they are recorded.
+	pub fn with_redaction(self, redaction: Redaction) -> Self {
+		TracingBuilder { redaction, ..self }
+	}
+
+	/// Hand the spans and events over to the receiver and the handlers on an exporter thread,
+	/// through a queue of `capacity` spans and events, see [`QueuedTraceHandler`].
+	///
+	/// The threads closing the spans don't wait for the handlers, but the spans and events are
+	/// dropped when the queue is full.
+	pub fn with_queue(self, capacity: usize) -> Self {
+		TracingBuilder { queue: Some(capacity), ..self }
+	}
+
+	/// See [`ProfilingSubscriber::with_clock`].
+	pub fn with_clock(self, clock: impl Clock + 'static) -> Self {
+		TracingBuilder { clock: Some(Box::new(clock)), ..self }
+	}
+
+	/// See [`ProfilingSubscriber::with_scale_decoder`].
+	pub fn with_scale_decoder(self, scale_decoder: ScaleDecoder) -> Self {
+		TracingBuilder { scale_decoder: Some(scale_decoder), ..self }
+	}
+
+	/// See [`ProfilingSubscriber::with_block_summaries`].
+	pub fn with_block_summaries(
+		self,
+		max_spans: usize,
+		sink: impl Fn(BlockSummary) + Send + Sync + 'static,
+	) -> Self {
+		let block_summaries = BlockSummaries::new(max_spans, Box::new(sink));
+		TracingBuilder { block_summaries: Some(block_summaries), ..self }
+	}
+
+	/// See [`ProfilingSubscriber::with_limits`].
+	pub fn with_limits(self, limits: TraceLimits) -> Self {
+		TracingBuilder { limits: Some(limits), ..self }
+	}
+
+	/// See [`ProfilingSubscriber::with_import_deadlines`].
+	pub fn with_import_deadlines(self, import_deadlines: ImportDeadlines) -> Self {
+		TracingBuilder { import_deadlines: Some(import_deadlines), ..self }
+	}
+
+	/// See [`ProfilingSubscriber::with_span_metrics`].
+	pub fn with_span_metrics(self, max_series: usize) -> Self {
+		TracingBuilder { span_metrics: Some(max_series), ..self }
+	}
+
+	/// See [`ProfilingSubscriber::with_weight_drift`].
+	pub fn with_weight_drift(self, weight_drift: WeightDrift) -> Self {
+		TracingBuilder { weight_drift: Some(weight_drift), ..self }
+	}
+
+	/// See [`ProfilingSubscriber::with_dispatch_origins`].
+	pub fn with_dispatch_origins(self, dispatch_origins: DispatchOrigins) -> Self {
+		TracingBuilder { dispatch_origins: Some(dispatch_origins), ..self }
+	}
+
+	/// See [`ProfilingSubscriber::with_finality_metrics`].
+	pub fn with_finality_metrics(self, finality_metrics: FinalityMetrics) -> Self {
+		TracingBuilder { finality_metrics: Some(finality_metrics), ..self }
+	}
+
+	/// See [`ProfilingSubscriber::with_storage_counters`].
+	pub fn with_storage_counters(self) -> Self {
+		TracingBuilder { storage_counters: true, ..self }
+	}
+
+	/// See [`ProfilingSubscriber::with_dispatch_errors`].
+	pub fn with_dispatch_errors(self) -> Self {
+		TracingBuilder { dispatch_errors: true, ..self }
+	}
+
+	/// See [`ProfilingSubscriber::with_pov_breakdowns`].
+	pub fn with_pov_breakdowns(self, pov_breakdowns: PovBreakdowns) -> Self {
+		TracingBuilder { pov_breakdowns: Some(pov_breakdowns), ..self }
+	}
+
+	/// See [`ProfilingSubscriber::with_sentry`].
+	pub fn with_sentry(self, sentry: SentryReporter) -> Self {
+		TracingBuilder { sentry: Some(sentry), ..self }
+	}
+
+	/// Add a layer on top of the `ProfilingSubscriber`, after the layers added before it.
+	///
+	/// A span or event disabled by any layer is disabled. The layers can't look the spans up,
+	/// so the ones needing a `Registry`, e.g. `tracing_subscriber::fmt::Layer`, are not supported:
+	/// add a [`FmtLayer`](crate::FmtLayer) to format the events instead.
+	pub fn with_layer(mut self, layer: impl Layer<ProfilingSubscriber> + Send + Sync) -> Self {
+		self.layers.push(Box::new(layer));
+		self
+	}
+
+	/// Wrap the handling of the spans of the runtime with `wrapper`, inside the wrappers added
+	/// before it.
+	pub fn with_wasm_wrapper(mut self, wrapper: impl WasmSpanWrapper + 'static) -> Self {
+		self.wasm_wrappers.push(Box::new(wrapper));
+		self
+	}
+
+	/// Let `extension` add its layers, handlers and wrappers.
+	pub fn with_extension(self, extension: &dyn TracingExtension) -> Self {
+		extension.extend(self)
+	}
+
+	/// Build the subscriber, without setting it as the default.
+	pub fn build(self) -> TracingHandle {
+		let TracingBuilder {
+			targets, receiver, handlers, redaction, scale_decoder, block_summaries, limits,
+			import_deadlines, span_metrics, weight_drift, dispatch_origins, finality_metrics,
+			storage_counters, dispatch_errors, pov_breakdowns, sentry, layers, wasm_wrappers, queue,
+			clock,
+		} = self;
+		let mut handlers: Vec<_> = receiver
+			.map(|receiver| receiver.into_handler(redaction.clone()))
+			.into_iter()
+			.chain(handlers)
+			.collect();
+		let handler = match handlers.len() {
+			0 => TracingReceiver::Log.into_handler(redaction.clone()),
+			1 => handlers.remove(0),
+			_ => Box::new(Handlers(handlers)),
+		};
+		let (handler, queue) = match queue {
+			Some(capacity) => {
+				let handler = QueuedTraceHandler::new(handler, capacity);
+				let metrics = handler.metrics();
+				(Box::new(handler) as Box<dyn TraceHandler>, Some(metrics))
+			},
+			None => (handler, None),
+		};
+		let subscriber = ProfilingSubscriber {
+			redaction,
+			block_summaries,
+			wasm_wrappers,
+			..ProfilingSubscriber::new_with_handler(handler, &targets)
+		};
+		let subscriber = match scale_decoder {
+			Some(scale_decoder) => subscriber.with_scale_decoder(scale_decoder),
+			None => subscriber,
+		};
+		let subscriber = match limits {
+			Some(limits) => subscriber.with_limits(limits),
+			None => subscriber,
+		};
+		let subscriber = match import_deadlines {
+			Some(import_deadlines) => subscriber.with_import_deadlines(import_deadlines),
+			None => subscriber,
+		};
+		let subscriber = match span_metrics {
+			Some(max_series) => subscriber.with_span_metrics(max_series),
+			None => subscriber,
+		};
+		let subscriber = match weight_drift {
+			Some(weight_drift) => subscriber.with_weight_drift(weight_drift),
+			None => subscriber,
+		};
+		let subscriber = match dispatch_origins {
+			Some(dispatch_origins) => subscriber.with_dispatch_origins(dispatch_origins),
+			None => subscriber,
+		};
+		let subscriber = match finality_metrics {
+			Some(finality_metrics) => subscriber.with_finality_metrics(finality_metrics),
+			None => subscriber,
+		};
+		let subscriber = if storage_counters { subscriber.with_storage_counters() } else { subscriber };
+		let subscriber = if dispatch_errors { subscriber.with_dispatch_errors() } else { subscriber };
+		let subscriber = match pov_breakdowns {
+			Some(pov_breakdowns) => subscriber.with_pov_breakdowns(pov_breakdowns),
+			None => subscriber,
+		};
+		let subscriber = match sentry {
+			Some(sentry) => subscriber.with_sentry(sentry),
+			None => subscriber,
+		};
+		let subscriber = match clock {
+			Some(clock) => ProfilingSubscriber { clock, ..subscriber },
+			None => subscriber,
+		};
+		let dispatch = if layers.is_empty() {
+			Dispatch::new(subscriber)
+		} else {
+			Dispatch::new(Layers(layers).with_subscriber(subscriber))
+		};
+		TracingHandle { dispatch, global: false, queue }
+	}
+
+	/// Build the subscriber and set it as the global default.
+	///
+	/// The targets are reported to the runtime, see `sp_tracing::set_host_targets`, the spans of
+	/// the threads are provided to the reports of the panics and traps, see
+	/// `sp_tracing::span_context`, and the panics are reported to the telemetry and to Sentry if
+	/// configured.
+	///
+	/// The telemetry messages are routed through the subscriber, as events of the
+	/// `sc_telemetry::TELEMETRY_TARGET` at the level of their verbosity, so that the targets, the
+	/// layers, the limits and the redaction apply to them, see
+	/// `sc_telemetry::route_through_tracing`. They are all sent unless the targets configure the
+	/// level of the telemetry, e.g. `telemetry=info` to only send the messages of verbosity 0 and
+	/// 1.
+	pub fn init(self) -> Result<TracingHandle, SetGlobalDefaultError> {
+		let targets = self.targets.clone();
+		let mut handle = self.build();
+		tracing::dispatcher::set_global_default(handle.dispatch.clone())?;
+		handle.global = true;
+		sp_tracing::set_host_targets(&targets);
+		sp_tracing::set_span_context(crate::global_span_context);
+		sp_tracing::set_trace_id(crate::global_trace_id);
+		sc_telemetry::route_through_tracing();
+		crate::set_panic_hook();
+		Ok(handle)
+	}
+}
+
+/// Extends the subscriber a node builds from its configuration, e.g. with the exporters of a
+/// chain, see `sc_service::Configuration::tracing_extensions`.
+pub trait TracingExtension: Send + Sync {
+	/// Add the layers, handlers and wasm span wrappers of the extension to `builder`.
+	fn extend(&self, builder: TracingBuilder) -> TracingBuilder;
+}
+
+impl<F: Fn(TracingBuilder) -> TracingBuilder + Send + Sync> TracingExtension for F {
+	fn extend(&self, builder: TracingBuilder) -> TracingBuilder {
+		self(builder)
+	}
+}
+
+impl fmt::Debug for dyn TracingExtension {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "TracingExtension")
+	}
+}
+
+/// Reconfigures the subscriber built by a [`TracingBuilder`].
+#[derive(Clone)]
+pub struct TracingHandle {
+	dispatch: Dispatch,
+	global: bool,
+	queue: Option<QueueMetrics>,
+}
+
+impl TracingHandle {
+	/// The dispatcher of the subscriber, e.g. for `tracing::dispatcher::with_default`.
+	pub fn dispatch(&self) -> &Dispatch {
+		&self.dispatch
+	}
+
+	/// Replace the targets, see [`ProfilingSubscriber::set_targets`].
+	///
+	/// The new targets are reported to the runtime if the subscriber is the global default.
+	pub fn set_targets(&self, targets: &str) {
+		self.subscriber().set_targets(targets);
+		if self.global {
+			sp_tracing::set_host_targets(targets);
+		}
+	}
+
+	/// See [`ProfilingSubscriber::set_scale_decoder`].
+	pub fn set_scale_decoder(&self, scale_decoder: ScaleDecoder) {
+		self.subscriber().set_scale_decoder(scale_decoder);
+	}
+
+	/// See [`ProfilingSubscriber::counters`].
+	pub fn counters(&self) -> Counters {
+		self.subscriber().counters()
+	}
+
+	/// See [`ProfilingSubscriber::open_spans`].
+	pub fn open_spans(&self) -> Vec<SpanDatum> {
+		self.subscriber().open_spans()
+	}
+
+	/// See [`ProfilingSubscriber::import_deadlines`].
+	pub fn import_deadlines(&self) -> Option<ImportDeadlines> {
+		self.subscriber().import_deadlines()
+	}
+
+	/// See [`ProfilingSubscriber::check_import_deadlines`].
+	pub fn check_import_deadlines(&self) {
+		self.subscriber().check_import_deadlines()
+	}
+
+	/// See [`ProfilingSubscriber::span_metrics`].
+	pub fn span_metrics(&self) -> Option<SpanMetrics> {
+		self.subscriber().span_metrics()
+	}
+
+	/// See [`ProfilingSubscriber::weight_drift`].
+	pub fn weight_drift(&self) -> Option<WeightDrift> {
+		self.subscriber().weight_drift()
+	}
+
+	/// See [`ProfilingSubscriber::dispatch_origins`].
+	pub fn dispatch_origins(&self) -> Option<DispatchOrigins> {
+		self.subscriber().dispatch_origins()
+	}
+
+	/// See [`ProfilingSubscriber::finality_metrics`].
+	pub fn finality_metrics(&self) -> Option<FinalityMetrics> {
+		self.subscriber().finality_metrics()
+	}
+
+	/// See [`ProfilingSubscriber::pov_breakdowns`].
+	pub fn pov_breakdowns(&self) -> Option<PovBreakdowns> {
+		self.subscriber().pov_breakdowns()
+	}
+
+	/// The metrics of the queue of the handlers, if any, see [`TracingBuilder::with_queue`].
+	pub fn queue_metrics(&self) -> Option<QueueMetrics> {
+		self.queue.clone()
+	}
+
+	/// See [`ProfilingSubscriber::flush`].
+	pub fn flush(&self) {
+		self.subscriber().flush()
+	}
+
+	/// See [`ProfilingSubscriber::shutdown`].
+	pub fn shutdown(&self) {
+		self.subscriber().shutdown()
+	}
+
+	fn subscriber(&self) -> &ProfilingSubscriber {
+		self.dispatch.downcast_ref()
+			.expect("The dispatch of a TracingHandle wraps a ProfilingSubscriber; qed")
+	}
+}
+
+/// Sends the spans and events to every handler, in order.
+struct Handlers(Vec<Box<dyn TraceHandler>>);
+
+impl TraceHandler for Handlers {
+	fn handle_span(&self, span: SpanDatum) {
+		if let Some((last, handlers)) = self.0.split_last() {
+			for handler in handlers {
+				handler.handle_span(span.clone());
+			}
+			last.handle_span(span);
+		}
+	}
+
+	fn handle_event(&self, event: TraceEvent) {
+		if let Some((last, handlers)) = self.0.split_last() {
+			for handler in handlers {
+				handler.handle_event(event.clone());
+			}
+			last.handle_event(event);
+		}
+	}
+
+	fn flush(&self) {
+		for handler in &self.0 {
+			handler.flush();
+		}
+	}
+}
+
+/// The layers of a [`TracingBuilder`], notified in order.
+struct Layers(Vec<BoxedLayer>);
+
+impl Layer<ProfilingSubscriber> for Layers {
+	fn register_callsite(&self, metadata: &'static Metadata<'static>) -> Interest {
+		let mut interest = Interest::always();
+		for layer in &self.0 {
+			let layer_interest = layer.register_callsite(metadata);
+			if layer_interest.is_never() {
+				return layer_interest;
+			}
+			if layer_interest.is_sometimes() {
+				interest = layer_interest;
+			}
+		}
+		interest
+	}
+
+	fn enabled(&self, metadata: &Metadata<'_>, ctx: Context<'_, ProfilingSubscriber>) -> bool {
+		self.0.iter().all(|layer| layer.enabled(metadata, ctx.clone()))
+	}
+
+	fn new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, ProfilingSubscriber>) {
+		for layer in &self.0 {
+			layer.new_span(attrs, id, ctx.clone());
+		}
+	}
+
+	fn on_record(&self, span: &Id, values: &Record<'_>, ctx: Context<'_, ProfilingSubscriber>) {
+		for layer in &self.0 {
+			layer.on_record(span, values, ctx.clone());
+		}
+	}
+
+	fn on_follows_from(&self, span: &Id, follows: &Id, ctx: Context<'_, ProfilingSubscriber>) {
+		for layer in &self.0 {
+			layer.on_follows_from(span, follows, ctx.clone());
+		}
+	}
+
+	fn on_event(&self, event: &Event<'_>, ctx: Context<'_, ProfilingSubscriber>) {
+		for layer in &self.0 {
+			layer.on_event(event, ctx.clone());
+		}
+	}
+
+	fn on_enter(&self, id: &Id, ctx: Context<'_, ProfilingSubscriber>) {
+		for layer in &self.0 {
+			layer.on_enter(id, ctx.clone());
+		}
+	}
+
+	fn on_exit(&self, id: &Id, ctx: Context<'_, ProfilingSubscriber>) {
+		for layer in &self.0 {
+			layer.on_exit(id, ctx.clone());
+		}
+	}
+
+	fn on_close(&self, id: Id, ctx: Context<'_, ProfilingSubscriber>) {
+		for layer in &self.0 {
+			layer.on_close(id.clone(), ctx.clone());
+		}
+	}
+
+	fn on_id_change(&self, old: &Id, new: &Id, ctx: Context<'_, ProfilingSubscriber>) {
+		for layer in &self.0 {
+			layer.on_id_change(old, new, ctx.clone());
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use parking_lot::Mutex;
+	use std::sync::Arc;
+
+	#[derive(Clone, Default)]
+	struct Recorder {
+		spans: Arc<Mutex<Vec<String>>>,
+		events: Arc<Mutex<Vec<String>>>,
+	}
+
+	impl TraceHandler for Recorder {
+		fn handle_span(&self, span: SpanDatum) {
+			self.spans.lock().push(span.name);
+		}
+
+		fn handle_event(&self, event: TraceEvent) {
+			self.events.lock().push(event.target);
+		}
+	}
+
+	struct EventLayer(Arc<Mutex<Vec<String>>>);
+
+	impl Layer<ProfilingSubscriber> for EventLayer {
+		fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, ProfilingSubscriber>) {
+			self.0.lock().push(event.metadata().target().to_owned());
+		}
+	}
+
+	#[test]
+	fn handlers_and_layers_are_composed() {
+		let (first, second) = (Recorder::default(), Recorder::default());
+		let layer_events = Arc::new(Mutex::new(Vec::new()));
+		let handle = TracingBuilder::new()
+			.with_targets("builder_composed")
+			.with_handler(first.clone())
+			.with_handler(second.clone())
+			.with_layer(EventLayer(layer_events.clone()))
+			.build();
+		tracing::dispatcher::with_default(handle.dispatch(), || {
+			let span = tracing::info_span!(target: "builder_composed", "composed_span");
+			span.in_scope(|| tracing::info!(target: "builder_composed", "composed event"));
+		});
+		for recorder in &[first, second] {
+			assert_eq!(*recorder.spans.lock(), vec!["composed_span".to_owned()]);
+			assert_eq!(*recorder.events.lock(), vec!["builder_composed".to_owned()]);
+		}
+		assert_eq!(*layer_events.lock(), vec!["builder_composed".to_owned()]);
+	}
+
+	#[test]
+	fn targets_are_reconfigured() {
+		let recorder = Recorder::default();
+		let handle = TracingBuilder::new()
+			.with_targets("builder_enabled")
+			.with_handler(recorder.clone())
+			.build();
+		let emit = || tracing::dispatcher::with_default(handle.dispatch(), || {
+			tracing::info!(target: "builder_enabled", "enabled");
+			tracing::info!(target: "builder_reconfigured", "reconfigured");
+		});
+		emit();
+		assert_eq!(*recorder.events.lock(), vec!["builder_enabled".to_owned()]);
+		handle.set_targets("builder_reconfigured");
+		emit();
+		assert_eq!(
+			*recorder.events.lock(),
+			vec!["builder_enabled".to_owned(), "builder_reconfigured".to_owned()],
+		);
+	}
+
+	#[test]
+	fn extensions_wrap_the_wasm_spans() {
+		let recorder = Recorder::default();
+		let extension = {
+			let recorder = recorder.clone();
+			move |builder: TracingBuilder| builder
+				.with_handler(recorder.clone())
+				.with_wasm_wrapper(|mut span: SpanDatum, handler: &dyn TraceHandler| {
+					if span.name != "dropped_span" {
+						span.name = format!("chain::{}", span.name);
+						handler.handle_span(span);
+					}
+				})
+		};
+		let handle = TracingBuilder::new()
+			.with_targets("builder_wasm")
+			.with_extension(&extension)
+			.build();
+		tracing::dispatcher::with_default(handle.dispatch(), || {
+			let mut proxy = sp_tracing::proxy::TracingProxy::new();
+			for name in &["wasm_span", "dropped_span"] {
+				let id = proxy.enter_span("builder_wasm", name, false);
+				proxy.exit_span(id);
+			}
+			tracing::info_span!(target: "builder_wasm", "native_span").in_scope(|| {});
+		});
+		assert_eq!(
+			*recorder.spans.lock(),
+			vec!["chain::wasm_span".to_owned(), "native_span".to_owned()],
+		);
+	}
+}
@@ -0,0 +1,144 @@
+// Copyright 2019-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Source of the timestamps of the spans and events.
+//!
+//! The [`ProfilingSubscriber`](crate::ProfilingSubscriber) reads the [`SystemClock`] by default,
+//! the monotonic clock of the system, so that the durations of the spans don't jump with the wall
+//! clock. A [`ManualClock`] only advances when told to, or by a fixed tick at every reading, so
+//! that the durations and the order of the spans and events are deterministic, e.g. to assert on
+//! them in tests or to record traces with logical timestamps.
+
+use std::{
+	sync::{Arc, atomic::{AtomicU64, Ordering}},
+	time::{Duration, Instant, SystemTime},
+};
+
+/// A clock timing the spans and events.
+pub trait Clock: Send + Sync {
+	/// The current time.
+	fn now(&self) -> Instant;
+
+	/// The wall clock time of `instant`, read from this clock.
+	fn wall_time(&self, instant: Instant) -> SystemTime;
+}
+
+/// The clock of the system: monotonic, the wall clock times being relative to the current one.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+	fn now(&self) -> Instant {
+		Instant::now()
+	}
+
+	fn wall_time(&self, instant: Instant) -> SystemTime {
+		let now = Instant::now();
+		if instant <= now {
+			SystemTime::now() - (now - instant)
+		} else {
+			SystemTime::now() + (instant - now)
+		}
+	}
+}
+
+#[derive(Debug)]
+struct Manual {
+	origin: Instant,
+	epoch: SystemTime,
+	elapsed_ns: AtomicU64,
+	tick_ns: u64,
+}
+
+/// A clock advanced by hand, shared by its clones.
+#[derive(Debug, Clone)]
+pub struct ManualClock {
+	inner: Arc<Manual>,
+}
+
+impl ManualClock {
+	/// A clock at `epoch`, advanced by [`ManualClock::advance`] only.
+	pub fn new(epoch: SystemTime) -> Self {
+		Self::ticking(epoch, Duration::from_nanos(0))
+	}
+
+	/// A clock at `epoch`, advanced by `tick` after every reading, so that every span entry and
+	/// exit and every event gets a distinct logical timestamp.
+	pub fn ticking(epoch: SystemTime, tick: Duration) -> Self {
+		ManualClock {
+			inner: Arc::new(Manual {
+				origin: Instant::now(),
+				epoch,
+				elapsed_ns: AtomicU64::new(0),
+				tick_ns: tick.as_nanos() as u64,
+			}),
+		}
+	}
+
+	/// Advance the clock by `duration`.
+	pub fn advance(&self, duration: Duration) {
+		self.inner.elapsed_ns.fetch_add(duration.as_nanos() as u64, Ordering::SeqCst);
+	}
+
+	/// Time elapsed since the epoch of the clock.
+	pub fn elapsed(&self) -> Duration {
+		Duration::from_nanos(self.inner.elapsed_ns.load(Ordering::SeqCst))
+	}
+}
+
+impl Clock for ManualClock {
+	fn now(&self) -> Instant {
+		let elapsed = self.inner.elapsed_ns.fetch_add(self.inner.tick_ns, Ordering::SeqCst);
+		self.inner.origin + Duration::from_nanos(elapsed)
+	}
+
+	fn wall_time(&self, instant: Instant) -> SystemTime {
+		self.inner.epoch + instant.saturating_duration_since(self.inner.origin)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::time::UNIX_EPOCH;
+
+	#[test]
+	fn manual_clock_only_advances_when_told_to() {
+		let clock = ManualClock::new(UNIX_EPOCH);
+		let start = clock.now();
+		assert_eq!(clock.now(), start);
+		clock.clone().advance(Duration::from_millis(5));
+		let end = clock.now();
+		assert_eq!(end - start, Duration::from_millis(5));
+		assert_eq!(clock.wall_time(end), UNIX_EPOCH + Duration::from_millis(5));
+	}
+
+	#[test]
+	fn ticking_clock_advances_at_every_reading() {
+		let clock = ManualClock::ticking(UNIX_EPOCH, Duration::from_micros(1));
+		let (first, second) = (clock.now(), clock.now());
+		assert_eq!(second - first, Duration::from_micros(1));
+		assert_eq!(clock.wall_time(second), UNIX_EPOCH + Duration::from_micros(1));
+		assert_eq!(clock.elapsed(), Duration::from_micros(2));
+	}
+
+	#[test]
+	fn system_clock_is_the_wall_clock() {
+		let now = SystemTime::now();
+		let wall_time = SystemClock.wall_time(SystemClock.now());
+		assert!(wall_time >= now - Duration::from_secs(1) && wall_time <= now + Duration::from_secs(1));
+	}
+}
@@ -0,0 +1,239 @@
+// Copyright 2019-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Export of span statistics to a CSV file, e.g. to analyze a profiling run in a spreadsheet.
+//!
+//! The durations of the spans are aggregated by target and name since the handler was created,
+//! and the file is rewritten with the statistics at most every [`WRITE_INTERVAL`] and when the
+//! handler is flushed. Every row is a target and name, with the columns `target`, `name`,
+//! `count`, `total_ns`, `mean_ns`, `p95_ns` and `max_ns`. The file is tab separated if its
+//! extension is `tsv`.
+//!
+//! The 95th percentile is estimated from a histogram of the durations, within 12.5%.
+
+use std::{fmt::Write, path::PathBuf, time::{Duration, Instant}};
+use parking_lot::Mutex;
+use rustc_hash::FxHashMap;
+use sc_telemetry::ExporterHealth;
+
+use crate::{SpanDatum, TraceEvent, TraceHandler, parquet::write_file};
+
+/// Minimum time between two writes of the statistics.
+pub const WRITE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Number of buckets of the histograms per power of two.
+const SUB_BUCKETS: u32 = 8;
+
+/// Number of buckets of the histograms, enough for any `u64`.
+const BUCKETS: usize = ((65 - SUB_BUCKETS.trailing_zeros()) * SUB_BUCKETS) as usize;
+
+/// Durations of the spans of a target and name.
+pub(crate) struct Stats {
+	pub count: u64,
+	pub total_ns: u64,
+	pub max_ns: u64,
+	/// Number of durations in each bucket, see `bucket`.
+	histogram: Vec<u64>,
+}
+
+impl Stats {
+	pub fn new() -> Self {
+		Stats { count: 0, total_ns: 0, max_ns: 0, histogram: vec![0; BUCKETS] }
+	}
+
+	pub fn record(&mut self, duration_ns: u64) {
+		self.count += 1;
+		self.total_ns = self.total_ns.saturating_add(duration_ns);
+		self.max_ns = self.max_ns.max(duration_ns);
+		self.histogram[bucket(duration_ns)] += 1;
+	}
+
+	/// Upper bound of the bucket of the 95th percentile, capped to the maximum.
+	pub fn p95_ns(&self) -> u64 {
+		let rank = (self.count * 95 + 99) / 100;
+		let mut seen = 0;
+		for (bucket, count) in self.histogram.iter().enumerate() {
+			seen += count;
+			if seen >= rank {
+				return bucket_end(bucket).min(self.max_ns);
+			}
+		}
+		self.max_ns
+	}
+}
+
+/// Index of the bucket of `value`: the power of two below it, then the next bits.
+fn bucket(value: u64) -> usize {
+	if value < SUB_BUCKETS as u64 {
+		return value as usize;
+	}
+	let log = 63 - value.leading_zeros();
+	let shift = log - SUB_BUCKETS.trailing_zeros();
+	let sub = (value >> shift) as u32 - SUB_BUCKETS;
+	((shift + 1) * SUB_BUCKETS + sub) as usize
+}
+
+/// Largest value of the bucket `index`.
+fn bucket_end(index: usize) -> u64 {
+	let (power, sub) = (index as u32 / SUB_BUCKETS, index as u64 % SUB_BUCKETS as u64);
+	if power == 0 {
+		return sub;
+	}
+	let shift = power - 1;
+	((SUB_BUCKETS as u64 + sub) << shift) + ((1 << shift) - 1)
+}
+
+struct State {
+	stats: FxHashMap<(String, String), Stats>,
+	written: Instant,
+}
+
+/// TraceHandler writing span statistics to a CSV file
+pub struct CsvTraceHandler {
+	path: PathBuf,
+	separator: char,
+	state: Mutex<State>,
+	health: ExporterHealth,
+}
+
+impl CsvTraceHandler {
+	/// Create a handler writing the statistics to the file at `path`, replacing any existing file.
+	pub fn new(path: PathBuf) -> Self {
+		let separator = match path.extension() {
+			Some(extension) if extension == "tsv" => '\t',
+			_ => ',',
+		};
+		CsvTraceHandler {
+			health: ExporterHealth::new(format!("csv {}", path.display())),
+			path,
+			separator,
+			state: Mutex::new(State { stats: Default::default(), written: Instant::now() }),
+		}
+	}
+
+	fn write(&self, state: &mut State) {
+		let mut stats: Vec<_> = state.stats.iter().collect();
+		stats.sort_by(|a, b| a.0.cmp(b.0));
+		let columns = ["target", "name", "count", "total_ns", "mean_ns", "p95_ns", "max_ns"];
+		let mut data = columns.join(&self.separator.to_string());
+		for ((target, name), stats) in stats {
+			let _ = write!(
+				data,
+				"\n{}{sep}{}{sep}{}{sep}{}{sep}{}{sep}{}{sep}{}",
+				self.escape(target),
+				self.escape(name),
+				stats.count,
+				stats.total_ns,
+				stats.total_ns / stats.count.max(1),
+				stats.p95_ns(),
+				stats.max_ns,
+				sep = self.separator,
+			);
+		}
+		data.push('\n');
+		let rows = state.stats.len() as u64;
+		let start = Instant::now();
+		match write_file(&self.path, data.as_bytes()) {
+			Ok(()) => self.health.record_sent(rows, start.elapsed()),
+			Err(e) => {
+				log::warn!(target: "tracing", "Unable to write the span statistics to {}: {}", self.path.display(), e);
+				self.health.record_dropped(rows);
+			},
+		}
+		state.written = Instant::now();
+	}
+
+	/// Quote the values including the separator, quotes or new lines.
+	fn escape(&self, value: &str) -> String {
+		if value.contains(|c| c == self.separator || c == '"' || c == '\n' || c == '\r') {
+			format!("\"{}\"", value.replace('"', "\"\""))
+		} else {
+			value.to_owned()
+		}
+	}
+}
+
+impl TraceHandler for CsvTraceHandler {
+	fn handle_span(&self, span_datum: SpanDatum) {
+		let mut state = self.state.lock();
+		state.stats.entry((span_datum.target, span_datum.name))
+			.or_insert_with(Stats::new)
+			.record(span_datum.overall_time.as_nanos() as u64);
+		if state.written.elapsed() >= WRITE_INTERVAL {
+			self.write(&mut state);
+		}
+	}
+
+	fn handle_event(&self, _: TraceEvent) {}
+
+	fn flush(&self) {
+		let mut state = self.state.lock();
+		self.write(&mut state);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::ProfilingSubscriber;
+
+	#[test]
+	fn buckets_are_within_an_eighth() {
+		for index in 0..BUCKETS - 1 {
+			let end = bucket_end(index);
+			assert_eq!(bucket(end), index);
+			assert_eq!(bucket(end + 1), index + 1);
+			if index > SUB_BUCKETS as usize {
+				let start = bucket_end(index - 1) + 1;
+				assert!((end - start + 1) * SUB_BUCKETS as u64 <= start);
+			}
+		}
+		assert_eq!(bucket_end(BUCKETS - 1), u64::max_value());
+		assert_eq!(bucket(u64::max_value()), BUCKETS - 1);
+	}
+
+	#[test]
+	fn p95_is_estimated() {
+		let mut stats = Stats::new();
+		for duration in 1..=1000 {
+			stats.record(duration * 1000);
+		}
+		let p95 = stats.p95_ns();
+		assert!(p95 >= 950_000 && p95 <= 1_000_000, "{}", p95);
+		assert_eq!(stats.max_ns, 1_000_000);
+	}
+
+	#[test]
+	fn writes_span_statistics() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("stats.tsv");
+		let handler = CsvTraceHandler::new(path.clone());
+		let subscriber = ProfilingSubscriber::new_with_handler(Box::new(handler), "test_target");
+		let dispatch = tracing::Dispatch::new(subscriber);
+		tracing::dispatcher::with_default(&dispatch, || {
+			for _ in 0..3 {
+				tracing::info_span!(target: "test_target", "import\tblock").in_scope(|| {});
+			}
+			crate::flush_global();
+		});
+
+		let stats = std::fs::read_to_string(&path).unwrap();
+		let lines: Vec<_> = stats.lines().collect();
+		assert_eq!(lines[0], "target\tname\tcount\ttotal_ns\tmean_ns\tp95_ns\tmax_ns");
+		assert!(lines[1].starts_with("test_target\t\"import\tblock\"\t3\t"), "{}", lines[1]);
+		assert_eq!(lines.len(), 2);
+	}
+}
@@ -0,0 +1,418 @@
+// Copyright 2019-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Export of the block execution traces to Datadog APM, through the traces API of the agent.
+//!
+//! Every `import_block` span is a trace, its descendants being its spans. The spans are sent
+//! with the chain as service and `<target>::<name>` as resource, e.g. `pallet_balances::transfer`
+//! for the dispatch of a call, their string and boolean values as meta and their numeric values
+//! as metrics. The spans of the other traces are dropped, except those whose parent doesn't end
+//! within [`MAX_PENDING_AGE`] or before the handler is flushed, which are sent as a trace of their
+//! own.
+//!
+//! The traces are sent by a background thread, in batches of at most [`MAX_BATCH`] traces or
+//! every [`BATCH_INTERVAL`], the traces exceeding [`MAX_QUEUED`] being dropped.
+
+use std::{
+	sync::mpsc,
+	thread,
+	time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use codec::Encode;
+use parking_lot::Mutex;
+use rustc_hash::FxHashMap;
+use sc_telemetry::ExporterHealth;
+use serde::Serialize;
+use sp_core::twox_64;
+
+use crate::{Redaction, SpanDatum, TraceEvent, TraceHandler, http::Endpoint, summary::BLOCK_SPAN};
+
+/// Maximum number of traces sent at once.
+pub const MAX_BATCH: usize = 100;
+
+/// Maximum time a trace waits for its batch to fill.
+pub const BATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Maximum number of traces waiting to be sent, further traces are dropped.
+pub const MAX_QUEUED: usize = 1000;
+
+/// Maximum number of spans waiting for their trace to end, further spans are dropped.
+const MAX_PENDING: usize = 100_000;
+
+/// Maximum time the spans wait for their parent to end, before being sent as a trace of their own.
+pub const MAX_PENDING_AGE: Duration = Duration::from_secs(60);
+
+/// Time between two lookups of the spans waiting for longer than [`MAX_PENDING_AGE`].
+const EXPIRY_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Maximum time `flush` waits for the queued traces to be sent.
+const FLUSH_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Path of the traces API, used if the endpoint has none.
+const TRACES_PATH: &str = "/v0.3/traces";
+
+/// Endpoint of the local agent, by default.
+const DEFAULT_ENDPOINT: &str = "http://localhost:8126";
+
+/// Where to send the traces, and how to describe them.
+#[derive(Debug, Clone)]
+pub struct DatadogConfig {
+	endpoint: Endpoint,
+	/// Service of the spans, e.g. the chain name.
+	pub service: String,
+	/// Meta of all the spans, e.g. `node`.
+	pub meta: Vec<(String, String)>,
+}
+
+impl DatadogConfig {
+	/// Parses an endpoint like `http://localhost:8126`, the path defaulting to the traces API.
+	pub fn new(endpoint: &str) -> Result<Self, String> {
+		let mut parsed = Endpoint::parse(endpoint).ok_or_else(|| format!(
+			"Invalid Datadog agent endpoint `{}`, expected `http[s]://<host>[:<port>][<path>]`",
+			endpoint,
+		))?;
+		if parsed.path == "/" {
+			parsed.path = TRACES_PATH.to_owned();
+		}
+		Ok(DatadogConfig { endpoint: parsed, service: "substrate".into(), meta: Vec::new() })
+	}
+}
+
+impl Default for DatadogConfig {
+	/// Sends to the local agent, on its default port.
+	fn default() -> Self {
+		Self::new(DEFAULT_ENDPOINT).expect("the default endpoint is valid; qed")
+	}
+}
+
+#[derive(Serialize)]
+struct Span {
+	trace_id: u64,
+	span_id: u64,
+	parent_id: u64,
+	name: String,
+	resource: String,
+	service: String,
+	#[serde(rename = "type")]
+	kind: &'static str,
+	start: u64,
+	duration: u64,
+	meta: FxHashMap<String, String>,
+	metrics: FxHashMap<String, f64>,
+	error: u8,
+}
+
+enum Message {
+	Trace(Vec<Span>),
+	Flush(mpsc::SyncSender<()>),
+}
+
+/// The spans of the traces not ended yet, by parent, with the time the first of them ended.
+#[derive(Default)]
+struct Pending {
+	children: FxHashMap<u64, (Instant, Vec<Span>)>,
+	len: usize,
+	next_expiry: Option<Instant>,
+}
+
+impl Pending {
+	/// Remove the spans waiting for a parent since before `since`, by parent.
+	fn take_orphans(&mut self, since: Instant) -> Vec<(u64, Vec<Span>)> {
+		let expired: Vec<_> = self.children.iter()
+			.filter(|(_, (first, _))| *first < since)
+			.map(|(parent_id, _)| *parent_id)
+			.collect();
+		expired.into_iter().filter_map(|parent_id| {
+			let (_, spans) = self.children.remove(&parent_id)?;
+			self.len -= spans.len();
+			Some((parent_id, spans))
+		}).collect()
+	}
+}
+
+/// TraceHandler sending the block execution traces to Datadog APM
+pub struct DatadogTraceHandler {
+	sender: mpsc::SyncSender<Message>,
+	pending: Mutex<Pending>,
+	health: ExporterHealth,
+	service: String,
+	meta: Vec<(String, String)>,
+	redaction: Redaction,
+	/// Mixed into the ids, unique to this handler.
+	seed: u128,
+}
+
+impl DatadogTraceHandler {
+	/// Create a handler sending to the configured endpoint, the values of the spans being
+	/// redacted with `redaction` first.
+	pub fn new(config: DatadogConfig, redaction: Redaction) -> Self {
+		let (sender, receiver) = mpsc::sync_channel(MAX_QUEUED);
+		let endpoint = config.endpoint.clone();
+		let health = ExporterHealth::new("datadog");
+		let sender_health = health.clone();
+		let spawned = thread::Builder::new()
+			.name("datadog-sender".into())
+			.spawn(move || run(endpoint, sender_health, receiver));
+		if let Err(e) = spawned {
+			log::warn!(target: "tracing", "Unable to start the Datadog sender: {}", e);
+		}
+		let seed = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos();
+		DatadogTraceHandler {
+			sender,
+			pending: Default::default(),
+			health,
+			service: config.service,
+			meta: config.meta,
+			redaction,
+			seed,
+		}
+	}
+
+	/// An id of the spans sent by this handler.
+	fn id(&self, id: u64) -> u64 {
+		u64::from_le_bytes(twox_64(&(self.seed, id).encode()))
+	}
+
+	/// Queue the spans of a trace, the root span being the last one.
+	fn send_trace(&self, trace_id: u64, mut spans: Vec<Span>) {
+		for span in &mut spans {
+			span.trace_id = trace_id;
+		}
+		if self.sender.try_send(Message::Trace(spans)).is_ok() {
+			self.health.record_queued(1);
+		} else {
+			self.health.record_dropped(1);
+		}
+	}
+
+	/// Send the spans whose parent has not ended, each parent being the root of a trace.
+	fn send_orphans(&self, orphans: Vec<(u64, Vec<Span>)>) {
+		for (parent_id, spans) in orphans {
+			self.send_trace(self.id(parent_id), spans);
+		}
+	}
+
+	fn span(&self, span_datum: &SpanDatum) -> Span {
+		let start = span_datum.start_wall_time;
+		let values = self.redaction.redact(&span_datum.values);
+		let mut meta: FxHashMap<_, _> = self.meta.iter().cloned().collect();
+		meta.extend(values.string_values);
+		meta.extend(values.bool_values.into_iter().map(|(k, v)| (k, v.to_string())));
+		meta.insert("target".into(), span_datum.target.clone());
+		let metrics = values.u64_values.into_iter().map(|(k, v)| (k, v as f64))
+			.chain(values.i64_values.into_iter().map(|(k, v)| (k, v as f64)))
+			.collect();
+		Span {
+			trace_id: 0,
+			span_id: self.id(span_datum.id.into_u64()),
+			parent_id: span_datum.parent_id.as_ref().map_or(0, |id| self.id(id.into_u64())),
+			name: span_datum.name.clone(),
+			resource: format!("{}::{}", span_datum.target, span_datum.name),
+			service: self.service.clone(),
+			kind: "custom",
+			start: start.duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos() as u64,
+			duration: span_datum.overall_time.as_nanos() as u64,
+			meta,
+			metrics,
+			error: 0,
+		}
+	}
+}
+
+impl TraceHandler for DatadogTraceHandler {
+	fn handle_span(&self, span_datum: SpanDatum) {
+		let span = self.span(&span_datum);
+		let now = Instant::now();
+		let mut pending = self.pending.lock();
+		// The spans of the subtree of the span, which all closed before it.
+		let mut spans = match pending.children.remove(&span_datum.id.into_u64()) {
+			Some((_, spans)) => spans,
+			None => Vec::new(),
+		};
+		pending.len -= spans.len();
+		spans.push(span);
+		match span_datum.parent_id {
+			Some(parent_id) if pending.len + spans.len() <= MAX_PENDING => {
+				pending.len += spans.len();
+				pending.children.entry(parent_id.into_u64())
+					.or_insert_with(|| (now, Vec::new()))
+					.1
+					.append(&mut spans);
+			},
+			Some(_) => log::debug!(target: "tracing", "Dropping {} spans sent to Datadog", spans.len()),
+			None if span_datum.name == BLOCK_SPAN => self.send_trace(self.id(span_datum.id.into_u64()), spans),
+			None => {},
+		}
+		if pending.next_expiry.map_or(true, |next_expiry| next_expiry <= now) {
+			pending.next_expiry = Some(now + EXPIRY_INTERVAL);
+			let orphans = now.checked_sub(MAX_PENDING_AGE)
+				.map(|since| pending.take_orphans(since))
+				.unwrap_or_default();
+			drop(pending);
+			self.send_orphans(orphans);
+		}
+	}
+
+	fn handle_event(&self, _: TraceEvent) {}
+
+	fn flush(&self) {
+		let orphans = self.pending.lock().take_orphans(Instant::now() + Duration::from_nanos(1));
+		self.send_orphans(orphans);
+		let (done, flushed) = mpsc::sync_channel(1);
+		if self.sender.send(Message::Flush(done)).is_ok() {
+			let _ = flushed.recv_timeout(FLUSH_TIMEOUT);
+		}
+	}
+}
+
+/// Send the batches of traces, from a background thread.
+fn run(endpoint: Endpoint, health: ExporterHealth, receiver: mpsc::Receiver<Message>) {
+	let mut batch = Vec::new();
+	let mut deadline = None;
+	loop {
+		let message = match deadline {
+			Some(deadline) => receiver.recv_timeout(deadline - Instant::now().min(deadline)),
+			None => receiver.recv().map_err(|_| mpsc::RecvTimeoutError::Disconnected),
+		};
+		let done = match message {
+			Ok(Message::Trace(trace)) => {
+				batch.push(trace);
+				if batch.len() < MAX_BATCH {
+					deadline = deadline.or_else(|| Some(Instant::now() + BATCH_INTERVAL));
+					continue;
+				}
+				None
+			},
+			Ok(Message::Flush(done)) => Some(done),
+			Err(mpsc::RecvTimeoutError::Timeout) => None,
+			Err(mpsc::RecvTimeoutError::Disconnected) => {
+				send(&endpoint, &health, &mut batch);
+				return;
+			},
+		};
+		send(&endpoint, &health, &mut batch);
+		deadline = None;
+		if let Some(done) = done {
+			let _ = done.send(());
+		}
+	}
+}
+
+fn send(endpoint: &Endpoint, health: &ExporterHealth, batch: &mut Vec<Vec<Span>>) {
+	if batch.is_empty() {
+		return;
+	}
+	let traces = batch.len() as u64;
+	health.record_dequeued(traces);
+	let count = traces.to_string();
+	let headers = [
+		("Datadog-Meta-Lang", "rust"),
+		("Datadog-Meta-Tracer-Version", env!("CARGO_PKG_VERSION")),
+		("X-Datadog-Trace-Count", count.as_str()),
+	];
+	let body = serde_json::to_vec(&batch).unwrap_or_default();
+	let start = Instant::now();
+	match endpoint.post(&headers, &body) {
+		Ok(()) => health.record_sent(traces, start.elapsed()),
+		Err(e) => {
+			log::warn!(target: "tracing", "Unable to send {} traces to Datadog: {}", traces, e);
+			health.record_dropped(traces);
+		},
+	}
+	batch.clear();
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::net::TcpListener;
+	use crate::{ProfilingSubscriber, http::tests::accept};
+
+	#[test]
+	fn defaults_to_the_traces_api() {
+		assert_eq!(DatadogConfig::default().endpoint.path, TRACES_PATH);
+		assert!(DatadogConfig::new("localhost:8126").is_err());
+	}
+
+	#[test]
+	fn sends_block_traces() {
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let mut config = DatadogConfig::new(&format!("http://{}", listener.local_addr().unwrap())).unwrap();
+		config.service = "dev".into();
+		let server = thread::spawn(move || accept(&listener, "200 OK"));
+		let handler = DatadogTraceHandler::new(config, Default::default());
+		let subscriber = ProfilingSubscriber::new_with_handler(Box::new(handler), "test_target,pallet_balances");
+		let dispatch = tracing::Dispatch::new(subscriber);
+		tracing::dispatcher::with_default(&dispatch, || {
+			// Not a block execution.
+			tracing::info_span!(target: "test_target", "other").in_scope(|| {});
+			tracing::info_span!(target: "test_target", "import_block", block_number = 7u64).in_scope(|| {
+				tracing::info_span!(target: "pallet_balances", "transfer", dest = "bob").in_scope(|| {});
+			});
+			crate::flush_global();
+		});
+
+		let (headers, body) = server.join().unwrap();
+		assert!(headers[0].starts_with("POST /v0.3/traces HTTP/1.1"), "{}", headers[0]);
+		assert!(headers.contains(&"X-Datadog-Trace-Count: 1".to_owned()));
+		let traces: serde_json::Value = serde_json::from_str(&body).unwrap();
+		let trace = traces[0].as_array().unwrap();
+		assert_eq!(trace.len(), 2);
+		let (transfer, block) = (&trace[0], &trace[1]);
+		assert_eq!(transfer["resource"], "pallet_balances::transfer");
+		assert_eq!(transfer["service"], "dev");
+		assert_eq!(transfer["meta"]["dest"], "bob");
+		assert_eq!(transfer["parent_id"], block["span_id"]);
+		assert_eq!(transfer["trace_id"], block["trace_id"]);
+		assert_eq!(block["parent_id"], 0);
+		assert_eq!(block["metrics"]["block_number"], serde_json::json!(7.0));
+	}
+
+	#[test]
+	fn sends_orphans_when_flushed() {
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let config = DatadogConfig::new(&format!("http://{}", listener.local_addr().unwrap())).unwrap();
+		let server = thread::spawn(move || accept(&listener, "200 OK"));
+		let handler = DatadogTraceHandler::new(config, Default::default());
+		let subscriber = ProfilingSubscriber::new_with_handler(Box::new(handler), "test_target");
+		let dispatch = tracing::Dispatch::new(subscriber);
+		tracing::dispatcher::with_default(&dispatch, || {
+			// Never ends before the flush.
+			let sync = tracing::info_span!(target: "test_target", "sync");
+			sync.in_scope(|| tracing::info_span!(target: "test_target", "request").in_scope(|| {}));
+			crate::flush_global();
+			drop(sync);
+		});
+
+		let (_, body) = server.join().unwrap();
+		let traces: serde_json::Value = serde_json::from_str(&body).unwrap();
+		let trace = traces[0].as_array().unwrap();
+		assert_eq!(trace.len(), 1);
+		assert_eq!(trace[0]["resource"], "test_target::request");
+		assert_eq!(trace[0]["trace_id"], trace[0]["parent_id"]);
+	}
+
+	#[test]
+	fn takes_the_orphans_by_age() {
+		let mut pending = Pending::default();
+		let now = Instant::now();
+		pending.children.insert(1, (now - Duration::from_secs(2), Vec::new()));
+		pending.children.insert(2, (now, Vec::new()));
+		let orphans = pending.take_orphans(now - Duration::from_secs(1));
+		assert_eq!(orphans.iter().map(|(id, _)| *id).collect::<Vec<_>>(), vec![1]);
+		assert_eq!(pending.children.len(), 1);
+	}
+}
@@ -0,0 +1,259 @@
+// Copyright 2019-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Deadlines of the import of a block.
+//!
+//! The imports are timed from the creation of their `import_block` span. Every import taking
+//! longer than a deadline is reported by an event on [`IMPORT_DEADLINE_TARGET`], a `WARN` past
+//! the first deadline and an `ERROR` past the later ones, with the time spent in the spans under
+//! the block span, slowest first. While the import is in progress, the event also lists the spans
+//! still open under it, i.e. where the import is stuck, so the deadlines are checked by a
+//! watchdog, see [`ProfilingSubscriber::check_import_deadlines`], rather than when the import
+//! ends.
+//!
+//! [`ProfilingSubscriber::check_import_deadlines`]: crate::ProfilingSubscriber::check_import_deadlines
+
+use std::cmp::Reverse;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+use rustc_hash::FxHashMap;
+
+use crate::{BLOCK_HASH_KEY, BLOCK_NUMBER_KEY, SpanDatum, SpanSummary, Values};
+
+/// Target of the events reporting the imports past their deadlines.
+pub const IMPORT_DEADLINE_TARGET: &str = "import_deadline";
+
+/// Number of targets and names of spans the events keep the time spent in.
+const MAX_SPANS: usize = 10;
+
+struct Import {
+	number: u64,
+	hash: String,
+	started: Instant,
+	/// Number of deadlines the import was reported past.
+	reported: usize,
+	spans: FxHashMap<(String, String), (u64, u64)>,
+}
+
+/// An import past one of its deadlines.
+pub(crate) struct Overdue {
+	/// Id of the block span of the import.
+	pub id: u64,
+	number: u64,
+	hash: String,
+	/// Index of the latest deadline the import is past.
+	deadline: usize,
+	elapsed: Duration,
+	/// Whether the import is over.
+	pub finished: bool,
+	spans: Vec<SpanSummary>,
+}
+
+struct Inner {
+	deadlines: Vec<Duration>,
+	exceeded: Vec<AtomicU64>,
+	imports: Mutex<FxHashMap<u64, Import>>,
+	finished: Mutex<Vec<Overdue>>,
+}
+
+/// The deadlines of the imports of the blocks, and the number of imports past each of them.
+#[derive(Clone)]
+pub struct ImportDeadlines {
+	inner: Arc<Inner>,
+}
+
+impl ImportDeadlines {
+	/// Report the imports past each of the `deadlines`, e.g. 2 and 6 seconds.
+	pub fn new(mut deadlines: Vec<Duration>) -> Self {
+		deadlines.sort();
+		deadlines.dedup();
+		ImportDeadlines {
+			inner: Arc::new(Inner {
+				exceeded: deadlines.iter().map(|_| AtomicU64::new(0)).collect(),
+				deadlines,
+				imports: Default::default(),
+				finished: Default::default(),
+			}),
+		}
+	}
+
+	/// Number of imports that took longer than each of the deadlines, shortest deadline first.
+	pub fn exceeded(&self) -> Vec<(Duration, u64)> {
+		self.inner.deadlines.iter()
+			.zip(&self.inner.exceeded)
+			.map(|(deadline, exceeded)| (*deadline, exceeded.load(Ordering::Relaxed)))
+			.collect()
+	}
+
+	/// Start timing the import of the block span `id`.
+	pub(crate) fn start(&self, id: u64, values: &Values) {
+		self.inner.imports.lock().insert(id, Import {
+			number: values.u64_values.get(BLOCK_NUMBER_KEY).copied().unwrap_or_default(),
+			hash: values.string_values.get(BLOCK_HASH_KEY).cloned().unwrap_or_default(),
+			started: Instant::now(),
+			reported: 0,
+			spans: Default::default(),
+		});
+	}
+
+	/// Add a span closed under the block span `block`.
+	pub(crate) fn record_span(&self, block: u64, span_datum: &SpanDatum) {
+		if let Some(import) = self.inner.imports.lock().get_mut(&block) {
+			let key = (span_datum.target.clone(), span_datum.name.clone());
+			let (count, duration_ns) = import.spans.entry(key).or_default();
+			*count += 1;
+			*duration_ns = duration_ns.saturating_add(span_datum.overall_time.as_nanos() as u64);
+		}
+	}
+
+	/// Stop timing the import of the block span `id`, keeping it for the watchdog to report if
+	/// it is past a deadline.
+	pub(crate) fn finish(&self, id: u64) {
+		let import = match self.inner.imports.lock().remove(&id) {
+			Some(import) => import,
+			None => return,
+		};
+		if let Some(overdue) = self.overdue(id, &import, true) {
+			self.inner.finished.lock().push(overdue);
+		}
+	}
+
+	/// The imports past a deadline they were not reported past yet, and the finished imports
+	/// that were past a deadline.
+	pub(crate) fn take_overdue(&self) -> Vec<Overdue> {
+		let mut overdue = std::mem::take(&mut *self.inner.finished.lock());
+		let mut imports = self.inner.imports.lock();
+		for (id, import) in imports.iter_mut() {
+			if let Some(late) = self.overdue(*id, import, false) {
+				import.reported = late.deadline + 1;
+				overdue.push(late);
+			}
+		}
+		overdue
+	}
+
+	/// Number of deadlines shorter than `elapsed`.
+	fn passed(&self, elapsed: Duration) -> usize {
+		self.inner.deadlines.iter().take_while(|deadline| **deadline < elapsed).count()
+	}
+
+	/// The import, if past a deadline it was not reported past yet or, once `finished`, past any
+	/// deadline. The imports are counted past the deadlines they were not reported past yet.
+	fn overdue(&self, id: u64, import: &Import, finished: bool) -> Option<Overdue> {
+		let elapsed = import.started.elapsed();
+		let passed = self.passed(elapsed);
+		for exceeded in &self.inner.exceeded[import.reported.min(passed)..passed] {
+			exceeded.fetch_add(1, Ordering::Relaxed);
+		}
+		// The finished imports are reported once more, with their whole breakdown.
+		if passed == 0 || (!finished && passed == import.reported) {
+			return None;
+		}
+		let mut spans = import.spans.iter()
+			.map(|((target, name), (count, duration_ns))| SpanSummary {
+				target: target.clone(),
+				name: name.clone(),
+				count: *count,
+				duration_ns: *duration_ns,
+			})
+			.collect::<Vec<_>>();
+		spans.sort_by_key(|span| Reverse(span.duration_ns));
+		spans.truncate(MAX_SPANS);
+		Some(Overdue {
+			id,
+			number: import.number,
+			hash: import.hash.clone(),
+			deadline: passed - 1,
+			elapsed,
+			finished,
+			spans,
+		})
+	}
+
+	/// Report the `overdue` import, whose spans `open` are still open.
+	pub(crate) fn report(&self, overdue: &Overdue, open: &[String]) {
+		let deadline = self.inner.deadlines[overdue.deadline].as_millis() as u64;
+		let elapsed = overdue.elapsed.as_millis() as u64;
+		let spans = serde_json::to_string(&overdue.spans).unwrap_or_default();
+		let open = open.join(", ");
+		let state = if overdue.finished { "took" } else { "still running after" };
+		macro_rules! report {
+			($log:ident, $tracing:ident) => {
+				log::$log!(
+					target: IMPORT_DEADLINE_TARGET,
+					"Import of #{} ({}) {} {}ms, past the deadline of {}ms. Slowest spans: {}{}{}",
+					overdue.number, overdue.hash, state, elapsed, deadline, spans,
+					if open.is_empty() { "" } else { ". Open spans: " }, open,
+				);
+				tracing::$tracing!(
+					target: IMPORT_DEADLINE_TARGET,
+					block_number = overdue.number,
+					block_hash = overdue.hash.as_str(),
+					deadline_ms = deadline,
+					elapsed_ms = elapsed,
+					finished = overdue.finished,
+					spans = spans.as_str(),
+					open = open.as_str(),
+					"Block import past its deadline",
+				);
+			}
+		}
+		if overdue.deadline == 0 {
+			report!(warn, warn);
+		} else {
+			report!(error, error);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn import(deadlines: &ImportDeadlines, id: u64, elapsed: Duration) {
+		let mut values = Values::default();
+		values.u64_values.insert(BLOCK_NUMBER_KEY.to_owned(), id);
+		deadlines.start(id, &values);
+		deadlines.inner.imports.lock().get_mut(&id).unwrap().started -= elapsed;
+	}
+
+	#[test]
+	fn reports_each_deadline_once() {
+		let deadlines = ImportDeadlines::new(vec![Duration::from_secs(6), Duration::from_secs(2)]);
+		import(&deadlines, 1, Duration::from_secs(3));
+		import(&deadlines, 2, Duration::from_millis(1));
+		import(&deadlines, 3, Duration::from_secs(7));
+
+		let mut overdue = deadlines.take_overdue();
+		overdue.sort_by_key(|overdue| overdue.id);
+		let reported: Vec<_> = overdue.iter().map(|o| (o.number, o.deadline, o.finished)).collect();
+		assert_eq!(reported, vec![(1, 0, false), (3, 1, false)]);
+		assert!(deadlines.take_overdue().is_empty());
+
+		// The finished imports are reported with their breakdown, the fast ones are not.
+		deadlines.finish(1);
+		deadlines.finish(2);
+		let reported: Vec<_> = deadlines.take_overdue().iter().map(|o| (o.number, o.finished)).collect();
+		assert_eq!(reported, vec![(1, true)]);
+		assert_eq!(
+			deadlines.exceeded(),
+			vec![(Duration::from_secs(2), 2), (Duration::from_secs(6), 1)],
+		);
+	}
+}
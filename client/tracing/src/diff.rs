@@ -0,0 +1,209 @@
+// Copyright 2019-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Comparison of the traces written by the [`BinaryTraceHandler`](crate::BinaryTraceHandler).
+//!
+//! The spans of each trace are summed up by target and name, e.g. to compare the import of a
+//! block on two runtime versions, or in native and in wasm.
+
+use std::{collections::BTreeMap, io::Read};
+use rustc_hash::FxHashMap;
+use sc_tracing_format::{Record, Span, TraceReader, Value};
+
+use crate::BLOCK_NUMBER_KEY;
+
+/// Number of spans of a target and name and the time spent in them.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SpanStats {
+	/// Number of spans.
+	pub count: u64,
+	/// Total time spent in the spans, in nanoseconds.
+	pub duration_ns: u64,
+}
+
+/// The spans of a trace, by target and name.
+pub type TraceStats = BTreeMap<(String, String), SpanStats>;
+
+/// Difference between the spans of a target and name of two traces.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpanDiff {
+	/// Target of the spans.
+	pub target: String,
+	/// Name of the spans.
+	pub name: String,
+	/// The spans in the first trace, `None` if they were added.
+	pub before: Option<SpanStats>,
+	/// The spans in the second trace, `None` if they were removed.
+	pub after: Option<SpanStats>,
+}
+
+impl SpanDiff {
+	/// Change of the time spent in the spans, in nanoseconds.
+	pub fn delta_ns(&self) -> i128 {
+		let duration = |stats: Option<SpanStats>| stats.map_or(0, |s| s.duration_ns as i128);
+		duration(self.after) - duration(self.before)
+	}
+}
+
+/// Read the trace from `input` and sum up its spans by target and name.
+///
+/// With a `block_number`, only the spans under a span recording this block number are counted.
+pub fn trace_stats<R: Read>(input: R, block_number: Option<u64>) -> sc_tracing_format::Result<TraceStats> {
+	let mut spans = Vec::new();
+	for record in TraceReader::new(input)? {
+		if let Record::Span(span) = record? {
+			spans.push(span);
+		}
+	}
+
+	// Spans are recorded when they close, so the ancestors of a span are only known at the end.
+	let ancestry = BlockAncestry::new(&spans);
+
+	let mut stats = TraceStats::new();
+	for span in spans {
+		if block_number.map_or(true, |number| ancestry.block_of(span.id) == Some(number)) {
+			let entry = stats.entry((span.target, span.name)).or_default();
+			entry.count += 1;
+			entry.duration_ns = entry.duration_ns.saturating_add(span.duration_ns);
+		}
+	}
+	Ok(stats)
+}
+
+/// The blocks the spans of a trace were recorded in.
+pub(crate) struct BlockAncestry {
+	parents: FxHashMap<u64, Option<u64>>,
+	blocks: FxHashMap<u64, u64>,
+}
+
+impl BlockAncestry {
+	/// Index the parents of the `spans`, and the spans recording a block number.
+	pub(crate) fn new(spans: &[Span]) -> Self {
+		BlockAncestry {
+			parents: spans.iter().map(|span| (span.id, span.parent_id)).collect(),
+			blocks: spans.iter()
+				.filter_map(|span| span.fields.iter().find_map(|(name, value)| match value {
+					Value::U64(number) if name == BLOCK_NUMBER_KEY => Some((span.id, *number)),
+					_ => None,
+				}))
+				.collect(),
+		}
+	}
+
+	/// The block number recorded by the span `id` or by its closest ancestor recording one.
+	pub(crate) fn block_of(&self, id: u64) -> Option<u64> {
+		let mut id = Some(id);
+		// Bounded, in case the parents of a corrupted trace form a cycle.
+		for _ in 0..=self.parents.len() {
+			let current = id?;
+			if let Some(number) = self.blocks.get(&current) {
+				return Some(*number);
+			}
+			id = self.parents.get(&current).cloned().flatten();
+		}
+		None
+	}
+}
+
+/// Compare the spans of two traces, the largest changes of the time spent in them first.
+///
+/// The spans of a target and name present in both traces are only reported if their number or
+/// duration changed.
+pub fn diff(before: &TraceStats, after: &TraceStats) -> Vec<SpanDiff> {
+	let mut diffs: Vec<_> = before.keys().chain(after.keys().filter(|key| !before.contains_key(key)))
+		.filter_map(|key| {
+			let (before, after) = (before.get(key).cloned(), after.get(key).cloned());
+			if before == after {
+				return None;
+			}
+			Some(SpanDiff { target: key.0.clone(), name: key.1.clone(), before, after })
+		})
+		.collect();
+	diffs.sort_by_key(|diff| std::cmp::Reverse(diff.delta_ns().abs()));
+	diffs
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sc_tracing_format::{Level, TraceWriter};
+
+	/// The id, parent id, name, duration and block number of a span.
+	type TestSpan<'a> = (u64, Option<u64>, &'a str, u64, Option<u64>);
+
+	fn trace(spans: &[TestSpan]) -> Vec<u8> {
+		let mut writer = TraceWriter::new(Vec::new()).unwrap();
+		for (id, parent_id, name, duration_ns, block_number) in spans {
+			writer.write(&Record::Span(Span {
+				id: *id,
+				parent_id: *parent_id,
+				target: "test".into(),
+				name: name.to_string(),
+				level: Level::Info,
+				start_us: 0,
+				duration_ns: *duration_ns,
+				fields: block_number.iter()
+					.map(|number| (BLOCK_NUMBER_KEY.to_owned(), Value::U64(*number)))
+					.collect(),
+			})).unwrap();
+		}
+		writer.into_inner()
+	}
+
+	fn key(name: &str) -> (String, String) {
+		("test".to_owned(), name.to_owned())
+	}
+
+	#[test]
+	fn sums_up_spans_of_a_block() {
+		let trace = trace(&[
+			(2, Some(1), "execute", 10, None),
+			(3, Some(2), "execute", 5, None),
+			(1, None, "import_block", 20, Some(1)),
+			(5, Some(4), "execute", 7, None),
+			(4, None, "import_block", 30, Some(2)),
+		]);
+
+		let stats = trace_stats(&trace[..], None).unwrap();
+		assert_eq!(stats[&key("execute")], SpanStats { count: 3, duration_ns: 22 });
+		assert_eq!(stats[&key("import_block")], SpanStats { count: 2, duration_ns: 50 });
+
+		let stats = trace_stats(&trace[..], Some(1)).unwrap();
+		assert_eq!(stats[&key("execute")], SpanStats { count: 2, duration_ns: 15 });
+		assert_eq!(stats[&key("import_block")], SpanStats { count: 1, duration_ns: 20 });
+		assert!(trace_stats(&trace[..], Some(3)).unwrap().is_empty());
+	}
+
+	#[test]
+	fn reports_changed_spans() {
+		let before = trace_stats(&trace(&[
+			(1, None, "same", 10, None),
+			(2, None, "slower", 10, None),
+			(3, None, "removed", 5, None),
+		])[..], None).unwrap();
+		let after = trace_stats(&trace(&[
+			(1, None, "same", 10, None),
+			(2, None, "slower", 40, None),
+			(3, None, "added", 20, None),
+		])[..], None).unwrap();
+
+		let diffs = diff(&before, &after);
+		let names: Vec<_> = diffs.iter().map(|diff| (diff.name.as_str(), diff.delta_ns())).collect();
+		assert_eq!(names, vec![("slower", 30), ("added", 20), ("removed", -5)]);
+		assert_eq!(diffs[1].before, None);
+		assert_eq!(diffs[2].after, None);
+	}
+}
@@ -0,0 +1,106 @@
+// Copyright 2019-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Counts, durations and declared weights of the dispatches, by the kind of their origin.
+//!
+//! The extrinsics are dispatched with a `signed`, `none` or `inherent` origin, and the calls
+//! they dispatch in turn with a `root` or `custom` origin, e.g. by `sudo` or the collectives, see
+//! [`ORIGIN_KEY`]. The dispatches of the runtimes not recording their origin are accounted under
+//! [`UNKNOWN_ORIGIN`], and past [`MAX_ORIGINS`] kinds under the [`OTHER_SERIES`].
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use parking_lot::Mutex;
+
+use crate::OTHER_SERIES;
+
+/// Value of the dispatch spans naming the kind of their origin.
+pub const ORIGIN_KEY: &str = "origin";
+
+/// Kind of origin of the dispatch spans without an [`ORIGIN_KEY`].
+pub const UNKNOWN_ORIGIN: &str = "unknown";
+
+/// Number of kinds of origin accounted, the [`OTHER_SERIES`] included.
+pub const MAX_ORIGINS: usize = 16;
+
+/// The dispatches with a kind of origin.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OriginStats {
+	/// Number of dispatches.
+	pub dispatches: u64,
+	/// Time spent in the dispatches.
+	pub time: Duration,
+	/// Sum of the weights declared by the dispatches.
+	pub weight: u64,
+}
+
+/// The dispatches by the kind of their origin.
+#[derive(Clone, Default)]
+pub struct DispatchOrigins {
+	inner: Arc<Mutex<BTreeMap<String, OriginStats>>>,
+}
+
+impl DispatchOrigins {
+	/// No dispatch accounted yet.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Account a dispatch with an origin of `kind` that lasted `time`, having declared `weight`.
+	pub(crate) fn record(&self, kind: &str, time: Duration, weight: u64) {
+		let mut origins = self.inner.lock();
+		let kind = if origins.contains_key(kind) || origins.len() + 1 < MAX_ORIGINS {
+			kind
+		} else {
+			OTHER_SERIES
+		};
+		let stats = origins.entry(kind.to_owned()).or_default();
+		stats.dispatches = stats.dispatches.saturating_add(1);
+		stats.time += time;
+		stats.weight = stats.weight.saturating_add(weight);
+	}
+
+	/// The dispatches of every kind of origin dispatched so far.
+	pub fn origins(&self) -> Vec<(String, OriginStats)> {
+		self.inner.lock().iter().map(|(kind, stats)| (kind.clone(), stats.clone())).collect()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn accounts_the_dispatches_by_origin() {
+		let origins = DispatchOrigins::new();
+		let ms = Duration::from_millis;
+		origins.record("signed", ms(1), 10);
+		origins.record("inherent", ms(2), 20);
+		origins.record("signed", ms(3), 30);
+		for kind in 0..MAX_ORIGINS {
+			origins.record(&kind.to_string(), ms(1), 1);
+		}
+
+		let origins = origins.origins();
+		assert_eq!(origins.len(), MAX_ORIGINS);
+		let stats = |kind: &str| origins.iter().find(|(k, _)| k == kind).map(|(_, stats)| stats.clone());
+		assert_eq!(stats("signed"), Some(OriginStats { dispatches: 2, time: ms(4), weight: 40 }));
+		assert_eq!(stats("inherent"), Some(OriginStats { dispatches: 1, time: ms(2), weight: 20 }));
+		assert_eq!(stats(OTHER_SERIES).map(|stats| stats.dispatches), Some(3));
+	}
+}
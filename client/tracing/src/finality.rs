@@ -0,0 +1,206 @@
+// Copyright 2019-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Rounds, votes, finalized blocks and proofs of the finality gadgets.
+//!
+//! The gadgets, e.g. GRANDPA, report them under the `sp_tracing::FINALITY_TARGET` with the macros
+//! of `sp_tracing`, e.g. `sp_tracing::finality_round`, recording the `gadget` reporting them, so
+//! that all the gadgets are accounted alike. Past [`MAX_GADGETS`] gadgets, and past [`MAX_KINDS`]
+//! kinds of votes or of proofs of a gadget, they are accounted under the [`OTHER_SERIES`].
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+
+use crate::{OTHER_SERIES, Values};
+
+/// Value of the spans and events of the finality gadgets naming the gadget.
+pub const GADGET_KEY: &str = "gadget";
+
+/// Name of the span of the generation or the verification of a finality proof.
+pub const PROOF_SPAN: &str = "proof";
+
+/// Number of gadgets accounted, the [`OTHER_SERIES`] included.
+pub const MAX_GADGETS: usize = 8;
+
+/// Number of kinds of votes, and of proofs, accounted per gadget, the [`OTHER_SERIES`] included.
+pub const MAX_KINDS: usize = 16;
+
+/// The proofs of a kind of a finality gadget.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProofStats {
+	/// Number of proofs.
+	pub proofs: u64,
+	/// Time spent in the proofs.
+	pub time: Duration,
+}
+
+/// What a finality gadget did.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GadgetStats {
+	/// Number of rounds completed.
+	pub rounds: u64,
+	/// The last round completed.
+	pub last_round: u64,
+	/// Time between the completions of the rounds, the first round completed not being timed.
+	pub round_time: Duration,
+	/// Number of votes cast, by kind.
+	pub votes: BTreeMap<String, u64>,
+	/// Number of the last block finalized.
+	pub finalized_number: u64,
+	/// The proofs generated or verified, by kind.
+	pub proofs: BTreeMap<String, ProofStats>,
+}
+
+#[derive(Default)]
+struct Gadget {
+	stats: GadgetStats,
+	last_round_end: Option<Instant>,
+}
+
+/// What the finality gadgets did, by gadget.
+#[derive(Clone, Default)]
+pub struct FinalityMetrics {
+	inner: Arc<Mutex<BTreeMap<String, Gadget>>>,
+}
+
+impl FinalityMetrics {
+	/// Nothing accounted yet.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Account the event of a finality gadget with `values`, emitted at `time`.
+	pub(crate) fn record_event(&self, values: &Values, time: Instant) {
+		let event = values.string_values.get("event").map(String::as_str);
+		let u64_value = |key| values.u64_values.get(key).copied().unwrap_or_default();
+		self.with_gadget(values, |gadget| match event {
+			Some("round") => {
+				let stats = &mut gadget.stats;
+				stats.rounds = stats.rounds.saturating_add(1);
+				stats.last_round = u64_value("round");
+				if let Some(last_round_end) = gadget.last_round_end {
+					stats.round_time += time.saturating_duration_since(last_round_end);
+				}
+				gadget.last_round_end = Some(time);
+			},
+			Some("vote") => {
+				let kind = values.string_values.get("vote").map_or("", String::as_str);
+				*kind_entry(&mut gadget.stats.votes, kind) += 1;
+			},
+			Some("finalized") => gadget.stats.finalized_number = u64_value("number"),
+			_ => {},
+		});
+	}
+
+	/// Account the proof span of a finality gadget with `values`, that lasted `time`.
+	pub(crate) fn record_proof(&self, values: &Values, time: Duration) {
+		let kind = values.string_values.get("kind").map_or("", String::as_str);
+		self.with_gadget(values, |gadget| {
+			let stats = kind_entry(&mut gadget.stats.proofs, kind);
+			stats.proofs = stats.proofs.saturating_add(1);
+			stats.time += time;
+		});
+	}
+
+	fn with_gadget(&self, values: &Values, f: impl FnOnce(&mut Gadget)) {
+		let name = match values.string_values.get(GADGET_KEY) {
+			Some(name) => name.as_str(),
+			None => return,
+		};
+		let mut gadgets = self.inner.lock();
+		let name = if gadgets.contains_key(name) || gadgets.len() + 1 < MAX_GADGETS {
+			name
+		} else {
+			OTHER_SERIES
+		};
+		f(gadgets.entry(name.to_owned()).or_default());
+	}
+
+	/// What every gadget did so far.
+	pub fn gadgets(&self) -> Vec<(String, GadgetStats)> {
+		self.inner.lock().iter().map(|(name, gadget)| (name.clone(), gadget.stats.clone())).collect()
+	}
+}
+
+/// The entry of `kind` in `kinds`, the [`OTHER_SERIES`] one if there are too many kinds.
+fn kind_entry<'a, T: Default>(kinds: &'a mut BTreeMap<String, T>, kind: &str) -> &'a mut T {
+	let kind = if kinds.contains_key(kind) || kinds.len() + 1 < MAX_KINDS {
+		kind
+	} else {
+		OTHER_SERIES
+	};
+	kinds.entry(kind.to_owned()).or_default()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn values(gadget: &str, strings: &[(&str, &str)], numbers: &[(&str, u64)]) -> Values {
+		let mut values = Values::default();
+		values.string_values.insert(GADGET_KEY.to_owned(), gadget.to_owned());
+		for (key, value) in strings {
+			values.string_values.insert(key.to_string(), value.to_string());
+		}
+		for (key, value) in numbers {
+			values.u64_values.insert(key.to_string(), *value);
+		}
+		values
+	}
+
+	#[test]
+	fn accounts_the_gadgets_apart() {
+		let metrics = FinalityMetrics::new();
+		let start = Instant::now();
+		let ms = Duration::from_millis;
+		let round = |round| values("grandpa", &[("event", "round")], &[("round", round)]);
+		metrics.record_event(&round(1), start);
+		metrics.record_event(&round(2), start + ms(30));
+		metrics.record_event(&round(3), start + ms(50));
+		let prevote = values("grandpa", &[("event", "vote"), ("vote", "prevote")], &[("round", 3)]);
+		metrics.record_event(&prevote, start);
+		metrics.record_event(&prevote, start);
+		metrics.record_event(&values("beefy", &[("event", "finalized")], &[("number", 7)]), start);
+		metrics.record_proof(&values("beefy", &[("kind", "verify")], &[]), ms(4));
+		metrics.record_event(&Values::default(), start);
+
+		let gadgets = metrics.gadgets();
+		assert_eq!(gadgets.len(), 2);
+		let (name, grandpa) = &gadgets[1];
+		assert_eq!(name, "grandpa");
+		assert_eq!((grandpa.rounds, grandpa.last_round, grandpa.round_time), (3, 3, ms(50)));
+		assert_eq!(grandpa.votes.get("prevote"), Some(&2));
+		let (name, beefy) = &gadgets[0];
+		assert_eq!(name, "beefy");
+		assert_eq!(beefy.finalized_number, 7);
+		assert_eq!(beefy.proofs.get("verify"), Some(&ProofStats { proofs: 1, time: ms(4) }));
+	}
+
+	#[test]
+	fn accounts_the_other_kinds_together() {
+		let metrics = FinalityMetrics::new();
+		for kind in 0..MAX_KINDS + 1 {
+			let vote = values("grandpa", &[("event", "vote"), ("vote", &kind.to_string())], &[]);
+			metrics.record_event(&vote, Instant::now());
+		}
+		let votes = metrics.gadgets().remove(0).1.votes;
+		assert_eq!(votes.len(), MAX_KINDS);
+		assert_eq!(votes.get(OTHER_SERIES), Some(&2));
+	}
+}
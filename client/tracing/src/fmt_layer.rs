@@ -0,0 +1,196 @@
+// Copyright 2019-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Formatting of the events as lines of text, as a layer of the `ProfilingSubscriber`.
+//!
+//! `tracing_subscriber::fmt::Layer` looks the spans up in a `Registry`, which the
+//! `ProfilingSubscriber` is not, so [`FmtLayer`] keeps the names and parents of the open spans
+//! itself. Every event is written as a line with its time, level, the names of the spans it is in,
+//! its target and its fields, e.g.
+//! `Aug 20 10:00:00.000  INFO import_block:apply_extrinsic: sync: Imported dest="bob"`.
+
+use std::{fmt::Write as _, io::{self, Write as _}};
+
+use parking_lot::Mutex;
+use rustc_hash::FxHashMap;
+use tracing::{Event, span::{Attributes, Id}};
+use tracing_subscriber::{
+	fmt::{MakeWriter, time::{FormatTime, SystemTime}},
+	layer::{Context, Layer},
+};
+
+use crate::{ProfilingSubscriber, Redaction, Values};
+
+/// Layer writing the events to `stderr`, or to the writers of a `MakeWriter`.
+///
+/// ```
+/// use sc_tracing::{FmtLayer, TracingBuilder};
+///
+/// let handle = TracingBuilder::new()
+///     .with_targets("sync=info")
+///     .with_layer(FmtLayer::new().with_writer(std::io::stdout))
+///     .build();
+/// # let _ = handle;
+/// ```
+pub struct FmtLayer<W = fn() -> io::Stderr> {
+	make_writer: W,
+	redaction: Redaction,
+	/// The names and parents of the open spans.
+	spans: Mutex<FxHashMap<Id, (&'static str, Option<Id>)>>,
+}
+
+impl FmtLayer {
+	/// A layer writing to `stderr`.
+	pub fn new() -> Self {
+		FmtLayer { make_writer: io::stderr, redaction: Default::default(), spans: Default::default() }
+	}
+}
+
+impl Default for FmtLayer {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl<W> FmtLayer<W> {
+	/// Write to the writers of `make_writer` instead, one per event.
+	pub fn with_writer<W2: MakeWriter>(self, make_writer: W2) -> FmtLayer<W2> {
+		FmtLayer { make_writer, redaction: self.redaction, spans: self.spans }
+	}
+
+	/// Redact the fields of the events with `redaction` first, e.g. the one of the subscriber.
+	pub fn with_redaction(self, redaction: Redaction) -> Self {
+		FmtLayer { redaction, ..self }
+	}
+
+	/// The names of the spans `id` is in, outermost first.
+	fn span_names(&self, id: Option<&Id>) -> Vec<&'static str> {
+		let spans = self.spans.lock();
+		let mut names = Vec::new();
+		let mut id = id;
+		while let Some((name, parent_id)) = id.and_then(|id| spans.get(id)) {
+			names.push(*name);
+			id = parent_id.as_ref();
+		}
+		names.reverse();
+		names
+	}
+}
+
+impl<W: MakeWriter + 'static> Layer<ProfilingSubscriber> for FmtLayer<W> {
+	fn new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, ProfilingSubscriber>) {
+		let parent_id = match attrs.parent() {
+			Some(parent_id) => Some(parent_id.clone()),
+			None if attrs.is_contextual() => ctx.current_span().id().cloned(),
+			None => None,
+		};
+		self.spans.lock().insert(id.clone(), (attrs.metadata().name(), parent_id));
+	}
+
+	fn on_close(&self, id: Id, _ctx: Context<'_, ProfilingSubscriber>) {
+		self.spans.lock().remove(&id);
+	}
+
+	fn on_event(&self, event: &Event<'_>, ctx: Context<'_, ProfilingSubscriber>) {
+		let mut values = Values::default();
+		event.record(&mut values);
+		let parent_id = match event.parent() {
+			Some(parent_id) => Some(parent_id.clone()),
+			None if event.is_contextual() => ctx.current_span().id().cloned(),
+			None => None,
+		};
+		let spans = self.span_names(parent_id.as_ref());
+
+		let metadata = event.metadata();
+		let mut line = String::new();
+		let _ = SystemTime.format_time(&mut line);
+		let _ = write!(line, " {:>5} ", metadata.level());
+		if !spans.is_empty() {
+			let _ = write!(line, "{}: ", spans.join(":"));
+		}
+		let _ = write!(line, "{}:", metadata.target());
+		write_values(&mut line, self.redaction.redact(&values));
+		line.push('\n');
+		let _ = self.make_writer.make_writer().write_all(line.as_bytes());
+	}
+}
+
+/// Append the message, then the other fields sorted by name.
+fn write_values(line: &mut String, mut values: Values) {
+	if let Some(message) = values.string_values.remove("message") {
+		let _ = write!(line, " {}", message);
+	}
+	let mut fields: Vec<(String, String)> = values.bool_values.into_iter().map(|(k, v)| (k, v.to_string()))
+		.chain(values.i64_values.into_iter().map(|(k, v)| (k, v.to_string())))
+		.chain(values.u64_values.into_iter().map(|(k, v)| (k, v.to_string())))
+		.chain(values.string_values.into_iter().map(|(k, v)| (k, format!("{:?}", v))))
+		.collect();
+	fields.sort();
+	for (name, value) in fields {
+		let _ = write!(line, " {}={}", name, value);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::sync::Arc;
+	use crate::TracingBuilder;
+
+	#[derive(Clone, Default)]
+	struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+	impl io::Write for Buffer {
+		fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+			self.0.lock().extend_from_slice(buf);
+			Ok(buf.len())
+		}
+
+		fn flush(&mut self) -> io::Result<()> {
+			Ok(())
+		}
+	}
+
+	#[test]
+	fn formats_events_in_their_spans() {
+		let buffer = Buffer::default();
+		let writer = buffer.clone();
+		let layer = FmtLayer::new()
+			.with_writer(move || writer.clone())
+			.with_redaction(Redaction::new("dest=drop").unwrap());
+		let handle = TracingBuilder::new()
+			.with_targets("fmt_layer")
+			.with_layer(layer)
+			.build();
+		tracing::dispatcher::with_default(handle.dispatch(), || {
+			let span = tracing::info_span!(target: "fmt_layer", "import_block");
+			span.in_scope(|| tracing::info_span!(target: "fmt_layer", "apply").in_scope(|| {
+				tracing::warn!(target: "fmt_layer", amount = 42u64, dest = "bob", ok = true, "Transferred");
+			}));
+			tracing::info!(target: "fmt_layer", name = "alice", "Outside");
+		});
+
+		let output = String::from_utf8(buffer.0.lock().clone()).unwrap();
+		let lines: Vec<_> = output.lines().collect();
+		assert_eq!(lines.len(), 2);
+		assert!(
+			lines[0].ends_with("  WARN import_block:apply: fmt_layer: Transferred amount=42 ok=true"),
+			"{}",
+			lines[0],
+		);
+		assert!(lines[1].ends_with("  INFO fmt_layer: Outside name=\"alice\""), "{}", lines[1]);
+	}
+}
@@ -0,0 +1,420 @@
+// Copyright 2019-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Export of the spans and events to Graylog, as GELF messages over UDP or TCP.
+//!
+//! Every span and event is a message, its values being additional fields along with its target,
+//! id and parent. The short message of an event is its message, and that of a span is
+//! `<target>::<name>`, its duration being the `_duration_ns` field.
+//!
+//! The messages are sent by a background thread, the messages exceeding [`MAX_QUEUED`] being
+//! dropped. Over UDP, they are compressed with gzip unless disabled, then split into chunks if
+//! they don't fit in a datagram, the messages needing more than [`MAX_CHUNKS`] chunks being
+//! dropped. Over TCP, they are sent uncompressed and delimited by null bytes, as Graylog expects.
+
+use std::{
+	io::{self, Write},
+	net::{SocketAddr, TcpStream, ToSocketAddrs, UdpSocket},
+	sync::mpsc,
+	thread,
+	time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use codec::Encode;
+use flate2::{Compression, write::GzEncoder};
+use sc_telemetry::ExporterHealth;
+use serde_json::{Map, Value};
+use sp_core::twox_64;
+use tracing::Level;
+
+use crate::{Redaction, SpanDatum, TraceEvent, TraceHandler, Values, udp::{self, MAX_PACKET_SIZE}};
+
+/// Maximum number of messages waiting to be sent, further messages are dropped.
+pub const MAX_QUEUED: usize = 10_000;
+
+/// Maximum number of chunks of a message sent over UDP.
+pub const MAX_CHUNKS: usize = 128;
+
+/// Magic bytes, message id, sequence number and sequence count of a chunk.
+const CHUNK_HEADER_SIZE: usize = 12;
+
+/// Maximum time `flush` waits for the queued messages to be sent.
+const FLUSH_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Maximum time to connect to the TCP endpoint.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Maximum time to write a message to the TCP endpoint, after which it is reconnected.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy)]
+enum Transport {
+	Udp(SocketAddr),
+	Tcp(SocketAddr),
+}
+
+/// Where to send the messages, and how to describe them.
+#[derive(Debug, Clone)]
+pub struct GelfConfig {
+	transport: Transport,
+	/// Host of the messages, e.g. the node name.
+	pub host: String,
+	/// Additional fields of all the messages, e.g. `chain`, without the `_` prefix.
+	pub fields: Vec<(String, String)>,
+	/// Whether the messages sent over UDP are compressed with gzip.
+	pub compress: bool,
+}
+
+impl GelfConfig {
+	/// Parses an endpoint like `udp://localhost:12201` or `tcp://localhost:12201`.
+	pub fn new(endpoint: &str) -> Result<Self, String> {
+		let invalid = || format!(
+			"Invalid GELF endpoint `{}`, expected `udp://<host>:<port>` or `tcp://<host>:<port>`",
+			endpoint,
+		);
+		let addr = |rest: &str| rest.to_socket_addrs().ok()
+			.and_then(|mut addrs| addrs.next())
+			.ok_or_else(invalid);
+		let transport = if endpoint.starts_with("udp://") {
+			Transport::Udp(addr(&endpoint["udp://".len()..])?)
+		} else if endpoint.starts_with("tcp://") {
+			Transport::Tcp(addr(&endpoint["tcp://".len()..])?)
+		} else {
+			return Err(invalid());
+		};
+		Ok(GelfConfig { transport, host: "substrate".into(), fields: Vec::new(), compress: true })
+	}
+}
+
+enum Message {
+	Gelf(Vec<u8>),
+	Flush(mpsc::SyncSender<()>),
+}
+
+/// TraceHandler sending the spans and events to Graylog
+pub struct GelfTraceHandler {
+	sender: mpsc::SyncSender<Message>,
+	health: ExporterHealth,
+	host: String,
+	fields: Vec<(String, String)>,
+	redaction: Redaction,
+}
+
+impl GelfTraceHandler {
+	/// Create a handler sending to the configured endpoint, the values of the spans and events
+	/// being redacted with `redaction` first.
+	pub fn new(config: GelfConfig, redaction: Redaction) -> Self {
+		let (sender, receiver) = mpsc::sync_channel(MAX_QUEUED);
+		let (transport, compress) = (config.transport, config.compress);
+		let health = ExporterHealth::new("gelf");
+		let sender_health = health.clone();
+		let spawned = thread::Builder::new()
+			.name("gelf-sender".into())
+			.spawn(move || Sender::new(transport, compress, sender_health).run(receiver));
+		if let Err(e) = spawned {
+			log::warn!(target: "tracing", "Unable to start the GELF sender: {}", e);
+		}
+		GelfTraceHandler { sender, health, host: config.host, fields: config.fields, redaction }
+	}
+
+	/// The GELF message, its redacted values becoming additional fields.
+	fn message(&self, short_message: String, time: SystemTime, level: &Level, values: Values) -> Map<String, Value> {
+		let mut message = Map::new();
+		for (key, value) in &self.fields {
+			message.insert(field_name(key), value.clone().into());
+		}
+		for (key, value) in values.bool_values {
+			message.insert(field_name(&key), value.to_string().into());
+		}
+		for (key, value) in values.i64_values {
+			message.insert(field_name(&key), value.into());
+		}
+		for (key, value) in values.u64_values {
+			message.insert(field_name(&key), value.into());
+		}
+		for (key, value) in values.string_values {
+			message.insert(field_name(&key), value.into());
+		}
+		let timestamp = time.duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as f64 / 1000.0;
+		message.insert("version".into(), "1.1".into());
+		message.insert("host".into(), self.host.clone().into());
+		message.insert("short_message".into(), short_message.into());
+		message.insert("timestamp".into(), timestamp.into());
+		message.insert("level".into(), severity(level).into());
+		message
+	}
+
+	fn send(&self, message: Map<String, Value>) {
+		let message = serde_json::to_vec(&message).unwrap_or_default();
+		if self.sender.try_send(Message::Gelf(message)).is_ok() {
+			self.health.record_queued(1);
+		} else {
+			self.health.record_dropped(1);
+		}
+	}
+}
+
+impl TraceHandler for GelfTraceHandler {
+	fn handle_span(&self, span_datum: SpanDatum) {
+		let start = span_datum.start_wall_time;
+		let short_message = format!("{}::{}", span_datum.target, span_datum.name);
+		let values = self.redaction.redact(&span_datum.values);
+		let mut message = self.message(short_message, start, &span_datum.level, values);
+		message.insert("_target".into(), span_datum.target.into());
+		message.insert("_span_id".into(), span_datum.id.into_u64().into());
+		if let Some(parent_id) = span_datum.parent_id {
+			message.insert("_parent_id".into(), parent_id.into_u64().into());
+		}
+		message.insert("_duration_ns".into(), (span_datum.overall_time.as_nanos() as u64).into());
+		self.send(message);
+	}
+
+	fn handle_event(&self, event: TraceEvent) {
+		let mut values = self.redaction.redact(&event.values);
+		let short_message = values.string_values.remove("message")
+			.unwrap_or_else(|| event.name.to_owned());
+		let mut message = self.message(short_message, event.time, &event.level, values);
+		message.insert("_target".into(), event.target.into());
+		if let Some(parent_id) = event.parent_id {
+			message.insert("_parent_id".into(), parent_id.into_u64().into());
+		}
+		self.send(message);
+	}
+
+	fn flush(&self) {
+		let (done, flushed) = mpsc::sync_channel(1);
+		if self.sender.send(Message::Flush(done)).is_ok() {
+			let _ = flushed.recv_timeout(FLUSH_TIMEOUT);
+		}
+	}
+}
+
+/// The syslog severity of `level`.
+fn severity(level: &Level) -> u8 {
+	match *level {
+		Level::ERROR => 3,
+		Level::WARN => 4,
+		Level::INFO => 6,
+		Level::DEBUG | Level::TRACE => 7,
+	}
+}
+
+/// The name of an additional field: `_` followed by letters, digits, underscores, dashes and
+/// dots, `_id` being reserved.
+fn field_name(key: &str) -> String {
+	let name: String = std::iter::once('_')
+		.chain(key.chars().map(|c| if c.is_ascii_alphanumeric() || "_-.".contains(c) { c } else { '_' }))
+		.collect();
+	if name == "_id" {
+		"_id_".into()
+	} else {
+		name
+	}
+}
+
+/// Split a message in chunks fitting in a datagram, if it needs more than one.
+fn chunks(id: u64, message: &[u8]) -> Option<Vec<Vec<u8>>> {
+	if message.len() <= MAX_PACKET_SIZE {
+		return Some(vec![message.to_vec()]);
+	}
+	let parts: Vec<_> = message.chunks(MAX_PACKET_SIZE - CHUNK_HEADER_SIZE).collect();
+	if parts.len() > MAX_CHUNKS {
+		return None;
+	}
+	Some(parts.iter().enumerate().map(|(sequence, part)| {
+		let mut chunk = Vec::with_capacity(CHUNK_HEADER_SIZE + part.len());
+		chunk.extend_from_slice(&[0x1e, 0x0f]);
+		chunk.extend_from_slice(&id.to_be_bytes());
+		chunk.push(sequence as u8);
+		chunk.push(parts.len() as u8);
+		chunk.extend_from_slice(part);
+		chunk
+	}).collect())
+}
+
+/// Sends the messages, from a background thread.
+struct Sender {
+	transport: Transport,
+	compress: bool,
+	udp: Option<UdpSocket>,
+	tcp: Option<TcpStream>,
+	/// Mixed into the ids of the chunked messages, unique to this sender.
+	seed: u128,
+	sent: u64,
+	health: ExporterHealth,
+}
+
+impl Sender {
+	fn new(transport: Transport, compress: bool, health: ExporterHealth) -> Self {
+		let seed = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos();
+		Sender { transport, compress, udp: None, tcp: None, seed, sent: 0, health }
+	}
+
+	fn run(mut self, receiver: mpsc::Receiver<Message>) {
+		for message in receiver {
+			match message {
+				Message::Gelf(message) => {
+					self.health.record_dequeued(1);
+					let start = Instant::now();
+					match self.send(&message) {
+						Ok(()) => self.health.record_sent(1, start.elapsed()),
+						Err(e) => {
+							log::debug!(target: "tracing", "Unable to send a message to Graylog: {}", e);
+							self.health.record_dropped(1);
+						},
+					}
+				},
+				Message::Flush(done) => {
+					let _ = done.send(());
+				},
+			}
+		}
+	}
+
+	fn send(&mut self, message: &[u8]) -> io::Result<()> {
+		self.sent += 1;
+		match self.transport {
+			Transport::Udp(addr) => {
+				let message = if self.compress {
+					let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+					encoder.write_all(message)?;
+					encoder.finish()?
+				} else {
+					message.to_vec()
+				};
+				let id = u64::from_le_bytes(twox_64(&(self.seed, self.sent).encode()));
+				let chunks = chunks(id, &message).ok_or_else(|| io::Error::new(
+					io::ErrorKind::InvalidData,
+					format!("{} bytes don't fit in {} chunks", message.len(), MAX_CHUNKS),
+				))?;
+				if self.udp.is_none() {
+					self.udp = Some(udp::connect(addr)?);
+				}
+				let socket = self.udp.as_ref().expect("connected above; qed");
+				for chunk in chunks {
+					socket.send(&chunk)?;
+				}
+				Ok(())
+			},
+			Transport::Tcp(addr) => {
+				// Reconnect once if the connection was closed since the previous message.
+				for retry in 0..2 {
+					if self.tcp.is_none() {
+						let stream = TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT)?;
+						stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+						self.tcp = Some(stream);
+					}
+					let stream = self.tcp.as_mut().expect("connected above; qed");
+					match stream.write_all(message).and_then(|_| stream.write_all(&[0])) {
+						Ok(()) => return Ok(()),
+						Err(e) => {
+							// The next message reconnects, if this one doesn't.
+							self.tcp = None;
+							self.health.record_reconnect();
+							if retry > 0 {
+								return Err(e);
+							}
+						},
+					}
+				}
+				Ok(())
+			},
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::io::Read;
+	use flate2::read::GzDecoder;
+
+	fn event(message: &str) -> TraceEvent {
+		let mut values = Values::new();
+		values.u64_values.insert("amount".into(), 42);
+		values.string_values.insert("message".into(), message.into());
+		values.string_values.insert("id".into(), "1".into());
+		TraceEvent {
+			name: "event",
+			target: "test_target".into(),
+			level: Level::WARN,
+			values,
+			parent_id: None,
+			time: UNIX_EPOCH + Duration::from_millis(1500),
+		}
+	}
+
+	#[test]
+	fn sends_compressed_messages_over_udp() {
+		let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+		let mut config = GelfConfig::new(&format!("udp://{}", socket.local_addr().unwrap())).unwrap();
+		config.host = "alice".into();
+		config.fields.push(("chain".into(), "dev".into()));
+		let handler = GelfTraceHandler::new(config, Default::default());
+		handler.handle_event(event("Imported #42"));
+		handler.flush();
+
+		let mut datagram = [0; MAX_PACKET_SIZE];
+		let len = socket.recv(&mut datagram).unwrap();
+		let mut message = String::new();
+		GzDecoder::new(&datagram[..len]).read_to_string(&mut message).unwrap();
+		let message: Value = serde_json::from_str(&message).unwrap();
+		assert_eq!(message["version"], "1.1");
+		assert_eq!(message["host"], "alice");
+		assert_eq!(message["short_message"], "Imported #42");
+		assert_eq!(message["timestamp"], serde_json::json!(1.5));
+		assert_eq!(message["level"], 4);
+		assert_eq!(message["_chain"], "dev");
+		assert_eq!(message["_target"], "test_target");
+		assert_eq!(message["_amount"], 42);
+		assert_eq!(message["_id_"], "1");
+		assert!(message.get("_message").is_none());
+	}
+
+	#[test]
+	fn sends_delimited_messages_over_tcp() {
+		let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+		let config = GelfConfig::new(&format!("tcp://{}", listener.local_addr().unwrap())).unwrap();
+		let handler = GelfTraceHandler::new(config, Default::default());
+		handler.handle_event(event("first"));
+		handler.handle_event(event("second"));
+		handler.flush();
+		drop(handler);
+
+		let mut received = Vec::new();
+		listener.accept().unwrap().0.read_to_end(&mut received).unwrap();
+		let messages: Vec<Value> = received.split(|b| *b == 0)
+			.filter(|message| !message.is_empty())
+			.map(|message| serde_json::from_slice(message).unwrap())
+			.collect();
+		assert_eq!(messages.len(), 2);
+		assert_eq!(messages[1]["short_message"], "second");
+	}
+
+	#[test]
+	fn chunks_large_messages() {
+		assert_eq!(chunks(1, b"small").unwrap(), vec![b"small".to_vec()]);
+
+		let message = vec![7; (MAX_PACKET_SIZE - CHUNK_HEADER_SIZE) * 2 + 1];
+		let chunks = chunks(0x0102, &message).unwrap();
+		assert_eq!(chunks.len(), 3);
+		assert!(chunks.iter().all(|chunk| chunk.len() <= MAX_PACKET_SIZE));
+		assert_eq!(&chunks[2][..CHUNK_HEADER_SIZE], &[0x1e, 0x0f, 0, 0, 0, 0, 0, 0, 1, 2, 2, 3]);
+		assert_eq!(chunks.iter().map(|chunk| chunk.len() - CHUNK_HEADER_SIZE).sum::<usize>(), message.len());
+
+		let message = vec![7; (MAX_PACKET_SIZE - CHUNK_HEADER_SIZE) * MAX_CHUNKS + 1];
+		assert!(super::chunks(1, &message).is_none());
+	}
+}
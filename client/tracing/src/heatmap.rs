@@ -0,0 +1,305 @@
+// Copyright 2019-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Heatmap of the storage accesses in the traces written by the
+//! [`BinaryTraceHandler`](crate::BinaryTraceHandler).
+//!
+//! The accesses are the events of the [`STORAGE_ACCESS_TARGET`], aggregated by the prefix of
+//! their keys. Prefixes of 32 bytes are the pallets and their storage items, as the keys of the
+//! storage items start with the hashes of the pallet and item names.
+
+use std::{io::Read, ops::RangeInclusive};
+use rustc_hash::{FxHashMap, FxHashSet};
+use sc_tracing_format::{Event, Record, TraceReader, Value};
+use sp_tracing::STORAGE_ACCESS_TARGET;
+
+use crate::diff::BlockAncestry;
+
+/// Average number of bytes per access above which the values of a prefix are large.
+pub const LARGE_VALUE_BYTES: u64 = 1024;
+
+/// Number of distinct keys above which a prefix rarely accessed per key is a lazy migration
+/// candidate.
+pub const LAZY_MIGRATION_KEYS: u64 = 100;
+
+/// What the accesses of a prefix suggest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeatHint {
+	/// At least 90% of the accesses are reads, and the keys are read more than once per block:
+	/// caching the values would save the repeated reads.
+	Cache,
+	/// Many keys, each accessed about once: a migration of the prefix would touch much more than
+	/// the blocks do, so it could be applied to the keys as they are accessed.
+	LazyMigration,
+	/// The values read and written are large on average: splitting them, e.g. in a map, would
+	/// spare accessing them as a whole.
+	Layout,
+}
+
+impl std::fmt::Display for HeatHint {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			HeatHint::Cache => write!(f, "cache"),
+			HeatHint::LazyMigration => write!(f, "lazy-migration"),
+			HeatHint::Layout => write!(f, "layout"),
+		}
+	}
+}
+
+/// The storage accesses under a prefix of the keys.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct PrefixHeat {
+	/// Storage key of the child trie, in hex, `None` for the main trie.
+	pub child: Option<String>,
+	/// Prefix of the keys, in hex.
+	pub prefix: String,
+	/// Number of reads.
+	pub reads: u64,
+	/// Number of writes, including removals.
+	pub writes: u64,
+	/// Number of bytes read.
+	pub read_bytes: u64,
+	/// Number of bytes written.
+	pub written_bytes: u64,
+	/// Number of distinct keys accessed.
+	pub keys: u64,
+	/// Number of distinct blocks the keys were accessed in.
+	pub blocks: u64,
+}
+
+impl PrefixHeat {
+	/// Number of reads and writes.
+	pub fn accesses(&self) -> u64 {
+		self.reads.saturating_add(self.writes)
+	}
+
+	/// What the accesses of the prefix suggest, if anything.
+	pub fn hints(&self) -> Vec<HeatHint> {
+		let accesses = self.accesses().max(1);
+		let keys = self.keys.max(1);
+		let mut hints = Vec::new();
+		if self.reads >= accesses * 9 / 10 && self.reads > keys * self.blocks.max(1) {
+			hints.push(HeatHint::Cache);
+		}
+		if self.keys >= LAZY_MIGRATION_KEYS && accesses < keys * 2 {
+			hints.push(HeatHint::LazyMigration);
+		}
+		if self.read_bytes.saturating_add(self.written_bytes) / accesses >= LARGE_VALUE_BYTES {
+			hints.push(HeatHint::Layout);
+		}
+		hints
+	}
+}
+
+#[derive(Default)]
+struct Heat {
+	stats: PrefixHeat,
+	keys: FxHashSet<String>,
+	blocks: FxHashSet<u64>,
+}
+
+/// Aggregates the storage accesses of traces by the prefix of their keys.
+pub struct StorageHeatmap {
+	prefix_len: usize,
+	blocks: Option<RangeInclusive<u64>>,
+	prefixes: FxHashMap<(Option<String>, String), Heat>,
+}
+
+impl StorageHeatmap {
+	/// Aggregate the accesses by the first `prefix_len` bytes of their keys.
+	///
+	/// With `blocks`, only the accesses of the imports of these blocks are aggregated.
+	pub fn new(prefix_len: usize, blocks: Option<RangeInclusive<u64>>) -> Self {
+		StorageHeatmap {
+			prefix_len,
+			blocks,
+			prefixes: Default::default(),
+		}
+	}
+
+	/// Read the trace from `input` and aggregate its storage accesses.
+	///
+	/// Traces of the same blocks should not be read twice, as their accesses would be counted
+	/// twice.
+	pub fn read_trace<R: Read>(&mut self, input: R) -> sc_tracing_format::Result<()> {
+		let mut spans = Vec::new();
+		let mut events = Vec::new();
+		for record in TraceReader::new(input)? {
+			match record? {
+				Record::Span(span) => spans.push(span),
+				Record::Event(event) if event.target == STORAGE_ACCESS_TARGET => events.push(event),
+				Record::Event(_) => {},
+			}
+		}
+
+		// The events are recorded before the spans they happened in close.
+		let ancestry = BlockAncestry::new(&spans);
+		for event in events {
+			let block = event.parent_id.and_then(|id| ancestry.block_of(id));
+			match (&self.blocks, block) {
+				(None, _) => {},
+				(Some(range), Some(block)) if range.contains(&block) => {},
+				_ => continue,
+			}
+			self.record(&event, block);
+		}
+		Ok(())
+	}
+
+	fn record(&mut self, event: &Event, block: Option<u64>) {
+		let field = |name: &str| event.fields.iter()
+			.find_map(|(field, value)| if field == name { Some(value) } else { None });
+		let key = match field("key") {
+			Some(Value::Str(key)) => key,
+			_ => return,
+		};
+		let child = match field("child") {
+			Some(Value::Str(child)) => Some(child.clone()),
+			_ => None,
+		};
+		let write = field("write") == Some(&Value::Bool(true));
+		let len = match field("len") {
+			Some(Value::U64(len)) => *len,
+			_ => 0,
+		};
+
+		// The keys are in hex, two characters per byte.
+		let prefix = &key[..key.len().min(self.prefix_len * 2)];
+		let heat = self.prefixes.entry((child, prefix.to_owned())).or_default();
+		if write {
+			heat.stats.writes += 1;
+			heat.stats.written_bytes = heat.stats.written_bytes.saturating_add(len);
+		} else {
+			heat.stats.reads += 1;
+			heat.stats.read_bytes = heat.stats.read_bytes.saturating_add(len);
+		}
+		if !heat.keys.contains(key) {
+			heat.keys.insert(key.clone());
+		}
+		if let Some(block) = block {
+			heat.blocks.insert(block);
+		}
+	}
+
+	/// The prefixes accessed, the most accessed first.
+	pub fn ranked(&self) -> Vec<PrefixHeat> {
+		let mut ranked: Vec<_> = self.prefixes.iter()
+			.map(|((child, prefix), heat)| PrefixHeat {
+				child: child.clone(),
+				prefix: prefix.clone(),
+				keys: heat.keys.len() as u64,
+				blocks: heat.blocks.len() as u64,
+				..heat.stats.clone()
+			})
+			.collect();
+		ranked.sort_by(|a, b| b.accesses().cmp(&a.accesses())
+			.then_with(|| (b.read_bytes + b.written_bytes).cmp(&(a.read_bytes + a.written_bytes)))
+			.then_with(|| (&a.child, &a.prefix).cmp(&(&b.child, &b.prefix)))
+		);
+		ranked
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sc_tracing_format::{Level, Span, TraceWriter};
+	use crate::BLOCK_NUMBER_KEY;
+
+	fn access(parent_id: u64, key: &str, write: bool, len: u64) -> Record {
+		Record::Event(Event {
+			parent_id: Some(parent_id),
+			target: STORAGE_ACCESS_TARGET.into(),
+			name: "event".into(),
+			level: Level::Trace,
+			time_us: 0,
+			fields: vec![
+				("method".into(), Value::Str("get".into())),
+				("write".into(), Value::Bool(write)),
+				("len".into(), Value::U64(len)),
+				("key".into(), Value::Str(key.into())),
+			],
+		})
+	}
+
+	fn block(id: u64, number: u64) -> Record {
+		Record::Span(Span {
+			id,
+			parent_id: None,
+			target: "test".into(),
+			name: "import_block".into(),
+			level: Level::Info,
+			start_us: 0,
+			duration_ns: 0,
+			fields: vec![(BLOCK_NUMBER_KEY.into(), Value::U64(number))],
+		})
+	}
+
+	fn trace(records: Vec<Record>) -> Vec<u8> {
+		let mut writer = TraceWriter::new(Vec::new()).unwrap();
+		for record in records {
+			writer.write(&record).unwrap();
+		}
+		writer.into_inner()
+	}
+
+	#[test]
+	fn ranks_the_prefixes_of_a_block_range() {
+		let trace = trace(vec![
+			access(1, "aa01", false, 4),
+			access(1, "aa01", false, 4),
+			access(1, "aa01", false, 4),
+			access(1, "bb01", true, 2048),
+			block(1, 1),
+			access(2, "aa02", false, 4),
+			block(2, 2),
+			access(3, "bb02", true, 8),
+			block(3, 3),
+		]);
+
+		let mut heatmap = StorageHeatmap::new(1, Some(1..=2));
+		heatmap.read_trace(&trace[..]).unwrap();
+		let ranked = heatmap.ranked();
+		assert_eq!(ranked, vec![
+			PrefixHeat {
+				child: None,
+				prefix: "aa".into(),
+				reads: 4,
+				writes: 0,
+				read_bytes: 16,
+				written_bytes: 0,
+				keys: 2,
+				blocks: 2,
+			},
+			PrefixHeat {
+				child: None,
+				prefix: "bb".into(),
+				reads: 0,
+				writes: 1,
+				read_bytes: 0,
+				written_bytes: 2048,
+				keys: 1,
+				blocks: 1,
+			},
+		]);
+		assert_eq!(ranked[0].hints(), vec![]);
+		assert_eq!(ranked[1].hints(), vec![HeatHint::Layout]);
+
+		let mut heatmap = StorageHeatmap::new(1, Some(1..=1));
+		heatmap.read_trace(&trace[..]).unwrap();
+		assert_eq!(heatmap.ranked()[0].hints(), vec![HeatHint::Cache]);
+	}
+}
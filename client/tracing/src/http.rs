@@ -0,0 +1,203 @@
+// Copyright 2019-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! HTTP client posting the traces to external services, e.g. Loki and Sentry.
+//!
+//! The requests are made over HTTP or HTTPS by a hyper client, on a runtime of its own started
+//! by the first of them, so that the exporters don't need to run on a runtime.
+
+use std::{io, sync::mpsc, time::Duration};
+
+use hyper::{Body, Client as HyperClient, Request, client::HttpConnector, header};
+use hyper_rustls::HttpsConnector;
+use parking_lot::Mutex;
+
+/// Timeout of the requests, connection included.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+type Client = HyperClient<HttpsConnector<HttpConnector>, Body>;
+
+/// The client shared by the endpoints, and the runtime it runs on.
+struct Shared {
+	client: Client,
+	handle: tokio::runtime::Handle,
+	/// Never shut down, only kept for the client to keep running.
+	_runtime: Mutex<tokio::runtime::Runtime>,
+}
+
+impl Shared {
+	fn start() -> io::Result<Self> {
+		let runtime = tokio::runtime::Builder::new()
+			.threaded_scheduler()
+			.core_threads(1)
+			.enable_all()
+			.thread_name("tracing-http")
+			.build()?;
+		let handle = runtime.handle().clone();
+		// The connector must be created within the runtime it runs on.
+		let client = handle.enter(|| HyperClient::builder()
+			// As some services are picky about the case of the headers they read.
+			.http1_title_case_headers(true)
+			.build(HttpsConnector::new()));
+		Ok(Shared { client, handle, _runtime: Mutex::new(runtime) })
+	}
+}
+
+lazy_static::lazy_static! {
+	static ref SHARED: Result<Shared, String> = Shared::start().map_err(|e| e.to_string());
+}
+
+fn other(e: impl ToString) -> io::Error {
+	io::Error::new(io::ErrorKind::Other, e.to_string())
+}
+
+/// Where to post, parsed from `http[s]://[<user>@]<host>[:<port>][<path>]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Endpoint {
+	pub secure: bool,
+	pub user: Option<String>,
+	pub host: String,
+	pub port: u16,
+	pub path: String,
+}
+
+impl Endpoint {
+	/// Parses the `url`, returning `None` if it isn't an HTTP or HTTPS one.
+	pub fn parse(url: &str) -> Option<Self> {
+		let (secure, rest) = if url.starts_with("https://") {
+			(true, &url["https://".len()..])
+		} else if url.starts_with("http://") {
+			(false, &url["http://".len()..])
+		} else {
+			return None;
+		};
+		let (authority, path) = match rest.find('/') {
+			Some(i) => (&rest[..i], &rest[i..]),
+			None => (rest, "/"),
+		};
+		let (user, authority) = match authority.rfind('@') {
+			Some(i) => (Some(authority[..i].to_owned()), &authority[i + 1..]),
+			None => (None, authority),
+		};
+		let (host, port) = match authority.rfind(':') {
+			Some(i) => (&authority[..i], authority[i + 1..].parse().ok()?),
+			None => (authority, if secure { 443 } else { 80 }),
+		};
+		if host.is_empty() {
+			return None;
+		}
+		Some(Endpoint { secure, user, host: host.to_owned(), port, path: path.to_owned() })
+	}
+
+	fn uri(&self) -> String {
+		let scheme = if self.secure { "https" } else { "http" };
+		format!("{}://{}:{}{}", scheme, self.host, self.port, self.path)
+	}
+
+	/// Post `body` to the endpoint, failing unless the response status is a success.
+	///
+	/// The body is JSON unless `headers` has a `Content-Type`. This blocks until the response,
+	/// for at most 10 seconds.
+	pub fn post(&self, headers: &[(&str, &str)], body: &[u8]) -> io::Result<()> {
+		let shared = SHARED.as_ref().map_err(other)?;
+		let mut request = Request::post(self.uri());
+		if !headers.iter().any(|(name, _)| name.eq_ignore_ascii_case("Content-Type")) {
+			request = request.header(header::CONTENT_TYPE, "application/json");
+		}
+		for (name, value) in headers {
+			request = request.header(*name, *value);
+		}
+		let request = request.body(Body::from(body.to_vec())).map_err(other)?;
+
+		let (tx, rx) = mpsc::sync_channel(1);
+		let client = shared.client.clone();
+		shared.handle.spawn(async move {
+			let result = match tokio::time::timeout(TIMEOUT, client.request(request)).await {
+				Ok(Ok(response)) if response.status().is_success() => Ok(()),
+				Ok(Ok(response)) => Err(format!("Unexpected response status {}", response.status())),
+				Ok(Err(e)) => Err(e.to_string()),
+				Err(_) => Err(format!("No response after {:?}", TIMEOUT)),
+			};
+			let _ = tx.send(result);
+		});
+		// The request times out on its own, this only guards against the runtime being gone.
+		rx.recv_timeout(TIMEOUT * 2).map_err(|_| io::Error::from(io::ErrorKind::TimedOut))?.map_err(other)
+	}
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+	use super::*;
+	use std::{io::{BufRead, BufReader, Read, Write}, net::TcpListener};
+
+	/// Accept a request, answering with `status`, and return its headers and body.
+	pub fn accept(listener: &TcpListener, status: &str) -> (Vec<String>, String) {
+		let (mut stream, _) = listener.accept().unwrap();
+		let mut reader = BufReader::new(stream.try_clone().unwrap());
+		let (mut headers, mut length) = (Vec::new(), 0);
+		loop {
+			let mut header = String::new();
+			reader.read_line(&mut header).unwrap();
+			if header == "\r\n" {
+				break;
+			}
+			if let Some(value) = header.strip_prefix("Content-Length: ") {
+				length = value.trim().parse().unwrap();
+			}
+			headers.push(header.trim_end().to_owned());
+		}
+		let mut body = vec![0; length];
+		reader.read_exact(&mut body).unwrap();
+		write!(stream, "HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", status).unwrap();
+		(headers, String::from_utf8(body).unwrap())
+	}
+
+	#[test]
+	fn parses_endpoints() {
+		let endpoint = |user: Option<&str>, host: &str, port, path: &str| Endpoint {
+			secure: port == 443,
+			user: user.map(Into::into),
+			host: host.into(),
+			port,
+			path: path.into(),
+		};
+		assert_eq!(Endpoint::parse("http://localhost:3100"), Some(endpoint(None, "localhost", 3100, "/")));
+		assert_eq!(Endpoint::parse("http://loki/custom/push"), Some(endpoint(None, "loki", 80, "/custom/push")));
+		assert_eq!(Endpoint::parse("http://key@sentry:9000/1"), Some(endpoint(Some("key"), "sentry", 9000, "/1")));
+		assert_eq!(Endpoint::parse("https://loki/push"), Some(endpoint(None, "loki", 443, "/push")));
+		assert_eq!(Endpoint::parse("https://loki:443").unwrap().uri(), "https://loki:443/");
+		assert_eq!(Endpoint::parse("udp://loki"), None);
+		assert_eq!(Endpoint::parse("http://:3100"), None);
+		assert_eq!(Endpoint::parse("http://loki:port"), None);
+	}
+
+	#[test]
+	fn posts_with_headers() {
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let endpoint = Endpoint::parse(&format!("http://{}/push", listener.local_addr().unwrap())).unwrap();
+		let server = std::thread::spawn(move || {
+			let first = accept(&listener, "403 Forbidden");
+			let second = accept(&listener, "200 OK");
+			(first, second)
+		});
+		assert!(endpoint.post(&[], b"{}").is_err());
+		assert!(endpoint.post(&[("X-Test", "1")], b"{}").is_ok());
+		let ((_, first), (headers, second)) = server.join().unwrap();
+		assert_eq!((first.as_str(), second.as_str()), ("{}", "{}"));
+		assert!(headers[0].starts_with("POST /push HTTP/1.1"));
+		assert!(headers.contains(&"X-Test: 1".to_owned()));
+	}
+}
@@ -0,0 +1,407 @@
+// Copyright 2019-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Export of span aggregates and node metrics to InfluxDB, in its line protocol.
+//!
+//! The durations of the spans are aggregated by target and name, and pushed with the node
+//! metrics by a background thread every [`PUSH_INTERVAL`] and when the handler is flushed,
+//! either to the HTTP write API, of InfluxDB 1.x or of InfluxDB 2 with [`InfluxConfig::set_v2`],
+//! or over UDP. Every push covers the spans closed since the previous one.
+//!
+//! The span aggregates are points of the `spans` measurement, tagged with `target` and `name`,
+//! with the fields `count`, `total_ns`, `mean_ns`, `p95_ns` and `max_ns`. The node metrics are
+//! points of the `metrics` measurement, tagged with their labels, with a field named after the
+//! metric. The measurements can be renamed or disabled, see [`InfluxConfig::set_measurements`].
+
+use std::{
+	fmt,
+	mem,
+	net::{SocketAddr, ToSocketAddrs},
+	sync::{Arc, mpsc},
+	thread,
+	time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use parking_lot::Mutex;
+use rustc_hash::FxHashMap;
+use sc_telemetry::ExporterHealth;
+
+use crate::{SpanDatum, TraceEvent, TraceHandler, csv::Stats, http::Endpoint, udp};
+
+/// Time between two pushes.
+pub const PUSH_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Maximum time `flush` waits for the push.
+const FLUSH_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Path of the 1.x write API, used if the HTTP endpoint has none.
+const WRITE_PATH: &str = "/write?db=substrate";
+
+/// Path of the 2 write API, followed by the organization and bucket.
+const V2_WRITE_PATH: &str = "/api/v2/write";
+
+/// A node metric: its name, labels and value.
+pub type NodeMetric = (String, Vec<(String, String)>, f64);
+
+/// Source of the node metrics pushed with the span aggregates, e.g. a Prometheus registry.
+pub type MetricsSource = Arc<dyn Fn() -> Vec<NodeMetric> + Send + Sync>;
+
+#[derive(Debug, Clone)]
+enum Transport {
+	Http(Endpoint),
+	Udp(SocketAddr),
+}
+
+/// Where and what to push.
+#[derive(Clone)]
+pub struct InfluxConfig {
+	transport: Transport,
+	/// Token of the `Authorization` header of the requests to the 2 write API.
+	token: Option<String>,
+	/// Tags of all the points, e.g. `chain` and `node`.
+	pub tags: Vec<(String, String)>,
+	/// Measurement of the span aggregates, if pushed.
+	pub spans_measurement: Option<String>,
+	/// Measurement of the node metrics, if pushed.
+	pub metrics_measurement: Option<String>,
+	/// The node metrics.
+	pub metrics: Option<MetricsSource>,
+}
+
+impl fmt::Debug for InfluxConfig {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_struct("InfluxConfig")
+			.field("transport", &self.transport)
+			.field("tags", &self.tags)
+			.field("spans_measurement", &self.spans_measurement)
+			.field("metrics_measurement", &self.metrics_measurement)
+			.finish()
+	}
+}
+
+impl InfluxConfig {
+	/// Parses an endpoint like `http://localhost:8086/write?db=substrate` or
+	/// `udp://localhost:8089`, the HTTP path defaulting to the 1.x write API of the `substrate`
+	/// database.
+	pub fn new(endpoint: &str) -> Result<Self, String> {
+		let invalid = || format!(
+			"Invalid InfluxDB endpoint `{}`, expected `http[s]://<host>[:<port>][<path>]` or `udp://<host>:<port>`",
+			endpoint,
+		);
+		let transport = if endpoint.starts_with("udp://") {
+			let addr = endpoint["udp://".len()..].to_socket_addrs().ok()
+				.and_then(|mut addrs| addrs.next())
+				.ok_or_else(invalid)?;
+			Transport::Udp(addr)
+		} else {
+			let mut endpoint = Endpoint::parse(endpoint).ok_or_else(invalid)?;
+			if endpoint.path == "/" {
+				endpoint.path = WRITE_PATH.to_owned();
+			}
+			Transport::Http(endpoint)
+		};
+		Ok(InfluxConfig {
+			transport,
+			token: None,
+			tags: Vec::new(),
+			spans_measurement: Some("substrate_spans".into()),
+			metrics_measurement: Some("substrate_metrics".into()),
+			metrics: None,
+		})
+	}
+
+	/// Push to the 2 write API, in the `bucket` of `org`, authorized by `token`.
+	pub fn set_v2(&mut self, org: &str, bucket: &str, token: String) -> Result<(), String> {
+		let endpoint = match &mut self.transport {
+			Transport::Http(endpoint) => endpoint,
+			Transport::Udp(_) => return Err("The InfluxDB 2 write API requires an HTTP endpoint".into()),
+		};
+		endpoint.path = format!(
+			"{}?org={}&bucket={}&precision=ns",
+			V2_WRITE_PATH,
+			encode_query(org),
+			encode_query(bucket),
+		);
+		self.token = Some(token);
+		Ok(())
+	}
+
+	/// Push only the measurements of a comma separated list like `spans=span_stats,metrics`,
+	/// `spans` and `metrics` being optionally renamed.
+	pub fn set_measurements(&mut self, measurements: &str) -> Result<(), String> {
+		self.spans_measurement = None;
+		self.metrics_measurement = None;
+		for measurement in measurements.split(',').map(str::trim).filter(|m| !m.is_empty()) {
+			let (kind, name) = match measurement.find('=') {
+				Some(i) => (&measurement[..i], Some(measurement[i + 1..].to_owned())),
+				None => (measurement, None),
+			};
+			match kind {
+				"spans" => self.spans_measurement = Some(name.unwrap_or_else(|| "substrate_spans".into())),
+				"metrics" => self.metrics_measurement = Some(name.unwrap_or_else(|| "substrate_metrics".into())),
+				_ => return Err(format!("Unknown InfluxDB measurement `{}`, expected `spans` or `metrics`", kind)),
+			}
+		}
+		Ok(())
+	}
+}
+
+/// TraceHandler pushing span aggregates and node metrics to InfluxDB
+pub struct InfluxTraceHandler {
+	stats: Arc<Mutex<FxHashMap<(String, String), Stats>>>,
+	sender: mpsc::SyncSender<mpsc::SyncSender<()>>,
+}
+
+impl InfluxTraceHandler {
+	/// Create a handler pushing to the configured endpoint.
+	pub fn new(config: InfluxConfig) -> Self {
+		let stats = Arc::new(Mutex::new(FxHashMap::default()));
+		let (sender, receiver) = mpsc::sync_channel(1);
+		let pusher = Pusher { config, stats: stats.clone(), health: ExporterHealth::new("influx") };
+		let spawned = thread::Builder::new()
+			.name("influx-pusher".into())
+			.spawn(move || pusher.run(receiver));
+		if let Err(e) = spawned {
+			log::warn!(target: "tracing", "Unable to start the InfluxDB pusher: {}", e);
+		}
+		InfluxTraceHandler { stats, sender }
+	}
+}
+
+impl TraceHandler for InfluxTraceHandler {
+	fn handle_span(&self, span_datum: SpanDatum) {
+		self.stats.lock().entry((span_datum.target, span_datum.name))
+			.or_insert_with(Stats::new)
+			.record(span_datum.overall_time.as_nanos() as u64);
+	}
+
+	fn handle_event(&self, _: TraceEvent) {}
+
+	fn flush(&self) {
+		let (done, pushed) = mpsc::sync_channel(1);
+		if self.sender.send(done).is_ok() {
+			let _ = pushed.recv_timeout(FLUSH_TIMEOUT);
+		}
+	}
+}
+
+/// Pushes the points, from a background thread.
+struct Pusher {
+	config: InfluxConfig,
+	stats: Arc<Mutex<FxHashMap<(String, String), Stats>>>,
+	health: ExporterHealth,
+}
+
+impl Pusher {
+	fn run(self, receiver: mpsc::Receiver<mpsc::SyncSender<()>>) {
+		loop {
+			match receiver.recv_timeout(PUSH_INTERVAL) {
+				Ok(done) => {
+					self.push();
+					let _ = done.send(());
+				},
+				Err(mpsc::RecvTimeoutError::Timeout) => self.push(),
+				Err(mpsc::RecvTimeoutError::Disconnected) => {
+					self.push();
+					return;
+				},
+			}
+		}
+	}
+
+	fn push(&self) {
+		let lines = self.lines(SystemTime::now());
+		if lines.is_empty() {
+			return;
+		}
+		let start = Instant::now();
+		let pushed = match &self.config.transport {
+			Transport::Http(endpoint) => {
+				let authorization = self.config.token.as_ref().map(|token| format!("Token {}", token));
+				let mut headers = vec![("Content-Type", "text/plain; charset=utf-8")];
+				if let Some(authorization) = &authorization {
+					headers.push(("Authorization", authorization));
+				}
+				endpoint.post(&headers, lines.join("\n").as_bytes())
+			},
+			Transport::Udp(addr) => udp::connect(*addr)
+				.and_then(|socket| udp::packets(&lines).iter().try_for_each(|packet| {
+					socket.send(packet.as_bytes()).map(drop)
+				})),
+		};
+		match pushed {
+			Ok(()) => self.health.record_sent(lines.len() as u64, start.elapsed()),
+			Err(e) => {
+				log::warn!(target: "tracing", "Unable to push {} points to InfluxDB: {}", lines.len(), e);
+				self.health.record_dropped(lines.len() as u64);
+			},
+		}
+	}
+
+	/// The points of the spans closed since the previous push, and of the node metrics.
+	fn lines(&self, now: SystemTime) -> Vec<String> {
+		let timestamp = now.duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos();
+		let tags: String = self.config.tags.iter()
+			.map(|(k, v)| format!(",{}={}", escape(k, ",= "), escape(v, ",= ")))
+			.collect();
+		let mut lines = Vec::new();
+		let stats = mem::take(&mut *self.stats.lock());
+		if let Some(measurement) = &self.config.spans_measurement {
+			let mut stats: Vec<_> = stats.into_iter().collect();
+			stats.sort_by(|a, b| a.0.cmp(&b.0));
+			for ((target, name), stats) in stats {
+				lines.push(format!(
+					"{},target={},name={}{} count={}i,total_ns={}i,mean_ns={}i,p95_ns={}i,max_ns={}i {}",
+					escape(measurement, ", "),
+					escape(&target, ",= "),
+					escape(&name, ",= "),
+					tags,
+					stats.count,
+					stats.total_ns,
+					stats.total_ns / stats.count.max(1),
+					stats.p95_ns(),
+					stats.max_ns,
+					timestamp,
+				));
+			}
+		}
+		if let (Some(measurement), Some(metrics)) = (&self.config.metrics_measurement, &self.config.metrics) {
+			for (name, labels, value) in metrics() {
+				if !value.is_finite() {
+					continue;
+				}
+				let labels: String = labels.iter()
+					.filter(|(_, v)| !v.is_empty())
+					.map(|(k, v)| format!(",{}={}", escape(k, ",= "), escape(v, ",= ")))
+					.collect();
+				lines.push(format!(
+					"{}{}{} {}={:?} {}",
+					escape(measurement, ", "),
+					labels,
+					tags,
+					escape(&name, ",= "),
+					value,
+					timestamp,
+				));
+			}
+		}
+		lines
+	}
+}
+
+/// Escape the characters of `chars` and backslashes.
+fn escape(value: &str, chars: &str) -> String {
+	let mut escaped = String::with_capacity(value.len());
+	for c in value.chars() {
+		if c == '\\' || chars.contains(c) {
+			escaped.push('\\');
+		}
+		escaped.push(if c == '\n' { ' ' } else { c });
+	}
+	escaped
+}
+
+/// Percent-encode a value of the query string.
+fn encode_query(value: &str) -> String {
+	let mut encoded = String::with_capacity(value.len());
+	for byte in value.bytes() {
+		match byte {
+			b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => encoded.push(byte as char),
+			_ => encoded.push_str(&format!("%{:02X}", byte)),
+		}
+	}
+	encoded
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::net::TcpListener;
+	use crate::{ProfilingSubscriber, http::tests::accept};
+
+	#[test]
+	fn configures_measurements() {
+		let mut config = InfluxConfig::new("http://localhost:8086").unwrap();
+		assert!(matches!(&config.transport, Transport::Http(endpoint) if endpoint.path == WRITE_PATH));
+		config.set_measurements("spans=span_stats").unwrap();
+		assert_eq!(config.spans_measurement.as_deref(), Some("span_stats"));
+		assert_eq!(config.metrics_measurement, None);
+		assert!(config.set_measurements("events").is_err());
+		let config = InfluxConfig::new("udp://127.0.0.1:8089").unwrap();
+		assert!(matches!(config.transport, Transport::Udp(_)));
+		assert!(InfluxConfig::new("udp://localhost").is_err());
+	}
+
+	#[test]
+	fn configures_the_v2_write_api() {
+		let mut config = InfluxConfig::new("https://localhost:8086").unwrap();
+		config.set_v2("my org", "blocks", "secret".into()).unwrap();
+		assert!(matches!(
+			&config.transport,
+			Transport::Http(endpoint) if endpoint.path == "/api/v2/write?org=my%20org&bucket=blocks&precision=ns"
+		));
+		let mut config = InfluxConfig::new("udp://127.0.0.1:8089").unwrap();
+		assert!(config.set_v2("org", "blocks", "secret".into()).is_err());
+	}
+
+	#[test]
+	fn formats_points() {
+		let mut config = InfluxConfig::new("udp://127.0.0.1:8089").unwrap();
+		config.tags.push(("node".into(), "alice bob".into()));
+		config.metrics = Some(Arc::new(|| vec![
+			("substrate_block_height".into(), vec![("status".into(), "best".into())], 42.0),
+			("substrate_nan".into(), Vec::new(), std::f64::NAN),
+		]));
+		let pusher = Pusher { config, stats: Default::default(), health: ExporterHealth::new("influx") };
+		let mut stats = Stats::new();
+		stats.record(10);
+		stats.record(30);
+		pusher.stats.lock().insert(("sync".into(), "import block".into()), stats);
+
+		let lines = pusher.lines(UNIX_EPOCH + Duration::from_secs(1));
+		assert_eq!(lines, vec![
+			"substrate_spans,target=sync,name=import\\ block,node=alice\\ bob \
+				count=2i,total_ns=40i,mean_ns=20i,p95_ns=30i,max_ns=30i 1000000000",
+			"substrate_metrics,status=best,node=alice\\ bob substrate_block_height=42.0 1000000000",
+		]);
+		// The aggregates are reset by every push.
+		assert_eq!(pusher.lines(UNIX_EPOCH).len(), 1);
+	}
+
+	#[test]
+	fn pushes_over_http() {
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let mut config = InfluxConfig::new(&format!("http://{}", listener.local_addr().unwrap())).unwrap();
+		config.set_v2("substrate", "blocks", "secret".into()).unwrap();
+		let server = thread::spawn(move || accept(&listener, "204 No Content"));
+		let handler = InfluxTraceHandler::new(config);
+		let subscriber = ProfilingSubscriber::new_with_handler(Box::new(handler), "test_target");
+		let dispatch = tracing::Dispatch::new(subscriber);
+		tracing::dispatcher::with_default(&dispatch, || {
+			tracing::info_span!(target: "test_target", "import_block").in_scope(|| {});
+			crate::flush_global();
+		});
+
+		let (headers, body) = server.join().unwrap();
+		assert!(
+			headers[0].starts_with("POST /api/v2/write?org=substrate&bucket=blocks&precision=ns HTTP/1.1"),
+			"{}",
+			headers[0],
+		);
+		assert!(headers.contains(&"Authorization: Token secret".to_owned()));
+		assert!(headers.contains(&"Content-Type: text/plain; charset=utf-8".to_owned()));
+		assert!(body.starts_with("substrate_spans,target=test_target,name=import_block count=1i,"), "{}", body);
+	}
+}
@@ -24,12 +24,78 @@
 //!
 //! Currently we provide `Log` (default), `Telemetry` variants for `Receiver`
 
+mod audit;
+mod binary;
+mod builder;
+mod clock;
+mod csv;
+mod datadog;
+mod deadline;
+mod diff;
+mod dispatch_origins;
+mod finality;
+mod fmt_layer;
+mod gelf;
+mod heatmap;
+mod http;
+mod influx;
+mod limits;
+mod loki;
+mod panic;
+mod parquet;
+mod pov;
+mod queue;
+mod redaction;
+mod replay;
+mod scale;
+mod sentry;
+mod span_metrics;
+mod spans;
+mod summary;
+mod udp;
+mod weight_drift;
+mod weights;
+
+pub use audit::AuditSubscriber;
+pub use binary::BinaryTraceHandler;
+pub use builder::{BoxedLayer, TracingBuilder, TracingExtension, TracingHandle};
+pub use clock::{Clock, ManualClock, SystemClock};
+pub use csv::CsvTraceHandler;
+pub use datadog::{DatadogConfig, DatadogTraceHandler};
+pub use deadline::{IMPORT_DEADLINE_TARGET, ImportDeadlines};
+pub use diff::{SpanDiff, SpanStats, TraceStats, diff, trace_stats};
+pub use dispatch_origins::{DispatchOrigins, MAX_ORIGINS, ORIGIN_KEY, OriginStats, UNKNOWN_ORIGIN};
+pub use finality::{
+	FinalityMetrics, GADGET_KEY, GadgetStats, MAX_GADGETS, MAX_KINDS, PROOF_SPAN, ProofStats,
+};
+pub use fmt_layer::FmtLayer;
+pub use gelf::{GelfConfig, GelfTraceHandler};
+pub use heatmap::{HeatHint, LAZY_MIGRATION_KEYS, LARGE_VALUE_BYTES, PrefixHeat, StorageHeatmap};
+pub use influx::{InfluxConfig, InfluxTraceHandler, MetricsSource, NodeMetric};
+pub use limits::{DROPPED_RECORDS_EVENT, TraceLimits};
+pub use loki::{LokiConfig, LokiMetrics, LokiTraceHandler};
+pub use panic::shutdown_after_panic;
+pub use parquet::{BLOCK_NUMBER_KEY, ParquetTraceHandler};
+pub use pov::{
+	BUILD_BLOCK_SPAN, PARENT_HASH_KEY, PROOF_SIZE_KEY, PovBreakdown, PovBreakdowns, PovContribution,
+};
+pub use queue::{QueueMetrics, QueuedTraceHandler};
+pub use redaction::{Redaction, RedactionMode};
+pub use replay::{Replayed, replay, replay_as_json, replay_summaries};
+pub use scale::ScaleDecoder;
+pub use sentry::{SentryConfig, SentryReporter};
+pub use span_metrics::{OTHER_SERIES, SpanMetrics};
+pub use summary::{BLOCK_HASH_KEY, BlockSummary, CounterSummary, SpanSummary, record_collected_summary};
+pub use weight_drift::{CallDrift, WeightDrift};
+pub use weights::{CONFIDENCE_LEVELS, DispatchTimes, WeightSuggestion, write_weight_info};
+
 use rustc_hash::FxHashMap;
 use std::fmt;
-use std::sync::atomic::{AtomicU64, Ordering};
-use std::time::{Duration, Instant};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
 
-use parking_lot::Mutex;
+use parking_lot::{Mutex, RwLock};
 use serde::ser::{Serialize, Serializer, SerializeMap};
 use tracing::{
 	event::Event,
@@ -39,21 +105,111 @@ use tracing::{
 	span::{Attributes, Id, Record},
 	subscriber::Subscriber,
 };
+use tracing_core::span::Current;
 use tracing_subscriber::CurrentSpan;
 
-use sc_telemetry::{telemetry, SUBSTRATE_INFO};
-use sp_tracing::proxy::{WASM_NAME_KEY, WASM_TARGET_KEY, WASM_TRACE_IDENTIFIER};
+use sc_telemetry::{telemetry, SUBSTRATE_INFO, TELEMETRY_TARGET};
+use limits::BlockLimits;
+use sentry::Breadcrumb;
+use spans::Spans;
+use summary::{BLOCK_SPAN, BlockSummaries};
+use sp_tracing::{
+	ASSERTION_TARGET, COUNTER_TARGET, DISPATCH_TARGET, FINALITY_TARGET, PROOF_SIZE_TARGET,
+	RUNTIME_EVENT_TARGET, STORAGE_ACCESS_TARGET,
+	proxy::{
+		TASK_NAME_KEY, TASK_TRACE_IDENTIFIER, TRACE_ID_KEY, WASM_NAME_KEY, WASM_ON_CHAIN_KEY,
+		WASM_TARGET_KEY, WASM_TRACE_IDENTIFIER,
+	},
+};
 
 const ZERO_DURATION: Duration = Duration::from_nanos(0);
 const PROXY_TARGET: &'static str = "sp_tracing::proxy";
 
+/// Value of the spans counting the storage reads under them, see
+/// [`ProfilingSubscriber::with_storage_counters`].
+pub const STORAGE_READS_KEY: &str = "storage_reads";
+/// Value of the spans counting the storage writes under them.
+pub const STORAGE_WRITES_KEY: &str = "storage_writes";
+/// Value of the spans counting the bytes read from the storage under them.
+pub const STORAGE_READ_BYTES_KEY: &str = "storage_read_bytes";
+/// Value of the spans counting the bytes written to the storage under them.
+pub const STORAGE_WRITTEN_BYTES_KEY: &str = "storage_written_bytes";
+/// Value of the spans counting the bytes of the trie nodes added to a storage proof under them.
+pub const PROOF_BYTES_KEY: &str = "proof_bytes";
+
+/// Value of the dispatch spans and of the events of their failures naming the pallet of the
+/// dispatched call, the target of its span.
+pub const PALLET_KEY: &str = "pallet";
+/// Value of the dispatch spans and of the events of their failures naming the dispatched call,
+/// the name of its span.
+pub const CALL_KEY: &str = "call";
+/// Value of the events of the failed dispatches naming their error, e.g.
+/// `Balances.InsufficientBalance` once decoded with the metadata, see [`ScaleDecoder`].
+pub const ERROR_KEY: &str = "error";
+/// Value of the events of the failed dispatches with the index of the module of the error.
+pub const MODULE_INDEX_KEY: &str = "module_index";
+/// Value of the events of the failed dispatches with the index of the error in its module.
+pub const ERROR_INDEX_KEY: &str = "error_index";
+/// Value of the dispatch spans summarizing the runtime events deposited under them, separated by
+/// `; `, e.g. `Balances.Transfer(5GrwvaEF.., 5FHneW46.., 1.5 DOT)`, see
+/// [`ScaleDecoder::decode_event`].
+pub const EVENTS_KEY: &str = "events";
+
+/// Length of the event summaries of a dispatch span past which the next events are elided.
+const MAX_EVENTS_LEN: usize = 4096;
+
+/// Targets of privileged actions, offences and governance, which are always enabled and whose
+/// events are always forwarded to telemetry, whatever the configured targets and receiver.
+const AUDIT_TARGETS: &[&str] = &["sudo", "offences", "democracy"];
+
 /// Responsible for assigning ids to new spans, which are not re-used.
 pub struct ProfilingSubscriber {
-	next_id: AtomicU64,
-	targets: Vec<(String, Level)>,
+	targets: RwLock<Vec<(String, Level)>>,
 	trace_handler: Box<dyn TraceHandler>,
-	span_data: Mutex<FxHashMap<Id, SpanDatum>>,
+	wasm_wrappers: Vec<Box<dyn WasmSpanWrapper>>,
+	spans: Spans,
 	current_span: CurrentSpan,
+	counters: Counters,
+	redaction: Redaction,
+	scale_decoder: RwLock<Option<Arc<ScaleDecoder>>>,
+	block_summaries: Option<BlockSummaries>,
+	block_limits: Option<BlockLimits>,
+	import_deadlines: Option<ImportDeadlines>,
+	span_metrics: Option<SpanMetrics>,
+	weight_drift: Option<WeightDrift>,
+	dispatch_origins: Option<DispatchOrigins>,
+	finality_metrics: Option<FinalityMetrics>,
+	storage_counters: bool,
+	dispatch_errors: bool,
+	pov_breakdowns: Option<PovBreakdowns>,
+	sentry: Option<SentryReporter>,
+	clock: Box<dyn Clock>,
+}
+
+/// The sums of the increments of the counters of `sp_tracing::counter`, by counter and label.
+#[derive(Debug, Clone, Default)]
+pub struct Counters {
+	inner: Arc<Mutex<FxHashMap<(String, String), u64>>>,
+}
+
+impl Counters {
+	fn increment(&self, values: &Values) {
+		let name = values.string_values.get("counter");
+		let label = values.string_values.get("label");
+		let value = values.u64_values.get("value");
+		if let (Some(name), Some(label), Some(value)) = (name, label, value) {
+			let mut inner = self.inner.lock();
+			let counter = inner.entry((name.clone(), label.clone())).or_default();
+			*counter = counter.saturating_add(*value);
+		}
+	}
+
+	/// Calls `f` with the name, label and value of every counter.
+	pub fn for_each(&self, mut f: impl FnMut(&str, &str, u64)) {
+		for ((name, label), value) in self.inner.lock().iter() {
+			f(name, label, *value);
+		}
+	}
 }
 
 /// Used to configure how to receive the metrics
@@ -63,6 +219,21 @@ pub enum TracingReceiver {
 	Log,
 	/// Output to telemetry
 	Telemetry,
+	/// Output to Parquet files in the given directory, see [`ParquetTraceHandler`]
+	Parquet(PathBuf),
+	/// Output to a file in the compact binary format of `sc-tracing-format`,
+	/// see [`BinaryTraceHandler`]
+	Binary(PathBuf),
+	/// Output span statistics to the given CSV file, see [`CsvTraceHandler`]
+	Csv(PathBuf),
+	/// Output to Grafana Loki, see [`LokiTraceHandler`]
+	Loki(LokiConfig),
+	/// Output span aggregates and node metrics to InfluxDB, see [`InfluxTraceHandler`]
+	Influx(InfluxConfig),
+	/// Output the block execution traces to Datadog APM, see [`DatadogTraceHandler`]
+	Datadog(DatadogConfig),
+	/// Output to Graylog, as GELF messages, see [`GelfTraceHandler`]
+	Gelf(GelfConfig),
 }
 
 impl Default for TracingReceiver {
@@ -71,26 +242,87 @@ impl Default for TracingReceiver {
 	}
 }
 
+impl TracingReceiver {
+	/// The handler processing the spans and events sent to this receiver.
+	///
+	/// The `redaction` applies to the `Telemetry`, `Loki`, `Datadog` and `Gelf` receivers.
+	pub fn into_handler(self, redaction: Redaction) -> Box<dyn TraceHandler> {
+		match self {
+			TracingReceiver::Log => Box::new(LogTraceHandler),
+			TracingReceiver::Telemetry => Box::new(TelemetryTraceHandler::new(redaction)),
+			TracingReceiver::Parquet(dir) => Box::new(ParquetTraceHandler::new(dir)),
+			TracingReceiver::Binary(path) => Box::new(BinaryTraceHandler::new(&path)),
+			TracingReceiver::Csv(path) => Box::new(CsvTraceHandler::new(path)),
+			TracingReceiver::Loki(config) => Box::new(LokiTraceHandler::new(config, redaction)),
+			TracingReceiver::Influx(config) => Box::new(InfluxTraceHandler::new(config)),
+			TracingReceiver::Datadog(config) => Box::new(DatadogTraceHandler::new(config, redaction)),
+			TracingReceiver::Gelf(config) => Box::new(GelfTraceHandler::new(config, redaction)),
+		}
+	}
+}
+
 /// A handler for tracing `SpanDatum`
 pub trait TraceHandler: Send + Sync {
 	/// Process a `SpanDatum`
 	fn handle_span(&self, span: SpanDatum);
 	/// Process a `TraceEvent`
 	fn handle_event(&self, event: TraceEvent);
+	/// Flush any data buffered by the handler
+	fn flush(&self) {}
+}
+
+/// Wraps the handling of the spans of the runtime, after they are decoded.
+///
+/// The wrapper may change the span, drop it, or export it elsewhere, before passing
+/// it to `handler`.
+pub trait WasmSpanWrapper: Send + Sync {
+	/// Process a `SpanDatum` of the runtime, `handler` being the next wrapper or the trace handler
+	fn wrap_span(&self, span: SpanDatum, handler: &dyn TraceHandler);
+}
+
+impl<F: Fn(SpanDatum, &dyn TraceHandler) + Send + Sync> WasmSpanWrapper for F {
+	fn wrap_span(&self, span: SpanDatum, handler: &dyn TraceHandler) {
+		self(span, handler)
+	}
+}
+
+/// The wasm span wrappers, from the outermost, around the trace handler.
+struct WrappedHandler<'a> {
+	wrappers: &'a [Box<dyn WasmSpanWrapper>],
+	handler: &'a dyn TraceHandler,
+}
+
+impl<'a> TraceHandler for WrappedHandler<'a> {
+	fn handle_span(&self, span: SpanDatum) {
+		match self.wrappers.split_first() {
+			Some((wrapper, wrappers)) =>
+				wrapper.wrap_span(span, &WrappedHandler { wrappers, handler: self.handler }),
+			None => self.handler.handle_span(span),
+		}
+	}
+
+	fn handle_event(&self, event: TraceEvent) {
+		self.handler.handle_event(event)
+	}
+
+	fn flush(&self) {
+		self.handler.flush()
+	}
 }
 
 /// Represents a tracing event, complete with values
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct TraceEvent {
 	pub name: &'static str,
 	pub target: String,
 	pub level: Level,
 	pub values: Values,
 	pub parent_id: Option<Id>,
+	pub time: SystemTime,
 }
 
 /// Represents a single instance of a tracing span
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SpanDatum {
 	/// id for this span
 	pub id: Id,
@@ -106,10 +338,14 @@ pub struct SpanDatum {
 	pub line: u32,
 	/// Time that the span was last entered
 	pub start_time: Instant,
+	/// Wall clock time that the span was last entered
+	pub start_wall_time: SystemTime,
 	/// Total duration of span while entered
 	pub overall_time: Duration,
 	/// Values recorded to this span
 	pub values: Values,
+	/// Static metadata of the span callsite
+	pub metadata: &'static Metadata<'static>,
 }
 
 /// Holds associated values for a tracing span
@@ -138,6 +374,19 @@ impl Values {
 			self.u64_values.is_empty() &&
 			self.string_values.is_empty()
 	}
+
+	/// Adds the values of `other` that are not already set
+	pub fn extend_missing(&mut self, other: &Values) {
+		fn extend<T: Clone>(into: &mut FxHashMap<String, T>, from: &FxHashMap<String, T>) {
+			for (k, v) in from {
+				into.entry(k.clone()).or_insert_with(|| v.clone());
+			}
+		}
+		extend(&mut self.bool_values, &other.bool_values);
+		extend(&mut self.i64_values, &other.i64_values);
+		extend(&mut self.u64_values, &other.u64_values);
+		extend(&mut self.string_values, &other.string_values);
+	}
 }
 
 impl Visit for Values {
@@ -221,13 +470,12 @@ impl ProfilingSubscriber {
 	/// either with a level: "pallet=trace,frame=debug"
 	/// or without: "pallet,frame" in which case the level defaults to `trace`.
 	/// wasm_tracing indicates whether to enable wasm traces
-	pub fn new(receiver: TracingReceiver, targets: &str) -> ProfilingSubscriber {
-		match receiver {
-			TracingReceiver::Log => Self::new_with_handler(Box::new(LogTraceHandler), targets),
-			TracingReceiver::Telemetry => Self::new_with_handler(
-				Box::new(TelemetryTraceHandler),
-				targets,
-			),
+	/// The `redaction` applies to everything sent to telemetry, the audit events included, and to Loki, Datadog, Graylog and Sentry.
+	pub fn new(receiver: TracingReceiver, targets: &str, redaction: Redaction) -> ProfilingSubscriber {
+		let trace_handler = receiver.into_handler(redaction.clone());
+		ProfilingSubscriber {
+			redaction,
+			..Self::new_with_handler(trace_handler, targets)
 		}
 	}
 
@@ -236,21 +484,642 @@ impl ProfilingSubscriber {
 	/// either with a level, eg: "pallet=trace"
 	/// or without: "pallet" in which case the level defaults to `trace`.
 	/// wasm_tracing indicates whether to enable wasm traces
+	/// An empty list only enables the audit targets, e.g. `sudo` and `democracy`.
 	pub fn new_with_handler(trace_handler: Box<dyn TraceHandler>, targets: &str)
 		-> ProfilingSubscriber
 	{
-		let targets: Vec<_> = targets.split(',').map(|s| parse_target(s)).collect();
 		ProfilingSubscriber {
-			next_id: AtomicU64::new(1),
-			targets,
+			targets: RwLock::new(parse_targets(targets)),
 			trace_handler,
-			span_data: Mutex::new(FxHashMap::default()),
-			current_span: Default::default()
+			wasm_wrappers: Vec::new(),
+			spans: Default::default(),
+			current_span: Default::default(),
+			counters: Default::default(),
+			redaction: Default::default(),
+			scale_decoder: RwLock::new(None),
+			block_summaries: None,
+			block_limits: None,
+			import_deadlines: None,
+			span_metrics: None,
+			weight_drift: None,
+			dispatch_origins: None,
+			finality_metrics: None,
+			storage_counters: false,
+			dispatch_errors: false,
+			pov_breakdowns: None,
+			sentry: None,
+			clock: Box::new(SystemClock),
+		}
+	}
+
+	/// Decode the SCALE encoded values of the spans and events with the given decoder,
+	/// before they are handled.
+	///
+	/// The decoder is built from the metadata of a given runtime, so it has to be replaced when
+	/// the runtime is upgraded, see [`ProfilingSubscriber::set_scale_decoder`].
+	pub fn with_scale_decoder(self, scale_decoder: ScaleDecoder) -> Self {
+		ProfilingSubscriber { scale_decoder: RwLock::new(Some(Arc::new(scale_decoder))), ..self }
+	}
+
+	/// Time the spans and events with `clock` rather than the clock of the system, e.g. a
+	/// [`ManualClock`] for their durations and order to be deterministic.
+	pub fn with_clock(self, clock: impl Clock + 'static) -> Self {
+		ProfilingSubscriber { clock: Box::new(clock), ..self }
+	}
+
+	/// Sum up the trace of every imported block, and hand its summary to `sink`.
+	///
+	/// The summaries keep the `max_spans` targets and names most time was spent in. The
+	/// `import_block` spans are enabled whatever the targets, while the other spans are only
+	/// summed up if their target is enabled.
+	pub fn with_block_summaries(
+		self,
+		max_spans: usize,
+		sink: impl Fn(BlockSummary) + Send + Sync + 'static,
+	) -> Self {
+		ProfilingSubscriber {
+			block_summaries: Some(BlockSummaries::new(max_spans, Box::new(sink))),
+			..self
+		}
+	}
+
+	/// Hand at most the spans and events of `limits` to the trace handler for every imported
+	/// block, counting the dropped ones in a [`DROPPED_RECORDS_EVENT`] under the block span.
+	///
+	/// Like the summaries, the limits apply to the spans and events under an `import_block`
+	/// span, which is enabled whatever the targets.
+	pub fn with_limits(self, limits: TraceLimits) -> Self {
+		ProfilingSubscriber { block_limits: Some(BlockLimits::new(limits)), ..self }
+	}
+
+	/// Time the imports of the blocks, reporting the ones past the deadlines of
+	/// `import_deadlines` when [`ProfilingSubscriber::check_import_deadlines`] is called.
+	///
+	/// The `import_block` spans and the [`IMPORT_DEADLINE_TARGET`] are enabled whatever the
+	/// targets, but the breakdowns of the imports only hold the spans of the enabled targets.
+	pub fn with_import_deadlines(self, import_deadlines: ImportDeadlines) -> Self {
+		ProfilingSubscriber { import_deadlines: Some(import_deadlines), ..self }
+	}
+
+	/// Count the spans and their durations by target and name, in at most `max_series`
+	/// series, see [`SpanMetrics`].
+	pub fn with_span_metrics(self, max_series: usize) -> Self {
+		ProfilingSubscriber { span_metrics: Some(SpanMetrics::new(max_series)), ..self }
+	}
+
+	/// Measure the drift of the calls from the weights declared by their dispatch spans, see
+	/// [`WeightDrift`].
+	///
+	/// The dispatch spans are enabled whatever the targets, but the spans of the calls under
+	/// them are only measured if the targets of their pallets are enabled. The calls executed
+	/// in wasm are only measured if wasm tracing is enabled, see `sp_tracing::set_wasm_tracing`.
+	pub fn with_weight_drift(self, weight_drift: WeightDrift) -> Self {
+		ProfilingSubscriber { weight_drift: Some(weight_drift), ..self }
+	}
+
+	/// Account the dispatch spans by the kind of their origin, see [`DispatchOrigins`], e.g. to
+	/// tell the load of the inherents and unsigned extrinsics from the one of the users.
+	///
+	/// The dispatch spans are enabled whatever the targets, but only emitted natively.
+	pub fn with_dispatch_origins(self, dispatch_origins: DispatchOrigins) -> Self {
+		ProfilingSubscriber { dispatch_origins: Some(dispatch_origins), ..self }
+	}
+
+	/// Account the rounds, votes, finalized blocks and proofs of the finality gadgets, see
+	/// [`FinalityMetrics`], so that all the gadgets are exposed alike.
+	///
+	/// The spans and events of the `sp_tracing::FINALITY_TARGET` are enabled whatever the
+	/// targets, but only handled if their target is enabled.
+	pub fn with_finality_metrics(self, finality_metrics: FinalityMetrics) -> Self {
+		ProfilingSubscriber { finality_metrics: Some(finality_metrics), ..self }
+	}
+
+	/// Tally the storage accesses of the externalities in the spans they happen in, and in their
+	/// ancestors, as [`STORAGE_READS_KEY`], [`STORAGE_WRITES_KEY`], [`STORAGE_READ_BYTES_KEY`]
+	/// and [`STORAGE_WRITTEN_BYTES_KEY`] values, e.g. to check the reads and writes of a call
+	/// against its weight. The bytes of the trie nodes added to the storage proofs being recorded
+	/// are tallied as [`PROOF_BYTES_KEY`] values.
+	///
+	/// The accesses are tallied whatever the targets, but only handled as events if their
+	/// targets, `sp_tracing::STORAGE_ACCESS_TARGET` and `sp_tracing::PROOF_SIZE_TARGET`, are
+	/// enabled.
+	pub fn with_storage_counters(self) -> Self {
+		ProfilingSubscriber { storage_counters: true, ..self }
+	}
+
+	/// Report the failed dispatches, with the pallet and call of their dispatch span and their
+	/// error, see [`ERROR_KEY`], whatever the targets.
+	pub fn with_dispatch_errors(self) -> Self {
+		ProfilingSubscriber { dispatch_errors: true, ..self }
+	}
+
+	/// Whether the dispatch spans and the events of their failures are enabled whatever the
+	/// targets, for the features built on them.
+	fn dispatch_enabled(&self) -> bool {
+		self.dispatch_errors || self.weight_drift.is_some() || self.dispatch_origins.is_some()
+			|| self.scale_decoder.read().is_some()
+	}
+
+	/// Break down the proofs of the blocks built under a [`BUILD_BLOCK_SPAN`] by the spans their
+	/// trie nodes are read in, see [`PovBreakdowns`].
+	///
+	/// The build spans are enabled whatever the targets, but the nodes are attributed to the
+	/// innermost enabled span, e.g. to the runtime API call when the targets of the pallets are
+	/// not enabled.
+	pub fn with_pov_breakdowns(self, pov_breakdowns: PovBreakdowns) -> Self {
+		ProfilingSubscriber { pov_breakdowns: Some(pov_breakdowns), ..self }
+	}
+
+	/// Report the `ERROR` events, whatever the targets, and the panics, once
+	/// [`set_panic_hook`] is called, to Sentry.
+	pub fn with_sentry(self, sentry: SentryReporter) -> Self {
+		ProfilingSubscriber { sentry: Some(sentry), ..self }
+	}
+
+	/// Replace the targets, in the format of [`ProfilingSubscriber::new`].
+	///
+	/// The spans and events already created keep being handled, whatever the new targets.
+	pub fn set_targets(&self, targets: &str) {
+		*self.targets.write() = parse_targets(targets);
+		// The callsites cached whether they were enabled by the previous targets.
+		tracing_core::callsite::rebuild_interest_cache();
+	}
+
+	/// Replace the decoder of the SCALE encoded values, e.g. with the one of an upgraded runtime.
+	///
+	/// The spans already created are decoded by the new decoder when they are closed.
+	pub fn set_scale_decoder(&self, scale_decoder: ScaleDecoder) {
+		*self.scale_decoder.write() = Some(Arc::new(scale_decoder));
+		// The callsites of the runtime events cached whether they were enabled without a decoder.
+		tracing_core::callsite::rebuild_interest_cache();
+	}
+
+	fn current_scale_decoder(&self) -> Option<Arc<ScaleDecoder>> {
+		self.scale_decoder.read().clone()
+	}
+
+	/// The counters incremented through this subscriber.
+	pub fn counters(&self) -> Counters {
+		self.counters.clone()
+	}
+
+	/// The metrics of the spans, if counted, see [`ProfilingSubscriber::with_span_metrics`].
+	pub fn span_metrics(&self) -> Option<SpanMetrics> {
+		self.span_metrics.clone()
+	}
+
+	/// The deadlines of the imports, if timed, see [`ProfilingSubscriber::with_import_deadlines`].
+	pub fn import_deadlines(&self) -> Option<ImportDeadlines> {
+		self.import_deadlines.clone()
+	}
+
+	/// Report the imports past a deadline they were not reported past yet, with the spans still
+	/// open under them, and the imports that finished past a deadline, see [`ImportDeadlines`].
+	///
+	/// This is meant to be called periodically by a watchdog, as the events of the imports can't
+	/// be emitted from within the subscriber.
+	pub fn check_import_deadlines(&self) {
+		let import_deadlines = match &self.import_deadlines {
+			Some(import_deadlines) => import_deadlines,
+			None => return,
+		};
+		for overdue in import_deadlines.take_overdue() {
+			let open = if overdue.finished {
+				Vec::new()
+			} else {
+				self.spans.snapshot().into_iter()
+					.filter(|span_datum| span_datum.id.into_u64() != overdue.id
+						&& self.block_span(Some(&span_datum.id)) == Some(overdue.id))
+					.map(|span_datum| {
+						let values = &span_datum.values.string_values;
+						let (target, name) = match (values.get(WASM_TARGET_KEY), values.get(WASM_NAME_KEY)) {
+							(Some(target), Some(name)) if span_datum.name == WASM_TRACE_IDENTIFIER =>
+								(target.as_str(), name.as_str()),
+							_ => (span_datum.target.as_str(), span_datum.name.as_str()),
+						};
+						let open_for = self.clock.now().saturating_duration_since(span_datum.start_time);
+						format!("{}::{} ({}ms)", target, name, open_for.as_millis())
+					})
+					.collect()
+			};
+			import_deadlines.report(&overdue, &open);
+		}
+	}
+
+	/// The drift of the calls, if measured, see [`ProfilingSubscriber::with_weight_drift`].
+	pub fn weight_drift(&self) -> Option<WeightDrift> {
+		self.weight_drift.clone()
+	}
+
+	/// The dispatches by origin, if accounted, see [`ProfilingSubscriber::with_dispatch_origins`].
+	pub fn dispatch_origins(&self) -> Option<DispatchOrigins> {
+		self.dispatch_origins.clone()
+	}
+
+	/// What the finality gadgets did, if accounted, see
+	/// [`ProfilingSubscriber::with_finality_metrics`].
+	pub fn finality_metrics(&self) -> Option<FinalityMetrics> {
+		self.finality_metrics.clone()
+	}
+
+	/// The breakdowns of the proofs of the built blocks, if any, see
+	/// [`ProfilingSubscriber::with_pov_breakdowns`].
+	pub fn pov_breakdowns(&self) -> Option<PovBreakdowns> {
+		self.pov_breakdowns.clone()
+	}
+
+	/// The spans still open, in the order they were created in.
+	pub fn open_spans(&self) -> Vec<SpanDatum> {
+		self.spans.snapshot()
+	}
+
+	/// Flush the data buffered by the trace handler.
+	pub fn flush(&self) {
+		self.trace_handler.flush();
+	}
+
+	/// Report all spans that are still open, then flush the trace handler.
+	///
+	/// The reported spans carry an `unfinished` value. This is meant to be called right before
+	/// the process exits, e.g. on panic, so that the spans that were in progress are not lost.
+	pub fn shutdown(&self) {
+		for mut span_datum in self.spans.take_all() {
+			span_datum.values.bool_values.insert("unfinished".to_owned(), true);
+			self.report_span(span_datum);
+		}
+		self.flush();
+	}
+
+	fn report_span(&self, mut span_datum: SpanDatum) {
+		if let Some(scale_decoder) = self.current_scale_decoder() {
+			scale_decoder.decode(&mut span_datum.values);
+		}
+		if span_datum.name == WASM_TRACE_IDENTIFIER {
+			span_datum.values.bool_values.insert("wasm".to_owned(), true);
+			if let Some(n) = span_datum.values.string_values.remove(WASM_NAME_KEY) {
+				span_datum.name = n;
+			}
+			if let Some(t) = span_datum.values.string_values.remove(WASM_TARGET_KEY) {
+				span_datum.target = t;
+			}
+			self.summarize_span(&span_datum);
+			self.count_span(&span_datum);
+			self.measure_dispatch(&span_datum);
+			let on_chain = span_datum.values.bool_values.get(WASM_ON_CHAIN_KEY) == Some(&true);
+			if (on_chain || self.check_target(&span_datum.target, &span_datum.level))
+				&& self.admit_span(&span_datum)
+			{
+				WrappedHandler { wrappers: &self.wasm_wrappers, handler: &*self.trace_handler }
+					.handle_span(span_datum);
+			}
+		} else {
+			self.summarize_span(&span_datum);
+			self.count_span(&span_datum);
+			self.measure_dispatch(&span_datum);
+			self.count_dispatch(&span_datum);
+			self.count_finality_proof(&span_datum);
+			if let Some(pov_breakdowns) = &self.pov_breakdowns {
+				if PovBreakdowns::is_build_span(&span_datum.name, &span_datum.values) {
+					pov_breakdowns.finish(&span_datum);
+				}
+			}
+			// The dispatch, build and finality spans may only be enabled to measure their calls,
+			// proofs and gadgets.
+			let forced = (span_datum.target == DISPATCH_TARGET || span_datum.name == BUILD_BLOCK_SPAN
+				|| span_datum.target == FINALITY_TARGET)
+				&& !self.check_target(&span_datum.target, &span_datum.level);
+			if !forced && self.admit_span(&span_datum) {
+				self.trace_handler.handle_span(span_datum);
+			}
+		}
+	}
+
+	fn count_span(&self, span_datum: &SpanDatum) {
+		if let Some(span_metrics) = &self.span_metrics {
+			span_metrics.record(&span_datum.target, &span_datum.name, span_datum.overall_time);
+		}
+	}
+
+	/// Account the span by the kind of its origin, if it is a dispatch span.
+	fn count_dispatch(&self, span_datum: &SpanDatum) {
+		if let Some(dispatch_origins) = &self.dispatch_origins {
+			if span_datum.target == DISPATCH_TARGET && span_datum.name == DISPATCH_TARGET {
+				let values = &span_datum.values;
+				let origin = values.string_values.get(ORIGIN_KEY).map_or(UNKNOWN_ORIGIN, String::as_str);
+				let weight = values.u64_values.get("weight").copied().unwrap_or_default();
+				dispatch_origins.record(origin, span_datum.overall_time, weight);
+			}
+		}
+	}
+
+	/// Account the span in the finality gadgets, if it is the span of a finality proof.
+	fn count_finality_proof(&self, span_datum: &SpanDatum) {
+		if let Some(finality_metrics) = &self.finality_metrics {
+			if span_datum.target == FINALITY_TARGET && span_datum.name == PROOF_SPAN {
+				finality_metrics.record_proof(&span_datum.values, span_datum.overall_time);
+			}
 		}
 	}
 
+	/// Record the span as the call of its dispatch span and account it in the drift of the call,
+	/// if it is the span of a call under a dispatch span.
+	fn measure_dispatch(&self, span_datum: &SpanDatum) {
+		let weight = span_datum.parent_id.as_ref().and_then(|id| self.spans.with(id, |s| {
+			if !is_dispatch_span(&s.name, &s.values) {
+				return None;
+			}
+			s.values.string_values.insert(PALLET_KEY.to_owned(), span_datum.target.clone());
+			s.values.string_values.insert(CALL_KEY.to_owned(), span_datum.name.clone());
+			s.values.u64_values.get("weight").copied()
+		}));
+		if let (Some(weight_drift), Some(weight)) = (&self.weight_drift, weight.flatten()) {
+			weight_drift.record(&span_datum.target, &span_datum.name, span_datum.overall_time, weight);
+		}
+	}
+
+	/// Replace the SCALE encoded runtime event of `values` by its summary, and append it to the
+	/// events of its dispatch span, see [`EVENTS_KEY`].
+	fn summarize_runtime_event(&self, parent_id: Option<&Id>, values: &mut Values) {
+		let summary = self.current_scale_decoder()
+			.and_then(|scale_decoder| scale_decoder.decode_event(values.string_values.get("event")?));
+		let summary = match summary {
+			Some(summary) => summary,
+			None => return,
+		};
+		if let Some(dispatch) = self.ancestor_span(parent_id, is_dispatch_span) {
+			self.spans.with(&Id::from_u64(dispatch), |s| {
+				let events = s.values.string_values.entry(EVENTS_KEY.to_owned()).or_default();
+				if events.len() < MAX_EVENTS_LEN {
+					if !events.is_empty() {
+						events.push_str("; ");
+					}
+					events.push_str(&summary);
+				} else if !events.ends_with("; ..") {
+					events.push_str("; ..");
+				}
+			});
+		}
+		values.string_values.insert("event".to_owned(), summary);
+	}
+
+	/// Add the pallet and call of the dispatch span `parent_id` to the values of the event of its
+	/// failure, with its trace id.
+	fn enrich_dispatch_error(&self, parent_id: Option<&Id>, values: &mut Values) {
+		let call = parent_id.and_then(|id| self.spans.with(id, |s| {
+			let pallet = s.values.string_values.get(PALLET_KEY).cloned();
+			let call = s.values.string_values.get(CALL_KEY).cloned();
+			(pallet, call)
+		}));
+		if let Some((pallet, call)) = call {
+			if let Some(pallet) = pallet {
+				values.string_values.entry(PALLET_KEY.to_owned()).or_insert(pallet);
+			}
+			if let Some(call) = call {
+				values.string_values.entry(CALL_KEY.to_owned()).or_insert(call);
+			}
+		}
+	}
+
+	/// Tally the storage access or proof node of the event `target` and `values` in the span
+	/// `id` and its ancestors.
+	fn count_storage_access(&self, id: Option<&Id>, target: &str, values: &Values) {
+		let increment = |span_datum: &mut SpanDatum, key: &str, increment: u64| {
+			*span_datum.values.u64_values.entry(key.to_owned()).or_default() += increment;
+		};
+		if target == PROOF_SIZE_TARGET {
+			let bytes = values.u64_values.get("bytes").copied().unwrap_or_default();
+			self.with_ancestors(id, |span_datum| increment(span_datum, PROOF_BYTES_KEY, bytes));
+			return;
+		}
+		let len = values.u64_values.get("len").copied().unwrap_or_default();
+		let (count_key, bytes_key) = if values.bool_values.get("write") == Some(&true) {
+			(STORAGE_WRITES_KEY, STORAGE_WRITTEN_BYTES_KEY)
+		} else {
+			(STORAGE_READS_KEY, STORAGE_READ_BYTES_KEY)
+		};
+		self.with_ancestors(id, |span_datum| {
+			increment(span_datum, count_key, 1);
+			increment(span_datum, bytes_key, len);
+		});
+	}
+
+	/// Call `f` with the span `id` and each of its ancestors.
+	fn with_ancestors(&self, id: Option<&Id>, mut f: impl FnMut(&mut SpanDatum)) {
+		let mut id = id.cloned();
+		while let Some(span) = id.take() {
+			id = self.spans.with(&span, |span_datum| {
+				f(span_datum);
+				span_datum.parent_id.clone()
+			}).flatten();
+		}
+	}
+
+	/// Attribute the proof node of `values` to the span `id`, if it is under a build span.
+	fn break_down_proof(&self, pov_breakdowns: &PovBreakdowns, id: Option<&Id>, values: &Values) {
+		let block = match self.ancestor_span(id, PovBreakdowns::is_build_span) {
+			Some(block) => block,
+			None => return,
+		};
+		let span = id.and_then(|id| self.spans.with(id, |span_datum| {
+			let values = &span_datum.values.string_values;
+			match (values.get(WASM_TARGET_KEY), values.get(WASM_NAME_KEY)) {
+				(Some(target), Some(name)) if span_datum.name == WASM_TRACE_IDENTIFIER =>
+					(target.clone(), name.clone()),
+				_ => (span_datum.target.clone(), span_datum.name.clone()),
+			}
+		}));
+		if let Some((target, name)) = span {
+			let bytes = values.u64_values.get("bytes").copied().unwrap_or_default();
+			pov_breakdowns.record(block, &target, &name, bytes);
+		}
+	}
+
+	/// Whether the span fits in the limits of its block, if any. The dropped records of a
+	/// block are reported when its span closes.
+	fn admit_span(&self, span_datum: &SpanDatum) -> bool {
+		let block_limits = match &self.block_limits {
+			Some(block_limits) => block_limits,
+			None => return true,
+		};
+		if BlockSummaries::is_block_span(&span_datum.name, &span_datum.values) {
+			if let Some(dropped) = block_limits.finish(span_datum) {
+				self.trace_handler.handle_event(dropped);
+			}
+			return true;
+		}
+		match self.block_span(span_datum.parent_id.as_ref()) {
+			Some(block) => block_limits.admit_span(block, &span_datum.values),
+			None => true,
+		}
+	}
+
+	fn summarize_span(&self, span_datum: &SpanDatum) {
+		if self.block_summaries.is_none() && self.import_deadlines.is_none() {
+			return;
+		}
+		if BlockSummaries::is_block_span(&span_datum.name, &span_datum.values) {
+			if let Some(block_summaries) = &self.block_summaries {
+				block_summaries.finish(span_datum);
+			}
+			if let Some(import_deadlines) = &self.import_deadlines {
+				import_deadlines.finish(span_datum.id.into_u64());
+			}
+		} else if let Some(block) = self.block_span(span_datum.parent_id.as_ref()) {
+			if let Some(block_summaries) = &self.block_summaries {
+				block_summaries.record_span(block, span_datum);
+			}
+			if let Some(import_deadlines) = &self.import_deadlines {
+				import_deadlines.record_span(block, span_datum);
+			}
+		}
+	}
+
+	/// The id of the block span the span `id` belongs to, if any.
+	fn block_span(&self, id: Option<&Id>) -> Option<u64> {
+		self.ancestor_span(id, BlockSummaries::is_block_span)
+	}
+
+	/// The id of the span `id`, or of its closest ancestor, that is a root according to `is_root`.
+	fn ancestor_span(&self, id: Option<&Id>, is_root: fn(&str, &Values) -> bool) -> Option<u64> {
+		let mut id = id?.clone();
+		loop {
+			let (is_root, parent_id) = self.spans.with(&id, |span_datum| (
+				is_root(&span_datum.name, &span_datum.values),
+				span_datum.parent_id.clone(),
+			))?;
+			if is_root {
+				return Some(id.into_u64());
+			}
+			id = parent_id?;
+		}
+	}
+
+	/// Record the client trace id of the span `parent_id`, if any, see
+	/// `sp_tracing::proxy::TracingProxy::enter_trace_context`.
+	fn inherit_trace_id(&self, values: &mut Values, parent_id: Option<&Id>) {
+		if values.string_values.contains_key(TRACE_ID_KEY) {
+			return;
+		}
+		let trace_id = parent_id.and_then(|id| self.spans.with(id, |s| {
+			s.values.string_values.get(TRACE_ID_KEY).cloned()
+		}));
+		if let Some(trace_id) = trace_id.flatten() {
+			values.string_values.insert(TRACE_ID_KEY.to_owned(), trace_id);
+		}
+	}
+
+	/// The open spans from the root to the span `id`.
+	///
+	/// With `try_lock`, the stack stops at the first span that is locked.
+	fn span_stack(&self, id: Option<&Id>, try_lock: bool) -> Vec<Breadcrumb> {
+		let breadcrumb = |span_datum: &mut SpanDatum| (
+			Breadcrumb {
+				target: span_datum.target.clone(),
+				name: span_datum.name.clone(),
+				level: span_datum.level.clone(),
+				start: span_datum.start_wall_time,
+				values: span_datum.values.clone(),
+			},
+			span_datum.parent_id.clone(),
+		);
+		let mut stack = Vec::new();
+		let mut id = id.cloned();
+		while let Some(span) = id.take() {
+			let found = if try_lock {
+				self.spans.try_with(&span, breadcrumb)
+			} else {
+				self.spans.with(&span, breadcrumb)
+			};
+			if let Some((breadcrumb, parent_id)) = found {
+				stack.push(breadcrumb);
+				id = parent_id;
+			}
+		}
+		stack.reverse();
+		stack
+	}
+
+	/// The open spans of the current thread, formatted from the root as `target::name{values}`
+	/// separated by ` > `, `None` if there are none.
+	///
+	/// The values are redacted and, as this is meant for the reports of the panics, the stack
+	/// stops at the first span that is locked.
+	fn span_context(&self) -> Option<String> {
+		self.format_span_context(self.span_stack(self.current_span.id().as_ref(), true))
+	}
+
+	/// The spans of `stack`, formatted as by [`Self::span_context`].
+	fn format_span_context(&self, stack: Vec<Breadcrumb>) -> Option<String> {
+		if stack.is_empty() {
+			return None;
+		}
+		let spans: Vec<_> = stack.into_iter().map(|span| {
+			let mut values = self.redaction.redact(&span.values);
+			let wasm_target = values.string_values.remove(WASM_TARGET_KEY);
+			let wasm_name = values.string_values.remove(WASM_NAME_KEY);
+			values.bool_values.remove(WASM_ON_CHAIN_KEY);
+			values.bool_values.remove("is_valid_trace");
+			let target = wasm_target.unwrap_or(span.target);
+			let name = wasm_name.unwrap_or(span.name);
+			if values.is_empty() {
+				format!("{}::{}", target, name)
+			} else {
+				format!("{}::{}{{{}}}", target, name, values)
+			}
+		}).collect();
+		Some(spans.join(" > "))
+	}
+
+	/// The spans of `stack` with their values redacted, e.g. for Sentry.
+	fn redact_stack(&self, stack: &[Breadcrumb]) -> Vec<Breadcrumb> {
+		stack.iter()
+			.map(|span| Breadcrumb { values: self.redaction.redact(&span.values), ..span.clone() })
+			.collect()
+	}
+
+	/// The trace id of the current span, inherited from its trace context, see
+	/// `sp_tracing::proxy::TRACE_ID_KEY`.
+	fn trace_id(&self) -> Option<String> {
+		let id = self.current_span.id()?;
+		self.spans.with(&id, |s| s.values.string_values.get(TRACE_ID_KEY).cloned()).flatten()
+	}
+
+	/// Report a panic, with the spans it happened in, to the telemetry and to Sentry, if
+	/// configured.
+	/// The report of a panic of the current thread, built by `report` from the spans the thread
+	/// is in.
+	///
+	/// The panic may have happened while the spans were locked, so this doesn't wait for any lock:
+	/// the locked spans are skipped.
+	fn panic_report(&self, report: impl FnOnce(Vec<Breadcrumb>) -> panic::PanicReport) -> panic::PanicReport {
+		report(self.span_stack(self.current_span.id().as_ref(), true))
+	}
+
+	fn report_panic(&self, report: &panic::PanicReport) {
+		sc_telemetry::without_routing(|| telemetry!(SUBSTRATE_INFO; "node.panic";
+			"message" => report.info.clone(),
+			"spans" => self.format_span_context(report.spans.clone())
+		));
+		if let Some(sentry) = &self.sentry {
+			sentry.report_panic(report.message.clone(), report.thread.as_deref(), &self.redact_stack(&report.spans));
+		}
+	}
+
+	/// Whether the telemetry messages of `level` are sent: all of them, unless the targets
+	/// configure the level of the [`TELEMETRY_TARGET`], see `sc_telemetry::verbosity_level`.
+	fn telemetry_enabled(&self, level: &Level) -> bool {
+		let targets = self.targets.read();
+		let mut configured = targets.iter()
+			.filter(|t| TELEMETRY_TARGET.starts_with(t.0.as_str()))
+			.peekable();
+		configured.peek().is_none() || configured.any(|t| level <= &t.1)
+	}
+
 	fn check_target(&self, target: &str, level: &Level) -> bool {
-		for t in &self.targets {
+		if target == COUNTER_TARGET || target == ASSERTION_TARGET || AUDIT_TARGETS.contains(&target) {
+			return true;
+		}
+		for t in self.targets.read().iter() {
 			if target.starts_with(t.0.as_str()) && level <= &t.1 {
 				return true;
 			}
@@ -259,6 +1128,90 @@ impl ProfilingSubscriber {
 	}
 }
 
+/// Chain a panic hook reporting the panics through the global `ProfilingSubscriber`, if one is
+/// set, to the telemetry and to Sentry if set [`ProfilingSubscriber::with_sentry`].
+///
+/// The hook doesn't wait for any lock, the reports are sent from a thread of their own.
+pub fn set_panic_hook() {
+	panic::start();
+	let previous = std::panic::take_hook();
+	std::panic::set_hook(Box::new(move |info| {
+		tracing::dispatcher::get_default(|dispatch| {
+			if let Some(subscriber) = dispatch.downcast_ref::<ProfilingSubscriber>() {
+				panic::queue(subscriber.panic_report(|spans| panic::PanicReport::new(info, spans)));
+			}
+		});
+		previous(info)
+	}));
+}
+
+/// The open spans of the current thread in the global `ProfilingSubscriber`, if one is set.
+///
+/// This is the provider of `sp_tracing::span_context`, set by [`TracingBuilder::init`]. It waits
+/// for no lock, to be called by the panic hook: the spans are skipped when locked, and the
+/// redaction only reads its fields.
+pub fn global_span_context() -> Option<String> {
+	tracing::dispatcher::get_default(|dispatch| {
+		dispatch.downcast_ref::<ProfilingSubscriber>()
+			.and_then(|subscriber| subscriber.span_context())
+	})
+}
+
+/// The trace id of the current span of the global `ProfilingSubscriber`, if one is set.
+///
+/// This is the provider of `sp_tracing::trace_id`, set by [`TracingBuilder::init`].
+pub fn global_trace_id() -> Option<String> {
+	tracing::dispatcher::get_default(|dispatch| {
+		dispatch.downcast_ref::<ProfilingSubscriber>()
+			.and_then(|subscriber| subscriber.trace_id())
+	})
+}
+
+/// The open spans of the global `ProfilingSubscriber`, if one is set.
+///
+/// See [`ProfilingSubscriber::open_spans`].
+pub fn global_open_spans() -> Vec<SpanDatum> {
+	tracing::dispatcher::get_default(|dispatch| {
+		dispatch.downcast_ref::<ProfilingSubscriber>()
+			.map(|subscriber| subscriber.open_spans())
+			.unwrap_or_default()
+	})
+}
+
+/// Flush the global `ProfilingSubscriber`, if one is set.
+pub fn flush_global() {
+	tracing::dispatcher::get_default(|dispatch| {
+		if let Some(subscriber) = dispatch.downcast_ref::<ProfilingSubscriber>() {
+			subscriber.flush();
+		}
+	})
+}
+
+/// Report the open spans of the global `ProfilingSubscriber` and flush it, if one is set.
+///
+/// See [`ProfilingSubscriber::shutdown`].
+pub fn shutdown_global() {
+	tracing::dispatcher::get_default(|dispatch| {
+		if let Some(subscriber) = dispatch.downcast_ref::<ProfilingSubscriber>() {
+			subscriber.shutdown();
+		}
+	})
+}
+
+/// Whether the span is the dispatch span of an extrinsic or of a nested call, see
+/// `sp_tracing::DISPATCH_TARGET`, either native or of the runtime.
+fn is_dispatch_span(name: &str, values: &Values) -> bool {
+	name == DISPATCH_TARGET || (name == WASM_TRACE_IDENTIFIER
+		&& values.string_values.get(WASM_NAME_KEY).map(String::as_str) == Some(DISPATCH_TARGET))
+}
+
+fn parse_targets(targets: &str) -> Vec<(String, Level)> {
+	targets.split(',')
+		.filter(|s| !s.is_empty())
+		.map(|s| parse_target(s))
+		.collect()
+}
+
 // Default to TRACE if no level given or unable to parse Level
 // We do not support a global `Level` currently
 fn parse_target(s: &str) -> (String, Level) {
@@ -278,7 +1231,23 @@ fn parse_target(s: &str) -> (String, Level) {
 
 impl Subscriber for ProfilingSubscriber {
 	fn enabled(&self, metadata: &Metadata<'_>) -> bool {
-		if metadata.target() == PROXY_TARGET || self.check_target(metadata.target(), metadata.level()) {
+		let block_span = (self.block_summaries.is_some() || self.block_limits.is_some()
+			|| self.import_deadlines.is_some()) && metadata.name() == BLOCK_SPAN;
+		let import_deadline = self.import_deadlines.is_some() && metadata.target() == IMPORT_DEADLINE_TARGET;
+		let telemetry = metadata.target() == TELEMETRY_TARGET && self.telemetry_enabled(metadata.level());
+		let sentry_event = self.sentry.is_some() && metadata.is_event() && *metadata.level() == Level::ERROR;
+		let dispatch = metadata.target() == DISPATCH_TARGET && self.dispatch_enabled();
+		let runtime_event = self.scale_decoder.read().is_some() && metadata.target() == RUNTIME_EVENT_TARGET;
+		let storage_access = (self.storage_counters
+			&& (metadata.target() == STORAGE_ACCESS_TARGET || metadata.target() == PROOF_SIZE_TARGET))
+			|| (self.block_summaries.is_some() && metadata.target() == STORAGE_ACCESS_TARGET);
+		let proof = self.pov_breakdowns.is_some()
+			&& (metadata.name() == BUILD_BLOCK_SPAN || metadata.target() == PROOF_SIZE_TARGET);
+		let finality = self.finality_metrics.is_some() && metadata.target() == FINALITY_TARGET;
+		if metadata.target() == PROXY_TARGET || block_span || import_deadline || telemetry || sentry_event || dispatch
+			|| runtime_event || storage_access || proof || finality
+			|| self.check_target(metadata.target(), metadata.level())
+		{
 			log::debug!(target: "tracing", "Enabled target: {}, level: {}", metadata.target(), metadata.level());
 			true
 		} else {
@@ -288,35 +1257,63 @@ impl Subscriber for ProfilingSubscriber {
 	}
 
 	fn new_span(&self, attrs: &Attributes<'_>) -> Id {
-		let id = Id::from_u64(self.next_id.fetch_add(1, Ordering::Relaxed));
+		let id = self.spans.next_id();
 		let mut values = Values::default();
 		attrs.record(&mut values);
-		// If this is a wasm trace, check if target/level is enabled
+		// If this is a wasm trace, check if target/level is enabled, unless it is enabled on-chain
 		if let Some(wasm_target) = values.string_values.get(WASM_TARGET_KEY) {
-			if !self.check_target(wasm_target, attrs.metadata().level()) {
+			let on_chain = values.bool_values.get(WASM_ON_CHAIN_KEY) == Some(&true);
+			// The dispatch spans may only be enabled for the features built on them.
+			let dispatch = wasm_target == DISPATCH_TARGET && self.dispatch_enabled();
+			if !on_chain && !dispatch && !self.check_target(wasm_target, attrs.metadata().level()) {
 				return id
 			}
 		}
+		let parent_id = attrs.parent().cloned().or_else(|| self.current_span.id());
+		self.inherit_trace_id(&mut values, parent_id.as_ref());
+		let mut name = attrs.metadata().name().to_owned();
+		let mut target = attrs.metadata().target().to_owned();
+		// Task spans link the spans of a task to its parent, under the target of the parent, the
+		// proxied one for the spans of the runtime, e.g. of the instances it spawns.
+		if name == TASK_TRACE_IDENTIFIER {
+			let parent_target = parent_id.as_ref()
+				.and_then(|id| self.spans.with(id, |s| match s.values.string_values.get(WASM_TARGET_KEY) {
+					Some(target) if s.name == WASM_TRACE_IDENTIFIER => target.clone(),
+					_ => s.target.clone(),
+				}));
+			match (parent_target, values.string_values.remove(TASK_NAME_KEY)) {
+				(Some(parent_target), Some(task_name)) => {
+					name = task_name;
+					target = parent_target;
+				},
+				_ => return id,
+			}
+		}
+		let start_time = self.clock.now();
 		let span_datum = SpanDatum {
 			id: id.clone(),
-			parent_id: attrs.parent().cloned().or_else(|| self.current_span.id()),
-			name: attrs.metadata().name().to_owned(),
-			target: attrs.metadata().target().to_owned(),
+			parent_id,
+			name,
+			target,
 			level: attrs.metadata().level().clone(),
 			line: attrs.metadata().line().unwrap_or(0),
-			start_time: Instant::now(),
+			start_time,
+			start_wall_time: self.clock.wall_time(start_time),
 			overall_time: ZERO_DURATION,
 			values,
+			metadata: attrs.metadata(),
 		};
-		self.span_data.lock().insert(id.clone(), span_datum);
+		if let Some(import_deadlines) = &self.import_deadlines {
+			if BlockSummaries::is_block_span(&span_datum.name, &span_datum.values) {
+				import_deadlines.start(id.into_u64(), &span_datum.values);
+			}
+		}
+		self.spans.insert(span_datum);
 		id
 	}
 
 	fn record(&self, span: &Id, values: &Record<'_>) {
-		let mut span_data = self.span_data.lock();
-		if let Some(s) = span_data.get_mut(span) {
-			values.record(&mut s.values);
-		}
+		self.spans.with(span, |s| values.record(&mut s.values));
 	}
 
 	fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
@@ -324,54 +1321,137 @@ impl Subscriber for ProfilingSubscriber {
 	fn event(&self, event: &Event<'_>) {
 		let mut values = Values::default();
 		event.record(&mut values);
+		let parent_id = event.parent().cloned().or_else(|| self.current_span.id());
+		self.inherit_trace_id(&mut values, parent_id.as_ref());
+		let target = event.metadata().target();
+		if target == COUNTER_TARGET {
+			self.counters.increment(&values);
+			if let Some(block_summaries) = &self.block_summaries {
+				if let Some(block) = self.block_span(parent_id.as_ref()) {
+					block_summaries.record_counter(block, &values);
+				}
+			}
+			return;
+		}
+		if target == TELEMETRY_TARGET {
+			// The telemetry messages are sampled and redacted like the other events, but they are
+			// sent to the telemetry rather than handled.
+			let admitted = match (&self.block_limits, self.block_span(parent_id.as_ref())) {
+				(Some(block_limits), Some(block)) => block_limits.admit_event(block, &values),
+				_ => true,
+			};
+			if admitted {
+				sc_telemetry::admit_routed(&self.redaction);
+			}
+			return;
+		}
+		if target == STORAGE_ACCESS_TARGET || target == PROOF_SIZE_TARGET {
+			if self.storage_counters {
+				self.count_storage_access(parent_id.as_ref(), target, &values);
+			}
+			if let (Some(block_summaries), STORAGE_ACCESS_TARGET) = (&self.block_summaries, target) {
+				if let Some(block) = self.block_span(parent_id.as_ref()) {
+					block_summaries.record_storage_access(block, &values);
+				}
+			}
+			if let (Some(pov_breakdowns), PROOF_SIZE_TARGET) = (&self.pov_breakdowns, target) {
+				self.break_down_proof(pov_breakdowns, parent_id.as_ref(), &values);
+			}
+			// The accesses may only be enabled to be tallied.
+			if !self.check_target(target, event.metadata().level()) {
+				return;
+			}
+		}
+		if target == RUNTIME_EVENT_TARGET {
+			self.summarize_runtime_event(parent_id.as_ref(), &mut values);
+			// The runtime events may only be enabled to be summarized in their dispatch span.
+			if !self.check_target(target, event.metadata().level()) {
+				return;
+			}
+		}
+		if target == DISPATCH_TARGET {
+			// The failures may only be enabled for the other features built on the dispatches.
+			if !self.dispatch_errors && !self.check_target(target, event.metadata().level()) {
+				return;
+			}
+			self.enrich_dispatch_error(parent_id.as_ref(), &mut values);
+		}
+		if target == FINALITY_TARGET {
+			if let Some(finality_metrics) = &self.finality_metrics {
+				finality_metrics.record_event(&values, self.clock.now());
+			}
+			// The events of the finality gadgets may only be enabled to be accounted.
+			if !self.check_target(target, event.metadata().level()) {
+				return;
+			}
+		}
+		if let Some(scale_decoder) = self.current_scale_decoder() {
+			scale_decoder.decode(&mut values);
+		}
+		if let (Some(sentry), &Level::ERROR) = (&self.sentry, event.metadata().level()) {
+			let stack = self.redact_stack(&self.span_stack(parent_id.as_ref(), false));
+			sentry.report_event(target, event.metadata().name(), &self.redaction.redact(&values), &stack);
+			// The event is only enabled for Sentry.
+			if !self.check_target(target, event.metadata().level()) {
+				return;
+			}
+		}
+		if AUDIT_TARGETS.contains(&target) {
+			// Audit records must be self-contained, so they include the values of their span.
+			let parent_values = parent_id.as_ref()
+				.and_then(|id| self.spans.with(id, |s| s.values.clone()));
+			if let Some(parent_values) = parent_values {
+				values.extend_missing(&parent_values);
+			}
+			audit::forward_audit_event(event, &values, &self.redaction);
+		}
+		if let Some(block_limits) = &self.block_limits {
+			if let Some(block) = self.block_span(parent_id.as_ref()) {
+				if !block_limits.admit_event(block, &values) {
+					return;
+				}
+			}
+		}
 		let trace_event = TraceEvent {
 			name: event.metadata().name(),
-			target: event.metadata().target().to_owned(),
+			target: target.to_owned(),
 			level: event.metadata().level().clone(),
 			values,
-			parent_id: event.parent().cloned().or_else(|| self.current_span.id()),
+			parent_id,
+			time: self.clock.wall_time(self.clock.now()),
 		};
 		self.trace_handler.handle_event(trace_event);
 	}
 
 	fn enter(&self, span: &Id) {
 		self.current_span.enter(span.clone());
-		let mut span_data = self.span_data.lock();
-		let start_time = Instant::now();
-		if let Some(mut s) = span_data.get_mut(&span) {
+		let start_time = self.clock.now();
+		let start_wall_time = self.clock.wall_time(start_time);
+		self.spans.with(span, |s| {
 			s.start_time = start_time;
-		}
+			s.start_wall_time = start_wall_time;
+		});
 	}
 
 	fn exit(&self, span: &Id) {
 		self.current_span.exit();
-		let end_time = Instant::now();
-		let mut span_data = self.span_data.lock();
-		if let Some(mut s) = span_data.get_mut(&span) {
-			s.overall_time = end_time - s.start_time + s.overall_time;
+		let end_time = self.clock.now();
+		self.spans.with(span, |s| s.overall_time = end_time.saturating_duration_since(s.start_time) + s.overall_time);
+	}
+
+	fn current_span(&self) -> Current {
+		match self.current_span.id() {
+			Some(id) => match self.spans.with(&id, |s| s.metadata) {
+				Some(metadata) => Current::new(id, metadata),
+				None => Current::none(),
+			},
+			None => Current::none(),
 		}
 	}
 
 	fn try_close(&self, span: Id) -> bool {
-		let span_datum = {
-			let mut span_data = self.span_data.lock();
-			span_data.remove(&span)
-		};
-		if let Some(mut span_datum) = span_datum {
-			if span_datum.name == WASM_TRACE_IDENTIFIER {
-				span_datum.values.bool_values.insert("wasm".to_owned(), true);
-				if let Some(n) = span_datum.values.string_values.remove(WASM_NAME_KEY) {
-					span_datum.name = n;
-				}
-				if let Some(t) = span_datum.values.string_values.remove(WASM_TARGET_KEY) {
-					span_datum.target = t;
-				}
-				if self.check_target(&span_datum.target, &span_datum.level) {
-					self.trace_handler.handle_span(span_datum);
-				}
-			} else {
-				self.trace_handler.handle_span(span_datum);
-			}
+		if let Some(span_datum) = self.spans.remove(&span) {
+			self.report_span(span_datum);
 		};
 		true
 	}
@@ -390,39 +1470,131 @@ fn log_level(level: Level) -> log::Level {
 	}
 }
 
+/// The fields of a span or event logged by `LogTraceHandler`, the system log outputs mapping
+/// them to their own structured fields.
+///
+/// The `trace_start_ns` of the spans is their last entry on the monotonic clock, to correlate them
+/// with the samples of perf, see `sc_executor::set_wasmtime_jitdump`.
+struct LogFields<'a> {
+	fields: &'a [(&'static str, String)],
+	values: &'a Values,
+}
+
+impl<'a> log::kv::Source for LogFields<'a> {
+	fn visit<'kvs>(&'kvs self, visitor: &mut dyn log::kv::Visitor<'kvs>) -> Result<(), log::kv::Error> {
+		use log::kv::{ToKey, ToValue};
+		for (k, v) in self.fields {
+			visitor.visit_pair(k.to_key(), v.to_value())?;
+		}
+		for (k, v) in &self.values.bool_values {
+			visitor.visit_pair(k.to_key(), v.to_value())?;
+		}
+		for (k, v) in &self.values.i64_values {
+			visitor.visit_pair(k.to_key(), v.to_value())?;
+		}
+		for (k, v) in &self.values.u64_values {
+			visitor.visit_pair(k.to_key(), v.to_value())?;
+		}
+		for (k, v) in &self.values.string_values {
+			visitor.visit_pair(k.to_key(), v.to_value())?;
+		}
+		Ok(())
+	}
+}
+
+/// The time of `instant` on the monotonic clock, in nanoseconds, which is the clock of the perf
+/// jitdump files and of `perf record -k mono`.
+#[cfg(unix)]
+fn monotonic_ns(instant: Instant) -> Option<u64> {
+	let mut now = libc::timespec { tv_sec: 0, tv_nsec: 0 };
+	// Safe, `now` outlives the call.
+	if unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut now) } != 0 {
+		return None;
+	}
+	let now = Duration::new(now.tv_sec as u64, now.tv_nsec as u32);
+	now.checked_sub(instant.elapsed()).map(|time| time.as_nanos() as u64)
+}
+
+#[cfg(not(unix))]
+fn monotonic_ns(_: Instant) -> Option<u64> {
+	None
+}
+
+fn log_with_fields(level: Level, args: fmt::Arguments, fields: LogFields) {
+	let level = log_level(level);
+	if level <= log::max_level() {
+		log::logger().log(&log::Record::builder()
+			.args(args)
+			.level(level)
+			.target(module_path!())
+			.module_path_static(Some(module_path!()))
+			.file_static(Some(file!()))
+			.line(Some(line!()))
+			.key_values(&fields)
+			.build());
+	}
+}
+
 impl TraceHandler for LogTraceHandler {
 	fn handle_span(&self, span_datum: SpanDatum) {
+		let fields = [
+			("trace_target", span_datum.target.clone()),
+			("trace_name", span_datum.name.clone()),
+			("trace_time_ns", span_datum.overall_time.as_nanos().to_string()),
+			("trace_start_ns", monotonic_ns(span_datum.start_time).map(|t| t.to_string()).unwrap_or_default()),
+			("trace_id", span_datum.id.into_u64().to_string()),
+			("trace_parent_id", span_datum.parent_id.as_ref().map(|s| s.into_u64()).unwrap_or_default().to_string()),
+		];
+		let fields = LogFields { fields: &fields, values: &span_datum.values };
 		if span_datum.values.is_empty() {
-			log::log!(
-				log_level(span_datum.level),
-				"{}: {}, time: {}, id: {}, parent_id: {:?}",
-				span_datum.target,
-				span_datum.name,
-				span_datum.overall_time.as_nanos(),
-				span_datum.id.into_u64(),
-				span_datum.parent_id.map(|s| s.into_u64()),
+			log_with_fields(
+				span_datum.level,
+				format_args!(
+					"{}: {}, time: {}, id: {}, parent_id: {:?}",
+					span_datum.target,
+					span_datum.name,
+					span_datum.overall_time.as_nanos(),
+					span_datum.id.into_u64(),
+					span_datum.parent_id.map(|s| s.into_u64()),
+				),
+				fields,
 			);
 		} else {
-			log::log!(
-				log_level(span_datum.level),
-				"{}: {}, time: {}, id: {}, parent_id: {:?}, values: {}",
-				span_datum.target,
-				span_datum.name,
-				span_datum.overall_time.as_nanos(),
-				span_datum.id.into_u64(),
-				span_datum.parent_id.map(|s| s.into_u64()),
-				span_datum.values,
+			log_with_fields(
+				span_datum.level,
+				format_args!(
+					"{}: {}, time: {}, id: {}, parent_id: {:?}, values: {}",
+					span_datum.target,
+					span_datum.name,
+					span_datum.overall_time.as_nanos(),
+					span_datum.id.into_u64(),
+					span_datum.parent_id.map(|s| s.into_u64()),
+					span_datum.values,
+				),
+				fields,
 			);
 		}
 	}
 
+	fn flush(&self) {
+		log::logger().flush();
+	}
+
 	fn handle_event(&self, event: TraceEvent) {
-		log::log!(
-			log_level(event.level),
-			"{}, parent_id: {:?}, {}",
-			event.target,
-			event.parent_id.map(|s| s.into_u64()),
-			event.values,
+		let fields = [
+			("trace_target", event.target.clone()),
+			("trace_name", event.name.to_owned()),
+			("trace_parent_id", event.parent_id.as_ref().map(|s| s.into_u64()).unwrap_or_default().to_string()),
+		];
+		log_with_fields(
+			event.level,
+			format_args!(
+				"{}, parent_id: {:?}, {}",
+				event.target,
+				event.parent_id.map(|s| s.into_u64()),
+				event.values,
+			),
+			LogFields { fields: &fields, values: &event.values },
 		);
 	}
 }
@@ -430,27 +1602,41 @@ impl TraceHandler for LogTraceHandler {
 /// TraceHandler for sending span data to telemetry,
 /// Please see telemetry documentation for details on how to specify endpoints and
 /// set the required telemetry level to activate tracing messages
-pub struct TelemetryTraceHandler;
+#[derive(Default)]
+pub struct TelemetryTraceHandler {
+	redaction: Redaction,
+}
+
+impl TelemetryTraceHandler {
+	/// Create a handler redacting the given fields before sending them.
+	pub fn new(redaction: Redaction) -> Self {
+		TelemetryTraceHandler { redaction }
+	}
+}
 
 impl TraceHandler for TelemetryTraceHandler {
 	fn handle_span(&self, span_datum: SpanDatum) {
-		telemetry!(SUBSTRATE_INFO; "tracing.profiling";
+		sc_telemetry::without_routing(|| telemetry!(SUBSTRATE_INFO; "tracing.profiling";
 			"name" => span_datum.name,
 			"target" => span_datum.target,
 			"time" => span_datum.overall_time.as_nanos(),
 			"id" => span_datum.id.into_u64(),
 			"parent_id" => span_datum.parent_id.map(|i| i.into_u64()),
-			"values" => span_datum.values
-		);
+			"values" => self.redaction.redact(&span_datum.values)
+		));
 	}
 
 	fn handle_event(&self, event: TraceEvent) {
-		telemetry!(SUBSTRATE_INFO; "tracing.event";
+		sc_telemetry::without_routing(|| telemetry!(SUBSTRATE_INFO; "tracing.event";
 			"name" => event.name,
 			"target" => event.target,
 			"parent_id" => event.parent_id.map(|i| i.into_u64()),
-			"values" => event.values
-		);
+			"values" => self.redaction.redact(&event.values)
+		));
+	}
+
+	fn flush(&self) {
+		sc_telemetry::flush();
 	}
 }
 
@@ -509,6 +1695,119 @@ mod tests {
 		assert!(time > 0);
 	}
 
+	#[test]
+	fn test_span_timed_with_clock() {
+		let (sub, spans, events) = setup_subscriber();
+		let clock = ManualClock::new(std::time::UNIX_EPOCH);
+		let _sub_guard = tracing::subscriber::set_default(sub.with_clock(clock.clone()));
+		let span = tracing::info_span!(target: "test_target", "test_span1");
+		clock.advance(Duration::from_millis(1));
+		span.in_scope(|| {
+			clock.advance(Duration::from_millis(2));
+			tracing::info!(target: "test_target", "inside");
+			clock.advance(Duration::from_millis(3));
+		});
+		clock.advance(Duration::from_millis(4));
+		span.in_scope(|| clock.advance(Duration::from_millis(5)));
+		drop(span);
+
+		let sd = spans.lock().remove(0);
+		assert_eq!(sd.overall_time, Duration::from_millis(10));
+		assert_eq!(sd.start_wall_time, std::time::UNIX_EPOCH + Duration::from_millis(10));
+		assert_eq!(events.lock()[0].time, std::time::UNIX_EPOCH + Duration::from_millis(3));
+	}
+
+	#[cfg(unix)]
+	#[test]
+	fn monotonic_ns_follows_instants() {
+		let now = Instant::now();
+		let earlier = now - Duration::from_millis(10);
+		let (now, earlier) = (monotonic_ns(now).unwrap(), monotonic_ns(earlier).unwrap());
+		// The second call reads the clock later, shortening the difference.
+		assert!(now > earlier && now - earlier <= 10_000_000, "{}", now - earlier);
+	}
+
+	#[test]
+	fn test_current_span() {
+		let (sub, _spans, _events) = setup_subscriber();
+		let _sub_guard = tracing::subscriber::set_default(sub);
+		assert!(tracing::Span::current().metadata().is_none());
+		let span = tracing::info_span!(target: "test_target", "test_span1");
+		let _guard = span.enter();
+		let current = tracing::Span::current();
+		assert_eq!(current.metadata().unwrap().name(), "test_span1");
+		assert_eq!(current.id(), span.id());
+	}
+
+	#[test]
+	fn test_span_context() {
+		let (sub, _spans, _events) = setup_subscriber();
+		let _sub_guard = tracing::subscriber::set_default(sub);
+		assert_eq!(global_span_context(), None);
+		let span = tracing::info_span!(target: "test_target", "import_block", block_number = 42u64);
+		let _guard = span.enter();
+		let mut proxy = sp_tracing::proxy::TracingProxy::new();
+		let wasm_span = proxy.enter_span("test_target", "transfer", false);
+		assert_eq!(
+			global_span_context().unwrap(),
+			"test_target::import_block{block_number=42} > test_target::transfer",
+		);
+		proxy.exit_span(wasm_span);
+	}
+
+	#[test]
+	fn test_panic_report_does_not_wait_for_locks() {
+		let (sub, spans, _events) = setup_subscriber();
+		let dispatch = tracing::Dispatch::new(sub);
+		let _sub_guard = tracing::dispatcher::set_default(&dispatch);
+		let subscriber = dispatch.downcast_ref::<ProfilingSubscriber>().unwrap();
+		let outer = tracing::info_span!(target: "test_target", "outer_span");
+		let _outer_guard = outer.enter();
+		let inner = tracing::info_span!(target: "test_target", "inner_span");
+		let _inner_guard = inner.enter();
+
+		// As if the thread panicked while the outer span is locked.
+		let report = subscriber.spans.with(&outer.id().unwrap(), |_| {
+			subscriber.panic_report(|spans| panic::PanicReport {
+				info: "panicked at 'test'".into(),
+				message: "test".into(),
+				thread: None,
+				spans,
+			})
+		}).unwrap();
+		let names = report.spans.iter().map(|s| s.name.as_str()).collect::<Vec<_>>();
+		assert_eq!(names, vec!["inner_span"]);
+
+		// Then the reporter thread reports it and flushes the open spans, see `shutdown_after_panic`.
+		subscriber.report_panic(&report);
+		subscriber.shutdown();
+		let flushed = spans.lock().drain(..).map(|s| s.name).collect::<Vec<_>>();
+		assert_eq!(flushed, vec!["outer_span", "inner_span"]);
+	}
+
+	#[test]
+	fn test_shutdown_reports_open_spans() {
+		let (sub, spans, _events) = setup_subscriber();
+		let _sub_guard = tracing::subscriber::set_default(sub);
+		let span1 = tracing::info_span!(target: "test_target", "test_span1");
+		let _guard1 = span1.enter();
+		let span2 = tracing::info_span!(target: "test_target", "test_span2");
+		assert_eq!(spans.lock().len(), 0);
+
+		shutdown_global();
+
+		let reported = spans.lock().drain(..).collect::<Vec<_>>();
+		assert_eq!(reported.len(), 2);
+		assert_eq!(reported[0].name, "test_span1");
+		assert_eq!(reported[1].name, "test_span2");
+		assert!(reported.iter().all(|sd| sd.values.bool_values.get("unfinished") == Some(&true)));
+
+		drop(span2);
+		drop(_guard1);
+		drop(span1);
+		assert_eq!(spans.lock().len(), 0);
+	}
+
 	#[test]
 	fn test_span_parent_id() {
 		let (sub, spans, _events) = setup_subscriber();
@@ -564,6 +1863,608 @@ mod tests {
 		assert_eq!(te1.values.string_values.remove(&"message".to_owned()).unwrap(), "test_event".to_owned());
 	}
 
+	#[test]
+	fn test_on_chain_wasm_span_is_reported() {
+		let (sub, spans, _events) = setup_subscriber();
+		let _sub_guard = tracing::subscriber::set_default(sub);
+		let mut proxy = sp_tracing::proxy::TracingProxy::new();
+
+		let id = proxy.enter_span("other_target", "disabled_span", false);
+		proxy.exit_span(id);
+		assert_eq!(spans.lock().len(), 0);
+
+		let id = proxy.enter_span("other_target", "on_chain_span", true);
+		proxy.exit_span(id);
+		let sd = spans.lock().remove(0);
+		assert_eq!(sd.name, "on_chain_span");
+		assert_eq!(sd.target, "other_target");
+		assert_eq!(sd.values.bool_values.get("wasm"), Some(&true));
+	}
+
+	#[test]
+	fn test_trace_context_is_recorded_on_nested_spans() {
+		let (sub, spans, events) = setup_subscriber();
+		let _sub_guard = tracing::subscriber::set_default(sub);
+		let mut proxy = sp_tracing::proxy::TracingProxy::new();
+
+		let context = proxy.enter_trace_context(&[0xab; 16]);
+		let id = proxy.enter_span("test_target", "wasm_span", false);
+		tracing::info!(target: "test_target", "test_event");
+		proxy.exit_span(id);
+		proxy.exit_span(context);
+		tracing::info_span!(target: "test_target", "untraced_span").in_scope(|| {});
+
+		let trace_id = "ab".repeat(16);
+		let spans = spans.lock();
+		assert_eq!(spans[0].name, "wasm_span");
+		assert_eq!(spans[0].values.string_values.get(TRACE_ID_KEY), Some(&trace_id));
+		assert_eq!(spans[1].name, "trace_context");
+		assert_eq!(spans[2].values.string_values.get(TRACE_ID_KEY), None);
+		assert_eq!(events.lock()[0].values.string_values.get(TRACE_ID_KEY), Some(&trace_id));
+	}
+
+	#[test]
+	fn test_task_span_links_threads() {
+		let (sub, spans, _events) = setup_subscriber();
+		let dispatch = tracing::Dispatch::new(sub);
+		let _sub_guard = tracing::dispatcher::set_default(&dispatch);
+
+		let span1 = tracing::info_span!(target: "test_target", "test_span1");
+		let _guard1 = span1.enter();
+		let task_span = sp_tracing::proxy::task_span(&tracing::Span::current(), "task");
+		std::thread::spawn(move || tracing::dispatcher::with_default(&dispatch, || {
+			task_span.in_scope(|| tracing::info_span!(target: "test_target", "test_span2").in_scope(|| ()));
+			let orphan = sp_tracing::proxy::task_span(&tracing::Span::current(), "orphan");
+			orphan.in_scope(|| ());
+		})).join().unwrap();
+		drop(_guard1);
+		drop(span1);
+
+		let spans = spans.lock();
+		assert_eq!(spans.len(), 3);
+		let span = |name| spans.iter().find(|s| s.name == name).unwrap();
+		assert_eq!(span("task").target, "test_target");
+		assert_eq!(span("task").parent_id, Some(span("test_span1").id.clone()));
+		assert_eq!(span("test_span2").parent_id, Some(span("task").id.clone()));
+	}
+
+	#[test]
+	fn test_spawned_instances_nest_their_spans() {
+		let (sub, spans, _events) = setup_subscriber();
+		let dispatch = tracing::Dispatch::new(sub);
+		let _sub_guard = tracing::dispatcher::set_default(&dispatch);
+
+		let mut proxy = sp_tracing::proxy::TracingProxy::new();
+		let call = proxy.enter_span("test_target", "call", false);
+		let spawned = proxy.spawned("task");
+		std::thread::spawn(move || tracing::dispatcher::with_default(&dispatch, || {
+			let mut spawned = spawned;
+			let id = spawned.enter_span("test_target", "parallel_work", false);
+			spawned.exit_span(id);
+		})).join().unwrap();
+		proxy.exit_span(call);
+
+		let spans = spans.lock();
+		assert_eq!(spans.len(), 3);
+		let span = |name| spans.iter().find(|s| s.name == name).unwrap();
+		assert_eq!(span("task").target, "test_target");
+		assert_eq!(span("task").parent_id, Some(span("call").id.clone()));
+		assert_eq!(span("parallel_work").parent_id, Some(span("task").id.clone()));
+	}
+
+	#[test]
+	fn test_audit_event_includes_span_values() {
+		let (sub, _spans, events) = setup_subscriber();
+		let _sub_guard = tracing::subscriber::set_default(sub);
+
+		let span1 = tracing::info_span!(target: "sudo", "sudo_call", call = "set_code");
+		let _guard1 = span1.enter();
+		tracing::event!(target: "sudo", tracing::Level::WARN, result = "Ok(())", "test_event");
+		tracing::event!(target: "other_target", tracing::Level::WARN, "test_event");
+
+		let te1 = events.lock().remove(0);
+		assert_eq!(te1.values.string_values.get("call").unwrap(), "set_code");
+		assert_eq!(te1.values.string_values.get("result").unwrap(), "Ok(())");
+		assert!(events.lock().is_empty());
+	}
+
+	#[test]
+	fn test_counters() {
+		let (sub, _spans, events) = setup_subscriber();
+		let counters = sub.counters();
+		let _sub_guard = tracing::subscriber::set_default(sub);
+
+		sp_tracing::counter!("transfers", 1u32, 1);
+		sp_tracing::counter!("transfers", 1u32, 2);
+		sp_tracing::counter!("transfers", 2u32, 1);
+
+		let mut values = Vec::new();
+		counters.for_each(|name, label, value| values.push((name.to_owned(), label.to_owned(), value)));
+		values.sort();
+		assert_eq!(values, vec![
+			("transfers".to_owned(), "1".to_owned(), 3),
+			("transfers".to_owned(), "2".to_owned(), 1),
+		]);
+		assert!(events.lock().is_empty());
+	}
+
+	#[test]
+	fn test_block_summaries() {
+		let (sub, spans, _events) = setup_subscriber();
+		let summaries = Arc::new(Mutex::new(Vec::new()));
+		let sink = summaries.clone();
+		let sub = sub.with_block_summaries(1, move |summary| sink.lock().push(summary));
+		let _sub_guard = tracing::subscriber::set_default(sub);
+
+		// Disabled target, enabled by name.
+		tracing::debug_span!(target: "other_target", "import_block", block_number = 7u64, block_hash = "0x07")
+			.in_scope(|| {
+				tracing::info_span!(target: "test_target", "fast").in_scope(|| ());
+				tracing::info_span!(target: "test_target", "slow").in_scope(|| {
+					std::thread::sleep(Duration::from_millis(1));
+					sp_tracing::counter!("reads", 1u32, 2);
+					tracing::trace!(target: STORAGE_ACCESS_TARGET, method = "Get", write = false, len = 4u64);
+				});
+				tracing::info_span!(target: "other_target", "ignored").in_scope(|| ());
+				tracing::trace!(target: STORAGE_ACCESS_TARGET, method = "Put", write = true, len = 8u64);
+				tracing::trace!(target: STORAGE_ACCESS_TARGET, method = "Append", write = true, len = 8u64);
+				sp_tracing::counter!("reads", 1u32, 1);
+			});
+		sp_tracing::counter!("reads", 1u32, 1);
+
+		let summaries = summaries.lock();
+		assert_eq!(summaries.len(), 1);
+		assert_eq!(summaries[0].block_hash, "0x07");
+		assert_eq!(summaries[0].block_number, 7);
+		assert_eq!(summaries[0].spans.len(), 1);
+		assert_eq!(summaries[0].spans[0].name, "slow");
+		assert_eq!(summaries[0].spans[0].count, 1);
+		assert_eq!(
+			summaries[0].counters,
+			vec![
+				CounterSummary { name: "reads".to_owned(), label: "1".to_owned(), value: 3 },
+				CounterSummary { name: "storage_accesses".to_owned(), label: "Read".to_owned(), value: 1 },
+				CounterSummary { name: "storage_accesses".to_owned(), label: "Write".to_owned(), value: 2 },
+			],
+		);
+		assert_eq!(spans.lock().len(), 3);
+	}
+
+	#[test]
+	fn test_telemetry_levels() {
+		let (sub, _spans, _events) = setup_subscriber();
+		assert!(sub.telemetry_enabled(&Level::TRACE));
+		sub.set_targets("test_target,telemetry=info");
+		assert!(sub.telemetry_enabled(&Level::INFO));
+		assert!(!sub.telemetry_enabled(&Level::DEBUG));
+	}
+
+	#[test]
+	fn test_import_deadlines() {
+		let (sub, _spans, events) = setup_subscriber();
+		let deadlines = ImportDeadlines::new(vec![Duration::from_millis(1), Duration::from_secs(60)]);
+		let dispatch = tracing::Dispatch::new(sub.with_import_deadlines(deadlines.clone()));
+		let sub: &ProfilingSubscriber = dispatch.downcast_ref().unwrap();
+
+		tracing::dispatcher::with_default(&dispatch, || {
+			tracing::debug_span!(target: "other_target", "import_block", block_number = 7u64).in_scope(|| {
+				tracing::info_span!(target: "test_target", "verify").in_scope(|| ());
+				tracing::info_span!(target: "test_target", "execute").in_scope(|| {
+					std::thread::sleep(Duration::from_millis(2));
+					sub.check_import_deadlines();
+				});
+			});
+			sub.check_import_deadlines();
+		});
+
+		let events = events.lock();
+		assert_eq!(events.len(), 2);
+		assert!(events.iter().all(|event| event.target == IMPORT_DEADLINE_TARGET && event.level == Level::WARN));
+		let running = &events[0].values;
+		assert_eq!(running.u64_values.get("block_number"), Some(&7));
+		assert_eq!(running.bool_values.get("finished"), Some(&false));
+		assert!(running.string_values["open"].starts_with("test_target::execute ("));
+		assert!(running.string_values["spans"].contains("\"name\":\"verify\""));
+		let finished = &events[1].values;
+		assert_eq!(finished.bool_values.get("finished"), Some(&true));
+		assert!(finished.string_values["spans"].contains("\"name\":\"execute\""));
+		assert_eq!(deadlines.exceeded(), vec![(Duration::from_millis(1), 1), (Duration::from_secs(60), 0)]);
+	}
+
+	#[test]
+	fn test_block_limits() {
+		let (sub, spans, events) = setup_subscriber();
+		let limits = TraceLimits { max_spans: 1, max_events: 1, max_field_bytes: 1024 };
+		let _sub_guard = tracing::subscriber::set_default(sub.with_limits(limits));
+
+		tracing::info_span!(target: "test_target", "import_block", block_number = 7u64).in_scope(|| {
+			tracing::info_span!(target: "test_target", "first").in_scope(|| ());
+			tracing::info_span!(target: "test_target", "second").in_scope(|| ());
+			tracing::info!(target: "test_target", "first");
+			tracing::info!(target: "test_target", "second");
+		});
+		// Not under a block.
+		tracing::info_span!(target: "test_target", "third").in_scope(|| ());
+
+		let names: Vec<_> = spans.lock().iter().map(|s| s.name.clone()).collect();
+		assert_eq!(names, vec!["first", "import_block", "third"]);
+		let events = events.lock();
+		assert_eq!(events.len(), 2);
+		let dropped = &events[1];
+		assert_eq!(dropped.name, DROPPED_RECORDS_EVENT);
+		assert_eq!(dropped.parent_id, Some(spans.lock()[1].id.clone()));
+		assert_eq!(dropped.values.string_values.get("message").unwrap(), "2 records dropped");
+		assert_eq!(dropped.values.u64_values.get("dropped_spans"), Some(&1));
+		assert_eq!(dropped.values.u64_values.get("dropped_events"), Some(&1));
+	}
+
+	#[test]
+	fn test_assertion_events_are_enabled() {
+		let (sub, _spans, events) = setup_subscriber();
+		let _sub_guard = tracing::subscriber::set_default(sub);
+
+		tracing::error!(target: ASSERTION_TARGET, condition = "total >= reserved", "assertion failed");
+
+		let event = events.lock().remove(0);
+		assert_eq!(event.target, ASSERTION_TARGET);
+		assert_eq!(event.values.string_values.get("condition").unwrap(), "total >= reserved");
+	}
+
+	#[test]
+	fn test_storage_counters() {
+		let (sub, spans, events) = setup_subscriber();
+		let _sub_guard = tracing::subscriber::set_default(sub.with_storage_counters());
+
+		tracing::info_span!(target: "test_target", "call").in_scope(|| {
+			// Disabled target, enabled to be tallied.
+			tracing::trace!(target: STORAGE_ACCESS_TARGET, method = "Get", write = false, len = 4u64);
+			tracing::info_span!(target: "test_target", "nested").in_scope(|| {
+				tracing::trace!(target: STORAGE_ACCESS_TARGET, method = "Put", write = true, len = 8u64);
+			});
+		});
+
+		assert!(events.lock().is_empty());
+		let spans = spans.lock();
+		let counters = |i: usize| {
+			let values = &spans[i].values.u64_values;
+			[STORAGE_READS_KEY, STORAGE_WRITES_KEY, STORAGE_READ_BYTES_KEY, STORAGE_WRITTEN_BYTES_KEY]
+				.iter()
+				.map(|key| values.get(*key).copied().unwrap_or_default())
+				.collect::<Vec<_>>()
+		};
+		assert_eq!(spans[0].name, "nested");
+		assert_eq!(counters(0), vec![0, 1, 0, 8]);
+		assert_eq!(counters(1), vec![1, 1, 4, 8]);
+	}
+
+	#[test]
+	fn test_pov_breakdowns() {
+		let (sub, spans, events) = setup_subscriber();
+		let pov_breakdowns = PovBreakdowns::new(1);
+		let _sub_guard = tracing::subscriber::set_default(sub.with_pov_breakdowns(pov_breakdowns.clone()));
+
+		for number in 1..=2u64 {
+			let span = tracing::debug_span!(
+				target: "other_target",
+				"build_block",
+				block_number = number,
+				parent_hash = "0x01",
+				proof_size = tracing::field::Empty,
+			);
+			span.in_scope(|| {
+				tracing::trace!(target: PROOF_SIZE_TARGET, bytes = 10u64);
+				tracing::info_span!(target: "test_target", "transfer").in_scope(|| {
+					tracing::trace!(target: PROOF_SIZE_TARGET, bytes = 20u64);
+					tracing::trace!(target: PROOF_SIZE_TARGET, bytes = 30u64);
+				});
+			});
+			span.record("proof_size", &40u64);
+		}
+
+		// The forced build spans and proof events are not handled.
+		assert!(events.lock().is_empty());
+		assert_eq!(spans.lock().iter().map(|s| s.name.as_str()).collect::<Vec<_>>(), vec!["transfer"; 2]);
+		assert_eq!(pov_breakdowns.breakdowns(), vec![PovBreakdown {
+			block_number: 2,
+			parent_hash: "0x01".into(),
+			block_hash: None,
+			proof_size: Some(40),
+			recorded_bytes: 60,
+			contributions: vec![
+				PovContribution { target: "test_target".into(), name: "transfer".into(), nodes: 2, bytes: 50 },
+				PovContribution { target: "other_target".into(), name: "build_block".into(), nodes: 1, bytes: 10 },
+			],
+		}]);
+	}
+
+	#[test]
+	fn test_weight_drift_of_dispatches() {
+		let (sub, spans, _events) = setup_subscriber();
+		let weight_drift = WeightDrift::new(1, f64::MAX);
+		let _sub_guard = tracing::subscriber::set_default(sub.with_weight_drift(weight_drift.clone()));
+
+		// Disabled target, enabled to measure the call.
+		tracing::info_span!(target: DISPATCH_TARGET, DISPATCH_TARGET, weight = 1_000u64).in_scope(|| {
+			tracing::info_span!(target: "test_target", "call").in_scope(|| ());
+		});
+		// Not under a dispatch.
+		tracing::info_span!(target: "test_target", "other").in_scope(|| ());
+
+		let names: Vec<_> = spans.lock().iter().map(|s| s.name.clone()).collect();
+		assert_eq!(names, vec!["call", "other"]);
+		let calls = weight_drift.calls();
+		assert_eq!(calls.len(), 1);
+		assert_eq!((calls[0].target.as_str(), calls[0].name.as_str()), ("test_target", "call"));
+		assert_eq!(calls[0].samples, 1);
+		assert!(calls[0].score > 0.0);
+	}
+
+	#[test]
+	fn test_weight_drift_of_wasm_dispatches() {
+		let (sub, spans, _events) = setup_subscriber();
+		let weight_drift = WeightDrift::new(1, f64::MAX);
+		let _sub_guard = tracing::subscriber::set_default(sub.with_weight_drift(weight_drift.clone()));
+
+		let mut proxy = sp_tracing::proxy::TracingProxy::new();
+		let dispatch = proxy.enter_span(DISPATCH_TARGET, DISPATCH_TARGET, false);
+		proxy.record_weight(dispatch, 1_000);
+		let call = proxy.enter_span("test_target", "call", false);
+		proxy.exit_span(call);
+		proxy.exit_span(dispatch);
+
+		let names: Vec<_> = spans.lock().iter().map(|s| s.name.clone()).collect();
+		assert_eq!(names, vec!["call"]);
+		let calls = weight_drift.calls();
+		assert_eq!(calls.len(), 1);
+		assert_eq!((calls[0].target.as_str(), calls[0].name.as_str()), ("test_target", "call"));
+		assert_eq!(calls[0].samples, 1);
+	}
+
+	#[test]
+	fn test_dispatch_origins() {
+		let (sub, _spans, _events) = setup_subscriber();
+		let dispatch_origins = DispatchOrigins::new();
+		let _sub_guard = tracing::subscriber::set_default(sub.with_dispatch_origins(dispatch_origins.clone()));
+
+		tracing::info_span!(target: DISPATCH_TARGET, DISPATCH_TARGET, weight = 1_000u64, origin = "signed")
+			.in_scope(|| {
+				// A call dispatched by the call of the extrinsic.
+				tracing::info_span!(target: DISPATCH_TARGET, DISPATCH_TARGET, weight = 500u64, origin = "root")
+					.in_scope(|| ());
+			});
+		tracing::info_span!(target: DISPATCH_TARGET, DISPATCH_TARGET, weight = 10u64).in_scope(|| ());
+
+		let origins: Vec<_> = dispatch_origins.origins().into_iter()
+			.map(|(origin, stats)| (origin, stats.dispatches, stats.weight))
+			.collect();
+		assert_eq!(origins, vec![
+			("root".to_owned(), 1, 500),
+			("signed".to_owned(), 1, 1_000),
+			(UNKNOWN_ORIGIN.to_owned(), 1, 10),
+		]);
+	}
+
+	#[test]
+	fn test_finality_metrics() {
+		let (sub, spans, events) = setup_subscriber();
+		let clock = ManualClock::new(std::time::UNIX_EPOCH);
+		let finality_metrics = FinalityMetrics::new();
+		let sub = sub.with_clock(clock.clone()).with_finality_metrics(finality_metrics.clone());
+		let _sub_guard = tracing::subscriber::set_default(sub);
+
+		sp_tracing::finality_vote!("grandpa", "prevote", 1);
+		sp_tracing::finality_round!("grandpa", 0, 1);
+		clock.advance(Duration::from_millis(30));
+		sp_tracing::finality_round!("grandpa", 0, 2);
+		sp_tracing::finality_finalized!("grandpa", 12);
+		{
+			sp_tracing::enter_finality_proof_span!("grandpa", "verify");
+			clock.advance(Duration::from_millis(5));
+		}
+
+		let gadgets = finality_metrics.gadgets();
+		assert_eq!(gadgets.len(), 1);
+		let (name, grandpa) = &gadgets[0];
+		assert_eq!(name, "grandpa");
+		assert_eq!((grandpa.rounds, grandpa.last_round), (2, 2));
+		assert_eq!(grandpa.round_time, Duration::from_millis(30));
+		assert_eq!(grandpa.votes.get("prevote"), Some(&1));
+		assert_eq!(grandpa.finalized_number, 12);
+		assert_eq!(
+			grandpa.proofs.get("verify"),
+			Some(&ProofStats { proofs: 1, time: Duration::from_millis(5) }),
+		);
+		// The finality target is not enabled, so they are only accounted.
+		assert!(spans.lock().is_empty());
+		assert!(events.lock().is_empty());
+	}
+
+	#[test]
+	fn test_dispatch_errors_are_enriched() {
+		let (sub, _spans, events) = setup_subscriber();
+		let _sub_guard = tracing::subscriber::set_default(sub.with_dispatch_errors());
+
+		// Disabled target, enabled to report its failure.
+		tracing::info_span!(target: DISPATCH_TARGET, DISPATCH_TARGET, weight = 1_000u64).in_scope(|| {
+			tracing::info_span!(target: "test_target", "transfer").in_scope(|| ());
+			tracing::info!(
+				target: DISPATCH_TARGET,
+				module_index = 2u64,
+				error_index = 1u64,
+				error = "InsufficientBalance",
+				"Dispatch failed",
+			);
+		});
+
+		let events = events.lock();
+		assert_eq!(events.len(), 1);
+		let values = &events[0].values;
+		assert_eq!(values.string_values[PALLET_KEY], "test_target");
+		assert_eq!(values.string_values[CALL_KEY], "transfer");
+		assert_eq!(values.string_values[ERROR_KEY], "InsufficientBalance");
+		assert_eq!(values.u64_values[MODULE_INDEX_KEY], 2);
+	}
+
+	#[test]
+	fn test_dispatch_errors_are_opt_in() {
+		let (sub, spans, events) = setup_subscriber();
+		let _sub_guard = tracing::subscriber::set_default(sub);
+
+		tracing::info_span!(target: DISPATCH_TARGET, DISPATCH_TARGET, weight = 1_000u64).in_scope(|| {
+			tracing::info!(target: DISPATCH_TARGET, error = "Bad origin", "Dispatch failed");
+		});
+
+		assert!(spans.lock().is_empty());
+		assert!(events.lock().is_empty());
+	}
+
+	#[test]
+	fn test_trace_id_of_current_span() {
+		let (sub, _spans, _events) = setup_subscriber();
+		let _sub_guard = tracing::subscriber::set_default(sub);
+		assert_eq!(global_trace_id(), None);
+
+		sp_tracing::proxy::trace_context_span(&[0xab; 16]).in_scope(|| {
+			tracing::info_span!(target: "test_target", "offchain_worker").in_scope(|| {
+				assert_eq!(global_trace_id(), Some("ab".repeat(16)));
+			});
+		});
+		assert_eq!(global_trace_id(), None);
+	}
+
+	/// The encoded metadata of a runtime with the event `Issued(u32, u64)` of the given module.
+	fn issued_event_metadata(module: &str) -> Vec<u8> {
+		use codec::Encode;
+		use frame_metadata::{
+			DecodeDifferent, EventMetadata, ExtrinsicMetadata, ModuleMetadata, RuntimeMetadataPrefixed,
+			RuntimeMetadataV11,
+		};
+
+		let metadata: RuntimeMetadataPrefixed = RuntimeMetadataV11 {
+			modules: DecodeDifferent::Decoded(vec![ModuleMetadata {
+				name: DecodeDifferent::Decoded(module.into()),
+				storage: None,
+				calls: None,
+				event: Some(DecodeDifferent::Decoded(vec![EventMetadata {
+					name: DecodeDifferent::Decoded("Issued".into()),
+					arguments: DecodeDifferent::Decoded(vec!["u32".into(), "u64".into()]),
+					documentation: DecodeDifferent::Decoded(Vec::new()),
+				}])),
+				constants: DecodeDifferent::Decoded(Vec::new()),
+				errors: DecodeDifferent::Decoded(Vec::new()),
+			}]),
+			extrinsic: ExtrinsicMetadata { version: 4, signed_extensions: Vec::new() },
+		}.into();
+		metadata.encode()
+	}
+
+	#[test]
+	fn test_runtime_events_are_summarized_in_dispatch_span() {
+		use codec::Encode;
+
+		let scale_decoder = ScaleDecoder::new(&issued_event_metadata("Assets"), &Default::default()).unwrap();
+		let spans = Arc::new(Mutex::new(Vec::new()));
+		let events = Arc::new(Mutex::new(Vec::new()));
+		let handler = TestTraceHandler { spans: spans.clone(), events: events.clone() };
+		let sub = ProfilingSubscriber::new_with_handler(Box::new(handler), "test_target,dispatch")
+			.with_scale_decoder(scale_decoder);
+		let _sub_guard = tracing::subscriber::set_default(sub);
+
+		// The runtime events are not enabled, only summarized.
+		tracing::info_span!(target: DISPATCH_TARGET, DISPATCH_TARGET, weight = 1_000u64).in_scope(|| {
+			tracing::info_span!(target: "test_target", "issue").in_scope(|| {
+				tracing::trace!(target: RUNTIME_EVENT_TARGET, event = ?(0u8, 0u8, 7u32, 100u64).encode());
+				tracing::trace!(target: RUNTIME_EVENT_TARGET, event = ?vec![0u8, 0]);
+			});
+		});
+
+		assert!(events.lock().is_empty());
+		let spans = spans.lock();
+		let dispatch = spans.iter().find(|span| span.name == DISPATCH_TARGET).unwrap();
+		assert_eq!(dispatch.values.string_values[EVENTS_KEY], "Assets.Issued(7, 100); Assets.Issued(..)");
+	}
+
+	#[test]
+	fn test_scale_decoder_is_replaced_on_runtime_upgrade() {
+		use codec::Encode;
+
+		let scale_decoder = ScaleDecoder::new(&issued_event_metadata("Assets"), &Default::default()).unwrap();
+		let spans = Arc::new(Mutex::new(Vec::new()));
+		let events = Arc::new(Mutex::new(Vec::new()));
+		let handler = TestTraceHandler { spans: spans.clone(), events };
+		let sub = ProfilingSubscriber::new_with_handler(Box::new(handler), "dispatch")
+			.with_scale_decoder(scale_decoder);
+		let _sub_guard = tracing::subscriber::set_default(sub);
+		let dispatch = || tracing::info_span!(target: DISPATCH_TARGET, DISPATCH_TARGET).in_scope(|| {
+			tracing::trace!(target: RUNTIME_EVENT_TARGET, event = ?(0u8, 0u8, 7u32, 100u64).encode());
+		});
+
+		dispatch();
+		tracing::dispatcher::get_default(|dispatch| {
+			let scale_decoder = ScaleDecoder::new(&issued_event_metadata("Tokens"), &Default::default());
+			dispatch.downcast_ref::<ProfilingSubscriber>().unwrap().set_scale_decoder(scale_decoder.unwrap());
+		});
+		dispatch();
+
+		let summaries = spans.lock().iter()
+			.map(|span| span.values.string_values[EVENTS_KEY].clone())
+			.collect::<Vec<_>>();
+		assert_eq!(summaries, vec!["Assets.Issued(7, 100)", "Tokens.Issued(7, 100)"]);
+	}
+
+	#[test]
+	fn test_collected_summaries_are_events() {
+		let spans = Arc::new(Mutex::new(Vec::new()));
+		let events = Arc::new(Mutex::new(Vec::new()));
+		let handler = TestTraceHandler { spans, events: events.clone() };
+		let sub = ProfilingSubscriber::new_with_handler(Box::new(handler), "trace_collection");
+		let _sub_guard = tracing::subscriber::set_default(sub);
+
+		record_collected_summary("QmReporter", &BlockSummary {
+			block_hash: "0x07".into(),
+			block_number: 7,
+			duration_ns: 1_000,
+			spans: vec![SpanSummary { target: "test_target".into(), name: "slow".into(), count: 1, duration_ns: 900 }],
+			counters: Vec::new(),
+		});
+
+		let event = events.lock().remove(0);
+		assert_eq!(event.target, "trace_collection");
+		assert_eq!(event.values.string_values.get("peer").unwrap(), "QmReporter");
+		assert_eq!(event.values.string_values.get("block_hash").unwrap(), "0x07");
+		assert_eq!(event.values.u64_values.get("block_number"), Some(&7));
+		assert_eq!(event.values.string_values.get("counters").unwrap(), "[]");
+	}
+
+	#[test]
+	fn test_sentry_reports_error_events() {
+		let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+		let dsn = format!("http://key@{}/1", listener.local_addr().unwrap());
+		let server = std::thread::spawn(move || http::tests::accept(&listener, "200 OK"));
+		let (mut sub, _spans, events) = setup_subscriber();
+		sub.redaction = Redaction::new("sender=drop,origin").unwrap();
+		let sub = sub.with_sentry(SentryReporter::new(SentryConfig::new(&dsn).unwrap()));
+		let _sub_guard = tracing::subscriber::set_default(sub);
+
+		tracing::info_span!(target: "test_target", "import_block", block_number = 7u64, sender = "alice", origin = "bob")
+			.in_scope(|| {
+				// Disabled target, enabled for Sentry only.
+				tracing::event!(target: "other_target", tracing::Level::ERROR, sender = "alice", "import failed");
+			});
+
+		let (_, body) = server.join().unwrap();
+		let report: serde_json::Value = serde_json::from_str(&body).unwrap();
+		assert_eq!(report["logger"], "other_target");
+		assert_eq!(report["message"], "import failed");
+		assert_eq!(report["breadcrumbs"]["values"][0]["message"], "import_block");
+		assert_eq!(report["breadcrumbs"]["values"][0]["data"]["block_number"], 7);
+		assert_eq!(report["breadcrumbs"]["values"][0]["data"]["sender"], serde_json::Value::Null);
+		let origin = report["breadcrumbs"]["values"][0]["data"]["origin"].as_str().unwrap();
+		assert!(origin.starts_with("0x") && origin != "bob");
+		assert_eq!(report["extra"]["sender"], serde_json::Value::Null);
+		assert!(events.lock().is_empty());
+	}
+
 	#[test]
 	fn test_event_parent_id() {
 		let (sub, spans, events) = setup_subscriber();
@@ -0,0 +1,146 @@
+// Copyright 2019-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Caps on the spans and events traced under an `import_block` span.
+//!
+//! Past the caps of the block, its spans and events are dropped instead of being handed to the
+//! trace handler, and an event counting them is handed over right before the block span.
+
+use std::time::SystemTime;
+
+use parking_lot::Mutex;
+use rustc_hash::FxHashMap;
+use tracing::Level;
+
+use crate::{SpanDatum, TraceEvent, Values};
+
+/// Name of the event counting the spans and events of a block that were dropped.
+pub const DROPPED_RECORDS_EVENT: &str = "dropped_records";
+
+/// The most spans, events and bytes of values traced under a block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceLimits {
+	/// Maximum number of spans of a block.
+	pub max_spans: usize,
+	/// Maximum number of events of a block.
+	pub max_events: usize,
+	/// Maximum number of bytes of the values of the spans and events of a block, their keys
+	/// included.
+	pub max_field_bytes: usize,
+}
+
+impl Default for TraceLimits {
+	fn default() -> Self {
+		TraceLimits {
+			max_spans: 50_000,
+			max_events: 50_000,
+			max_field_bytes: 32 * 1024 * 1024,
+		}
+	}
+}
+
+#[derive(Default)]
+struct Usage {
+	spans: usize,
+	events: usize,
+	field_bytes: usize,
+	dropped_spans: u64,
+	dropped_events: u64,
+}
+
+/// Accounts the spans and events of the blocks being imported.
+pub(crate) struct BlockLimits {
+	limits: TraceLimits,
+	usage: Mutex<FxHashMap<u64, Usage>>,
+}
+
+impl BlockLimits {
+	pub fn new(limits: TraceLimits) -> Self {
+		BlockLimits { limits, usage: Default::default() }
+	}
+
+	/// Whether a span with `values` fits in the limits of the block span `block`, in which case
+	/// it is accounted.
+	pub fn admit_span(&self, block: u64, values: &Values) -> bool {
+		let mut usage = self.usage.lock();
+		let usage = usage.entry(block).or_default();
+		let field_bytes = usage.field_bytes.saturating_add(field_bytes(values));
+		if usage.spans < self.limits.max_spans && field_bytes <= self.limits.max_field_bytes {
+			usage.spans += 1;
+			usage.field_bytes = field_bytes;
+			true
+		} else {
+			usage.dropped_spans += 1;
+			false
+		}
+	}
+
+	/// Whether an event with `values` fits in the limits of the block span `block`, in which
+	/// case it is accounted.
+	pub fn admit_event(&self, block: u64, values: &Values) -> bool {
+		let mut usage = self.usage.lock();
+		let usage = usage.entry(block).or_default();
+		let field_bytes = usage.field_bytes.saturating_add(field_bytes(values));
+		if usage.events < self.limits.max_events && field_bytes <= self.limits.max_field_bytes {
+			usage.events += 1;
+			usage.field_bytes = field_bytes;
+			true
+		} else {
+			usage.dropped_events += 1;
+			false
+		}
+	}
+
+	/// Stop accounting the block of the closed `block_span`, returning the event counting its
+	/// dropped spans and events, if any.
+	pub fn finish(&self, block_span: &SpanDatum) -> Option<TraceEvent> {
+		let usage = self.usage.lock().remove(&block_span.id.into_u64())?;
+		let dropped = usage.dropped_spans + usage.dropped_events;
+		if dropped == 0 {
+			return None;
+		}
+		log::warn!(
+			target: "tracing",
+			"Dropped {} spans and {} events of the trace of {} over its limits",
+			usage.dropped_spans,
+			usage.dropped_events,
+			block_span.name,
+		);
+		let mut values = Values::new();
+		values.string_values.insert("message".to_owned(), format!("{} records dropped", dropped));
+		values.u64_values.insert("dropped_spans".to_owned(), usage.dropped_spans);
+		values.u64_values.insert("dropped_events".to_owned(), usage.dropped_events);
+		Some(TraceEvent {
+			name: DROPPED_RECORDS_EVENT,
+			target: block_span.target.clone(),
+			level: Level::WARN,
+			values,
+			parent_id: Some(block_span.id.clone()),
+			time: SystemTime::now(),
+		})
+	}
+}
+
+/// The bytes held by the keys and values of `values`.
+fn field_bytes(values: &Values) -> usize {
+	fn bytes<T>(values: &FxHashMap<String, T>, value_bytes: impl Fn(&T) -> usize) -> usize {
+		values.iter().map(|(key, value)| key.len() + value_bytes(value)).sum()
+	}
+	bytes(&values.bool_values, |_| 1)
+		+ bytes(&values.i64_values, |_| 8)
+		+ bytes(&values.u64_values, |_| 8)
+		+ bytes(&values.string_values, String::len)
+}
@@ -0,0 +1,356 @@
+// Copyright 2019-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Export of the spans and events to Grafana Loki, through its push API.
+//!
+//! The spans and events are formatted as log lines and sent by a background thread, in batches of
+//! at most [`MAX_BATCH`] lines or every [`BATCH_INTERVAL`]. The lines are labelled with their
+//! target and the configured labels, e.g. the chain and the node name. A batch Loki doesn't
+//! accept is retried with an exponential backoff, then dropped, as are the lines exceeding the
+//! [`MAX_QUEUED`] queued in the meantime.
+
+use std::{
+	sync::{Arc, atomic::{AtomicU64, Ordering}, mpsc},
+	thread,
+	time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use rustc_hash::FxHashMap;
+use sc_telemetry::ExporterHealth;
+use serde::Serialize;
+
+use crate::{Redaction, SpanDatum, TraceEvent, TraceHandler, http::Endpoint};
+
+/// Maximum number of lines sent at once.
+pub const MAX_BATCH: usize = 1000;
+
+/// Maximum time a line waits for its batch to fill.
+pub const BATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Maximum number of lines waiting to be sent, further lines are dropped.
+pub const MAX_QUEUED: usize = 10_000;
+
+/// Number of times a batch is retried before being dropped.
+const MAX_RETRIES: u32 = 5;
+
+/// Time before the first retry of a batch, doubled after every retry.
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+
+/// Maximum time `flush` waits for the queued lines to be sent.
+const FLUSH_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Path of the push API, used if the endpoint has none.
+const PUSH_PATH: &str = "/loki/api/v1/push";
+
+/// Label of the target of the lines.
+const TARGET_LABEL: &str = "target";
+
+#[derive(Debug, Default)]
+struct Counts {
+	sent: AtomicU64,
+	dropped: AtomicU64,
+	retries: AtomicU64,
+}
+
+/// The number of lines sent to Loki and dropped, shared by the handler and its sender thread.
+#[derive(Debug, Clone, Default)]
+pub struct LokiMetrics {
+	inner: Arc<Counts>,
+}
+
+impl LokiMetrics {
+	/// Number of lines accepted by Loki.
+	pub fn sent(&self) -> u64 {
+		self.inner.sent.load(Ordering::Relaxed)
+	}
+
+	/// Number of lines dropped, either because the queue was full or because Loki didn't accept
+	/// their batch.
+	pub fn dropped(&self) -> u64 {
+		self.inner.dropped.load(Ordering::Relaxed)
+	}
+
+	/// Number of retried batches.
+	pub fn retries(&self) -> u64 {
+		self.inner.retries.load(Ordering::Relaxed)
+	}
+}
+
+/// Where and how to push the lines.
+#[derive(Debug, Clone)]
+pub struct LokiConfig {
+	endpoint: Endpoint,
+	/// Labels of all the lines, e.g. `chain` and `node`.
+	pub labels: Vec<(String, String)>,
+	/// Metrics of the handler.
+	pub metrics: LokiMetrics,
+}
+
+impl LokiConfig {
+	/// Parses an endpoint like `http://localhost:3100` or `https://loki.example.com`, the path
+	/// defaulting to the push API.
+	pub fn new(endpoint: &str) -> Result<Self, String> {
+		let mut parsed = Endpoint::parse(endpoint).ok_or_else(|| format!(
+			"Invalid Loki endpoint `{}`, expected `http[s]://<host>[:<port>][<path>]`",
+			endpoint,
+		))?;
+		if parsed.path == "/" {
+			parsed.path = PUSH_PATH.to_owned();
+		}
+		Ok(LokiConfig { endpoint: parsed, labels: Vec::new(), metrics: Default::default() })
+	}
+}
+
+struct Line {
+	target: String,
+	time: SystemTime,
+	line: String,
+}
+
+enum Message {
+	Line(Line),
+	Flush(mpsc::SyncSender<()>),
+}
+
+/// TraceHandler pushing the spans and events to Loki
+pub struct LokiTraceHandler {
+	sender: mpsc::SyncSender<Message>,
+	metrics: LokiMetrics,
+	health: ExporterHealth,
+	redaction: Redaction,
+}
+
+impl LokiTraceHandler {
+	/// Create a handler pushing to the configured endpoint, the values of the spans and events
+	/// being redacted with `redaction` first.
+	pub fn new(config: LokiConfig, redaction: Redaction) -> Self {
+		let (sender, receiver) = mpsc::sync_channel(MAX_QUEUED);
+		let metrics = config.metrics.clone();
+		let health = ExporterHealth::new("loki");
+		let sender_health = health.clone();
+		let spawned = thread::Builder::new()
+			.name("loki-sender".into())
+			.spawn(move || Sender::new(config, sender_health).run(receiver));
+		if let Err(e) = spawned {
+			log::warn!(target: "tracing", "Unable to start the Loki sender: {}", e);
+		}
+		LokiTraceHandler { sender, metrics, health, redaction }
+	}
+
+	fn send(&self, target: String, time: SystemTime, line: String) {
+		if self.sender.try_send(Message::Line(Line { target, time, line })).is_ok() {
+			self.health.record_queued(1);
+		} else {
+			self.metrics.inner.dropped.fetch_add(1, Ordering::Relaxed);
+			self.health.record_dropped(1);
+		}
+	}
+}
+
+impl TraceHandler for LokiTraceHandler {
+	fn handle_span(&self, span_datum: SpanDatum) {
+		let start = span_datum.start_wall_time;
+		let line = format!(
+			"level={} span={} time={} id={} parent_id={:?} {}",
+			span_datum.level,
+			span_datum.name,
+			span_datum.overall_time.as_nanos(),
+			span_datum.id.into_u64(),
+			span_datum.parent_id.map(|id| id.into_u64()),
+			self.redaction.redact(&span_datum.values),
+		);
+		self.send(span_datum.target, start, line);
+	}
+
+	fn handle_event(&self, event: TraceEvent) {
+		let line = format!(
+			"level={} parent_id={:?} {}",
+			event.level,
+			event.parent_id.map(|id| id.into_u64()),
+			self.redaction.redact(&event.values),
+		);
+		self.send(event.target, event.time, line);
+	}
+
+	fn flush(&self) {
+		let (done, flushed) = mpsc::sync_channel(1);
+		if self.sender.send(Message::Flush(done)).is_ok() {
+			let _ = flushed.recv_timeout(FLUSH_TIMEOUT);
+		}
+	}
+}
+
+#[derive(Serialize)]
+struct Push<'a> {
+	streams: Vec<Stream<'a>>,
+}
+
+#[derive(Serialize)]
+struct Stream<'a> {
+	stream: FxHashMap<&'a str, &'a str>,
+	values: Vec<(String, &'a str)>,
+}
+
+/// Sends the batches of lines, from a background thread.
+struct Sender {
+	config: LokiConfig,
+	health: ExporterHealth,
+	batch: Vec<Line>,
+}
+
+impl Sender {
+	fn new(config: LokiConfig, health: ExporterHealth) -> Self {
+		Sender { config, health, batch: Vec::new() }
+	}
+
+	fn run(mut self, receiver: mpsc::Receiver<Message>) {
+		let mut deadline = None;
+		loop {
+			let message = match deadline {
+				Some(deadline) => receiver.recv_timeout(deadline - Instant::now().min(deadline)),
+				None => receiver.recv().map_err(|_| mpsc::RecvTimeoutError::Disconnected),
+			};
+			match message {
+				Ok(Message::Line(line)) => {
+					self.batch.push(line);
+					if self.batch.len() >= MAX_BATCH {
+						self.send();
+						deadline = None;
+					} else if deadline.is_none() {
+						deadline = Some(Instant::now() + BATCH_INTERVAL);
+					}
+				},
+				Ok(Message::Flush(done)) => {
+					self.send();
+					deadline = None;
+					let _ = done.send(());
+				},
+				Err(mpsc::RecvTimeoutError::Timeout) => {
+					self.send();
+					deadline = None;
+				},
+				Err(mpsc::RecvTimeoutError::Disconnected) => {
+					self.send();
+					return;
+				},
+			}
+		}
+	}
+
+	/// Send the batch, retrying with an exponential backoff.
+	fn send(&mut self) {
+		if self.batch.is_empty() {
+			return;
+		}
+		let body = self.encode();
+		let lines = self.batch.len() as u64;
+		self.health.record_dequeued(lines);
+		let mut backoff = INITIAL_BACKOFF;
+		for retry in 0..=MAX_RETRIES {
+			let start = Instant::now();
+			match self.config.endpoint.post(&[], &body) {
+				Ok(()) => {
+					self.config.metrics.inner.sent.fetch_add(lines, Ordering::Relaxed);
+					self.health.record_sent(lines, start.elapsed());
+					self.batch.clear();
+					return;
+				},
+				Err(e) if retry < MAX_RETRIES => {
+					log::debug!(target: "tracing", "Unable to push to Loki, retrying in {:?}: {}", backoff, e);
+					self.config.metrics.inner.retries.fetch_add(1, Ordering::Relaxed);
+					thread::sleep(backoff);
+					backoff *= 2;
+				},
+				Err(e) => log::warn!(target: "tracing", "Unable to push {} lines to Loki: {}", self.batch.len(), e),
+			}
+		}
+		self.config.metrics.inner.dropped.fetch_add(lines, Ordering::Relaxed);
+		self.health.record_dropped(lines);
+		self.batch.clear();
+	}
+
+	/// The body of the push request, with a stream per target.
+	fn encode(&self) -> Vec<u8> {
+		let mut streams = FxHashMap::<&str, Stream>::default();
+		for line in &self.batch {
+			let labels = &self.config.labels;
+			let stream = streams.entry(&line.target).or_insert_with(|| Stream {
+				stream: labels.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+					.chain(std::iter::once((TARGET_LABEL, line.target.as_str())))
+					.collect(),
+				values: Vec::new(),
+			});
+			let time = line.time.duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos();
+			stream.values.push((time.to_string(), &line.line));
+		}
+		serde_json::to_vec(&Push { streams: streams.into_iter().map(|(_, stream)| stream).collect() })
+			.unwrap_or_default()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::net::TcpListener;
+	use crate::http::tests::accept;
+
+	#[test]
+	fn defaults_to_the_push_api() {
+		assert_eq!(LokiConfig::new("http://localhost:3100").unwrap().endpoint.path, PUSH_PATH);
+		assert_eq!(LokiConfig::new("http://loki/custom/push").unwrap().endpoint.path, "/custom/push");
+		assert!(LokiConfig::new("https://loki").unwrap().endpoint.secure);
+		assert!(LokiConfig::new("loki:3100").is_err());
+	}
+
+	#[test]
+	fn pushes_and_retries_batches() {
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let endpoint = format!("http://{}", listener.local_addr().unwrap());
+		let mut config = LokiConfig::new(&endpoint).unwrap();
+		config.labels.push(("chain".into(), "dev".into()));
+		let metrics = config.metrics.clone();
+		let handler = LokiTraceHandler::new(config, Redaction::new("secret=drop").unwrap());
+
+		let server = thread::spawn(move || {
+			let (_, first) = accept(&listener, "500 Internal Server Error");
+			let (_, second) = accept(&listener, "204 No Content");
+			(first, second)
+		});
+		let mut values = crate::Values::new();
+		values.u64_values.insert("amount".into(), 42);
+		values.string_values.insert("secret".into(), "hunter2".into());
+		handler.handle_event(TraceEvent {
+			name: "event",
+			target: "test_target".into(),
+			level: tracing::Level::INFO,
+			values,
+			parent_id: None,
+			time: UNIX_EPOCH + Duration::from_secs(1),
+		});
+		handler.flush();
+
+		let (first, second) = server.join().unwrap();
+		assert_eq!(first, second);
+		let push: serde_json::Value = serde_json::from_str(&second).unwrap();
+		let stream = &push["streams"][0];
+		assert_eq!(stream["stream"]["chain"], "dev");
+		assert_eq!(stream["stream"]["target"], "test_target");
+		assert_eq!(stream["values"][0][0], "1000000000");
+		assert_eq!(stream["values"][0][1], "level=INFO parent_id=None amount=42");
+		assert_eq!((metrics.sent(), metrics.retries(), metrics.dropped()), (1, 1, 0));
+		let health = handler.health.status();
+		assert_eq!((health.queued, health.sent, health.dropped, health.sends), (0, 1, 0, 1));
+	}
+}
@@ -0,0 +1,173 @@
+// Copyright 2019-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Hands the reports of the panics over to a thread of their own.
+//!
+//! A panic may be raised while its thread holds the locks of the subscriber or of the telemetry,
+//! so the panic hook only collects the report, without waiting for any lock, and pushes it to a
+//! bounded lock-free queue. The reporter thread sends it to the telemetry and to Sentry, and
+//! flushes the traces and the telemetry before the process is aborted, see
+//! [`shutdown_after_panic`].
+
+use std::panic::PanicInfo;
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossbeam_queue::ArrayQueue;
+
+use crate::ProfilingSubscriber;
+use crate::sentry::Breadcrumb;
+
+/// Number of reports queued for the reporter thread, beyond which they are dropped.
+const MAX_QUEUED: usize = 16;
+/// How long [`shutdown_after_panic`] waits for the queued reports to be sent and the traces and
+/// the telemetry to be flushed, longer than Sentry is given to send the report of a panic.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
+const THREAD_NAME: &str = "panic-reporter";
+
+/// A panic, as collected by the panic hook.
+pub(crate) struct PanicReport {
+	/// The panic, as displayed by `PanicInfo`.
+	pub info: String,
+	/// The payload of the panic and its location.
+	pub message: String,
+	/// The name of the thread that panicked.
+	pub thread: Option<String>,
+	/// The spans the thread was in, outermost first.
+	pub spans: Vec<Breadcrumb>,
+}
+
+impl PanicReport {
+	pub fn new(info: &PanicInfo, spans: Vec<Breadcrumb>) -> Self {
+		let payload = info.payload().downcast_ref::<&str>().map(|s| s.to_string())
+			.or_else(|| info.payload().downcast_ref::<String>().cloned())
+			.unwrap_or_else(|| "Box<Any>".to_owned());
+		let message = match info.location() {
+			Some(location) => format!("{} at {}:{}", payload, location.file(), location.line()),
+			None => payload,
+		};
+		PanicReport {
+			info: info.to_string(),
+			message,
+			thread: thread::current().name().map(ToOwned::to_owned),
+			spans,
+		}
+	}
+}
+
+enum Item {
+	Panic(PanicReport),
+	Shutdown(mpsc::SyncSender<()>),
+}
+
+struct Reporter {
+	queue: ArrayQueue<Item>,
+	thread: Option<thread::Thread>,
+}
+
+lazy_static::lazy_static! {
+	static ref REPORTER: Reporter = Reporter::start();
+}
+
+impl Reporter {
+	fn start() -> Self {
+		let spawned = thread::Builder::new()
+			.name(THREAD_NAME.into())
+			.spawn(|| run(&REPORTER.queue));
+		let thread = match spawned {
+			Ok(handle) => Some(handle.thread().clone()),
+			Err(e) => {
+				log::warn!(target: "tracing", "Unable to start the panic reporter: {}", e);
+				None
+			},
+		};
+		Reporter { queue: ArrayQueue::new(MAX_QUEUED), thread }
+	}
+
+	fn push(&self, item: Item) -> Result<(), Item> {
+		let thread = match &self.thread {
+			Some(thread) => thread,
+			None => return Err(item),
+		};
+		self.queue.push(item).map_err(|e| e.0)?;
+		thread.unpark();
+		Ok(())
+	}
+}
+
+/// Whether the current thread is the reporter thread, which can't wait for itself.
+fn on_reporter_thread() -> bool {
+	thread::current().name() == Some(THREAD_NAME)
+}
+
+/// Start the reporter thread, so that it is not started by the first panic.
+pub(crate) fn start() {
+	lazy_static::initialize(&REPORTER);
+}
+
+/// Queue the report of a panic for the reporter thread, dropping it if the queue is full.
+pub(crate) fn queue(report: PanicReport) {
+	if !on_reporter_thread() {
+		let _ = REPORTER.push(Item::Panic(report));
+	}
+}
+
+/// Report the open spans of the global `ProfilingSubscriber` and flush it and the telemetry, from
+/// the reporter thread once the reports queued before are sent.
+///
+/// This is meant to be called right before the process is aborted because of a panic, e.g. with
+/// `sp_panic_handler::set_with_exit_hook`. As the panicking thread may hold the locks they need,
+/// it waits for at most 10 seconds.
+pub fn shutdown_after_panic() {
+	if on_reporter_thread() {
+		return;
+	}
+	let deadline = Instant::now() + SHUTDOWN_TIMEOUT;
+	let (done, flushed) = mpsc::sync_channel(1);
+	let mut shutdown = Item::Shutdown(done);
+	// The shutdown is not dropped when the queue is full, but waits for room.
+	loop {
+		match REPORTER.push(shutdown) {
+			Ok(()) => break,
+			Err(_) if REPORTER.thread.is_none() || Instant::now() >= deadline => return,
+			Err(item) => {
+				shutdown = item;
+				thread::yield_now();
+			},
+		}
+	}
+	let _ = flushed.recv_timeout(deadline - Instant::now().min(deadline));
+}
+
+/// Send the queued reports through the global `ProfilingSubscriber`.
+fn run(queue: &ArrayQueue<Item>) {
+	loop {
+		match queue.pop() {
+			Ok(Item::Panic(report)) => tracing::dispatcher::get_default(|dispatch| {
+				if let Some(subscriber) = dispatch.downcast_ref::<ProfilingSubscriber>() {
+					subscriber.report_panic(&report);
+				}
+			}),
+			Ok(Item::Shutdown(done)) => {
+				crate::shutdown_global();
+				sc_telemetry::flush();
+				let _ = done.send(());
+			},
+			Err(_) => thread::park(),
+		}
+	}
+}
@@ -0,0 +1,626 @@
+// Copyright 2019-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Export of the spans to Parquet files, for offline analysis.
+//!
+//! The spans are buffered until the root span of their trace closes, then the trace is written to
+//! `<dir>/block_number=<n>/<trace id>.parquet`, `n` being the `block_number` value recorded by one
+//! of its spans, e.g. `import_block`. The traces of no block are written to the
+//! `block_number=__HIVE_DEFAULT_PARTITION__` directory, the Hive convention for missing values, so
+//! that the directory can be read as a partitioned dataset, e.g. `pandas.read_parquet(dir)`.
+//!
+//! Every row is a span, with the columns:
+//! - `trace_id`: the id of the trace, unique across restarts of the node.
+//! - `span_id`, `parent_id`: the ids of the span and of its parent, `0` for the root, unique
+//!   within the trace.
+//! - `target`, `name`: the target and name of the span.
+//! - `fields`: the values recorded by the span, as a JSON object.
+//! - `start`: the time the span was last entered, in microseconds since the UNIX epoch.
+//! - `duration_ns`: the time spent in the span, in nanoseconds.
+//!
+//! The files are written uncompressed, with a single row group and plain encoding, which every
+//! Parquet reader supports.
+
+use std::{fs, io, path::{Path, PathBuf}, time::{Duration, Instant, SystemTime, UNIX_EPOCH}};
+use parking_lot::Mutex;
+use rustc_hash::FxHashMap;
+use sc_telemetry::ExporterHealth;
+
+use crate::{SpanDatum, TraceEvent, TraceHandler};
+
+/// Field recording the number of the block a trace belongs to.
+pub const BLOCK_NUMBER_KEY: &str = "block_number";
+
+/// Partition of the traces that don't belong to a block.
+const DEFAULT_PARTITION: &str = "__HIVE_DEFAULT_PARTITION__";
+
+/// Traces whose root span isn't closed are written anyway above this many buffered spans.
+const MAX_PENDING_SPANS: usize = 100_000;
+
+/// The spans whose parent isn't closed for this long are written anyway, with their descendants,
+/// e.g. when the parent is never closed or is not traced.
+const PENDING_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// A span, as written to the Parquet files.
+struct Row {
+	span_id: u64,
+	parent_id: Option<u64>,
+	target: String,
+	name: String,
+	fields: String,
+	start_us: i64,
+	duration_ns: i64,
+	block_number: Option<u64>,
+	/// When the span was buffered.
+	closed: Instant,
+}
+
+#[derive(Default)]
+struct Pending {
+	spans: FxHashMap<u64, Row>,
+	children: FxHashMap<u64, Vec<u64>>,
+	/// When the spans waiting past `PENDING_TIMEOUT` were last written.
+	last_expiry: Option<Instant>,
+}
+
+impl Pending {
+	/// Remove the span `root` and its descendants.
+	fn take_trace(&mut self, root: u64) -> Vec<Row> {
+		// The parent of `root` may never be closed, so it mustn't keep referring to it.
+		let parent = self.spans.get(&root).and_then(|row| row.parent_id);
+		if let Some(parent) = parent {
+			if let Some(siblings) = self.children.get_mut(&parent) {
+				siblings.retain(|id| *id != root);
+				if siblings.is_empty() {
+					self.children.remove(&parent);
+				}
+			}
+		}
+		let mut trace = Vec::new();
+		let mut ids = vec![root];
+		while let Some(id) = ids.pop() {
+			ids.extend(self.children.remove(&id).unwrap_or_default());
+			trace.extend(self.spans.remove(&id));
+		}
+		trace
+	}
+
+	/// The spans whose parent isn't buffered, either because they are roots or because their
+	/// parent isn't closed yet.
+	fn roots(&self) -> Vec<u64> {
+		self.roots_closed_before(None)
+	}
+
+	/// The roots, see [`Pending::roots`], buffered before `before` if given.
+	fn roots_closed_before(&self, before: Option<Instant>) -> Vec<u64> {
+		self.spans.values()
+			.filter(|row| row.parent_id.map_or(true, |parent| !self.spans.contains_key(&parent)))
+			.filter(|row| before.map_or(true, |before| row.closed < before))
+			.map(|row| row.span_id)
+			.collect()
+	}
+}
+
+/// TraceHandler writing the traces to Parquet files in a directory
+pub struct ParquetTraceHandler {
+	dir: PathBuf,
+	session: u128,
+	pending: Mutex<Pending>,
+	health: ExporterHealth,
+}
+
+impl ParquetTraceHandler {
+	/// Create a handler writing the traces to `dir`.
+	pub fn new(dir: PathBuf) -> Self {
+		ParquetTraceHandler {
+			health: ExporterHealth::new(format!("parquet {}", dir.display())),
+			dir,
+			session: SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis(),
+			pending: Default::default(),
+		}
+	}
+
+	fn write_trace(&self, trace: Vec<Row>) {
+		let root = match trace.first() {
+			Some(root) => root.span_id,
+			None => return,
+		};
+		let trace_id = format!("{}-{}", self.session, root);
+		let partition = match trace.iter().find_map(|row| row.block_number) {
+			Some(number) => format!("{}={}", BLOCK_NUMBER_KEY, number),
+			None => format!("{}={}", BLOCK_NUMBER_KEY, DEFAULT_PARTITION),
+		};
+		let path = self.dir.join(partition).join(format!("{}.parquet", trace_id));
+		let start = Instant::now();
+		match write_file(&path, &encode(&trace_id, &trace)) {
+			Ok(()) => self.health.record_sent(trace.len() as u64, start.elapsed()),
+			Err(e) => {
+				log::warn!(target: "tracing", "Unable to write the trace to {}: {}", path.display(), e);
+				self.health.record_dropped(trace.len() as u64);
+			},
+		}
+	}
+
+	fn write_pending(&self, pending: &mut Pending) {
+		for root in pending.roots() {
+			let trace = pending.take_trace(root);
+			self.write_trace(trace);
+		}
+	}
+
+	/// Write the spans whose parent isn't closed after `PENDING_TIMEOUT`, checked at most once
+	/// per `PENDING_TIMEOUT`.
+	fn write_expired(&self, pending: &mut Pending, now: Instant) {
+		match pending.last_expiry {
+			Some(last) if now.duration_since(last) < PENDING_TIMEOUT => return,
+			_ => pending.last_expiry = Some(now),
+		}
+		let expired = match now.checked_sub(PENDING_TIMEOUT) {
+			Some(expired) => expired,
+			None => return,
+		};
+		for root in pending.roots_closed_before(Some(expired)) {
+			let trace = pending.take_trace(root);
+			self.write_trace(trace);
+		}
+	}
+}
+
+impl TraceHandler for ParquetTraceHandler {
+	fn handle_span(&self, span_datum: SpanDatum) {
+		let now = Instant::now();
+		let start = span_datum.start_wall_time;
+		let row = Row {
+			span_id: span_datum.id.into_u64(),
+			parent_id: span_datum.parent_id.map(|id| id.into_u64()),
+			target: span_datum.target,
+			name: span_datum.name,
+			fields: serde_json::to_string(&span_datum.values).unwrap_or_default(),
+			start_us: start.duration_since(UNIX_EPOCH).unwrap_or_default().as_micros() as i64,
+			duration_ns: span_datum.overall_time.as_nanos() as i64,
+			block_number: span_datum.values.u64_values.get(BLOCK_NUMBER_KEY).cloned(),
+			closed: now,
+		};
+
+		let mut pending = self.pending.lock();
+		let id = row.span_id;
+		match row.parent_id {
+			Some(parent) => {
+				pending.children.entry(parent).or_default().push(id);
+				pending.spans.insert(id, row);
+			},
+			None => {
+				pending.spans.insert(id, row);
+				let trace = pending.take_trace(id);
+				self.write_trace(trace);
+			},
+		}
+		if pending.spans.len() > MAX_PENDING_SPANS {
+			self.write_pending(&mut pending);
+		} else {
+			self.write_expired(&mut pending, now);
+		}
+	}
+
+	fn handle_event(&self, _: TraceEvent) {}
+
+	fn flush(&self) {
+		let mut pending = self.pending.lock();
+		self.write_pending(&mut pending);
+	}
+}
+
+/// Write `data` to `path`, through a temporary file so that readers never see partial files.
+pub(crate) fn write_file(path: &Path, data: &[u8]) -> io::Result<()> {
+	if let Some(dir) = path.parent() {
+		fs::create_dir_all(dir)?;
+	}
+	let mut tmp = path.as_os_str().to_owned();
+	tmp.push(".tmp");
+	let tmp = PathBuf::from(tmp);
+	fs::write(&tmp, data)?;
+	fs::rename(&tmp, path)
+}
+
+/// A value of the Thrift compact protocol, in which the Parquet metadata is encoded.
+enum Thrift {
+	I32(i32),
+	I64(i64),
+	Binary(Vec<u8>),
+	List(u8, Vec<Thrift>),
+	Struct(Vec<(i16, Thrift)>),
+}
+
+const THRIFT_I32: u8 = 5;
+const THRIFT_I64: u8 = 6;
+const THRIFT_BINARY: u8 = 8;
+const THRIFT_LIST: u8 = 9;
+const THRIFT_STRUCT: u8 = 12;
+
+impl Thrift {
+	fn string(s: &str) -> Self {
+		Thrift::Binary(s.as_bytes().to_vec())
+	}
+
+	fn type_id(&self) -> u8 {
+		match self {
+			Thrift::I32(_) => THRIFT_I32,
+			Thrift::I64(_) => THRIFT_I64,
+			Thrift::Binary(_) => THRIFT_BINARY,
+			Thrift::List(..) => THRIFT_LIST,
+			Thrift::Struct(_) => THRIFT_STRUCT,
+		}
+	}
+
+	fn encode_to(&self, out: &mut Vec<u8>) {
+		match self {
+			Thrift::I32(v) => write_varint(out, zigzag(*v as i64)),
+			Thrift::I64(v) => write_varint(out, zigzag(*v)),
+			Thrift::Binary(bytes) => {
+				write_varint(out, bytes.len() as u64);
+				out.extend_from_slice(bytes);
+			},
+			Thrift::List(elem_type, items) => {
+				if items.len() < 15 {
+					out.push((items.len() as u8) << 4 | elem_type);
+				} else {
+					out.push(0xf0 | elem_type);
+					write_varint(out, items.len() as u64);
+				}
+				for item in items {
+					item.encode_to(out);
+				}
+			},
+			Thrift::Struct(fields) => {
+				let mut last_id = 0;
+				for (id, value) in fields {
+					match id - last_id {
+						delta @ 1..=15 => out.push((delta as u8) << 4 | value.type_id()),
+						_ => {
+							out.push(value.type_id());
+							write_varint(out, zigzag(*id as i64));
+						},
+					}
+					value.encode_to(out);
+					last_id = *id;
+				}
+				out.push(0);
+			},
+		}
+	}
+}
+
+fn zigzag(v: i64) -> u64 {
+	((v << 1) ^ (v >> 63)) as u64
+}
+
+fn write_varint(out: &mut Vec<u8>, mut v: u64) {
+	while v >= 0x80 {
+		out.push(v as u8 | 0x80);
+		v >>= 7;
+	}
+	out.push(v as u8);
+}
+
+// Physical and converted types of the Parquet format.
+const TYPE_INT64: i32 = 2;
+const TYPE_BYTE_ARRAY: i32 = 6;
+const CONVERTED_UTF8: i32 = 0;
+const CONVERTED_TIMESTAMP_MICROS: i32 = 10;
+
+const ENCODING_PLAIN: i32 = 0;
+const ENCODING_RLE: i32 = 3;
+
+/// A column, with its values plain encoded.
+struct Column {
+	name: &'static str,
+	physical_type: i32,
+	converted_type: Option<i32>,
+	data: Vec<u8>,
+}
+
+impl Column {
+	fn int64(name: &'static str, converted_type: Option<i32>, values: impl Iterator<Item = i64>) -> Self {
+		let data = values.flat_map(|v| v.to_le_bytes().to_vec()).collect();
+		Column { name, physical_type: TYPE_INT64, converted_type, data }
+	}
+
+	fn utf8<'a>(name: &'static str, values: impl Iterator<Item = &'a str>) -> Self {
+		let mut data = Vec::new();
+		for value in values {
+			data.extend_from_slice(&(value.len() as u32).to_le_bytes());
+			data.extend_from_slice(value.as_bytes());
+		}
+		Column { name, physical_type: TYPE_BYTE_ARRAY, converted_type: Some(CONVERTED_UTF8), data }
+	}
+}
+
+/// Encode the spans of a trace as a Parquet file.
+fn encode(trace_id: &str, rows: &[Row]) -> Vec<u8> {
+	let columns = vec![
+		Column::utf8("trace_id", rows.iter().map(|_| trace_id)),
+		Column::int64("span_id", None, rows.iter().map(|row| row.span_id as i64)),
+		Column::int64("parent_id", None, rows.iter().map(|row| row.parent_id.unwrap_or(0) as i64)),
+		Column::utf8("target", rows.iter().map(|row| row.target.as_str())),
+		Column::utf8("name", rows.iter().map(|row| row.name.as_str())),
+		Column::utf8("fields", rows.iter().map(|row| row.fields.as_str())),
+		Column::int64("start", Some(CONVERTED_TIMESTAMP_MICROS), rows.iter().map(|row| row.start_us)),
+		Column::int64("duration_ns", None, rows.iter().map(|row| row.duration_ns)),
+	];
+	let num_rows = rows.len() as i64;
+
+	let mut out = b"PAR1".to_vec();
+	let mut chunks = Vec::new();
+	let mut schema = vec![Thrift::Struct(vec![
+		(4, Thrift::string("schema")),
+		(5, Thrift::I32(columns.len() as i32)),
+	])];
+	for column in &columns {
+		let offset = out.len() as i64;
+		let size = column.data.len() as i32;
+		Thrift::Struct(vec![
+			// A data page, holding all the values of the column.
+			(1, Thrift::I32(0)),
+			(2, Thrift::I32(size)),
+			(3, Thrift::I32(size)),
+			(5, Thrift::Struct(vec![
+				(1, Thrift::I32(num_rows as i32)),
+				(2, Thrift::I32(ENCODING_PLAIN)),
+				(3, Thrift::I32(ENCODING_RLE)),
+				(4, Thrift::I32(ENCODING_RLE)),
+			])),
+		]).encode_to(&mut out);
+		out.extend_from_slice(&column.data);
+		let chunk_size = out.len() as i64 - offset;
+
+		chunks.push(Thrift::Struct(vec![
+			(2, Thrift::I64(offset)),
+			(3, Thrift::Struct(vec![
+				(1, Thrift::I32(column.physical_type)),
+				(2, Thrift::List(THRIFT_I32, vec![Thrift::I32(ENCODING_PLAIN)])),
+				(3, Thrift::List(THRIFT_BINARY, vec![Thrift::string(column.name)])),
+				// Uncompressed.
+				(4, Thrift::I32(0)),
+				(5, Thrift::I64(num_rows)),
+				(6, Thrift::I64(chunk_size)),
+				(7, Thrift::I64(chunk_size)),
+				(9, Thrift::I64(offset)),
+			])),
+		]));
+		let mut element = vec![
+			(1, Thrift::I32(column.physical_type)),
+			// Required.
+			(3, Thrift::I32(0)),
+			(4, Thrift::string(column.name)),
+		];
+		if let Some(converted_type) = column.converted_type {
+			element.push((6, Thrift::I32(converted_type)));
+		}
+		schema.push(Thrift::Struct(element));
+	}
+
+	let total_size = out.len() as i64 - 4;
+	let metadata_start = out.len();
+	Thrift::Struct(vec![
+		(1, Thrift::I32(1)),
+		(2, Thrift::List(THRIFT_STRUCT, schema)),
+		(3, Thrift::I64(num_rows)),
+		(4, Thrift::List(THRIFT_STRUCT, vec![Thrift::Struct(vec![
+			(1, Thrift::List(THRIFT_STRUCT, chunks)),
+			(2, Thrift::I64(total_size)),
+			(3, Thrift::I64(num_rows)),
+		])])),
+		(6, Thrift::string(concat!("sc-tracing version ", env!("CARGO_PKG_VERSION")))),
+	]).encode_to(&mut out);
+	let metadata_len = (out.len() - metadata_start) as u32;
+	out.extend_from_slice(&metadata_len.to_le_bytes());
+	out.extend_from_slice(b"PAR1");
+	out
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::ProfilingSubscriber;
+
+	/// Decode a Thrift compact value of type `type_id`.
+	fn decode(type_id: u8, input: &mut &[u8]) -> Thrift {
+		fn varint(input: &mut &[u8]) -> u64 {
+			let mut v = 0;
+			for shift in (0..).step_by(7) {
+				let byte = input[0];
+				*input = &input[1..];
+				v |= ((byte & 0x7f) as u64) << shift;
+				if byte < 0x80 {
+					break;
+				}
+			}
+			v
+		}
+		fn unzigzag(v: u64) -> i64 {
+			(v >> 1) as i64 ^ -((v & 1) as i64)
+		}
+
+		match type_id {
+			THRIFT_I32 => Thrift::I32(unzigzag(varint(input)) as i32),
+			THRIFT_I64 => Thrift::I64(unzigzag(varint(input))),
+			THRIFT_BINARY => {
+				let len = varint(input) as usize;
+				let bytes = input[..len].to_vec();
+				*input = &input[len..];
+				Thrift::Binary(bytes)
+			},
+			THRIFT_LIST => {
+				let header = input[0];
+				*input = &input[1..];
+				let len = match header >> 4 {
+					15 => varint(input) as usize,
+					len => len as usize,
+				};
+				Thrift::List(header & 0x0f, (0..len).map(|_| decode(header & 0x0f, input)).collect())
+			},
+			THRIFT_STRUCT => {
+				let mut fields = Vec::new();
+				let mut last_id = 0;
+				loop {
+					let header = input[0];
+					*input = &input[1..];
+					if header == 0 {
+						break;
+					}
+					let id = match header >> 4 {
+						0 => unzigzag(varint(input)) as i16,
+						delta => last_id + delta as i16,
+					};
+					fields.push((id, decode(header & 0x0f, input)));
+					last_id = id;
+				}
+				Thrift::Struct(fields)
+			},
+			_ => panic!("Unexpected type {}", type_id),
+		}
+	}
+
+	fn field(value: &Thrift, id: i16) -> &Thrift {
+		match value {
+			Thrift::Struct(fields) => &fields.iter().find(|(i, _)| *i == id).unwrap().1,
+			_ => panic!("Not a struct"),
+		}
+	}
+
+	fn int(value: &Thrift) -> i64 {
+		match value {
+			Thrift::I32(v) => *v as i64,
+			Thrift::I64(v) => *v,
+			_ => panic!("Not an integer"),
+		}
+	}
+
+	fn list(value: &Thrift) -> &[Thrift] {
+		match value {
+			Thrift::List(_, items) => items,
+			_ => panic!("Not a list"),
+		}
+	}
+
+	fn string(value: &Thrift) -> String {
+		match value {
+			Thrift::Binary(bytes) => String::from_utf8(bytes.clone()).unwrap(),
+			_ => panic!("Not a string"),
+		}
+	}
+
+	/// Decode the metadata of a Parquet file, and the plain encoded values of its `column`.
+	fn read(file: &[u8], column: &str) -> (Thrift, Vec<u8>) {
+		assert_eq!(&file[..4], b"PAR1");
+		assert_eq!(&file[file.len() - 4..], b"PAR1");
+		let mut len = [0; 4];
+		len.copy_from_slice(&file[file.len() - 8..file.len() - 4]);
+		let metadata_start = file.len() - 8 - u32::from_le_bytes(len) as usize;
+		let metadata = decode(THRIFT_STRUCT, &mut &file[metadata_start..file.len() - 8]);
+
+		let chunks = list(field(&list(field(&metadata, 4))[0], 1));
+		let chunk = chunks.iter()
+			.map(|chunk| field(chunk, 3))
+			.find(|meta| string(&list(field(meta, 3))[0]) == column)
+			.unwrap();
+		let mut page = &file[int(field(chunk, 9)) as usize..];
+		let header = decode(THRIFT_STRUCT, &mut page);
+		assert_eq!(int(field(field(&header, 5), 1)), int(field(&metadata, 3)));
+		let values = page[..int(field(&header, 2)) as usize].to_vec();
+		(metadata, values)
+	}
+
+	#[test]
+	fn writes_the_spans_of_unclosed_parents_after_timeout() {
+		let dir = tempfile::tempdir().unwrap();
+		let handler = ParquetTraceHandler::new(dir.path().to_owned());
+		let closed = Instant::now();
+		let row = |span_id, parent_id| Row {
+			span_id,
+			parent_id,
+			target: "test_target".into(),
+			name: "orphan".into(),
+			fields: "{}".into(),
+			start_us: 0,
+			duration_ns: 0,
+			block_number: None,
+			closed,
+		};
+		let mut pending = handler.pending.lock();
+		// The span 1 is never closed.
+		pending.children.entry(1).or_default().push(2);
+		pending.spans.insert(2, row(2, Some(1)));
+		pending.children.entry(2).or_default().push(3);
+		pending.spans.insert(3, row(3, Some(2)));
+
+		handler.write_expired(&mut pending, closed + PENDING_TIMEOUT / 2);
+		assert_eq!(pending.spans.len(), 2);
+		// Checked once per timeout only.
+		handler.write_expired(&mut pending, closed + PENDING_TIMEOUT * 5 / 4);
+		assert_eq!(pending.spans.len(), 2);
+		handler.write_expired(&mut pending, closed + PENDING_TIMEOUT * 2);
+		assert!(pending.spans.is_empty());
+		assert!(pending.children.is_empty());
+
+		let partition = dir.path().join("block_number=__HIVE_DEFAULT_PARTITION__");
+		assert_eq!(fs::read_dir(partition).unwrap().count(), 1);
+	}
+
+	#[test]
+	fn writes_traces_partitioned_by_block() {
+		let dir = tempfile::tempdir().unwrap();
+		let handler = ParquetTraceHandler::new(dir.path().to_owned());
+		let subscriber = ProfilingSubscriber::new_with_handler(Box::new(handler), "test_target");
+		let dispatch = tracing::Dispatch::new(subscriber);
+		tracing::dispatcher::with_default(&dispatch, || {
+			tracing::info_span!(target: "test_target", "import_block", block_number = 42u64).in_scope(|| {
+				for i in 0..20u64 {
+					tracing::info_span!(target: "test_target", "apply_extrinsic", index = i).in_scope(|| ());
+				}
+			});
+			tracing::info_span!(target: "test_target", "validate_transaction").in_scope(|| ());
+		});
+
+		let files = |partition: &str| fs::read_dir(dir.path().join(partition)).unwrap()
+			.map(|entry| entry.unwrap().path())
+			.collect::<Vec<_>>();
+		let block_files = files("block_number=42");
+		assert_eq!(block_files.len(), 1);
+		assert_eq!(files("block_number=__HIVE_DEFAULT_PARTITION__").len(), 1);
+
+		let file = fs::read(&block_files[0]).unwrap();
+		let (metadata, names) = read(&file, "name");
+		assert_eq!(int(field(&metadata, 3)), 21);
+		let schema = list(field(&metadata, 2)).iter()
+			.map(|element| string(field(element, 4)))
+			.collect::<Vec<_>>();
+		assert_eq!(
+			schema,
+			vec!["schema", "trace_id", "span_id", "parent_id", "target", "name", "fields", "start", "duration_ns"],
+		);
+		// The root of the trace comes first.
+		assert_eq!(&names[..4], &(12u32).to_le_bytes());
+		assert_eq!(&names[4..16], b"import_block");
+
+		let int64s = |column| read(&file, column).1.chunks(8)
+			.map(|v| { let mut b = [0; 8]; b.copy_from_slice(v); i64::from_le_bytes(b) })
+			.collect::<Vec<_>>();
+		let parents = int64s("parent_id");
+		assert_eq!(parents[0], 0);
+		assert!(parents[1..].iter().all(|parent| *parent == int64s("span_id")[0]));
+	}
+}
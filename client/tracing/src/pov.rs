@@ -0,0 +1,150 @@
+// Copyright 2019-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Breakdowns of the proofs of validity (PoV) of the built blocks.
+//!
+//! While a block is built under a `build_block` span recording a storage proof, the trie nodes
+//! added to the proof are attributed to the spans they are read in, e.g. the spans of the calls
+//! of the pallets. The nodes are counted once, by the span reading them first, and their bytes
+//! bound the size of the compact proof, which leaves out the hashes of the nodes it holds.
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::{BLOCK_HASH_KEY, BLOCK_NUMBER_KEY, SpanDatum, Values};
+
+/// Name of the span the building of a block is traced under.
+pub const BUILD_BLOCK_SPAN: &str = "build_block";
+
+/// Field of the `build_block` span recording the hash of the parent of the block.
+pub const PARENT_HASH_KEY: &str = "parent_hash";
+
+/// Field of the `build_block` span recording the encoded size of the storage proof of the block.
+pub const PROOF_SIZE_KEY: &str = "proof_size";
+
+/// The trie nodes of a proof read under the spans of a target and name.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PovContribution {
+	/// Target of the spans.
+	pub target: String,
+	/// Name of the spans.
+	pub name: String,
+	/// Number of trie nodes.
+	pub nodes: u64,
+	/// Bytes of the trie nodes.
+	pub bytes: u64,
+}
+
+/// Breakdown of the proof of validity of a built block.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PovBreakdown {
+	/// Number of the block.
+	pub block_number: u64,
+	/// Hash of the parent of the block, as recorded by the `build_block` span.
+	pub parent_hash: String,
+	/// Hash of the block, if it was built.
+	pub block_hash: Option<String>,
+	/// Encoded size of the storage proof, if it was built.
+	pub proof_size: Option<u64>,
+	/// Bytes of the trie nodes of the proof, the sum of the contributions.
+	pub recorded_bytes: u64,
+	/// The contributions of the spans, largest first.
+	pub contributions: Vec<PovContribution>,
+}
+
+/// The number of nodes and bytes read under the spans of a target and name.
+type Contributions = FxHashMap<(String, String), (u64, u64)>;
+
+struct Inner {
+	capacity: usize,
+	pending: Mutex<FxHashMap<u64, Contributions>>,
+	recent: Mutex<VecDeque<PovBreakdown>>,
+}
+
+/// The breakdowns of the proofs of the last built blocks.
+#[derive(Clone)]
+pub struct PovBreakdowns {
+	inner: Arc<Inner>,
+}
+
+impl PovBreakdowns {
+	/// Keeps the breakdowns of the last `capacity` built blocks.
+	pub fn new(capacity: usize) -> Self {
+		PovBreakdowns {
+			inner: Arc::new(Inner {
+				capacity: capacity.max(1),
+				pending: Default::default(),
+				recent: Default::default(),
+			}),
+		}
+	}
+
+	/// Whether the span is the root of the building of a block.
+	pub(crate) fn is_build_span(name: &str, values: &Values) -> bool {
+		name == BUILD_BLOCK_SPAN && values.u64_values.contains_key(BLOCK_NUMBER_KEY)
+	}
+
+	/// Attribute a trie node of `bytes` to the spans of `target` and `name` under the build span
+	/// `block`.
+	pub(crate) fn record(&self, block: u64, target: &str, name: &str, bytes: u64) {
+		let mut pending = self.inner.pending.lock();
+		let (nodes, total) = pending.entry(block).or_default()
+			.entry((target.to_owned(), name.to_owned())).or_default();
+		*nodes += 1;
+		*total = total.saturating_add(bytes);
+	}
+
+	/// Finish the breakdown of the closed build span.
+	pub(crate) fn finish(&self, span_datum: &SpanDatum) {
+		let contributions = self.inner.pending.lock()
+			.remove(&span_datum.id.into_u64())
+			.unwrap_or_default();
+		// Blocks built without recording a proof have nothing to break down.
+		if contributions.is_empty() {
+			return;
+		}
+		let mut contributions: Vec<_> = contributions.into_iter()
+			.map(|((target, name), (nodes, bytes))| PovContribution { target, name, nodes, bytes })
+			.collect();
+		contributions.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.name.cmp(&b.name)));
+		let values = &span_datum.values;
+		let breakdown = PovBreakdown {
+			block_number: values.u64_values.get(BLOCK_NUMBER_KEY).copied().unwrap_or_default(),
+			parent_hash: values.string_values.get(PARENT_HASH_KEY).cloned().unwrap_or_default(),
+			block_hash: values.string_values.get(BLOCK_HASH_KEY).cloned(),
+			proof_size: values.u64_values.get(PROOF_SIZE_KEY).copied(),
+			recorded_bytes: contributions.iter().map(|c| c.bytes).fold(0, u64::saturating_add),
+			contributions,
+		};
+
+		let mut recent = self.inner.recent.lock();
+		if recent.len() == self.inner.capacity {
+			recent.pop_front();
+		}
+		recent.push_back(breakdown);
+	}
+
+	/// The breakdowns of the last built blocks, the most recent last.
+	pub fn breakdowns(&self) -> Vec<PovBreakdown> {
+		self.inner.recent.lock().iter().cloned().collect()
+	}
+}
@@ -0,0 +1,268 @@
+// Copyright 2019-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Hands the spans and events over to the trace handlers on a thread of their own.
+//!
+//! The threads executing the blocks push the spans and events to a bounded lock-free queue, and
+//! an exporter thread drains it into the handler. When the queue is full, the spans and events
+//! are dropped and counted, instead of stalling the execution.
+
+use std::sync::{Arc, mpsc, atomic::{AtomicBool, AtomicU64, Ordering}};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossbeam_queue::ArrayQueue;
+use sc_telemetry::ExporterHealth;
+
+use crate::{SpanDatum, TraceEvent, TraceHandler};
+
+/// How long the exporter thread waits for more spans and events once the queue is empty.
+const PARK_TIMEOUT: Duration = Duration::from_millis(50);
+/// How long `flush` waits for the spans and events queued before it to be handled.
+const FLUSH_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Default)]
+struct Counts {
+	exported: AtomicU64,
+	dropped: AtomicU64,
+}
+
+/// The number of spans and events handed over to the handler and dropped, shared by the
+/// `QueuedTraceHandler` and its exporter thread.
+#[derive(Debug, Clone, Default)]
+pub struct QueueMetrics {
+	inner: Arc<Counts>,
+}
+
+impl QueueMetrics {
+	/// Number of spans and events handed over to the handler.
+	pub fn exported(&self) -> u64 {
+		self.inner.exported.load(Ordering::Relaxed)
+	}
+
+	/// Number of spans and events dropped because the queue was full.
+	pub fn dropped(&self) -> u64 {
+		self.inner.dropped.load(Ordering::Relaxed)
+	}
+}
+
+enum Item {
+	Span(SpanDatum),
+	Event(TraceEvent),
+	Flush(mpsc::SyncSender<()>),
+}
+
+struct Shared {
+	queue: ArrayQueue<Item>,
+	metrics: QueueMetrics,
+	health: ExporterHealth,
+	closed: AtomicBool,
+}
+
+/// TraceHandler queueing the spans and events for another handler, which handles them on the
+/// exporter thread
+pub struct QueuedTraceHandler {
+	shared: Arc<Shared>,
+	exporter: Option<thread::Thread>,
+}
+
+impl QueuedTraceHandler {
+	/// Queue up to `capacity` spans and events for `handler`.
+	pub fn new(handler: Box<dyn TraceHandler>, capacity: usize) -> Self {
+		let shared = Arc::new(Shared {
+			queue: ArrayQueue::new(capacity.max(1)),
+			metrics: Default::default(),
+			health: ExporterHealth::new("tracing-queue"),
+			closed: AtomicBool::new(false),
+		});
+		let exporter = {
+			let shared = shared.clone();
+			thread::Builder::new()
+				.name("tracing-exporter".into())
+				.spawn(move || export(&shared, &*handler))
+		};
+		let exporter = match exporter {
+			Ok(exporter) => Some(exporter.thread().clone()),
+			Err(e) => {
+				log::warn!(target: "tracing", "Unable to start the tracing exporter: {}", e);
+				None
+			},
+		};
+		QueuedTraceHandler { shared, exporter }
+	}
+
+	/// The metrics of the queue.
+	pub fn metrics(&self) -> QueueMetrics {
+		self.shared.metrics.clone()
+	}
+
+	fn push(&self, item: Item) {
+		match self.shared.queue.push(item) {
+			Ok(()) => {
+				self.shared.health.record_queued(1);
+				if let Some(exporter) = &self.exporter {
+					exporter.unpark();
+				}
+			},
+			Err(_) => {
+				self.shared.health.record_dropped(1);
+				if self.shared.metrics.inner.dropped.fetch_add(1, Ordering::Relaxed) == 0 {
+					log::warn!(target: "tracing", "The tracing queue is full, dropping spans and events");
+				}
+			},
+		}
+	}
+}
+
+impl TraceHandler for QueuedTraceHandler {
+	fn handle_span(&self, span_datum: SpanDatum) {
+		self.push(Item::Span(span_datum));
+	}
+
+	fn handle_event(&self, event: TraceEvent) {
+		self.push(Item::Event(event));
+	}
+
+	fn flush(&self) {
+		let exporter = match &self.exporter {
+			Some(exporter) => exporter,
+			None => return,
+		};
+		let deadline = Instant::now() + FLUSH_TIMEOUT;
+		let (done, flushed) = mpsc::sync_channel(1);
+		let mut flush = Item::Flush(done);
+		// The flush is not dropped when the queue is full, but waits for room.
+		while let Err(item) = self.shared.queue.push(flush) {
+			if Instant::now() >= deadline {
+				return;
+			}
+			flush = item.0;
+			exporter.unpark();
+			thread::yield_now();
+		}
+		exporter.unpark();
+		let _ = flushed.recv_timeout(deadline - Instant::now().min(deadline));
+	}
+}
+
+impl Drop for QueuedTraceHandler {
+	fn drop(&mut self) {
+		self.shared.closed.store(true, Ordering::Relaxed);
+		if let Some(exporter) = &self.exporter {
+			exporter.unpark();
+		}
+	}
+}
+
+/// Hand the queued spans and events over to `handler`, until the `QueuedTraceHandler` is dropped.
+fn export(shared: &Shared, handler: &dyn TraceHandler) {
+	let exported = |start: Instant| {
+		shared.health.record_dequeued(1);
+		shared.health.record_sent(1, start.elapsed());
+		shared.metrics.inner.exported.fetch_add(1, Ordering::Relaxed);
+	};
+	loop {
+		match shared.queue.pop() {
+			Ok(Item::Span(span_datum)) => {
+				let start = Instant::now();
+				handler.handle_span(span_datum);
+				exported(start);
+			},
+			Ok(Item::Event(event)) => {
+				let start = Instant::now();
+				handler.handle_event(event);
+				exported(start);
+			},
+			Ok(Item::Flush(done)) => {
+				handler.flush();
+				let _ = done.send(());
+			},
+			Err(_) if shared.closed.load(Ordering::Relaxed) => {
+				handler.flush();
+				return;
+			},
+			Err(_) => thread::park_timeout(PARK_TIMEOUT),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use parking_lot::Mutex;
+	use tracing::Level;
+
+	#[derive(Clone, Default)]
+	struct Recorder {
+		events: Arc<Mutex<Vec<String>>>,
+		// Blocks the handling of the events while locked.
+		gate: Arc<Mutex<()>>,
+	}
+
+	impl TraceHandler for Recorder {
+		fn handle_span(&self, _: SpanDatum) {}
+
+		fn handle_event(&self, event: TraceEvent) {
+			let _gate = self.gate.lock();
+			self.events.lock().push(event.name.to_owned());
+		}
+	}
+
+	fn event(name: &'static str) -> TraceEvent {
+		TraceEvent {
+			name,
+			target: "queue".into(),
+			level: Level::INFO,
+			values: Default::default(),
+			parent_id: None,
+			time: std::time::SystemTime::now(),
+		}
+	}
+
+	#[test]
+	fn events_are_exported_in_order() {
+		let recorder = Recorder::default();
+		let handler = QueuedTraceHandler::new(Box::new(recorder.clone()), 16);
+		handler.handle_event(event("first"));
+		handler.handle_event(event("second"));
+		handler.flush();
+		assert_eq!(*recorder.events.lock(), vec!["first", "second"]);
+		assert_eq!(handler.metrics().exported(), 2);
+		assert_eq!(handler.metrics().dropped(), 0);
+	}
+
+	#[test]
+	fn events_are_dropped_when_full() {
+		let recorder = Recorder::default();
+		let handler = QueuedTraceHandler::new(Box::new(recorder.clone()), 2);
+		let gate = recorder.gate.lock();
+		// The exporter takes the first event and blocks, the next two fill the queue.
+		handler.handle_event(event("first"));
+		while !handler.shared.queue.is_empty() {
+			thread::yield_now();
+		}
+		for name in &["second", "third", "fourth", "fifth"] {
+			handler.handle_event(event(name));
+		}
+		drop(gate);
+		handler.flush();
+		assert_eq!(*recorder.events.lock(), vec!["first", "second", "third"]);
+		assert_eq!(handler.metrics().exported(), 3);
+		assert_eq!(handler.metrics().dropped(), 2);
+		let health = handler.shared.health.status();
+		assert_eq!((health.queued, health.sent, health.dropped), (0, 3, 2));
+	}
+}
@@ -0,0 +1,159 @@
+// Copyright 2019-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Redaction of the values of the traces sent to telemetry, Loki, Datadog and Graylog.
+
+use std::fmt;
+use blake2_rfc::blake2b::Blake2b;
+use rand::RngCore;
+use rustc_hash::FxHashMap;
+use sp_core::hexdisplay::HexDisplay;
+
+use crate::Values;
+
+/// What to do with a redacted field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RedactionMode {
+	/// Replace the value with its hash, so that equal values can still be correlated.
+	Hash,
+	/// Remove the field.
+	Drop,
+}
+
+/// Fields to redact from the traces before they leave the node through telemetry, Loki, Datadog or
+/// Graylog.
+///
+/// The policy is keyed by field name, whatever the target of the span or event, e.g. `sender`,
+/// `remark` or `key`. The values are hashed with a secret key drawn when the policy is created,
+/// so that they can be correlated within a run of the node but not guessed by hashing candidate
+/// values, e.g. the known accounts. Hashing still keeps the values of small domains, like booleans
+/// or amounts, correlatable, so those are better dropped.
+#[derive(Clone)]
+pub struct Redaction {
+	fields: FxHashMap<String, RedactionMode>,
+	key: [u8; 32],
+}
+
+impl Default for Redaction {
+	fn default() -> Self {
+		let mut key = [0; 32];
+		rand::thread_rng().fill_bytes(&mut key);
+		Redaction { fields: Default::default(), key }
+	}
+}
+
+impl fmt::Debug for Redaction {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		// The key is left out, not to be logged.
+		f.debug_struct("Redaction").field("fields", &self.fields).finish()
+	}
+}
+
+impl Redaction {
+	/// Parses a comma separated list of field names, e.g. `sender,remark=drop`.
+	///
+	/// The values of the fields are hashed, or dropped if the name is suffixed with `=drop`.
+	pub fn new(fields: &str) -> Result<Self, String> {
+		let fields = fields.split(',')
+			.filter(|field| !field.is_empty())
+			.map(|field| match field.find('=') {
+				None => Ok((field.to_owned(), RedactionMode::Hash)),
+				Some(i) => match &field[i + 1..] {
+					"hash" => Ok((field[..i].to_owned(), RedactionMode::Hash)),
+					"drop" => Ok((field[..i].to_owned(), RedactionMode::Drop)),
+					mode => Err(format!("Invalid redaction mode `{}` for field `{}`", mode, &field[..i])),
+				},
+			})
+			.collect::<Result<_, _>>()?;
+		Ok(Redaction { fields, ..Default::default() })
+	}
+
+	/// Whether no field is redacted.
+	pub fn is_empty(&self) -> bool {
+		self.fields.is_empty()
+	}
+
+	/// Returns the given values with the configured fields hashed or dropped.
+	///
+	/// Hashed values are stored as strings, whatever their original type.
+	pub fn redact(&self, values: &Values) -> Values {
+		let mut values = values.clone();
+		for (name, mode) in &self.fields {
+			let value = values.bool_values.remove(name).map(|v| v.to_string())
+				.or_else(|| values.i64_values.remove(name).map(|v| v.to_string()))
+				.or_else(|| values.u64_values.remove(name).map(|v| v.to_string()))
+				.or_else(|| values.string_values.remove(name));
+			if let (Some(value), RedactionMode::Hash) = (value, mode) {
+				values.string_values.insert(name.clone(), self.hash(&value));
+			}
+		}
+		values
+	}
+
+	fn hash(&self, value: &str) -> String {
+		let mut hasher = Blake2b::with_key(16, &self.key);
+		hasher.update(value.as_bytes());
+		format!("0x{}", HexDisplay::from(&hasher.finalize().as_bytes()))
+	}
+}
+
+/// The fields of the telemetry messages routed through the subscriber are redacted like the
+/// values of the traces, see `sc_telemetry::route_through_tracing`.
+impl sc_telemetry::RedactFields for Redaction {
+	fn is_redacted(&self, key: &str) -> bool {
+		self.fields.contains_key(key)
+	}
+
+	fn redact(&self, key: &str, value: String) -> Option<String> {
+		match self.fields.get(key) {
+			Some(RedactionMode::Drop) => None,
+			Some(RedactionMode::Hash) => Some(self.hash(&value)),
+			None => Some(value),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn redacts_configured_fields() {
+		assert!(Redaction::new("sender=forget").is_err());
+		let redaction = Redaction::new("sender,amount=drop,key=hash").unwrap();
+
+		let mut values = Values::new();
+		values.string_values.insert("sender".to_owned(), "5GrwvaEF".to_owned());
+		values.u64_values.insert("amount".to_owned(), 100);
+		values.u64_values.insert("key".to_owned(), 7);
+		values.string_values.insert("call".to_owned(), "transfer".to_owned());
+		let redacted = redaction.redact(&values);
+
+		let sender = &redacted.string_values["sender"];
+		assert!(sender.starts_with("0x") && sender != "5GrwvaEF");
+		assert_eq!(sender, &redaction.redact(&values).string_values["sender"]);
+		assert!(!redacted.u64_values.contains_key("amount"));
+		assert!(!redacted.string_values.contains_key("amount"));
+		assert!(!redacted.u64_values.contains_key("key"));
+		assert!(redacted.string_values["key"].starts_with("0x"));
+		assert_eq!(redacted.string_values["call"], "transfer");
+
+		// Another node hashes the values with another key.
+		let other = Redaction::new("sender").unwrap().redact(&values);
+		assert_ne!(sender, &other.string_values["sender"]);
+		assert_ne!(sender, &format!("0x{}", HexDisplay::from(&sp_core::blake2_128(b"5GrwvaEF"))));
+	}
+}
@@ -0,0 +1,327 @@
+// Copyright 2019-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Replay of the traces written by the [`BinaryTraceHandler`](crate::BinaryTraceHandler).
+//!
+//! The spans and events of a trace are handed to a [`TraceHandler`] in the order they were
+//! recorded, so that e.g. a trace captured on a validator can be turned into Parquet files later.
+//! The summaries of the traces of the blocks stored in the database of a node can be replayed
+//! the same way, see [`replay_summaries`].
+
+use std::{io::{Read, Write}, time::{Duration, Instant, SystemTime, UNIX_EPOCH}};
+use rustc_hash::FxHashMap;
+use sc_tracing_format::{Level, Record, TraceReader, Value};
+use tracing::{
+	callsite::Callsite,
+	metadata::{Kind, Metadata},
+	span::Id,
+	subscriber::Interest,
+};
+
+use crate::{BLOCK_HASH_KEY, BLOCK_NUMBER_KEY, BlockSummary, SpanDatum, TraceEvent, TraceHandler, Values};
+use crate::summary::BLOCK_SPAN;
+
+/// Target of the spans and events replayed from trace summaries.
+const SUMMARY_TARGET: &str = "trace_summary";
+
+struct ReplayCallsite;
+
+impl Callsite for ReplayCallsite {
+	fn set_interest(&self, _: Interest) {}
+
+	fn metadata(&self) -> &Metadata<'_> {
+		&REPLAY_METADATA
+	}
+}
+
+static REPLAY_CALLSITE: ReplayCallsite = ReplayCallsite;
+
+/// Metadata of the replayed spans, whose callsites are not known.
+static REPLAY_METADATA: Metadata<'static> = tracing_core::metadata! {
+	name: "replay",
+	target: "tracing",
+	level: tracing::Level::TRACE,
+	fields: &[],
+	callsite: &REPLAY_CALLSITE,
+	kind: Kind::SPAN,
+};
+
+/// Number of records replayed.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Replayed {
+	/// Number of spans.
+	pub spans: u64,
+	/// Number of events.
+	pub events: u64,
+}
+
+/// Read the trace from `input` and write its spans and events to `out` as JSON Lines, one
+/// object per record, see [`Record`].
+pub fn replay_as_json<R: Read, W: Write>(input: R, out: W) -> sc_tracing_format::Result<Replayed> {
+	let mut replayed = Replayed::default();
+	let records = TraceReader::new(input)?.inspect(|record| match record {
+		Ok(Record::Span(_)) => replayed.spans += 1,
+		Ok(Record::Event(_)) => replayed.events += 1,
+		Err(_) => (),
+	});
+	sc_tracing_format::write_json(records, out)?;
+	Ok(replayed)
+}
+
+/// Read the trace from `input` and hand its spans and events to `handler`, which is flushed
+/// at the end.
+///
+/// The names of the events are leaked, as the handlers expect static ones, which is fine as
+/// long as a handful of traces are replayed.
+pub fn replay<R: Read>(input: R, handler: &dyn TraceHandler) -> sc_tracing_format::Result<Replayed> {
+	let mut replayed = Replayed::default();
+	let mut names = FxHashMap::<String, &'static str>::default();
+	let reader = TraceReader::new(input)?;
+	for record in reader {
+		match record? {
+			Record::Span(span) => {
+				// Spans are given the start time they were recorded with, relatively to now.
+				let elapsed = SystemTime::now().duration_since(time(span.start_us)).unwrap_or_default();
+				let now = Instant::now();
+				handler.handle_span(SpanDatum {
+					id: Id::from_u64(span.id),
+					parent_id: span.parent_id.map(Id::from_u64),
+					name: span.name,
+					target: span.target,
+					level: level(span.level),
+					line: 0,
+					start_time: now.checked_sub(elapsed).unwrap_or(now),
+					start_wall_time: time(span.start_us),
+					overall_time: Duration::from_nanos(span.duration_ns),
+					values: values(span.fields),
+					metadata: &REPLAY_METADATA,
+				});
+				replayed.spans += 1;
+			},
+			Record::Event(event) => {
+				let name = match names.get(&event.name) {
+					Some(name) => *name,
+					None => {
+						let name: &'static str = Box::leak(event.name.clone().into_boxed_str());
+						names.insert(event.name, name);
+						name
+					},
+				};
+				handler.handle_event(TraceEvent {
+					name,
+					target: event.target,
+					level: level(event.level),
+					values: values(event.fields),
+					parent_id: event.parent_id.map(Id::from_u64),
+					time: time(event.time_us),
+				});
+				replayed.events += 1;
+			},
+		}
+	}
+	handler.flush();
+	Ok(replayed)
+}
+
+/// Hand the trace `summaries` to `handler`, which is flushed at the end.
+///
+/// The trace of each block is replayed as an `import_block` span, with the number and the hash of
+/// the block, and a child span for each target and name summed up, lasting the total time spent
+/// in them and recording their number in a `count` field. The increments of the counters are
+/// replayed as events of the `sp_tracing::COUNTER_TARGET` in the block span. As the summaries
+/// don't record when the blocks were imported, the spans and events are replayed as of now.
+pub fn replay_summaries(
+	summaries: impl IntoIterator<Item = BlockSummary>,
+	handler: &dyn TraceHandler,
+) -> Replayed {
+	let mut replayed = Replayed::default();
+	let mut next_id = 1;
+	let mut span = |parent_id: Option<Id>, target: String, name: String, duration_ns: u64, values: Values| {
+		let id = Id::from_u64(next_id);
+		next_id += 1;
+		let (now, wall_time) = (Instant::now(), SystemTime::now());
+		SpanDatum {
+			id,
+			parent_id,
+			name,
+			target,
+			level: tracing::Level::INFO,
+			line: 0,
+			start_time: now,
+			start_wall_time: wall_time,
+			overall_time: Duration::from_nanos(duration_ns),
+			values,
+			metadata: &REPLAY_METADATA,
+		}
+	};
+	for summary in summaries {
+		let mut values = Values::new();
+		values.u64_values.insert(BLOCK_NUMBER_KEY.to_owned(), summary.block_number);
+		values.string_values.insert(BLOCK_HASH_KEY.to_owned(), summary.block_hash);
+		let block = span(None, SUMMARY_TARGET.to_owned(), BLOCK_SPAN.to_owned(), summary.duration_ns, values);
+		for spans in summary.spans {
+			let mut values = Values::new();
+			values.u64_values.insert("count".to_owned(), spans.count);
+			handler.handle_span(span(Some(block.id.clone()), spans.target, spans.name, spans.duration_ns, values));
+			replayed.spans += 1;
+		}
+		for counter in summary.counters {
+			let mut values = Values::new();
+			values.string_values.insert("counter".to_owned(), counter.name);
+			values.string_values.insert("label".to_owned(), counter.label);
+			values.u64_values.insert("value".to_owned(), counter.value);
+			handler.handle_event(TraceEvent {
+				name: "counter",
+				target: sp_tracing::COUNTER_TARGET.to_owned(),
+				level: tracing::Level::TRACE,
+				values,
+				parent_id: Some(block.id.clone()),
+				time: SystemTime::now(),
+			});
+			replayed.events += 1;
+		}
+		// Like the recorded spans, the block span is handed over after its children.
+		handler.handle_span(block);
+		replayed.spans += 1;
+	}
+	handler.flush();
+	replayed
+}
+
+fn time(us: u64) -> SystemTime {
+	UNIX_EPOCH + Duration::from_micros(us)
+}
+
+fn level(level: Level) -> tracing::Level {
+	match level {
+		Level::Error => tracing::Level::ERROR,
+		Level::Warn => tracing::Level::WARN,
+		Level::Info => tracing::Level::INFO,
+		Level::Debug => tracing::Level::DEBUG,
+		Level::Trace => tracing::Level::TRACE,
+	}
+}
+
+fn values(fields: Vec<(String, Value)>) -> Values {
+	let mut values = Values::new();
+	for (name, value) in fields {
+		match value {
+			Value::Bool(v) => { values.bool_values.insert(name, v); },
+			Value::I64(v) => { values.i64_values.insert(name, v); },
+			Value::U64(v) => { values.u64_values.insert(name, v); },
+			Value::Str(v) => { values.string_values.insert(name, v); },
+		}
+	}
+	values
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::{fs::File, sync::Arc};
+	use parking_lot::Mutex;
+	use crate::{BinaryTraceHandler, ProfilingSubscriber};
+
+	#[derive(Default)]
+	struct Collect {
+		spans: Mutex<Vec<SpanDatum>>,
+		events: Mutex<Vec<TraceEvent>>,
+	}
+
+	impl TraceHandler for Arc<Collect> {
+		fn handle_span(&self, span: SpanDatum) {
+			self.spans.lock().push(span);
+		}
+
+		fn handle_event(&self, event: TraceEvent) {
+			self.events.lock().push(event);
+		}
+	}
+
+	#[test]
+	fn replays_binary_traces() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("trace.sctrace");
+		let handler = BinaryTraceHandler::new(&path);
+		let subscriber = ProfilingSubscriber::new_with_handler(Box::new(handler), "test_target");
+		let dispatch = tracing::Dispatch::new(subscriber);
+		tracing::dispatcher::with_default(&dispatch, || {
+			tracing::info_span!(target: "test_target", "import_block", block_number = 42u64).in_scope(|| {
+				tracing::debug_span!(target: "test_target", "execute_block").in_scope(|| {
+					tracing::warn!(target: "test_target", delta = -1i64);
+				});
+			});
+		});
+		drop(dispatch);
+
+		let collect = Arc::new(Collect::default());
+		let replayed = replay(File::open(&path).unwrap(), &collect).unwrap();
+		assert_eq!(replayed, Replayed { spans: 2, events: 1 });
+
+		let spans = collect.spans.lock();
+		assert_eq!(spans[0].name, "execute_block");
+		assert_eq!(spans[0].level, tracing::Level::DEBUG);
+		assert_eq!(spans[0].parent_id, Some(spans[1].id.clone()));
+		assert_eq!(spans[1].name, "import_block");
+		assert_eq!(spans[1].values.u64_values.get("block_number"), Some(&42));
+		let events = collect.events.lock();
+		assert_eq!(events[0].parent_id, Some(spans[0].id.clone()));
+		assert_eq!(events[0].values.i64_values.get("delta"), Some(&-1));
+	}
+
+	#[test]
+	fn replays_summaries() {
+		let summary = BlockSummary {
+			block_hash: "0x07".into(),
+			block_number: 7,
+			duration_ns: 2_000,
+			spans: vec![crate::SpanSummary {
+				target: "pallet_balances".into(),
+				name: "transfer".into(),
+				count: 3,
+				duration_ns: 1_500,
+			}],
+			counters: vec![crate::CounterSummary {
+				name: "storage_accesses".into(),
+				label: "Read".into(),
+				value: 12,
+			}],
+		};
+
+		let collect = Arc::new(Collect::default());
+		let replayed = replay_summaries(vec![summary.clone(), summary], &collect);
+		assert_eq!(replayed, Replayed { spans: 4, events: 2 });
+
+		let spans = collect.spans.lock();
+		assert_eq!(spans[0].name, "transfer");
+		assert_eq!(spans[0].overall_time, Duration::from_nanos(1_500));
+		assert_eq!(spans[0].values.u64_values.get("count"), Some(&3));
+		assert_eq!(spans[0].parent_id, Some(spans[1].id.clone()));
+		assert_eq!(spans[1].name, BLOCK_SPAN);
+		assert_eq!(spans[1].values.u64_values.get(BLOCK_NUMBER_KEY), Some(&7));
+		assert_eq!(spans[1].values.string_values.get(BLOCK_HASH_KEY).map(String::as_str), Some("0x07"));
+		assert_ne!(spans[1].id, spans[3].id);
+		let events = collect.events.lock();
+		assert_eq!(events[0].parent_id, Some(spans[1].id.clone()));
+		assert_eq!(events[0].values.u64_values.get("value"), Some(&12));
+	}
+
+	#[test]
+	fn rejects_other_files() {
+		let collect = Arc::new(Collect::default());
+		assert!(replay(&b"not a trace"[..], &collect).is_err());
+	}
+}
@@ -0,0 +1,477 @@
+// Copyright 2019-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Decoding of the SCALE encoded values of the traces, using the runtime metadata.
+//!
+//! Runtime traces record the values they don't know how to format, e.g. `call.encode()`, as
+//! byte arrays. The [`ScaleDecoder`] replaces the byte arrays of the fields whose type is known,
+//! account ids with their SS58 address, balances with their amount of tokens and calls with
+//! their name, e.g. `Balances.transfer`.
+//!
+//! The type of a field is inferred from the arguments of the calls of the runtime sharing its
+//! name, e.g. `dest` and `value` from `Balances.transfer`.
+//!
+//! The module errors of the failed dispatches, recorded as their `module_index` and
+//! `error_index`, are named from the errors of the modules, e.g. `Balances.InsufficientBalance`.
+//!
+//! The events deposited by the runtime are summarized with their name and the arguments whose
+//! type is known, e.g. `Balances.Transfer(5GrwvaEF.., 5FHneW46.., 1.5 DOT)`, see
+//! [`ScaleDecoder::decode_event`].
+
+use std::{collections::hash_map::Entry, convert::TryFrom};
+use codec::{Compact, Decode};
+use frame_metadata::{DecodeDifferent, RuntimeMetadata, RuntimeMetadataPrefixed};
+use rustc_hash::{FxHashMap, FxHashSet};
+use sp_core::{crypto::{AccountId32, Ss58AddressFormat, Ss58Codec}, hexdisplay::HexDisplay};
+
+use crate::{ERROR_INDEX_KEY, ERROR_KEY, MODULE_INDEX_KEY, Values};
+
+/// Type of a SCALE encoded field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FieldType {
+	AccountId,
+	Balance,
+	Call,
+}
+
+impl FieldType {
+	/// The type of a call argument, from its type name in the metadata.
+	fn from_type_name(ty: &str) -> Option<Self> {
+		if ty.ends_with("::Call") || ty.ends_with("::Call>") {
+			Some(FieldType::Call)
+		} else if ty.contains("Balance") {
+			Some(FieldType::Balance)
+		} else if ty.contains("AccountId") || ty.ends_with("StaticLookup>::Source") {
+			Some(FieldType::AccountId)
+		} else {
+			None
+		}
+	}
+}
+
+/// Decodes the SCALE encoded values of the trace fields whose type is known from the metadata.
+#[derive(Debug, Clone)]
+pub struct ScaleDecoder {
+	fields: FxHashMap<String, FieldType>,
+	calls: FxHashMap<(u8, u8), String>,
+	errors: FxHashMap<(u8, u8), String>,
+	events: FxHashMap<(u8, u8), (String, Vec<String>)>,
+	ss58_format: Ss58AddressFormat,
+	token_decimals: u32,
+	token_symbol: Option<String>,
+}
+
+impl ScaleDecoder {
+	/// Create a decoder from the encoded runtime metadata and the properties of the chain.
+	///
+	/// The `ss58Format`, `tokenDecimals` and `tokenSymbol` properties are used to format account
+	/// ids and balances, if set.
+	pub fn new(
+		metadata: &[u8],
+		properties: &serde_json::Map<String, serde_json::Value>,
+	) -> Result<Self, String> {
+		let metadata = RuntimeMetadataPrefixed::decode(&mut &metadata[..])
+			.map_err(|e| format!("Invalid runtime metadata: {}", e))?;
+		let modules = match metadata.1 {
+			RuntimeMetadata::V11(ref metadata) => decoded(&metadata.modules),
+			_ => return Err("Unsupported runtime metadata version".into()),
+		};
+
+		let mut fields = FxHashMap::default();
+		let mut ambiguous = FxHashSet::default();
+		let mut calls = FxHashMap::default();
+		let mut errors = FxHashMap::default();
+		// Unlike the calls, the errors are indexed by the index of their module in the runtime.
+		for (module_index, module) in modules.into_iter().flatten().enumerate() {
+			let name = decoded(&module.name).map(String::as_str).unwrap_or_default();
+			for (error_index, error) in decoded(&module.errors).into_iter().flatten().enumerate() {
+				let error = decoded(&error.name).map(String::as_str).unwrap_or_default();
+				errors.insert((module_index as u8, error_index as u8), format!("{}.{}", name, error));
+			}
+		}
+		// Likewise, only the modules with events are indexed in the outer `Event` enum.
+		let mut events = FxHashMap::default();
+		let modules_with_events = modules.into_iter().flatten()
+			.filter_map(|module| module.event.as_ref().map(|events| (&module.name, events)));
+		for (module_index, (module, module_events)) in modules_with_events.enumerate() {
+			let module = decoded(module).map(String::as_str).unwrap_or_default();
+			for (event_index, event) in decoded(module_events).into_iter().flatten().enumerate() {
+				let name = decoded(&event.name).map(String::as_str).unwrap_or_default();
+				let arguments = decoded(&event.arguments).cloned().unwrap_or_default();
+				events.insert(
+					(module_index as u8, event_index as u8),
+					(format!("{}.{}", module, name), arguments),
+				);
+			}
+		}
+		// Only the modules with calls are indexed in the outer `Call` enum.
+		let modules = modules.into_iter().flatten()
+			.filter_map(|module| module.calls.as_ref().map(|calls| (&module.name, calls)));
+		for (module_index, (module, module_calls)) in modules.enumerate() {
+			let module = decoded(module).map(String::as_str).unwrap_or_default();
+			for (call_index, call) in decoded(module_calls).into_iter().flatten().enumerate() {
+				let name = decoded(&call.name).map(String::as_str).unwrap_or_default();
+				calls.insert((module_index as u8, call_index as u8), format!("{}.{}", module, name));
+
+				for argument in decoded(&call.arguments).into_iter().flatten() {
+					let name = decoded(&argument.name).cloned().unwrap_or_default();
+					let ty = decoded(&argument.ty).and_then(|ty| FieldType::from_type_name(ty));
+					match fields.entry(name) {
+						Entry::Vacant(entry) => { entry.insert(ty); },
+						Entry::Occupied(entry) => if *entry.get() != ty {
+							ambiguous.insert(entry.key().clone());
+						},
+					}
+				}
+			}
+		}
+		// Names used for arguments of different types are not decoded.
+		let fields = fields.into_iter()
+			.filter(|(name, _)| !ambiguous.contains(name))
+			.filter_map(|(name, ty)| Some((name, ty?)))
+			.collect();
+
+		let ss58_format = properties.get("ss58Format")
+			.and_then(|format| format.as_u64())
+			.map(|format| {
+				let format = format as u8;
+				Ss58AddressFormat::try_from(format).unwrap_or(Ss58AddressFormat::Custom(format))
+			})
+			.unwrap_or_default();
+		Ok(ScaleDecoder {
+			fields,
+			calls,
+			errors,
+			events,
+			ss58_format,
+			token_decimals: properties.get("tokenDecimals")
+				.and_then(|decimals| decimals.as_u64())
+				.unwrap_or(0) as u32,
+			token_symbol: properties.get("tokenSymbol")
+				.and_then(|symbol| symbol.as_str())
+				.map(Into::into),
+		})
+	}
+
+	/// Replace the SCALE encoded values of the known fields by their human readable form.
+	///
+	/// The values that don't decode as the type of their field are left unchanged.
+	pub fn decode(&self, values: &mut Values) {
+		let error_index = (
+			values.u64_values.get(MODULE_INDEX_KEY),
+			values.u64_values.get(ERROR_INDEX_KEY),
+		);
+		if let (Some(module_index), Some(error_index)) = error_index {
+			if let Some(error) = self.errors.get(&(*module_index as u8, *error_index as u8)) {
+				values.string_values.insert(ERROR_KEY.to_owned(), error.clone());
+			}
+		}
+		for (name, value) in values.string_values.iter_mut() {
+			let decoded = self.fields.get(name)
+				.and_then(|ty| Some((ty, parse_bytes(value)?)))
+				.and_then(|(ty, bytes)| self.decode_value(*ty, &bytes));
+			if let Some(decoded) = decoded {
+				*value = decoded;
+			}
+		}
+	}
+
+	/// Summary of a runtime event recorded as SCALE encoded bytes, see
+	/// `sp_tracing::RUNTIME_EVENT_TARGET`.
+	///
+	/// The arguments are decoded until the first one whose type is unknown, the next ones being
+	/// elided as `..`, e.g. `System.ExtrinsicSuccess(..)`.
+	pub fn decode_event(&self, event: &str) -> Option<String> {
+		let bytes = parse_bytes(event)?;
+		let (name, arguments) = match &bytes[..] {
+			[module, event, ..] => self.events.get(&(*module, *event))?,
+			_ => return None,
+		};
+		if arguments.is_empty() {
+			return Some(name.clone());
+		}
+		let mut input = &bytes[2..];
+		let mut decoded = Vec::with_capacity(arguments.len());
+		for ty in arguments {
+			match self.decode_argument(ty, &mut input) {
+				Some(argument) => decoded.push(argument),
+				None => {
+					decoded.push("..".to_owned());
+					break;
+				},
+			}
+		}
+		Some(format!("{}({})", name, decoded.join(", ")))
+	}
+
+	/// Decode an event argument from its type name, the arguments of the events being encoded
+	/// as is, unlike the compact balances of the calls.
+	fn decode_argument(&self, ty: &str, input: &mut &[u8]) -> Option<String> {
+		let ty = ty.rsplit("::").next().unwrap_or(ty);
+		Some(match ty {
+			"bool" => bool::decode(input).ok()?.to_string(),
+			"u8" => u8::decode(input).ok()?.to_string(),
+			"u16" => u16::decode(input).ok()?.to_string(),
+			"u32" => u32::decode(input).ok()?.to_string(),
+			"u64" => u64::decode(input).ok()?.to_string(),
+			"u128" => u128::decode(input).ok()?.to_string(),
+			"Hash" => format!("0x{}", HexDisplay::from(&<[u8; 32]>::decode(input).ok()?)),
+			ty if ty.contains("Balance") => self.format_balance(u128::decode(input).ok()?),
+			ty if ty.contains("AccountId") => AccountId32::decode(input).ok()?
+				.to_ss58check_with_version(self.ss58_format),
+			_ => return None,
+		})
+	}
+
+	fn decode_value(&self, ty: FieldType, bytes: &[u8]) -> Option<String> {
+		match ty {
+			FieldType::AccountId => {
+				// Lookup sources of the indices pallet prefix account ids with `0xff`.
+				let bytes = match bytes.len() {
+					33 if bytes[0] == 0xff => &bytes[1..],
+					_ => bytes,
+				};
+				let account = <[u8; 32]>::try_from(bytes).ok()?;
+				Some(AccountId32::from(account).to_ss58check_with_version(self.ss58_format))
+			},
+			FieldType::Balance => {
+				let amount = match bytes.len() {
+					16 => u128::decode(&mut &bytes[..]).ok()?,
+					_ => <Compact<u128>>::decode(&mut &bytes[..]).ok()?.0,
+				};
+				Some(self.format_balance(amount))
+			},
+			FieldType::Call => match bytes {
+				[module, call, ..] => self.calls.get(&(*module, *call)).cloned(),
+				_ => None,
+			},
+		}
+	}
+
+	fn format_balance(&self, amount: u128) -> String {
+		let unit = 10u128.checked_pow(self.token_decimals);
+		let mut formatted = match unit {
+			Some(unit) if unit > 1 && amount % unit != 0 => {
+				let fraction = format!("{:0width$}", amount % unit, width = self.token_decimals as usize);
+				format!("{}.{}", amount / unit, fraction.trim_end_matches('0'))
+			},
+			Some(unit) => (amount / unit).to_string(),
+			None => amount.to_string(),
+		};
+		if let Some(symbol) = &self.token_symbol {
+			formatted.push(' ');
+			formatted.push_str(symbol);
+		}
+		formatted
+	}
+}
+
+fn decoded<B, O>(value: &DecodeDifferent<B, O>) -> Option<&O> {
+	match value {
+		DecodeDifferent::Decoded(value) => Some(value),
+		DecodeDifferent::Encode(_) => None,
+	}
+}
+
+/// Parse bytes recorded either as hex, `0x0102`, or with `Debug`, `[1, 2]`.
+fn parse_bytes(value: &str) -> Option<Vec<u8>> {
+	if let Some(hex) = value.strip_prefix("0x") {
+		(0..hex.len()).step_by(2)
+			.map(|i| hex.get(i..i + 2).and_then(|byte| u8::from_str_radix(byte, 16).ok()))
+			.collect()
+	} else if let Some(list) = value.strip_prefix('[').and_then(|list| list.strip_suffix(']')) {
+		list.split(',')
+			.filter(|byte| !byte.trim().is_empty())
+			.map(|byte| byte.trim().parse().ok())
+			.collect()
+	} else {
+		None
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use codec::Encode;
+	use frame_metadata::{
+		ErrorMetadata, EventMetadata, ExtrinsicMetadata, FunctionArgumentMetadata, FunctionMetadata, ModuleMetadata,
+		RuntimeMetadataV11,
+	};
+
+	fn call(name: &str, arguments: &[(&str, &str)]) -> FunctionMetadata {
+		FunctionMetadata {
+			name: DecodeDifferent::Decoded(name.into()),
+			arguments: DecodeDifferent::Decoded(arguments.iter()
+				.map(|(name, ty)| FunctionArgumentMetadata {
+					name: DecodeDifferent::Decoded((*name).into()),
+					ty: DecodeDifferent::Decoded((*ty).into()),
+				})
+				.collect()),
+			documentation: DecodeDifferent::Decoded(Vec::new()),
+		}
+	}
+
+	fn module(name: &str, calls: Option<Vec<FunctionMetadata>>) -> ModuleMetadata {
+		ModuleMetadata {
+			name: DecodeDifferent::Decoded(name.into()),
+			storage: None,
+			calls: calls.map(DecodeDifferent::Decoded),
+			event: None,
+			constants: DecodeDifferent::Decoded(Vec::new()),
+			errors: DecodeDifferent::Decoded(Vec::new()),
+		}
+	}
+
+	fn with_errors(module: ModuleMetadata, errors: &[&str]) -> ModuleMetadata {
+		ModuleMetadata {
+			errors: DecodeDifferent::Decoded(errors.iter()
+				.map(|name| ErrorMetadata {
+					name: DecodeDifferent::Decoded((*name).into()),
+					documentation: DecodeDifferent::Decoded(Vec::new()),
+				})
+				.collect()),
+			..module
+		}
+	}
+
+	fn with_events(module: ModuleMetadata, events: &[(&str, &[&str])]) -> ModuleMetadata {
+		ModuleMetadata {
+			event: Some(DecodeDifferent::Decoded(events.iter()
+				.map(|(name, arguments)| EventMetadata {
+					name: DecodeDifferent::Decoded((*name).into()),
+					arguments: DecodeDifferent::Decoded(arguments.iter().map(|ty| (*ty).into()).collect()),
+					documentation: DecodeDifferent::Decoded(Vec::new()),
+				})
+				.collect())),
+			..module
+		}
+	}
+
+	fn decoder() -> ScaleDecoder {
+		let metadata: RuntimeMetadataPrefixed = RuntimeMetadataV11 {
+			modules: DecodeDifferent::Decoded(vec![
+				module("Timestamp", Some(vec![call("set", &[("now", "Compact<T::Moment>")])])),
+				module("Authorship", None),
+				with_events(with_errors(module("Balances", Some(vec![
+					call("transfer", &[
+						("dest", "<T::Lookup as StaticLookup>::Source"),
+						("value", "Compact<T::Balance>"),
+					]),
+					call("set_balance", &[
+						("who", "<T::Lookup as StaticLookup>::Source"),
+						("new_free", "Compact<T::Balance>"),
+						("new_reserved", "Compact<T::Balance>"),
+					]),
+				])), &["VestingBalance", "InsufficientBalance"]), &[
+					("Endowed", &["AccountId", "Balance"]),
+					("Transfer", &["AccountId", "AccountId", "Balance"]),
+				]),
+				with_events(
+					module("Sudo", Some(vec![call("sudo", &[("call", "Box<<T as Trait>::Call>")])])),
+					&[("Sudid", &["DispatchResult"]), ("KeyChanged", &["AccountId"]), ("Unlocked", &[])],
+				),
+				module("Democracy", Some(vec![call("propose", &[("value", "u32")])])),
+			]),
+			extrinsic: ExtrinsicMetadata { version: 4, signed_extensions: Vec::new() },
+		}.into();
+		let properties = serde_json::json!({
+			"ss58Format": 42,
+			"tokenDecimals": 12,
+			"tokenSymbol": "DOT",
+		});
+		ScaleDecoder::new(&metadata.encode(), properties.as_object().unwrap()).unwrap()
+	}
+
+	#[test]
+	fn decodes_known_fields() {
+		let account = [1u8; 32];
+		let mut values = Values::new();
+		values.string_values.insert("who".into(), format!("{:?}", account.to_vec()));
+		values.string_values.insert(
+			"new_free".into(),
+			format!("0x{}", HexDisplay::from(&Compact(1_500_000_000_000u128).encode())),
+		);
+		values.string_values.insert(
+			"new_reserved".into(),
+			format!("0x{}", HexDisplay::from(&2_000_000_000_000u128.encode())),
+		);
+		values.string_values.insert("call".into(), format!("{:?}", vec![1u8, 0, 255, 1, 2]));
+		values.string_values.insert("other".into(), "[1, 2]".into());
+		decoder().decode(&mut values);
+
+		let expected = AccountId32::from(account)
+			.to_ss58check_with_version(Ss58AddressFormat::SubstrateAccount);
+		assert_eq!(values.string_values["who"], expected);
+		assert_eq!(values.string_values["new_free"], "1.5 DOT");
+		assert_eq!(values.string_values["new_reserved"], "2 DOT");
+		assert_eq!(values.string_values["call"], "Balances.transfer");
+		assert_eq!(values.string_values["other"], "[1, 2]");
+	}
+
+	#[test]
+	fn names_module_errors() {
+		let mut values = Values::new();
+		values.u64_values.insert(MODULE_INDEX_KEY.into(), 2);
+		values.u64_values.insert(ERROR_INDEX_KEY.into(), 1);
+		values.string_values.insert(ERROR_KEY.into(), "InsufficientBalance".into());
+		decoder().decode(&mut values);
+		assert_eq!(values.string_values[ERROR_KEY], "Balances.InsufficientBalance");
+
+		// Authorship has no errors.
+		values.u64_values.insert(MODULE_INDEX_KEY.into(), 1);
+		values.string_values.insert(ERROR_KEY.into(), "Other".into());
+		decoder().decode(&mut values);
+		assert_eq!(values.string_values[ERROR_KEY], "Other");
+	}
+
+	#[test]
+	fn leaves_ambiguous_and_invalid_fields_unchanged() {
+		let mut values = Values::new();
+		values.string_values.insert("value".into(), "0x0a".into());
+		values.string_values.insert("dest".into(), "[1, 2]".into());
+		values.string_values.insert("now".into(), "0x0a".into());
+		let original = values.clone();
+		decoder().decode(&mut values);
+
+		assert_eq!(values.string_values, original.string_values);
+	}
+
+	#[test]
+	fn summarizes_events() {
+		let (from, to) = (AccountId32::from([1u8; 32]), AccountId32::from([2u8; 32]));
+		let ss58 = |account: &AccountId32|
+			account.to_ss58check_with_version(Ss58AddressFormat::SubstrateAccount);
+		let decoder = decoder();
+
+		let transfer = (0u8, 1u8, from.clone(), to.clone(), 1_500_000_000_000u128).encode();
+		assert_eq!(
+			decoder.decode_event(&format!("{:?}", transfer)).unwrap(),
+			format!("Balances.Transfer({}, {}, 1.5 DOT)", ss58(&from), ss58(&to)),
+		);
+		let sudid = (1u8, 0u8, Ok::<(), ()>(())).encode();
+		assert_eq!(decoder.decode_event(&format!("{:?}", sudid)).unwrap(), "Sudo.Sudid(..)");
+		let unlocked = (1u8, 2u8).encode();
+		assert_eq!(decoder.decode_event(&format!("{:?}", unlocked)).unwrap(), "Sudo.Unlocked");
+
+		// Unknown events and truncated arguments.
+		assert_eq!(decoder.decode_event(&format!("{:?}", vec![2u8, 0])), None);
+		let endowed = (0u8, 0u8, from.clone()).encode();
+		assert_eq!(
+			decoder.decode_event(&format!("{:?}", endowed)).unwrap(),
+			format!("Balances.Endowed({}, ..)", ss58(&from)),
+		);
+	}
+}
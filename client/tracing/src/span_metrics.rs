@@ -0,0 +1,119 @@
+// Copyright 2019-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Counts and durations of the closed spans, by target and name, for export as metrics.
+//!
+//! The targets and names of the spans come from the node and the runtime, so the number of
+//! series is capped: past [`SpanMetrics::new`]'s `max_series` distinct targets and names, the
+//! spans are accounted under the [`OTHER_SERIES`] target and name, and counted as overflowing.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use parking_lot::Mutex;
+use rustc_hash::FxHashMap;
+
+/// Target and name of the series the spans past the cap of the series are accounted under.
+pub const OTHER_SERIES: &str = "other";
+
+#[derive(Default, Clone, Copy)]
+struct Series {
+	count: u64,
+	time: Duration,
+}
+
+struct Inner {
+	max_series: usize,
+	series: Mutex<FxHashMap<(String, String), Series>>,
+	overflow: AtomicU64,
+}
+
+/// The counts and durations of the closed spans, by target and name.
+#[derive(Clone)]
+pub struct SpanMetrics {
+	inner: Arc<Inner>,
+}
+
+impl SpanMetrics {
+	/// Metrics of at most `max_series` targets and names, the [`OTHER_SERIES`] included.
+	pub fn new(max_series: usize) -> Self {
+		SpanMetrics {
+			inner: Arc::new(Inner {
+				max_series: max_series.max(1),
+				series: Default::default(),
+				overflow: AtomicU64::new(0),
+			}),
+		}
+	}
+
+	/// Account a span of `target` and `name` that lasted `time`.
+	pub(crate) fn record(&self, target: &str, name: &str, time: Duration) {
+		let mut series = self.inner.series.lock();
+		let key = (target.to_owned(), name.to_owned());
+		// One series is kept for the spans past the cap.
+		let key = if series.contains_key(&key) || series.len() + 1 < self.inner.max_series {
+			key
+		} else {
+			self.inner.overflow.fetch_add(1, Ordering::Relaxed);
+			(OTHER_SERIES.to_owned(), OTHER_SERIES.to_owned())
+		};
+		let series = series.entry(key).or_default();
+		series.count = series.count.saturating_add(1);
+		series.time += time;
+	}
+
+	/// Calls `f` with the target, name, count and total duration of every series.
+	pub fn for_each(&self, mut f: impl FnMut(&str, &str, u64, Duration)) {
+		for ((target, name), series) in self.inner.series.lock().iter() {
+			f(target, name, series.count, series.time);
+		}
+	}
+
+	/// The number of spans accounted under the [`OTHER_SERIES`] for want of a series of their
+	/// own.
+	pub fn overflow(&self) -> u64 {
+		self.inner.overflow.load(Ordering::Relaxed)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn series_past_the_cap_are_aggregated() {
+		let metrics = SpanMetrics::new(3);
+		let ms = Duration::from_millis;
+		metrics.record("runtime", "a", ms(1));
+		metrics.record("runtime", "b", ms(2));
+		metrics.record("runtime", "c", ms(3));
+		metrics.record("runtime", "a", ms(4));
+		metrics.record("runtime", "d", ms(5));
+
+		let mut series = Vec::new();
+		metrics.for_each(|target, name, count, time| {
+			series.push((target.to_owned(), name.to_owned(), count, time))
+		});
+		series.sort();
+		assert_eq!(series, vec![
+			(OTHER_SERIES.to_owned(), OTHER_SERIES.to_owned(), 2, ms(8)),
+			("runtime".to_owned(), "a".to_owned(), 2, ms(5)),
+			("runtime".to_owned(), "b".to_owned(), 1, ms(2)),
+		]);
+		assert_eq!(metrics.overflow(), 2);
+	}
+}
@@ -0,0 +1,273 @@
+// Copyright 2019-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The open spans of a `ProfilingSubscriber`, in a lock-free table.
+//!
+//! The runtime instances execute the blocks and the RPC calls concurrently, each on the thread of
+//! its call, and every host call of their spans goes through the subscriber. The spans are kept in
+//! a fixed number of slots, claimed with a compare-and-swap of their id, so that opening, looking
+//! up and closing a span doesn't lock the table. Only the span itself is locked while it is
+//! updated, which only contends with the threads updating the same span.
+//!
+//! A span is looked for in at most [`MAX_PROBES`] slots from the slot of its id. A closed span
+//! leaves a tombstone, so that the slots are never empty again once claimed and the spans after it
+//! are still found. When no slot is free, the span is not kept, and is neither recorded nor handled.
+
+use std::{sync::atomic::{AtomicU64, Ordering}, time::Duration};
+
+use parking_lot::Mutex;
+use tracing::span::Id;
+
+use crate::SpanDatum;
+
+/// Number of bits of the index of the slots.
+const SLOT_BITS: u32 = 14;
+const SLOTS: usize = 1 << SLOT_BITS;
+/// Number of slots a span is looked for in, from the slot of its id.
+const MAX_PROBES: usize = 64;
+
+/// Id of the slots never claimed.
+const EMPTY: u64 = 0;
+/// Id of the slots of the closed spans.
+const TOMBSTONE: u64 = u64::max_value();
+
+/// Maximum time `take_all` waits for a span, e.g. locked by a thread that panicked.
+const TAKE_TIMEOUT: Duration = Duration::from_millis(100);
+
+#[derive(Default)]
+struct Slot {
+	id: AtomicU64,
+	span: Mutex<Option<Box<SpanDatum>>>,
+}
+
+/// The open spans, by id.
+pub(crate) struct Spans {
+	next_id: AtomicU64,
+	slots: Vec<Slot>,
+	dropped: AtomicU64,
+}
+
+impl Default for Spans {
+	fn default() -> Self {
+		Spans {
+			next_id: AtomicU64::new(1),
+			slots: (0..SLOTS).map(|_| Default::default()).collect(),
+			dropped: AtomicU64::new(0),
+		}
+	}
+}
+
+impl Spans {
+	/// A new id. The ids are not re-used and are in the order they are created in, see `order`.
+	pub fn next_id(&self) -> Id {
+		Id::from_u64(self.next_id.fetch_add(1, Ordering::Relaxed))
+	}
+
+	/// The order the span `id` was created in.
+	pub fn order(id: &Id) -> u64 {
+		id.into_u64()
+	}
+
+	/// Keep `span_datum` in a free slot, if any.
+	pub fn insert(&self, span_datum: SpanDatum) {
+		let id = span_datum.id.into_u64();
+		for slot in self.probe(id) {
+			let claimed = slot.id.load(Ordering::Acquire);
+			if (claimed == EMPTY || claimed == TOMBSTONE) &&
+				slot.id.compare_exchange(claimed, id, Ordering::AcqRel, Ordering::Relaxed).is_ok()
+			{
+				*slot.span.lock() = Some(Box::new(span_datum));
+				return;
+			}
+		}
+		if self.dropped.fetch_add(1, Ordering::Relaxed) == 0 {
+			log::warn!(target: "tracing", "Too many open spans, dropping the new ones");
+		}
+	}
+
+	/// Calls `f` with the span `id`, if open.
+	pub fn with<R>(&self, id: &Id, f: impl FnOnce(&mut SpanDatum) -> R) -> Option<R> {
+		let mut span = self.find(id)?.span.lock();
+		span.as_mut().filter(|span_datum| span_datum.id == *id).map(|span_datum| f(span_datum))
+	}
+
+	/// Like `with`, but `None` if the span is locked, e.g. by the thread calling it.
+	pub fn try_with<R>(&self, id: &Id, f: impl FnOnce(&mut SpanDatum) -> R) -> Option<R> {
+		let mut span = self.find(id)?.span.try_lock()?;
+		span.as_mut().filter(|span_datum| span_datum.id == *id).map(|span_datum| f(span_datum))
+	}
+
+	pub fn remove(&self, id: &Id) -> Option<SpanDatum> {
+		let slot = self.find(id)?;
+		let mut span = slot.span.lock();
+		if span.as_ref().map_or(true, |span_datum| span_datum.id != *id) {
+			return None;
+		}
+		let span_datum = span.take();
+		slot.id.store(TOMBSTONE, Ordering::Release);
+		span_datum.map(|span_datum| *span_datum)
+	}
+
+	/// Remove all the spans, in the order they were created in.
+	///
+	/// The spans still locked after [`TAKE_TIMEOUT`], e.g. by the thread calling it or by a thread
+	/// that panicked while holding it, are left open.
+	pub fn take_all(&self) -> Vec<SpanDatum> {
+		let mut spans = Vec::new();
+		for slot in self.claimed() {
+			let mut span = match slot.span.try_lock_for(TAKE_TIMEOUT) {
+				Some(span) => span,
+				None => {
+					log::warn!(target: "tracing", "Unable to take a locked open span");
+					continue;
+				},
+			};
+			if let Some(span_datum) = span.take() {
+				slot.id.store(TOMBSTONE, Ordering::Release);
+				spans.push(*span_datum);
+			}
+		}
+		spans.sort_by_key(|span_datum| Self::order(&span_datum.id));
+		spans
+	}
+
+	/// A copy of all the spans, in the order they were created in.
+	pub fn snapshot(&self) -> Vec<SpanDatum> {
+		let mut spans = self.claimed()
+			.filter_map(|slot| slot.span.lock().as_deref().cloned())
+			.collect::<Vec<_>>();
+		spans.sort_by_key(|span_datum| Self::order(&span_datum.id));
+		spans
+	}
+
+	/// The slot of the span `id`, if open.
+	fn find(&self, id: &Id) -> Option<&Slot> {
+		let id = id.into_u64();
+		for slot in self.probe(id) {
+			match slot.id.load(Ordering::Acquire) {
+				claimed if claimed == id => return Some(slot),
+				EMPTY => return None,
+				_ => {},
+			}
+		}
+		None
+	}
+
+	/// The slots the span `id` may be in, starting with the slot of its id.
+	fn probe(&self, id: u64) -> impl Iterator<Item = &Slot> {
+		// Fibonacci hashing, spreading the consecutive ids over the slots.
+		let start = (id.wrapping_mul(0x9E37_79B9_7F4A_7C15) >> (64 - SLOT_BITS)) as usize;
+		(0..MAX_PROBES).map(move |i| &self.slots[(start + i) % SLOTS])
+	}
+
+	/// The slots of the open spans.
+	fn claimed(&self) -> impl Iterator<Item = &Slot> {
+		self.slots.iter().filter(|slot| {
+			let claimed = slot.id.load(Ordering::Acquire);
+			claimed != EMPTY && claimed != TOMBSTONE
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::time::{Instant, SystemTime};
+	use tracing_core::{Callsite, Interest, Kind, Metadata};
+
+	struct TestCallsite;
+
+	impl Callsite for TestCallsite {
+		fn set_interest(&self, _: Interest) {}
+
+		fn metadata(&self) -> &Metadata<'_> {
+			&METADATA
+		}
+	}
+
+	static CALLSITE: TestCallsite = TestCallsite;
+
+	static METADATA: Metadata<'static> = tracing_core::metadata! {
+		name: "span",
+		target: "spans",
+		level: tracing::Level::INFO,
+		fields: &[],
+		callsite: &CALLSITE,
+		kind: Kind::SPAN,
+	};
+
+	fn span_datum(id: &Id) -> SpanDatum {
+		SpanDatum {
+			id: id.clone(),
+			parent_id: None,
+			name: "span".into(),
+			target: "spans".into(),
+			level: tracing::Level::INFO,
+			line: 0,
+			start_time: Instant::now(),
+			start_wall_time: SystemTime::now(),
+			overall_time: Duration::default(),
+			values: Default::default(),
+			metadata: &METADATA,
+		}
+	}
+
+	#[test]
+	fn spans_are_found_past_the_closed_ones() {
+		let spans = Spans::default();
+		let ids: Vec<_> = (0..SLOTS + 10).map(|_| spans.next_id()).collect();
+		assert!(ids.windows(2).all(|ids| Spans::order(&ids[0]) < Spans::order(&ids[1])));
+		for id in &ids {
+			spans.insert(span_datum(id));
+			assert_eq!(spans.with(id, |span_datum| span_datum.id.clone()).as_ref(), Some(id));
+			// Keep every tenth span open.
+			if id.into_u64() % 10 != 0 {
+				assert!(spans.remove(id).is_some());
+				assert!(spans.with(id, |_| ()).is_none());
+			}
+		}
+		let open: Vec<_> = ids.iter().filter(|id| id.into_u64() % 10 == 0).cloned().collect();
+		assert!(open.iter().all(|id| spans.with(id, |_| ()).is_some()));
+		let taken: Vec<_> = spans.take_all().into_iter().map(|span_datum| span_datum.id).collect();
+		assert_eq!(taken, open);
+		assert!(spans.snapshot().is_empty());
+	}
+
+	#[test]
+	fn spans_are_dropped_when_no_slot_is_free() {
+		let spans = Spans::default();
+		let ids: Vec<_> = (0..SLOTS + 1).map(|_| spans.next_id()).collect();
+		for id in &ids {
+			spans.insert(span_datum(id));
+		}
+		assert!(spans.dropped.load(Ordering::Relaxed) > 0);
+		let kept = spans.snapshot().len() as u64;
+		assert_eq!(kept + spans.dropped.load(Ordering::Relaxed), ids.len() as u64);
+	}
+
+	#[test]
+	fn take_all_skips_the_locked_spans() {
+		let spans = Spans::default();
+		let (locked, other) = (spans.next_id(), spans.next_id());
+		spans.insert(span_datum(&locked));
+		spans.insert(span_datum(&other));
+		let slot = spans.find(&locked).unwrap();
+		let _locked = slot.span.lock();
+		let taken: Vec<_> = spans.take_all().into_iter().map(|span_datum| span_datum.id).collect();
+		assert_eq!(taken, vec![other]);
+		assert!(spans.try_with(&locked, |_| ()).is_none());
+	}
+}
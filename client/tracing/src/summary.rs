@@ -0,0 +1,174 @@
+// Copyright 2019-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Summaries of the traces of the imported blocks.
+//!
+//! The spans and counters recorded under an `import_block` span are summed up until it closes,
+//! then the summary of the block is handed to a sink, e.g. to be stored in the database.
+
+use std::cmp::Reverse;
+use codec::{Decode, Encode};
+use parking_lot::Mutex;
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::{BLOCK_NUMBER_KEY, SpanDatum, Values};
+
+/// Field recording the hash of the block a trace belongs to.
+pub const BLOCK_HASH_KEY: &str = "block_hash";
+
+/// Name of the span the import of a block is traced under.
+pub(crate) const BLOCK_SPAN: &str = "import_block";
+
+/// Summary of the trace of the import of a block.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockSummary {
+	/// Hash of the block, as recorded by the `import_block` span.
+	pub block_hash: String,
+	/// Number of the block.
+	pub block_number: u64,
+	/// Time spent importing the block, in nanoseconds.
+	pub duration_ns: u64,
+	/// The spans most time was spent in, by target and name, slowest first.
+	pub spans: Vec<SpanSummary>,
+	/// The increments of the counters, e.g. `storage_accesses` and `block_weight`.
+	pub counters: Vec<CounterSummary>,
+}
+
+/// Time spent in the spans of a target and name.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpanSummary {
+	/// Target of the spans.
+	pub target: String,
+	/// Name of the spans.
+	pub name: String,
+	/// Number of spans.
+	pub count: u64,
+	/// Total time spent in the spans, in nanoseconds.
+	pub duration_ns: u64,
+}
+
+/// Sum of the increments of a counter and label, see `sp_tracing::counter`.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CounterSummary {
+	/// Name of the counter.
+	pub name: String,
+	/// Label of the counter.
+	pub label: String,
+	/// Sum of the increments.
+	pub value: u64,
+}
+
+/// Record the summary collected from the node `peer` as an `INFO` event of the
+/// `trace_collection` target, for the receiver to export it along with the traces of this node.
+pub fn record_collected_summary(peer: &str, summary: &BlockSummary) {
+	let spans = serde_json::to_string(&summary.spans).unwrap_or_default();
+	let counters = serde_json::to_string(&summary.counters).unwrap_or_default();
+	tracing::info!(
+		target: "trace_collection",
+		peer,
+		block_hash = summary.block_hash.as_str(),
+		block_number = summary.block_number,
+		duration_ns = summary.duration_ns,
+		spans = spans.as_str(),
+		counters = counters.as_str(),
+		"Collected the trace summary of #{} from {}",
+		summary.block_number,
+		peer,
+	);
+}
+
+/// Counter of the storage accesses of the blocks.
+const STORAGE_ACCESSES_COUNTER: &str = "storage_accesses";
+
+#[derive(Default)]
+struct Pending {
+	spans: FxHashMap<(String, String), (u64, u64)>,
+	counters: FxHashMap<(String, String), u64>,
+}
+
+/// Sums up the traces of the imported blocks.
+pub(crate) struct BlockSummaries {
+	max_spans: usize,
+	sink: Box<dyn Fn(BlockSummary) + Send + Sync>,
+	pending: Mutex<FxHashMap<u64, Pending>>,
+}
+
+impl BlockSummaries {
+	pub fn new(max_spans: usize, sink: Box<dyn Fn(BlockSummary) + Send + Sync>) -> Self {
+		BlockSummaries { max_spans, sink, pending: Default::default() }
+	}
+
+	/// Whether the span is the root of the trace of a block.
+	pub fn is_block_span(name: &str, values: &Values) -> bool {
+		name == BLOCK_SPAN && values.u64_values.contains_key(BLOCK_NUMBER_KEY)
+	}
+
+	/// Add a span of the trace of the block span `block`.
+	pub fn record_span(&self, block: u64, span_datum: &SpanDatum) {
+		let mut pending = self.pending.lock();
+		let key = (span_datum.target.clone(), span_datum.name.clone());
+		let (count, duration_ns) = pending.entry(block).or_default().spans.entry(key).or_default();
+		*count += 1;
+		*duration_ns = duration_ns.saturating_add(span_datum.overall_time.as_nanos() as u64);
+	}
+
+	/// Add the increment of a counter to the trace of the block span `block`.
+	pub fn record_counter(&self, block: u64, values: &Values) {
+		let name = values.string_values.get("counter");
+		let label = values.string_values.get("label");
+		if let (Some(name), Some(label), Some(value)) = (name, label, values.u64_values.get("value")) {
+			let mut pending = self.pending.lock();
+			let counter = pending.entry(block).or_default()
+				.counters.entry((name.clone(), label.clone())).or_default();
+			*counter = counter.saturating_add(*value);
+		}
+	}
+
+	/// Count a storage access, an event of `sp_tracing::STORAGE_ACCESS_TARGET`, of the trace of the
+	/// block span `block`, as the `Read` or `Write` label of the `storage_accesses` counter.
+	pub fn record_storage_access(&self, block: u64, values: &Values) {
+		let label = if values.bool_values.get("write") == Some(&true) { "Write" } else { "Read" };
+		let mut pending = self.pending.lock();
+		let counter = pending.entry(block).or_default()
+			.counters.entry((STORAGE_ACCESSES_COUNTER.to_owned(), label.to_owned())).or_default();
+		*counter = counter.saturating_add(1);
+	}
+
+	/// Close the trace of a block and hand its summary to the sink.
+	pub fn finish(&self, span_datum: &SpanDatum) {
+		let pending = self.pending.lock().remove(&span_datum.id.into_u64()).unwrap_or_default();
+		let mut spans = pending.spans.into_iter()
+			.map(|((target, name), (count, duration_ns))| SpanSummary { target, name, count, duration_ns })
+			.collect::<Vec<_>>();
+		spans.sort_by_key(|span| Reverse(span.duration_ns));
+		spans.truncate(self.max_spans);
+		let mut counters = pending.counters.into_iter()
+			.map(|((name, label), value)| CounterSummary { name, label, value })
+			.collect::<Vec<_>>();
+		counters.sort_by(|a, b| (&a.name, &a.label).cmp(&(&b.name, &b.label)));
+		(self.sink)(BlockSummary {
+			block_hash: span_datum.values.string_values.get(BLOCK_HASH_KEY).cloned().unwrap_or_default(),
+			block_number: span_datum.values.u64_values.get(BLOCK_NUMBER_KEY).cloned().unwrap_or_default(),
+			duration_ns: span_datum.overall_time.as_nanos() as u64,
+			spans,
+			counters,
+		});
+	}
+}
@@ -0,0 +1,67 @@
+// Copyright 2019-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The datagrams of the exporters sending over UDP.
+
+use std::{io, mem, net::{SocketAddr, UdpSocket}};
+
+/// Maximum size of the UDP datagrams, fitting in the usual MTU.
+pub(crate) const MAX_PACKET_SIZE: usize = 1420;
+
+/// A socket bound to an ephemeral port of the same address family as `addr`, connected to it.
+pub(crate) fn connect(addr: SocketAddr) -> io::Result<UdpSocket> {
+	let local: SocketAddr = if addr.is_ipv4() {
+		([0, 0, 0, 0], 0).into()
+	} else {
+		([0u16; 8], 0).into()
+	};
+	let socket = UdpSocket::bind(local)?;
+	socket.connect(addr)?;
+	Ok(socket)
+}
+
+/// The lines grouped in datagrams, separated by newlines. A line longer than a datagram is
+/// sent alone.
+pub(crate) fn packets(lines: &[String]) -> Vec<String> {
+	let mut packets = Vec::new();
+	let mut packet = String::new();
+	for line in lines {
+		if !packet.is_empty() && packet.len() + 1 + line.len() > MAX_PACKET_SIZE {
+			packets.push(mem::take(&mut packet));
+		}
+		if !packet.is_empty() {
+			packet.push('\n');
+		}
+		packet.push_str(line);
+	}
+	if !packet.is_empty() {
+		packets.push(packet);
+	}
+	packets
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn groups_lines_in_packets() {
+		let line = "a".repeat(MAX_PACKET_SIZE / 2);
+		let lines = vec![line.clone(), line.clone(), "b".into()];
+		assert_eq!(packets(&lines), vec![line.clone(), format!("{}\n{}", line, "b")]);
+		assert!(packets(&[]).is_empty());
+	}
+}
@@ -0,0 +1,147 @@
+// Copyright 2019-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The drift of the measured times of the calls from their declared weights.
+//!
+//! The weights are benchmarked so that one unit of weight is one picosecond of execution on the
+//! reference hardware. The score of a call is the time spent in its last dispatches over the
+//! weight they declared, in picoseconds per unit of weight: about 1 on the reference hardware,
+//! and above on slower hardware, or when the benchmarks of the call are outdated.
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::Duration;
+
+use parking_lot::Mutex;
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+
+/// The drift of a call from its declared weight.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallDrift {
+	/// Target of the span of the call, i.e. its pallet.
+	pub target: String,
+	/// Name of the span of the call.
+	pub name: String,
+	/// Picoseconds spent per unit of declared weight, over the `samples`.
+	pub score: f64,
+	/// Number of dispatches the score is measured over.
+	pub samples: usize,
+}
+
+#[derive(Default)]
+struct Call {
+	samples: VecDeque<(Duration, u64)>,
+	alerting: bool,
+}
+
+impl Call {
+	fn score(&self) -> f64 {
+		let time: Duration = self.samples.iter().map(|(time, _)| *time).sum();
+		let weight: u64 = self.samples.iter().map(|(_, weight)| *weight).fold(0, u64::saturating_add);
+		time.as_nanos() as f64 * 1_000.0 / weight.max(1) as f64
+	}
+}
+
+struct Inner {
+	window: usize,
+	alert_ratio: f64,
+	calls: Mutex<FxHashMap<(String, String), Call>>,
+}
+
+/// The drift of the calls from their declared weights, over their last dispatches.
+#[derive(Clone)]
+pub struct WeightDrift {
+	inner: Arc<Inner>,
+}
+
+impl WeightDrift {
+	/// Scores the calls over their last `window` dispatches, and warns when a full window scores
+	/// above `alert_ratio`.
+	pub fn new(window: usize, alert_ratio: f64) -> Self {
+		WeightDrift {
+			inner: Arc::new(Inner {
+				window: window.max(1),
+				alert_ratio,
+				calls: Default::default(),
+			}),
+		}
+	}
+
+	/// Account a dispatch of the call `target` and `name` that lasted `time`, having declared
+	/// `weight`.
+	pub(crate) fn record(&self, target: &str, name: &str, time: Duration, weight: u64) {
+		// Calls without weight are not benchmarked.
+		if weight == 0 {
+			return;
+		}
+		let mut calls = self.inner.calls.lock();
+		let call = calls.entry((target.to_owned(), name.to_owned())).or_default();
+		if call.samples.len() == self.inner.window {
+			call.samples.pop_front();
+		}
+		call.samples.push_back((time, weight));
+		if call.samples.len() < self.inner.window {
+			return;
+		}
+		let score = call.score();
+		let alerting = score > self.inner.alert_ratio;
+		if alerting && !call.alerting {
+			log::warn!(
+				target: "tracing",
+				"{}::{} took {:.2}ps per unit of weight over its last {} dispatches, above {:.2}",
+				target, name, score, call.samples.len(), self.inner.alert_ratio,
+			);
+		}
+		call.alerting = alerting;
+	}
+
+	/// The drift of every call dispatched so far.
+	pub fn calls(&self) -> Vec<CallDrift> {
+		self.inner.calls.lock().iter()
+			.map(|((target, name), call)| CallDrift {
+				target: target.clone(),
+				name: name.clone(),
+				score: call.score(),
+				samples: call.samples.len(),
+			})
+			.collect()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn scores_the_last_dispatches() {
+		let drift = WeightDrift::new(2, 2.0);
+		let us = Duration::from_micros;
+		drift.record("balances", "transfer", us(1), 1_000_000);
+		drift.record("balances", "transfer", us(3), 1_000_000);
+		drift.record("balances", "transfer", us(5), 1_000_000);
+		drift.record("system", "remark", us(1), 0);
+
+		assert_eq!(drift.calls(), vec![CallDrift {
+			target: "balances".into(),
+			name: "transfer".into(),
+			score: 4.0,
+			samples: 2,
+		}]);
+		assert!(drift.inner.calls.lock()[&("balances".into(), "transfer".into())].alerting);
+	}
+}
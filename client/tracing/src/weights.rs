@@ -0,0 +1,272 @@
+// Copyright 2019-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Weights of the calls suggested by their dispatches in the traces written by the
+//! [`BinaryTraceHandler`](crate::BinaryTraceHandler).
+//!
+//! The dispatches are the spans of the [`DISPATCH_TARGET`], with the pallet and call they
+//! dispatched, see [`PALLET_KEY`] and [`CALL_KEY`], and the weight they declared. One unit of
+//! weight is one picosecond of execution on the reference hardware, so the suggested weight of a
+//! call is the upper bound of the confidence interval of the mean time of its dispatches, in
+//! picoseconds. The times include the accesses to the database, so the suggestions compare to the
+//! whole declared weights, including their reads and writes, and only the calls whose weights
+//! don't depend on their arguments can use them as is.
+
+use std::collections::BTreeMap;
+use std::io::{self, Read, Write};
+
+use sc_tracing_format::{Record, Span, TraceReader, Value};
+use sp_tracing::DISPATCH_TARGET;
+
+use crate::{CALL_KEY, PALLET_KEY};
+
+/// Confidence levels supported by [`DispatchTimes::suggestions`], in percent, with their z-score.
+pub const CONFIDENCE_LEVELS: &[(f64, f64)] = &[
+	(80.0, 1.282),
+	(90.0, 1.645),
+	(95.0, 1.960),
+	(98.0, 2.326),
+	(99.0, 2.576),
+	(99.9, 3.291),
+];
+
+/// The weight suggested for a call by its dispatches, in units of weight.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WeightSuggestion {
+	/// Pallet of the call.
+	pub pallet: String,
+	/// Name of the call.
+	pub call: String,
+	/// Number of dispatches measured.
+	pub samples: u64,
+	/// Mean time of the dispatches.
+	pub mean: u64,
+	/// Standard deviation of the time of the dispatches.
+	pub std_dev: u64,
+	/// Lower bound of the confidence interval of the mean.
+	pub lower: u64,
+	/// Upper bound of the confidence interval of the mean, the suggested weight.
+	pub upper: u64,
+	/// Longest time of the dispatches.
+	pub max: u64,
+	/// Mean weight declared by the dispatches.
+	pub declared: u64,
+}
+
+impl WeightSuggestion {
+	/// The suggested weight over the declared weight, in percent.
+	pub fn change_percent(&self) -> f64 {
+		(self.upper as f64 - self.declared as f64) * 100.0 / self.declared.max(1) as f64
+	}
+}
+
+#[derive(Default)]
+struct Samples {
+	count: u64,
+	/// Sum and sum of the squares of the times, in picoseconds.
+	sum: f64,
+	sum_squares: f64,
+	max: u64,
+	declared: u128,
+}
+
+/// Collects the times of the dispatches of traces, by pallet and call.
+#[derive(Default)]
+pub struct DispatchTimes {
+	calls: BTreeMap<(String, String), Samples>,
+}
+
+impl DispatchTimes {
+	/// Collect the dispatches of no trace yet.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Read the trace from `input` and collect the times of its dispatches.
+	///
+	/// Traces of the same blocks should not be read twice, as their dispatches would be counted
+	/// twice.
+	pub fn read_trace<R: Read>(&mut self, input: R) -> sc_tracing_format::Result<()> {
+		for record in TraceReader::new(input)? {
+			if let Record::Span(span) = record? {
+				if span.target == DISPATCH_TARGET {
+					self.record(&span);
+				}
+			}
+		}
+		Ok(())
+	}
+
+	fn record(&mut self, span: &Span) {
+		let field = |name: &str| span.fields.iter()
+			.find_map(|(field, value)| if field == name { Some(value) } else { None });
+		let (pallet, call) = match (field(PALLET_KEY), field(CALL_KEY)) {
+			(Some(Value::Str(pallet)), Some(Value::Str(call))) => (pallet.clone(), call.clone()),
+			_ => return,
+		};
+		let declared = match field("weight") {
+			Some(Value::U64(weight)) => *weight,
+			_ => return,
+		};
+
+		let time = span.duration_ns.saturating_mul(1_000);
+		let samples = self.calls.entry((pallet, call)).or_default();
+		samples.count += 1;
+		samples.sum += time as f64;
+		samples.sum_squares += time as f64 * time as f64;
+		samples.max = samples.max.max(time);
+		samples.declared += declared as u128;
+	}
+
+	/// The weights suggested for the calls with at least `min_samples` dispatches, by pallet and
+	/// call, with the `confidence` of [`CONFIDENCE_LEVELS`].
+	///
+	/// Returns `None` if the `confidence` is not supported.
+	pub fn suggestions(&self, confidence: f64, min_samples: u64) -> Option<Vec<WeightSuggestion>> {
+		let z = CONFIDENCE_LEVELS.iter()
+			.find(|(level, _)| (level - confidence).abs() < f64::EPSILON)
+			.map(|(_, z)| *z)?;
+
+		Some(self.calls.iter()
+			.filter(|(_, samples)| samples.count >= min_samples.max(1))
+			.map(|((pallet, call), samples)| {
+				let count = samples.count as f64;
+				let mean = samples.sum / count;
+				// Sample variance, with Bessel's correction.
+				let variance = if samples.count > 1 {
+					((samples.sum_squares - count * mean * mean) / (count - 1.0)).max(0.0)
+				} else {
+					0.0
+				};
+				let std_dev = variance.sqrt();
+				let margin = z * std_dev / count.sqrt();
+				WeightSuggestion {
+					pallet: pallet.clone(),
+					call: call.clone(),
+					samples: samples.count,
+					mean: mean as u64,
+					std_dev: std_dev as u64,
+					lower: (mean - margin).max(0.0) as u64,
+					upper: (mean + margin).ceil() as u64,
+					max: samples.max,
+					declared: (samples.declared / samples.count as u128) as u64,
+				}
+			})
+			.collect())
+	}
+}
+
+/// Write the `suggestions` as implementations of the `WeightInfo` traits of their pallets, in the
+/// style of the weights generated by the benchmarks.
+pub fn write_weight_info<W: Write>(
+	output: &mut W,
+	suggestions: &[WeightSuggestion],
+	confidence: f64,
+) -> io::Result<()> {
+	writeln!(output, "//! Weights suggested by the dispatches measured in production traces.")?;
+	writeln!(output, "//!")?;
+	writeln!(
+		output,
+		"//! The weights are the upper bounds of the {}% confidence intervals of the mean times of",
+		confidence,
+	)?;
+	writeln!(
+		output,
+		"//! the dispatches, including their accesses to the database, so they replace the whole",
+	)?;
+	writeln!(output, "//! weights of the calls, reads and writes included.")?;
+	writeln!(output)?;
+	writeln!(output, "#![allow(unused_parens)]")?;
+	writeln!(output, "#![allow(unused_imports)]")?;
+	writeln!(output)?;
+	writeln!(output, "use frame_support::weights::{{Weight, constants::RocksDbWeight as DbWeight}};")?;
+
+	let mut pallets: BTreeMap<&str, Vec<&WeightSuggestion>> = BTreeMap::new();
+	for suggestion in suggestions {
+		pallets.entry(&suggestion.pallet).or_default().push(suggestion);
+	}
+	for (pallet, calls) in pallets {
+		writeln!(output)?;
+		writeln!(output, "pub struct WeightInfo;")?;
+		writeln!(output, "impl {}::WeightInfo for WeightInfo {{", pallet)?;
+		for call in calls {
+			writeln!(
+				output,
+				"\t// {} dispatches: mean {}, interval [{}, {}], max {}, declared {} ({:+.1}%)",
+				call.samples, call.mean, call.lower, call.upper, call.max, call.declared,
+				call.change_percent(),
+			)?;
+			writeln!(output, "\tfn {}() -> Weight {{", call.call)?;
+			writeln!(output, "\t\t({} as Weight)", call.upper)?;
+			writeln!(output, "\t}}")?;
+		}
+		writeln!(output, "}}")?;
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sc_tracing_format::{Level, TraceWriter};
+
+	fn dispatch(id: u64, call: &str, duration_ns: u64, weight: u64) -> Record {
+		Record::Span(Span {
+			id,
+			parent_id: None,
+			target: DISPATCH_TARGET.into(),
+			name: DISPATCH_TARGET.into(),
+			level: Level::Info,
+			start_us: 0,
+			duration_ns,
+			fields: vec![
+				("weight".into(), Value::U64(weight)),
+				(PALLET_KEY.into(), Value::Str("pallet_balances".into())),
+				(CALL_KEY.into(), Value::Str(call.into())),
+			],
+		})
+	}
+
+	#[test]
+	fn suggests_the_weights_of_the_calls() {
+		let mut writer = TraceWriter::new(Vec::new()).unwrap();
+		for (id, duration_ns) in [9_000, 10_000, 11_000, 10_000].iter().enumerate() {
+			writer.write(&dispatch(id as u64, "transfer", *duration_ns, 8_000_000)).unwrap();
+		}
+		writer.write(&dispatch(10, "set_balance", 5_000, 5_000_000)).unwrap();
+		let trace = writer.into_inner();
+
+		let mut times = DispatchTimes::new();
+		times.read_trace(&trace[..]).unwrap();
+		assert!(times.suggestions(42.0, 1).is_none());
+
+		let suggestions = times.suggestions(95.0, 2).unwrap();
+		assert_eq!(suggestions.len(), 1);
+		let transfer = &suggestions[0];
+		assert_eq!((transfer.call.as_str(), transfer.samples), ("transfer", 4));
+		assert_eq!((transfer.mean, transfer.max, transfer.declared), (10_000_000, 11_000_000, 8_000_000));
+		// The standard deviation is 816_497, the margin 1.96 * 816_497 / 2.
+		assert_eq!(transfer.std_dev, 816_496);
+		assert_eq!((transfer.lower, transfer.upper), (9_199_833, 10_800_167));
+
+		let mut output = Vec::new();
+		write_weight_info(&mut output, &suggestions, 95.0).unwrap();
+		let output = String::from_utf8(output).unwrap();
+		assert!(output.contains("impl pallet_balances::WeightInfo for WeightInfo {"));
+		assert!(output.contains("\tfn transfer() -> Weight {\n\t\t(10800167 as Weight)\n\t}"));
+		assert!(!output.contains("set_balance"));
+	}
+}
@@ -403,6 +403,11 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 		self.ready.by_hash(hash)
 	}
 
+	/// Returns future transaction by hash.
+	pub fn future_by_hash(&self, hash: &Hash) -> Option<Arc<Transaction<Hash, Ex>>> {
+		self.future.by_hashes(&[hash.clone()]).pop().flatten()
+	}
+
 	/// Makes sure that the transactions in the queues stay within provided limits.
 	///
 	/// Removes and returns worst transactions from the queues and all transactions that depend on them.
@@ -630,6 +635,8 @@ mod tests {
 		}).unwrap();
 		assert_eq!(pool.ready().count(), 0);
 		assert_eq!(pool.ready.len(), 0);
+		assert!(pool.ready_by_hash(&1).is_none());
+		assert_eq!(pool.future_by_hash(&1).map(|tx| tx.priority), Some(5));
 		pool.import(Transaction {
 			data: vec![2u8],
 			bytes: 1,
@@ -645,6 +652,8 @@ mod tests {
 		// then
 		assert_eq!(pool.ready().count(), 2);
 		assert_eq!(pool.ready.len(), 2);
+		assert!(pool.future_by_hash(&1).is_none());
+		assert!(pool.ready_by_hash(&1).is_some());
 	}
 
 	#[test]
@@ -18,6 +18,7 @@
 
 use std::{
 	collections::{HashSet, HashMap},
+	fmt,
 	hash,
 	sync::Arc,
 };
@@ -29,6 +30,7 @@ use crate::watcher::Watcher;
 use serde::Serialize;
 
 use parking_lot::{Mutex, RwLock};
+use sp_core::hexdisplay::HexDisplay;
 use sp_runtime::{
 	generic::BlockId,
 	traits::{self, SaturatedConversion},
@@ -183,6 +185,16 @@ impl<B: ChainApi> ValidatedPool<B> {
 	fn submit_one(&self, tx: ValidatedTransactionFor<B>) -> Result<ExtrinsicHash<B>, B::Error> {
 		match tx {
 			ValidatedTransaction::Valid(tx) => {
+				sp_tracing::event!(
+					debug;
+					target: "txpool",
+					hash = ?tx.hash,
+					priority = tx.priority,
+					longevity = tx.valid_till,
+					requires = %Tags(&tx.requires),
+					provides = %Tags(&tx.provides),
+					"Importing transaction"
+				);
 				let imported = self.pool.write().import(tx)?;
 
 				if let base::Imported::Ready { ref hash, .. } = imported {
@@ -311,6 +323,9 @@ impl<B: ChainApi> ValidatedPool<B> {
 					let removed_hash = removed_tx.hash.clone();
 					let updated_transaction = updated_transactions.remove(&removed_hash);
 					let tx_to_resubmit = if let Some(updated_tx) = updated_transaction {
+						if let ValidatedTransaction::Valid(ref updated) = updated_tx {
+							trace_validity_change(&removed_tx, updated);
+						}
 						updated_tx
 					} else {
 						// in most cases we'll end up in successful `try_unwrap`, but if not
@@ -416,6 +431,11 @@ impl<B: ChainApi> ValidatedPool<B> {
 		self.pool.read().ready_by_hash(hash)
 	}
 
+	/// Get future transaction by hash
+	pub fn future_by_hash(&self, hash: &ExtrinsicHash<B>) -> Option<TransactionFor<B>> {
+		self.pool.read().future_by_hash(hash)
+	}
+
 	/// Prunes ready transactions that provide given list of tags.
 	pub fn prune_tags(
 		&self,
@@ -609,6 +629,48 @@ impl<B: ChainApi> ValidatedPool<B> {
 	}
 }
 
+/// Formats the tags of a transaction in hex.
+struct Tags<'a>(&'a [Tag]);
+
+impl<'a> fmt::Display for Tags<'a> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "[")?;
+		for (index, tag) in self.0.iter().enumerate() {
+			if index > 0 {
+				write!(f, ", ")?;
+			}
+			write!(f, "0x{}", HexDisplay::from(tag))?;
+		}
+		write!(f, "]")
+	}
+}
+
+/// Emit an event if the revalidation of the transaction `old` changed its validity to the one of
+/// `new`, e.g. its priority or the tags it requires.
+fn trace_validity_change<H: fmt::Debug, Ex>(
+	old: &base::Transaction<H, Ex>,
+	new: &base::Transaction<H, Ex>,
+) {
+	if old.priority != new.priority
+		|| old.valid_till != new.valid_till
+		|| old.requires != new.requires
+		|| old.provides != new.provides
+	{
+		sp_tracing::event!(
+			debug;
+			target: "txpool",
+			hash = ?new.hash,
+			old_priority = old.priority,
+			priority = new.priority,
+			old_longevity = old.valid_till,
+			longevity = new.valid_till,
+			requires = %Tags(&new.requires),
+			provides = %Tags(&new.provides),
+			"Transaction validity changed"
+		);
+	}
+}
+
 fn fire_events<H, B, Ex>(
 	listener: &mut Listener<H, B>,
 	imported: &base::Imported<H, Ex>,
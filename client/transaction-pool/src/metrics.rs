@@ -20,7 +20,9 @@
 
 use std::sync::Arc;
 
-use prometheus_endpoint::{register, Counter, PrometheusError, Registry, U64};
+use prometheus_endpoint::{
+	register, Counter, Histogram, HistogramOpts, PrometheusError, Registry, U64,
+};
 
 #[derive(Clone, Default)]
 pub struct MetricsLink(Arc<Option<Metrics>>);
@@ -49,6 +51,11 @@ pub struct Metrics {
 	pub validations_invalid: Counter<U64>,
 	pub block_transactions_pruned: Counter<U64>,
 	pub block_transactions_resubmitted: Counter<U64>,
+	pub revalidation_batches: Counter<U64>,
+	pub revalidation_transactions: Counter<U64>,
+	pub revalidation_invalid: Counter<U64>,
+	pub revalidation_unknown: Counter<U64>,
+	pub revalidation_batch_duration: Histogram,
 }
 
 impl Metrics {
@@ -82,6 +89,43 @@ impl Metrics {
 				)?,
 				registry,
 			)?,
+			revalidation_batches: register(
+				Counter::new(
+					"sub_txpool_revalidation_batches",
+					"Total number of batches of transactions revalidated",
+				)?,
+				registry,
+			)?,
+			revalidation_transactions: register(
+				Counter::new(
+					"sub_txpool_revalidation_transactions",
+					"Total number of transactions revalidated",
+				)?,
+				registry,
+			)?,
+			revalidation_invalid: register(
+				Counter::new(
+					"sub_txpool_revalidation_invalid",
+					"Total number of transactions that were removed from the pool as invalid by the revalidation",
+				)?,
+				registry,
+			)?,
+			revalidation_unknown: register(
+				Counter::new(
+					"sub_txpool_revalidation_unknown",
+					"Total number of transactions that were kept in the pool with an unknown validity by the revalidation",
+				)?,
+				registry,
+			)?,
+			revalidation_batch_duration: register(
+				Histogram::with_opts(
+					HistogramOpts::new(
+						"sub_txpool_revalidation_batch_duration",
+						"Time taken to revalidate a batch of transactions, in seconds",
+					).buckets(vec![0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0, 5.0, 10.0]),
+				)?,
+				registry,
+			)?,
 		})
 	}
 }
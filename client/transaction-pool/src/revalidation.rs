@@ -24,10 +24,15 @@ use sc_transaction_graph::{ChainApi, Pool, ExtrinsicHash, NumberFor, ValidatedTr
 use sp_runtime::traits::{Zero, SaturatedConversion};
 use sp_runtime::generic::BlockId;
 use sp_runtime::transaction_validity::TransactionValidityError;
+use sp_transaction_pool::RevalidationStats;
 use sp_utils::mpsc::{tracing_unbounded, TracingUnboundedSender, TracingUnboundedReceiver};
 
 use futures::prelude::*;
+use parking_lot::Mutex;
 use std::time::Duration;
+use wasm_timer::Instant;
+
+use crate::metrics::MetricsLink;
 
 #[cfg(not(test))]
 const BACKGROUND_REVALIDATION_INTERVAL: Duration = Duration::from_millis(200);
@@ -42,12 +47,54 @@ struct WorkerPayload<Api: ChainApi> {
 	transactions: Vec<ExtrinsicHash<Api>>,
 }
 
+/// Reports the revalidated batches to the metrics and the statistics of the pool.
+#[derive(Clone, Default)]
+struct BatchReport {
+	metrics: MetricsLink,
+	stats: Arc<Mutex<RevalidationStats>>,
+}
+
+/// Outcome of the revalidation of a batch.
+#[derive(Default)]
+struct BatchOutcome {
+	size: usize,
+	revalidated: usize,
+	invalidated: usize,
+	unknown: usize,
+}
+
+impl BatchReport {
+	fn batch(&self, at: u64, outcome: &BatchOutcome, duration: Duration) {
+		self.metrics.report(|metrics| {
+			metrics.revalidation_batches.inc();
+			metrics.revalidation_transactions.inc_by(outcome.revalidated as u64);
+			metrics.revalidation_invalid.inc_by(outcome.invalidated as u64);
+			metrics.revalidation_unknown.inc_by(outcome.unknown as u64);
+			metrics.revalidation_batch_duration.observe(duration.as_secs_f64());
+		});
+
+		let mut stats = self.stats.lock();
+		stats.batches += 1;
+		stats.revalidated += outcome.revalidated as u64;
+		stats.invalidated += outcome.invalidated as u64;
+		stats.unknown += outcome.unknown as u64;
+		stats.last_batch_size = outcome.size as u64;
+		stats.last_batch_duration_ms = duration.as_millis() as u64;
+		stats.last_batch_at = Some(at);
+	}
+
+	fn queued(&self, queued: usize) {
+		self.stats.lock().queued = queued as u64;
+	}
+}
+
 /// Async revalidation worker.
 ///
 /// Implements future and can be spawned in place or in background.
 struct RevalidationWorker<Api: ChainApi> {
 	api: Arc<Api>,
 	pool: Arc<Pool<Api>>,
+	report: BatchReport,
 	best_block: NumberFor<Api>,
 	block_ordered: BTreeMap<NumberFor<Api>, HashSet<ExtrinsicHash<Api>>>,
 	members: HashMap<ExtrinsicHash<Api>, NumberFor<Api>>,
@@ -59,12 +106,35 @@ impl<Api: ChainApi> Unpin for RevalidationWorker<Api> {}
 ///
 /// Each transaction is validated  against chain, and invalid are
 /// removed from the `pool`, while valid are resubmitted.
+///
+/// The batch is traced in a `revalidate_batch` span of the `txpool` target, with its size and
+/// outcome, and every transaction removed as invalid in an event of the span.
 async fn batch_revalidate<Api: ChainApi>(
 	pool: Arc<Pool<Api>>,
 	api: Arc<Api>,
 	at: NumberFor<Api>,
 	batch: impl IntoIterator<Item=ExtrinsicHash<Api>>,
+	report: &BatchReport,
 ) {
+	use sp_tracing::tracing::{self, field::Empty};
+
+	let started = Instant::now();
+	let at_number = at.saturated_into::<u64>();
+	let batch = batch.into_iter().collect::<Vec<_>>();
+	if batch.is_empty() {
+		return;
+	}
+	let span = tracing::debug_span!(
+		target: "txpool",
+		"revalidate_batch",
+		at = at_number,
+		size = batch.len(),
+		revalidated = Empty,
+		invalidated = Empty,
+		unknown = Empty,
+		duration_ms = Empty,
+	);
+	let mut outcome = BatchOutcome { size: batch.len(), ..Default::default() };
 	let mut invalid_hashes = Vec::new();
 	let mut revalidated = HashMap::new();
 
@@ -77,16 +147,22 @@ async fn batch_revalidate<Api: ChainApi>(
 		})
 	).await;
 
+	// The span is only entered once the validations are done, as it can't be held across the
+	// `await`, but its duration is recorded from the start of the batch.
+	let _entered = span.enter();
+	outcome.revalidated = validation_results.len();
 	for (validation_result, ext_hash, ext) in validation_results {
 		match validation_result {
 			Ok(Err(TransactionValidityError::Invalid(err))) => {
 				log::debug!(target: "txpool", "[{:?}]: Revalidation: invalid {:?}", ext_hash, err);
+				tracing::debug!(target: "txpool", hash = ?ext_hash, error = ?err, "Invalid during revalidation");
 				invalid_hashes.push(ext_hash);
 			},
 			Ok(Err(TransactionValidityError::Unknown(err))) => {
 				// skipping unknown, they might be pushed by valid or invalid transaction
 				// when latter resubmitted.
 				log::trace!(target: "txpool", "[{:?}]: Unknown during revalidation: {:?}", ext_hash, err);
+				outcome.unknown += 1;
 			},
 			Ok(Ok(validity)) => {
 				revalidated.insert(
@@ -108,25 +184,40 @@ async fn batch_revalidate<Api: ChainApi>(
 					ext_hash,
 					validation_err
 				);
+				tracing::debug!(
+					target: "txpool",
+					hash = ?ext_hash,
+					error = ?validation_err,
+					"Error during revalidation",
+				);
 				invalid_hashes.push(ext_hash);
 			}
 		}
 	}
 
-	pool.validated_pool().remove_invalid(&invalid_hashes);
+	outcome.invalidated = pool.validated_pool().remove_invalid(&invalid_hashes).len();
 	if revalidated.len() > 0 {
 		pool.resubmit(revalidated);
 	}
+
+	let duration = started.elapsed();
+	span.record("revalidated", &(outcome.revalidated as u64));
+	span.record("invalidated", &(outcome.invalidated as u64));
+	span.record("unknown", &(outcome.unknown as u64));
+	span.record("duration_ms", &(duration.as_millis() as u64));
+	report.batch(at_number, &outcome, duration);
 }
 
 impl<Api: ChainApi> RevalidationWorker<Api> {
 	fn new(
 		api: Arc<Api>,
 		pool: Arc<Pool<Api>>,
+		report: BatchReport,
 	) -> Self {
 		Self {
 			api,
 			pool,
+			report,
 			block_ordered: Default::default(),
 			members: Default::default(),
 			best_block: Zero::zero(),
@@ -223,7 +314,14 @@ impl<Api: ChainApi> RevalidationWorker<Api> {
 					let next_batch = this.prepare_batch();
 					let batch_len = next_batch.len();
 
-					batch_revalidate(this.pool.clone(), this.api.clone(), this.best_block, next_batch).await;
+					batch_revalidate(
+						this.pool.clone(),
+						this.api.clone(),
+						this.best_block,
+						next_batch,
+						&this.report,
+					).await;
+					this.report.queued(this.len());
 
 					#[cfg(test)]
 					{
@@ -248,6 +346,7 @@ impl<Api: ChainApi> RevalidationWorker<Api> {
 						Some(worker_payload) => {
 							this.best_block = worker_payload.at;
 							this.push(worker_payload);
+							this.report.queued(this.len());
 
 							if this.members.len() > 0 {
 								log::debug!(
@@ -278,6 +377,7 @@ pub struct RevalidationQueue<Api: ChainApi> {
 	pool: Arc<Pool<Api>>,
 	api: Arc<Api>,
 	background: Option<TracingUnboundedSender<WorkerPayload<Api>>>,
+	report: BatchReport,
 }
 
 impl<Api: ChainApi> RevalidationQueue<Api>
@@ -285,11 +385,12 @@ where
 	Api: 'static,
 {
 	/// New revalidation queue without background worker.
-	pub fn new(api: Arc<Api>, pool: Arc<Pool<Api>>) -> Self {
+	pub fn new(api: Arc<Api>, pool: Arc<Pool<Api>>, metrics: MetricsLink) -> Self {
 		Self {
 			api,
 			pool,
 			background: None,
+			report: BatchReport { metrics, stats: Default::default() },
 		}
 	}
 
@@ -297,26 +398,34 @@ where
 		api: Arc<Api>,
 		pool: Arc<Pool<Api>>,
 		interval: R,
+		metrics: MetricsLink,
 	) -> (Self, Pin<Box<dyn Future<Output=()> + Send>>) where R: Send + 'static, R::Guard: Send {
 		let (to_worker, from_queue) = tracing_unbounded("mpsc_revalidation_queue");
 
-		let worker = RevalidationWorker::new(api.clone(), pool.clone());
+		let report = BatchReport { metrics, stats: Default::default() };
+		let worker = RevalidationWorker::new(api.clone(), pool.clone(), report.clone());
 
 		let queue =
 			Self {
 				api,
 				pool,
 				background: Some(to_worker),
+				report,
 			};
 
 		(queue, worker.run(from_queue, interval).boxed())
 	}
 
 	/// New revalidation queue with background worker.
-	pub fn new_background(api: Arc<Api>, pool: Arc<Pool<Api>>) ->
+	pub fn new_background(api: Arc<Api>, pool: Arc<Pool<Api>>, metrics: MetricsLink) ->
 		(Self, Pin<Box<dyn Future<Output=()> + Send>>)
 	{
-		Self::new_with_interval(api, pool, intervalier::Interval::new(BACKGROUND_REVALIDATION_INTERVAL))
+		Self::new_with_interval(
+			api,
+			pool,
+			intervalier::Interval::new(BACKGROUND_REVALIDATION_INTERVAL),
+			metrics,
+		)
 	}
 
 	/// New revalidation queue with background worker and test signal.
@@ -325,7 +434,7 @@ where
 		(Self, Pin<Box<dyn Future<Output=()> + Send>>, intervalier::BackSignalControl)
 	{
 		let (interval, notifier) = intervalier::BackSignalInterval::new(BACKGROUND_REVALIDATION_INTERVAL);
-		let (queue, background) = Self::new_with_interval(api, pool, interval);
+		let (queue, background) = Self::new_with_interval(api, pool, interval, Default::default());
 
 		(queue, background, notifier)
 	}
@@ -354,9 +463,14 @@ where
 		} else {
 			let pool = self.pool.clone();
 			let api = self.api.clone();
-			batch_revalidate(pool, api, at, transactions).await
+			batch_revalidate(pool, api, at, transactions, &self.report).await
 		}
 	}
+
+	/// The statistics of the batches revalidated so far.
+	pub fn stats(&self) -> RevalidationStats {
+		self.report.stats.lock().clone()
+	}
 }
 
 #[cfg(test)]
@@ -378,7 +492,7 @@ mod tests {
 	fn smoky() {
 		let (api, pool) = setup();
 		let pool = Arc::new(pool);
-		let queue = Arc::new(RevalidationQueue::new(api.clone(), pool.clone()));
+		let queue = Arc::new(RevalidationQueue::new(api.clone(), pool.clone(), Default::default()));
 
 		let uxt = uxt(Alice, 0);
 		let uxt_hash = block_on(
@@ -392,4 +506,30 @@ mod tests {
 		// number of ready
 		assert_eq!(pool.validated_pool().status().ready, 1);
 	}
+
+	#[test]
+	fn reports_the_revalidated_batches() {
+		let (api, pool) = setup();
+		let pool = Arc::new(pool);
+		let queue = Arc::new(RevalidationQueue::new(api.clone(), pool.clone(), Default::default()));
+
+		let valid = block_on(
+			pool.submit_one(&BlockId::number(0), TransactionSource::External, uxt(Alice, 0))
+		).expect("Should be valid");
+		let invalid_uxt = uxt(Alice, 1);
+		let invalid = block_on(
+			pool.submit_one(&BlockId::number(0), TransactionSource::External, invalid_uxt.clone())
+		).expect("Should be valid");
+		api.add_invalid(&invalid_uxt);
+
+		block_on(queue.revalidate_later(0, vec![valid, invalid]));
+
+		let stats = queue.stats();
+		assert_eq!(
+			(stats.batches, stats.revalidated, stats.invalidated, stats.unknown),
+			(1, 2, 1, 0),
+		);
+		assert_eq!((stats.last_batch_size, stats.last_batch_at), (2, Some(0)));
+		assert_eq!(pool.validated_pool().status().ready, 1);
+	}
 }
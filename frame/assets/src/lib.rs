@@ -176,6 +176,7 @@ decl_module! {
 			<TotalSupply<T>>::insert(id, total);
 
 			Self::deposit_event(RawEvent::Issued(id, origin, total));
+			frame_support::sp_tracing::counter!("assets_issue", id, 1);
 		}
 
 		/// Move some assets from one holder to another.
@@ -200,6 +201,7 @@ decl_module! {
 			ensure!(origin_balance >= amount, Error::<T>::BalanceLow);
 
 			Self::deposit_event(RawEvent::Transferred(id, origin, target.clone(), amount));
+			frame_support::sp_tracing::counter!("assets_transfer", id, 1);
 			<Balances<T>>::insert(origin_account, origin_balance - amount);
 			<Balances<T>>::mutate((id, target), |balance| *balance += amount);
 		}
@@ -220,6 +222,7 @@ decl_module! {
 
 			<TotalSupply<T>>::mutate(id, |total_supply| *total_supply -= balance);
 			Self::deposit_event(RawEvent::Destroyed(id, origin, balance));
+			frame_support::sp_tracing::counter!("assets_destroy", id, 1);
 		}
 	}
 }
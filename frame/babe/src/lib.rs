@@ -652,6 +652,17 @@ impl<T: Trait> Module<T> {
 		let offender = T::KeyOwnerProofSystem::check_proof(key, key_owner_proof)
 			.ok_or(Error::InvalidKeyOwnershipProof)?;
 
+		// reported under the target of the offences pallet, so that it is forwarded to telemetry.
+		frame_support::sp_tracing::event!(
+			warn;
+			target: "offences",
+			offender = ?offender,
+			slot = slot_number,
+			session_index,
+			reporter = ?reporter,
+			"BABE equivocation reported",
+		);
+
 		let offence = BabeEquivocationOffence {
 			slot: slot_number,
 			validator_set_count,
@@ -0,0 +1,161 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Opt-in checks of the invariants of the balances module, meant for development networks.
+//!
+//! [`CheckInvariants`] verifies at the end of every block that the total issuance equals the sum
+//! of the balances of all accounts, that no account holds less than the existential deposit and
+//! that the frozen balances of every account match its locks. Every violation is reported as an
+//! `ERROR` log naming the offending account. As its cost grows with the number of accounts, it
+//! must only be enabled on development and test networks, by adding it to the modules of the
+//! executive of a runtime whose `AccountStore` is `frame_system`:
+//!
+//! ```ignore
+//! pub type Executive = frame_executive::Executive<
+//! 	Runtime,
+//! 	Block,
+//! 	frame_system::ChainContext<Runtime>,
+//! 	Runtime,
+//! 	(AllModules, pallet_balances::invariants::CheckInvariants<Runtime>),
+//! >;
+//! ```
+//!
+//! Runtimes storing the balances in this module can call [`check`] with its `Account` storage.
+
+use sp_std::marker::PhantomData;
+use frame_support::{
+	debug, StorageValue, StorageMap, IterableStorageMap,
+	traits::{Get, OnFinalize, OnInitialize, OnRuntimeUpgrade, OffchainWorker},
+};
+use sp_runtime::traits::{Zero, CheckedAdd};
+use crate::{Trait, Instance, DefaultInstance, AccountData, Locks, TotalIssuance, Reasons};
+
+/// Target of the logs of the invariant checks.
+const LOG_TARGET: &str = "balances::invariants";
+
+/// Checks the invariants of the balances module at the end of every block, see the
+/// [module documentation](self).
+pub struct CheckInvariants<T, I = DefaultInstance>(PhantomData<(T, I)>);
+
+impl<T, I> OnFinalize<T::BlockNumber> for CheckInvariants<T, I> where
+	T: Trait<I> + frame_system::Trait<AccountData = AccountData<<T as Trait<I>>::Balance>>,
+	I: Instance,
+{
+	fn on_finalize(n: T::BlockNumber) {
+		if !check_system_accounts::<T, I>() {
+			debug::error!(target: LOG_TARGET, "Balances invariants violated at block {:?}", n);
+		}
+	}
+}
+
+impl<T: frame_system::Trait, I> OnInitialize<T::BlockNumber> for CheckInvariants<T, I> {}
+
+impl<T, I> OnRuntimeUpgrade for CheckInvariants<T, I> {}
+
+impl<T: frame_system::Trait, I> OffchainWorker<T::BlockNumber> for CheckInvariants<T, I> {}
+
+/// Checks the invariants against the accounts stored in `frame_system`.
+///
+/// Returns `true` iff all of them hold.
+pub fn check_system_accounts<T, I>() -> bool where
+	T: Trait<I> + frame_system::Trait<AccountData = AccountData<<T as Trait<I>>::Balance>>,
+	I: Instance,
+{
+	check::<T, I, _>(frame_system::Account::<T>::iter().map(|(who, info)| (who, info.data)))
+}
+
+/// Checks the invariants against `accounts`, which must be all accounts holding a balance.
+///
+/// Returns `true` iff all of them hold.
+pub fn check<T, I, A>(accounts: A) -> bool where
+	T: Trait<I>,
+	I: Instance,
+	A: Iterator<Item = (T::AccountId, AccountData<T::Balance>)>,
+{
+	// Make sure the violations are also reported when executing the runtime in wasm.
+	debug::RuntimeLogger::init();
+
+	let existential_deposit = T::ExistentialDeposit::get();
+	let mut valid = true;
+	let mut sum = Some(T::Balance::zero());
+
+	for (who, account) in accounts {
+		let total = account.free.checked_add(&account.reserved);
+		sum = sum.and_then(|sum| total.and_then(|total| sum.checked_add(&total)));
+
+		match total {
+			None => {
+				debug::error!(
+					target: LOG_TARGET,
+					"Balance of {:?} overflows: {:?}",
+					who,
+					account,
+				);
+				valid = false;
+			},
+			Some(total) if !total.is_zero() && total < existential_deposit => {
+				debug::error!(
+					target: LOG_TARGET,
+					"Balance of {:?} is below the existential deposit of {:?}: {:?}",
+					who,
+					existential_deposit,
+					account,
+				);
+				valid = false;
+			},
+			_ => {},
+		}
+
+		// The locks of reaped accounts are kept until they are removed, but don't freeze anything.
+		if total.map_or(false, |total| total.is_zero()) {
+			continue
+		}
+
+		let locks = Locks::<T, I>::get(&who);
+		let frozen = |reasons: Reasons| locks.iter()
+			.filter(|lock| lock.reasons == Reasons::All || lock.reasons == reasons)
+			.map(|lock| lock.amount)
+			.max()
+			.unwrap_or_else(Zero::zero);
+		let (misc_frozen, fee_frozen) = (frozen(Reasons::Misc), frozen(Reasons::Fee));
+		if account.misc_frozen != misc_frozen || account.fee_frozen != fee_frozen {
+			debug::error!(
+				target: LOG_TARGET,
+				"Frozen balances of {:?} don't match its locks, expected misc {:?} and fee {:?}: {:?}, locks: {:?}",
+				who,
+				misc_frozen,
+				fee_frozen,
+				account,
+				locks,
+			);
+			valid = false;
+		}
+	}
+
+	let total_issuance = TotalIssuance::<T, I>::get();
+	if sum != Some(total_issuance) {
+		debug::error!(
+			target: LOG_TARGET,
+			"Total issuance {:?} doesn't match the sum of all balances {:?}",
+			total_issuance,
+			sum,
+		);
+		valid = false;
+	}
+
+	valid
+}
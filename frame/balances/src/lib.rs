@@ -155,6 +155,8 @@ mod tests_local;
 mod tests_composite;
 mod benchmarking;
 mod default_weight;
+#[cfg(feature = "invariant-checks")]
+pub mod invariants;
 
 use sp_std::prelude::*;
 use sp_std::{cmp, result, mem, fmt::Debug, ops::BitOr, convert::Infallible};
@@ -160,3 +160,23 @@ impl ExtBuilder {
 }
 
 decl_tests!{ Test, ExtBuilder, EXISTENTIAL_DEPOSIT }
+
+#[cfg(feature = "invariant-checks")]
+#[test]
+fn invariants_hold_until_balances_are_corrupted() {
+	use crate::invariants::check_system_accounts;
+
+	<ExtBuilder>::default().monied(true).build().execute_with(|| {
+		let _ = Balances::deposit_creating(&5, 50);
+		Balances::set_lock(ID_1, &2, 5, WithdrawReasons::all());
+		assert_ok!(Balances::reserve(&3, 10));
+		assert!(check_system_accounts::<Test, _>());
+
+		TotalIssuance::<Test>::mutate(|i| *i += 1);
+		assert!(!check_system_accounts::<Test, _>());
+		TotalIssuance::<Test>::mutate(|i| *i -= 1);
+
+		System::mutate(&2, |account| account.fee_frozen = 0);
+		assert!(!check_system_accounts::<Test, _>());
+	});
+}
@@ -34,6 +34,20 @@ pub use frame_support;
 pub use paste;
 pub use sp_storage::TrackedStorageKey;
 
+/// Enter the span of the measured logic of a benchmark, in the host when running in wasm, where
+/// `sp_tracing::entered_span` doesn't enter it.
+#[doc(hidden)]
+pub fn enter_extrinsic_span() -> frame_support::sp_tracing::EnteredSpan {
+	#[cfg(feature = "std")]
+	{
+		frame_support::sp_tracing::entered_span!(target: "benchmark", "extrinsic";)
+	}
+	#[cfg(not(feature = "std"))]
+	{
+		sp_io::enter_host_span("benchmark", "extrinsic")
+	}
+}
+
 /// Construct pallet benchmarks for weighing dispatchables.
 ///
 /// Works around the idea of complexity parameters, named by a single letter (which is usually
@@ -173,6 +187,11 @@ pub use sp_storage::TrackedStorageKey;
 ///   });
 /// }
 /// ```
+///
+/// The benchmarks are traced for the `benchmark` target: a `pallet` span, see [`add_benchmark`],
+/// holds a `benchmark` span per benchmark, which holds an `iteration` span per repetition, with
+/// the values of the `components`. The measured part of the iteration is in an `extrinsic` span,
+/// followed by a `debug` event with its results. The spans are only emitted by the native runtime.
 #[macro_export]
 macro_rules! benchmarks {
 	(
@@ -778,6 +797,11 @@ macro_rules! impl_benchmark {
 					$( stringify!($name) => SelectedBenchmark::$name, )*
 					_ => return Err("Could not find extrinsic."),
 				};
+				$crate::frame_support::sp_tracing::enter_span!(
+					target: "benchmark",
+					"benchmark";
+					benchmark = extrinsic,
+				);
 
 				// Add whitelist to DB including whitelisted caller
 				let mut whitelist = whitelist.to_vec();
@@ -808,6 +832,14 @@ macro_rules! impl_benchmark {
 				| -> Result<(), &'static str> {
 					// Run the benchmark `repeat` times.
 					for _ in 0..repeat {
+						// Trace every iteration with the values of its components.
+						$crate::frame_support::sp_tracing::enter_span!(
+							target: "benchmark",
+							"iteration";
+							components = ?c,
+							verify,
+						);
+
 						// Set up the externalities environment for the setup we want to
 						// benchmark.
 						let closure_to_benchmark = <
@@ -835,11 +867,14 @@ macro_rules! impl_benchmark {
 								"Start Benchmark: {:?}", c
 							);
 
+							// Entered out of the measured time.
+							let extrinsic_span = $crate::enter_extrinsic_span();
 							let start_extrinsic = $crate::benchmarking::current_time();
 
 							closure_to_benchmark()?;
 
 							let finish_extrinsic = $crate::benchmarking::current_time();
+							drop(extrinsic_span);
 							let elapsed_extrinsic = finish_extrinsic - start_extrinsic;
 							// Commit the changes to get proper write count
 							$crate::benchmarking::commit_db();
@@ -868,6 +903,15 @@ macro_rules! impl_benchmark {
 								writes: read_write_count.2,
 								repeat_writes: read_write_count.3,
 							});
+							$crate::frame_support::sp_tracing::event!(
+								debug;
+								target: "benchmark",
+								extrinsic_time = elapsed_extrinsic as u64,
+								storage_root_time = elapsed_storage_root as u64,
+								reads = read_write_count.0,
+								writes = read_write_count.2,
+								"Benchmark iteration",
+							);
 						}
 
 						// Wipe the DB back to the genesis state.
@@ -1064,6 +1108,11 @@ macro_rules! add_benchmark {
 			extra,
 		} = config;
 		if &pallet[..] == &name_string[..] || &pallet[..] == &b"*"[..] {
+			$crate::frame_support::sp_tracing::enter_span!(
+				target: "benchmark",
+				"pallet";
+				pallet = stringify!($name),
+			);
 			if &pallet[..] == &b"*"[..] || &benchmark[..] == &b"*"[..] {
 				for benchmark in $( $location )*::benchmarks(*extra).into_iter() {
 					$batches.push($crate::BenchmarkBatch {
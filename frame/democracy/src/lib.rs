@@ -187,6 +187,9 @@ pub mod benchmarking;
 
 const DEMOCRACY_ID: LockIdentifier = *b"democrac";
 
+/// Tracing target of the lifecycle of proposals and referenda.
+pub const LOG_TARGET: &str = "democracy";
+
 /// The maximum number of vetoers on a single proposal used to compute Weight.
 ///
 /// NOTE: This is not enforced by any logic.
@@ -605,6 +608,15 @@ decl_module! {
 			PublicPropCount::put(index + 1);
 			<DepositOf<T>>::insert(index, (&[&who][..], value));
 
+			frame_support::sp_tracing::event!(
+				info;
+				target: LOG_TARGET,
+				proposal_index = index,
+				proposal_hash = ?proposal_hash,
+				proposer = ?who,
+				deposit = ?value,
+				"Public proposal submitted",
+			);
 			<PublicProps<T>>::append((index, proposal_hash, who));
 
 			Self::deposit_event(RawEvent::Proposed(index, value));
@@ -710,6 +722,13 @@ decl_module! {
 				);
 			}
 			<NextExternal<T>>::put((proposal_hash, VoteThreshold::SuperMajorityApprove));
+			frame_support::sp_tracing::event!(
+				info;
+				target: LOG_TARGET,
+				proposal_hash = ?proposal_hash,
+				threshold = ?VoteThreshold::SuperMajorityApprove,
+				"External proposal submitted",
+			);
 		}
 
 		/// Schedule a majority-carries referendum to be tabled next once it is legal to schedule
@@ -730,6 +749,13 @@ decl_module! {
 		fn external_propose_majority(origin, proposal_hash: T::Hash) {
 			T::ExternalMajorityOrigin::ensure_origin(origin)?;
 			<NextExternal<T>>::put((proposal_hash, VoteThreshold::SimpleMajority));
+			frame_support::sp_tracing::event!(
+				info;
+				target: LOG_TARGET,
+				proposal_hash = ?proposal_hash,
+				threshold = ?VoteThreshold::SimpleMajority,
+				"External proposal submitted",
+			);
 		}
 
 		/// Schedule a negative-turnout-bias referendum to be tabled next once it is legal to
@@ -750,6 +776,13 @@ decl_module! {
 		fn external_propose_default(origin, proposal_hash: T::Hash) {
 			T::ExternalDefaultOrigin::ensure_origin(origin)?;
 			<NextExternal<T>>::put((proposal_hash, VoteThreshold::SuperMajorityAgainst));
+			frame_support::sp_tracing::event!(
+				info;
+				target: LOG_TARGET,
+				proposal_hash = ?proposal_hash,
+				threshold = ?VoteThreshold::SuperMajorityAgainst,
+				"External proposal submitted",
+			);
 		}
 
 		/// Schedule the currently externally-proposed majority-carries referendum to be tabled
@@ -1224,6 +1257,7 @@ impl<T: Trait> Module<T> {
 	/// Remove a referendum.
 	pub fn internal_cancel_referendum(ref_index: ReferendumIndex) {
 		Self::deposit_event(RawEvent::Cancelled(ref_index));
+		frame_support::sp_tracing::event!(info; target: LOG_TARGET, ref_index, "Referendum cancelled");
 		ReferendumInfoOf::<T>::remove(ref_index);
 	}
 
@@ -1483,6 +1517,16 @@ impl<T: Trait> Module<T> {
 		let item = ReferendumInfo::Ongoing(status);
 		<ReferendumInfoOf<T>>::insert(ref_index, item);
 		Self::deposit_event(RawEvent::Started(ref_index, threshold));
+		frame_support::sp_tracing::event!(
+			info;
+			target: LOG_TARGET,
+			ref_index,
+			proposal_hash = ?proposal_hash,
+			threshold = ?threshold,
+			end = ?end,
+			delay = ?delay,
+			"Referendum started",
+		);
 		ref_index
 	}
 
@@ -1500,6 +1544,13 @@ impl<T: Trait> Module<T> {
 		if let Some((proposal, threshold)) = <NextExternal<T>>::take() {
 			LastTabledWasExternal::put(true);
 			Self::deposit_event(RawEvent::ExternalTabled);
+			frame_support::sp_tracing::event!(
+				info;
+				target: LOG_TARGET,
+				proposal_hash = ?proposal,
+				threshold = ?threshold,
+				"External proposal tabled",
+			);
 			Self::inject_referendum(
 				now + T::VotingPeriod::get(),
 				proposal,
@@ -1528,6 +1579,15 @@ impl<T: Trait> Module<T> {
 				for d in &depositors {
 					T::Currency::unreserve(d, deposit);
 				}
+				frame_support::sp_tracing::event!(
+					info;
+					target: LOG_TARGET,
+					proposal_index = prop_index,
+					proposal_hash = ?proposal,
+					deposit = ?deposit,
+					depositors = depositors.len(),
+					"Public proposal tabled",
+				);
 				Self::deposit_event(RawEvent::Tabled(prop_index, deposit, depositors));
 				Self::inject_referendum(
 					now + T::VotingPeriod::get(),
@@ -1551,6 +1611,14 @@ impl<T: Trait> Module<T> {
 
 				let ok = proposal.dispatch(frame_system::RawOrigin::Root.into()).is_ok();
 				Self::deposit_event(RawEvent::Executed(index, ok));
+				frame_support::sp_tracing::event!(
+					info;
+					target: LOG_TARGET,
+					ref_index = index,
+					proposal_hash = ?proposal_hash,
+					ok,
+					"Proposal enacted",
+				);
 
 				Ok(())
 			} else {
@@ -1572,6 +1640,16 @@ impl<T: Trait> Module<T> {
 		let total_issuance = T::Currency::total_issuance();
 		let approved = status.threshold.approved(status.tally, total_issuance);
 
+		frame_support::sp_tracing::event!(
+			info;
+			target: LOG_TARGET,
+			ref_index = index,
+			proposal_hash = ?status.proposal_hash,
+			total_issuance = ?total_issuance,
+			approved,
+			"Referendum ended",
+		);
+
 		if approved {
 			Self::deposit_event(RawEvent::Passed(index));
 			if status.delay.is_zero() {
@@ -1593,6 +1671,15 @@ impl<T: Trait> Module<T> {
 					Call::enact_proposal(status.proposal_hash, index).into(),
 				).is_err() {
 					frame_support::print("LOGIC ERROR: bake_referendum/schedule_named failed");
+				} else {
+					frame_support::sp_tracing::event!(
+						info;
+						target: LOG_TARGET,
+						ref_index = index,
+						proposal_hash = ?status.proposal_hash,
+						when = ?when,
+						"Enactment scheduled",
+					);
 				}
 			}
 		} else {
@@ -1629,10 +1716,13 @@ impl<T: Trait> Module<T> {
 		let r = last.saturating_sub(next);
 		weight = weight.saturating_add(T::WeightInfo::on_initialize_base(r));
 		// tally up votes for any expiring referenda.
-		for (index, info) in Self::maturing_referenda_at_inner(now, next..last).into_iter() {
-			let approved = Self::bake_referendum(now, index, info)?;
-			ReferendumInfoOf::<T>::insert(index, ReferendumInfo::Finished { end: now, approved });
-			weight = T::MaximumBlockWeight::get();
+		frame_support::sp_tracing::within_iteration_span! {
+			target: LOG_TARGET, "bake_referendum";
+			for (index, info) in Self::maturing_referenda_at_inner(now, next..last), ref_index = index => {
+				let approved = Self::bake_referendum(now, index, info)?;
+				ReferendumInfoOf::<T>::insert(index, ReferendumInfo::Finished { end: now, approved });
+				weight = T::MaximumBlockWeight::get();
+			}
 		}
 
 		Ok(weight)
@@ -112,6 +112,14 @@
 //!
 //! pub type Executive = executive::Executive<Runtime, Block, Context, Runtime, AllModules, CustomOnRuntimeUpgrade>;
 //! ```
+//!
+//! ### Tracing in wasm
+//!
+//! Natively, the Executive traces the dispatches, their failures and the runtime upgrades with
+//! `sp_tracing`. In wasm, it only reports them to the node through the host functions of
+//! `sp_io::wasm_tracing` when built with the `with-tracing` feature, as a runtime calling them
+//! can't be executed by the nodes lacking them. Upgrade the nodes before enacting a runtime
+//! built with the feature.
 
 #![cfg_attr(not(feature = "std"), no_std)]
 
@@ -129,6 +137,7 @@ use sp_runtime::{
 	},
 	transaction_validity::{TransactionValidity, TransactionSource},
 };
+use sp_runtime::DispatchError;
 use codec::{Codec, Encode};
 use frame_system::{extrinsics_root, DigestOf};
 
@@ -138,6 +147,84 @@ pub trait ExecuteBlock<Block: BlockT> {
 	fn execute_block(block: Block);
 }
 
+sp_tracing::span_schema! {
+	/// The dispatch of an extrinsic, with its declared weight, compared by the node to the time
+	/// spent in the span of the call, see `sc_tracing::WeightDrift`, and the kind of its origin,
+	/// see `dispatch_origin`.
+	struct DispatchSpan {
+		target: sp_tracing::DISPATCH_TARGET,
+		name: sp_tracing::DISPATCH_TARGET,
+		fields: { weight: u64, origin: &str },
+	}
+}
+
+/// Enter the `DispatchSpan` of an extrinsic, in the host when running in wasm with the
+/// `with-tracing` feature, where the spans of the schemas are not entered otherwise.
+#[cfg_attr(not(feature = "std"), allow(unused_variables))]
+fn enter_dispatch_span(weight: u64, origin: &str) -> sp_tracing::EnteredSpan {
+	#[cfg(feature = "std")]
+	{
+		DispatchSpan.enter(weight, origin)
+	}
+	#[cfg(all(not(feature = "std"), feature = "with-tracing"))]
+	{
+		sp_io::enter_host_span(DispatchSpan::TARGET, DispatchSpan::NAME)
+	}
+	#[cfg(all(not(feature = "std"), not(feature = "with-tracing")))]
+	{
+		sp_tracing::EnteredSpan::none()
+	}
+}
+
+/// The spans of the executive, see [`TraceSpans`](frame_support::debug::TraceSpans).
+pub const TRACE_SPANS: frame_support::debug::TraceSpans = &[
+	(sp_tracing::DISPATCH_TARGET, sp_tracing::DISPATCH_TARGET),
+	(module_path!(), "validate_transaction"),
+	(module_path!(), "using_encoded"),
+	(module_path!(), "check"),
+	(module_path!(), "dispatch_info"),
+	(module_path!(), "validate"),
+];
+
+/// The kind of origin of an extrinsic, `"signed"`, `"inherent"` for the unsigned extrinsics of
+/// the mandatory class, or `"none"`, as the node aggregates the dispatches by their origin, see
+/// `sc_tracing::DispatchOrigins`.
+fn dispatch_origin(signed: Option<bool>, class: DispatchClass) -> &'static str {
+	match (signed, class) {
+		(Some(true), _) => "signed",
+		(_, DispatchClass::Mandatory) => "inherent",
+		_ => "none",
+	}
+}
+
+/// Emit the event of a failed dispatch within its span, through the host in wasm with the
+/// `with-tracing` feature, which the node enriches with the pallet and call of the span and names
+/// from the metadata, see `sc_tracing::ERROR_KEY`.
+#[cfg_attr(not(any(feature = "std", feature = "with-tracing")), allow(unused_variables))]
+fn trace_dispatch_error(error: &DispatchError) {
+	#[cfg(any(feature = "std", feature = "with-tracing"))]
+	{
+		let module_error = match *error {
+			DispatchError::Module { index, error, .. } => Some((index, error)),
+			_ => None,
+		};
+		sp_io::wasm_tracing::dispatch_failed(module_error, (*error).into());
+	}
+}
+
+/// Mark the start of the migrations of a runtime upgrade, for the node to time them, see
+/// `sp_tracing::time_runtime_upgrade`, through the host in wasm with the `with-tracing` feature.
+fn runtime_upgrade_started() {
+	#[cfg(any(feature = "std", feature = "with-tracing"))]
+	sp_io::wasm_tracing::runtime_upgrade_started();
+}
+
+/// Mark the end of the migrations, see `runtime_upgrade_started`.
+fn runtime_upgrade_finished() {
+	#[cfg(any(feature = "std", feature = "with-tracing"))]
+	sp_io::wasm_tracing::runtime_upgrade_finished();
+}
+
 pub type CheckedOf<E, C> = <E as Checkable<C>>::Checked;
 pub type CallOf<E, C> = <CheckedOf<E, C> as Applyable>::Call;
 pub type OriginOf<E, C> = <CallOf<E, C> as Dispatchable>::Origin;
@@ -233,10 +320,12 @@ where
 		digest: &Digest<System::Hash>,
 	) {
 		if Self::runtime_upgraded() {
+			runtime_upgrade_started();
 			// System is not part of `AllModules`, so we need to call this manually.
 			let mut weight = <frame_system::Module::<System> as OnRuntimeUpgrade>::on_runtime_upgrade();
 			weight = weight.saturating_add(COnRuntimeUpgrade::on_runtime_upgrade());
 			weight = weight.saturating_add(<AllModules as OnRuntimeUpgrade>::on_runtime_upgrade());
+			runtime_upgrade_finished();
 			<frame_system::Module<System>>::register_extra_weight_unchecked(weight, DispatchClass::Mandatory);
 		}
 		<frame_system::Module<System>>::initialize(
@@ -313,6 +402,13 @@ where
 
 		// post-extrinsics book-keeping
 		<frame_system::Module<System>>::note_finished_extrinsics();
+		for class in &[DispatchClass::Normal, DispatchClass::Operational] {
+			sp_tracing::counter!(
+				"block_weight",
+				class,
+				<frame_system::Module<System>>::block_weight().get(*class)
+			);
+		}
 		<frame_system::Module<System> as OnFinalize<System::BlockNumber>>::on_finalize(block_number);
 		<AllModules as OnFinalize<System::BlockNumber>>::on_finalize(block_number);
 	}
@@ -355,6 +451,7 @@ where
 		encoded_len: usize,
 		to_note: Option<Vec<u8>>,
 	) -> ApplyExtrinsicResult {
+		let signed = traits::Extrinsic::is_signed(&uxt);
 		// Verify that the signature is good.
 		let xt = uxt.check(&Default::default())?;
 
@@ -369,7 +466,17 @@ where
 
 		// Decode parameters and dispatch
 		let dispatch_info = xt.get_dispatch_info();
+		let dispatch_span = enter_dispatch_span(
+			dispatch_info.weight,
+			dispatch_origin(signed, dispatch_info.class),
+		);
+		// The fields of the spans entered in wasm are not passed to the host.
+		dispatch_span.record_weight(dispatch_info.weight);
 		let r = Applyable::apply::<UnsignedValidator>(xt, &dispatch_info, encoded_len)?;
+		if let Err(error) = &r {
+			trace_dispatch_error(&error.error);
+		}
+		drop(dispatch_span);
 
 		<frame_system::Module<System>>::note_applied_extrinsic(&r, dispatch_info);
 
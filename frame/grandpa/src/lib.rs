@@ -575,6 +575,18 @@ impl<T: Trait> Module<T> {
 			return Err(Error::<T>::InvalidEquivocationProof.into());
 		}
 
+		// reported under the target of the offences pallet, so that it is forwarded to telemetry.
+		frame_support::sp_tracing::event!(
+			warn;
+			target: "offences",
+			offender = ?offender,
+			set_id,
+			round,
+			session_index,
+			reporter = ?reporter,
+			"GRANDPA equivocation reported",
+		);
+
 		// report to the offences module rewarding the sender.
 		T::HandleEquivocation::report_offence(
 			reporter.into_iter().collect(),
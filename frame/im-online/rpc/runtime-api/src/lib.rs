@@ -0,0 +1,55 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Runtime API definition for the im-online module.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use sp_std::prelude::*;
+use codec::Codec;
+
+pub use pallet_im_online::HeartbeatInfo;
+
+sp_api::decl_runtime_apis! {
+	pub trait ImOnlineApi<AuthorityId> where
+		AuthorityId: Codec,
+	{
+		/// The heartbeat status of every authority of the current session.
+		fn heartbeats() -> Vec<(AuthorityId, HeartbeatInfo)>;
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn should_serialize_and_deserialize_properly() {
+		let info = HeartbeatInfo {
+			session_index: 5,
+			authority_index: 1,
+			heartbeat_received: true,
+			authored_blocks: 0,
+			is_online: true,
+		};
+
+		let json_str = r#"{"sessionIndex":5,"authorityIndex":1,"heartbeatReceived":true,"authoredBlocks":0,"isOnline":true}"#;
+
+		assert_eq!(serde_json::to_string(&info).unwrap(), json_str);
+		assert_eq!(serde_json::from_str::<HeartbeatInfo>(json_str).unwrap(), info);
+	}
+}
@@ -0,0 +1,104 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! RPC interface for the im-online module.
+
+use std::sync::Arc;
+use codec::Codec;
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use sc_keystore::KeyStorePtr;
+use sc_rpc_api::DenyUnsafe;
+use sp_api::ProvideRuntimeApi;
+use sp_application_crypto::RuntimeAppPublic;
+use sp_blockchain::HeaderBackend;
+use sp_core::traits::BareCryptoStore;
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+pub use pallet_im_online_rpc_runtime_api::{HeartbeatInfo, ImOnlineApi as ImOnlineRuntimeApi};
+pub use self::gen_client::Client as ImOnlineClient;
+pub use performance::{MissedSlot, PerformanceReport, ValidatorApi, ValidatorClient};
+
+mod performance;
+
+#[rpc]
+pub trait ImOnlineApi<BlockHash, AuthorityId> {
+	/// Returns the status in the current session of the heartbeats of the authorities whose
+	/// im-online key is in the keystore.
+	#[rpc(name = "imOnline_localHeartbeats")]
+	fn local_heartbeats(&self, at: Option<BlockHash>) -> Result<Vec<(AuthorityId, HeartbeatInfo)>>;
+}
+
+/// A struct that implements the [`ImOnlineApi`].
+pub struct ImOnline<C, B> {
+	client: Arc<C>,
+	keystore: KeyStorePtr,
+	deny_unsafe: DenyUnsafe,
+	_marker: std::marker::PhantomData<B>,
+}
+
+impl<C, B> ImOnline<C, B> {
+	/// Create new `ImOnline` with the given reference to the client and to the keystore.
+	pub fn new(client: Arc<C>, keystore: KeyStorePtr, deny_unsafe: DenyUnsafe) -> Self {
+		ImOnline { client, keystore, deny_unsafe, _marker: Default::default() }
+	}
+}
+
+/// Error type of this RPC api.
+pub enum Error {
+	/// The call to runtime failed.
+	RuntimeError,
+}
+
+impl From<Error> for i64 {
+	fn from(e: Error) -> i64 {
+		match e {
+			Error::RuntimeError => 1,
+		}
+	}
+}
+
+impl<C, Block, AuthorityId> ImOnlineApi<<Block as BlockT>::Hash, AuthorityId> for ImOnline<C, Block>
+where
+	Block: BlockT,
+	C: Send + Sync + 'static + ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+	C::Api: ImOnlineRuntimeApi<Block, AuthorityId>,
+	AuthorityId: Codec + RuntimeAppPublic,
+{
+	fn local_heartbeats(
+		&self,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Vec<(AuthorityId, HeartbeatInfo)>> {
+		self.deny_unsafe.check_if_safe()?;
+
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(||
+			// If the block hash is not supplied assume the best block.
+			self.client.info().best_hash
+		));
+
+		let heartbeats = api.heartbeats(&at).map_err(|e| RpcError {
+			code: ErrorCode::ServerError(Error::RuntimeError.into()),
+			message: "Unable to query heartbeats.".into(),
+			data: Some(format!("{:?}", e).into()),
+		})?;
+
+		let keystore = self.keystore.read();
+		Ok(heartbeats.into_iter()
+			.filter(|(authority, _)| keystore.has_keys(&[(authority.to_raw_vec(), AuthorityId::ID)]))
+			.collect())
+	}
+}
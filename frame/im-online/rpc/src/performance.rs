@@ -0,0 +1,259 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! RPC reporting the performance of the local validator in a session.
+
+use std::collections::HashMap;
+use codec::{Codec, Decode};
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use sc_client_api::backend::AuxStore;
+use sc_consensus_slots::{SlotOutcome, SlotRecord, slot_records};
+use serde::{Deserialize, Serialize};
+use sp_api::ProvideRuntimeApi;
+use sp_application_crypto::RuntimeAppPublic;
+use sp_blockchain::HeaderBackend;
+use sp_core::traits::BareCryptoStore;
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+use crate::{Error, HeartbeatInfo, ImOnline, ImOnlineRuntimeApi};
+
+pub use self::gen_client::Client as ValidatorClient;
+
+/// A slot of the session in which the local validator didn't author a block.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MissedSlot {
+	/// The slot number.
+	pub slot: u64,
+	/// Why no block was authored.
+	pub reason: String,
+}
+
+/// The performance of the local validator in a session, as observed by the node.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PerformanceReport<AuthorityId> {
+	/// Index of the session.
+	pub session_index: u32,
+	/// Number of slots the authority schedule assigned to the local authorities, i.e. the blocks
+	/// the node was expected to author.
+	pub expected_slots: u32,
+	/// Number of blocks authored and imported by the node.
+	pub authored_blocks: u32,
+	/// Number of expected slots whose authoring was abandoned before it ended, e.g. as the node
+	/// stopped.
+	pub pending_slots: u32,
+	/// The expected slots in which no block was authored.
+	pub missed_slots: Vec<MissedSlot>,
+	/// Average time taken to propose a block, in milliseconds.
+	pub average_proposal_ms: Option<u64>,
+	/// Number of expected slots whose session is unknown, the state of their parent block being
+	/// pruned, between expected slots of the session.
+	pub unresolved_slots: u32,
+	/// The heartbeat status of the local authorities, if the session is the current one.
+	pub heartbeats: Option<Vec<(AuthorityId, HeartbeatInfo)>>,
+}
+
+#[rpc]
+pub trait ValidatorApi<AuthorityId> {
+	/// Returns the performance of the local validator in the session `session_index`: the
+	/// blocks authored against the slots the authority schedule assigned to it, the missed slots
+	/// with the reason, the average proposal time and, for the current session, the heartbeat
+	/// status.
+	///
+	/// Only the slots seen by the slot workers of the node, at most the last
+	/// `sc_consensus_slots::MAX_SLOT_RECORDS`, are reported.
+	#[rpc(name = "validator_performanceReport")]
+	fn performance_report(&self, session_index: u32) -> Result<PerformanceReport<AuthorityId>>;
+}
+
+impl<C, Block, AuthorityId> ValidatorApi<AuthorityId> for ImOnline<C, Block>
+where
+	Block: BlockT,
+	C: Send + Sync + 'static + ProvideRuntimeApi<Block> + HeaderBackend<Block> + AuxStore,
+	C::Api: ImOnlineRuntimeApi<Block, AuthorityId>,
+	AuthorityId: Codec + RuntimeAppPublic,
+{
+	fn performance_report(&self, session_index: u32) -> Result<PerformanceReport<AuthorityId>> {
+		self.deny_unsafe.check_if_safe()?;
+
+		let records = slot_records(&*self.client).map_err(|e| RpcError {
+			code: ErrorCode::ServerError(Error::RuntimeError.into()),
+			message: "Unable to read the recorded slots.".into(),
+			data: Some(format!("{:?}", e).into()),
+		})?;
+		let mut parent_sessions = HashMap::new();
+		let sessions: Vec<_> = records.iter()
+			.map(|record| *parent_sessions
+				.entry(record.parent.clone())
+				.or_insert_with(|| parent_session(&*self.client, &record.parent)))
+			.collect();
+		let (records, unresolved_slots) = session_records(session_index, records, &sessions);
+
+		let best = BlockId::hash(self.client.info().best_hash);
+		let heartbeats = self.client.runtime_api().heartbeats(&best).map_err(|e| RpcError {
+			code: ErrorCode::ServerError(Error::RuntimeError.into()),
+			message: "Unable to query heartbeats.".into(),
+			data: Some(format!("{:?}", e).into()),
+		})?;
+		let current = heartbeats.iter().any(|(_, info)| info.session_index == session_index);
+		let heartbeats = if current {
+			let keystore = self.keystore.read();
+			Some(heartbeats.into_iter()
+				.filter(|(authority, _)| keystore.has_keys(&[(authority.to_raw_vec(), AuthorityId::ID)]))
+				.collect())
+		} else {
+			None
+		};
+
+		Ok(report(session_index, &records, unresolved_slots, heartbeats))
+	}
+}
+
+/// The session of the block with the SCALE encoded hash `parent`, if its state is available.
+fn parent_session<C, Block, AuthorityId>(client: &C, parent: &[u8]) -> Option<u32>
+where
+	Block: BlockT,
+	C: ProvideRuntimeApi<Block>,
+	C::Api: ImOnlineRuntimeApi<Block, AuthorityId>,
+	AuthorityId: Codec,
+{
+	let parent = Block::Hash::decode(&mut &parent[..]).ok()?;
+	let heartbeats = client.runtime_api().heartbeats(&BlockId::hash(parent)).ok()?;
+	heartbeats.first().map(|(_, info)| info.session_index)
+}
+
+/// The records of the session `session_index`, given the sessions of the `records`, and the
+/// number of records of unknown session between records of the session.
+///
+/// The sessions follow each other by slot, so that a slot between two slots of a session is in
+/// it.
+fn session_records(
+	session_index: u32,
+	records: Vec<SlotRecord>,
+	sessions: &[Option<u32>],
+) -> (Vec<SlotRecord>, u32) {
+	let mut unresolved_slots = 0;
+	// The session of the previous record of known session, and the number of records of unknown
+	// session since.
+	let mut previous = None;
+	let mut unresolved = 0;
+	let mut session_records = Vec::new();
+	for (record, session) in records.into_iter().zip(sessions) {
+		match session {
+			Some(session) => {
+				if previous == Some(session_index) && *session == session_index {
+					unresolved_slots += unresolved;
+				}
+				previous = Some(*session);
+				unresolved = 0;
+				if *session == session_index {
+					session_records.push(record);
+				}
+			},
+			None => unresolved += 1,
+		}
+	}
+	(session_records, unresolved_slots)
+}
+
+/// The report of the expected slots of a session.
+fn report<AuthorityId>(
+	session_index: u32,
+	records: &[SlotRecord],
+	unresolved_slots: u32,
+	heartbeats: Option<Vec<(AuthorityId, HeartbeatInfo)>>,
+) -> PerformanceReport<AuthorityId> {
+	let count = |outcome: fn(&SlotOutcome) -> bool| {
+		records.iter().filter(|record| outcome(&record.outcome)).count() as u32
+	};
+	let proposal_times: Vec<_> = records.iter().filter_map(|record| record.proposal_ms).collect();
+	let average_proposal_ms = if proposal_times.is_empty() {
+		None
+	} else {
+		let total: u128 = proposal_times.iter().map(|ms| *ms as u128).sum();
+		Some((total / proposal_times.len() as u128) as u64)
+	};
+	PerformanceReport {
+		session_index,
+		expected_slots: records.len() as u32,
+		authored_blocks: count(|outcome| *outcome == SlotOutcome::Authored),
+		pending_slots: count(|outcome| *outcome == SlotOutcome::Pending),
+		missed_slots: records.iter().filter_map(|record| match &record.outcome {
+			SlotOutcome::Missed(reason) => Some(MissedSlot { slot: record.slot, reason: reason.clone() }),
+			_ => None,
+		}).collect(),
+		average_proposal_ms,
+		unresolved_slots,
+		heartbeats,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn record(slot: u64, proposal_ms: Option<u64>, outcome: SlotOutcome) -> SlotRecord {
+		SlotRecord {
+			slot,
+			engine: "babe".into(),
+			parent: Vec::new(),
+			proposal_ms,
+			outcome,
+		}
+	}
+
+	#[test]
+	fn should_report_the_expected_slots() {
+		let records = vec![
+			record(1, Some(100), SlotOutcome::Authored),
+			record(2, Some(300), SlotOutcome::Authored),
+			record(3, None, SlotOutcome::Missed("Block production took too long".into())),
+			record(4, None, SlotOutcome::Pending),
+		];
+
+		let report = report::<u64>(5, &records, 1, None);
+
+		assert_eq!(report, PerformanceReport {
+			session_index: 5,
+			expected_slots: 4,
+			authored_blocks: 2,
+			pending_slots: 1,
+			missed_slots: vec![MissedSlot { slot: 3, reason: "Block production took too long".into() }],
+			average_proposal_ms: Some(200),
+			unresolved_slots: 1,
+			heartbeats: None,
+		});
+		assert_eq!(
+			serde_json::to_string(&report.missed_slots).unwrap(),
+			r#"[{"slot":3,"reason":"Block production took too long"}]"#,
+		);
+	}
+
+	#[test]
+	fn should_count_the_unresolved_slots_of_the_session() {
+		let records: Vec<_> = (0..8).map(|slot| record(slot, None, SlotOutcome::Authored)).collect();
+		let sessions = [None, Some(4), None, Some(5), None, None, Some(5), None];
+
+		let (records, unresolved_slots) = session_records(5, records, &sessions);
+
+		assert_eq!(records.iter().map(|record| record.slot).collect::<Vec<_>>(), vec![3, 6]);
+		// Only the slots between slots of the session are known to be in it.
+		assert_eq!(unresolved_slots, 2);
+		assert_eq!(session_records(4, Vec::new(), &[]).1, 0);
+	}
+}
@@ -39,6 +39,9 @@
 //! ### Public Functions
 //!
 //! - `is_online` - True if the validator sent a heartbeat in the current session.
+//! - `heartbeats` - The heartbeat status of every authority in the current session, exposed to
+//!   the node through the [`ImOnlineApi`](../pallet_im_online_rpc_runtime_api/trait.ImOnlineApi.html)
+//!   runtime API.
 //!
 //! ## Usage
 //!
@@ -78,6 +81,8 @@ use codec::{Encode, Decode};
 use sp_core::offchain::OpaqueNetworkState;
 use sp_std::prelude::*;
 use sp_std::convert::TryInto;
+#[cfg(feature = "std")]
+use serde::{Serialize, Deserialize};
 use pallet_session::historical::IdentificationTuple;
 use sp_runtime::{
 	offchain::storage::StorageValueRef,
@@ -103,6 +108,18 @@ use frame_system::offchain::{
 	SubmitTransaction,
 };
 
+/// Tracing target used for heartbeat creation, submission and inclusion.
+///
+/// The heartbeats submitted and included, and the authorities found offline at the end of the
+/// sessions, are also counted with `sp_tracing::counter!`, which the node exposes as the
+/// `substrate_runtime_counters_total` Prometheus metric.
+pub const LOG_TARGET: &str = "imonline";
+
+/// See [`TraceSpans`](frame_support::debug::TraceSpans).
+pub const TRACE_SPANS: frame_support::debug::TraceSpans = &[
+	(module_path!(), "heartbeat"),
+];
+
 pub mod sr25519 {
 	mod app_sr25519 {
 		use sp_application_crypto::{app_crypto, key_types::IM_ONLINE, sr25519};
@@ -226,6 +243,23 @@ pub struct Heartbeat<BlockNumber>
 	pub validators_len: u32,
 }
 
+/// Status of the heartbeat of an authority in the current session, see [`Module::heartbeats`].
+#[derive(Eq, PartialEq, Encode, Decode, Default, Clone)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct HeartbeatInfo {
+	/// Index of the current session.
+	pub session_index: u32,
+	/// Index of the authority on the list of validators.
+	pub authority_index: u32,
+	/// Whether a heartbeat of the authority was included in the current session.
+	pub heartbeat_received: bool,
+	/// Number of blocks authored by the authority in the current session.
+	pub authored_blocks: u32,
+	/// Whether the authority is considered online, i.e. it sent a heartbeat or authored a block.
+	pub is_online: bool,
+}
+
 pub trait WeightInfo {
 	fn heartbeat(k: u32, e: u32, ) -> Weight;
 	fn validate_unsigned(k: u32, e: u32, ) -> Weight;
@@ -366,6 +400,25 @@ decl_module! {
 			);
 			let keys = Keys::<T>::get();
 			let public = keys.get(heartbeat.authority_index as usize);
+			frame_support::sp_tracing::event!(debug;
+				target: LOG_TARGET,
+				session_index = current_session,
+				heartbeat_session_index = heartbeat.session_index,
+				authority_index = heartbeat.authority_index,
+				block_number = ?heartbeat.block_number,
+				duplicated = exists,
+				known_key = public.is_some(),
+				"Heartbeat included",
+			);
+			frame_support::sp_tracing::counter!(
+				"im_online_heartbeats_included",
+				match (exists, public.is_some()) {
+					(true, _) => "duplicated",
+					(false, false) => "unknown_key",
+					(false, true) => "new",
+				},
+				1
+			);
 			if let (false, Some(public)) = (exists, public) {
 				Self::deposit_event(Event::<T>::HeartbeatReceived(public.clone()));
 
@@ -455,6 +508,29 @@ impl<T: Trait> Module<T> {
 		<ReceivedHeartbeats>::contains_key(&current_session, &authority_index)
 	}
 
+	/// Returns the heartbeat status of every authority of the current session.
+	pub fn heartbeats() -> Vec<(T::AuthorityId, HeartbeatInfo)> {
+		let session_index = <pallet_session::Module<T>>::current_index();
+		let current_validators = <pallet_session::Module<T>>::validators();
+
+		Keys::<T>::get().into_iter()
+			.enumerate()
+			.map(|(index, key)| {
+				let authority_index = index as AuthIndex;
+				let authored_blocks = current_validators.get(index)
+					.map_or(0, |validator| <AuthoredBlocks<T>>::get(&session_index, validator));
+				let heartbeat_received = <ReceivedHeartbeats>::contains_key(&session_index, &authority_index);
+				(key, HeartbeatInfo {
+					session_index,
+					authority_index,
+					heartbeat_received,
+					authored_blocks,
+					is_online: heartbeat_received || authored_blocks != 0,
+				})
+			})
+			.collect()
+	}
+
 	/// Note that the given authority has authored a block in the current session.
 	fn note_authorship(author: T::ValidatorId) {
 		let current_session = <pallet_session::Module<T>>::current_index();
@@ -497,6 +573,11 @@ impl<T: Trait> Module<T> {
 		block_number: T::BlockNumber,
 		validators_len: u32,
 	) -> OffchainResult<T, ()> {
+		frame_support::sp_tracing::enter_span!("heartbeat";
+			session_index,
+			authority_index,
+			block_number = ?block_number,
+		);
 		// A helper function to prepare heartbeat call.
 		let prepare_heartbeat = || -> OffchainResult<T, Call<T>> {
 			let network_state = sp_io::offchain::network_state()
@@ -535,10 +616,20 @@ impl<T: Trait> Module<T> {
 					call,
 				);
 
-				SubmitTransaction::<T, Call<T>>::submit_unsigned_transaction(call.into())
-					.map_err(|_| OffchainErr::SubmitTransaction)?;
+				let result = SubmitTransaction::<T, Call<T>>::submit_unsigned_transaction(call.into())
+					.map_err(|_| OffchainErr::SubmitTransaction);
+				frame_support::sp_tracing::event!(debug;
+					target: LOG_TARGET,
+					result = ?result,
+					"Heartbeat submitted",
+				);
+				frame_support::sp_tracing::counter!(
+					"im_online_heartbeats_submitted",
+					if result.is_ok() { "ok" } else { "failed" },
+					1
+				);
 
-				Ok(())
+				result
 			},
 		)
 	}
@@ -664,6 +755,16 @@ impl<T: Trait> pallet_session::OneSessionHandler<T::AccountId> for Module<T> {
 				T::FullIdentificationOf::convert(id.clone()).map(|full_id| (id, full_id))
 			).collect::<Vec<IdentificationTuple<T>>>();
 
+		frame_support::sp_tracing::event!(info;
+			target: LOG_TARGET,
+			session_index,
+			validators = keys.len() as u64,
+			offline = offenders.len() as u64,
+			"Session ending",
+		);
+		frame_support::sp_tracing::counter!("im_online_sessions_ended", "validators", keys.len());
+		frame_support::sp_tracing::counter!("im_online_sessions_ended", "offline", offenders.len());
+
 		// Remove all received heartbeats and number of authored blocks from the
 		// current session, they have already been processed and won't be needed
 		// anymore.
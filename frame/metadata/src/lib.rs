@@ -423,3 +423,54 @@ impl Into<RuntimeMetadataPrefixed> for RuntimeMetadataLastVersion {
 		RuntimeMetadataPrefixed(META_RESERVED, RuntimeMetadata::V11(self))
 	}
 }
+
+/// The tracing target of a module and the names of the spans the module emits under it.
+#[derive(Clone, PartialEq, Eq, Encode, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Decode, Serialize))]
+pub struct TraceTargetMetadata {
+	/// Name of the module in the runtime.
+	pub module: DecodeDifferentStr,
+	/// Target of the spans of the module, its path in its crate.
+	pub target: DecodeDifferentStr,
+	/// Names of the spans of the dispatchables and hooks of the module, and of its custom spans.
+	pub spans: DecodeDifferent<Vec<&'static str>, Vec<StringBuf>>,
+}
+
+/// The tracing targets and span names of the modules of the runtime, for the tools filtering
+/// the traces. They are not part of the `RuntimeMetadata`, and are returned by a runtime API of
+/// their own.
+#[derive(Clone, PartialEq, Eq, Encode, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Decode, Serialize))]
+pub struct RuntimeTraceMetadata {
+	pub targets: Vec<TraceTargetMetadata>,
+}
+
+impl RuntimeTraceMetadata {
+	/// Add the custom spans, as `(target, name)`, that `module` emits besides the spans of its
+	/// dispatchables and hooks, e.g. the `TRACE_SPANS` of its crate, see
+	/// `frame_support::debug::TraceSpans`.
+	pub fn with_spans(mut self, module: &'static str, spans: &[(&'static str, &'static str)]) -> Self {
+		for (target, name) in spans {
+			let entry = self.targets.iter_mut().find(|entry| match (&entry.module, &entry.target) {
+				(DecodeDifferent::Encode(m), DecodeDifferent::Encode(t)) => *m == module && t == target,
+				_ => false,
+			});
+			match entry {
+				Some(TraceTargetMetadata { spans: DecodeDifferent::Encode(names), .. }) => names.push(*name),
+				Some(_) => (),
+				None => self.targets.push(TraceTargetMetadata {
+					module: DecodeDifferent::Encode(module),
+					target: DecodeDifferent::Encode(target),
+					spans: DecodeDifferent::Encode(sp_std::vec![*name]),
+				}),
+			}
+		}
+		self
+	}
+}
+
+impl Into<sp_core::OpaqueMetadata> for RuntimeTraceMetadata {
+	fn into(self) -> sp_core::OpaqueMetadata {
+		sp_core::OpaqueMetadata::new(self.encode())
+	}
+}
@@ -52,7 +52,7 @@ use sp_io::hashing::blake2_256;
 use frame_support::{decl_module, decl_event, decl_error, decl_storage, Parameter, ensure, RuntimeDebug};
 use frame_support::{traits::{Get, ReservableCurrency, Currency},
 	weights::{Weight, GetDispatchInfo, constants::{WEIGHT_PER_NANOS, WEIGHT_PER_MICROS}},
-	dispatch::{DispatchResultWithPostInfo, DispatchErrorWithPostInfo, PostDispatchInfo},
+	dispatch::{DispatchResultWithPostInfo, DispatchErrorWithPostInfo, GetCallMetadata, PostDispatchInfo},
 };
 use frame_system::{self as system, ensure_signed, RawOrigin};
 use sp_runtime::{DispatchError, DispatchResult, traits::{Dispatchable, Zero}};
@@ -60,6 +60,14 @@ use sp_runtime::{DispatchError, DispatchResult, traits::{Dispatchable, Zero}};
 mod tests;
 mod benchmarking;
 
+/// Tracing target used for the calls dispatched by a multisig.
+pub const LOG_TARGET: &str = "multisig";
+
+/// See [`TraceSpans`](frame_support::debug::TraceSpans).
+pub const TRACE_SPANS: frame_support::debug::TraceSpans = &[
+	(module_path!(), "multisig_call"),
+];
+
 type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as frame_system::Trait>::AccountId>>::Balance;
 /// Just a bunch of bytes, but they should decode to a valid `Call`.
 pub type OpaqueCall = Vec<u8>;
@@ -97,7 +105,7 @@ pub trait Trait: frame_system::Trait {
 
 	/// The overarching call type.
 	type Call: Parameter + Dispatchable<Origin=Self::Origin, PostInfo=PostDispatchInfo>
-		+ GetDispatchInfo + From<frame_system::Call<Self>>;
+		+ GetDispatchInfo + GetCallMetadata + From<frame_system::Call<Self>>;
 
 	/// The currency mechanism.
 	type Currency: ReservableCurrency<Self::AccountId>;
@@ -300,7 +308,19 @@ decl_module! {
 			let id = Self::multi_account_id(&signatories, 1);
 
 			let call_len = call.using_encoded(|c| c.len());
+			frame_support::sp_tracing::enter_span!("multisig_call";
+				multisig = ?id,
+				threshold = 1,
+				signatories = ?signatories,
+				pallet = call.get_call_metadata().pallet_name,
+				call = call.get_call_metadata().function_name,
+			);
 			let result = call.dispatch(RawOrigin::Signed(id).into());
+			frame_support::sp_tracing::event!(debug;
+				target: LOG_TARGET,
+				result = ?result.as_ref().map(|_| ()).map_err(|e| e.error),
+				"Multisig call dispatched",
+			);
 
 			result.map(|post_dispatch_info| post_dispatch_info.actual_weight
 				.map(|actual_weight| weight_of::as_multi_threshold_1::<T>(
@@ -575,7 +595,20 @@ impl<T: Trait> Module<T> {
 				Self::clear_call(&call_hash);
 				T::Currency::unreserve(&m.depositor, m.deposit);
 
+				frame_support::sp_tracing::enter_span!("multisig_call";
+					multisig = ?id,
+					threshold,
+					approvals = ?m.approvals,
+					executor = ?who,
+					pallet = call.get_call_metadata().pallet_name,
+					call = call.get_call_metadata().function_name,
+				);
 				let result = call.dispatch(RawOrigin::Signed(id.clone()).into());
+				frame_support::sp_tracing::event!(debug;
+					target: LOG_TARGET,
+					result = ?result.as_ref().map(|_| ()).map_err(|e| e.error),
+					"Multisig call dispatched",
+				);
 				Self::deposit_event(RawEvent::MultisigExecuted(
 					who, timepoint, id, call_hash, result.map(|_| ()).map_err(|e| e.error)
 				));
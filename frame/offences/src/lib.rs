@@ -38,6 +38,9 @@ use sp_staking::{
 };
 use codec::{Encode, Decode};
 
+/// Tracing target of offence reports and of the slashes they result in.
+pub const LOG_TARGET: &str = "offences";
+
 /// A binary blob which represents a SCALE codec-encoded `O::TimeSlot`.
 type OpaqueTimeSlot = Vec<u8>;
 
@@ -139,6 +142,14 @@ decl_module! {
 						// should not happen if staking's `can_report` is implemented properly.
 						match T::OnOffenceHandler::on_offence(&offences, &perbill, *session) {
 							Ok(weight) => {
+								frame_support::sp_tracing::event!(
+									warn;
+									target: LOG_TARGET,
+									session_index = *session,
+									offenders = ?offences.iter().map(|d| &d.offender).collect::<Vec<_>>(),
+									slash_fractions = ?perbill,
+									"Deferred offence applied",
+								);
 								consumed += weight;
 								false
 							},
@@ -196,6 +207,19 @@ where
 			offence.session_index(),
 		);
 
+		frame_support::sp_tracing::event!(
+			warn;
+			target: LOG_TARGET,
+			kind = sp_std::str::from_utf8(&O::ID).unwrap_or_default(),
+			session_index = offence.session_index(),
+			time_slot = ?time_slot.encode(),
+			offenders = ?concurrent_offenders.iter().map(|d| &d.offender).collect::<Vec<_>>(),
+			validator_set_count,
+			slash_fraction = ?new_fraction,
+			applied,
+			"Offence reported",
+		);
+
 		// Deposit the event.
 		Self::deposit_event(Event::Offence(O::ID, time_slot.encode(), applied));
 
@@ -46,7 +46,7 @@ use frame_support::{
 	decl_module, decl_event, decl_error, decl_storage, Parameter, ensure, RuntimeDebug, traits::{
 		Get, ReservableCurrency, Currency, InstanceFilter, OriginTrait, IsType,
 	}, weights::{Weight, GetDispatchInfo},
-	dispatch::{PostDispatchInfo, IsSubType}, storage::IterableStorageMap,
+	dispatch::{GetCallMetadata, PostDispatchInfo, IsSubType}, storage::IterableStorageMap,
 };
 use frame_system::{self as system, ensure_signed};
 use frame_support::dispatch::DispatchError;
@@ -55,6 +55,14 @@ mod tests;
 mod benchmarking;
 mod default_weight;
 
+/// Tracing target used for the calls dispatched by a proxy.
+pub const LOG_TARGET: &str = "proxy";
+
+/// See [`TraceSpans`](frame_support::debug::TraceSpans).
+pub const TRACE_SPANS: frame_support::debug::TraceSpans = &[
+	(module_path!(), "proxied_call"),
+];
+
 type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as frame_system::Trait>::AccountId>>::Balance;
 
 pub trait WeightInfo {
@@ -77,7 +85,7 @@ pub trait Trait: frame_system::Trait {
 
 	/// The overarching call type.
 	type Call: Parameter + Dispatchable<Origin=Self::Origin, PostInfo=PostDispatchInfo>
-		+ GetDispatchInfo + From<frame_system::Call<Self>> + IsSubType<Call<Self>>
+		+ GetDispatchInfo + GetCallMetadata + From<frame_system::Call<Self>> + IsSubType<Call<Self>>
 		+ IsType<<Self as frame_system::Trait>::Call>;
 
 	/// The currency mechanism.
@@ -656,6 +664,14 @@ impl<T: Trait> Module<T> {
 		real: T::AccountId,
 		call: <T as Trait>::Call,
 	) {
+		frame_support::sp_tracing::enter_span!("proxied_call";
+			real = ?real,
+			delegate = ?def.delegate,
+			proxy_type = ?def.proxy_type,
+			delay = ?def.delay,
+			pallet = call.get_call_metadata().pallet_name,
+			call = call.get_call_metadata().function_name,
+		);
 		// This is a freshly authenticated new account, the origin restrictions doesn't apply.
 		let mut origin: T::Origin = frame_system::RawOrigin::Signed(real).into();
 		origin.add_filter(move |c: &<T as frame_system::Trait>::Call| {
@@ -672,6 +688,11 @@ impl<T: Trait> Module<T> {
 			}
 		});
 		let e = call.dispatch(origin);
+		frame_support::sp_tracing::event!(debug;
+			target: LOG_TARGET,
+			result = ?e.as_ref().map(|_| ()).map_err(|e| e.error),
+			"Proxied call dispatched",
+		);
 		Self::deposit_event(RawEvent::ProxyExecuted(e.map(|_| ()).map_err(|e| e.error)));
 	}
 }
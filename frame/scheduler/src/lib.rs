@@ -58,7 +58,7 @@ use codec::{Encode, Decode, Codec};
 use sp_runtime::{RuntimeDebug, traits::{Zero, One, BadOrigin, Saturating}};
 use frame_support::{
 	decl_module, decl_storage, decl_event, decl_error, IterableStorageMap,
-	dispatch::{Dispatchable, DispatchError, DispatchResult, Parameter},
+	dispatch::{Dispatchable, DispatchError, DispatchResult, GetCallMetadata, Parameter},
 	traits::{Get, schedule::{self, DispatchTime}, OriginTrait, EnsureOrigin, IsType},
 	weights::{GetDispatchInfo, Weight},
 };
@@ -97,7 +97,8 @@ pub trait Trait: system::Trait {
 	type PalletsOrigin: From<system::RawOrigin<Self::AccountId>> + Codec + Clone + Eq;
 
 	/// The aggregated call type.
-	type Call: Parameter + Dispatchable<Origin=<Self as Trait>::Origin> + GetDispatchInfo + From<system::Call<Self>>;
+	type Call: Parameter + Dispatchable<Origin=<Self as Trait>::Origin> + GetDispatchInfo + GetCallMetadata
+		+ From<system::Call<Self>>;
 
 	/// The maximum weight that may be scheduled per block for any dispatchables of less priority
 	/// than `schedule::HARD_DEADLINE`.
@@ -110,6 +111,14 @@ pub trait Trait: system::Trait {
 	type WeightInfo: WeightInfo;
 }
 
+/// Tracing target used for the execution of the agenda.
+pub const LOG_TARGET: &str = "scheduler";
+
+/// See [`TraceSpans`](frame_support::debug::TraceSpans).
+pub const TRACE_SPANS: frame_support::debug::TraceSpans = &[
+	(module_path!(), "scheduled_call"),
+];
+
 /// Just a simple index for naming period tasks.
 pub type PeriodicIndex = u32;
 /// The location of a scheduled task that can be used to remove it.
@@ -344,7 +353,7 @@ decl_module! {
 			let base_weight: Weight = T::DbWeight::get().reads_writes(1, 2) // Agenda + Agenda(next)
 				.saturating_add(10_000_000); // Base Weight
 			let mut total_weight: Weight = 0;
-			queued.into_iter()
+			let postponed = queued.into_iter()
 				.enumerate()
 				.scan(base_weight, |cumulative_weight, (order, (index, s))| {
 					*cumulative_weight = cumulative_weight
@@ -363,12 +372,34 @@ decl_module! {
 					Some((order, index, *cumulative_weight, s))
 				})
 				.filter_map(|(order, index, cumulative_weight, mut s)| {
+					frame_support::sp_tracing::enter_span!("scheduled_call";
+						when = ?now,
+						index,
+						pallet = s.call.get_call_metadata().pallet_name,
+						call = s.call.get_call_metadata().function_name,
+						origin = <<T as Trait>::Origin as From<T::PalletsOrigin>>::from(s.origin.clone()).kind(),
+						weight = s.call.get_dispatch_info().weight,
+						priority = s.priority,
+					);
 					// We allow a scheduled call if any is true:
 					// - It's priority is `HARD_DEADLINE`
 					// - It does not push the weight past the limit.
 					// - It is the first item in the schedule
 					if s.priority <= schedule::HARD_DEADLINE || cumulative_weight <= limit || order == 0 {
 						let r = s.call.clone().dispatch(s.origin.clone().into());
+						if r.is_ok() {
+							frame_support::sp_tracing::event!(debug;
+								target: LOG_TARGET,
+								result = ?r.as_ref().map(|_| ()).map_err(|e| e.error),
+								"Scheduled call dispatched",
+							);
+						} else {
+							frame_support::sp_tracing::event!(warn;
+								target: LOG_TARGET,
+								result = ?r.as_ref().map(|_| ()).map_err(|e| e.error),
+								"Scheduled call failed",
+							);
+						}
 						let maybe_id = s.maybe_id.clone();
 						if let &Some((period, count)) = &s.maybe_periodic {
 							if count > 1 {
@@ -396,13 +427,29 @@ decl_module! {
 						total_weight = cumulative_weight;
 						None
 					} else {
+						frame_support::sp_tracing::event!(debug;
+							target: LOG_TARGET,
+							cumulative_weight,
+							"Scheduled call postponed",
+						);
 						Some(Some(s))
 					}
 				})
-				.for_each(|unused| {
-					let next = now + One::one();
-					Agenda::<T>::append(next, unused);
-				});
+				.collect::<Vec<_>>();
+
+			if !postponed.is_empty() {
+				frame_support::sp_tracing::event!(warn;
+					target: LOG_TARGET,
+					when = ?now,
+					postponed = postponed.len() as u64,
+					limit,
+					"Weight limit reached, scheduled calls postponed to the next block",
+				);
+			}
+			let next = now + One::one();
+			for unused in postponed {
+				Agenda::<T>::append(next, unused);
+			}
 
 			total_weight
 		}
@@ -123,6 +123,9 @@ mod tests;
 #[cfg(feature = "historical")]
 pub mod historical;
 
+/// Tracing target of session key changes.
+pub const LOG_TARGET: &str = "session";
+
 /// Decides whether the session should be ended.
 pub trait ShouldEndSession<BlockNumber> {
 	/// Return `true` if the session should be ended.
@@ -647,6 +650,22 @@ impl<T: Trait> Module<T> {
 			(queued_amalgamated, changed)
 		};
 
+		frame_support::sp_tracing::if_tracing!({
+			for (validator, keys) in &queued_amalgamated {
+				let active = session_keys.iter().find(|(v, _)| v == validator).map(|(_, k)| k);
+				if active != Some(keys) {
+					frame_support::sp_tracing::event!(
+						info;
+						target: LOG_TARGET,
+						validator = ?validator,
+						keys = ?keys,
+						active_from_session = session_index + 1,
+						"Session keys queued",
+					);
+				}
+			}
+		});
+
 		<QueuedKeys<T>>::put(queued_amalgamated.clone());
 		QueuedChanged::put(next_changed);
 
@@ -740,6 +759,16 @@ impl<T: Trait> Module<T> {
 			Self::put_key_owner(*id, key, who);
 		}
 
+		frame_support::sp_tracing::event!(
+			info;
+			target: LOG_TARGET,
+			validator = ?who,
+			old_keys = ?old_keys,
+			new_keys = ?keys,
+			block = ?<frame_system::Module<T>>::block_number(),
+			"Session keys set, they are queued at the end of the session and active in the next one",
+		);
+
 		Self::put_keys(who, &keys);
 		Ok(old_keys)
 	}
@@ -758,6 +787,11 @@ impl<T: Trait> Module<T> {
 		Ok(())
 	}
 
+	/// The session keys set by `v` for the next sessions, if any.
+	pub fn next_keys(v: &T::ValidatorId) -> Option<T::Keys> {
+		Self::load_keys(v)
+	}
+
 	fn load_keys(v: &T::ValidatorId) -> Option<T::Keys> {
 		<NextKeys<T>>::get(v)
 	}
@@ -336,6 +336,20 @@ pub const MAX_NOMINATIONS: usize = <CompactAssignments as VotingLimit>::LIMIT;
 
 pub(crate) const LOG_TARGET: &'static str = "staking";
 
+/// Tracing target of slashes, the same as the one of the offences they result from.
+pub const OFFENCE_LOG_TARGET: &'static str = "offences";
+
+/// See [`TraceSpans`](frame_support::debug::TraceSpans).
+pub const TRACE_SPANS: frame_support::debug::TraceSpans = &[
+	(module_path!(), "election"),
+	(module_path!(), "election_snapshot"),
+	(module_path!(), "election_phragmen"),
+	(module_path!(), "election_score"),
+	(module_path!(), "election_feasibility_check"),
+	(concat!(module_path!(), "::offchain_election"), "election_prepare_submission"),
+	(concat!(module_path!(), "::offchain_election"), "election_score"),
+];
+
 // syntactic sugar for logging.
 #[macro_export]
 macro_rules! log {
@@ -2311,6 +2325,7 @@ impl<T: Trait> Module<T> {
 	/// This data is used to efficiently evaluate election results. returns `true` if the operation
 	/// is successful.
 	pub fn create_stakers_snapshot() -> (bool, Weight) {
+		frame_support::sp_tracing::enter_span!("election_snapshot");
 		let mut consumed_weight = 0;
 		let mut add_db_reads_writes = |reads, writes| {
 			consumed_weight += T::DbWeight::get().reads_writes(reads, writes);
@@ -2321,6 +2336,12 @@ impl<T: Trait> Module<T> {
 		let num_validators = validators.len();
 		let num_nominators = nominators.len();
 		add_db_reads_writes((num_validators + num_nominators) as Weight, 0);
+		frame_support::sp_tracing::event!(debug;
+			target: LOG_TARGET,
+			validators = num_validators as u64,
+			nominators = num_nominators as u64,
+			"Creating stakers snapshot",
+		);
 
 		if
 			num_validators > MAX_VALIDATORS ||
@@ -2574,6 +2595,14 @@ impl<T: Trait> Module<T> {
 		era: EraIndex,
 		election_size: ElectionSize,
 	) -> DispatchResultWithPostInfo {
+		frame_support::sp_tracing::enter_span!("election_feasibility_check";
+			compute = ?compute,
+			era,
+			claimed_score = ?claimed_score,
+			winners = winners.len() as u64,
+			validators = election_size.validators as u64,
+			nominators = election_size.nominators as u64,
+		);
 		// Do the basic checks. era, claimed score and window open.
 		Self::pre_dispatch_checks(claimed_score, era)?;
 		// the weight that we will refund in case of a correct submission. We compute this now
@@ -2716,7 +2745,10 @@ impl<T: Trait> Module<T> {
 		ensure!(num_error == 0, Error::<T>::OffchainElectionBogusEdge);
 
 		// Check if the score is the same as the claimed one.
-		let submitted_score = evaluate_support(&supports);
+		let submitted_score = {
+			frame_support::sp_tracing::enter_span!("election_score");
+			evaluate_support(&supports)
+		};
 		ensure!(submitted_score == claimed_score, Error::<T>::OffchainElectionBogusScore);
 
 		// At last, alles Ok. Exposures and store the result.
@@ -2884,6 +2916,7 @@ impl<T: Trait> Module<T> {
 	///
 	/// This should only be called at the end of an era.
 	fn select_and_update_validators(current_era: EraIndex) -> Option<Vec<T::AccountId>> {
+		frame_support::sp_tracing::enter_span!("election"; era = current_era);
 		if let Some(ElectionResult::<T::AccountId, BalanceOf<T>> {
 			elected_stashes,
 			exposures,
@@ -3036,12 +3069,23 @@ impl<T: Trait> Module<T> {
 			(n, s, ns)
 		}));
 
-		seq_phragmen::<_, Accuracy>(
+		frame_support::sp_tracing::enter_span!("election_phragmen";
+			candidates = all_validators.len() as u64,
+			voters = all_nominators.len() as u64,
+			desired = Self::validator_count(),
+		);
+		let result = seq_phragmen::<_, Accuracy>(
 			Self::validator_count() as usize,
 			Self::minimum_validator_count().max(1) as usize,
 			all_validators,
 			all_nominators,
-		)
+		);
+		frame_support::sp_tracing::event!(debug;
+			target: LOG_TARGET,
+			winners = ?result.as_ref().map(|r| r.winners.len()),
+			"Phragmen election computed",
+		);
+		result
 	}
 
 	/// Consume a set of [`Supports`] from [`sp_npos_elections`] and collect them into a [`Exposure`]
@@ -3354,6 +3398,12 @@ for Module<T> where
 
 			// Skip if the validator is invulnerable.
 			if invulnerables.contains(stash) {
+				frame_support::sp_tracing::event!(
+					info;
+					target: OFFENCE_LOG_TARGET,
+					stash = ?stash,
+					"Invulnerable offender not slashed",
+				);
 				continue
 			}
 
@@ -3377,6 +3427,18 @@ for Module<T> where
 					add_db_reads_writes(rw, rw);
 				}
 				unapplied.reporters = details.reporters.clone();
+				frame_support::sp_tracing::event!(
+					warn;
+					target: OFFENCE_LOG_TARGET,
+					stash = ?stash,
+					slash_fraction = ?slash_fraction,
+					slash_session,
+					slash_era,
+					own = ?unapplied.own,
+					nominators = nominators_len,
+					deferred = slash_defer_duration != 0,
+					"Slash computed",
+				);
 				if slash_defer_duration == 0 {
 					// apply right away.
 					slashing::apply_slash::<T>(unapplied);
@@ -148,6 +148,11 @@ pub fn prepare_submission<T: Trait>(
 ), OffchainElectionError> where
 	ExtendedBalance: From<<OffchainAccuracy as PerThing>::Inner>,
 {
+	frame_support::sp_tracing::enter_span!("election_prepare_submission";
+		winners = winners.len() as u64,
+		assignments = assignments.len() as u64,
+		do_reduce,
+	);
 	// make sure that the snapshot is available.
 	let snapshot_validators =
 		<Module<T>>::snapshot_validators().ok_or(OffchainElectionError::SnapshotUnavailable)?;
@@ -215,6 +220,7 @@ pub fn prepare_submission<T: Trait>(
 	// assignment set is also all multiples of this value. After reduce, this no longer holds. Hence
 	// converting to ratio thereafter is not trivially reversible.
 	let score = {
+		frame_support::sp_tracing::enter_span!("election_score");
 		let staked = sp_npos_elections::assignment_ratio_to_staked(
 			low_accuracy_assignment.clone(),
 			<Module<T>>::slashable_balance_of_vote_weight,
@@ -633,6 +633,17 @@ pub(crate) fn apply_slash<T: Trait>(unapplied_slash: UnappliedSlash<T::AccountId
 		);
 	}
 
+	frame_support::sp_tracing::event!(
+		warn;
+		target: crate::OFFENCE_LOG_TARGET,
+		validator = ?unapplied_slash.validator,
+		own = ?unapplied_slash.own,
+		others = ?unapplied_slash.others,
+		reporters = ?unapplied_slash.reporters,
+		payout = ?unapplied_slash.payout,
+		"Slash applied",
+	);
+
 	pay_reporters::<T>(reward_payout, slashed_imbalance, &unapplied_slash.reporters);
 }
 
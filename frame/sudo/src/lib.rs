@@ -96,7 +96,7 @@ use frame_support::{
 use frame_support::{
 	weights::{Weight, GetDispatchInfo, Pays},
 	traits::UnfilteredDispatchable,
-	dispatch::DispatchResultWithPostInfo,
+	dispatch::{DispatchResultWithPostInfo, GetCallMetadata},
 };
 use frame_system::ensure_signed;
 
@@ -110,9 +110,17 @@ pub trait Trait: frame_system::Trait {
 	type Event: From<Event<Self>> + Into<<Self as frame_system::Trait>::Event>;
 
 	/// A sudo-able call.
-	type Call: Parameter + UnfilteredDispatchable<Origin=Self::Origin> + GetDispatchInfo;
+	type Call: Parameter + UnfilteredDispatchable<Origin=Self::Origin> + GetDispatchInfo + GetCallMetadata;
 }
 
+/// Tracing target of the audit spans and events emitted for every privileged action.
+pub const AUDIT_TARGET: &str = "sudo";
+
+/// See [`TraceSpans`](frame_support::debug::TraceSpans).
+pub const TRACE_SPANS: frame_support::debug::TraceSpans = &[
+	(AUDIT_TARGET, "sudo_call"),
+];
+
 decl_module! {
 	/// Sudo module declaration.
 	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
@@ -136,7 +144,19 @@ decl_module! {
 			let sender = ensure_signed(origin)?;
 			ensure!(sender == Self::key(), Error::<T>::RequireSudo);
 
+			frame_support::sp_tracing::enter_span!(target: AUDIT_TARGET, "sudo_call";
+				sudo = ?sender,
+				origin = "root",
+				pallet = call.get_call_metadata().pallet_name,
+				call = call.get_call_metadata().function_name,
+				call_hash = ?<T::Hashing as sp_runtime::traits::Hash>::hash_of(&call),
+			);
 			let res = call.dispatch_bypass_filter(frame_system::RawOrigin::Root.into());
+			frame_support::sp_tracing::event!(warn;
+				target: AUDIT_TARGET,
+				result = ?res.as_ref().map(|_| ()).map_err(|e| e.error),
+				"Sudo call dispatched",
+			);
 			Self::deposit_event(RawEvent::Sudid(res.map(|_| ()).map_err(|e| e.error)));
 			// Sudo user does not pay a fee.
 			Ok(Pays::No.into())
@@ -158,7 +178,19 @@ decl_module! {
 			let sender = ensure_signed(origin)?;
 			ensure!(sender == Self::key(), Error::<T>::RequireSudo);
 
+			frame_support::sp_tracing::enter_span!(target: AUDIT_TARGET, "sudo_call";
+				sudo = ?sender,
+				origin = "root",
+				pallet = call.get_call_metadata().pallet_name,
+				call = call.get_call_metadata().function_name,
+				call_hash = ?<T::Hashing as sp_runtime::traits::Hash>::hash_of(&call),
+			);
 			let res = call.dispatch_bypass_filter(frame_system::RawOrigin::Root.into());
+			frame_support::sp_tracing::event!(warn;
+				target: AUDIT_TARGET,
+				result = ?res.as_ref().map(|_| ()).map_err(|e| e.error),
+				"Sudo call dispatched",
+			);
 			Self::deposit_event(RawEvent::Sudid(res.map(|_| ()).map_err(|e| e.error)));
 			// Sudo user does not pay a fee.
 			Ok(Pays::No.into())
@@ -180,6 +212,12 @@ decl_module! {
 			ensure!(sender == Self::key(), Error::<T>::RequireSudo);
 			let new = T::Lookup::lookup(new)?;
 
+			frame_support::sp_tracing::event!(warn;
+				target: AUDIT_TARGET,
+				sudo = ?sender,
+				new = ?new,
+				"Sudo key changed",
+			);
 			Self::deposit_event(RawEvent::KeyChanged(Self::key()));
 			<Key<T>>::put(new);
 			// Sudo user does not pay a fee.
@@ -208,7 +246,20 @@ decl_module! {
 
 			let who = T::Lookup::lookup(who)?;
 
-			let res = match call.dispatch_bypass_filter(frame_system::RawOrigin::Signed(who).into()) {
+			frame_support::sp_tracing::enter_span!(target: AUDIT_TARGET, "sudo_call";
+				sudo = ?sender,
+				origin = ?who,
+				pallet = call.get_call_metadata().pallet_name,
+				call = call.get_call_metadata().function_name,
+				call_hash = ?<T::Hashing as sp_runtime::traits::Hash>::hash_of(&call),
+			);
+			let res = call.dispatch_bypass_filter(frame_system::RawOrigin::Signed(who).into());
+			frame_support::sp_tracing::event!(warn;
+				target: AUDIT_TARGET,
+				result = ?res.as_ref().map(|_| ()).map_err(|e| e.error),
+				"Sudo call dispatched",
+			);
+			let res = match res {
 				Ok(_) => true,
 				Err(e) => {
 					sp_runtime::print(e);
@@ -139,26 +139,68 @@ macro_rules! runtime_print {
 	}
 }
 
+/// The spans a module emits besides the ones of its dispatchables and hooks, as `(target, name)`.
+///
+/// The modules export them as their `TRACE_SPANS`, for the runtime to add them to its trace
+/// metadata with `frame_metadata::RuntimeTraceMetadata::with_spans`, so that the node knows the
+/// spans it may enable before they are emitted.
+pub type TraceSpans = &'static [(&'static str, &'static str)];
+
 /// Print out the debuggable type.
 pub fn debug(data: &impl Debug) {
 	runtime_print!("{:?}", data);
 }
 
+/// Size of the buffer a `Writer` formats in before allocating.
+const WRITER_BUFFER_SIZE: usize = 512;
+
 /// A target for `core::write!` macro - constructs a string in memory.
-#[derive(Default)]
-pub struct Writer(Vec<u8>);
+///
+/// The string is formatted in a buffer of the `Writer`, on the stack, so that formatting the
+/// messages of the logs and prints doesn't go through the allocator of the runtime, unless they
+/// are longer than `WRITER_BUFFER_SIZE` bytes.
+pub struct Writer {
+	buffer: [u8; WRITER_BUFFER_SIZE],
+	len: usize,
+	overflow: Vec<u8>,
+}
+
+impl Default for Writer {
+	fn default() -> Self {
+		Writer { buffer: [0; WRITER_BUFFER_SIZE], len: 0, overflow: Vec::new() }
+	}
+}
 
 impl fmt::Write for Writer {
 	fn write_str(&mut self, s: &str) -> fmt::Result {
-		self.0.extend(s.as_bytes());
+		let bytes = s.as_bytes();
+		if !self.overflow.is_empty() {
+			self.overflow.extend(bytes);
+		} else if self.len + bytes.len() <= WRITER_BUFFER_SIZE {
+			self.buffer[self.len..self.len + bytes.len()].copy_from_slice(bytes);
+			self.len += bytes.len();
+		} else {
+			self.overflow.reserve(self.len + bytes.len());
+			self.overflow.extend(&self.buffer[..self.len]);
+			self.overflow.extend(bytes);
+		}
 		Ok(())
 	}
 }
 
 impl Writer {
+	/// The string written so far.
+	pub fn as_bytes(&self) -> &[u8] {
+		if self.overflow.is_empty() {
+			&self.buffer[..self.len]
+		} else {
+			&self.overflow
+		}
+	}
+
 	/// Print the content of this `Writer` out.
 	pub fn print(&self) {
-		sp_io::misc::print_utf8(&self.0)
+		sp_io::misc::print_utf8(self.as_bytes())
 	}
 }
 
@@ -210,9 +252,166 @@ impl log::Log for RuntimeLogger {
 		sp_io::logging::log(
 			record.level().into(),
 			record.target(),
-			&w.0,
+			w.as_bytes(),
 		);
 	}
 
 	fn flush(&self) {}
 }
+
+/// Asserts that a boolean expression is `true`, like `debug_assert!`, without panicking in the
+/// builds without debug assertions.
+///
+/// With debug assertions, e.g. in the tests and the dev builds, this panics like `assert!`.
+/// Without, a violated assertion emits an `ERROR` event of the `sp_tracing::ASSERTION_TARGET`,
+/// with the `condition`, its `location` and the message, within the current span, so that
+/// invariant violations on live chains are reported instead of halting the chain, see
+/// [`assertion_failed`].
+///
+/// # Example
+///
+/// ```
+/// let (total, reserved) = (10, 3);
+/// frame_support::debug_assert_event!(reserved <= total, "reserved {} above total {}", reserved, total);
+/// ```
+#[macro_export]
+macro_rules! debug_assert_event {
+	( $cond:expr $(,)? ) => {
+		$crate::__debug_assert_event!(
+			stringify!($cond), $cond, concat!("assertion failed: ", stringify!($cond))
+		)
+	};
+	( $cond:expr, $( $args:tt )+ ) => {
+		$crate::__debug_assert_event!(stringify!($cond), $cond, $( $args )+)
+	};
+}
+
+/// Asserts that two expressions are equal, like `debug_assert_eq!`, emitting an `ERROR` event
+/// instead of panicking in the builds without debug assertions, see [`debug_assert_event`].
+///
+/// # Example
+///
+/// ```
+/// let (issuance, total) = (10, 10);
+/// frame_support::debug_assert_eq_event!(issuance, total, "issuance out of sync");
+/// ```
+#[macro_export]
+macro_rules! debug_assert_eq_event {
+	( $left:expr, $right:expr $(,)? ) => {
+		$crate::debug_assert_eq_event!($left, $right, "")
+	};
+	( $left:expr, $right:expr, $( $args:tt )+ ) => {
+		match (&$left, &$right) {
+			(left, right) => $crate::__debug_assert_event!(
+				concat!(stringify!($left), " == ", stringify!($right)),
+				*left == *right,
+				"assertion failed: `(left == right)`\n  left: `{:?}`,\n right: `{:?}`: {}",
+				left,
+				right,
+				format_args!($( $args )+)
+			),
+		}
+	};
+}
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __debug_assert_event {
+	( $condition:expr, $cond:expr, $( $args:tt )+ ) => {
+		if cfg!(debug_assertions) {
+			assert!($cond, $( $args )+);
+		} else if !$cond {
+			$crate::debug::assertion_failed(
+				$condition,
+				concat!(file!(), ":", line!()),
+				format_args!($( $args )+),
+			)
+		}
+	};
+}
+
+/// Maximum length of the messages of the assertions violated in wasm, see
+/// [`assertion_failed`].
+pub const MAX_ASSERTION_MESSAGE_LEN: usize = 256;
+
+/// Report the violated assertion of a [`debug_assert_event`].
+///
+/// In wasm, the event is only emitted with the `with-tracing` feature, by the host, through
+/// `sp_io::wasm_tracing::assertion_failed`, with the message formatted on the stack, as the
+/// runtime may have no allocator left, and cut to [`MAX_ASSERTION_MESSAGE_LEN`] bytes.
+#[cfg(feature = "std")]
+pub fn assertion_failed(condition: &str, location: &str, message: fmt::Arguments) {
+	sp_tracing::tracing::error!(
+		target: sp_tracing::ASSERTION_TARGET,
+		condition,
+		location,
+		"{}",
+		message,
+	);
+}
+
+/// Report the violated assertion of a [`debug_assert_event`].
+///
+/// In wasm, the event is only emitted with the `with-tracing` feature, by the host, through
+/// `sp_io::wasm_tracing::assertion_failed`, with the message formatted on the stack, as the
+/// runtime may have no allocator left, and cut to [`MAX_ASSERTION_MESSAGE_LEN`] bytes.
+#[cfg(all(not(feature = "std"), feature = "with-tracing"))]
+pub fn assertion_failed(condition: &str, location: &str, message: fmt::Arguments) {
+	let mut buffer = MessageBuffer { bytes: [0; MAX_ASSERTION_MESSAGE_LEN], len: 0 };
+	let _ = fmt::write(&mut buffer, message);
+	let message = core::str::from_utf8(&buffer.bytes[..buffer.len]).unwrap_or_default();
+	sp_io::wasm_tracing::assertion_failed(condition, location, message)
+}
+
+/// Report the violated assertion of a [`debug_assert_event`].
+///
+/// In wasm, the event is only emitted with the `with-tracing` feature, see the
+/// `sp_io::wasm_tracing::assertion_failed` host function.
+#[cfg(all(not(feature = "std"), not(feature = "with-tracing")))]
+pub fn assertion_failed(_condition: &str, _location: &str, _message: fmt::Arguments) {}
+
+/// The message of an assertion, cut on a char boundary once full.
+#[cfg(all(not(feature = "std"), feature = "with-tracing"))]
+struct MessageBuffer {
+	bytes: [u8; MAX_ASSERTION_MESSAGE_LEN],
+	len: usize,
+}
+
+#[cfg(all(not(feature = "std"), feature = "with-tracing"))]
+impl fmt::Write for MessageBuffer {
+	fn write_str(&mut self, s: &str) -> fmt::Result {
+		let mut end = s.len().min(self.bytes.len() - self.len);
+		while !s.is_char_boundary(end) {
+			end -= 1;
+		}
+		self.bytes[self.len..self.len + end].copy_from_slice(&s.as_bytes()[..end]);
+		self.len += end;
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use fmt::Write;
+
+	#[test]
+	#[should_panic(expected = "assertion failed: `(left == right)`\n  left: `1`,\n right: `2`: out of sync")]
+	fn debug_assert_event_panics_with_debug_assertions() {
+		debug_assert_event!(1 + 1 == 2);
+		debug_assert_eq_event!(1, 2, "out of {}", "sync");
+	}
+
+	#[test]
+	fn writer_allocates_only_the_long_strings() {
+		let mut w = Writer::default();
+		let _ = write!(&mut w, "{} {}", "short", 1);
+		assert_eq!(w.as_bytes(), b"short 1");
+		assert!(w.overflow.is_empty());
+
+		let long = "a".repeat(WRITER_BUFFER_SIZE);
+		let _ = write!(&mut w, "{}", long);
+		let _ = write!(&mut w, "!");
+		assert_eq!(w.as_bytes(), format!("short 1{}!", long).as_bytes());
+	}
+}
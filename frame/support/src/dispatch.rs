@@ -1675,6 +1675,28 @@ macro_rules! decl_module {
 			$( $integrity_test )*
 		}
 
+		impl<$trait_instance: $trait_name $(<I>, $instance: $instantiable)?> $mod_type<$trait_instance $(, $instance)?>
+			where $( $other_where_bounds )*
+		{
+			/// The target of the spans of the dispatchables and hooks.
+			#[doc(hidden)]
+			pub fn trace_target() -> &'static str {
+				module_path!()
+			}
+
+			/// The names of the spans of the dispatchables and of the implemented hooks.
+			#[doc(hidden)]
+			pub fn trace_span_names() -> $crate::sp_std::vec::Vec<&'static str> {
+				#[allow(unused_mut)]
+				let mut names = $crate::sp_std::vec::Vec::new();
+				$crate::__trace_hook_span!(names; "on_initialize"; $( $on_initialize )*);
+				$crate::__trace_hook_span!(names; "on_runtime_upgrade"; $( $on_runtime_upgrade )*);
+				$crate::__trace_hook_span!(names; "on_finalize"; $( $on_finalize )*);
+				$( names.push(stringify!($fn_name)); )*
+				names
+			}
+		}
+
 		/// Can also be called using [`Call`].
 		///
 		/// [`Call`]: enum.Call.html
@@ -1975,6 +1997,19 @@ macro_rules! impl_outer_dispatch {
 				self,
 				origin: $origin,
 			) -> $crate::dispatch::DispatchResultWithPostInfo {
+				// The calls dispatched by other calls with a root or custom origin, e.g. by `sudo`
+				// or the collectives, are dispatches of their own, see `sp_tracing::DISPATCH_TARGET`.
+				let _dispatch_span = $crate::sp_tracing::if_tracing!(
+					match $crate::traits::OriginTrait::kind(&origin) {
+						"signed" | "none" => $crate::sp_tracing::EnteredSpan::none(),
+						kind => $crate::sp_tracing::entered_span!(
+							target: $crate::sp_tracing::DISPATCH_TARGET,
+							$crate::sp_tracing::DISPATCH_TARGET;
+							weight = $crate::dispatch::GetDispatchInfo::get_dispatch_info(&self).weight,
+							origin = kind
+						),
+					}
+				);
 				$crate::impl_outer_dispatch! {
 					@DISPATCH_MATCH
 					self
@@ -2042,6 +2077,16 @@ macro_rules! impl_outer_dispatch {
 	}
 }
 
+/// Push the name of the span of a hook to `names`, if the hook is implemented.
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __trace_hook_span {
+	($names:ident; $name:literal;) => {};
+	($names:ident; $name:literal; $( $hook:tt )+) => {
+		$names.push($name);
+	};
+}
+
 /// Implement metadata for dispatch.
 #[macro_export]
 #[doc(hidden)]
@@ -2594,6 +2639,15 @@ mod tests {
 		assert_eq!(["aux_0", "aux_1", "aux_2", "aux_3", "aux_4", "aux_5", "operational"], call_names);
 	}
 
+	#[test]
+	fn trace_span_names() {
+		assert_eq!(Module::<TraitImpl>::trace_target(), "frame_support::dispatch::tests");
+		assert_eq!(Module::<TraitImpl>::trace_span_names(), vec![
+			"on_initialize", "on_runtime_upgrade", "on_finalize",
+			"aux_0", "aux_1", "aux_2", "aux_3", "aux_4", "aux_5", "operational",
+		]);
+	}
+
 	#[test]
 	fn get_module_names() {
 		let module_names = OuterCall::get_module_names();
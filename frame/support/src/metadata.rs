@@ -19,7 +19,7 @@ pub use frame_metadata::{
 	DecodeDifferent, FnEncode, RuntimeMetadata, ModuleMetadata, RuntimeMetadataLastVersion,
 	DefaultByteGetter, RuntimeMetadataPrefixed, StorageEntryMetadata, StorageMetadata,
 	StorageEntryType, StorageEntryModifier, DefaultByte, StorageHasher, ModuleErrorMetadata,
-	ExtrinsicMetadata,
+	ExtrinsicMetadata, RuntimeTraceMetadata, TraceTargetMetadata,
 };
 
 /// Implements the metadata support for the given runtime and all its modules.
@@ -81,10 +81,49 @@ macro_rules! impl_runtime_metadata {
 						},
 				}.into()
 			}
+
+			/// The tracing targets and span names of the modules, see
+			/// `frame_metadata::RuntimeTraceMetadata`.
+			pub fn trace_metadata() -> $crate::metadata::RuntimeTraceMetadata {
+				$crate::metadata::RuntimeTraceMetadata {
+					targets: $crate::__runtime_modules_to_trace_metadata!($runtime;; $( $rest )*),
+				}
+			}
 		}
 	}
 }
 
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __runtime_modules_to_trace_metadata {
+	(
+		$runtime: ident;
+		$( $metadata:expr ),*;
+		$mod:ident::$module:ident $( < $instance:ident > )? as $name:ident $(with)+ $($kw:ident)*,
+		$( $rest:tt )*
+	) => {
+		$crate::__runtime_modules_to_trace_metadata!(
+			$runtime;
+			$( $metadata, )* $crate::metadata::TraceTargetMetadata {
+				module: $crate::metadata::DecodeDifferent::Encode(stringify!($name)),
+				target: $crate::metadata::DecodeDifferent::Encode(
+					$mod::$module::<$runtime $(, $mod::$instance )?>::trace_target()
+				),
+				spans: $crate::metadata::DecodeDifferent::Encode(
+					$mod::$module::<$runtime $(, $mod::$instance )?>::trace_span_names()
+				),
+			};
+			$( $rest )*
+		)
+	};
+	(
+		$runtime:ident;
+		$( $metadata:expr ),*;
+	) => {
+		$crate::sp_std::vec![ $( $metadata ),* ]
+	};
+}
+
 #[macro_export]
 #[doc(hidden)]
 macro_rules! __runtime_modules_to_metadata {
@@ -610,4 +649,47 @@ mod tests {
 
 		pretty_assertions::assert_eq!(expected_metadata, metadata_decoded.unwrap());
 	}
+
+	#[test]
+	fn runtime_trace_metadata() {
+		let target = |module: &'static str| TraceTargetMetadata {
+			module: DecodeDifferent::Encode(module),
+			target: DecodeDifferent::Encode(""),
+			spans: DecodeDifferent::Encode(Vec::new()),
+		};
+		let expected = RuntimeTraceMetadata {
+			targets: vec![
+				TraceTargetMetadata {
+					target: DecodeDifferent::Encode("frame_support::metadata::tests::system"),
+					..target("System")
+				},
+				TraceTargetMetadata {
+					target: DecodeDifferent::Encode("frame_support::metadata::tests::event_module"),
+					spans: DecodeDifferent::Encode(vec!["aux_0"]),
+					..target("Module")
+				},
+				TraceTargetMetadata {
+					target: DecodeDifferent::Encode("frame_support::metadata::tests::event_module2"),
+					..target("Module2")
+				},
+			],
+		};
+
+		let encoded = TestRuntime::trace_metadata().encode();
+		pretty_assertions::assert_eq!(expected, RuntimeTraceMetadata::decode(&mut &encoded[..]).unwrap());
+	}
+
+	#[test]
+	fn runtime_trace_metadata_with_spans() {
+		let metadata = TestRuntime::trace_metadata()
+			.with_spans("Module", &[("frame_support::metadata::tests::event_module", "custom")])
+			.with_spans("Executive", &[("dispatch", "dispatch")]);
+		let encoded = metadata.encode();
+		let decoded = RuntimeTraceMetadata::decode(&mut &encoded[..]).unwrap();
+
+		assert_eq!(decoded.targets.len(), 4);
+		assert_eq!(decoded.targets[1].spans, DecodeDifferent::Decoded(vec!["aux_0".into(), "custom".into()]));
+		assert_eq!(decoded.targets[3].module, DecodeDifferent::Decoded("Executive".into()));
+		assert_eq!(decoded.targets[3].spans, DecodeDifferent::Decoded(vec!["dispatch".into()]));
+	}
 }
@@ -226,6 +226,15 @@ macro_rules! impl_outer_origin {
 			fn caller(&self) -> &Self::PalletsOrigin {
 				&self.caller
 			}
+
+			fn kind(&self) -> &'static str {
+				match self.caller {
+					$caller_name::system($system::Origin::<$runtime>::Root) => "root",
+					$caller_name::system($system::Origin::<$runtime>::Signed(_)) => "signed",
+					$caller_name::system($system::Origin::<$runtime>::None) => "none",
+					_ => "custom",
+				}
+			}
 		}
 
 		$crate::paste::item! {
@@ -472,4 +481,12 @@ mod tests {
 		assert_eq!(origin.filter_call(&0), true);
 		assert_eq!(origin.filter_call(&1), false);
 	}
+
+	#[test]
+	fn test_kind() {
+		assert_eq!(OriginWithSystem::root().kind(), "root");
+		assert_eq!(OriginWithSystem::signed(0).kind(), "signed");
+		assert_eq!(OriginWithSystem::none().kind(), "none");
+		assert_eq!(OriginWithSystem::from(origin_without_generic::Origin).kind(), "custom");
+	}
 }
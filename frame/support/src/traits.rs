@@ -1627,6 +1627,15 @@ pub trait OriginTrait: Sized {
 
 	/// Get the caller.
 	fn caller(&self) -> &Self::PalletsOrigin;
+
+	/// The kind of the caller, as recorded on the dispatch spans: `"root"`, `"signed"` or `"none"`
+	/// for the system origins, and `"custom"` for the origins of the other pallets, e.g. the
+	/// collectives.
+	///
+	/// Defaults to `"custom"`, the origins of `impl_outer_origin` telling the system ones apart.
+	fn kind(&self) -> &'static str {
+		"custom"
+	}
 }
 
 /// Trait to be used when types are exactly same.
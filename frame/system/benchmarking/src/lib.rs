@@ -46,6 +46,15 @@ benchmarks! {
 	set_heap_pages {
 	}: _(RawOrigin::Root, Default::default())
 
+	set_tracing_targets {
+		let t in 0 .. 1024;
+		let targets = vec![b'a'; t as usize];
+	}: _(RawOrigin::Root, targets.clone())
+	verify {
+		let expected = if targets.is_empty() { None } else { Some(targets) };
+		assert_eq!(storage::unhashed::get_raw(well_known_keys::TRACING_TARGETS), expected);
+	}
+
 	// `set_code` was not benchmarked because it is pretty hard to come up with a real
 	// Wasm runtime to test the upgrade with. But this is okay because we will make
 	// `set_code` take a full block anyway.
@@ -164,6 +173,7 @@ mod tests {
 		new_test_ext().execute_with(|| {
 			assert_ok!(test_benchmark_remark::<Test>());
 			assert_ok!(test_benchmark_set_heap_pages::<Test>());
+			assert_ok!(test_benchmark_set_tracing_targets::<Test>());
 			assert_ok!(test_benchmark_set_code_without_checks::<Test>());
 			assert_ok!(test_benchmark_set_changes_trie_config::<Test>());
 			assert_ok!(test_benchmark_set_storage::<Test>());
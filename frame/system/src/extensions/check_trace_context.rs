@@ -0,0 +1,178 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use codec::{Encode, Decode};
+#[cfg(any(feature = "std", feature = "with-tracing"))]
+use sp_std::convert::TryFrom;
+use crate::Trait;
+use sp_runtime::{
+	traits::{SignedExtension, DispatchInfoOf, PostDispatchInfoOf},
+	transaction_validity::TransactionValidityError,
+	DispatchResult,
+};
+
+/// The trace id a client supplies with an extrinsic, to follow it from the client to the runtime
+/// of the nodes executing it.
+///
+/// The nodes tracing the runtime record the trace id, hex encoded, on the spans and events of the
+/// dispatch of the extrinsic, see `sp_io::wasm_tracing::enter_trace_context`. The id is opaque
+/// and doesn't affect the validity of the extrinsic.
+///
+/// In wasm, the trace context is only entered with the `with-tracing` feature, as the nodes
+/// lacking the host function can't execute a runtime calling it.
+#[derive(Encode, Decode, Clone, Eq, PartialEq)]
+pub struct CheckTraceContext<T: Trait + Send + Sync>(Option<[u8; 16]>, sp_std::marker::PhantomData<T>);
+
+impl<T: Trait + Send + Sync> sp_std::fmt::Debug for CheckTraceContext<T> {
+	#[cfg(feature = "std")]
+	fn fmt(&self, f: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
+		write!(f, "CheckTraceContext({:?})", self.0)
+	}
+
+	#[cfg(not(feature = "std"))]
+	fn fmt(&self, _: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
+		Ok(())
+	}
+}
+
+impl<T: Trait + Send + Sync> CheckTraceContext<T> {
+	/// Creates new `SignedExtension` carrying the given trace id, if any.
+	pub fn from(trace_id: Option<[u8; 16]>) -> Self {
+		Self(trace_id, sp_std::marker::PhantomData)
+	}
+
+	/// Creates new `SignedExtension` carrying the trace id of the host's current trace context,
+	/// if any, e.g. for an offchain worker to link the dispatch of the transactions it signs to
+	/// its own trace, see `sp_io::wasm_tracing::trace_id`.
+	///
+	/// In wasm, the trace id is only queried with the `with-tracing` feature, and is `None`
+	/// otherwise.
+	pub fn current() -> Self {
+		Self::from(current_trace_id())
+	}
+}
+
+/// The span of the trace context entered by `CheckTraceContext::pre_dispatch`, exited when
+/// dropped, after the dispatch or as soon as a later extension fails its `pre_dispatch`.
+#[derive(Default, Debug)]
+pub struct TraceContextSpan(u64);
+
+impl Drop for TraceContextSpan {
+	fn drop(&mut self) {
+		if self.0 != 0 {
+			exit_trace_context(self.0);
+		}
+	}
+}
+
+/// The trace id of the host's current trace context, if any.
+#[cfg(any(feature = "std", feature = "with-tracing"))]
+fn current_trace_id() -> Option<[u8; 16]> {
+	sp_io::wasm_tracing::trace_id().and_then(|trace_id| <[u8; 16]>::try_from(&trace_id[..]).ok())
+}
+
+#[cfg(not(any(feature = "std", feature = "with-tracing")))]
+fn current_trace_id() -> Option<[u8; 16]> {
+	None
+}
+
+/// Enter the trace context of `trace_id`, returning the id of its span.
+#[cfg(any(feature = "std", feature = "with-tracing"))]
+fn enter_trace_context(trace_id: &[u8; 16]) -> u64 {
+	sp_io::wasm_tracing::enter_trace_context(trace_id)
+}
+
+/// Enter the trace context of `trace_id`, returning the id of its span.
+///
+/// In wasm, the trace context is only entered with the `with-tracing` feature.
+#[cfg(not(any(feature = "std", feature = "with-tracing")))]
+fn enter_trace_context(_trace_id: &[u8; 16]) -> u64 {
+	0
+}
+
+#[cfg(any(feature = "std", feature = "with-tracing"))]
+fn exit_trace_context(span: u64) {
+	sp_io::wasm_tracing::exit_span(span)
+}
+
+#[cfg(not(any(feature = "std", feature = "with-tracing")))]
+fn exit_trace_context(_span: u64) {}
+
+impl<T: Trait + Send + Sync> SignedExtension for CheckTraceContext<T> {
+	type AccountId = T::AccountId;
+	type Call = <T as Trait>::Call;
+	type AdditionalSigned = ();
+	type Pre = TraceContextSpan;
+	const IDENTIFIER: &'static str = "CheckTraceContext";
+
+	fn additional_signed(&self) -> Result<(), TransactionValidityError> {
+		Ok(())
+	}
+
+	fn pre_dispatch(
+		self,
+		_who: &Self::AccountId,
+		_call: &Self::Call,
+		_info: &DispatchInfoOf<Self::Call>,
+		_len: usize,
+	) -> Result<Self::Pre, TransactionValidityError> {
+		Ok(TraceContextSpan(self.0.map_or(0, |trace_id| enter_trace_context(&trace_id))))
+	}
+
+	fn post_dispatch(
+		pre: Self::Pre,
+		_info: &DispatchInfoOf<Self::Call>,
+		_post_info: &PostDispatchInfoOf<Self::Call>,
+		_len: usize,
+		_result: &DispatchResult,
+	) -> Result<(), TransactionValidityError> {
+		// Exits the span.
+		drop(pre);
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::mock::{Test, new_test_ext, CALL};
+	use frame_support::weights::{DispatchInfo, PostDispatchInfo};
+
+	#[test]
+	fn signed_ext_check_trace_context_works_without_tracing() {
+		new_test_ext().execute_with(|| {
+			let info = DispatchInfo::default();
+			for trace_id in vec![None, Some([1; 16])] {
+				let ext = CheckTraceContext::<Test>::from(trace_id);
+				assert!(ext.validate(&1, CALL, &info, 0).is_ok());
+				let pre = ext.pre_dispatch(&1, CALL, &info, 0).unwrap();
+				assert_eq!(pre.0, 0);
+				assert!(CheckTraceContext::<Test>::post_dispatch(
+					pre, &info, &PostDispatchInfo::default(), 0, &Ok(()),
+				).is_ok());
+			}
+		})
+	}
+
+	#[test]
+	fn current_trace_id_is_the_one_of_the_host() {
+		assert!(CheckTraceContext::<Test>::current() == CheckTraceContext::<Test>::from(None));
+
+		frame_support::sp_tracing::set_trace_id(|| Some("ab".repeat(16)));
+		assert!(CheckTraceContext::<Test>::current() == CheckTraceContext::<Test>::from(Some([0xab; 16])));
+	}
+}
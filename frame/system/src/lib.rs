@@ -143,8 +143,9 @@ mod default_weights;
 
 pub use extensions::{
 	check_mortality::CheckMortality, check_genesis::CheckGenesis, check_nonce::CheckNonce,
-	check_spec_version::CheckSpecVersion, check_tx_version::CheckTxVersion,
-	check_weight::CheckWeight,
+	check_spec_version::CheckSpecVersion,
+	check_trace_context::{CheckTraceContext, TraceContextSpan},
+	check_tx_version::CheckTxVersion, check_weight::CheckWeight,
 };
 // Backward compatible re-export.
 pub use extensions::check_mortality::CheckMortality as CheckEra;
@@ -167,6 +168,7 @@ pub trait WeightInfo {
 	fn kill_storage(i: u32, ) -> Weight;
 	fn kill_prefix(p: u32, ) -> Weight;
 	fn suicide() -> Weight;
+	fn set_tracing_targets(t: u32, ) -> Weight;
 }
 
 pub trait Trait: 'static + Eq + Clone {
@@ -513,6 +515,8 @@ decl_error! {
 		NonDefaultComposite,
 		/// There is a non-zero reference count preventing the account from being purged.
 		NonZeroRefCount,
+		/// The tracing targets are not valid UTF-8.
+		InvalidTracingTargets,
 	}
 }
 
@@ -709,6 +713,33 @@ decl_module! {
 			ensure!(account.data == T::AccountData::default(), Error::<T>::NonDefaultComposite);
 			Self::kill_account(&who);
 		}
+
+		/// Set the tracing targets of the runtime, in the format of `--tracing-targets`.
+		///
+		/// The targets apply to the wasm spans of the runtime on every node, taking precedence
+		/// over the node's settings: `balances` enables the spans of `balances`, `balances=warn`
+		/// disables them. Empty targets restore the node's settings. Nodes read the targets from
+		/// the state the runtime is called at, outside of the execution, so they apply from the
+		/// next block on.
+		///
+		/// # <weight>
+		/// - `O(T)` where `T` length of `targets`
+		/// - 1 storage write.
+		/// - 1 write to TRACING_TARGETS
+		/// # </weight>
+		#[weight = (
+			T::SystemWeightInfo::set_tracing_targets(targets.len() as u32),
+			DispatchClass::Operational,
+		)]
+		fn set_tracing_targets(origin, targets: Vec<u8>) {
+			ensure_root(origin)?;
+			sp_std::str::from_utf8(&targets).map_err(|_| Error::<T>::InvalidTracingTargets)?;
+			if targets.is_empty() {
+				storage::unhashed::kill(well_known_keys::TRACING_TARGETS);
+			} else {
+				storage::unhashed::put_raw(well_known_keys::TRACING_TARGETS, &targets);
+			}
+		}
 	}
 }
 
@@ -947,6 +978,12 @@ impl<T: Trait> Module<T> {
 		};
 
 		Events::<T>::append(&event);
+		frame_support::sp_tracing::event!(
+			trace;
+			target: frame_support::sp_tracing::RUNTIME_EVENT_TARGET,
+			event = ?event.event.encode(),
+			"Deposited event",
+		);
 
 		for topic in topics {
 			<EventTopics<T>>::append(topic, &(block_number, event_idx));
@@ -19,7 +19,7 @@ use crate::*;
 use mock::{*, Origin};
 use sp_core::H256;
 use sp_runtime::DispatchError;
-use frame_support::weights::WithPostDispatchInfo;
+use frame_support::{assert_noop, assert_ok, weights::WithPostDispatchInfo};
 
 #[test]
 fn origin_works() {
@@ -398,6 +398,30 @@ fn runtime_upgraded_with_set_storage() {
 	});
 }
 
+#[test]
+fn set_tracing_targets_works() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			System::set_tracing_targets(RawOrigin::Signed(1).into(), b"balances".to_vec()),
+			DispatchError::BadOrigin,
+		);
+		assert_noop!(
+			System::set_tracing_targets(RawOrigin::Root.into(), vec![0xff]),
+			Error::<Test>::InvalidTracingTargets,
+		);
+
+		assert_ok!(System::set_tracing_targets(RawOrigin::Root.into(), b"balances=warn".to_vec()));
+		assert_eq!(
+			storage::unhashed::get_raw(well_known_keys::TRACING_TARGETS),
+			Some(b"balances=warn".to_vec()),
+		);
+		assert_eq!(sp_io::wasm_tracing::enter_span("balances", "transfer"), 0);
+
+		assert_ok!(System::set_tracing_targets(RawOrigin::Root.into(), vec![]));
+		assert_eq!(storage::unhashed::get_raw(well_known_keys::TRACING_TARGETS), None);
+	});
+}
+
 #[test]
 fn events_not_emitted_during_genesis() {
 	new_test_ext().execute_with(|| {
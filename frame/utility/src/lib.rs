@@ -62,7 +62,7 @@ use sp_io::hashing::blake2_256;
 use frame_support::{decl_module, decl_event, decl_storage, Parameter};
 use frame_support::{
 	traits::{OriginTrait, UnfilteredDispatchable},
-	weights::{Weight, GetDispatchInfo, DispatchClass}, dispatch::PostDispatchInfo,
+	weights::{Weight, GetDispatchInfo, DispatchClass}, dispatch::{GetCallMetadata, PostDispatchInfo},
 };
 use frame_system::{ensure_signed, ensure_root};
 use sp_runtime::{DispatchError, DispatchResult, traits::Dispatchable};
@@ -83,13 +83,21 @@ pub trait Trait: frame_system::Trait {
 
 	/// The overarching call type.
 	type Call: Parameter + Dispatchable<Origin=Self::Origin, PostInfo=PostDispatchInfo>
-		+ GetDispatchInfo + From<frame_system::Call<Self>>
+		+ GetDispatchInfo + GetCallMetadata + From<frame_system::Call<Self>>
 		+ UnfilteredDispatchable<Origin=Self::Origin>;
 
 	/// Weight information for extrinsics in this pallet.
 	type WeightInfo: WeightInfo;
 }
 
+/// Tracing target used for the calls dispatched by a batch.
+pub const LOG_TARGET: &str = "utility";
+
+/// See [`TraceSpans`](frame_support::debug::TraceSpans).
+pub const TRACE_SPANS: frame_support::debug::TraceSpans = &[
+	(module_path!(), "batch_call"),
+];
+
 decl_storage! {
 	trait Store for Module<T: Trait> as Utility {}
 }
@@ -157,11 +165,22 @@ decl_module! {
 		fn batch(origin, calls: Vec<<T as Trait>::Call>) {
 			let is_root = ensure_root(origin.clone()).is_ok();
 			for (index, call) in calls.into_iter().enumerate() {
+				frame_support::sp_tracing::enter_span!("batch_call";
+					index,
+					pallet = call.get_call_metadata().pallet_name,
+					call = call.get_call_metadata().function_name,
+					weight = call.get_dispatch_info().weight,
+				);
 				let result = if is_root {
 					call.dispatch_bypass_filter(origin.clone())
 				} else {
 					call.dispatch(origin.clone())
 				};
+				frame_support::sp_tracing::event!(debug;
+					target: LOG_TARGET,
+					result = ?result.as_ref().map(|_| ()).map_err(|e| e.error),
+					"Batched call dispatched",
+				);
 				if let Err(e) = result {
 					Self::deposit_event(Event::BatchInterrupted(index as u32, e.error));
 					return Ok(());
@@ -26,8 +26,8 @@
 //! Every Substrate runtime needs to implement the [`Core`] runtime api. This api provides the basic
 //! functionality that every runtime needs to export.
 //!
-//! Besides the macros and the [`Core`] runtime api, this crates provides the [`Metadata`] runtime
-//! api, the [`ApiExt`] trait, the [`CallApiAt`] trait and the [`ConstructRuntimeApi`] trait.
+//! Besides the macros and the [`Core`] runtime api, this crates provides the [`Metadata`] and
+//! [`TraceMetadata`] runtime apis, the [`ApiExt`] trait, the [`CallApiAt`] trait and the [`ConstructRuntimeApi`] trait.
 //!
 //! On a meta level this implies, the client calls the generated API from the client perspective.
 
@@ -593,4 +593,11 @@ decl_runtime_apis! {
 		/// Returns the metadata of a runtime.
 		fn metadata() -> OpaqueMetadata;
 	}
+
+	/// The `TraceMetadata` api trait that returns the tracing targets and span names of the
+	/// runtime, e.g. to offer them to the users filtering the traces.
+	pub trait TraceMetadata {
+		/// Returns the encoded `frame_metadata::RuntimeTraceMetadata` of a runtime.
+		fn trace_metadata() -> OpaqueMetadata;
+	}
 }
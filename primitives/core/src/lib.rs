@@ -257,7 +257,7 @@ pub trait TypeId {
 /// A log level matching the one from `log` crate.
 ///
 /// Used internally by `sp_io::log` method.
-#[derive(Encode, Decode, sp_runtime_interface::pass_by::PassByEnum, Copy, Clone)]
+#[derive(Encode, Decode, sp_runtime_interface::pass_by::PassByEnum, Copy, Clone, PartialEq, Eq, RuntimeDebug)]
 pub enum LogLevel {
 	/// `Error` log level.
 	Error = 1,
@@ -309,6 +309,32 @@ impl From<LogLevel> for log::Level {
 	}
 }
 
+/// The tracing capabilities of the host.
+///
+/// Returned by `sp_io::wasm_tracing::capabilities`, so that the runtime can skip building
+/// expensive diagnostic data when nobody is listening.
+#[derive(Encode, Decode, sp_runtime_interface::pass_by::PassByCodec, Clone, Default, PartialEq, Eq, RuntimeDebug)]
+pub struct TracingCapabilities {
+	/// Whether the host processes the spans of the runtime at all.
+	pub enabled: bool,
+	/// Most verbose level of the `targets`, `None` if there is none.
+	pub max_level: Option<LogLevel>,
+	/// Targets processed by the host with their most verbose level, the on-chain ones included.
+	pub targets: Vec<(Vec<u8>, LogLevel)>,
+}
+
+impl TracingCapabilities {
+	/// Whether the host may process data of the given `target` and `level`.
+	///
+	/// This errs on the side of caution: it may return `true` for a target that is disabled by a
+	/// more specific on-chain target, but never `false` for a target that is processed.
+	pub fn is_enabled(&self, target: &str, level: LogLevel) -> bool {
+		self.enabled && self.targets.iter().any(|(prefix, max_level)| {
+			target.as_bytes().starts_with(prefix) && level as u32 <= *max_level as u32
+		})
+	}
+}
+
 /// Encodes the given value into a buffer and returns the pointer and the length as a single `u64`.
 ///
 /// When Substrate calls into Wasm it expects a fixed signature for functions exported
@@ -190,6 +190,22 @@ pub struct OpaqueNetworkState {
 	pub external_addresses: Vec<OpaqueMultiaddr>,
 }
 
+/// Recent performance aggregates of the local node.
+///
+/// They describe the conditions of the node the offchain worker runs on, e.g. to suggest fees
+/// adapted to its load, and differ from node to node.
+#[derive(Clone, Default, Eq, PartialEq, Encode, Decode, RuntimeDebug, PassByCodec)]
+pub struct NodeMetrics {
+	/// Number of the recent block imports the average import time is taken over.
+	pub recent_imports: u32,
+	/// Average time of the recent block imports, in microseconds, `None` if none was timed.
+	pub average_import_micros: Option<u64>,
+	/// Number of transactions in the pool ready to be included in a block.
+	pub ready_transactions: u64,
+	/// Number of transactions in the pool waiting for other transactions to be included.
+	pub future_transactions: u64,
+}
+
 /// Simple blob to hold a `PeerId` without committing to its format.
 #[derive(Default, Clone, Eq, PartialEq, Encode, Decode, RuntimeDebug, PassByInner)]
 pub struct OpaquePeerId(pub Vec<u8>);
@@ -277,6 +293,8 @@ pub enum Capability {
 	OffchainWorkerDbRead = 32,
 	/// Access to offchain worker DB (writes).
 	OffchainWorkerDbWrite = 64,
+	/// Access to the performance aggregates of the node.
+	NodeMetrics = 128,
 }
 
 /// A set of capabilities
@@ -334,6 +352,9 @@ pub trait Externalities: Send {
 	/// Returns information about the local node's network state.
 	fn network_state(&self) -> Result<OpaqueNetworkState, ()>;
 
+	/// Returns the recent performance aggregates of the local node.
+	fn node_metrics(&self) -> NodeMetrics;
+
 	/// Returns current UNIX timestamp (in millis)
 	fn timestamp(&mut self) -> Timestamp;
 
@@ -506,6 +527,10 @@ impl<T: Externalities + ?Sized> Externalities for Box<T> {
 		(& **self).network_state()
 	}
 
+	fn node_metrics(&self) -> NodeMetrics {
+		(& **self).node_metrics()
+	}
+
 	fn timestamp(&mut self) -> Timestamp {
 		(&mut **self).timestamp()
 	}
@@ -611,6 +636,11 @@ impl<T: Externalities> Externalities for LimitedExternalities<T> {
 		self.externalities.network_state()
 	}
 
+	fn node_metrics(&self) -> NodeMetrics {
+		self.check(Capability::NodeMetrics, "node_metrics");
+		self.externalities.node_metrics()
+	}
+
 	fn timestamp(&mut self) -> Timestamp {
 		self.check(Capability::Http, "timestamp");
 		self.externalities.timestamp()
@@ -759,5 +789,7 @@ mod tests {
 		assert!(!none.has(Capability::TransactionPool));
 		assert!(all.has(Capability::TransactionPool));
 		assert!(!some.has(Capability::TransactionPool));
+		assert!(all.has(Capability::NodeMetrics));
+		assert!(!Capabilities::rich_offchain_call().has(Capability::NodeMetrics));
 	}
 }
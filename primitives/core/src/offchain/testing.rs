@@ -33,6 +33,7 @@ use crate::offchain::{
 	Timestamp,
 	StorageKind,
 	OpaqueNetworkState,
+	NodeMetrics,
 	TransactionPool,
 	OffchainStorage,
 };
@@ -130,6 +131,8 @@ pub struct OffchainState {
 	pub seed: [u8; 32],
 	/// A timestamp simulating the current time.
 	pub timestamp: Timestamp,
+	/// The performance aggregates of the node.
+	pub node_metrics: NodeMetrics,
 }
 
 impl OffchainState {
@@ -220,6 +223,10 @@ impl offchain::Externalities for TestOffchainExt {
 		})
 	}
 
+	fn node_metrics(&self) -> NodeMetrics {
+		self.0.read().node_metrics.clone()
+	}
+
 	fn timestamp(&mut self) -> Timestamp {
 		self.0.read().timestamp
 	}
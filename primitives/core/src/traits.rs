@@ -364,6 +364,28 @@ impl TaskExecutorExt {
 	}
 }
 
+sp_externalities::decl_extension! {
+	/// The on-chain tracing targets, stored under `well_known_keys::TRACING_TARGETS`, at the
+	/// block the runtime is called at.
+	///
+	/// Registered by the node, which reads them from its state outside of the execution, so that
+	/// the tracing host functions never read the storage and the proofs recorded by an execution
+	/// do not depend on how it is traced.
+	pub struct TracingTargetsExt(Option<Vec<u8>>);
+}
+
+impl TracingTargetsExt {
+	/// New instance of the tracing targets extension.
+	pub fn new(targets: Option<Vec<u8>>) -> Self {
+		Self(targets)
+	}
+
+	/// The on-chain tracing targets, if any are set.
+	pub fn targets(&self) -> Option<&[u8]> {
+		self.0.as_deref()
+	}
+}
+
 /// Something that can spawn futures (blocking and non-blocking) with an assigned name.
 #[dyn_clonable::clonable]
 pub trait SpawnNamed: Clone + Send + Sync {
@@ -44,6 +44,18 @@ use parking_lot::RwLock;
 #[cfg(feature = "std")]
 use std::{sync::Arc, format};
 
+#[cfg(feature = "std")]
+use sp_tracing::tracing;
+
+/// Target of the spans of the creation and of the check of the inherent data, and of the events of
+/// their failures.
+pub const INHERENTS_TARGET: &str = "inherents";
+
+/// Number of bytes of the inherent data provided by the node recorded on the events of the failed
+/// checks, e.g. the whole timestamp.
+#[cfg(feature = "std")]
+const MAX_TRACED_DATA: usize = 64;
+
 /// An error that can occur within the inherent data system.
 #[cfg(feature = "std")]
 #[derive(Debug, Encode, Decode, derive_more::Display)]
@@ -302,15 +314,77 @@ impl InherentDataProviders {
 	}
 
 	/// Create inherent data.
+	///
+	/// Each provider is called in a span of the [`INHERENTS_TARGET`], recording the `size` of the
+	/// data it provided.
 	pub fn create_inherent_data(&self) -> Result<InherentData, Error> {
 		let mut data = InherentData::new();
 		self.providers.read().iter().try_for_each(|p| {
-			p.provide_inherent_data(&mut data)
-				.map_err(|e| format!("Error for `{:?}`: {:?}", p.inherent_identifier(), e))
+			let identifier = p.inherent_identifier();
+			let span = tracing::trace_span!(
+				target: INHERENTS_TARGET,
+				"provide_inherent_data",
+				identifier = %String::from_utf8_lossy(identifier),
+				size = tracing::field::Empty,
+			);
+			let _guard = span.enter();
+			match p.provide_inherent_data(&mut data) {
+				Ok(()) => {
+					let size = data.data.get(identifier).map_or(0, |data| data.len());
+					span.record("size", &(size as u64));
+					Ok(())
+				},
+				Err(e) => {
+					tracing::warn!(target: INHERENTS_TARGET, error = %e, "Inherent data provider failed");
+					Err(format!("Error for `{:?}`: {:?}", identifier, e))
+				},
+			}
 		})?;
 		Ok(data)
 	}
 
+	/// Check the inherents of a block with `check`, e.g. the `check_inherents` runtime API,
+	/// against the inherent `data` of the node.
+	///
+	/// The check is done in a span of the [`INHERENTS_TARGET`], recording the number of
+	/// `inherents` and the `size` of the data. Each error of the check is emitted as an event of
+	/// the span, with the `size` of the data the node provided for the inherent and, up to 64
+	/// bytes, the data itself, e.g. the timestamp of a block too far in the future.
+	pub fn check_inherents<E>(
+		&self,
+		data: InherentData,
+		check: impl FnOnce(InherentData) -> Result<CheckInherentsResult, E>,
+	) -> Result<CheckInherentsResult, E> {
+		let provided: BTreeMap<_, _> = data.data.iter()
+			.map(|(identifier, data)| {
+				let traced = &data[..data.len().min(MAX_TRACED_DATA)];
+				(*identifier, (data.len(), sp_core::hexdisplay::HexDisplay::from(&traced).to_string()))
+			})
+			.collect();
+		let span = tracing::trace_span!(
+			target: INHERENTS_TARGET,
+			"check_inherents",
+			inherents = provided.len() as u64,
+			size = provided.values().map(|(size, _)| *size as u64).sum::<u64>(),
+		);
+		let _guard = span.enter();
+
+		let result = check(data)?;
+		for (identifier, error) in &result.errors.data {
+			let (size, data) = provided.get(identifier).cloned().unwrap_or_default();
+			tracing::warn!(
+				target: INHERENTS_TARGET,
+				identifier = %String::from_utf8_lossy(identifier),
+				error = %self.error_to_string(identifier, error),
+				fatal = result.fatal_error,
+				size = size as u64,
+				data = data.as_str(),
+				"Inherent check failed",
+			);
+		}
+		Ok(result)
+	}
+
 	/// Converts a given encoded error into a `String`.
 	///
 	/// Useful if the implementation encounters an error for an identifier it does not know.
@@ -533,6 +607,22 @@ mod tests {
 		);
 	}
 
+	#[test]
+	fn check_inherents_passes_the_result_through() {
+		let providers = InherentDataProviders::new();
+		providers.register_provider(TestInherentDataProvider::new()).unwrap();
+		let data = providers.create_inherent_data().unwrap();
+
+		let result = providers.check_inherents(data, |data| {
+			assert_eq!(data.get_data::<u32>(&TEST_INHERENT_0).unwrap(), Some(42));
+			let mut result = CheckInherentsResult::new();
+			result.put_error(TEST_INHERENT_0, &MakeFatalError::from(1u32))?;
+			Ok::<_, Error>(result)
+		}).unwrap();
+		assert!(result.fatal_error());
+		assert!(providers.check_inherents(InherentData::new(), |_| Err("failed")).is_err());
+	}
+
 	#[test]
 	fn encoded_error_to_string() {
 		let provider = TestInherentDataProvider::new();
@@ -66,11 +66,13 @@ impl BatchVerifier {
 		let invalid_clone = self.invalid.clone();
 		let (sender, receiver) = oneshot::channel();
 		self.pending_tasks.push(receiver);
+		// The task runs on another thread, keep its spans in the trace of the runtime call.
+		let span = sp_tracing::proxy::task_span(&sp_tracing::tracing::Span::current(), name);
 
 		self.scheduler.spawn(
 			name,
 			async move {
-				if !f() {
+				if !span.in_scope(f) {
 					invalid_clone.store(true, AtomicOrdering::Relaxed);
 				}
 				if sender.send(()).is_err() {
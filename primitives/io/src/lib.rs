@@ -35,16 +35,17 @@ use sp_std::ops::Deref;
 #[cfg(feature = "std")]
 use sp_core::{
 	crypto::Pair,
-	traits::{KeystoreExt, CallInWasmExt, TaskExecutorExt},
+	traits::{KeystoreExt, CallInWasmExt, TaskExecutorExt, TracingTargetsExt},
 	offchain::{OffchainExt, TransactionPoolExt},
 	hexdisplay::HexDisplay,
 	storage::ChildInfo,
 };
 
 use sp_core::{
-	crypto::KeyTypeId, ed25519, sr25519, ecdsa, H256, LogLevel,
+	crypto::KeyTypeId, ed25519, sr25519, ecdsa, H256, LogLevel, TracingCapabilities,
 	offchain::{
 		Timestamp, HttpRequestId, HttpRequestStatus, HttpError, StorageKind, OpaqueNetworkState,
+		NodeMetrics,
 	},
 };
 
@@ -794,6 +795,17 @@ pub trait Offchain {
 			.network_state()
 	}
 
+	/// Returns the recent performance aggregates of the local node, e.g. its average block
+	/// import time and the size of its transaction pool.
+	///
+	/// WARNING! This is a non-deterministic call, every node returning its own aggregates.
+	/// Do not use this within consensus critical logic.
+	fn node_metrics(&mut self) -> NodeMetrics {
+		self.extension::<OffchainExt>()
+			.expect("node_metrics can be called only in the offchain worker context")
+			.node_metrics()
+	}
+
 	/// Returns current UNIX timestamp (in millis)
 	fn timestamp(&mut self) -> Timestamp {
 		self.extension::<OffchainExt>()
@@ -986,6 +998,7 @@ pub trait Logging {
 	///
 	/// Instead of using directly, prefer setting up `RuntimeLogger` and using `log` macros.
 	fn log(level: LogLevel, target: &str, message: &[u8]) {
+		sp_tracing::probes::event(level as u64, target, message);
 		if let Ok(message) = std::str::from_utf8(message) {
 			log::log!(
 				target: target,
@@ -1003,38 +1016,98 @@ sp_externalities::decl_extension! {
 	pub struct TracingProxyExt(sp_tracing::proxy::TracingProxy);
 }
 
+#[cfg(feature = "std")]
+impl TracingProxyExt {
+	/// The proxy of an instance of the runtime spawned by the runtime of `ext`, to be registered
+	/// in the externalities of the spawned instance, see `TracingProxy::spawned`.
+	pub fn spawned(mut ext: &mut dyn Externalities, task_name: &str) -> Self {
+		let proxy = match ext.extension::<TracingProxyExt>() {
+			Some(proxy) => proxy.spawned(task_name),
+			None => sp_tracing::proxy::TracingProxy::new().spawned(task_name),
+		};
+		TracingProxyExt(proxy)
+	}
+}
+
+#[cfg(feature = "std")]
+sp_externalities::decl_extension! {
+	/// Extension exposing a monotonic clock to the runtime, see `wasm_tracing::monotonic_ns`.
+	///
+	/// Only registered by the tools benchmarking or tracing the runtime, never when importing or
+	/// authoring blocks, as the time is not deterministic.
+	pub struct MonotonicClockExt(std::time::Instant);
+}
+
+#[cfg(feature = "std")]
+impl MonotonicClockExt {
+	/// A clock counting from now.
+	pub fn new() -> Self {
+		MonotonicClockExt(std::time::Instant::now())
+	}
+}
+
+#[cfg(feature = "std")]
+impl Default for MonotonicClockExt {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
 /// Interface that provides functions for profiling the runtime.
 #[runtime_interface]
 pub trait WasmTracing {
 	/// To create and enter a `tracing` span, using `sp_tracing::proxy`
 	/// Returns 0 value to indicate that no further traces should be attempted
+	///
+	/// The on-chain tracing targets, stored under `well_known_keys::TRACING_TARGETS`, take
+	/// precedence over the node's settings for the targets they apply to.
 	fn enter_span(&mut self, target: &str, name: &str) -> u64 {
-		if sp_tracing::wasm_tracing_enabled() {
-			match self.extension::<TracingProxyExt>() {
-				Some(proxy) => return proxy.enter_span(target, name),
-				None => {
-					if self.register_extension(TracingProxyExt(sp_tracing::proxy::TracingProxy::new())).is_ok() {
-						if let Some(proxy) = self.extension::<TracingProxyExt>() {
-							return proxy.enter_span(target, name);
-						}
-					} else {
-						log::warn!(
-							target: "tracing",
-							"Unable to register extension: TracingProxyExt"
-						);
-					}
-				}
-			}
+		let on_chain = on_chain_tracing_targets(*self)
+			.and_then(|targets| sp_tracing::on_chain_target_enabled(&targets, target));
+		let id = if on_chain.unwrap_or_else(sp_tracing::wasm_tracing_enabled) {
+			enter_proxy_span(*self, target, name, on_chain.is_some())
+		} else {
+			0
+		};
+		sp_tracing::probes::span_enter(id, target, name);
+		if id == 0 {
+			log::debug!(
+				target: "tracing",
+				"Notify to runtime that tracing is disabled."
+			);
+		}
+		id
+	}
+
+	/// The tracing capabilities of the host, for the runtime to skip building expensive
+	/// diagnostic data that nobody listens to.
+	///
+	/// The targets of the node are only reported if wasm tracing is enabled, while the
+	/// on-chain targets, see `enter_span`, always are.
+	fn capabilities(&mut self) -> TracingCapabilities {
+		let on_chain = on_chain_tracing_targets(*self)
+			.and_then(|targets| String::from_utf8(targets).ok())
+			.unwrap_or_default();
+		let host = if sp_tracing::wasm_tracing_enabled() {
+			sp_tracing::host_targets()
+		} else {
+			String::new()
+		};
+
+		let targets = sp_tracing::parse_targets(&host)
+			.chain(sp_tracing::parse_targets(&on_chain))
+			.map(|(target, level)| (target.as_bytes().to_vec(), tracing_log_level(level)))
+			.collect::<Vec<_>>();
+		TracingCapabilities {
+			enabled: sp_tracing::wasm_tracing_enabled() || !on_chain.is_empty(),
+			max_level: targets.iter().map(|(_, level)| *level).max_by_key(|level| *level as u32),
+			targets,
 		}
-		log::debug!(
-			target: "tracing",
-			"Notify to runtime that tracing is disabled."
-		);
-		0
 	}
 
 	/// Exit a `tracing` span, using `sp_tracing::proxy`
 	fn exit_span(&mut self, id: u64) {
+		sp_tracing::probes::span_exit(id);
 		if let Some(proxy) = self.extension::<TracingProxyExt>() {
 			proxy.exit_span(id)
 		} else {
@@ -1044,6 +1117,130 @@ pub trait WasmTracing {
 			);
 		}
 	}
+
+	/// Record the declared `weight` of the span `id`, entered with `enter_span`, e.g. for the node
+	/// to measure the drift of the calls from the weights of their dispatch spans.
+	fn record_weight(&mut self, id: u64, weight: u64) {
+		if let Some(proxy) = self.extension::<TracingProxyExt>() {
+			proxy.record_weight(id, weight)
+		}
+	}
+
+	/// Emit the event of the failure of the current dispatch, within its span, with the indices
+	/// of the module and of the error of the module errors, see `frame_executive`.
+	fn dispatch_failed(module_error: Option<(u8, u8)>, error: &str) {
+		match module_error {
+			Some((module_index, error_index)) => sp_tracing::tracing::info!(
+				target: sp_tracing::DISPATCH_TARGET,
+				module_index = module_index as u64,
+				error_index = error_index as u64,
+				error,
+				"Dispatch failed",
+			),
+			None => sp_tracing::tracing::info!(
+				target: sp_tracing::DISPATCH_TARGET,
+				error,
+				"Dispatch failed",
+			),
+		}
+	}
+
+	/// Emit the `ERROR` event of an assertion of `frame_support::debug_assert_event` violated in
+	/// wasm, within the current span.
+	fn assertion_failed(condition: &str, location: &str, message: &str) {
+		sp_tracing::tracing::error!(
+			target: sp_tracing::ASSERTION_TARGET,
+			condition,
+			location,
+			"{}",
+			message,
+		);
+	}
+
+	/// Mark the start of the migrations run by the first block of a new runtime, for the node to
+	/// time them, see `sp_tracing::time_runtime_upgrade`.
+	fn runtime_upgrade_started() {
+		sp_tracing::runtime_upgrade_started();
+	}
+
+	/// Mark the end of the migrations, see `runtime_upgrade_started`.
+	fn runtime_upgrade_finished() {
+		sp_tracing::runtime_upgrade_finished();
+	}
+
+	/// The nanoseconds elapsed since the `MonotonicClockExt` was registered, for the runtime
+	/// to timestamp its own sections.
+	///
+	/// WARNING! This is a non-deterministic call, `None` unless benchmarking or tracing the
+	/// runtime with the extension registered. Do not use this within consensus critical logic.
+	fn monotonic_ns(&mut self) -> Option<u64> {
+		self.extension::<MonotonicClockExt>().map(|clock| clock.0.elapsed().as_nanos() as u64)
+	}
+
+	/// Enter the span of a client trace context, the `trace_id` the client supplied, using
+	/// `sp_tracing::proxy`. Exited with `exit_span`, like the other spans.
+	///
+	/// Returns 0 if wasm tracing is disabled, in which case the span must not be exited.
+	fn enter_trace_context(&mut self, trace_id: &[u8]) -> u64 {
+		if !sp_tracing::wasm_tracing_enabled() {
+			return 0;
+		}
+		if self.extension::<TracingProxyExt>().is_none()
+			&& self.register_extension(TracingProxyExt(sp_tracing::proxy::TracingProxy::new())).is_err()
+		{
+			return 0;
+		}
+		self.extension::<TracingProxyExt>().map_or(0, |proxy| proxy.enter_trace_context(trace_id))
+	}
+
+	/// The trace id of the trace context the host is in, e.g. of the offchain worker being run,
+	/// for the runtime to attach it to the transactions it submits, see `enter_trace_context`.
+	///
+	/// WARNING! This is a non-deterministic call, `None` unless the host traces the call within
+	/// a trace context. Do not use this within consensus critical logic.
+	fn trace_id() -> Option<Vec<u8>> {
+		let trace_id = sp_tracing::trace_id()?;
+		(0..trace_id.len()).step_by(2)
+			.map(|i| trace_id.get(i..i + 2).and_then(|byte| u8::from_str_radix(byte, 16).ok()))
+			.collect()
+	}
+}
+
+/// The on-chain tracing targets, as registered by the node in the `TracingTargetsExt` of `ext`.
+///
+/// They are never read from the storage here, as the reads would be recorded in the proofs of
+/// the wasm executions only.
+#[cfg(feature = "std")]
+fn on_chain_tracing_targets(mut ext: &mut dyn Externalities) -> Option<Vec<u8>> {
+	ext.extension::<TracingTargetsExt>()
+		.and_then(|targets| targets.targets().map(|targets| targets.to_vec()))
+}
+
+/// Enter a span of the `TracingProxyExt` of `ext`, registering it first if needed.
+#[cfg(feature = "std")]
+fn enter_proxy_span(mut ext: &mut dyn Externalities, target: &str, name: &str, on_chain: bool) -> u64 {
+	if ext.extension::<TracingProxyExt>().is_none()
+		&& ext.register_extension(TracingProxyExt(sp_tracing::proxy::TracingProxy::new())).is_err()
+	{
+		log::warn!(
+			target: "tracing",
+			"Unable to register extension: TracingProxyExt"
+		);
+		return 0;
+	}
+	ext.extension::<TracingProxyExt>().map_or(0, |proxy| proxy.enter_span(target, name, on_chain))
+}
+
+#[cfg(feature = "std")]
+fn tracing_log_level(level: sp_tracing::tracing::Level) -> LogLevel {
+	use sp_tracing::tracing::Level;
+	match level {
+		Level::ERROR => LogLevel::Error,
+		Level::WARN => LogLevel::Warn,
+		Level::INFO => LogLevel::Info,
+		Level::DEBUG => LogLevel::Debug,
+		Level::TRACE => LogLevel::Trace,
+	}
 }
 
 /// Wasm-only interface that provides functions for interacting with the sandbox.
@@ -1158,6 +1355,23 @@ mod allocator_impl {
 	}
 }
 
+/// The functions of `wasm_tracing` exiting and recording the spans entered by `enter_host_span`.
+#[cfg(not(feature = "std"))]
+static HOST_SPANS: sp_tracing::HostSpans = sp_tracing::HostSpans {
+	exit_span: wasm_tracing::exit_span,
+	record_weight: wasm_tracing::record_weight,
+};
+
+/// Enter the span `name` of `target` in the host, through `wasm_tracing::enter_span`, until the
+/// returned guard is dropped, e.g. for a span of a `sp_tracing::span_schema`, which is not entered
+/// in wasm otherwise.
+///
+/// The host only traces it if wasm tracing or the on-chain targets enable it.
+#[cfg(not(feature = "std"))]
+pub fn enter_host_span(target: &str, name: &str) -> sp_tracing::EnteredSpan {
+	sp_tracing::EnteredSpan::host(wasm_tracing::enter_span(target, name), &HOST_SPANS)
+}
+
 /// A default panic handler for WASM environment.
 #[cfg(all(not(feature = "disable_panic_handler"), not(feature = "std")))]
 #[panic_handler]
@@ -1404,4 +1618,39 @@ mod tests {
 			assert!(!crypto::finish_batch_verify());
 		});
 	}
+
+	#[test]
+	fn tracing_capabilities_include_on_chain_targets() {
+		let mut t = BasicExternalities::default();
+		t.execute_with(|| {
+			assert_eq!(wasm_tracing::capabilities(), TracingCapabilities::default());
+			// The targets are never read from the storage, only from the extension.
+			storage::set(sp_core::storage::well_known_keys::TRACING_TARGETS, b"balances");
+			assert_eq!(wasm_tracing::capabilities(), TracingCapabilities::default());
+		});
+
+		let mut t = BasicExternalities::default();
+		t.register_extension(TracingTargetsExt::new(Some(b"balances=debug,staking=warn".to_vec())));
+		t.execute_with(|| {
+			let capabilities = wasm_tracing::capabilities();
+			assert!(capabilities.enabled);
+			assert_eq!(capabilities.max_level, Some(LogLevel::Debug));
+			assert!(capabilities.is_enabled("balances::transfer", LogLevel::Info));
+			assert!(!capabilities.is_enabled("balances", LogLevel::Trace));
+			assert!(!capabilities.is_enabled("staking", LogLevel::Info));
+			assert!(!capabilities.is_enabled("democracy", LogLevel::Error));
+		});
+	}
+
+	#[test]
+	fn monotonic_clock_requires_the_extension() {
+		let mut t = BasicExternalities::default();
+		t.execute_with(|| assert_eq!(wasm_tracing::monotonic_ns(), None));
+
+		t.register_extension(MonotonicClockExt::new());
+		t.execute_with(|| {
+			let first = wasm_tracing::monotonic_ns().unwrap();
+			assert!(wasm_tracing::monotonic_ns().unwrap() >= first);
+		});
+	}
 }
@@ -19,7 +19,7 @@
 //!
 //! This crate provides the [`set`] function, which wraps around [`std::panic::set_hook`] and
 //! sets up a panic hook that prints a backtrace and invites the user to open an issue to the
-//! given URL.
+//! given URL. The report holds the spans the thread was in, see `sp_tracing::span_context`.
 //!
 //! By default, the panic handler aborts the process by calling [`std::process::exit`]. This can
 //! temporarily be disabled by using an [`AbortGuard`].
@@ -53,11 +53,20 @@ enum OnPanic {
 /// The `bug_url` parameter is an invitation for users to visit that URL to submit a bug report
 /// in the case where a panic happens.
 pub fn set(bug_url: &str, version: &str) {
+	set_with_exit_hook(bug_url, version, || ())
+}
+
+/// Set the panic hook, calling `on_exit` before the process is aborted.
+///
+/// Same as [`set`], except that `on_exit` is called right before [`std::process::exit`], which
+/// gives the opportunity to flush buffered data such as traces. It is not called when the panic
+/// unwinds.
+pub fn set_with_exit_hook(bug_url: &str, version: &str, on_exit: fn()) {
 	panic::set_hook(Box::new({
 		let version = version.to_string();
 		let bug_url = bug_url.to_string();
 		move |c| {
-			panic_hook(c, &bug_url, &version)
+			panic_hook(c, &bug_url, &version, on_exit)
 		}
 	}));
 }
@@ -131,7 +140,7 @@ impl Drop for AbortGuard {
 }
 
 /// Function being called when a panic happens.
-fn panic_hook(info: &PanicInfo, report_url: &str, version: &str) {
+fn panic_hook(info: &PanicInfo, report_url: &str, version: &str, on_exit: fn()) {
 	let location = info.location();
 	let file = location.as_ref().map(|l| l.file()).unwrap_or("<unknown>");
 	let line = location.as_ref().map(|l| l.line()).unwrap_or(0);
@@ -164,9 +173,14 @@ fn panic_hook(info: &PanicInfo, report_url: &str, version: &str) {
 		name, msg, file, line
 	);
 
+	if let Some(spans) = sp_tracing::span_context() {
+		let _ = writeln!(stderr, "Spans: {}", spans);
+	}
+
 	let _ = writeln!(stderr, ABOUT_PANIC!(), report_url);
 	ON_PANIC.with(|val| {
 		if val.get() == OnPanic::Abort {
+			on_exit();
 			::std::process::exit(1);
 		}
 	})
@@ -28,11 +28,12 @@ use sp_api::ProvideRuntimeApi;
 
 use sp_core::RuntimeDebug;
 use sp_core::crypto::KeyTypeId;
-use sp_staking::SessionIndex;
+pub use sp_staking::SessionIndex;
 use sp_std::vec::Vec;
 
 sp_api::decl_runtime_apis! {
 	/// Session keys runtime api.
+	#[api_version(2)]
 	pub trait SessionKeys {
 		/// Generate a set of session keys with optionally using the given seed.
 		/// The keys should be stored within the keystore exposed via runtime
@@ -47,6 +48,13 @@ sp_api::decl_runtime_apis! {
 		///
 		/// Returns the list of public raw public keys + key type.
 		fn decode_session_keys(encoded: Vec<u8>) -> Option<Vec<(Vec<u8>, KeyTypeId)>>;
+
+		/// The index of the current session, `None` if the runtime has no sessions.
+		fn current_session_index() -> Option<SessionIndex>;
+
+		/// The SCALE encoded session keys set by the SCALE encoded `validator` for the next
+		/// sessions, if any.
+		fn next_session_keys(validator: Vec<u8>) -> Option<Vec<u8>>;
 	}
 }
 
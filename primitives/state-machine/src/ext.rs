@@ -21,6 +21,7 @@ use crate::{
 	StorageKey, StorageValue, OverlayedChanges, StorageTransactionCache,
 	backend::Backend,
 	changes_trie::State as ChangesTrieState,
+	trace_comparison::STORAGE_ACCESS_TARGET,
 };
 
 use hash_db::Hasher;
@@ -36,6 +37,47 @@ use codec::{Decode, Encode, EncodeAppend};
 use std::{error, fmt, any::{Any, TypeId}};
 use log::{warn, trace};
 
+/// Kind of a storage access.
+enum StorageAccess {
+	Read,
+	Write,
+}
+
+/// Emit the event of a storage access of `method`, moving `len` bytes, recorded by the
+/// `BothTraced` strategy, see [`crate::trace_comparison`], and tallied in the spans and the block
+/// summaries by the node.
+///
+/// Like any event, it is only built when the subscriber is interested in the target.
+fn trace_access(
+	method: &'static str,
+	access: StorageAccess,
+	child_info: Option<&ChildInfo>,
+	key: &[u8],
+	len: usize,
+) {
+	let write = match access {
+		StorageAccess::Read => false,
+		StorageAccess::Write => true,
+	};
+	match child_info {
+		Some(child_info) => sp_tracing::tracing::trace!(
+			target: STORAGE_ACCESS_TARGET,
+			method,
+			write,
+			len = len as u64,
+			child = %HexDisplay::from(&child_info.storage_key()),
+			key = %HexDisplay::from(&key),
+		),
+		None => sp_tracing::tracing::trace!(
+			target: STORAGE_ACCESS_TARGET,
+			method,
+			write,
+			len = len as u64,
+			key = %HexDisplay::from(&key),
+		),
+	}
+}
+
 const EXT_NOT_ALLOWED_TO_FAIL: &str = "Externalities not allowed to fail within runtime";
 const BENCHMARKING_FN: &str = "\
 	This is a special fn only for benchmarking where a database commit happens from the runtime.
@@ -179,6 +221,7 @@ where
 		let _guard = sp_panic_handler::AbortGuard::force_abort();
 		let result = self.overlay.storage(key).map(|x| x.map(|x| x.to_vec())).unwrap_or_else(||
 			self.backend.storage(key).expect(EXT_NOT_ALLOWED_TO_FAIL));
+		trace_access("Get", StorageAccess::Read, None, key, result.as_ref().map_or(0, Vec::len));
 		trace!(target: "state", "{:04x}: Get {}={:?}",
 			self.id,
 			HexDisplay::from(&key),
@@ -194,6 +237,7 @@ where
 			.map(|x| x.map(|x| H::hash(x)))
 			.unwrap_or_else(|| self.backend.storage_hash(key).expect(EXT_NOT_ALLOWED_TO_FAIL));
 
+		trace_access("Hash", StorageAccess::Read, None, key, result.as_ref().map_or(0, |h| h.as_ref().len()));
 		trace!(target: "state", "{:04x}: Hash {}={:?}",
 			self.id,
 			HexDisplay::from(&key),
@@ -216,6 +260,7 @@ where
 					.expect(EXT_NOT_ALLOWED_TO_FAIL)
 			);
 
+		trace_access("GetChild", StorageAccess::Read, Some(child_info), key, result.as_ref().map_or(0, Vec::len));
 		trace!(target: "state", "{:04x}: GetChild({}) {}={:?}",
 			self.id,
 			HexDisplay::from(&child_info.storage_key()),
@@ -240,6 +285,9 @@ where
 					.expect(EXT_NOT_ALLOWED_TO_FAIL)
 			);
 
+		trace_access(
+			"ChildHash", StorageAccess::Read, Some(child_info), key, result.as_ref().map_or(0, |h| h.as_ref().len()),
+		);
 		trace!(target: "state", "{:04x}: ChildHash({}) {}={:?}",
 			self.id,
 			HexDisplay::from(&child_info.storage_key()),
@@ -257,6 +305,7 @@ where
 			_ => self.backend.exists_storage(key).expect(EXT_NOT_ALLOWED_TO_FAIL),
 		};
 
+		trace_access("Exists", StorageAccess::Read, None, key, 0);
 		trace!(target: "state", "{:04x}: Exists {}={:?}",
 			self.id,
 			HexDisplay::from(&key),
@@ -280,6 +329,7 @@ where
 				.expect(EXT_NOT_ALLOWED_TO_FAIL),
 		};
 
+		trace_access("ChildExists", StorageAccess::Read, Some(child_info), key, 0);
 		trace!(target: "state", "{:04x}: ChildExists({}) {}={:?}",
 			self.id,
 			HexDisplay::from(&child_info.storage_key()),
@@ -332,6 +382,7 @@ where
 	}
 
 	fn place_storage(&mut self, key: StorageKey, value: Option<StorageValue>) {
+		trace_access("Put", StorageAccess::Write, None, &key, value.as_ref().map_or(0, Vec::len));
 		trace!(target: "state", "{:04x}: Put {}={:?}",
 			self.id,
 			HexDisplay::from(&key),
@@ -353,6 +404,7 @@ where
 		key: StorageKey,
 		value: Option<StorageValue>,
 	) {
+		trace_access("PutChild", StorageAccess::Write, Some(child_info), &key, value.as_ref().map_or(0, Vec::len));
 		trace!(target: "state", "{:04x}: PutChild({}) {}={:?}",
 			self.id,
 			HexDisplay::from(&child_info.storage_key()),
@@ -369,6 +421,7 @@ where
 		&mut self,
 		child_info: &ChildInfo,
 	) {
+		trace_access("KillChild", StorageAccess::Write, Some(child_info), &[], 0);
 		trace!(target: "state", "{:04x}: KillChild({})",
 			self.id,
 			HexDisplay::from(&child_info.storage_key()),
@@ -383,6 +436,7 @@ where
 	}
 
 	fn clear_prefix(&mut self, prefix: &[u8]) {
+		trace_access("ClearPrefix", StorageAccess::Write, None, prefix, 0);
 		trace!(target: "state", "{:04x}: ClearPrefix {}",
 			self.id,
 			HexDisplay::from(&prefix),
@@ -405,6 +459,7 @@ where
 		child_info: &ChildInfo,
 		prefix: &[u8],
 	) {
+		trace_access("ClearChildPrefix", StorageAccess::Write, Some(child_info), prefix, 0);
 		trace!(target: "state", "{:04x}: ClearChildPrefix({}) {}",
 			self.id,
 			HexDisplay::from(&child_info.storage_key()),
@@ -424,6 +479,7 @@ where
 		key: Vec<u8>,
 		value: Vec<u8>,
 	) {
+		trace_access("Append", StorageAccess::Write, None, &key, value.len());
 		trace!(target: "state", "{:04x}: Append {}={}",
 			self.id,
 			HexDisplay::from(&key),
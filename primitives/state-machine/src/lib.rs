@@ -26,7 +26,7 @@ use codec::{Decode, Encode, Codec};
 use sp_core::{
 	offchain::storage::OffchainOverlayedChanges,
 	storage::ChildInfo, NativeOrEncoded, NeverNativeValue, hexdisplay::HexDisplay,
-	traits::{CodeExecutor, CallInWasmExt, RuntimeCode, SpawnNamed},
+	traits::{CodeExecutor, CallInWasmExt, RuntimeCode, SpawnNamed, TracingTargetsExt},
 };
 use sp_externalities::Extensions;
 
@@ -43,6 +43,7 @@ mod trie_backend;
 mod trie_backend_essence;
 mod stats;
 mod read_only;
+pub mod trace_comparison;
 
 pub use sp_trie::{trie_types::{Layout, TrieDBMut}, StorageProof, TrieMut, DBValue, MemoryDB};
 pub use testing::TestExternalities;
@@ -107,6 +108,9 @@ pub enum ExecutionStrategy {
 	Both,
 	/// First native, then if that fails or is not possible, wasm.
 	NativeElseWasm,
+	/// Like `Both`, tracing both variants and reporting any divergence of their spans, storage
+	/// accesses or durations. Meant for diagnostics, it is slower than `Both`.
+	BothTraced,
 }
 
 /// Storage backend trust level.
@@ -133,6 +137,8 @@ pub enum ExecutionManager<F> {
 	Both(F),
 	/// First native, then if that fails or is not possible, wasm.
 	NativeElseWasm,
+	/// Like `Both`, also comparing the traces of both variants, see [`trace_comparison`].
+	BothTraced(F),
 }
 
 impl<'a, F> From<&'a ExecutionManager<F>> for ExecutionStrategy {
@@ -142,6 +148,7 @@ impl<'a, F> From<&'a ExecutionManager<F>> for ExecutionStrategy {
 			ExecutionManager::AlwaysWasm(_) => ExecutionStrategy::AlwaysWasm,
 			ExecutionManager::NativeElseWasm => ExecutionStrategy::NativeElseWasm,
 			ExecutionManager::Both(_) => ExecutionStrategy::Both,
+			ExecutionManager::BothTraced(_) => ExecutionStrategy::BothTraced,
 		}
 	}
 }
@@ -155,20 +162,27 @@ impl ExecutionStrategy {
 			ExecutionStrategy::AlwaysWasm => ExecutionManager::AlwaysWasm(BackendTrustLevel::Trusted),
 			ExecutionStrategy::NativeWhenPossible => ExecutionManager::NativeWhenPossible,
 			ExecutionStrategy::NativeElseWasm => ExecutionManager::NativeElseWasm,
-			ExecutionStrategy::Both => ExecutionManager::Both(|wasm_result, native_result| {
-				warn!(
-					"Consensus error between wasm {:?} and native {:?}. Using wasm.",
-					wasm_result,
-					native_result,
-				);
-				warn!("   Native result {:?}", native_result);
-				warn!("   Wasm result {:?}", wasm_result);
-				wasm_result
-			}),
+			ExecutionStrategy::Both => ExecutionManager::Both(on_consensus_failure),
+			ExecutionStrategy::BothTraced => ExecutionManager::BothTraced(on_consensus_failure),
 		}
 	}
 }
 
+/// Default handler of a consensus failure, logging both results and using the wasm one.
+fn on_consensus_failure<R: Encode, E: fmt::Debug>(
+	wasm_result: CallResult<R, E>,
+	native_result: CallResult<R, E>,
+) -> CallResult<R, E> {
+	warn!(
+		"Consensus error between wasm {:?} and native {:?}. Using wasm.",
+		wasm_result,
+		native_result,
+	);
+	warn!("   Native result {:?}", native_result);
+	warn!("   Wasm result {:?}", wasm_result);
+	wasm_result
+}
+
 /// Evaluate to ExecutionManager::NativeElseWasm, without having to figure out the type.
 pub fn native_else_wasm<E, R: Decode>() -> ExecutionManager<DefaultHandler<R, E>> {
 	ExecutionManager::NativeElseWasm
@@ -342,10 +356,18 @@ impl<'a, B, H, N, Exec> StateMachine<'a, B, H, N, Exec> where
 		(result, was_native)
 	}
 
+	/// The on-chain tracing targets registered by the node, see `TracingTargetsExt`.
+	fn tracing_targets(&mut self) -> Option<Vec<u8>> {
+		self.extensions.get_mut(std::any::TypeId::of::<TracingTargetsExt>())
+			.and_then(|extension| extension.downcast_ref::<TracingTargetsExt>())
+			.and_then(|extension| extension.targets().map(|targets| targets.to_vec()))
+	}
+
 	fn execute_call_with_both_strategy<Handler, R, NC>(
 		&mut self,
 		mut native_call: Option<NC>,
 		on_consensus_failure: Handler,
+		traced: bool,
 	) -> CallResult<R, Exec::Error>
 		where
 			R: Decode + Encode + PartialEq,
@@ -356,14 +378,36 @@ impl<'a, B, H, N, Exec> StateMachine<'a, B, H, N, Exec> where
 			) -> CallResult<R, Exec::Error>
 	{
 		self.overlay.start_transaction();
-		let (result, was_native) = self.execute_aux(true, native_call.take());
+		// Both executions are recorded under the filter of the wasm spans as of the call.
+		let filter = if traced {
+			Some(trace_comparison::SpanFilter::new(self.tracing_targets()))
+		} else {
+			None
+		};
+		let ((result, was_native), native_trace) = match &filter {
+			Some(filter) => {
+				let (outcome, trace) = trace_comparison::record(
+					filter.clone(),
+					|| self.execute_aux(true, native_call.take()),
+				);
+				(outcome, Some(trace))
+			},
+			None => (self.execute_aux(true, native_call.take()), None),
+		};
 
 		if was_native {
 			self.overlay.rollback_transaction().expect(PROOF_CLOSE_TRANSACTION);
-			let (wasm_result, _) = self.execute_aux(
-				false,
-				native_call,
-			);
+			let (wasm_result, _) = match (native_trace, filter) {
+				(Some(native_trace), Some(filter)) => {
+					let (outcome, wasm_trace) = trace_comparison::record(filter, || self.execute_aux(
+						false,
+						native_call,
+					));
+					trace_comparison::report(self.method, &native_trace, &wasm_trace);
+					outcome
+				},
+				_ => self.execute_aux(false, native_call),
+			};
 
 			if (result.is_ok() && wasm_result.is_ok()
 				&& result.as_ref().ok() == wasm_result.as_ref().ok())
@@ -371,6 +415,13 @@ impl<'a, B, H, N, Exec> StateMachine<'a, B, H, N, Exec> where
 			{
 				result
 			} else {
+				sp_tracing::event!(
+					warn;
+					target: sp_tracing::EXECUTION_TARGET,
+					method = self.method,
+					reason = "consensus_failure",
+					"Native and wasm execution diverged",
+				);
 				on_consensus_failure(wasm_result, result)
 			}
 		} else {
@@ -397,6 +448,14 @@ impl<'a, B, H, N, Exec> StateMachine<'a, B, H, N, Exec> where
 			self.overlay.commit_transaction().expect(PROOF_CLOSE_TRANSACTION);
 			result
 		} else {
+			sp_tracing::event!(
+				warn;
+				target: sp_tracing::EXECUTION_TARGET,
+				method = self.method,
+				reason = "native_failed",
+				error = ?result.as_ref().err(),
+				"Native execution failed, falling back to wasm",
+			);
 			self.overlay.rollback_transaction().expect(PROOF_CLOSE_TRANSACTION);
 			let (wasm_result, _) = self.execute_aux(
 				false,
@@ -431,12 +490,27 @@ impl<'a, B, H, N, Exec> StateMachine<'a, B, H, N, Exec> where
 		let changes_tries_enabled = self.changes_trie_state.is_some();
 		self.overlay.set_collect_extrinsics(changes_tries_enabled);
 
+		sp_tracing::enter_span!(
+			target: sp_tracing::EXECUTION_TARGET,
+			"execute";
+			method = self.method,
+			strategy = ?ExecutionStrategy::from(&manager)
+		);
+
 		let result = {
 			match manager {
 				ExecutionManager::Both(on_consensus_failure) => {
 					self.execute_call_with_both_strategy(
 						native_call.take(),
 						on_consensus_failure,
+						false,
+					)
+				},
+				ExecutionManager::BothTraced(on_consensus_failure) => {
+					self.execute_call_with_both_strategy(
+						native_call.take(),
+						on_consensus_failure,
+						true,
 					)
 				},
 				ExecutionManager::NativeElseWasm => {
@@ -902,6 +976,77 @@ mod tests {
 		assert_eq!(state_machine.execute(ExecutionStrategy::NativeElseWasm).unwrap(), vec![66]);
 	}
 
+	#[test]
+	fn native_else_wasm_traces_the_fallback() {
+		use sp_tracing::tracing::{self, field, span, Event, Metadata, Subscriber};
+
+		/// Collects the `reason` of the events of the `EXECUTION_TARGET`.
+		#[derive(Clone, Default)]
+		struct Fallbacks(std::sync::Arc<parking_lot::Mutex<Vec<String>>>);
+
+		impl field::Visit for Fallbacks {
+			fn record_str(&mut self, field: &field::Field, value: &str) {
+				if field.name() == "reason" {
+					self.0.lock().push(value.to_owned());
+				}
+			}
+
+			fn record_debug(&mut self, _field: &field::Field, _value: &dyn std::fmt::Debug) {}
+		}
+
+		impl Subscriber for Fallbacks {
+			fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+				metadata.target() == sp_tracing::EXECUTION_TARGET
+			}
+
+			fn new_span(&self, _span: &span::Attributes<'_>) -> span::Id {
+				span::Id::from_u64(1)
+			}
+
+			fn record(&self, _span: &span::Id, _values: &span::Record<'_>) {}
+
+			fn record_follows_from(&self, _span: &span::Id, _follows: &span::Id) {}
+
+			fn event(&self, event: &Event<'_>) {
+				event.record(&mut self.clone());
+			}
+
+			fn enter(&self, _span: &span::Id) {}
+
+			fn exit(&self, _span: &span::Id) {}
+		}
+
+		let backend = trie_backend::tests::test_trie();
+		let mut overlayed_changes = Default::default();
+		let mut offchain_overlayed_changes = Default::default();
+		let wasm_code = RuntimeCode::empty();
+
+		let mut state_machine = StateMachine::new(
+			&backend,
+			changes_trie::disabled_state::<_, u64>(),
+			&mut overlayed_changes,
+			&mut offchain_overlayed_changes,
+			&DummyCodeExecutor {
+				change_changes_trie_config: false,
+				native_available: true,
+				native_succeeds: false,
+				fallback_succeeds: true,
+			},
+			"test",
+			&[],
+			Default::default(),
+			&wasm_code,
+			TaskExecutor::new(),
+		);
+
+		let fallbacks = Fallbacks::default();
+		let result = tracing::subscriber::with_default(fallbacks.clone(), || {
+			state_machine.execute(ExecutionStrategy::NativeElseWasm)
+		});
+		assert_eq!(result.unwrap(), vec![66]);
+		assert_eq!(*fallbacks.0.lock(), vec!["native_failed".to_owned()]);
+	}
+
 	#[test]
 	fn dual_execution_strategy_detects_consensus_failure() {
 		let mut consensus_failed = false;
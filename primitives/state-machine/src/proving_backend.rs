@@ -31,6 +31,7 @@ use crate::trie_backend::TrieBackend;
 use crate::trie_backend_essence::{Ephemeral, TrieBackendEssence, TrieBackendStorage};
 use crate::{Error, ExecutionError, Backend, DBValue};
 use sp_core::storage::ChildInfo;
+use sp_tracing::PROOF_SIZE_TARGET;
 
 /// Patricia trie-based backend specialized in get value proofs.
 pub struct ProvingBackendRecorder<'a, S: 'a + TrieBackendStorage<H>, H: 'a + Hasher> {
@@ -123,6 +124,8 @@ pub struct ProvingBackend<'a, S: 'a + TrieBackendStorage<H>, H: 'a + Hasher> (
 pub struct ProofRecorderBackend<'a, S: 'a + TrieBackendStorage<H>, H: 'a + Hasher> {
 	backend: &'a S,
 	proof_recorder: ProofRecorder<H>,
+	/// Whether the trie nodes added to the proof are reported as `PROOF_SIZE_TARGET` events.
+	trace_proof_size: bool,
 }
 
 impl<'a, S: 'a + TrieBackendStorage<H>, H: 'a + Hasher> ProvingBackend<'a, S, H>
@@ -130,20 +133,32 @@ impl<'a, S: 'a + TrieBackendStorage<H>, H: 'a + Hasher> ProvingBackend<'a, S, H>
 {
 	/// Create new proving backend.
 	pub fn new(backend: &'a TrieBackend<S, H>) -> Self {
-		let proof_recorder = Default::default();
-		Self::new_with_recorder(backend, proof_recorder)
+		Self::with_recorder(backend, Default::default(), false)
 	}
 
 	/// Create new proving backend with the given recorder.
+	///
+	/// This is the backend of the blocks being built recording a proof, so the trie nodes added
+	/// to the proof are reported as `sp_tracing::PROOF_SIZE_TARGET` events, unlike those of the
+	/// proofs of the RPCs and the light clients, created with `new`.
 	pub fn new_with_recorder(
 		backend: &'a TrieBackend<S, H>,
 		proof_recorder: ProofRecorder<H>,
+	) -> Self {
+		Self::with_recorder(backend, proof_recorder, true)
+	}
+
+	fn with_recorder(
+		backend: &'a TrieBackend<S, H>,
+		proof_recorder: ProofRecorder<H>,
+		trace_proof_size: bool,
 	) -> Self {
 		let essence = backend.essence();
 		let root = essence.root().clone();
 		let recorder = ProofRecorderBackend {
 			backend: essence.backend_storage(),
 			proof_recorder,
+			trace_proof_size,
 		};
 		ProvingBackend(TrieBackend::new(recorder, root))
 	}
@@ -169,7 +184,10 @@ impl<'a, S: 'a + TrieBackendStorage<H>, H: 'a + Hasher> TrieBackendStorage<H>
 			return Ok(v.clone());
 		}
 		let backend_value =  self.backend.get(key, prefix)?;
-		self.proof_recorder.write().insert(key.clone(), backend_value.clone());
+		let recorded = self.proof_recorder.write().insert(key.clone(), backend_value.clone());
+		if let (true, None, Some(node)) = (self.trace_proof_size, recorded, &backend_value) {
+			sp_tracing::tracing::trace!(target: PROOF_SIZE_TARGET, bytes = node.len() as u64);
+		}
 		Ok(backend_value)
 	}
 }
@@ -0,0 +1,418 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Comparison of the traces of the native and wasm executions of a call, see
+//! [`ExecutionStrategy::BothTraced`](crate::ExecutionStrategy::BothTraced).
+//!
+//! Each execution is traced by a [`Recorder`] set as the default subscriber of the thread, which
+//! records the spans in the order they are opened, with their depth, the time spent in every
+//! target and name, and the storage accesses of the externalities in order.
+//!
+//! The spans and events of the traced executions are not handed to the subscriber of the node.
+//! The spans of the wasm execution are only recorded if the runtime was built with tracing and
+//! its targets are enabled on the host, see `sp_io::wasm_tracing`, so both executions are
+//! recorded under the same [`SpanFilter`]. The reads of the on-chain tracing targets, done by the
+//! tracing of the wasm execution itself, are not recorded either.
+
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+use std::sync::{Arc, atomic::{AtomicU64, Ordering}};
+use std::time::{Duration, Instant};
+
+use log::{debug, warn};
+use parking_lot::Mutex;
+use sp_tracing::proxy::{WASM_NAME_KEY, WASM_TARGET_KEY, WASM_TRACE_IDENTIFIER};
+use sp_tracing::tracing::{
+	self,
+	field::{Field, Visit},
+	span::{Attributes, Id, Record},
+	Event, Metadata, Subscriber,
+};
+
+pub use sp_tracing::STORAGE_ACCESS_TARGET;
+
+/// Number of targets and names reported with the largest difference of time.
+const REPORTED_DURATIONS: usize = 5;
+
+/// The trace of an execution.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ExecutionTrace {
+	/// The spans, in the order they were opened, as `depth target::name`.
+	pub spans: Vec<String>,
+	/// The storage accesses, in order, as `method key` or `method child key`.
+	pub storage_accesses: Vec<String>,
+	/// The time spent in the spans, by target and name.
+	pub durations: BTreeMap<String, Duration>,
+}
+
+/// The targets whose spans are recorded, the ones the wasm execution enters spans of.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct SpanFilter {
+	/// The on-chain tracing targets, see `well_known_keys::TRACING_TARGETS`.
+	pub on_chain: Option<Vec<u8>>,
+	/// Whether wasm tracing is enabled on the host.
+	pub wasm_tracing: bool,
+}
+
+impl SpanFilter {
+	/// The filter of the executions of the current thread, given the on-chain targets.
+	pub fn new(on_chain: Option<Vec<u8>>) -> Self {
+		SpanFilter { on_chain, wasm_tracing: sp_tracing::wasm_tracing_enabled() }
+	}
+
+	/// Whether the spans of `target` are recorded, as decided by `sp_io::wasm_tracing::enter_span`.
+	fn enabled(&self, target: &str) -> bool {
+		self.on_chain.as_ref()
+			.and_then(|targets| sp_tracing::on_chain_target_enabled(targets, target))
+			.unwrap_or(self.wasm_tracing)
+	}
+}
+
+struct OpenSpan {
+	/// The target and name of the span, `None` if it is filtered out.
+	key: Option<String>,
+	entered: Option<Instant>,
+	time: Duration,
+	refs: usize,
+}
+
+#[derive(Default)]
+struct Recording {
+	open: HashMap<u64, OpenSpan>,
+	/// The entered spans, with whether they are recorded.
+	stack: Vec<(u64, bool)>,
+	trace: ExecutionTrace,
+}
+
+/// Subscriber recording the [`ExecutionTrace`] of the execution it is the default of.
+pub(crate) struct Recorder {
+	next_id: AtomicU64,
+	filter: SpanFilter,
+	recording: Arc<Mutex<Recording>>,
+}
+
+impl Recording {
+	fn finish(&mut self) -> ExecutionTrace {
+		// The spans still open, e.g. after a panic, are accounted as they are.
+		for (_, span) in std::mem::take(&mut self.open) {
+			if let Some(key) = span.key {
+				*self.trace.durations.entry(key).or_default() += span.time;
+			}
+		}
+		std::mem::take(&mut self.trace)
+	}
+}
+
+#[derive(Default)]
+struct Fields {
+	wasm_target: Option<String>,
+	wasm_name: Option<String>,
+	rendered: Vec<String>,
+}
+
+impl Visit for Fields {
+	fn record_str(&mut self, field: &Field, value: &str) {
+		match field.name() {
+			WASM_TARGET_KEY => self.wasm_target = Some(value.to_owned()),
+			WASM_NAME_KEY => self.wasm_name = Some(value.to_owned()),
+			_ => self.rendered.push(value.to_owned()),
+		}
+	}
+
+	fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+		self.record_str(field, &format!("{:?}", value));
+	}
+}
+
+impl Subscriber for Recorder {
+	fn enabled(&self, _: &Metadata<'_>) -> bool {
+		true
+	}
+
+	fn new_span(&self, attrs: &Attributes<'_>) -> Id {
+		let metadata = attrs.metadata();
+		let mut fields = Fields::default();
+		attrs.record(&mut fields);
+		let (target, name) = match (metadata.name(), fields.wasm_target, fields.wasm_name) {
+			(WASM_TRACE_IDENTIFIER, Some(target), Some(name)) => (target, name),
+			(name, _, _) => (metadata.target().to_owned(), name.to_owned()),
+		};
+		let key = if self.filter.enabled(&target) {
+			Some(format!("{}::{}", target, name))
+		} else {
+			None
+		};
+		let id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+		let mut recording = self.recording.lock();
+		if let Some(key) = &key {
+			let depth = recording.stack.iter().filter(|(_, recorded)| *recorded).count();
+			recording.trace.spans.push(format!("{} {}", depth, key));
+		}
+		recording.open.insert(id, OpenSpan { key, entered: None, time: Duration::default(), refs: 1 });
+		Id::from_u64(id)
+	}
+
+	fn record(&self, _: &Id, _: &Record<'_>) {}
+
+	fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+	fn event(&self, event: &Event<'_>) {
+		if event.metadata().target() != STORAGE_ACCESS_TARGET {
+			return;
+		}
+		let mut fields = Fields::default();
+		event.record(&mut fields);
+		self.recording.lock().trace.storage_accesses.push(fields.rendered.join(" "));
+	}
+
+	fn enter(&self, span: &Id) {
+		let mut recording = self.recording.lock();
+		let recorded = match recording.open.get_mut(&span.into_u64()) {
+			Some(span) => {
+				span.entered = Some(Instant::now());
+				span.key.is_some()
+			},
+			None => false,
+		};
+		recording.stack.push((span.into_u64(), recorded));
+	}
+
+	fn exit(&self, span: &Id) {
+		let mut recording = self.recording.lock();
+		recording.stack.pop();
+		if let Some(span) = recording.open.get_mut(&span.into_u64()) {
+			if let Some(entered) = span.entered.take() {
+				span.time += entered.elapsed();
+			}
+		}
+	}
+
+	fn clone_span(&self, span: &Id) -> Id {
+		if let Some(span) = self.recording.lock().open.get_mut(&span.into_u64()) {
+			span.refs += 1;
+		}
+		span.clone()
+	}
+
+	fn try_close(&self, span: Id) -> bool {
+		let mut recording = self.recording.lock();
+		let closed = match recording.open.get_mut(&span.into_u64()) {
+			Some(open) => {
+				open.refs -= 1;
+				open.refs == 0
+			},
+			None => return false,
+		};
+		if closed {
+			if let Some(OpenSpan { key: Some(key), time, .. }) = recording.open.remove(&span.into_u64()) {
+				*recording.trace.durations.entry(key).or_default() += time;
+			}
+		}
+		closed
+	}
+}
+
+/// Run `f` with a [`Recorder`] as the default subscriber, returning its result and its trace.
+///
+/// The spans and events of `f` are not handed to the subscriber of the node.
+pub(crate) fn record<R>(filter: SpanFilter, f: impl FnOnce() -> R) -> (R, ExecutionTrace) {
+	let recording = Arc::new(Mutex::new(Recording::default()));
+	let dispatch = tracing::Dispatch::new(Recorder {
+		next_id: AtomicU64::new(0),
+		filter,
+		recording: recording.clone(),
+	});
+	let result = tracing::dispatcher::with_default(&dispatch, f);
+	let trace = recording.lock().finish();
+	(result, trace)
+}
+
+/// The first position two sequences differ at, with the items of each there.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divergence {
+	/// Position of the first differing item.
+	pub position: usize,
+	/// The native item, `None` if the native sequence ended.
+	pub native: Option<String>,
+	/// The wasm item, `None` if the wasm sequence ended.
+	pub wasm: Option<String>,
+}
+
+fn divergence(native: &[String], wasm: &[String]) -> Option<Divergence> {
+	let position = native.iter().zip(wasm).position(|(n, w)| n != w)
+		.or_else(|| if native.len() != wasm.len() { Some(native.len().min(wasm.len())) } else { None })?;
+	Some(Divergence {
+		position,
+		native: native.get(position).cloned(),
+		wasm: wasm.get(position).cloned(),
+	})
+}
+
+/// The differences between the native and wasm traces of a call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceComparison {
+	/// Where the spans first differ, if they do.
+	pub spans: Option<Divergence>,
+	/// Where the storage accesses first differ, if they do.
+	pub storage_accesses: Option<Divergence>,
+	/// The targets and names with the largest difference of time, with the native and wasm
+	/// times.
+	pub durations: Vec<(String, Duration, Duration)>,
+}
+
+impl TraceComparison {
+	/// Compare the `native` and `wasm` traces of a call.
+	pub fn new(native: &ExecutionTrace, wasm: &ExecutionTrace) -> Self {
+		let mut durations: Vec<_> = native.durations.keys()
+			.chain(wasm.durations.keys())
+			.collect::<BTreeSet<_>>()
+			.into_iter()
+			.map(|key| (
+				key.clone(),
+				native.durations.get(key).copied().unwrap_or_default(),
+				wasm.durations.get(key).copied().unwrap_or_default(),
+			))
+			.collect();
+		let difference = |(_, native, wasm): &(String, Duration, Duration)| {
+			if native > wasm { *native - *wasm } else { *wasm - *native }
+		};
+		durations.sort_by_key(|entry| Reverse(difference(entry)));
+		durations.truncate(REPORTED_DURATIONS);
+		TraceComparison {
+			spans: divergence(&native.spans, &wasm.spans),
+			storage_accesses: divergence(&native.storage_accesses, &wasm.storage_accesses),
+			durations,
+		}
+	}
+
+	/// Whether the spans or the storage accesses differ.
+	pub fn diverges(&self) -> bool {
+		self.spans.is_some() || self.storage_accesses.is_some()
+	}
+}
+
+/// Log the comparison of the `native` and `wasm` traces of `method`, divergences as warnings.
+pub(crate) fn report(method: &str, native: &ExecutionTrace, wasm: &ExecutionTrace) {
+	let comparison = TraceComparison::new(native, wasm);
+	if let Some(divergence) = &comparison.spans {
+		warn!(
+			"Spans of {} diverge between native and wasm at {}: native {:?}, wasm {:?}",
+			method,
+			divergence.position,
+			divergence.native,
+			divergence.wasm,
+		);
+	}
+	if let Some(divergence) = &comparison.storage_accesses {
+		warn!(
+			"Storage accesses of {} diverge between native and wasm at {}: native {:?}, wasm {:?}",
+			method,
+			divergence.position,
+			divergence.native,
+			divergence.wasm,
+		);
+	}
+	let total = |trace: &ExecutionTrace| trace.durations.values().sum::<Duration>();
+	debug!(
+		"Traces of {}: {} and {} spans, {} and {} storage accesses, {:?} and {:?} in spans \
+		natively and in wasm, largest differences {:?}",
+		method,
+		native.spans.len(),
+		wasm.spans.len(),
+		native.storage_accesses.len(),
+		wasm.storage_accesses.len(),
+		total(native),
+		total(wasm),
+		comparison.durations,
+	);
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn trace(spans: &[&str], storage_accesses: &[&str]) -> ExecutionTrace {
+		ExecutionTrace {
+			spans: spans.iter().map(|s| s.to_string()).collect(),
+			storage_accesses: storage_accesses.iter().map(|s| s.to_string()).collect(),
+			durations: Default::default(),
+		}
+	}
+
+	fn all_spans() -> SpanFilter {
+		SpanFilter { on_chain: None, wasm_tracing: true }
+	}
+
+	#[test]
+	fn recorder_records_spans_and_storage_accesses() {
+		let ((), trace) = record(all_spans(), || {
+			let outer = tracing::info_span!(target: "frame", "execute_block");
+			let _enter = outer.enter();
+			tracing::trace!(target: STORAGE_ACCESS_TARGET, method = "Get", key = "0x00");
+			tracing::info_span!(target: "frame", "apply_extrinsic").in_scope(|| {
+				tracing::trace!(target: STORAGE_ACCESS_TARGET, method = "Put", key = "0x01");
+			});
+			tracing::trace!(target: "other", "not a storage access");
+		});
+
+		assert_eq!(trace.spans, vec!["0 frame::execute_block", "1 frame::apply_extrinsic"]);
+		assert_eq!(trace.storage_accesses, vec!["Get 0x00", "Put 0x01"]);
+		assert_eq!(trace.durations.len(), 2);
+	}
+
+	#[test]
+	fn recorder_records_the_spans_of_the_enabled_targets_only() {
+		let filter = SpanFilter { on_chain: Some(b"frame=warn,frame::balances".to_vec()), wasm_tracing: true };
+		let ((), trace) = record(filter, || {
+			tracing::info_span!(target: "frame", "execute_block").in_scope(|| {
+				tracing::info_span!(target: "frame::balances", "transfer").in_scope(|| {
+					tracing::info_span!(target: "frame", "deposit_event").in_scope(|| {});
+				});
+			});
+			tracing::info_span!(target: "sync", "import").in_scope(|| {});
+		});
+		assert_eq!(trace.spans, vec!["0 frame::balances::transfer", "0 sync::import"]);
+		assert_eq!(trace.durations.len(), 2);
+
+		let ((), trace) = record(SpanFilter::default(), || {
+			tracing::info_span!(target: "frame", "execute_block").in_scope(|| {});
+		});
+		assert!(trace.spans.is_empty());
+	}
+
+	#[test]
+	fn first_divergences_are_reported() {
+		let native = trace(&["0 a", "1 b", "1 c"], &["Get 0x00", "Put 0x01"]);
+		let wasm = trace(&["0 a", "1 c"], &["Get 0x00", "Put 0x01", "Put 0x02"]);
+
+		let comparison = TraceComparison::new(&native, &wasm);
+
+		assert!(comparison.diverges());
+		assert_eq!(comparison.spans, Some(Divergence {
+			position: 1,
+			native: Some("1 b".into()),
+			wasm: Some("1 c".into()),
+		}));
+		assert_eq!(comparison.storage_accesses, Some(Divergence {
+			position: 2,
+			native: None,
+			wasm: Some("Put 0x02".into()),
+		}));
+		assert!(!TraceComparison::new(&native, &native).diverges());
+	}
+}
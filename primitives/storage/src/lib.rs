@@ -160,6 +160,11 @@ pub mod well_known_keys {
 	/// The type of this value is encoded `u64`.
 	pub const HEAP_PAGES: &'static [u8] = b":heappages";
 
+	/// Tracing targets of the spans of the runtime, overriding the ones of the nodes.
+	///
+	/// Stored as a UTF-8 comma separated list of targets, in the format of `--tracing-targets`.
+	pub const TRACING_TARGETS: &'static [u8] = b":tracing_targets";
+
 	/// Current extrinsic index (u32) is stored under this key.
 	pub const EXTRINSIC_INDEX: &'static [u8] = b":extrinsic_index";
 
@@ -0,0 +1,35 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+fn main() {
+	#[cfg(feature = "usdt")]
+	usdt();
+}
+
+/// Compile the USDT probes, on the targets whose binaries `probes.c` can annotate.
+#[cfg(feature = "usdt")]
+fn usdt() {
+	use std::env::var;
+
+	println!("cargo:rerun-if-changed=src/probes.c");
+	let os = var("CARGO_CFG_TARGET_OS").unwrap_or_default();
+	let width = var("CARGO_CFG_TARGET_POINTER_WIDTH").unwrap_or_default();
+	if os == "linux" && width == "64" {
+		cc::Build::new().file("src/probes.c").compile("sp_tracing_probes");
+		println!("cargo:rustc-cfg=usdt_probes");
+	}
+}
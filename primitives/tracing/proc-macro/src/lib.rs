@@ -0,0 +1,119 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Macros to instrument Substrate code with the spans of `sp-tracing`.
+//!
+//! See `sp_tracing::instrument_all` for the documentation.
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use proc_macro_crate::crate_name;
+use quote::quote;
+use syn::{
+	Error, Ident, ImplItem, ImplItemMethod, ItemImpl, LitStr, Result, Token, Type,
+	parse::{Parse, ParseStream},
+	parse_macro_input, parse_quote,
+};
+
+/// Attribute opting a method out of the instrumentation.
+const NO_INSTRUMENT: &str = "no_instrument";
+
+/// Arguments of the attribute: `target = "<target>"`, optionally.
+struct Args {
+	target: Option<LitStr>,
+}
+
+impl Parse for Args {
+	fn parse(input: ParseStream) -> Result<Self> {
+		if input.is_empty() {
+			return Ok(Args { target: None });
+		}
+		let key: Ident = input.parse()?;
+		if key != "target" {
+			return Err(Error::new(key.span(), "expected `target = \"...\"`"));
+		}
+		input.parse::<Token![=]>()?;
+		let target = input.parse()?;
+		if !input.is_empty() {
+			return Err(input.error("unexpected tokens after the target"));
+		}
+		Ok(Args { target: Some(target) })
+	}
+}
+
+#[proc_macro_attribute]
+pub fn instrument_all(args: TokenStream, input: TokenStream) -> TokenStream {
+	let args = parse_macro_input!(args as Args);
+	let mut item = parse_macro_input!(input as ItemImpl);
+	instrument_impl(args, &mut item);
+	quote!( #item ).into()
+}
+
+fn instrument_impl(args: Args, item: &mut ItemImpl) {
+	let crate_ = generate_crate_access();
+	let type_name = type_name(&item.self_ty);
+	for impl_item in &mut item.items {
+		if let ImplItem::Method(method) = impl_item {
+			if !opt_out(method) && can_instrument(method) {
+				let name = LitStr::new(&format!("{}::{}", type_name, method.sig.ident), Span::call_site());
+				let enter = match &args.target {
+					Some(target) => quote!( #crate_::enter_span!(target: #target, #name;); ),
+					None => quote!( #crate_::enter_span!(#name); ),
+				};
+				method.block.stmts.insert(0, parse_quote!( #enter ));
+			}
+		}
+	}
+}
+
+/// Whether the method has the opt-out attribute, which is removed.
+fn opt_out(method: &mut ImplItemMethod) -> bool {
+	let len = method.attrs.len();
+	method.attrs.retain(|attr| !attr.path.is_ident(NO_INSTRUMENT));
+	method.attrs.len() != len
+}
+
+/// The guard of a span can't be kept across the awaits of an `async` method, nor created in a
+/// `const` one.
+fn can_instrument(method: &ImplItemMethod) -> bool {
+	method.sig.asyncness.is_none() && method.sig.constness.is_none()
+}
+
+/// The name of the type the methods belong to, without its generics.
+fn type_name(ty: &Type) -> String {
+	match ty {
+		Type::Path(path) if path.qself.is_none() => path.path.segments.last()
+			.map(|segment| segment.ident.to_string())
+			.unwrap_or_default(),
+		Type::Reference(reference) => type_name(&reference.elem),
+		Type::Group(group) => type_name(&group.elem),
+		Type::Paren(paren) => type_name(&paren.elem),
+		ty => quote!( #ty ).to_string().replace(' ', ""),
+	}
+}
+
+/// Generates the access to the `sp-tracing` crate.
+fn generate_crate_access() -> TokenStream2 {
+	match crate_name("sp-tracing") {
+		Ok(name) => {
+			let name = Ident::new(&name, Span::call_site());
+			quote!( ::#name )
+		},
+		// The doc tests and integration tests of `sp-tracing` itself.
+		Err(_) => quote!( ::sp_tracing ),
+	}
+}
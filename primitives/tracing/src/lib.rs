@@ -18,7 +18,9 @@
 //! Substrate tracing primitives and macros.
 //!
 //! To trace functions or invidual code in Substrate, this crate provides [`tracing_span`]
-//! and [`enter_span`]. See the individual docs for how to use these macros.
+//! and [`enter_span`]. See the individual docs for how to use these macros. Whole `impl` blocks
+//! are instrumented with [`instrument_all`], and the spans entered in hot paths are declared
+//! once, with typed fields, by [`span_schema`].
 //!
 //! Note that to allow traces from wasm execution environment there are
 //! 2 reserved identifiers for tracing `Field` recording, stored in the consts:
@@ -28,6 +30,9 @@
 //! Additionally, we have a const: `WASM_TRACE_IDENTIFIER`, which holds a span name used
 //! to signal that the 'actual' span name and target should be retrieved instead from
 //! the associated Fields mentioned above.
+//!
+//! With the `usdt` feature, the node also fires the static probes of [`probes`], for tracers
+//! like bpftrace to attach to without any tracing receiver.
 #![cfg_attr(not(feature = "std"), no_std)]
 
 #[cfg(feature = "std")]
@@ -42,12 +47,85 @@ pub use tracing;
 pub mod proxy;
 
 #[cfg(feature = "std")]
-use std::sync::atomic::{AtomicBool, Ordering};
+pub mod probes;
+
+#[cfg(feature = "std")]
+use std::sync::{RwLock, atomic::{AtomicBool, AtomicUsize, Ordering}};
 
 /// Flag to signal whether to run wasm tracing
 #[cfg(feature = "std")]
 static WASM_TRACING_ENABLED: AtomicBool = AtomicBool::new(false);
 
+#[cfg(feature = "std")]
+lazy_static::lazy_static! {
+	/// Targets of the host's subscriber, see [`set_host_targets`].
+	static ref HOST_TARGETS: RwLock<String> = RwLock::new(String::new());
+}
+
+/// Provider of the spans of the current thread, see [`set_span_context`], as the address of the
+/// function, 0 if unset, so that the panic hook reads it without taking a lock.
+#[cfg(feature = "std")]
+static SPAN_CONTEXT: AtomicUsize = AtomicUsize::new(0);
+
+/// Provider of the trace id of the current thread, see [`set_trace_id`], like [`SPAN_CONTEXT`].
+#[cfg(feature = "std")]
+static TRACE_ID: AtomicUsize = AtomicUsize::new(0);
+
+#[cfg(feature = "std")]
+fn set_provider(slot: &AtomicUsize, provider: fn() -> Option<String>) {
+	slot.store(provider as usize, Ordering::Release);
+}
+
+#[cfg(feature = "std")]
+fn call_provider(slot: &AtomicUsize) -> Option<String> {
+	match slot.load(Ordering::Acquire) {
+		0 => None,
+		address => {
+			// Safe, only the addresses of `fn() -> Option<String>` are stored, by `set_provider`.
+			let provider = unsafe { std::mem::transmute::<usize, fn() -> Option<String>>(address) };
+			provider()
+		},
+	}
+}
+
+/// Target of the events emitted by [`counter`].
+pub const COUNTER_TARGET: &str = "sp_tracing::counter";
+
+/// Target of the events emitted by `frame_support::debug_assert_event` for the violated
+/// assertions, enabled by the node whatever its targets.
+pub const ASSERTION_TARGET: &str = "sp_tracing::assertion";
+
+/// Target of the events of the storage accesses of the externalities, with the `method`, the
+/// `key`, whether it is a `write` and the `len` of the value read or written.
+pub const STORAGE_ACCESS_TARGET: &str = "state-access";
+
+/// Target of the events of the trie nodes added to the storage proofs being recorded, with the
+/// `bytes` of the node.
+pub const PROOF_SIZE_TARGET: &str = "state-proof";
+
+/// Target and name of the span of the dispatch of an extrinsic by `frame_executive`, or of a call
+/// dispatched by another with a root or custom origin, holding the span of the call and recording
+/// the declared `weight` and the kind of `origin` of the dispatch, and target of the event of its
+/// failure, with its `error`.
+pub const DISPATCH_TARGET: &str = "dispatch";
+
+/// Target of the events of the runtime events deposited by `frame_system`, recording the SCALE
+/// encoded `event`, which the node summarizes in the dispatch span of the event with the
+/// metadata, see `sc_tracing::EVENTS_KEY`.
+pub const RUNTIME_EVENT_TARGET: &str = "runtime-event";
+
+/// Target of the span of the execution of a runtime call by the state machine, recording the
+/// `method` and the execution `strategy`, of the event of the strategy chosen for the `context`
+/// of a call, and of the events of the fallbacks from native to wasm execution, with their
+/// `reason`: `incompatible_version`, `native_failed` or `consensus_failure`.
+pub const EXECUTION_TARGET: &str = "execution";
+
+/// Target of the spans and events of the finality gadgets, e.g. GRANDPA, emitted by
+/// [`finality_round`], [`finality_vote`], [`finality_finalized`] and [`enter_finality_proof_span`].
+/// They all record the `gadget` emitting them, and the events the `event` they are, so that the
+/// node accounts all the gadgets alike.
+pub const FINALITY_TARGET: &str = "finality";
+
 /// Runs given code within a tracing span, measuring it's execution time.
 ///
 /// If tracing is not enabled, the code is still executed.
@@ -76,23 +154,453 @@ macro_rules! tracing_span {
 
 /// Enter a span.
 ///
-/// The span will be valid, until the scope is left.
+/// The span will be valid, until the scope is left, see [`entered_span`] otherwise. Fields can
+/// be recorded on the span using the syntax of `tracing::span!`, they are only evaluated if the
+/// tracing dependency is enabled, and then only if the subscriber enables the span: a disabled
+/// span costs the check of its cached interest. In wasm, nothing is evaluated. The target
+/// defaults to the module path and can be overridden when fields are given.
 ///
 /// # Example
 ///
 /// ```
 /// sp_tracing::enter_span!("test-span");
+/// sp_tracing::enter_span!("test-span-with-fields"; index = 1, name = "test");
+/// sp_tracing::enter_span!(target: "test-target", "test-span-with-target"; index = 1);
 /// ```
 #[macro_export]
 macro_rules! enter_span {
+	( target: $target:expr, $name:expr; $( $fields:tt )* ) => {
+		let __tracing_span__ = $crate::if_tracing!(
+			$crate::tracing::span!(target: $target, $crate::tracing::Level::TRACE, $name, $( $fields )*)
+		);
+		let __tracing_guard__ = $crate::if_tracing!(__tracing_span__.enter());
+	};
 	( $name:expr ) => {
 		let __tracing_span__ = $crate::if_tracing!(
 			$crate::tracing::span!($crate::tracing::Level::TRACE, $name)
 		);
 		let __tracing_guard__ = $crate::if_tracing!(__tracing_span__.enter());
+	};
+	( $name:expr; $( $fields:tt )* ) => {
+		let __tracing_span__ = $crate::if_tracing!(
+			$crate::tracing::span!($crate::tracing::Level::TRACE, $name, $( $fields )*)
+		);
+		let __tracing_guard__ = $crate::if_tracing!(__tracing_span__.enter());
+	};
+}
+
+/// Run a `for` loop within a child span of the current span per iteration, recording the
+/// `index` of the iteration, e.g. to trace the processing of every item of a queue in
+/// `on_initialize`.
+///
+/// Unlike spans named after their item, the spans of all the iterations share the metadata of a
+/// single callsite, which the subscriber registers once whatever the number of items. More fields
+/// can be recorded from the bindings of the pattern, after the iterator, and are evaluated like
+/// the fields of [`enter_span`]. The span of an iteration is exited when it ends, on `break`,
+/// `continue` and `?` as well, and nothing is evaluated nor entered in wasm.
+///
+/// # Example
+///
+/// ```
+/// let queue = vec![(1, "transfer"), (2, "remark")];
+/// sp_tracing::within_iteration_span! {
+///     target: "test-target", "process_item";
+///     for (id, call) in queue, id = id => {
+///         assert!(id > 0 && !call.is_empty());
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! within_iteration_span {
+	(
+		target: $target:expr, $name:expr;
+		for $pat:pat in $iter:expr $( , $field:ident = $value:expr )* => $body:block
+	) => {
+		for (__iteration_index__, $pat) in ::core::iter::Iterator::enumerate(
+			::core::iter::IntoIterator::into_iter($iter)
+		) {
+			$crate::enter_span!(
+				target: $target, $name; index = __iteration_index__ as u64 $( , $field = $value )*
+			);
+			$body
+		}
+	};
+	(
+		$name:expr;
+		for $pat:pat in $iter:expr $( , $field:ident = $value:expr )* => $body:block
+	) => {
+		$crate::within_iteration_span! {
+			target: module_path!(), $name;
+			for $pat in $iter $( , $field = $value )* => $body
+		}
+	};
+}
+
+/// Enter a span, like [`enter_span`], evaluating to its [`EnteredSpan`] guard.
+///
+/// The span is exited when the guard is dropped, so the guard can be stored, e.g. in a struct,
+/// dropped early, or returned to the caller. In wasm, like with [`enter_span`], nothing is
+/// evaluated nor entered, the runtime entering the spans of the host through `sp-io`, see
+/// `EnteredSpan::host`.
+///
+/// # Example
+///
+/// ```
+/// struct Import {
+///     _span: sp_tracing::EnteredSpan,
+/// }
+///
+/// let import = Import { _span: sp_tracing::entered_span!("import") };
+/// let verify = sp_tracing::entered_span!(target: "test-target", "verify"; index = 1);
+/// drop(verify);
+/// drop(import);
+/// ```
+#[macro_export]
+macro_rules! entered_span {
+	( target: $target:expr, $name:expr; $( $fields:tt )* ) => {
+		$crate::__entered_span!(
+			$target,
+			$name,
+			$crate::tracing::span!(target: $target, $crate::tracing::Level::TRACE, $name, $( $fields )*)
+		)
+	};
+	( $name:expr ) => {
+		$crate::__entered_span!(
+			module_path!(),
+			$name,
+			$crate::tracing::span!($crate::tracing::Level::TRACE, $name)
+		)
+	};
+	( $name:expr; $( $fields:tt )* ) => {
+		$crate::__entered_span!(
+			module_path!(),
+			$name,
+			$crate::tracing::span!($crate::tracing::Level::TRACE, $name, $( $fields )*)
+		)
+	};
+}
+
+#[doc(hidden)]
+#[macro_export]
+#[cfg(feature = "std")]
+macro_rules! __entered_span {
+	( $target:expr, $name:expr, $span:expr ) => {
+		$crate::EnteredSpan::new($span)
+	};
+}
+
+#[doc(hidden)]
+#[macro_export]
+#[cfg(not(feature = "std"))]
+macro_rules! __entered_span {
+	( $target:expr, $name:expr, $span:expr ) => {
+		$crate::EnteredSpan::none()
+	};
+}
+
+/// Emit an event within the current span.
+///
+/// Takes the level (`trace`, `debug`, `info`, `warn` or `error`) followed by the arguments of
+/// the `tracing` macro of that level. The arguments are only evaluated if the tracing dependency
+/// is enabled and the subscriber enables the event, like the fields of [`enter_span`].
+///
+/// # Example
+///
+/// ```
+/// sp_tracing::event!(info; target: "test-target", index = 1, "Something happened");
+/// ```
+#[macro_export]
+macro_rules! event {
+	( $level:ident; $( $args:tt )* ) => {
+		$crate::if_tracing!($crate::tracing::$level!($( $args )*))
+	}
+}
+
+/// Increments the counter `name` for the given `label`, formatted with `Debug`, by `value`.
+///
+/// The node sums up the increments of every counter and label, and exposes them as the
+/// `substrate_runtime_counters_total` Prometheus metric. Like any other event, this only has
+/// an effect when executing natively, and counts every execution of the code, e.g. a block
+/// being both authored and imported on the same node.
+///
+/// # Example
+///
+/// ```
+/// let asset_id = 1;
+/// sp_tracing::counter!("assets_transfers", asset_id, 1);
+/// ```
+#[macro_export]
+macro_rules! counter {
+	( $name:expr, $label:expr, $value:expr ) => {
+		$crate::event!(
+			trace;
+			target: $crate::COUNTER_TARGET,
+			counter = $name,
+			label = ?$label,
+			value = $value as u64
+		)
+	}
+}
+
+/// Signals the completion of the round `round` of the voter set `set_id` of the finality gadget
+/// `gadget`, e.g. `"grandpa"`, as an `INFO` event of the [`FINALITY_TARGET`].
+///
+/// The node counts the rounds of every gadget and the time between their completions, and
+/// exposes them as the `substrate_finality_rounds_total` and
+/// `substrate_finality_round_seconds_total` Prometheus metrics, along with the last round
+/// completed. Like the other events of the gadgets, it is accounted whatever the targets.
+///
+/// # Example
+///
+/// ```
+/// sp_tracing::finality_round!("beefy", 0, 42);
+/// ```
+#[macro_export]
+macro_rules! finality_round {
+	( $gadget:expr, $set_id:expr, $round:expr ) => {
+		$crate::event!(
+			info;
+			target: $crate::FINALITY_TARGET,
+			gadget = $gadget,
+			event = "round",
+			set_id = $set_id as u64,
+			round = $round as u64
+		)
+	}
+}
+
+/// Signals a vote of the `kind`, e.g. `"prevote"`, cast by the node in the round `round` of the
+/// finality gadget `gadget`, as a `DEBUG` event of the [`FINALITY_TARGET`], counted as the
+/// `substrate_finality_votes_total` Prometheus metric.
+///
+/// # Example
+///
+/// ```
+/// sp_tracing::finality_vote!("beefy", "vote", 42);
+/// ```
+#[macro_export]
+macro_rules! finality_vote {
+	( $gadget:expr, $kind:expr, $round:expr ) => {
+		$crate::event!(
+			debug;
+			target: $crate::FINALITY_TARGET,
+			gadget = $gadget,
+			event = "vote",
+			vote = $kind,
+			round = $round as u64
+		)
 	}
 }
 
+/// Signals the finalization of the block `number` by the finality gadget `gadget`, as an `INFO`
+/// event of the [`FINALITY_TARGET`], exposed as the `substrate_finality_finalized_number`
+/// Prometheus metric.
+///
+/// # Example
+///
+/// ```
+/// sp_tracing::finality_finalized!("beefy", 100);
+/// ```
+#[macro_export]
+macro_rules! finality_finalized {
+	( $gadget:expr, $number:expr ) => {
+		$crate::event!(
+			info;
+			target: $crate::FINALITY_TARGET,
+			gadget = $gadget,
+			event = "finalized",
+			number = $number as u64
+		)
+	}
+}
+
+/// Enter the `proof` span of the [`FINALITY_TARGET`] of the generation or the verification of a
+/// finality proof of the `kind`, e.g. `"generate"` or `"verify"`, by the finality gadget
+/// `gadget`, until the scope is left.
+///
+/// The node counts the proofs of every gadget and kind and the time spent in them, and exposes
+/// them as the `substrate_finality_proofs_total` and `substrate_finality_proof_seconds_total`
+/// Prometheus metrics.
+///
+/// # Example
+///
+/// ```
+/// sp_tracing::enter_finality_proof_span!("beefy", "verify");
+/// ```
+#[macro_export]
+macro_rules! enter_finality_proof_span {
+	( $gadget:expr, $kind:expr ) => {
+		let __tracing_span__ = $crate::if_tracing!(
+			$crate::tracing::info_span!(target: $crate::FINALITY_TARGET, "proof", gadget = $gadget, kind = $kind)
+		);
+		let __tracing_guard__ = $crate::if_tracing!(__tracing_span__.enter());
+	}
+}
+
+/// Declares the schema of a span once, its target, name and typed fields, as a handle whose
+/// `enter` takes the values of the fields, in order, and returns the [`EnteredSpan`] guard.
+///
+/// The arity and the types of the values are checked at every call site, and the names of the
+/// fields are only written in the schema. The fields are recorded like with `tracing::span!`, so
+/// their types must implement `tracing::Value`, e.g. the integers, `bool` and `&str`. The spans
+/// are entered at the `TRACE` level. In wasm, they are not entered, like the spans of
+/// [`entered_span`], but the `TARGET` and `NAME` of the schema let the runtime enter them in the
+/// host, e.g. with `sp_io::enter_host_span`.
+///
+/// # Example
+///
+/// ```
+/// sp_tracing::span_schema! {
+///     /// The validation of a transaction.
+///     pub struct ValidateSpan {
+///         target: "txpool",
+///         name: "validate",
+///         fields: { priority: u64, size: u32, source: &str },
+///     }
+/// }
+///
+/// let _guard = ValidateSpan.enter(10, 120, "external");
+/// ```
+#[macro_export]
+macro_rules! span_schema {
+	(
+		$( #[$attr:meta] )*
+		$vis:vis struct $schema:ident {
+			target: $target:expr,
+			name: $name:expr,
+			fields: { $( $field:ident : $ty:ty ),* $(,)? } $(,)?
+		}
+	) => {
+		$( #[$attr] )*
+		#[derive(Clone, Copy, Debug, Default)]
+		$vis struct $schema;
+
+		impl $schema {
+			/// The target of the span.
+			#[allow(dead_code)]
+			$vis const TARGET: &'static str = $target;
+
+			/// The name of the span.
+			#[allow(dead_code)]
+			$vis const NAME: &'static str = $name;
+
+			/// Enter the span, with the values of its fields.
+			#[allow(unused_variables)]
+			#[inline]
+			$vis fn enter(self, $( $field: $ty ),*) -> $crate::EnteredSpan {
+				$crate::entered_span!(target: $target, $name; $( $field = $field ),*)
+			}
+		}
+	};
+}
+
+/// The guard of a span entered by [`entered_span`] or a [`span_schema`] handle, exiting the span
+/// when dropped.
+#[must_use = "the span is exited when the guard is dropped"]
+pub struct EnteredSpan {
+	#[cfg(feature = "std")]
+	span: tracing::Span,
+	/// The id of the span of the host, with the functions exiting it, if the host traces it.
+	#[cfg(not(feature = "std"))]
+	host: Option<(u64, &'static HostSpans)>,
+}
+
+/// The functions of the host exiting and recording the spans the runtime entered in the host,
+/// given by `sp-io` through its runtime interface, see [`EnteredSpan::host`].
+#[cfg(not(feature = "std"))]
+pub struct HostSpans {
+	/// Exit the span of the id.
+	pub exit_span: fn(u64),
+	/// Record the declared weight of the span of the id.
+	pub record_weight: fn(u64, u64),
+}
+
+impl EnteredSpan {
+	/// Enter `span`, until the guard is dropped.
+	#[cfg(feature = "std")]
+	pub fn new(span: tracing::Span) -> Self {
+		span.with_subscriber(|(id, dispatch)| dispatch.enter(id));
+		EnteredSpan { span }
+	}
+
+	/// The guard of the span `id` the runtime entered in the host, exited with the functions of
+	/// `host` when dropped. An `id` of 0, for a span the host doesn't trace, guards no span.
+	#[cfg(not(feature = "std"))]
+	pub fn host(id: u64, host: &'static HostSpans) -> Self {
+		EnteredSpan { host: if id == 0 { None } else { Some((id, host)) } }
+	}
+
+	/// Record the declared `weight` of the span, e.g. of a dispatch span.
+	///
+	/// The fields of the spans entered in wasm are not passed to the host, so the weight of a
+	/// dispatch span is recorded with this for the node to measure its drift in wasm too.
+	pub fn record_weight(&self, weight: u64) {
+		#[cfg(feature = "std")]
+		self.span.record("weight", &weight);
+		#[cfg(not(feature = "std"))]
+		{
+			if let Some((id, host)) = self.host {
+				(host.record_weight)(id, weight)
+			}
+		}
+	}
+
+	/// A guard not entering any span.
+	pub fn none() -> Self {
+		EnteredSpan {
+			#[cfg(feature = "std")]
+			span: tracing::Span::none(),
+			#[cfg(not(feature = "std"))]
+			host: None,
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+impl Drop for EnteredSpan {
+	fn drop(&mut self) {
+		self.span.with_subscriber(|(id, dispatch)| dispatch.exit(id));
+	}
+}
+
+#[cfg(not(feature = "std"))]
+impl Drop for EnteredSpan {
+	fn drop(&mut self) {
+		if let Some((id, host)) = self.host {
+			(host.exit_span)(id)
+		}
+	}
+}
+
+/// Wraps every method of an `impl` block in a span named `<Type>::<method>`, for the whole
+/// body of the method.
+///
+/// The spans are entered like with [`enter_span`], at the `TRACE` level, their target
+/// defaulting to the module path and being set with `target = "..."`. A method is left as is
+/// with `#[no_instrument]`, as are the `async` and `const` ones.
+///
+/// # Example
+///
+/// ```
+/// struct Pool;
+///
+/// #[sp_tracing::instrument_all(target = "pool")]
+/// impl Pool {
+///     // Traced in the `Pool::import` span.
+///     fn import(&self, tx: u32) -> bool {
+///         self.validate(tx)
+///     }
+///
+///     #[no_instrument]
+///     fn validate(&self, tx: u32) -> bool {
+///         tx > 0
+///     }
+/// }
+///
+/// assert!(Pool.import(1));
+/// ```
+pub use sp_tracing_proc_macro::instrument_all;
+
 /// Generates the given code if the tracing dependency is enabled.
 #[macro_export]
 #[cfg(feature = "std")]
@@ -106,12 +614,200 @@ macro_rules! if_tracing {
 	( $if:expr ) => {{}}
 }
 
+#[cfg(feature = "std")]
+thread_local! {
+	/// Whether wasm tracing is enabled on this thread only, see [`with_wasm_tracing`].
+	static THREAD_WASM_TRACING: std::cell::Cell<bool> = std::cell::Cell::new(false);
+}
+
 #[cfg(feature = "std")]
 pub fn wasm_tracing_enabled() -> bool {
-	WASM_TRACING_ENABLED.load(Ordering::Relaxed)
+	WASM_TRACING_ENABLED.load(Ordering::Relaxed) || THREAD_WASM_TRACING.with(|enabled| enabled.get())
+}
+
+/// Run `f` with wasm tracing enabled for the runtime executions of the current thread.
+///
+/// Unlike [`set_wasm_tracing`], the executions of the other threads are left as they are, so
+/// that a single execution can be traced while others run concurrently.
+#[cfg(feature = "std")]
+pub fn with_wasm_tracing<R>(f: impl FnOnce() -> R) -> R {
+	struct Restore(bool);
+	impl Drop for Restore {
+		fn drop(&mut self) {
+			THREAD_WASM_TRACING.with(|enabled| enabled.set(self.0));
+		}
+	}
+	let _restore = Restore(THREAD_WASM_TRACING.with(|enabled| enabled.replace(true)));
+	f()
 }
 
 #[cfg(feature = "std")]
 pub fn set_wasm_tracing(b: bool) {
 	WASM_TRACING_ENABLED.store(b, Ordering::Relaxed)
-}
\ No newline at end of file
+}
+
+/// Set the targets of the host's subscriber, in the format of `--tracing-targets`.
+///
+/// They are reported to the runtime by `sp_io::wasm_tracing::capabilities`.
+#[cfg(feature = "std")]
+pub fn set_host_targets(targets: &str) {
+	*HOST_TARGETS.write().unwrap_or_else(|e| e.into_inner()) = targets.to_owned();
+}
+
+/// The targets of the host's subscriber, as set by [`set_host_targets`].
+#[cfg(feature = "std")]
+pub fn host_targets() -> String {
+	HOST_TARGETS.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Set the provider of [`span_context`], done by the host's subscriber once set as the global
+/// default.
+#[cfg(feature = "std")]
+pub fn set_span_context(provider: fn() -> Option<String>) {
+	set_provider(&SPAN_CONTEXT, provider);
+}
+
+/// The spans the current thread is in, outermost first, as formatted by the host's subscriber,
+/// `None` if it is in none or no provider is set.
+///
+/// This is attached to the reports of the panics and of the traps of the runtime, so that they
+/// identify the block, pallet and call that were executing.
+#[cfg(feature = "std")]
+pub fn span_context() -> Option<String> {
+	call_provider(&SPAN_CONTEXT)
+}
+
+/// Set the provider of [`trace_id`], done by the host's subscriber once set as the global default.
+#[cfg(feature = "std")]
+pub fn set_trace_id(provider: fn() -> Option<String>) {
+	set_provider(&TRACE_ID, provider);
+}
+
+/// The hex encoded trace id of the spans the current thread is in, see [`proxy::TRACE_ID_KEY`],
+/// `None` if they have none or no provider is set.
+///
+/// This is given to the runtime by `sp_io::wasm_tracing::trace_id`, e.g. for an offchain worker
+/// to attach its trace id to the transactions it submits.
+#[cfg(feature = "std")]
+pub fn trace_id() -> Option<String> {
+	call_provider(&TRACE_ID)
+}
+
+#[cfg(feature = "std")]
+thread_local! {
+	/// The migrations of a runtime upgrade being timed on the thread, see [`time_runtime_upgrade`].
+	static RUNTIME_UPGRADE: std::cell::Cell<Option<RuntimeUpgradeTimer>> = std::cell::Cell::new(None);
+}
+
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Default)]
+struct RuntimeUpgradeTimer {
+	started: Option<std::time::Instant>,
+	elapsed: Option<std::time::Duration>,
+}
+
+/// Run `f`, e.g. the execution of the first block of a new runtime, returning the time taken by
+/// the migrations of the runtime upgrade it ran, `None` if the runtime didn't mark them with
+/// [`runtime_upgrade_started`] and [`runtime_upgrade_finished`].
+#[cfg(feature = "std")]
+pub fn time_runtime_upgrade<R>(f: impl FnOnce() -> R) -> (R, Option<std::time::Duration>) {
+	struct Restore(Option<RuntimeUpgradeTimer>);
+	impl Drop for Restore {
+		fn drop(&mut self) {
+			RUNTIME_UPGRADE.with(|timer| timer.set(self.0));
+		}
+	}
+	let restore = Restore(RUNTIME_UPGRADE.with(|timer| timer.replace(Some(Default::default()))));
+	let result = f();
+	let elapsed = RUNTIME_UPGRADE.with(|timer| timer.get()).and_then(|timer| timer.elapsed);
+	drop(restore);
+	(result, elapsed)
+}
+
+/// Mark the start of the migrations of a runtime upgrade, called by the runtime through
+/// `sp_io::wasm_tracing::runtime_upgrade_started`.
+#[cfg(feature = "std")]
+pub fn runtime_upgrade_started() {
+	RUNTIME_UPGRADE.with(|timer| if let Some(mut t) = timer.get() {
+		t.started = Some(std::time::Instant::now());
+		timer.set(Some(t));
+	})
+}
+
+/// Mark the end of the migrations of a runtime upgrade, see [`runtime_upgrade_started`].
+#[cfg(feature = "std")]
+pub fn runtime_upgrade_finished() {
+	RUNTIME_UPGRADE.with(|timer| if let Some(mut t) = timer.get() {
+		t.elapsed = t.started.map(|started| started.elapsed());
+		timer.set(Some(t));
+	})
+}
+
+/// Parses targets in the format of `--tracing-targets`: comma separated `target=level`, or
+/// `target` for the `TRACE` level.
+#[cfg(feature = "std")]
+pub fn parse_targets(targets: &str) -> impl Iterator<Item = (&str, tracing::Level)> {
+	targets.split(',').filter(|t| !t.is_empty()).map(|t| match t.find('=') {
+		Some(i) => (&t[..i], t[i + 1..].parse().unwrap_or(tracing::Level::TRACE)),
+		None => (t, tracing::Level::TRACE),
+	})
+}
+
+/// Whether the on-chain tracing `targets` enable the wasm spans of the given `target`, `None`
+/// if none of them applies to `target`.
+///
+/// The on-chain targets are in the format of `--tracing-targets`, e.g. `balances,staking=warn`.
+/// Wasm spans are `INFO` spans, so this example enables the spans of `balances` and disables
+/// the ones of `staking`. The most specific target, the longest prefix of `target`, applies.
+#[cfg(feature = "std")]
+pub fn on_chain_target_enabled(targets: &[u8], target: &str) -> Option<bool> {
+	let targets = std::str::from_utf8(targets).ok()?;
+	parse_targets(targets)
+		.filter(|(prefix, _)| target.starts_with(prefix))
+		// The longest prefix wins, the last one if several are as long.
+		.max_by_key(|(prefix, _)| prefix.len())
+		.map(|(_, level)| tracing::Level::INFO <= level)
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn wasm_tracing_is_enabled_on_the_current_thread_only() {
+		assert!(!wasm_tracing_enabled());
+		with_wasm_tracing(|| {
+			assert!(wasm_tracing_enabled());
+			assert!(!std::thread::spawn(wasm_tracing_enabled).join().unwrap());
+		});
+		assert!(!wasm_tracing_enabled());
+	}
+
+	#[test]
+	fn on_chain_targets_raise_and_lower_verbosity() {
+		let targets = b"balances,staking=warn,staking::slashing=debug";
+		assert_eq!(on_chain_target_enabled(targets, "balances"), Some(true));
+		assert_eq!(on_chain_target_enabled(targets, "staking"), Some(false));
+		assert_eq!(on_chain_target_enabled(targets, "staking::slashing"), Some(true));
+		assert_eq!(on_chain_target_enabled(targets, "democracy"), None);
+		let targets = b"staking::slashing=warn,staking";
+		assert_eq!(on_chain_target_enabled(targets, "staking::slashing"), Some(false));
+		assert_eq!(on_chain_target_enabled(targets, "staking"), Some(true));
+		assert_eq!(on_chain_target_enabled(b"balances=warn,balances", "balances"), Some(true));
+		assert_eq!(on_chain_target_enabled(b"", "balances"), None);
+		assert_eq!(on_chain_target_enabled(&[0xff], "balances"), None);
+	}
+
+	#[test]
+	fn times_runtime_upgrade_only_when_marked() {
+		assert_eq!(time_runtime_upgrade(|| 1).1, None);
+		let ((), elapsed) = time_runtime_upgrade(|| {
+			runtime_upgrade_started();
+			runtime_upgrade_finished();
+		});
+		assert!(elapsed.is_some());
+		// Outside of `time_runtime_upgrade`, the marks are ignored.
+		runtime_upgrade_started();
+		assert_eq!(time_runtime_upgrade(runtime_upgrade_finished).1, None);
+	}
+}
@@ -0,0 +1,96 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// The USDT probes of `probes.rs`, in the format of the `<sys/sdt.h>` of SystemTap, which is
+// the one bpftrace, perf and BCC read: a `nop` at the probe site, described by a note of the
+// `.note.stapsdt` section with the address of its semaphore, the counter of the tracers attached
+// to the probe, and the location of its arguments.
+
+#include <stddef.h>
+#include <stdint.h>
+
+#define SEMAPHORE(probe) \
+	__attribute__((section(".probes"), used)) volatile uint16_t sp_tracing_##probe##_semaphore = 0;
+
+#define PROBE(probe, args, ...) \
+	__asm__ __volatile__ ( \
+		".ifndef _.stapsdt.base\n" \
+		".pushsection .stapsdt.base, \"aG\", \"progbits\", .stapsdt.base, comdat\n" \
+		".weak _.stapsdt.base\n" \
+		".hidden _.stapsdt.base\n" \
+		"_.stapsdt.base: .space 1\n" \
+		".size _.stapsdt.base, 1\n" \
+		".popsection\n" \
+		".endif\n" \
+		"990: nop\n" \
+		".pushsection .note.stapsdt, \"\", \"note\"\n" \
+		".balign 4\n" \
+		".4byte 992f-991f, 994f-993f, 3\n" \
+		"991: .asciz \"stapsdt\"\n" \
+		"992: .balign 4\n" \
+		"993: .8byte 990b\n" \
+		".8byte _.stapsdt.base\n" \
+		".8byte sp_tracing_" #probe "_semaphore\n" \
+		".asciz \"substrate\"\n" \
+		".asciz \"" #probe "\"\n" \
+		".asciz \"" args "\"\n" \
+		"994: .balign 4\n" \
+		".popsection\n" \
+		:: __VA_ARGS__ \
+	)
+
+SEMAPHORE(span_enter)
+SEMAPHORE(span_exit)
+SEMAPHORE(event)
+SEMAPHORE(call_enter)
+SEMAPHORE(call_exit)
+SEMAPHORE(import_enter)
+SEMAPHORE(import_exit)
+
+void sp_tracing_probe_span_enter(
+	uint64_t id, const uint8_t *target, size_t target_len, const uint8_t *name, size_t name_len
+) {
+	PROBE(span_enter, "8@%0 8@%1 8@%2 8@%3 8@%4",
+		"r"(id), "r"(target), "r"(target_len), "r"(name), "r"(name_len));
+}
+
+void sp_tracing_probe_span_exit(uint64_t id) {
+	PROBE(span_exit, "8@%0", "r"(id));
+}
+
+void sp_tracing_probe_event(
+	uint64_t level, const uint8_t *target, size_t target_len, const uint8_t *message, size_t message_len
+) {
+	PROBE(event, "8@%0 8@%1 8@%2 8@%3 8@%4",
+		"r"(level), "r"(target), "r"(target_len), "r"(message), "r"(message_len));
+}
+
+void sp_tracing_probe_call_enter(const uint8_t *method, size_t method_len) {
+	PROBE(call_enter, "8@%0 8@%1", "r"(method), "r"(method_len));
+}
+
+void sp_tracing_probe_call_exit(const uint8_t *method, size_t method_len, uint64_t native, uint64_t ok) {
+	PROBE(call_exit, "8@%0 8@%1 8@%2 8@%3", "r"(method), "r"(method_len), "r"(native), "r"(ok));
+}
+
+void sp_tracing_probe_import_enter(uint64_t number, const uint8_t *hash, size_t hash_len) {
+	PROBE(import_enter, "8@%0 8@%1 8@%2", "r"(number), "r"(hash), "r"(hash_len));
+}
+
+void sp_tracing_probe_import_exit(uint64_t number, uint64_t ok) {
+	PROBE(import_exit, "8@%0 8@%1", "r"(number), "r"(ok));
+}
@@ -0,0 +1,169 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! USDT probes of the `substrate` provider, for bpftrace, perf or BCC to attach to.
+//!
+//! The probes are compiled in with the `usdt` feature, on 64-bit Linux, and fire whatever the
+//! tracing receiver or targets of the node. Their arguments are only computed while a tracer is
+//! attached to them, so they cost a load and a branch otherwise. The strings are passed as a
+//! pointer and a length, e.g. to print the wasm spans entered:
+//!
+//! ```text
+//! bpftrace -e 'usdt:./substrate:substrate:span_enter {
+//!     printf("%s::%s\n", str(arg1, arg2), str(arg3, arg4));
+//! }'
+//! ```
+//!
+//! Without the feature, the probes are no-ops.
+
+#[cfg(usdt_probes)]
+mod sys {
+	extern "C" {
+		pub static sp_tracing_span_enter_semaphore: u16;
+		pub static sp_tracing_span_exit_semaphore: u16;
+		pub static sp_tracing_event_semaphore: u16;
+		pub static sp_tracing_call_enter_semaphore: u16;
+		pub static sp_tracing_call_exit_semaphore: u16;
+		pub static sp_tracing_import_enter_semaphore: u16;
+		pub static sp_tracing_import_exit_semaphore: u16;
+
+		pub fn sp_tracing_probe_span_enter(
+			id: u64,
+			target: *const u8,
+			target_len: usize,
+			name: *const u8,
+			name_len: usize,
+		);
+		pub fn sp_tracing_probe_span_exit(id: u64);
+		pub fn sp_tracing_probe_event(
+			level: u64,
+			target: *const u8,
+			target_len: usize,
+			message: *const u8,
+			message_len: usize,
+		);
+		pub fn sp_tracing_probe_call_enter(method: *const u8, method_len: usize);
+		pub fn sp_tracing_probe_call_exit(method: *const u8, method_len: usize, native: u64, ok: u64);
+		pub fn sp_tracing_probe_import_enter(number: u64, hash: *const u8, hash_len: usize);
+		pub fn sp_tracing_probe_import_exit(number: u64, ok: u64);
+	}
+}
+
+/// Fires the probe of `semaphore` with `fire`, if a tracer is attached to it.
+macro_rules! probe {
+	( $semaphore:ident, $fire:expr ) => {
+		#[cfg(usdt_probes)]
+		// The semaphores are only written by the tracers, through `/proc/<pid>/mem`.
+		unsafe {
+			if core::ptr::read_volatile(&sys::$semaphore) != 0 {
+				$fire
+			}
+		}
+	}
+}
+
+/// `span_enter(id, target, target_len, name, name_len)`: a span of the runtime was entered,
+/// `id` being 0 if it isn't recorded.
+#[inline]
+#[cfg_attr(not(usdt_probes), allow(unused_variables))]
+pub fn span_enter(id: u64, target: &str, name: &str) {
+	probe!(sp_tracing_span_enter_semaphore, sys::sp_tracing_probe_span_enter(
+		id,
+		target.as_ptr(),
+		target.len(),
+		name.as_ptr(),
+		name.len(),
+	));
+}
+
+/// `span_exit(id)`: a span of the runtime was exited.
+#[inline]
+#[cfg_attr(not(usdt_probes), allow(unused_variables))]
+pub fn span_exit(id: u64) {
+	probe!(sp_tracing_span_exit_semaphore, sys::sp_tracing_probe_span_exit(id));
+}
+
+/// `event(level, target, target_len, message, message_len)`: the runtime logged a message,
+/// `level` going from 1 for errors to 5 for traces.
+#[inline]
+#[cfg_attr(not(usdt_probes), allow(unused_variables))]
+pub fn event(level: u64, target: &str, message: &[u8]) {
+	probe!(sp_tracing_event_semaphore, sys::sp_tracing_probe_event(
+		level,
+		target.as_ptr(),
+		target.len(),
+		message.as_ptr(),
+		message.len(),
+	));
+}
+
+/// `call_enter(method, method_len)`: the executor calls a method of the runtime.
+#[inline]
+#[cfg_attr(not(usdt_probes), allow(unused_variables))]
+pub fn call_enter(method: &str) {
+	probe!(sp_tracing_call_enter_semaphore, sys::sp_tracing_probe_call_enter(
+		method.as_ptr(),
+		method.len(),
+	));
+}
+
+/// `call_exit(method, method_len, native, ok)`: a call of the executor returned, `native` and
+/// `ok` being 1 if the native runtime was called and if the call succeeded.
+#[inline]
+#[cfg_attr(not(usdt_probes), allow(unused_variables))]
+pub fn call_exit(method: &str, native: bool, ok: bool) {
+	probe!(sp_tracing_call_exit_semaphore, sys::sp_tracing_probe_call_exit(
+		method.as_ptr(),
+		method.len(),
+		native as u64,
+		ok as u64,
+	));
+}
+
+/// `import_enter(number, hash, hash_len)`: the client starts importing a block.
+#[inline]
+#[cfg_attr(not(usdt_probes), allow(unused_variables))]
+pub fn import_enter(number: u64, hash: &[u8]) {
+	probe!(sp_tracing_import_enter_semaphore, sys::sp_tracing_probe_import_enter(
+		number,
+		hash.as_ptr(),
+		hash.len(),
+	));
+}
+
+/// `import_exit(number, ok)`: the client imported a block, `ok` being 1 if it succeeded.
+#[inline]
+#[cfg_attr(not(usdt_probes), allow(unused_variables))]
+pub fn import_exit(number: u64, ok: bool) {
+	probe!(sp_tracing_import_exit_semaphore, sys::sp_tracing_probe_import_exit(number, ok as u64));
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn probes_without_tracers_are_skipped() {
+		span_enter(1, "balances", "transfer");
+		event(3, "runtime", b"Something happened");
+		span_exit(1);
+		call_enter("Core_execute_block");
+		call_exit("Core_execute_block", false, true);
+		import_enter(1, &[0; 32]);
+		import_exit(1, true);
+	}
+}
@@ -26,6 +26,18 @@ pub const WASM_TRACE_IDENTIFIER: &'static str = "WASM_TRACE";
 pub const WASM_TARGET_KEY: &'static str = "proxied_wasm_target";
 /// Used to extract the real `name` from the associated values of the span
 pub const WASM_NAME_KEY: &'static str = "proxied_wasm_name";
+/// Used to signal that the span was enabled by the on-chain tracing targets
+pub const WASM_ON_CHAIN_KEY: &'static str = "proxied_wasm_on_chain";
+/// Used to record the declared weight of a dispatch span, see `TracingProxy::record_weight`
+pub const WASM_WEIGHT_KEY: &'static str = "weight";
+/// Used to identify the span of a task, see `task_span`
+pub const TASK_TRACE_IDENTIFIER: &'static str = "TASK_TRACE";
+/// Used to extract the real `name` from the associated values of a task span
+pub const TASK_NAME_KEY: &'static str = "proxied_task_name";
+/// Name of the span of a client trace context, see `enter_trace_context`
+pub const TRACE_CONTEXT_IDENTIFIER: &'static str = "trace_context";
+/// The hex encoded trace id of a client trace context, recorded on the spans opened within it
+pub const TRACE_ID_KEY: &'static str = "trace_id";
 
 const MAX_SPANS_LEN: usize = 1000;
 
@@ -39,11 +51,26 @@ rental! {
 	}
 }
 
+/// The span of a trace context with the given trace id, recording it hex encoded, e.g. for the
+/// node to trace its own computations like the clients do, see `TracingProxy::enter_trace_context`.
+pub fn trace_context_span(trace_id: &[u8]) -> tracing::Span {
+	let trace_id = trace_id.iter().map(|b| format!("{:02x}", b)).collect::<String>();
+	// The identifier `trace_id` must match its associated const, TRACE_ID_KEY.
+	info_span!(
+		TRACE_CONTEXT_IDENTIFIER,
+		is_valid_trace = true,
+		trace_id = trace_id.as_str(),
+	)
+}
+
 /// Requires a tracing::Subscriber to process span traces,
 /// this is available when running with client (and relevant cli params).
 pub struct TracingProxy {
 	next_id: u64,
 	spans: Vec<(u64, rent_span::SpanAndGuard)>,
+	/// Span of the task of a spawned instance, see `spawned`, entered with its first span.
+	task: Option<tracing::Span>,
+	task_guard: Option<rent_span::SpanAndGuard>,
 }
 
 impl Drop for TracingProxy {
@@ -65,6 +92,30 @@ impl TracingProxy {
 		TracingProxy {
 			next_id: 0,
 			spans: Vec::new(),
+			task: None,
+			task_guard: None,
+		}
+	}
+
+	/// The proxy of an instance of the runtime spawned by the runtime traced by `self`, e.g. to
+	/// run a task in parallel, possibly on another thread.
+	///
+	/// The spans of the spawned instance are nested in the `task_span` named `task_name` of the
+	/// span the runtime spawned the task from, rather than being roots of their own.
+	pub fn spawned(&self, task_name: &str) -> TracingProxy {
+		let mut proxy = TracingProxy::new();
+		proxy.task = Some(task_span(&self.current_span(), task_name));
+		proxy
+	}
+
+	/// The span the runtime is in: the last span entered, the span of the task of the instance,
+	/// or the current span of the host.
+	pub fn current_span(&self) -> tracing::Span {
+		match (self.spans.last(), &self.task, &self.task_guard) {
+			(Some((_, sg)), _, _) => sg.rent_all(|s| (*s.span).clone()),
+			(None, Some(task), _) => task.clone(),
+			(None, None, Some(sg)) => sg.rent_all(|s| (*s.span).clone()),
+			(None, None, None) => tracing::Span::current(),
 		}
 	}
 }
@@ -72,10 +123,49 @@ impl TracingProxy {
 impl TracingProxy {
 	/// Create and enter a `tracing` Span, returning the span id,
 	/// which should be passed to `exit_span(id)` to signal that the span should exit.
-	pub fn enter_span(&mut self, proxied_wasm_target: &str, proxied_wasm_name: &str) -> u64 {
-		// The identifiers `proxied_wasm_target` and `proxied_wasm_name` must match their associated const,
-		// WASM_TARGET_KEY and WASM_NAME_KEY.
-		let span = info_span!(WASM_TRACE_IDENTIFIER, is_valid_trace = true, proxied_wasm_target, proxied_wasm_name);
+	///
+	/// `proxied_wasm_on_chain` tells whether the span was enabled by the on-chain tracing targets,
+	/// in which case it is reported whatever the targets of the subscriber.
+	pub fn enter_span(
+		&mut self,
+		proxied_wasm_target: &str,
+		proxied_wasm_name: &str,
+		proxied_wasm_on_chain: bool,
+	) -> u64 {
+		self.enter_task();
+		// The identifiers `proxied_wasm_target`, `proxied_wasm_name`, `proxied_wasm_on_chain` and
+		// `weight` must match their associated const, WASM_TARGET_KEY, WASM_NAME_KEY,
+		// WASM_ON_CHAIN_KEY and WASM_WEIGHT_KEY.
+		let span = info_span!(
+			WASM_TRACE_IDENTIFIER,
+			is_valid_trace = true,
+			proxied_wasm_target,
+			proxied_wasm_name,
+			proxied_wasm_on_chain,
+			weight = tracing::field::Empty,
+		);
+		self.push_span(span)
+	}
+
+	/// Record the declared `weight` of the span `id`, e.g. of a dispatch span, whose fields the
+	/// runtime can't pass to `enter_span`.
+	pub fn record_weight(&mut self, id: u64, weight: u64) {
+		match self.spans.iter().rev().find(|(span_id, _)| *span_id == id) {
+			Some((_, sg)) => sg.rent_all(|s| { s.span.record(WASM_WEIGHT_KEY, &weight); }),
+			None => log::warn!(target: "tracing", "Span id not found in TracingProxy: {}", id),
+		}
+	}
+
+	/// Enter the span of the task of a spawned instance, before the spans are created in it, on the
+	/// thread the instance runs on.
+	fn enter_task(&mut self) {
+		if let Some(task) = self.task.take() {
+			self.task_guard = Some(rent_span::SpanAndGuard::new(Box::new(task), |span| span.enter()));
+		}
+	}
+
+	/// Enter `span`, until `exit_span` is called with the returned id.
+	fn push_span(&mut self, span: tracing::Span) -> u64 {
 		self.next_id += 1;
 		let sg = rent_span::SpanAndGuard::new(
 			Box::new(span),
@@ -96,6 +186,32 @@ impl TracingProxy {
 		self.next_id
 	}
 
+	/// Enter the span of the trace context of a client, e.g. the one of an extrinsic, returning
+	/// the span id to be passed to `exit_span(id)`.
+	///
+	/// The subscriber records the hex encoded `trace_id` on the spans and events within it.
+	pub fn enter_trace_context(&mut self, trace_id: &[u8]) -> u64 {
+		self.enter_task();
+		self.push_span(trace_context_span(trace_id))
+	}
+
+	/// The id of the last span entered, to be given to `exit_spans_after`.
+	pub fn last_id(&self) -> u64 {
+		self.next_id
+	}
+
+	/// Exit the spans entered after the span `id`, marking them as not valid.
+	///
+	/// This is meant for the spans left entered by a runtime call that didn't finish, e.g. because
+	/// it trapped, so that the spans entered afterwards are not nested in them.
+	pub fn exit_spans_after(&mut self, id: u64) {
+		while self.spans.last().map_or(false, |l| l.0 > id) {
+			if let Some((_, mut sg)) = self.spans.pop() {
+				sg.rent_all_mut(|s| { s.span.record("is_valid_trace", &false); });
+			}
+		}
+	}
+
 	/// Exit a span by dropping it along with it's associated guard.
 	pub fn exit_span(&mut self, id: u64) {
 		if self.spans.last().map(|l| id > l.0).unwrap_or(true) {
@@ -121,6 +237,18 @@ impl TracingProxy {
 	}
 }
 
+/// Create the span of a task spawned from the span `parent`, e.g. `Span::current()` when the task
+/// was spawned on another thread.
+///
+/// Entering it in the task keeps the spans of the task in the trace of `parent`. The span takes the
+/// target of `parent`, and is disabled if `parent` is.
+pub fn task_span(parent: &tracing::Span, proxied_task_name: &str) -> tracing::Span {
+	if parent.is_none() {
+		return tracing::Span::none();
+	}
+	// The identifier `proxied_task_name` must match its associated const, TASK_NAME_KEY.
+	info_span!(parent: parent, TASK_TRACE_IDENTIFIER, proxied_task_name)
+}
 
 #[cfg(test)]
 mod tests {
@@ -129,7 +257,7 @@ mod tests {
 	fn create_spans(proxy: &mut TracingProxy, qty: usize) -> Vec<u64> {
 		let mut spans = Vec::new();
 		for n in 0..qty {
-			spans.push(proxy.enter_span("target", &format!("{}", n)));
+			spans.push(proxy.enter_span("target", &format!("{}", n), false));
 		}
 		spans
 	}
@@ -162,4 +290,87 @@ mod tests {
 		proxy.exit_span(1);
 		assert_eq!(proxy.spans.len(), 0);
 	}
+
+	#[test]
+	fn exits_spans_left_entered() {
+		let mut proxy = TracingProxy::new();
+		let _spans = create_spans(&mut proxy, 2);
+		let last_id = proxy.last_id();
+		let _spans = create_spans(&mut proxy, 3);
+		proxy.exit_spans_after(last_id);
+		assert_eq!(proxy.spans.len(), 2);
+		// spans are still exited normally afterwards
+		proxy.exit_span(2);
+		assert_eq!(proxy.spans.len(), 1);
+	}
+
+	#[test]
+	fn spawned_instances_nest_their_spans() {
+		let mut proxy = TracingProxy::new();
+		let _spans = create_spans(&mut proxy, 2);
+		let mut spawned = std::thread::spawn({
+			let spawned = proxy.spawned("task");
+			move || {
+				let mut spawned = spawned;
+				let _spans = create_spans(&mut spawned, 2);
+				spawned
+			}
+		}).join().unwrap();
+		assert!(spawned.task.is_none() && spawned.task_guard.is_some());
+		assert_eq!(spawned.spans.len(), 2);
+		spawned.exit_span(1);
+		assert_eq!(spawned.spans.len(), 0);
+		assert!(TracingProxy::new().spawned("task").current_span().is_none());
+	}
+
+	#[test]
+	fn weights_are_recorded_on_the_spans() {
+		use std::sync::{Arc, Mutex};
+		use tracing::{field::{Field, Visit}, span::{Attributes, Id, Record}, Event, Metadata};
+
+		#[derive(Default)]
+		struct Weights(Arc<Mutex<Vec<u64>>>);
+
+		impl Visit for Weights {
+			fn record_u64(&mut self, field: &Field, value: u64) {
+				if field.name() == WASM_WEIGHT_KEY {
+					self.0.lock().unwrap().push(value);
+				}
+			}
+
+			fn record_debug(&mut self, _: &Field, _: &dyn std::fmt::Debug) {}
+		}
+
+		struct Recorder(Arc<Mutex<Vec<u64>>>);
+
+		impl tracing::Subscriber for Recorder {
+			fn enabled(&self, _: &Metadata<'_>) -> bool { true }
+			fn new_span(&self, _: &Attributes<'_>) -> Id { Id::from_u64(1) }
+			fn record(&self, _: &Id, values: &Record<'_>) {
+				values.record(&mut Weights(self.0.clone()));
+			}
+			fn record_follows_from(&self, _: &Id, _: &Id) {}
+			fn event(&self, _: &Event<'_>) {}
+			fn enter(&self, _: &Id) {}
+			fn exit(&self, _: &Id) {}
+		}
+
+		let weights = Arc::new(Mutex::new(Vec::new()));
+		tracing::subscriber::with_default(Recorder(weights.clone()), || {
+			let mut proxy = TracingProxy::new();
+			let spans = create_spans(&mut proxy, 2);
+			proxy.record_weight(spans[0], 42);
+			proxy.record_weight(3, 7);
+		});
+		assert_eq!(*weights.lock().unwrap(), vec![42]);
+	}
+
+	#[test]
+	fn trace_contexts_are_exited_like_spans() {
+		let mut proxy = TracingProxy::new();
+		let context = proxy.enter_trace_context(&[0xab; 16]);
+		let _spans = create_spans(&mut proxy, 2);
+		proxy.exit_span(context);
+		assert_eq!(proxy.spans.len(), 0);
+	}
 }
@@ -0,0 +1,111 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests of the spans of `instrument_all`.
+
+use std::sync::{Arc, Mutex, atomic::{AtomicU64, Ordering}};
+use tracing::{Event, Metadata, Subscriber, span::{Attributes, Id, Record}};
+
+/// Records the target and name of the spans entered.
+#[derive(Clone, Default)]
+struct Recorder {
+	next_id: Arc<AtomicU64>,
+	spans: Arc<Mutex<Vec<(String, &'static str)>>>,
+}
+
+impl Subscriber for Recorder {
+	fn enabled(&self, _: &Metadata) -> bool {
+		true
+	}
+
+	fn new_span(&self, span: &Attributes) -> Id {
+		let metadata = span.metadata();
+		self.spans.lock().unwrap().push((metadata.target().to_owned(), metadata.name()));
+		Id::from_u64(self.next_id.fetch_add(1, Ordering::Relaxed) + 1)
+	}
+
+	fn record(&self, _: &Id, _: &Record) {}
+
+	fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+	fn event(&self, _: &Event) {}
+
+	fn enter(&self, _: &Id) {}
+
+	fn exit(&self, _: &Id) {}
+}
+
+struct Queue<T>(Vec<T>);
+
+#[sp_tracing::instrument_all]
+impl<T> Queue<T> {
+	fn push(&mut self, item: T) {
+		self.0.push(item)
+	}
+
+	fn pop(&mut self) -> Option<T> {
+		if self.0.is_empty() {
+			return None;
+		}
+		Some(self.0.remove(0))
+	}
+
+	#[no_instrument]
+	fn len(&self) -> usize {
+		self.0.len()
+	}
+
+	const fn capacity() -> usize {
+		16
+	}
+}
+
+trait Drain {
+	fn drain(&mut self) -> usize;
+}
+
+#[sp_tracing::instrument_all(target = "queue")]
+impl<T> Drain for Queue<T> {
+	fn drain(&mut self) -> usize {
+		let mut drained = 0;
+		while self.pop().is_some() {
+			drained += 1;
+		}
+		drained
+	}
+}
+
+#[test]
+fn instruments_the_methods() {
+	let recorder = Recorder::default();
+	let spans = recorder.spans.clone();
+	tracing::subscriber::with_default(recorder, || {
+		let mut queue = Queue(Vec::new());
+		queue.push(1);
+		assert_eq!(queue.len(), 1);
+		assert_eq!(Queue::<u32>::capacity(), 16);
+		assert_eq!(queue.drain(), 1);
+	});
+
+	let target = module_path!().to_owned();
+	assert_eq!(*spans.lock().unwrap(), vec![
+		(target.clone(), "Queue::push"),
+		("queue".to_owned(), "Queue::drain"),
+		(target.clone(), "Queue::pop"),
+		(target, "Queue::pop"),
+	]);
+}
@@ -0,0 +1,137 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests of the spans of `within_iteration_span`.
+
+use std::fmt;
+use std::sync::{Arc, Mutex, atomic::{AtomicU64, Ordering}};
+use tracing::{
+	Event, Metadata, Subscriber,
+	field::{Field, Visit},
+	span::{Attributes, Id, Record},
+};
+
+/// Records the spans created, with the address of their metadata and their fields, and the entries
+/// and exits.
+#[derive(Clone, Default)]
+struct Recorder {
+	next_id: Arc<AtomicU64>,
+	log: Arc<Mutex<Vec<String>>>,
+	metadata: Arc<Mutex<Vec<usize>>>,
+}
+
+struct Fields(Vec<String>);
+
+impl Visit for Fields {
+	fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+		self.0.push(format!("{}={:?}", field.name(), value));
+	}
+}
+
+impl Subscriber for Recorder {
+	fn enabled(&self, _: &Metadata) -> bool {
+		true
+	}
+
+	fn new_span(&self, span: &Attributes) -> Id {
+		let mut fields = Fields(Vec::new());
+		span.record(&mut fields);
+		let metadata = span.metadata();
+		self.metadata.lock().unwrap().push(metadata as *const _ as usize);
+		self.log.lock().unwrap()
+			.push(format!("new {}::{} {}", metadata.target(), metadata.name(), fields.0.join(",")));
+		Id::from_u64(self.next_id.fetch_add(1, Ordering::Relaxed) + 1)
+	}
+
+	fn record(&self, _: &Id, _: &Record) {}
+
+	fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+	fn event(&self, _: &Event) {}
+
+	fn enter(&self, id: &Id) {
+		self.log.lock().unwrap().push(format!("enter {}", id.into_u64()));
+	}
+
+	fn exit(&self, id: &Id) {
+		self.log.lock().unwrap().push(format!("exit {}", id.into_u64()));
+	}
+}
+
+#[test]
+fn iterations_share_the_metadata_of_their_span() {
+	let recorder = Recorder::default();
+	let (log, metadata) = (recorder.log.clone(), recorder.metadata.clone());
+	let mut processed = Vec::new();
+	tracing::subscriber::with_default(recorder, || {
+		sp_tracing::within_iteration_span! {
+			target: "queue", "process_item";
+			for (id, item) in vec![(7u64, "a"), (8, "b"), (9, "c")], id = id => {
+				if item == "c" {
+					break;
+				}
+				processed.push(item);
+			}
+		}
+	});
+
+	assert_eq!(processed, vec!["a", "b"]);
+	assert_eq!(*log.lock().unwrap(), vec![
+		"new queue::process_item index=0,id=7",
+		"enter 1",
+		"exit 1",
+		"new queue::process_item index=1,id=8",
+		"enter 2",
+		"exit 2",
+		"new queue::process_item index=2,id=9",
+		"enter 3",
+		"exit 3",
+	]);
+	let metadata = metadata.lock().unwrap();
+	assert!(metadata.iter().all(|address| *address == metadata[0]));
+}
+
+fn evens_until_odd(items: &[u32]) -> Result<Vec<u32>, u32> {
+	let mut checked = Vec::new();
+	sp_tracing::within_iteration_span! {
+		"check_item";
+		for item in items => {
+			if item % 2 == 1 {
+				return Err(*item);
+			}
+			checked.push(*item);
+		}
+	}
+	Ok(checked)
+}
+
+#[test]
+fn iteration_spans_are_exited_on_early_returns() {
+	let recorder = Recorder::default();
+	let log = recorder.log.clone();
+	let result = tracing::subscriber::with_default(recorder, || evens_until_odd(&[2, 3, 4]));
+
+	assert_eq!(result, Err(3));
+	assert_eq!(*log.lock().unwrap(), vec![
+		format!("new {}::check_item index=0", module_path!()),
+		"enter 1".to_owned(),
+		"exit 1".to_owned(),
+		format!("new {}::check_item index=1", module_path!()),
+		"enter 2".to_owned(),
+		"exit 2".to_owned(),
+	]);
+}
@@ -0,0 +1,65 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests that the fields of the disabled spans and events are not evaluated.
+
+use std::cell::Cell;
+use tracing::{Event, Metadata, Subscriber, span::{Attributes, Id, Record}};
+
+/// Enables the `enabled` target only.
+struct Filter;
+
+impl Subscriber for Filter {
+	fn enabled(&self, metadata: &Metadata) -> bool {
+		metadata.target() == "enabled"
+	}
+
+	fn new_span(&self, _: &Attributes) -> Id {
+		Id::from_u64(1)
+	}
+
+	fn record(&self, _: &Id, _: &Record) {}
+
+	fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+	fn event(&self, _: &Event) {}
+
+	fn enter(&self, _: &Id) {}
+
+	fn exit(&self, _: &Id) {}
+}
+
+#[test]
+fn fields_are_evaluated_only_if_enabled() {
+	let evaluated = Cell::new(0);
+	let encode = || {
+		evaluated.set(evaluated.get() + 1);
+		evaluated.get()
+	};
+	tracing::subscriber::with_default(Filter, || {
+		{
+			sp_tracing::enter_span!(target: "disabled", "span"; value = encode());
+			sp_tracing::event!(trace; target: "disabled", value = encode());
+			let _span = sp_tracing::entered_span!(target: "disabled", "span"; value = encode());
+		}
+		assert_eq!(evaluated.get(), 0);
+
+		sp_tracing::enter_span!(target: "enabled", "span"; value = encode());
+		sp_tracing::event!(trace; target: "enabled", value = encode());
+		assert_eq!(evaluated.get(), 2);
+	});
+}
@@ -0,0 +1,137 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests of the spans of `span_schema` and `entered_span`.
+
+use std::fmt;
+use std::sync::{Arc, Mutex, atomic::{AtomicU64, Ordering}};
+use tracing::{
+	Event, Metadata, Subscriber,
+	field::{Field, Visit},
+	span::{Attributes, Id, Record},
+};
+
+/// Records the spans created, with their fields, and the entries and exits.
+#[derive(Clone, Default)]
+struct Recorder {
+	next_id: Arc<AtomicU64>,
+	log: Arc<Mutex<Vec<String>>>,
+}
+
+struct Fields(Vec<String>);
+
+impl Visit for Fields {
+	fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+		self.0.push(format!("{}={:?}", field.name(), value));
+	}
+}
+
+impl Subscriber for Recorder {
+	fn enabled(&self, _: &Metadata) -> bool {
+		true
+	}
+
+	fn new_span(&self, span: &Attributes) -> Id {
+		let mut fields = Fields(Vec::new());
+		span.record(&mut fields);
+		let metadata = span.metadata();
+		self.log.lock().unwrap()
+			.push(format!("new {}::{} {}", metadata.target(), metadata.name(), fields.0.join(",")));
+		Id::from_u64(self.next_id.fetch_add(1, Ordering::Relaxed) + 1)
+	}
+
+	fn record(&self, _: &Id, _: &Record) {}
+
+	fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+	fn event(&self, _: &Event) {}
+
+	fn enter(&self, id: &Id) {
+		self.log.lock().unwrap().push(format!("enter {}", id.into_u64()));
+	}
+
+	fn exit(&self, id: &Id) {
+		self.log.lock().unwrap().push(format!("exit {}", id.into_u64()));
+	}
+}
+
+sp_tracing::span_schema! {
+	struct ImportSpan {
+		target: "pool",
+		name: "import",
+		fields: { priority: u64, propagate: bool, source: &str },
+	}
+}
+
+sp_tracing::span_schema! {
+	struct PruneSpan {
+		target: "pool",
+		name: "prune",
+		fields: {},
+	}
+}
+
+#[test]
+fn schema_spans_are_entered_until_dropped() {
+	let recorder = Recorder::default();
+	let log = recorder.log.clone();
+	tracing::subscriber::with_default(recorder, || {
+		let import = ImportSpan.enter(10, true, "external");
+		{
+			let _prune = PruneSpan.enter();
+		}
+		drop(import);
+	});
+
+	assert_eq!(*log.lock().unwrap(), vec![
+		"new pool::import priority=10,propagate=true,source=\"external\"",
+		"enter 1",
+		"new pool::prune ",
+		"enter 2",
+		"exit 2",
+		"exit 1",
+	]);
+}
+
+struct Import {
+	_span: sp_tracing::EnteredSpan,
+}
+
+fn verify() -> sp_tracing::EnteredSpan {
+	sp_tracing::entered_span!(target: "pool", "verify"; valid = true)
+}
+
+#[test]
+fn entered_spans_are_exited_with_their_guard() {
+	let recorder = Recorder::default();
+	let log = recorder.log.clone();
+	tracing::subscriber::with_default(recorder, || {
+		let import = Import { _span: sp_tracing::entered_span!("import") };
+		let verify = verify();
+		drop(import);
+		drop(verify);
+	});
+
+	assert_eq!(*log.lock().unwrap(), vec![
+		format!("new {}::import ", module_path!()),
+		"enter 1".to_owned(),
+		"new pool::verify valid=true".to_owned(),
+		"enter 2".to_owned(),
+		"exit 1".to_owned(),
+		"exit 2".to_owned(),
+	]);
+}
@@ -53,6 +53,32 @@ impl PoolStatus {
 	}
 }
 
+/// Statistics of the revalidation of the ready transactions of the pool.
+///
+/// The ready transactions are revalidated in batches against the best block, and the invalid ones
+/// removed from the pool, so the transactions dropped without being included in a block can be
+/// traced back to their revalidation.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RevalidationStats {
+	/// Number of batches revalidated.
+	pub batches: u64,
+	/// Number of transactions revalidated, the ones that left the ready queue excluded.
+	pub revalidated: u64,
+	/// Number of transactions removed from the pool as invalid.
+	pub invalidated: u64,
+	/// Number of transactions kept in the pool with an unknown validity.
+	pub unknown: u64,
+	/// Number of transactions of the last batch.
+	pub last_batch_size: u64,
+	/// Duration of the revalidation of the last batch, in milliseconds.
+	pub last_batch_duration_ms: u64,
+	/// Block the last batch was revalidated at.
+	pub last_batch_at: Option<u64>,
+	/// Number of transactions queued for revalidation.
+	pub queued: u64,
+}
+
 /// Possible transaction status events.
 ///
 /// This events are being emitted by `TransactionPool` watchers,
@@ -231,6 +257,14 @@ pub trait TransactionPool: Send + Sync {
 	/// Returns pool status.
 	fn status(&self) -> PoolStatus;
 
+	// *** logging / RPC
+	/// Returns the statistics of the revalidation of the transactions.
+	///
+	/// The pools not revalidating their transactions don't have to implement it.
+	fn revalidation_stats(&self) -> RevalidationStats {
+		Default::default()
+	}
+
 	// *** logging / RPC / networking
 	/// Return an event stream of transactions imported to the pool.
 	fn import_notification_stream(&self) -> ImportNotificationStream<TxHash<Self>>;
@@ -244,6 +278,14 @@ pub trait TransactionPool: Send + Sync {
 
 	/// Return specific ready transaction by hash, if there is one.
 	fn ready_transaction(&self, hash: &TxHash<Self>) -> Option<Arc<Self::InPoolTransaction>>;
+
+	/// Return specific future transaction by hash, if there is one.
+	///
+	/// The future transactions require tags no ready transaction provides yet. The pools not
+	/// keeping future transactions don't have to implement it.
+	fn future_transaction(&self, _hash: &TxHash<Self>) -> Option<Arc<Self::InPoolTransaction>> {
+		None
+	}
 }
 
 /// Events that the transaction pool listens for.
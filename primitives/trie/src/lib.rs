@@ -882,4 +882,12 @@ mod tests {
 
 		assert_eq!(first_storage_root, second_storage_root);
 	}
+
+	#[test]
+	fn storage_proof_encoded_size_is_the_size_of_its_encoding() {
+		let proof = StorageProof::decode(&mut &include_bytes!("../test-res/proof")[..]).unwrap();
+		assert_eq!(proof.encoded_size(), proof.encode().len());
+		let proof = StorageProof::new(vec![vec![1; 100], Vec::new()]);
+		assert_eq!(proof.encoded_size(), proof.encode().len());
+	}
 }
@@ -15,7 +15,7 @@
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
 use sp_std::vec::Vec;
-use codec::{Encode, Decode};
+use codec::{Compact, Encode, Decode};
 use hash_db::{Hasher, HashDB};
 
 /// A proof that some set of key-value pairs are included in the storage trie. The proof contains
@@ -51,6 +51,13 @@ impl StorageProof {
 		self.trie_nodes.is_empty()
 	}
 
+	/// The size of the encoded proof, without encoding it.
+	pub fn encoded_size(&self) -> usize {
+		let len = |len: usize| Compact(len as u32).size_hint();
+		self.trie_nodes.iter()
+			.fold(len(self.trie_nodes.len()), |size, node| size + len(node.len()) + node.len())
+	}
+
 	/// Create an iterator over trie nodes constructed from the proof. The nodes are not guaranteed
 	/// to be traversed in any particular order.
 	pub fn iter_nodes(self) -> StorageProofNodeIterator {
@@ -751,6 +751,14 @@ cfg_if! {
 				) -> Option<Vec<(Vec<u8>, sp_core::crypto::KeyTypeId)>> {
 					SessionKeys::decode_into_raw_public_keys(&encoded)
 				}
+
+				fn current_session_index() -> Option<sp_session::SessionIndex> {
+					None
+				}
+
+				fn next_session_keys(_: Vec<u8>) -> Option<Vec<u8>> {
+					None
+				}
 			}
 
 			impl sp_finality_grandpa::GrandpaApi<Block> for Runtime {
@@ -993,6 +1001,14 @@ cfg_if! {
 				) -> Option<Vec<(Vec<u8>, sp_core::crypto::KeyTypeId)>> {
 					SessionKeys::decode_into_raw_public_keys(&encoded)
 				}
+
+				fn current_session_index() -> Option<sp_session::SessionIndex> {
+					None
+				}
+
+				fn next_session_keys(_: Vec<u8>) -> Option<Vec<u8>> {
+					None
+				}
 			}
 
 			impl frame_system_rpc_runtime_api::AccountNonceApi<Block, AccountId, Index> for Runtime {
@@ -0,0 +1,144 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2019-2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tracing of the browser node to the devtools.
+
+use std::{collections::HashMap, fmt::{self, Write}};
+use parking_lot::Mutex;
+use sc_tracing::{ProfilingSubscriber, TracingBuilder};
+use tracing::{Event, Id, Level, field::{Field, Visit}, span::{Attributes, Record}};
+use tracing_subscriber::layer::{Context, Layer};
+use wasm_bindgen::JsValue;
+
+/// Layer forwarding the spans of the node to the console and to the Performance API of the
+/// browser, and its events to the console.
+///
+/// Every entry of a span opens a collapsed group of the console, closed when the span is exited,
+/// and is measured between two marks, shown in the timings of the performance panel of the
+/// devtools. The marks and measures are cleared once made, as they are only recorded by the
+/// profiles of the devtools and would otherwise pile up in the performance timeline.
+#[derive(Default)]
+pub struct ConsoleLayer {
+	spans: Mutex<HashMap<u64, Span>>,
+}
+
+struct Span {
+	name: String,
+	fields: Fields,
+}
+
+/// The fields of a span or event, formatted after their message, if any.
+#[derive(Default)]
+struct Fields {
+	message: String,
+	fields: String,
+}
+
+impl Visit for Fields {
+	fn record_str(&mut self, field: &Field, value: &str) {
+		if field.name() == "message" {
+			self.message = value.to_owned();
+		} else {
+			let _ = write!(self.fields, " {}={}", field.name(), value);
+		}
+	}
+
+	fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+		if field.name() == "message" {
+			self.message = format!("{:?}", value);
+		} else {
+			let _ = write!(self.fields, " {}={:?}", field.name(), value);
+		}
+	}
+}
+
+impl fmt::Display for Fields {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{}{}", self.message, self.fields)
+	}
+}
+
+fn performance() -> Option<web_sys::Performance> {
+	web_sys::window()?.performance()
+}
+
+fn marks(id: &Id) -> (String, String) {
+	let id = id.into_u64();
+	(format!("{}:start", id), format!("{}:end", id))
+}
+
+impl Layer<ProfilingSubscriber> for ConsoleLayer {
+	fn new_span(&self, attrs: &Attributes<'_>, id: &Id, _ctx: Context<'_, ProfilingSubscriber>) {
+		let mut fields = Fields::default();
+		attrs.record(&mut fields);
+		let metadata = attrs.metadata();
+		let name = format!("{}::{}", metadata.target(), metadata.name());
+		self.spans.lock().insert(id.into_u64(), Span { name, fields });
+	}
+
+	fn on_record(&self, id: &Id, values: &Record<'_>, _ctx: Context<'_, ProfilingSubscriber>) {
+		if let Some(span) = self.spans.lock().get_mut(&id.into_u64()) {
+			values.record(&mut span.fields);
+		}
+	}
+
+	fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, ProfilingSubscriber>) {
+		let mut fields = Fields::default();
+		event.record(&mut fields);
+		let message = JsValue::from_str(&format!("{}: {}", event.metadata().target(), fields));
+		match *event.metadata().level() {
+			Level::ERROR => web_sys::console::error_1(&message),
+			Level::WARN => web_sys::console::warn_1(&message),
+			Level::INFO => web_sys::console::info_1(&message),
+			Level::DEBUG | Level::TRACE => web_sys::console::debug_1(&message),
+		}
+	}
+
+	fn on_enter(&self, id: &Id, _ctx: Context<'_, ProfilingSubscriber>) {
+		if let Some(span) = self.spans.lock().get(&id.into_u64()) {
+			let label = format!("{} {}", span.name, span.fields);
+			web_sys::console::group_collapsed_1(&JsValue::from_str(label.trim_end()));
+			if let Some(performance) = performance() {
+				let _ = performance.mark(&marks(id).0);
+			}
+		}
+	}
+
+	fn on_exit(&self, id: &Id, _ctx: Context<'_, ProfilingSubscriber>) {
+		if let Some(span) = self.spans.lock().get(&id.into_u64()) {
+			web_sys::console::group_end();
+			if let Some(performance) = performance() {
+				let (start, end) = marks(id);
+				let _ = performance.mark(&end);
+				let _ = performance.measure_with_start_mark_and_end_mark(&span.name, &start, &end);
+				performance.clear_marks_with_mark_name(&start);
+				performance.clear_marks_with_mark_name(&end);
+				performance.clear_measures_with_measure_name(&span.name);
+			}
+		}
+	}
+
+	fn on_close(&self, id: Id, _ctx: Context<'_, ProfilingSubscriber>) {
+		self.spans.lock().remove(&id.into_u64());
+	}
+}
+
+/// Add a [`ConsoleLayer`] to the subscriber of the node, see
+/// `sc_service::Configuration::tracing_extensions`.
+pub fn console_tracing(builder: TracingBuilder) -> TracingBuilder {
+	builder.with_layer(ConsoleLayer::default())
+}
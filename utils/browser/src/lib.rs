@@ -31,12 +31,17 @@ use std::pin::Pin;
 use sc_chain_spec::Extension;
 use libp2p_wasm_ext::{ExtTransport, ffi};
 
+mod console;
+
+pub use console::{ConsoleLayer, console_tracing};
+
 pub use console_error_panic_hook::set_once as set_console_error_panic_hook;
 pub use console_log::init_with_level as init_console_log;
 
 /// Create a service configuration from a chain spec.
 ///
-/// This configuration contains good defaults for a browser light client.
+/// This configuration contains good defaults for a browser light client. The node is traced to
+/// the devtools of the browser by adding [`console_tracing`] to its `tracing_extensions`.
 pub async fn browser_configuration<G, E>(chain_spec: GenericChainSpec<G, E>)
 	-> Result<Configuration, Box<dyn std::error::Error>>
 where
@@ -69,6 +74,7 @@ where
 			async {}
 		}).into(),
 		telemetry_external_transport: Some(transport),
+		telemetry_batching: None,
 		role: Role::Light,
 		database: {
 			info!("Opening Indexed DB database '{}'...", name);
@@ -82,10 +88,12 @@ where
 		disable_grandpa: Default::default(),
 		execution_strategies: Default::default(),
 		force_authoring: Default::default(),
+		authorship_post_mortem: None,
 		impl_name: String::from("parity-substrate"),
 		impl_version: String::from("0.0.0"),
 		offchain_worker: Default::default(),
 		prometheus_config: Default::default(),
+		statsd_config: None,
 		pruning: Default::default(),
 		rpc_cors: Default::default(),
 		rpc_http: Default::default(),
@@ -93,12 +101,26 @@ where
 		rpc_ws: Default::default(),
 		rpc_ws_max_connections: Default::default(),
 		rpc_methods: Default::default(),
+		rpc_slow_calls: Default::default(),
+		grpc: None,
 		state_cache_child_ratio: Default::default(),
 		state_cache_size: Default::default(),
 		tracing_receiver: Default::default(),
+		tracing_redaction: Default::default(),
+		tracing_summaries: None,
+		tracing_storage_counters: false,
+		tracing_weight_drift: false,
+		tracing_pov_breakdowns: false,
+		tracing_dispatch_errors: false,
+		import_deadlines: Default::default(),
+		tracing_sentry: None,
+		tracing_extensions: Vec::new(),
 		tracing_targets: Default::default(),
+		tracing_sync_targets: None,
 		transaction_pool: Default::default(),
 		wasm_method: Default::default(),
+		wasmtime_jitdump: false,
+		wasmtime_cache: None,
 		max_runtime_instances: 8,
 		announce_block: true,
 		base_path: None,
@@ -106,6 +128,8 @@ where
 			enable_color: false,
 			prefix: String::new(),
 		},
+		tokio_console: None,
+		health_check: None,
 	};
 
 	Ok(config)
@@ -60,8 +60,11 @@ impl BenchmarkCmd {
 		extensions.register(KeystoreExt(KeyStore::new()));
 		let (offchain, _) = TestOffchainExt::new();
 		extensions.register(OffchainExt::new(offchain));
+		extensions.register(sp_io::MonotonicClockExt::new());
 
-		let result = StateMachine::<_, _, NumberFor<BB>, _>::new(
+		let runtime_code = sp_state_machine::backend::BackendRuntimeCode::new(&state);
+		let runtime_code = runtime_code.runtime_code()?;
+		let execute = || StateMachine::<_, _, NumberFor<BB>, _>::new(
 			&state,
 			None,
 			&mut changes,
@@ -79,10 +82,29 @@ impl BenchmarkCmd {
 				self.extra,
 			).encode(),
 			extensions,
-			&sp_state_machine::backend::BackendRuntimeCode::new(&state).runtime_code()?,
+			&runtime_code,
 			sp_core::testing::TaskExecutor::new(),
 		)
-		.execute(strategy.into())
+		.execute(strategy.into());
+
+		// With targets, the benchmarks are traced by a subscriber of their own, set as the default
+		// of the thread.
+		let result = match &self.tracing_targets {
+			Some(targets) => {
+				let receiver = self.tracing_receiver.with_output(self.tracing_output.clone())?;
+				let tracing = sc_tracing::TracingBuilder::new()
+					.with_receiver(receiver)
+					.with_targets(targets)
+					.with_storage_counters()
+					.build();
+				sp_tracing::set_host_targets(targets);
+				sp_tracing::set_wasm_tracing(true);
+				let result = sp_tracing::tracing::dispatcher::with_default(tracing.dispatch(), execute);
+				tracing.flush();
+				result
+			},
+			None => execute(),
+		}
 		.map_err(|e| format!("Error executing runtime benchmark: {:?}", e))?;
 
 		let results = <std::result::Result<Vec<BenchmarkBatch>, String> as Decode>::decode(&mut &result[..])
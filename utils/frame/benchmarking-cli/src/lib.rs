@@ -18,8 +18,8 @@
 mod command;
 mod writer;
 
-use sc_cli::{ExecutionStrategy, WasmExecutionMethod};
-use std::fmt::Debug;
+use sc_cli::{ExecutionStrategy, TracingReceiver, WasmExecutionMethod};
+use std::{fmt::Debug, path::PathBuf};
 
 /// The `benchmark` command used to benchmark FRAME Pallets.
 #[derive(Debug, structopt::StructOpt)]
@@ -106,4 +106,24 @@ pub struct BenchmarkCmd {
 	/// Limit the memory the database cache can use.
 	#[structopt(long = "db-cache", value_name = "MiB", default_value = "128")]
 	pub database_cache_size: u32,
+
+	/// Comma separated list of targets to trace the benchmarks for, e.g. `benchmark,runtime`.
+	///
+	/// The spans of the `benchmark` target hold the values of the components of every iteration.
+	#[structopt(long = "tracing-targets", value_name = "TARGETS")]
+	pub tracing_targets: Option<String>,
+
+	/// Receiver to process the traces of the benchmarks.
+	#[structopt(
+		long = "tracing-receiver",
+		value_name = "RECEIVER",
+		possible_values = &TracingReceiver::variants(),
+		case_insensitive = true,
+		default_value = "Log"
+	)]
+	pub tracing_receiver: TracingReceiver,
+
+	/// Output of the receiver, see `sc_cli::TracingReceiver::with_output`.
+	#[structopt(long = "tracing-output", value_name = "PATH", parse(from_os_str))]
+	pub tracing_output: Option<PathBuf>,
 }
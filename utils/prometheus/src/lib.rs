@@ -14,7 +14,7 @@
 // You should have received a copy of the GNU General Public License
 // along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
 
-use futures_util::{FutureExt, future::Future};
+use futures_util::FutureExt;
 pub use prometheus::{
 	self,
 	Registry, Error as PrometheusError, Opts,
@@ -30,10 +30,12 @@ use prometheus::{Encoder, TextEncoder, core::Collector};
 use std::net::SocketAddr;
 
 #[cfg(not(target_os = "unknown"))]
-mod networking;
+pub mod networking;
 mod sourced;
+mod statsd;
 
 pub use sourced::{SourcedCounter, SourcedGauge, MetricSource};
+pub use statsd::{StatsdConfig, init_statsd};
 
 #[cfg(target_os = "unknown")]
 pub use unknown_os::init_prometheus;
@@ -105,23 +107,10 @@ mod known_os {
 
 	}
 
-	#[derive(Clone)]
-	pub struct Executor;
-
-	impl<T> hyper::rt::Executor<T> for Executor
-	where
-		T: Future + Send + 'static,
-		T::Output: Send + 'static,
-	{
-		fn execute(&self, future: T) {
-			async_std::task::spawn(future);
-		}
-	}
-
 	/// Initializes the metrics context, and starts an HTTP server
 	/// to serve metrics.
 	pub async fn init_prometheus(prometheus_addr: SocketAddr, registry: Registry) -> Result<(), Error>{
-		use networking::Incoming;
+		use networking::{Incoming, Executor};
 		let listener = async_std::net::TcpListener::bind(&prometheus_addr)
 			.await
 			.map_err(|_| Error::PortInUse(prometheus_addr))?;
@@ -15,10 +15,14 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+//! Adapters serving hyper over the async-std networking, so that the HTTP servers of the node
+//! don't depend on the executor the node runs on, e.g. the Prometheus endpoint.
+
 use async_std::pin::Pin;
 use std::task::{Poll, Context};
-use futures_util::{stream::Stream, io::{AsyncRead, AsyncWrite}};
+use futures_util::{future::Future, stream::Stream, io::{AsyncRead, AsyncWrite}};
 
+/// The connections accepted by an async-std listener, for `hyper::Server::builder`.
 pub struct Incoming<'a>(pub async_std::net::Incoming<'a>);
 
 impl hyper::server::accept::Accept for Incoming<'_> {
@@ -32,6 +36,7 @@ impl hyper::server::accept::Accept for Incoming<'_> {
 	}
 }
 
+/// An async-std TCP stream, implementing the I/O traits of tokio that hyper requires.
 pub struct TcpStream(pub async_std::net::TcpStream);
 
 impl tokio::io::AsyncRead for TcpStream {
@@ -65,3 +70,17 @@ impl tokio::io::AsyncWrite for TcpStream {
 			.poll_close(cx)
 	}
 }
+
+/// Executor spawning the tasks of a hyper server on async-std.
+#[derive(Clone)]
+pub struct Executor;
+
+impl<T> hyper::rt::Executor<T> for Executor
+where
+	T: Future + Send + 'static,
+	T::Output: Send + 'static,
+{
+	fn execute(&self, future: T) {
+		async_std::task::spawn(future);
+	}
+}
@@ -0,0 +1,284 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Push of the metrics of a registry to a StatsD or DogStatsD server.
+//!
+//! The counters are pushed as StatsD counters, incremented by their increase since the previous
+//! push, and the gauges as StatsD gauges. The histograms being already aggregated, their count,
+//! sum and buckets are pushed as counters too, suffixed with `.count`, `.sum` and `.bucket`. The
+//! labels become tags for DogStatsD, and are appended to the metric names otherwise.
+
+use std::{collections::HashMap, net::SocketAddr, time::Duration};
+use prometheus::proto::{LabelPair, MetricFamily, MetricType};
+
+/// Maximum size of the datagrams, fitting in the usual MTU.
+const MAX_PACKET_SIZE: usize = 1432;
+
+/// Where and how to push the metrics.
+#[derive(Debug, Clone)]
+pub struct StatsdConfig {
+	/// Address of the server.
+	pub endpoint: SocketAddr,
+	/// Whether the server is a DogStatsD one, the labels being pushed as tags.
+	pub dogstatsd: bool,
+	/// Time between two pushes.
+	pub interval: Duration,
+}
+
+/// Convert the metrics to StatsD lines, remembering the counters to push their increase.
+pub(crate) struct Exporter {
+	dogstatsd: bool,
+	previous: HashMap<String, f64>,
+}
+
+impl Exporter {
+	pub fn new(dogstatsd: bool) -> Self {
+		Exporter { dogstatsd, previous: HashMap::new() }
+	}
+
+	/// The lines of `families`, grouped in datagrams.
+	pub fn packets(&mut self, families: &[MetricFamily]) -> Vec<String> {
+		let mut packets = Vec::new();
+		let mut packet = String::new();
+		for line in self.lines(families) {
+			if !packet.is_empty() && packet.len() + 1 + line.len() > MAX_PACKET_SIZE {
+				packets.push(std::mem::take(&mut packet));
+			}
+			if !packet.is_empty() {
+				packet.push('\n');
+			}
+			packet.push_str(&line);
+		}
+		if !packet.is_empty() {
+			packets.push(packet);
+		}
+		packets
+	}
+
+	fn lines(&mut self, families: &[MetricFamily]) -> Vec<String> {
+		let mut lines = Vec::new();
+		for family in families {
+			let name = family.get_name();
+			for metric in family.get_metric() {
+				let labels = metric.get_label();
+				match family.get_field_type() {
+					MetricType::COUNTER =>
+						self.counter(&mut lines, name, labels, None, metric.get_counter().get_value()),
+					MetricType::GAUGE => self.gauge(&mut lines, name, labels, metric.get_gauge().get_value()),
+					// Not produced by the metrics of the registry.
+					MetricType::UNTYPED => {},
+					MetricType::HISTOGRAM => {
+						let histogram = metric.get_histogram();
+						let count = histogram.get_sample_count() as f64;
+						self.counter(&mut lines, &format!("{}.count", name), labels, None, count);
+						self.counter(&mut lines, &format!("{}.sum", name), labels, None, histogram.get_sample_sum());
+						for bucket in histogram.get_bucket() {
+							let le = bucket.get_upper_bound().to_string();
+							let count = bucket.get_cumulative_count() as f64;
+							self.counter(&mut lines, &format!("{}.bucket", name), labels, Some(("le", &le)), count);
+						}
+						let le = Some(("le", "inf"));
+						self.counter(&mut lines, &format!("{}.bucket", name), labels, le, count);
+					},
+					MetricType::SUMMARY => {
+						let summary = metric.get_summary();
+						let count = summary.get_sample_count() as f64;
+						self.counter(&mut lines, &format!("{}.count", name), labels, None, count);
+						self.counter(&mut lines, &format!("{}.sum", name), labels, None, summary.get_sample_sum());
+					},
+				}
+			}
+		}
+		lines
+	}
+
+	fn counter(
+		&mut self,
+		lines: &mut Vec<String>,
+		name: &str,
+		labels: &[LabelPair],
+		extra: Option<(&str, &str)>,
+		value: f64,
+	) {
+		let (name, tags) = self.series(name, labels, extra);
+		let key = format!("{}{}", name, tags);
+		let previous = self.previous.insert(key, value).unwrap_or_default();
+		// The counter restarted, e.g. after being unregistered.
+		let delta = if value >= previous { value - previous } else { value };
+		if delta > 0.0 {
+			lines.push(format!("{}:{}|c{}", name, delta, tags));
+		}
+	}
+
+	fn gauge(&self, lines: &mut Vec<String>, name: &str, labels: &[LabelPair], value: f64) {
+		let (name, tags) = self.series(name, labels, None);
+		// A signed value is a decrement for StatsD.
+		if value < 0.0 && !self.dogstatsd {
+			lines.push(format!("{}:0|g{}", name, tags));
+		}
+		lines.push(format!("{}:{}|g{}", name, value, tags));
+	}
+
+	/// The name and tags of a series.
+	fn series(&self, name: &str, labels: &[LabelPair], extra: Option<(&str, &str)>) -> (String, String) {
+		let labels = labels.iter()
+			.map(|label| (label.get_name(), label.get_value()))
+			.chain(extra);
+		if self.dogstatsd {
+			let tags: Vec<_> = labels
+				.map(|(k, v)| format!("{}:{}", sanitize(k, ""), sanitize(v, "")))
+				.collect();
+			let tags = if tags.is_empty() { String::new() } else { format!("|#{}", tags.join(",")) };
+			(sanitize(name, ""), tags)
+		} else {
+			let mut series = sanitize(name, "");
+			for (k, v) in labels {
+				series.push_str(&format!(".{}.{}", sanitize(k, "."), sanitize(v, ".")));
+			}
+			(series, String::new())
+		}
+	}
+}
+
+/// Replace the characters of `value` with a meaning in the StatsD lines, and those of `extra`.
+fn sanitize(value: &str, extra: &str) -> String {
+	value.chars()
+		.map(|c| if c.is_whitespace() || ":|@#,".contains(c) || extra.contains(c) { '_' } else { c })
+		.collect()
+}
+
+#[cfg(not(target_os = "unknown"))]
+mod known_os {
+	use super::*;
+	use crate::Registry;
+
+	/// Push the metrics of `registry` every `config.interval`.
+	pub async fn init_statsd(config: StatsdConfig, registry: Registry) -> Result<(), std::io::Error> {
+		let local: SocketAddr = if config.endpoint.is_ipv4() {
+			([0, 0, 0, 0], 0).into()
+		} else {
+			([0u16; 8], 0).into()
+		};
+		let socket = async_std::net::UdpSocket::bind(local).await?;
+		socket.connect(config.endpoint).await?;
+
+		log::info!("〽️ Pushing the metrics to StatsD at {}", config.endpoint);
+
+		let mut exporter = Exporter::new(config.dogstatsd);
+		loop {
+			async_std::task::sleep(config.interval).await;
+			for packet in exporter.packets(&registry.gather()) {
+				if let Err(e) = socket.send(packet.as_bytes()).await {
+					log::debug!("Unable to push the metrics to StatsD: {}", e);
+				}
+			}
+		}
+	}
+}
+
+// On WASM `init_statsd` becomes a no-op.
+#[cfg(target_os = "unknown")]
+mod unknown_os {
+	use super::*;
+	use crate::Registry;
+
+	pub async fn init_statsd(_: StatsdConfig, _registry: Registry) -> Result<(), std::io::Error> {
+		Ok(())
+	}
+}
+
+#[cfg(not(target_os = "unknown"))]
+pub use known_os::init_statsd;
+#[cfg(target_os = "unknown")]
+pub use unknown_os::init_statsd;
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{register, Counter, CounterVec, Gauge, Histogram, HistogramOpts, Opts, Registry, I64, U64};
+
+	fn registry() -> (Registry, CounterVec<U64>, Gauge<I64>, Histogram) {
+		let registry = Registry::new_custom(Some("substrate".into()), None).unwrap();
+		let blocks = register(CounterVec::new(
+			Opts::new("blocks_total", "Blocks"),
+			&["status"],
+		).unwrap(), &registry).unwrap();
+		let peers = register(Gauge::new("peers", "Peers").unwrap(), &registry).unwrap();
+		let import = register(Histogram::with_opts(
+			HistogramOpts::new("import_seconds", "Import time").buckets(vec![0.5]),
+		).unwrap(), &registry).unwrap();
+		(registry, blocks, peers, import)
+	}
+
+	#[test]
+	fn pushes_counter_increases() {
+		let (registry, blocks, peers, import) = registry();
+		let mut exporter = Exporter::new(false);
+		blocks.with_label_values(&["best"]).inc_by(3);
+		peers.set(-2);
+		import.observe(0.25);
+		assert_eq!(exporter.lines(&registry.gather()), vec![
+			"substrate_blocks_total.status.best:3|c",
+			"substrate_import_seconds.count:1|c",
+			"substrate_import_seconds.sum:0.25|c",
+			"substrate_import_seconds.bucket.le.0_5:1|c",
+			"substrate_import_seconds.bucket.le.inf:1|c",
+			"substrate_peers:0|g",
+			"substrate_peers:-2|g",
+		]);
+
+		blocks.with_label_values(&["best"]).inc();
+		peers.set(5);
+		import.observe(1.0);
+		assert_eq!(exporter.lines(&registry.gather()), vec![
+			"substrate_blocks_total.status.best:1|c",
+			"substrate_import_seconds.count:1|c",
+			"substrate_import_seconds.sum:1|c",
+			"substrate_import_seconds.bucket.le.inf:1|c",
+			"substrate_peers:5|g",
+		]);
+	}
+
+	#[test]
+	fn pushes_labels_as_dogstatsd_tags() {
+		let (registry, blocks, _, _) = registry();
+		let total: Counter<U64> = register(Counter::new("total", "Total").unwrap(), &registry).unwrap();
+		total.inc();
+		blocks.with_label_values(&["sync target"]).inc();
+		let mut exporter = Exporter::new(true);
+		assert_eq!(exporter.packets(&registry.gather()), vec![
+			"substrate_blocks_total:1|c|#status:sync_target\n\
+			substrate_peers:0|g\n\
+			substrate_total:1|c",
+		]);
+	}
+
+	#[test]
+	fn splits_packets() {
+		let registry = Registry::new();
+		let gauges = register(crate::GaugeVec::<U64>::new(
+			Opts::new("gauge", "Gauge"),
+			&["index"],
+		).unwrap(), &registry).unwrap();
+		for i in 0..200 {
+			gauges.with_label_values(&[&i.to_string()]).set(i);
+		}
+		let packets = Exporter::new(false).packets(&registry.gather());
+		assert!(packets.len() > 1);
+		assert!(packets.iter().all(|packet| packet.len() <= MAX_PACKET_SIZE));
+		assert_eq!(packets.iter().map(|packet| packet.lines().count()).sum::<usize>(), 200);
+	}
+}